@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use assistant_tool::Tool;
+use parking_lot::Mutex;
+
+use crate::tools::{
+    diagnostics_tool::DiagnosticsTool, edit_file_tool::EditFileTool,
+    list_directory_tool::ListDirectoryTool, project_search_tool::ProjectSearchTool,
+    read_file_tool::ReadFileTool, run_task_tool::RunTaskTool,
+};
+use crate::AssistantEdit;
+
+/// The state of a context in agent mode, where the model keeps calling the built-in project
+/// tools until it's done, and the edits it made are then reviewed by the user.
+pub(crate) struct Agent {
+    tools: Vec<Arc<dyn Tool>>,
+    edits: Arc<Mutex<Vec<AssistantEdit>>>,
+    steps: usize,
+}
+
+impl Agent {
+    pub fn new() -> Self {
+        let edits = Arc::new(Mutex::new(Vec::new()));
+        Self {
+            tools: vec![
+                Arc::new(ReadFileTool),
+                Arc::new(ListDirectoryTool),
+                Arc::new(ProjectSearchTool),
+                Arc::new(EditFileTool::new(edits.clone())),
+                Arc::new(RunTaskTool),
+                Arc::new(DiagnosticsTool),
+            ],
+            edits,
+            steps: 0,
+        }
+    }
+
+    /// Returns the tools available to the model in agent mode.
+    pub fn tools(&self) -> &[Arc<dyn Tool>] {
+        &self.tools
+    }
+
+    /// Returns the built-in tool with the given name.
+    pub fn tool(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.iter().find(|tool| tool.name() == name).cloned()
+    }
+
+    /// Starts counting steps anew, for a new request from the user.
+    pub fn reset_steps(&mut self) {
+        self.steps = 0;
+    }
+
+    /// Records that the model is about to use tools again, returning `false` once the
+    /// given number of steps has been exceeded.
+    pub fn begin_step(&mut self, max_steps: usize) -> bool {
+        if self.steps >= max_steps {
+            return false;
+        }
+        self.steps += 1;
+        true
+    }
+
+    /// Returns the edits recorded so far, leaving none behind.
+    pub fn take_edits(&self) -> Vec<AssistantEdit> {
+        std::mem::take(&mut *self.edits.lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_steps() {
+        let mut agent = Agent::new();
+        assert!(agent.begin_step(2));
+        assert!(agent.begin_step(2));
+        assert!(!agent.begin_step(2));
+
+        agent.reset_steps();
+        assert!(agent.begin_step(2));
+        assert!(agent.begin_step(2));
+        assert!(!agent.begin_step(2));
+    }
+}
@@ -1,5 +1,6 @@
 #![cfg_attr(target_os = "windows", allow(unused, dead_code))]
 
+mod agent;
 pub mod assistant_panel;
 pub mod assistant_settings;
 mod context;
@@ -15,19 +16,25 @@ pub mod slash_command_settings;
 mod streaming_diff;
 mod terminal_inline_assistant;
 mod tools;
+mod usage;
 
 pub use assistant_panel::{AssistantPanel, AssistantPanelEvent};
 use assistant_settings::AssistantSettings;
 use assistant_slash_command::SlashCommandRegistry;
-use assistant_tool::ToolRegistry;
+use assistant_tool::{Tool as _, ToolRegistry};
 use client::{proto, Client};
 use command_palette_hooks::CommandPaletteFilter;
 pub use context::*;
-use context_servers::ContextServerRegistry;
+use context_servers::{
+    manager::{ContextServer, ContextServerManager},
+    protocol::ServerCapability,
+    types::NotificationType,
+    ContextServerRegistry,
+};
 pub use context_store::*;
 use feature_flags::FeatureFlagAppExt;
 use fs::Fs;
-use gpui::{actions, AppContext, Global, SharedString, UpdateGlobal};
+use gpui::{actions, AppContext, AsyncAppContext, Global, SharedString, UpdateGlobal};
 use gpui::{impl_actions, Context as _};
 use indexed_docs::IndexedDocsRegistry;
 pub(crate) use inline_assistant::*;
@@ -38,9 +45,10 @@ pub(crate) use model_selector::*;
 pub use patch::*;
 pub use prompts::PromptBuilder;
 use prompts::PromptLoadingParams;
-use semantic_index::{CloudEmbeddingProvider, SemanticDb};
+use semantic_index::{SemanticDb, SemanticIndexSettings};
 use serde::{Deserialize, Serialize};
 use settings::{update_settings_file, Settings, SettingsStore};
+use slash_command::context_server_resource_command::ContextServerResourceSlashCommand;
 use slash_command::workflow_command::WorkflowSlashCommand;
 use slash_command::{
     auto_command, cargo_workspace_command, context_server_command, default_command, delta_command,
@@ -68,11 +76,13 @@ actions!(
         InsertActivePrompt,
         DeployHistory,
         DeployPromptLibrary,
+        DeployUsage,
         ConfirmCommand,
         NewContext,
         ToggleModelSelector,
         CycleNextInlineAssist,
-        CyclePreviousInlineAssist
+        CyclePreviousInlineAssist,
+        ToggleAgentMode
     ]
 );
 
@@ -211,18 +221,19 @@ pub fn init(
         });
     }
 
-    cx.spawn(|mut cx| {
+    SemanticIndexSettings::register(cx);
+    init_semantic_db(client.clone(), cx);
+    let mut embedding_model = SemanticIndexSettings::get_global(cx)
+        .embedding_model
+        .clone();
+    cx.observe_global::<SettingsStore>({
         let client = client.clone();
-        async move {
-            let embedding_provider = CloudEmbeddingProvider::new(client.clone());
-            let semantic_index = SemanticDb::new(
-                paths::embeddings_dir().join("semantic-index-db.0.mdb"),
-                Arc::new(embedding_provider),
-                &mut cx,
-            )
-            .await?;
-
-            cx.update(|cx| cx.set_global(semantic_index))
+        move |cx| {
+            let new_embedding_model = &SemanticIndexSettings::get_global(cx).embedding_model;
+            if *new_embedding_model != embedding_model {
+                embedding_model = new_embedding_model.clone();
+                init_semantic_db(client.clone(), cx);
+            }
         }
     })
     .detach();
@@ -282,6 +293,31 @@ pub fn init(
     prompt_builder
 }
 
+/// Opens the semantic index with the embedding model chosen in the settings, replacing the
+/// current one once it has loaded.
+fn init_semantic_db(client: Arc<Client>, cx: &mut AppContext) {
+    cx.spawn(|mut cx| async move {
+        let embedding_model = cx.update(|cx| {
+            SemanticIndexSettings::get_global(cx)
+                .embedding_model
+                .clone()
+        })?;
+        let semantic_index =
+            SemanticDb::open(paths::embeddings_dir().clone(), client, &mut cx).await?;
+
+        cx.update(|cx| {
+            // The model may have changed again while this one was loading.
+            if SemanticIndexSettings::get_global(cx).embedding_model == embedding_model {
+                cx.set_global(semantic_index);
+                SemanticDb::update_global(cx, |semantic_index, cx| {
+                    semantic_index.index_open_projects(cx)
+                });
+            }
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
 fn register_context_server_handlers(cx: &mut AppContext) {
     cx.subscribe(
         &context_servers::manager::ContextServerManager::global(cx),
@@ -291,6 +327,7 @@ fn register_context_server_handlers(cx: &mut AppContext) {
                     &manager,
                     |manager: &mut context_servers::manager::ContextServerManager, cx| {
                         let slash_command_registry = SlashCommandRegistry::global(cx);
+                        let tool_registry = ToolRegistry::global(cx);
                         let context_server_registry = ContextServerRegistry::global(cx);
                         if let Some(server) = manager.get_server(server_id) {
                             cx.spawn(|_, _| async move {
@@ -319,6 +356,28 @@ fn register_context_server_handlers(cx: &mut AppContext) {
                                         );
                                     }
                                 }
+
+                                if protocol.capable(ServerCapability::Resources) {
+                                    slash_command_registry
+                                        .register_command(ContextServerResourceSlashCommand, true);
+                                }
+
+                                if protocol.capable(ServerCapability::Tools) {
+                                    register_context_server_tools(
+                                        server.clone(),
+                                        tool_registry,
+                                        context_server_registry,
+                                    )
+                                    .await;
+
+                                    let server_id = server.id.clone();
+                                    protocol.on_notification(
+                                        NotificationType::ToolsListChanged.as_str(),
+                                        move |_, cx| {
+                                            reregister_context_server_tools(server_id.clone(), cx)
+                                        },
+                                    );
+                                }
                             })
                             .detach();
                         }
@@ -334,12 +393,87 @@ fn register_context_server_handlers(cx: &mut AppContext) {
                         context_server_registry.unregister_command(&server_id, &command_name);
                     }
                 }
+                unregister_context_server_tools(
+                    server_id,
+                    &ToolRegistry::global(cx),
+                    &context_server_registry,
+                );
+
+                let any_server_has_resources = manager.read(cx).servers().iter().any(|server| {
+                    server.client.read().as_ref().map_or(false, |protocol| {
+                        protocol.capable(ServerCapability::Resources)
+                    })
+                });
+                if !any_server_has_resources {
+                    slash_command_registry.unregister_command(ContextServerResourceSlashCommand);
+                }
             }
+            context_servers::manager::Event::ResourceUpdated { .. } => {}
         },
     )
     .detach();
 }
 
+/// Registers the tools of a context server, replacing any tools it registered before.
+async fn register_context_server_tools(
+    server: Arc<ContextServer>,
+    tool_registry: Arc<ToolRegistry>,
+    context_server_registry: Arc<ContextServerRegistry>,
+) {
+    let Some(protocol) = server.client.read().clone() else {
+        return;
+    };
+    let Some(response) = protocol.list_tools().await.log_err() else {
+        return;
+    };
+
+    unregister_context_server_tools(&server.id, &tool_registry, &context_server_registry);
+    for tool in response.tools {
+        let tool = tools::context_server_tool::ContextServerTool::new(&server, tool);
+        let tool_name = tool.name();
+        log::info!("registering context server tool: {:?}", tool_name);
+        context_server_registry.register_tool(server.id.clone(), &tool_name);
+        tool_registry.register_tool(tool);
+    }
+}
+
+/// Registers a context server's tools again, after the server reported that they changed.
+fn reregister_context_server_tools(server_id: String, cx: AsyncAppContext) {
+    cx.spawn(|cx| async move {
+        let Some((server, tool_registry, context_server_registry)) = cx
+            .update(|cx| {
+                let server = ContextServerManager::global(cx)
+                    .read(cx)
+                    .get_server(&server_id)?;
+                Some((
+                    server,
+                    ToolRegistry::global(cx),
+                    ContextServerRegistry::global(cx),
+                ))
+            })
+            .ok()
+            .flatten()
+        else {
+            return;
+        };
+        register_context_server_tools(server, tool_registry, context_server_registry).await;
+    })
+    .detach();
+}
+
+fn unregister_context_server_tools(
+    server_id: &str,
+    tool_registry: &ToolRegistry,
+    context_server_registry: &ContextServerRegistry,
+) {
+    if let Some(tools) = context_server_registry.get_tools(server_id) {
+        for tool_name in tools {
+            tool_registry.unregister_tool_by_name(&tool_name);
+            context_server_registry.unregister_tool(server_id, &tool_name);
+        }
+    }
+}
+
 fn init_language_model_settings(cx: &mut AppContext) {
     update_active_language_model_from_settings(cx);
 
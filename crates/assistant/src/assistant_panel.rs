@@ -1,9 +1,10 @@
 use crate::{
-    assistant_settings::{AssistantDockPosition, AssistantSettings},
+    assistant_settings::{AssistantDockPosition, AssistantSettings, ToolApproval},
     humanize_token_count,
     prompt_library::open_prompt_library,
     prompts::PromptBuilder,
     slash_command::{
+        context_server_resource_command::ContextServerResourceSlashCommand,
         default_command::DefaultSlashCommand,
         docs_command::{DocsSlashCommand, DocsSlashCommandArgs},
         file_command::{self, codeblock_fence_for_path},
@@ -11,18 +12,21 @@ use crate::{
     },
     slash_command_picker,
     terminal_inline_assistant::TerminalInlineAssistant,
+    usage::{self, UsageView},
     Assist, AssistantPatch, AssistantPatchStatus, CacheStatus, ConfirmCommand, Content, Context,
     ContextEvent, ContextId, ContextStore, ContextStoreEvent, CopyCode, CycleMessageRole,
-    DeployHistory, DeployPromptLibrary, InlineAssistant, InsertDraggedFiles, InsertIntoEditor,
-    Message, MessageId, MessageMetadata, MessageStatus, ModelPickerDelegate, ModelSelector,
-    NewContext, PendingSlashCommand, PendingSlashCommandStatus, QuoteSelection,
-    RemoteContextMetadata, SavedContextMetadata, Split, ToggleFocus, ToggleModelSelector,
+    DeployHistory, DeployPromptLibrary, DeployUsage, InlineAssistant, InsertDraggedFiles,
+    InsertIntoEditor, Message, MessageAnchor, MessageBranches, MessageId, MessageMetadata,
+    MessageStatus, ModelPickerDelegate, ModelSelector, NewContext, PendingSlashCommand,
+    PendingSlashCommandStatus, QuoteSelection, RemoteContextMetadata, ResolvedPatch,
+    SavedContextMatch, Split, ToggleAgentMode, ToggleFocus, ToggleModelSelector,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use assistant_slash_command::{SlashCommand, SlashCommandOutputSection};
-use assistant_tool::ToolRegistry;
+use assistant_tool::{Tool, ToolRegistry};
 use client::{proto, zed_urls, Client, Status};
 use collections::{BTreeSet, HashMap, HashSet};
+use context_servers::manager::ContextServerManager;
 use editor::{
     actions::{FoldAt, MoveToEndOfLine, Newline, ShowCompletions, UnfoldAt},
     display_map::{
@@ -38,7 +42,7 @@ use fs::Fs;
 use futures::FutureExt;
 use gpui::{
     canvas, div, img, percentage, point, pulsating_between, size, Action, Animation, AnimationExt,
-    AnyElement, AnyView, AppContext, AsyncWindowContext, ClipboardEntry, ClipboardItem,
+    AnyElement, AnyView, AppContext, AsyncWindowContext, ClickEvent, ClipboardEntry, ClipboardItem,
     CursorStyle, Empty, Entity, EventEmitter, ExternalPaths, FocusHandle, FocusableView,
     FontWeight, InteractiveElement, IntoElement, Model, ParentElement, Pixels, Render, RenderImage,
     SharedString, Size, StatefulInteractiveElement, Styled, Subscription, Task, Transformation,
@@ -151,7 +155,7 @@ pub struct AssistantPanel {
 #[derive(Clone)]
 enum ContextMetadata {
     Remote(RemoteContextMetadata),
-    Saved(SavedContextMetadata),
+    Saved(SavedContextMatch),
 }
 
 struct SavedContextPickerDelegate {
@@ -275,20 +279,27 @@ impl PickerDelegate for SavedContextPickerDelegate {
                             }),
                     )
             }
-            ContextMetadata::Saved(context) => div()
+            ContextMetadata::Saved(context_match) => div()
                 .flex()
                 .w_full()
                 .justify_between()
                 .gap_2()
                 .child(
-                    h_flex()
+                    v_flex()
                         .flex_1()
-                        .child(Label::new(context.title.clone()).size(LabelSize::Small))
+                        .child(
+                            Label::new(context_match.metadata.title.clone()).size(LabelSize::Small),
+                        )
+                        .children(context_match.excerpt.clone().map(|excerpt| {
+                            Label::new(excerpt)
+                                .color(Color::Muted)
+                                .size(LabelSize::XSmall)
+                        }))
                         .overflow_x_hidden(),
                 )
                 .child(
                     Label::new(format_distance_from_now(
-                        DateTimeType::Local(context.mtime),
+                        DateTimeType::Local(context_match.metadata.mtime),
                         false,
                         true,
                         true,
@@ -476,6 +487,7 @@ impl AssistantPanel {
                                         .action("New Context", Box::new(NewContext))
                                         .action("History", Box::new(DeployHistory))
                                         .action("Prompt Library", Box::new(DeployPromptLibrary))
+                                        .action("Usage", Box::new(DeployUsage))
                                         .action("Configure", Box::new(ShowConfiguration))
                                         .action(zoom_label, Box::new(ToggleZoom))
                                 }))
@@ -1038,6 +1050,41 @@ impl AssistantPanel {
         }
     }
 
+    fn fork_context(
+        &mut self,
+        context: &Model<Context>,
+        message_id: MessageId,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.project.read(cx).is_via_collab() {
+            return;
+        }
+        let Some(context) = self
+            .context_store
+            .update(cx, |store, cx| store.fork_context(context, message_id, cx))
+        else {
+            return;
+        };
+        context.update(cx, |context, cx| context.save(None, self.fs.clone(), cx));
+
+        let lsp_adapter_delegate = make_lsp_adapter_delegate(&self.project, cx)
+            .log_err()
+            .flatten();
+        let assistant_panel = cx.view().downgrade();
+        let editor = cx.new_view(|cx| {
+            ContextEditor::for_context(
+                context,
+                self.fs.clone(),
+                self.workspace.clone(),
+                self.project.clone(),
+                lsp_adapter_delegate,
+                assistant_panel,
+                cx,
+            )
+        });
+        self.show_context(editor, cx);
+    }
+
     fn show_context(&mut self, context_editor: View<ContextEditor>, cx: &mut ViewContext<Self>) {
         let focus = self.focus_handle(cx).contains_focused(cx);
         let prev_len = self.pane.read(cx).items_len();
@@ -1172,6 +1219,31 @@ impl AssistantPanel {
         }
     }
 
+    fn deploy_usage(&mut self, _: &DeployUsage, cx: &mut ViewContext<Self>) {
+        let usage_item_ix = self
+            .pane
+            .read(cx)
+            .items()
+            .position(|item| item.downcast::<UsageView>().is_some());
+
+        if let Some(usage_item_ix) = usage_item_ix {
+            self.pane.update(cx, |pane, cx| {
+                pane.activate_item(usage_item_ix, true, true, cx);
+                if let Some(usage) = pane
+                    .active_item()
+                    .and_then(|item| item.downcast::<UsageView>())
+                {
+                    usage.update(cx, |usage, cx| usage.reload(cx));
+                }
+            });
+        } else {
+            let usage = cx.new_view(UsageView::new);
+            self.pane.update(cx, |pane, cx| {
+                pane.add_item(Box::new(usage), true, true, None, cx);
+            });
+        }
+    }
+
     fn deploy_prompt_library(&mut self, _: &DeployPromptLibrary, cx: &mut ViewContext<Self>) {
         open_prompt_library(self.languages.clone(), cx).detach_and_log_err(cx);
     }
@@ -1325,6 +1397,7 @@ impl Render for AssistantPanel {
             )
             .on_action(cx.listener(AssistantPanel::deploy_history))
             .on_action(cx.listener(AssistantPanel::deploy_prompt_library))
+            .on_action(cx.listener(AssistantPanel::deploy_usage))
             .on_action(cx.listener(AssistantPanel::toggle_model_selector))
             .child(registrar.size_full().child(self.pane.clone()))
             .into_any_element()
@@ -1486,6 +1559,8 @@ pub struct ContextEditor {
     assistant_panel: WeakView<AssistantPanel>,
     last_error: Option<AssistError>,
     show_accept_terms: bool,
+    /// The tool uses the agent is waiting on before it continues.
+    agent_tool_uses: HashSet<Arc<str>>,
     pub(crate) slash_menu_handle:
         PopoverMenuHandle<Picker<slash_command_picker::SlashCommandDelegate>>,
     // dragged_file_worktrees is used to keep references to worktrees that were added
@@ -1528,12 +1603,16 @@ impl ContextEditor {
             editor
         });
 
-        let _subscriptions = vec![
+        let mut _subscriptions = vec![
             cx.observe(&context, |_, _, cx| cx.notify()),
             cx.subscribe(&context, Self::handle_context_event),
             cx.subscribe(&editor, Self::handle_editor_event),
             cx.subscribe(&editor, Self::handle_editor_search_event),
         ];
+        if let Some(context_server_manager) = ContextServerManager::try_global(cx) {
+            _subscriptions
+                .push(cx.subscribe(&context_server_manager, Self::handle_context_server_event));
+        }
 
         let sections = context.read(cx).slash_command_output_sections().to_vec();
         let patch_ranges = context.read(cx).patch_ranges().collect::<Vec<_>>();
@@ -1557,6 +1636,7 @@ impl ContextEditor {
             assistant_panel,
             last_error: None,
             show_accept_terms: false,
+            agent_tool_uses: HashSet::default(),
             slash_menu_handle: Default::default(),
             dragged_file_worktrees: Vec::new(),
         };
@@ -1602,8 +1682,101 @@ impl ContextEditor {
             return;
         }
 
-        self.last_error = None;
+        self.confirm_budget_and_assist(None, cx);
+    }
+
+    /// Sends the context to the model once the user confirms requests that exceed their usage
+    /// budget, regenerating the given message if there is one.
+    fn confirm_budget_and_assist(
+        &mut self,
+        regenerated_message: Option<MessageId>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(budget_warning) = self.budget_warning(cx) {
+            let answer = cx.prompt(
+                gpui::PromptLevel::Warning,
+                "This request exceeds your usage budget.",
+                Some(&budget_warning),
+                &["Send Anyway", "Cancel"],
+            );
+            cx.spawn(|this, mut cx| async move {
+                if answer.await? == 0 {
+                    this.update(&mut cx, |this, cx| {
+                        this.start_assist(regenerated_message, cx)
+                    })?;
+                }
+                anyhow::Ok(())
+            })
+            .detach_and_log_err(cx);
+            return;
+        }
+
+        self.start_assist(regenerated_message, cx);
+    }
+
+    /// Sends the next automatic request of the agent, pausing it until the user confirms
+    /// requests that exceed their usage budget.
+    fn confirm_budget_and_continue_agent(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(budget_warning) = self.budget_warning(cx) {
+            let answer = cx.prompt(
+                gpui::PromptLevel::Warning,
+                "The agent's next request exceeds your usage budget.",
+                Some(&budget_warning),
+                &["Continue Anyway", "Stop Agent"],
+            );
+            cx.spawn(|this, mut cx| async move {
+                let answer = answer.await?;
+                this.update(&mut cx, |this, cx| {
+                    if answer == 0 {
+                        this.send_to_model(cx);
+                    } else {
+                        this.last_error = Some(AssistError::Message(
+                            "The agent stopped because of your usage budget.".into(),
+                        ));
+                        this.review_agent_edits(cx);
+                        cx.notify();
+                    }
+                })
+            })
+            .detach_and_log_err(cx);
+            return;
+        }
+
         self.send_to_model(cx);
+    }
+
+    fn budget_warning(&self, cx: &AppContext) -> Option<String> {
+        LanguageModelRegistry::read_global(cx)
+            .active_model()
+            .zip(self.context.read(cx).token_count())
+            .and_then(|(model, token_count)| usage::budget_warning(model.as_ref(), token_count, cx))
+    }
+
+    fn start_assist(&mut self, regenerated_message: Option<MessageId>, cx: &mut ViewContext<Self>) {
+        self.last_error = None;
+        self.context.update(cx, |context, _| {
+            if let Some(agent) = context.agent_mut() {
+                agent.reset_steps();
+            }
+        });
+        if let Some(message_id) = regenerated_message {
+            if let Some(user_message) = self
+                .context
+                .update(cx, |context, cx| context.regenerate_message(message_id, cx))
+            {
+                self.select_user_message(user_message, cx);
+            }
+        } else {
+            self.send_to_model(cx);
+        }
+        cx.notify();
+    }
+
+    fn toggle_agent_mode(&mut self, _: &ToggleAgentMode, cx: &mut ViewContext<Self>) {
+        self.agent_tool_uses.clear();
+        self.context.update(cx, |context, cx| {
+            context.set_agent_mode(!context.is_agent_mode(), cx)
+        });
         cx.notify();
     }
 
@@ -1624,22 +1797,49 @@ impl ContextEditor {
 
     fn send_to_model(&mut self, cx: &mut ViewContext<Self>) {
         if let Some(user_message) = self.context.update(cx, |context, cx| context.assist(cx)) {
-            let new_selection = {
-                let cursor = user_message
-                    .start
-                    .to_offset(self.context.read(cx).buffer().read(cx));
-                cursor..cursor
-            };
-            self.editor.update(cx, |editor, cx| {
-                editor.change_selections(
-                    Some(Autoscroll::Strategy(AutoscrollStrategy::Fit)),
-                    cx,
-                    |selections| selections.select_ranges([new_selection]),
-                );
-            });
-            // Avoid scrolling to the new cursor position so the assistant's output is stable.
-            cx.defer(|this, _| this.scroll_position = None);
+            self.select_user_message(user_message, cx);
+        }
+    }
+
+    fn regenerate_message(&mut self, message_id: MessageId, cx: &mut ViewContext<Self>) {
+        let provider = LanguageModelRegistry::read_global(cx).active_provider();
+        if provider
+            .as_ref()
+            .map_or(false, |provider| provider.must_accept_terms(cx))
+        {
+            self.show_accept_terms = true;
+            cx.notify();
+            return;
         }
+
+        self.confirm_budget_and_assist(Some(message_id), cx);
+    }
+
+    fn select_user_message(&mut self, user_message: MessageAnchor, cx: &mut ViewContext<Self>) {
+        let new_selection = {
+            let cursor = user_message
+                .start
+                .to_offset(self.context.read(cx).buffer().read(cx));
+            cursor..cursor
+        };
+        self.editor.update(cx, |editor, cx| {
+            editor.change_selections(
+                Some(Autoscroll::Strategy(AutoscrollStrategy::Fit)),
+                cx,
+                |selections| selections.select_ranges([new_selection]),
+            );
+        });
+        // Avoid scrolling to the new cursor position so the assistant's output is stable.
+        cx.defer(|this, _| this.scroll_position = None);
+    }
+
+    fn fork_context(&mut self, message_id: MessageId, cx: &mut ViewContext<Self>) {
+        let context = self.context.clone();
+        self.assistant_panel
+            .update(cx, |assistant_panel, cx| {
+                assistant_panel.fork_context(&context, message_id, cx)
+            })
+            .ok();
     }
 
     fn cancel(&mut self, _: &editor::actions::Cancel, cx: &mut ViewContext<Self>) {
@@ -1749,6 +1949,46 @@ impl ContextEditor {
         }
     }
 
+    /// Runs the tool according to the user's approval settings for it.
+    fn run_tool(
+        &mut self,
+        tool: Arc<dyn Tool>,
+        input: serde_json::Value,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<String>> {
+        match AssistantSettings::get_global(cx).tool_approval(tool.as_ref()) {
+            ToolApproval::Allow => tool.run(input, self.workspace.clone(), cx),
+            ToolApproval::Ask => self.confirm_and_run_tool(tool, input, cx),
+            ToolApproval::Deny => {
+                Task::ready(Ok("The user doesn't allow this tool to run.".into()))
+            }
+        }
+    }
+
+    /// Asks the user whether the tool may run, and runs it if so. A declined tool reports
+    /// that to the model as its output, so the conversation can continue.
+    fn confirm_and_run_tool(
+        &mut self,
+        tool: Arc<dyn Tool>,
+        input: serde_json::Value,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<String>> {
+        let detail = serde_json::to_string_pretty(&input).unwrap_or_default();
+        let answer = cx.prompt(
+            gpui::PromptLevel::Warning,
+            &format!("Allow the assistant to run the \"{}\" tool?", tool.name()),
+            Some(&detail),
+            &["Run", "Cancel"],
+        );
+        let workspace = self.workspace.clone();
+        cx.spawn(|_, mut cx| async move {
+            if answer.await? != 0 {
+                return Ok("The user declined to run this tool.".to_string());
+            }
+            cx.update(|cx| tool.run(input, workspace, cx))?.await
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn run_command(
         &mut self,
@@ -1789,6 +2029,38 @@ impl ContextEditor {
         }
     }
 
+    /// Replaces the sections showing a context server resource with its new contents.
+    fn handle_context_server_event(
+        &mut self,
+        _: Model<ContextServerManager>,
+        event: &context_servers::manager::Event,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let context_servers::manager::Event::ResourceUpdated { server_id, uri } = event else {
+            return;
+        };
+
+        let context = self.context.read(cx);
+        let buffer = context.buffer().read(cx);
+        let sections = context
+            .slash_command_output_sections()
+            .iter()
+            .filter(|section| {
+                section.is_valid(buffer)
+                    && ContextServerResourceSlashCommand::section_shows_resource(
+                        section, server_id, uri,
+                    )
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for section in sections {
+            let output = ContextServerResourceSlashCommand::refresh(server_id, uri, cx);
+            self.context.update(cx, |context, cx| {
+                context.insert_command_output(section.range, output, false, false, cx)
+            });
+        }
+    }
+
     fn handle_context_event(
         &mut self,
         _: Model<Context>,
@@ -2071,6 +2343,23 @@ impl ContextEditor {
                 }
             }
             ContextEvent::UsePendingTools => {
+                let is_agent_mode = self.context.read(cx).is_agent_mode();
+                if is_agent_mode {
+                    let max_steps = AssistantSettings::get_global(cx).max_agent_steps;
+                    let within_limit = self.context.update(cx, |context, _| {
+                        context
+                            .agent_mut()
+                            .map_or(true, |agent| agent.begin_step(max_steps))
+                    });
+                    if !within_limit {
+                        self.last_error = Some(AssistError::Message(
+                            format!("The agent stopped after {max_steps} steps.").into(),
+                        ));
+                        self.review_agent_edits(cx);
+                        return;
+                    }
+                }
+
                 let pending_tool_uses = self
                     .context
                     .read(cx)
@@ -2081,16 +2370,42 @@ impl ContextEditor {
                     .collect::<Vec<_>>();
 
                 for tool_use in pending_tool_uses {
-                    let tool_registry = ToolRegistry::global(cx);
-                    if let Some(tool) = tool_registry.tool(&tool_use.name) {
-                        let task = tool.run(tool_use.input, self.workspace.clone(), cx);
+                    let tool = self
+                        .context
+                        .read(cx)
+                        .agent()
+                        .and_then(|agent| agent.tool(&tool_use.name))
+                        .or_else(|| ToolRegistry::global(cx).tool(&tool_use.name));
+                    let task = match tool {
+                        Some(tool) => self.run_tool(tool, tool_use.input, cx),
+                        None if is_agent_mode => {
+                            let name = tool_use.name.clone();
+                            Task::ready(Err(anyhow!("no tool named {name:?}")))
+                        }
+                        None => continue,
+                    };
 
-                        self.context.update(cx, |context, cx| {
-                            context.insert_tool_output(tool_use.id.clone(), task, cx);
-                        });
-                    }
+                    let task = if is_agent_mode {
+                        // The agent can only carry on once every tool has reported back, so
+                        // failures are reported to the model instead of stopping the agent.
+                        self.agent_tool_uses.insert(tool_use.id.clone());
+                        cx.spawn(|_, _| async move {
+                            Ok(task
+                                .await
+                                .unwrap_or_else(|error| format!("Error: {error:#}")))
+                        })
+                    } else {
+                        task
+                    };
+
+                    self.context.update(cx, |context, cx| {
+                        context.insert_tool_output(tool_use.id.clone(), task, cx);
+                    });
                 }
             }
+            ContextEvent::AgentFinished => {
+                self.review_agent_edits(cx);
+            }
             ContextEvent::ToolFinished {
                 tool_use_id,
                 output_range,
@@ -2131,6 +2446,13 @@ impl ContextEditor {
                     editor.insert_creases([crease], cx);
                     editor.fold_at(&FoldAt { buffer_row }, cx);
                 });
+
+                if self.agent_tool_uses.remove(tool_use_id)
+                    && self.agent_tool_uses.is_empty()
+                    && self.context.read(cx).is_agent_mode()
+                {
+                    self.confirm_budget_and_continue_agent(cx);
+                }
             }
             ContextEvent::Operation(_) => {}
             ContextEvent::ShowAssistError(error_message) => {
@@ -2440,17 +2762,61 @@ impl ContextEditor {
             .ok();
     }
 
-    async fn open_patch_editor(
-        this: WeakView<Self>,
-        patch: AssistantPatch,
-        mut cx: AsyncWindowContext,
-    ) -> Result<()> {
-        let project = this.update(&mut cx, |this, _| this.project.clone())?;
-        let resolved_patch = patch.resolve(project.clone(), &mut cx).await;
+    /// Shows the edits the agent made for review, once it's done.
+    fn review_agent_edits(&mut self, cx: &mut ViewContext<Self>) {
+        let edits = match self.context.read(cx).agent() {
+            Some(agent) => agent.take_edits(),
+            None => return,
+        };
+        self.agent_tool_uses.clear();
+        if edits.is_empty() {
+            return;
+        }
 
-        let editor = cx.new_view(|cx| {
+        let patch = AssistantPatch {
+            range: language::Anchor::MIN..language::Anchor::MIN,
+            title: "Agent Edits".into(),
+            edits: edits.into_iter().map(Ok).collect(),
+            status: AssistantPatchStatus::Ready,
+        };
+        cx.spawn(|this, mut cx| async move {
+            let project = this.update(&mut cx, |this, _| this.project.clone())?;
+            let resolved_patch = patch.resolve(project.clone(), &mut cx).await;
+            let editor =
+                Self::new_patch_editor(patch.title.clone(), &resolved_patch, project, &mut cx)?;
+
+            this.update(&mut cx, |this, cx| {
+                if let Some(error) = resolved_patch.errors.first() {
+                    this.last_error = Some(AssistError::Message(
+                        format!(
+                            "{} of the agent's edits could not be applied: {}",
+                            resolved_patch.errors.len(),
+                            error.message
+                        )
+                        .into(),
+                    ));
+                    cx.notify();
+                }
+
+                this.workspace
+                    .update(cx, |workspace, cx| {
+                        workspace.add_item_to_active_pane(Box::new(editor), None, true, cx)
+                    })
+                    .log_err();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn new_patch_editor(
+        title: SharedString,
+        resolved_patch: &ResolvedPatch,
+        project: Model<Project>,
+        cx: &mut AsyncWindowContext,
+    ) -> Result<View<ProposedChangesEditor>> {
+        cx.new_view(|cx| {
             let editor = ProposedChangesEditor::new(
-                patch.title.clone(),
+                title,
                 resolved_patch
                     .edit_groups
                     .iter()
@@ -2462,12 +2828,23 @@ impl ContextEditor {
                             .collect(),
                     })
                     .collect(),
-                Some(project.clone()),
+                Some(project),
                 cx,
             );
             resolved_patch.apply(&editor, cx);
             editor
-        })?;
+        })
+    }
+
+    async fn open_patch_editor(
+        this: WeakView<Self>,
+        patch: AssistantPatch,
+        mut cx: AsyncWindowContext,
+    ) -> Result<()> {
+        let project = this.update(&mut cx, |this, _| this.project.clone())?;
+        let resolved_patch = patch.resolve(project.clone(), &mut cx).await;
+        let editor =
+            Self::new_patch_editor(patch.title.clone(), &resolved_patch, project, &mut cx)?;
 
         this.update(&mut cx, |this, cx| {
             if let Some(patch_state) = this.patches.get_mut(&patch.range) {
@@ -2576,9 +2953,11 @@ impl ContextEditor {
                 .collect();
             let mut blocks_to_replace: HashMap<_, RenderBlock> = Default::default();
 
+            let context_editor = cx.view().downgrade();
             let render_block = |message: MessageMetadata| -> RenderBlock {
                 Box::new({
                     let context = self.context.clone();
+                    let context_editor = context_editor.clone();
                     move |cx| {
                         let message_id = MessageId(message.timestamp);
                         let show_spinner = message.role == Role::Assistant
@@ -2724,6 +3103,66 @@ impl ContextEditor {
                                 ),
                                 _ => None,
                             })
+                            .child(
+                                h_flex()
+                                    .absolute()
+                                    .right_4()
+                                    .gap_1()
+                                    .children(
+                                        context.read(cx).message_branches(message_id, cx).map(
+                                            |branches| render_branch_switcher(&context, branches),
+                                        ),
+                                    )
+                                    .when(
+                                        message.role == Role::Assistant && !show_spinner,
+                                        |this| {
+                                            this.child(
+                                                IconButton::new("regenerate", IconName::RotateCw)
+                                                    .icon_size(IconSize::XSmall)
+                                                    .icon_color(Color::Muted)
+                                                    .tooltip(|cx| {
+                                                        Tooltip::text(
+                                                            "Regenerate response as a new branch",
+                                                            cx,
+                                                        )
+                                                    })
+                                                    .on_click({
+                                                        let context_editor = context_editor.clone();
+                                                        move |_, cx| {
+                                                            context_editor
+                                                                .update(cx, |this, cx| {
+                                                                    this.regenerate_message(
+                                                                        message_id, cx,
+                                                                    )
+                                                                })
+                                                                .ok();
+                                                        }
+                                                    }),
+                                            )
+                                        },
+                                    )
+                                    .child(
+                                        IconButton::new("fork", IconName::Split)
+                                            .icon_size(IconSize::XSmall)
+                                            .icon_color(Color::Muted)
+                                            .tooltip(|cx| {
+                                                Tooltip::text(
+                                                    "Fork conversation from this message",
+                                                    cx,
+                                                )
+                                            })
+                                            .on_click({
+                                                let context_editor = context_editor.clone();
+                                                move |_, cx| {
+                                                    context_editor
+                                                        .update(cx, |this, cx| {
+                                                            this.fork_context(message_id, cx)
+                                                        })
+                                                        .ok();
+                                                }
+                                            }),
+                                    ),
+                            )
                             .into_any_element()
                     }
                 })
@@ -3901,6 +4340,7 @@ impl Render for ContextEditor {
             })
             .ok()
             .flatten();
+        let agent_mode = self.context.read(cx).is_agent_mode();
         v_flex()
             .key_context("ContextEditor")
             .capture_action(cx.listener(ContextEditor::cancel))
@@ -3912,6 +4352,7 @@ impl Render for ContextEditor {
             .capture_action(cx.listener(ContextEditor::confirm_command))
             .on_action(cx.listener(ContextEditor::assist))
             .on_action(cx.listener(ContextEditor::split))
+            .on_action(cx.listener(ContextEditor::toggle_agent_mode))
             .size_full()
             .children(self.render_notice(cx))
             .child(
@@ -3968,6 +4409,24 @@ impl Render for ContextEditor {
                                             })
                                             .into()
                                         }),
+                                )
+                                .child(
+                                    IconButton::new("agent-mode-button", IconName::Bolt)
+                                        .icon_size(IconSize::Small)
+                                        .selected(agent_mode)
+                                        .on_click(|_, cx| {
+                                            cx.dispatch_action(ToggleAgentMode.boxed_clone());
+                                        })
+                                        .tooltip(move |cx| {
+                                            Tooltip::text(
+                                                if agent_mode {
+                                                    "Turn Off Agent Mode"
+                                                } else {
+                                                    "Turn On Agent Mode"
+                                                },
+                                                cx,
+                                            )
+                                        }),
                                 ),
                         )
                         .child(
@@ -4541,9 +5000,9 @@ impl ContextHistory {
                         .open_remote_context(metadata.id.clone(), cx)
                         .detach_and_log_err(cx);
                 }
-                ContextMetadata::Saved(metadata) => {
+                ContextMetadata::Saved(context_match) => {
                     assistant_panel
-                        .open_saved_context(metadata.path.clone(), cx)
+                        .open_saved_context(context_match.metadata.path.clone(), cx)
                         .detach_and_log_err(cx);
                 }
             })
@@ -4551,6 +5010,40 @@ impl ContextHistory {
     }
 }
 
+fn render_branch_switcher(context: &Model<Context>, branches: MessageBranches) -> AnyElement {
+    let switch_to = |index: usize| {
+        let context = context.clone();
+        move |_: &ClickEvent, cx: &mut WindowContext| {
+            context.update(cx, |context, cx| {
+                context.switch_branch(branches.parent, index, cx)
+            })
+        }
+    };
+
+    h_flex()
+        .gap_0p5()
+        .child(
+            IconButton::new("previous-branch", IconName::ChevronLeft)
+                .icon_size(IconSize::XSmall)
+                .disabled(branches.active_index == 0)
+                .tooltip(|cx| Tooltip::text("Previous branch", cx))
+                .on_click(switch_to(branches.active_index.saturating_sub(1))),
+        )
+        .child(
+            Label::new(format!("{}/{}", branches.active_index + 1, branches.count))
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+        )
+        .child(
+            IconButton::new("next-branch", IconName::ChevronRight)
+                .icon_size(IconSize::XSmall)
+                .disabled(branches.active_index + 1 >= branches.count)
+                .tooltip(|cx| Tooltip::text("Next branch", cx))
+                .on_click(switch_to(branches.active_index + 1)),
+        )
+        .into_any_element()
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum WorkflowAssistStatus {
     Pending,
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use ::open_ai::Model as OpenAiModel;
 use anthropic::Model as AnthropicModel;
+use assistant_tool::Tool;
+use collections::HashMap;
 use feature_flags::FeatureFlagAppExt;
 use fs::Fs;
 use gpui::{AppContext, Pixels};
@@ -11,7 +13,7 @@ use language_model::settings::{
     OpenAiSettingsContent, OpenAiSettingsContentV1, VersionedAnthropicSettingsContent,
     VersionedOpenAiSettingsContent,
 };
-use language_model::{settings::AllLanguageModelSettings, CloudModel, LanguageModel};
+use language_model::{settings::AllLanguageModelSettings, CloudModel, LanguageModel, TokenUsage};
 use ollama::Model as OllamaModel;
 use schemars::{schema::Schema, JsonSchema};
 use serde::{Deserialize, Serialize};
@@ -63,12 +65,80 @@ pub struct AssistantSettings {
     pub inline_alternatives: Vec<LanguageModelSelection>,
     pub using_outdated_settings_version: bool,
     pub enable_experimental_live_diffs: bool,
+    pub max_agent_steps: usize,
+    pub tool_approvals: HashMap<String, ToolApproval>,
+    pub model_prices: HashMap<String, ModelPrice>,
+    pub usage_budget: UsageBudget,
 }
 
 impl AssistantSettings {
     pub fn are_live_diffs_enabled(&self, cx: &AppContext) -> bool {
         cx.is_staff() || self.enable_experimental_live_diffs
     }
+
+    /// Returns whether the given tool may run without asking the user first.
+    pub fn tool_approval(&self, tool: &dyn Tool) -> ToolApproval {
+        match self.tool_approvals.get(&tool.name()) {
+            Some(approval) => *approval,
+            None if tool.needs_confirmation() => ToolApproval::Ask,
+            None => ToolApproval::Allow,
+        }
+    }
+
+    /// Returns the price of the given model, if one has been configured.
+    pub fn model_price(&self, provider: &str, model: &str) -> Option<&ModelPrice> {
+        self.model_prices.get(&format!("{provider}/{model}"))
+    }
+}
+
+/// Whether the assistant may use a tool.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolApproval {
+    /// Run the tool without asking.
+    Allow,
+    /// Ask the user before each use of the tool.
+    Ask,
+    /// Never run the tool.
+    Deny,
+}
+
+/// The price of a model's tokens, in dollars per million tokens.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ModelPrice {
+    /// The price of input tokens.
+    pub input: f64,
+    /// The price of output tokens.
+    pub output: f64,
+    /// The price of input tokens written to the prompt cache.
+    ///
+    /// Default: the price of input tokens
+    pub cache_write: Option<f64>,
+    /// The price of input tokens read from the prompt cache.
+    ///
+    /// Default: the price of input tokens
+    pub cache_read: Option<f64>,
+}
+
+impl ModelPrice {
+    /// Returns the cost of the given usage, in dollars.
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let cost = usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_creation_input_tokens as f64 * self.cache_write.unwrap_or(self.input)
+            + usage.cache_read_input_tokens as f64 * self.cache_read.unwrap_or(self.input);
+        cost / 1_000_000.
+    }
+}
+
+/// Soft limits on the cost of assistant requests, in dollars. The assistant asks before
+/// sending a request that would exceed them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct UsageBudget {
+    /// The most a single request may cost.
+    pub per_request: Option<f64>,
+    /// The most that may be spent on requests each day.
+    pub daily: Option<f64>,
 }
 
 /// Assistant panel settings
@@ -247,6 +317,10 @@ impl AssistantSettingsContent {
                         }),
                     inline_alternatives: None,
                     enable_experimental_live_diffs: None,
+                    max_agent_steps: None,
+                    tool_approvals: None,
+                    model_prices: None,
+                    usage_budget: None,
                 },
                 VersionedAssistantSettingsContent::V2(settings) => settings.clone(),
             },
@@ -267,6 +341,10 @@ impl AssistantSettingsContent {
                 }),
                 inline_alternatives: None,
                 enable_experimental_live_diffs: None,
+                max_agent_steps: None,
+                tool_approvals: None,
+                model_prices: None,
+                usage_budget: None,
             },
         }
     }
@@ -384,6 +462,10 @@ impl Default for VersionedAssistantSettingsContent {
             default_model: None,
             inline_alternatives: None,
             enable_experimental_live_diffs: None,
+            max_agent_steps: None,
+            tool_approvals: None,
+            model_prices: None,
+            usage_budget: None,
         })
     }
 }
@@ -418,6 +500,24 @@ pub struct AssistantSettingsContentV2 {
     ///
     /// Default: false
     enable_experimental_live_diffs: Option<bool>,
+    /// The number of times the model may use tools in agent mode before it's stopped.
+    ///
+    /// Default: 25
+    max_agent_steps: Option<usize>,
+    /// Whether each tool may run without asking, keyed by the tool's name.
+    /// Tools that can have side effects ask by default, and the others are allowed.
+    ///
+    /// Default: {}
+    tool_approvals: Option<HashMap<String, ToolApproval>>,
+    /// The prices of models, used to show the cost of the assistant's usage.
+    /// Keyed by the provider and model, such as "anthropic/claude-3-5-sonnet-20240620".
+    ///
+    /// Default: {}
+    model_prices: Option<HashMap<String, ModelPrice>>,
+    /// Soft limits on the cost of assistant requests.
+    ///
+    /// Default: no limits
+    usage_budget: Option<UsageBudget>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
@@ -434,6 +534,7 @@ fn providers_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema:
             "google".into(),
             "ollama".into(),
             "openai".into(),
+            "openai_compatible".into(),
             "zed.dev".into(),
             "copilot_chat".into(),
         ]),
@@ -544,6 +645,14 @@ impl Settings for AssistantSettings {
                 &mut settings.enable_experimental_live_diffs,
                 value.enable_experimental_live_diffs,
             );
+            merge(&mut settings.max_agent_steps, value.max_agent_steps);
+            if let Some(tool_approvals) = value.tool_approvals {
+                settings.tool_approvals.extend(tool_approvals);
+            }
+            if let Some(model_prices) = value.model_prices {
+                settings.model_prices.extend(model_prices);
+            }
+            merge(&mut settings.usage_budget, value.usage_budget);
         }
 
         Ok(settings)
@@ -561,6 +670,49 @@ mod tests {
     use gpui::{ReadGlobal, TestAppContext};
 
     use super::*;
+    use crate::tools::{now_tool::NowTool, run_task_tool::RunTaskTool};
+
+    #[test]
+    fn test_tool_approval() {
+        let mut settings = AssistantSettings::default();
+        assert_eq!(settings.tool_approval(&NowTool), ToolApproval::Allow);
+        assert_eq!(settings.tool_approval(&RunTaskTool), ToolApproval::Ask);
+
+        settings
+            .tool_approvals
+            .insert("run_task".into(), ToolApproval::Allow);
+        settings
+            .tool_approvals
+            .insert("now".into(), ToolApproval::Deny);
+        assert_eq!(settings.tool_approval(&RunTaskTool), ToolApproval::Allow);
+        assert_eq!(settings.tool_approval(&NowTool), ToolApproval::Deny);
+    }
+
+    #[test]
+    fn test_model_price() {
+        let mut settings = AssistantSettings::default();
+        assert_eq!(settings.model_price("anthropic", "claude-3-5-sonnet"), None);
+
+        settings.model_prices.insert(
+            "anthropic/claude-3-5-sonnet".into(),
+            ModelPrice {
+                input: 3.,
+                output: 15.,
+                cache_write: None,
+                cache_read: Some(0.3),
+            },
+        );
+        let price = settings
+            .model_price("anthropic", "claude-3-5-sonnet")
+            .unwrap();
+        let cost = price.cost(&TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_creation_input_tokens: 100_000,
+            cache_read_input_tokens: 1_000_000,
+        });
+        assert!((cost - (3. + 1.5 + 0.3 + 0.3)).abs() < 1e-9);
+    }
 
     #[gpui::test]
     async fn test_deserialize_assistant_settings_with_version(cx: &mut TestAppContext) {
@@ -603,6 +755,10 @@ mod tests {
                             default_width: None,
                             default_height: None,
                             enable_experimental_live_diffs: None,
+                            max_agent_steps: None,
+                            tool_approvals: None,
+                            model_prices: None,
+                            usage_budget: None,
                         }),
                     )
                 },
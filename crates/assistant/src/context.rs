@@ -2,8 +2,8 @@
 mod context_tests;
 
 use crate::{
-    prompts::PromptBuilder, slash_command::SlashCommandLine, AssistantEdit, AssistantPatch,
-    AssistantPatchStatus, MessageId, MessageStatus,
+    agent::Agent, prompts::PromptBuilder, slash_command::SlashCommandLine, usage, AssistantEdit,
+    AssistantPatch, AssistantPatchStatus, MessageId, MessageStatus,
 };
 use anyhow::{anyhow, Context as _, Result};
 use assistant_slash_command::{
@@ -312,6 +312,8 @@ pub enum ContextEvent {
         expand_result: bool,
     },
     UsePendingTools,
+    /// The model stopped without asking to use tools while the context is in agent mode.
+    AgentFinished,
     ToolFinished {
         tool_use_id: Arc<str>,
         output_range: Range<language::Anchor>,
@@ -419,6 +421,14 @@ impl Content {
         }
     }
 
+    fn with_range(mut self, new_range: Range<language::Anchor>) -> Self {
+        match &mut self {
+            Self::Image { anchor, .. } => *anchor = new_range.start,
+            Self::ToolUse { range, .. } | Self::ToolResult { range, .. } => *range = new_range,
+        }
+        self
+    }
+
     fn cmp(&self, other: &Self, buffer: &BufferSnapshot) -> Ordering {
         let self_range = self.range();
         let other_range = other.range();
@@ -492,6 +502,8 @@ pub struct Context {
     xml_tags: Vec<XmlTag>,
     project: Option<Model<Project>>,
     prompt_builder: Arc<PromptBuilder>,
+    agent: Option<Agent>,
+    branch_points: Vec<BranchPoint<MessageId>>,
 }
 
 trait ContextAnnotation {
@@ -592,6 +604,8 @@ impl Context {
             patches: Vec::new(),
             xml_tags: Vec::new(),
             prompt_builder,
+            agent: None,
+            branch_points: Vec::new(),
         };
 
         let first_message_id = MessageId(clock::Lamport {
@@ -655,6 +669,16 @@ impl Context {
                     }
                 })
                 .collect(),
+            branch_points: self
+                .branch_points
+                .iter()
+                .filter(|branch_point| {
+                    self.message_anchors.iter().any(|anchor| {
+                        anchor.id == branch_point.parent && anchor.start.is_valid(buffer)
+                    })
+                })
+                .cloned()
+                .collect(),
         }
     }
 
@@ -667,6 +691,27 @@ impl Context {
         project: Option<Model<Project>>,
         telemetry: Option<Arc<Telemetry>>,
         cx: &mut ModelContext<Self>,
+    ) -> Self {
+        let mut this = Self::unsaved(
+            saved_context,
+            language_registry,
+            prompt_builder,
+            project,
+            telemetry,
+            cx,
+        );
+        this.path = Some(path);
+        this
+    }
+
+    /// Creates a context from a saved one that hasn't been written to disk yet, such as a fork.
+    pub fn unsaved(
+        mut saved_context: SavedContext,
+        language_registry: Arc<LanguageRegistry>,
+        prompt_builder: Arc<PromptBuilder>,
+        project: Option<Model<Project>>,
+        telemetry: Option<Arc<Telemetry>>,
+        cx: &mut ModelContext<Self>,
     ) -> Self {
         let id = saved_context.id.clone().unwrap_or_else(ContextId::new);
         let mut this = Self::new(
@@ -679,7 +724,7 @@ impl Context {
             telemetry,
             cx,
         );
-        this.path = Some(path);
+        this.branch_points = mem::take(&mut saved_context.branch_points);
         this.buffer.update(cx, |buffer, cx| {
             buffer.set_text(saved_context.text.as_str(), cx)
         });
@@ -688,6 +733,38 @@ impl Context {
         this
     }
 
+    /// Returns this conversation up to and including the given message, as a new context that
+    /// can be continued independently.
+    pub fn fork(&self, message_id: MessageId, cx: &AppContext) -> Option<SavedContext> {
+        let message = self.messages(cx).find(|message| message.id == message_id)?;
+        let buffer = self.buffer.read(cx);
+        // Exclude the newline that separates the message from the next one.
+        let end = if message.offset_range.end < buffer.len() {
+            message.offset_range.end - 1
+        } else {
+            message.offset_range.end
+        };
+
+        let mut saved_context = self.serialize(cx);
+        saved_context.id = Some(ContextId::new());
+        saved_context.text.truncate(end);
+        saved_context
+            .messages
+            .retain(|message| message.start <= end);
+        saved_context
+            .slash_command_output_sections
+            .retain(|section| section.range.end <= end);
+        // The branches that follow the forked message continue past the fork point.
+        saved_context.branch_points.retain(|branch_point| {
+            branch_point.parent != message_id
+                && saved_context
+                    .messages
+                    .iter()
+                    .any(|message| message.id == branch_point.parent)
+        });
+        Some(saved_context)
+    }
+
     pub fn id(&self) -> &ContextId {
         &self.id
     }
@@ -1781,6 +1858,10 @@ impl Context {
                 removed: vec![pending_command.source_range.clone()],
                 updated: vec![pending_command.clone()],
             });
+        } else {
+            // Output that replaces an earlier section, rather than a command, has no pending
+            // command to keep it running.
+            insert_output_task.detach();
         }
     }
 
@@ -1859,6 +1940,28 @@ impl Context {
         })
     }
 
+    pub(crate) fn agent(&self) -> Option<&Agent> {
+        self.agent.as_ref()
+    }
+
+    pub(crate) fn agent_mut(&mut self) -> Option<&mut Agent> {
+        self.agent.as_mut()
+    }
+
+    pub fn is_agent_mode(&self) -> bool {
+        self.agent.is_some()
+    }
+
+    /// Turns agent mode on or off. In agent mode the model is given the built-in project tools,
+    /// and keeps using them until it's done.
+    pub fn set_agent_mode(&mut self, enabled: bool, cx: &mut ModelContext<Self>) {
+        if enabled == self.is_agent_mode() {
+            return;
+        }
+        self.agent = enabled.then(Agent::new);
+        cx.notify();
+    }
+
     pub fn assist(&mut self, cx: &mut ModelContext<Self>) -> Option<MessageAnchor> {
         let model_registry = LanguageModelRegistry::read_global(cx);
         let provider = model_registry.active_provider()?;
@@ -1874,18 +1977,25 @@ impl Context {
 
         let mut request = self.to_completion_request(cx);
 
-        if cx.has_flag::<ToolUseFeatureFlag>() {
-            let tool_registry = ToolRegistry::global(cx);
-            request.tools = tool_registry
-                .tools()
-                .into_iter()
-                .map(|tool| LanguageModelRequestTool {
-                    name: tool.name(),
-                    description: tool.description(),
-                    input_schema: tool.input_schema(),
-                })
-                .collect();
+        let mut tools = Vec::new();
+        if let Some(agent) = self.agent.as_ref() {
+            tools.extend(agent.tools().iter().cloned());
         }
+        if self.agent.is_some() || cx.has_flag::<ToolUseFeatureFlag>() {
+            tools.extend(ToolRegistry::global(cx).tools());
+        }
+        // Providers reject requests with duplicate tool names. The agent's tools take
+        // precedence, as they do when the model uses one.
+        let mut tool_names = HashSet::default();
+        tools.retain(|tool| tool_names.insert(tool.name()));
+        request.tools = tools
+            .into_iter()
+            .map(|tool| LanguageModelRequestTool {
+                name: tool.name(),
+                description: tool.description(),
+                input_schema: tool.input_schema(),
+            })
+            .collect();
 
         let assistant_message = self
             .insert_message_after(last_message_id, Role::Assistant, MessageStatus::Pending, cx)
@@ -1897,12 +2007,15 @@ impl Context {
             .unwrap();
 
         let pending_completion_id = post_inc(&mut self.completion_count);
+        let input_token_estimate = self.token_count;
 
         let task = cx.spawn({
             |this, mut cx| async move {
                 let stream = model.stream_completion(request, &cx);
                 let assistant_message_id = assistant_message.id;
                 let mut response_latency = None;
+                let mut reported_usage = None;
+                let mut response_text = String::new();
                 let stream_completion = async {
                     let request_start = Instant::now();
                     let mut events = stream.await?;
@@ -1913,6 +2026,15 @@ impl Context {
                             response_latency = Some(request_start.elapsed());
                         }
                         let event = event?;
+                        match &event {
+                            LanguageModelCompletionEvent::UsageUpdate(usage) => {
+                                reported_usage = Some(*usage);
+                            }
+                            LanguageModelCompletionEvent::Text(chunk) => {
+                                response_text.push_str(chunk);
+                            }
+                            _ => {}
+                        }
 
                         this.update(&mut cx, |this, cx| {
                             let message_ix = this
@@ -1980,6 +2102,7 @@ impl Context {
                                             },
                                         );
                                     }
+                                    LanguageModelCompletionEvent::UsageUpdate(_) => {}
                                 }
                             });
 
@@ -2001,6 +2124,20 @@ impl Context {
 
                 let result = stream_completion.await;
 
+                if let Some(usage) = reported_usage {
+                    cx.update(|cx| usage::record_usage(model.as_ref(), usage, false, cx))
+                        .ok();
+                } else if result.is_ok() {
+                    // The provider didn't report the request's usage, so we estimate it.
+                    usage::record_estimated_usage(
+                        model.as_ref(),
+                        input_token_estimate.unwrap_or_default(),
+                        response_text,
+                        &cx,
+                    )
+                    .await;
+                }
+
                 this.update(&mut cx, |this, cx| {
                     let error_message = if let Some(error) = result.as_ref().err() {
                         if error.is::<PaymentRequiredError>() {
@@ -2055,11 +2192,15 @@ impl Context {
                         match stop_reason {
                             StopReason::ToolUse => {
                                 cx.emit(ContextEvent::UsePendingTools);
+                                return;
                             }
                             StopReason::EndTurn => {}
                             StopReason::MaxTokens => {}
                         }
                     }
+                    if this.is_agent_mode() {
+                        cx.emit(ContextEvent::AgentFinished);
+                    }
                 })
                 .ok();
             }
@@ -2179,6 +2320,334 @@ impl Context {
         }
     }
 
+    /// Replaces an assistant message, and the messages after it, with a new response. The
+    /// replaced messages are kept as a branch that can be switched back to.
+    pub fn regenerate_message(
+        &mut self,
+        message_id: MessageId,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<MessageAnchor> {
+        if !self.pending_completions.is_empty() {
+            return None;
+        }
+
+        // Check that we can assist before removing any messages.
+        let model_registry = LanguageModelRegistry::read_global(cx);
+        let provider = model_registry.active_provider()?;
+        model_registry.active_model()?;
+        if !provider.is_authenticated(cx) {
+            log::info!("completion provider has no credentials");
+            return None;
+        }
+
+        let messages = self.messages(cx).collect::<Vec<_>>();
+        let message_ix = messages
+            .iter()
+            .position(|message| message.id == message_id)?;
+        if messages[message_ix].role != Role::Assistant || message_ix == 0 {
+            return None;
+        }
+        let parent = messages[message_ix - 1].id;
+
+        let branch = self.take_branch(parent, cx)?;
+        if let Some(branch_point) = self
+            .branch_points
+            .iter_mut()
+            .find(|branch_point| branch_point.parent == parent)
+        {
+            branch_point.branches[branch_point.active_index] = Some(branch);
+            branch_point.branches.push(None);
+            branch_point.active_index = branch_point.branches.len() - 1;
+        } else {
+            self.branch_points.push(BranchPoint {
+                parent,
+                branches: vec![Some(branch), None],
+                active_index: 1,
+            });
+        }
+
+        self.assist(cx)
+    }
+
+    /// Returns the branches that the given message is the first message of.
+    pub fn message_branches(
+        &self,
+        message_id: MessageId,
+        cx: &AppContext,
+    ) -> Option<MessageBranches> {
+        let mut parent = None;
+        for message in self.messages(cx) {
+            if message.id == message_id {
+                let parent = parent?;
+                let branch_point = self
+                    .branch_points
+                    .iter()
+                    .find(|branch_point| branch_point.parent == parent)?;
+                return Some(MessageBranches {
+                    parent,
+                    active_index: branch_point.active_index,
+                    count: branch_point.branches.len(),
+                });
+            }
+            parent = Some(message.id);
+        }
+        None
+    }
+
+    /// Replaces the messages after `parent` with those of another of its branches.
+    pub fn switch_branch(&mut self, parent: MessageId, index: usize, cx: &mut ModelContext<Self>) {
+        if !self.pending_completions.is_empty() {
+            return;
+        }
+        let Some(branch_point) = self
+            .branch_points
+            .iter()
+            .find(|branch_point| branch_point.parent == parent)
+        else {
+            return;
+        };
+        if index == branch_point.active_index || index >= branch_point.branches.len() {
+            return;
+        }
+
+        let Some(active_branch) = self.take_branch(parent, cx) else {
+            return;
+        };
+        let Some(branch_point) = self
+            .branch_points
+            .iter_mut()
+            .find(|branch_point| branch_point.parent == parent)
+        else {
+            return;
+        };
+        branch_point.branches[branch_point.active_index] = Some(active_branch);
+        branch_point.active_index = index;
+        if let Some(branch) = branch_point.branches[index].take() {
+            self.restore_branch(branch, cx);
+        }
+
+        cx.emit(ContextEvent::MessagesEdited);
+        cx.notify();
+    }
+
+    /// Removes the messages after `parent`, which must be followed only by the messages of its
+    /// active branch, returning them as a branch.
+    fn take_branch(
+        &mut self,
+        parent: MessageId,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<SavedBranch> {
+        let messages = self.messages(cx).collect::<Vec<_>>();
+        let parent_ix = messages.iter().position(|message| message.id == parent)?;
+        let branch_messages = &messages[parent_ix + 1..];
+        let Some(first_message) = branch_messages.first() else {
+            return Some(SavedBranch {
+                text: String::new(),
+                messages: Vec::new(),
+                branch_points: Vec::new(),
+                slash_command_output_sections: Vec::new(),
+                contents: Vec::new(),
+                tool_uses: Vec::new(),
+            });
+        };
+
+        // Include the newline that separates the branch from its parent.
+        let start = first_message.offset_range.start - 1;
+        let end = self.buffer.read(cx).len();
+        let text = self
+            .buffer
+            .read(cx)
+            .text_for_range(start..end)
+            .collect::<String>();
+        let saved_messages = branch_messages
+            .iter()
+            .map(|message| SavedBranchMessage {
+                start: message.offset_range.start - start,
+                role: message.role,
+                status: message.status.clone(),
+            })
+            .collect();
+
+        let mut branch_points = Vec::new();
+        self.branch_points.retain_mut(|branch_point| {
+            if let Some(parent_ix) = branch_messages
+                .iter()
+                .position(|message| message.id == branch_point.parent)
+            {
+                branch_points.push(BranchPoint {
+                    parent: parent_ix,
+                    branches: mem::take(&mut branch_point.branches),
+                    active_index: branch_point.active_index,
+                });
+                false
+            } else {
+                true
+            }
+        });
+
+        let buffer = self.buffer.read(cx);
+        let relative_range = |range: Range<usize>| range.start - start..range.end - start;
+        let mut slash_command_output_sections = Vec::new();
+        self.slash_command_output_sections.retain(|section| {
+            if !section.is_valid(buffer) {
+                return true;
+            }
+            let range = section.range.to_offset(buffer);
+            if range.start <= start {
+                return true;
+            }
+            slash_command_output_sections.push(
+                assistant_slash_command::SlashCommandOutputSection {
+                    range: relative_range(range),
+                    icon: section.icon,
+                    label: section.label.clone(),
+                    metadata: section.metadata.clone(),
+                },
+            );
+            false
+        });
+        let mut contents = Vec::new();
+        self.contents.retain(|content| {
+            let range = content.range();
+            if !range.start.is_valid(buffer) || !range.end.is_valid(buffer) {
+                return true;
+            }
+            let range = range.to_offset(buffer);
+            if range.start <= start {
+                return true;
+            }
+            contents.push((relative_range(range), content.clone()));
+            false
+        });
+        let mut tool_uses = Vec::new();
+        self.pending_tool_uses_by_id.retain(|_, tool_use| {
+            let range = tool_use.source_range.to_offset(buffer);
+            if range.start <= start {
+                return true;
+            }
+            tool_uses.push((relative_range(range), tool_use.clone()));
+            false
+        });
+
+        self.buffer
+            .update(cx, |buffer, cx| buffer.edit([(start..end, "")], None, cx));
+
+        Some(SavedBranch {
+            text,
+            messages: saved_messages,
+            branch_points,
+            slash_command_output_sections,
+            contents,
+            tool_uses,
+        })
+    }
+
+    /// Appends the messages of a branch to the end of the context.
+    fn restore_branch(&mut self, branch: SavedBranch, cx: &mut ModelContext<Self>) {
+        let offset = self.buffer.read(cx).len();
+        if !branch.text.is_empty() {
+            self.buffer.update(cx, |buffer, cx| {
+                buffer.edit([(offset..offset, branch.text.as_str())], None, cx)
+            });
+        }
+
+        let mut message_ids = Vec::with_capacity(branch.messages.len());
+        for message in branch.messages {
+            let version = self.version.clone();
+            let anchor = MessageAnchor {
+                id: MessageId(self.next_timestamp()),
+                start: self.buffer.read(cx).anchor_before(offset + message.start),
+            };
+            let metadata = MessageMetadata {
+                role: message.role,
+                status: message.status,
+                timestamp: anchor.id.0,
+                cache: None,
+            };
+            self.insert_message(anchor.clone(), metadata.clone(), cx);
+            self.push_op(
+                ContextOperation::InsertMessage {
+                    anchor: anchor.clone(),
+                    metadata,
+                    version,
+                },
+                cx,
+            );
+            message_ids.push(anchor.id);
+        }
+
+        self.branch_points
+            .extend(branch.branch_points.into_iter().filter_map(|branch_point| {
+                Some(BranchPoint {
+                    parent: *message_ids.get(branch_point.parent)?,
+                    branches: branch_point.branches,
+                    active_index: branch_point.active_index,
+                })
+            }));
+
+        let buffer = self.buffer.clone();
+        let anchor_range = |range: Range<usize>, buffer: &Buffer| {
+            buffer.anchor_after(offset + range.start)..buffer.anchor_before(offset + range.end)
+        };
+
+        if !branch.slash_command_output_sections.is_empty() {
+            let version = self.version.clone();
+            let command_id = SlashCommandId(self.next_timestamp());
+            let (output_range, sections) = {
+                let buffer = buffer.read(cx);
+                let sections = branch
+                    .slash_command_output_sections
+                    .into_iter()
+                    .map(|section| SlashCommandOutputSection {
+                        range: anchor_range(section.range, buffer),
+                        icon: section.icon,
+                        label: section.label,
+                        metadata: section.metadata,
+                    })
+                    .collect::<Vec<_>>();
+                self.slash_command_output_sections
+                    .extend(sections.iter().cloned());
+                self.slash_command_output_sections
+                    .sort_by(|a, b| a.range.cmp(&b.range, buffer));
+                (anchor_range(0..branch.text.len(), buffer), sections)
+            };
+            self.finished_slash_commands.insert(command_id);
+            self.push_op(
+                ContextOperation::SlashCommandFinished {
+                    id: command_id,
+                    output_range: output_range.clone(),
+                    sections: sections.clone(),
+                    version,
+                },
+                cx,
+            );
+            cx.emit(ContextEvent::SlashCommandFinished {
+                output_range,
+                sections,
+                run_commands_in_output: false,
+                expand_result: false,
+            });
+        }
+
+        for (range, content) in branch.contents {
+            let range = anchor_range(range, buffer.read(cx));
+            self.insert_content(content.with_range(range), cx);
+        }
+
+        for (range, tool_use) in branch.tool_uses {
+            let buffer = buffer.read(cx);
+            let source_range =
+                buffer.anchor_after(offset + range.start)..buffer.anchor_after(offset + range.end);
+            self.pending_tool_uses_by_id.insert(
+                tool_use.id.clone(),
+                PendingToolUse {
+                    source_range,
+                    ..tool_use
+                },
+            );
+        }
+    }
+
     pub fn cycle_message_roles(&mut self, ids: HashSet<MessageId>, cx: &mut ModelContext<Self>) {
         for id in &ids {
             if let Some(metadata) = self.messages_metadata.get(id) {
@@ -2808,6 +3277,58 @@ pub struct SavedMessage {
     pub metadata: MessageMetadata,
 }
 
+/// The alternative continuations of a conversation after one of its messages, such as the
+/// responses an assistant message was regenerated with.
+///
+/// Only the active branch's messages are in the context's buffer; the others are kept as text
+/// until they are switched to. Branches are local to this replica and aren't shared with
+/// collaborators.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BranchPoint<P> {
+    /// The message the branches follow.
+    pub parent: P,
+    /// The branches, which are `None` for the active one.
+    pub branches: Vec<Option<SavedBranch>>,
+    pub active_index: usize,
+}
+
+/// The messages of an inactive branch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedBranch {
+    /// The text of the branch's messages, starting with the newline that precedes the first one.
+    pub text: String,
+    pub messages: Vec<SavedBranchMessage>,
+    /// The branch points within this branch, whose parents are indices into `messages`.
+    pub branch_points: Vec<BranchPoint<usize>>,
+    /// The slash command output sections within the branch, relative to the start of its text.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slash_command_output_sections:
+        Vec<assistant_slash_command::SlashCommandOutputSection<usize>>,
+    /// The images, tool uses and tool results within the branch. Like the context's own, they're
+    /// only kept in memory.
+    #[serde(skip)]
+    contents: Vec<(Range<usize>, Content)>,
+    /// The tool uses requested within the branch.
+    #[serde(skip)]
+    tool_uses: Vec<(Range<usize>, PendingToolUse)>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedBranchMessage {
+    /// The offset of the message's start in the branch's text.
+    pub start: usize,
+    pub role: Role,
+    pub status: MessageStatus,
+}
+
+/// The branches that follow a message, as shown on the first message of the active branch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageBranches {
+    pub parent: MessageId,
+    pub active_index: usize,
+    pub count: usize,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SavedContext {
     pub id: Option<ContextId>,
@@ -2818,6 +3339,8 @@ pub struct SavedContext {
     pub summary: String,
     pub slash_command_output_sections:
         Vec<assistant_slash_command::SlashCommandOutputSection<usize>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branch_points: Vec<BranchPoint<MessageId>>,
 }
 
 impl SavedContext {
@@ -2997,6 +3520,7 @@ impl SavedContextV0_3_0 {
                 .collect(),
             summary: self.summary,
             slash_command_output_sections: self.slash_command_output_sections,
+            branch_points: Vec::new(),
         }
     }
 }
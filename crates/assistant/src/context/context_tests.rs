@@ -1,7 +1,8 @@
 use super::{AssistantEdit, MessageCacheMetadata};
 use crate::{
-    assistant_panel, prompt_library, slash_command::file_command, AssistantEditKind, CacheStatus,
-    Context, ContextEvent, ContextId, ContextOperation, MessageId, MessageStatus, PromptBuilder,
+    assistant_panel, prompt_library, slash_command::file_command, AssistantEditKind, BranchPoint,
+    CacheStatus, Context, ContextEvent, ContextId, ContextOperation, MessageBranches, MessageId,
+    MessageStatus, PromptBuilder,
 };
 use anyhow::Result;
 use assistant_slash_command::{
@@ -1360,6 +1361,277 @@ fn test_mark_cache_anchors(cx: &mut AppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_branching(cx: &mut TestAppContext) {
+    let settings_store = cx.update(SettingsStore::test);
+    cx.set_global(settings_store);
+    cx.update(LanguageModelRegistry::test);
+    cx.update(assistant_panel::init);
+    let registry = Arc::new(LanguageRegistry::test(cx.executor()));
+    let prompt_builder = Arc::new(PromptBuilder::new(None).unwrap());
+    let context =
+        cx.new_model(|cx| Context::local(registry.clone(), None, None, prompt_builder.clone(), cx));
+    let buffer = context.read_with(cx, |context, _| context.buffer.clone());
+    let message_0 = context.read_with(cx, |context, _| context.message_anchors[0].id);
+    let message_1 = context.update(cx, |context, cx| {
+        context
+            .insert_message_after(message_0, Role::Assistant, MessageStatus::Done, cx)
+            .unwrap()
+    });
+    context.update(cx, |context, cx| {
+        context
+            .insert_message_after(message_1.id, Role::User, MessageStatus::Done, cx)
+            .unwrap()
+    });
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..0, "a"), (1..1, "b"), (2..2, "c")], None, cx)
+    });
+    assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "a\nb\nc");
+
+    // Regenerating a response replaces it, and the messages after it, with a new branch.
+    context
+        .update(cx, |context, cx| {
+            context.regenerate_message(message_1.id, cx)
+        })
+        .unwrap();
+    context.update(cx, |context, cx| context.cancel_last_assist(cx));
+    assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "a\n\n");
+    let roles_and_ranges = |context: &Model<Context>, cx: &mut TestAppContext| {
+        cx.read(|cx| messages(context, cx))
+            .into_iter()
+            .map(|(_, role, range)| (role, range))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        roles_and_ranges(&context, cx),
+        [
+            (Role::User, 0..2),
+            (Role::Assistant, 2..3),
+            (Role::User, 3..3)
+        ]
+    );
+    let regenerated_message = cx.read(|cx| messages(&context, cx))[1].0;
+    assert_eq!(
+        context.read_with(cx, |context, cx| context
+            .message_branches(regenerated_message, cx)),
+        Some(MessageBranches {
+            parent: message_0,
+            active_index: 1,
+            count: 2,
+        })
+    );
+    buffer.update(cx, |buffer, cx| buffer.edit([(2..2, "d")], None, cx));
+
+    // Switching back restores the original messages, keeping the regenerated ones.
+    context.update(cx, |context, cx| context.switch_branch(message_0, 0, cx));
+    assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "a\nb\nc");
+    assert_eq!(
+        roles_and_ranges(&context, cx),
+        [
+            (Role::User, 0..2),
+            (Role::Assistant, 2..4),
+            (Role::User, 4..5)
+        ]
+    );
+    let restored_message = cx.read(|cx| messages(&context, cx))[1].0;
+    assert_eq!(
+        context
+            .read_with(cx, |context, cx| context
+                .message_branches(restored_message, cx))
+            .map(|branches| branches.active_index),
+        Some(0)
+    );
+
+    // Inactive branches are saved along with the context.
+    let serialized_context = context.read_with(cx, |context, cx| context.serialize(cx));
+    let deserialized_context = cx.new_model(|cx| {
+        Context::deserialize(
+            serialized_context,
+            Default::default(),
+            registry.clone(),
+            prompt_builder.clone(),
+            None,
+            None,
+            cx,
+        )
+    });
+    deserialized_context.update(cx, |context, cx| context.switch_branch(message_0, 1, cx));
+    assert_eq!(
+        deserialized_context.read_with(cx, |context, cx| context.buffer.read(cx).text()),
+        "a\nd\n"
+    );
+    assert_eq!(
+        roles_and_ranges(&deserialized_context, cx),
+        [
+            (Role::User, 0..2),
+            (Role::Assistant, 2..4),
+            (Role::User, 4..4)
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_branching_with_slash_command_output(cx: &mut TestAppContext) {
+    let settings_store = cx.update(SettingsStore::test);
+    cx.set_global(settings_store);
+    cx.update(LanguageModelRegistry::test);
+    cx.update(assistant_panel::init);
+    let registry = Arc::new(LanguageRegistry::test(cx.executor()));
+    let prompt_builder = Arc::new(PromptBuilder::new(None).unwrap());
+    let context =
+        cx.new_model(|cx| Context::local(registry.clone(), None, None, prompt_builder.clone(), cx));
+    let buffer = context.read_with(cx, |context, _| context.buffer.clone());
+    let message_0 = context.read_with(cx, |context, _| context.message_anchors[0].id);
+    let message_1 = context.update(cx, |context, cx| {
+        context
+            .insert_message_after(message_0, Role::Assistant, MessageStatus::Done, cx)
+            .unwrap()
+    });
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..0, "a"), (1..1, "b\noutput")], None, cx)
+    });
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "a\nb\noutput"
+    );
+    context.update(cx, |context, cx| {
+        let buffer = context.buffer.read(cx);
+        let range = buffer.anchor_after(4)..buffer.anchor_before(10);
+        context
+            .slash_command_output_sections
+            .push(SlashCommandOutputSection {
+                range,
+                icon: ui::IconName::Ai,
+                label: "output".into(),
+                metadata: None,
+            });
+    });
+    let sections = |context: &Model<Context>, cx: &mut TestAppContext| {
+        context.read_with(cx, |context, cx| {
+            let buffer = context.buffer.read(cx);
+            context
+                .slash_command_output_sections()
+                .iter()
+                .filter(|section| section.is_valid(buffer))
+                .map(|section| (section.range.to_offset(buffer), section.label.clone()))
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(
+        sections(&context, cx),
+        [(4..10, SharedString::from("output"))]
+    );
+
+    // The section is taken along with the regenerated message.
+    context
+        .update(cx, |context, cx| {
+            context.regenerate_message(message_1.id, cx)
+        })
+        .unwrap();
+    context.update(cx, |context, cx| context.cancel_last_assist(cx));
+    assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "a\n\n");
+    assert!(sections(&context, cx).is_empty());
+
+    // Inactive branches are saved with their sections, which are restored when switching back.
+    let serialized_context = context.read_with(cx, |context, cx| context.serialize(cx));
+    let deserialized_context = cx.new_model(|cx| {
+        Context::deserialize(
+            serialized_context,
+            Default::default(),
+            registry.clone(),
+            prompt_builder.clone(),
+            None,
+            None,
+            cx,
+        )
+    });
+    for context in [&context, &deserialized_context] {
+        context.update(cx, |context, cx| context.switch_branch(message_0, 0, cx));
+        assert_eq!(
+            context.read_with(cx, |context, cx| context.buffer.read(cx).text()),
+            "a\nb\noutput"
+        );
+        assert_eq!(
+            sections(context, cx),
+            [(4..10, SharedString::from("output"))]
+        );
+    }
+}
+
+#[gpui::test]
+fn test_fork(cx: &mut AppContext) {
+    let settings_store = SettingsStore::test(cx);
+    LanguageModelRegistry::test(cx);
+    cx.set_global(settings_store);
+    assistant_panel::init(cx);
+    let registry = Arc::new(LanguageRegistry::test(cx.background_executor().clone()));
+    let prompt_builder = Arc::new(PromptBuilder::new(None).unwrap());
+    let context =
+        cx.new_model(|cx| Context::local(registry.clone(), None, None, prompt_builder.clone(), cx));
+    let buffer = context.read(cx).buffer.clone();
+    let message_0 = context.read(cx).message_anchors[0].id;
+    let message_1 = context.update(cx, |context, cx| {
+        context
+            .insert_message_after(message_0, Role::Assistant, MessageStatus::Done, cx)
+            .unwrap()
+    });
+    context.update(cx, |context, cx| {
+        context
+            .insert_message_after(message_1.id, Role::User, MessageStatus::Done, cx)
+            .unwrap()
+    });
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..0, "a"), (1..1, "b"), (2..2, "c")], None, cx)
+    });
+    context.update(cx, |context, _| {
+        for parent in [message_0, message_1.id] {
+            context.branch_points.push(BranchPoint {
+                parent,
+                branches: vec![None],
+                active_index: 0,
+            });
+        }
+    });
+
+    let saved_context = context.read(cx).fork(message_1.id, cx).unwrap();
+    assert_ne!(saved_context.id.as_ref(), Some(context.read(cx).id()));
+    let fork = cx.new_model(|cx| {
+        Context::unsaved(
+            saved_context,
+            registry.clone(),
+            prompt_builder.clone(),
+            None,
+            None,
+            cx,
+        )
+    });
+    assert_eq!(fork.read(cx).buffer.read(cx).text(), "a\nb");
+    assert_eq!(
+        messages(&fork, cx),
+        [
+            (message_0, Role::User, 0..2),
+            (message_1.id, Role::Assistant, 2..3)
+        ]
+    );
+
+    // Branches after the forked message continue past the fork point, so they aren't forked.
+    assert_eq!(
+        fork.read(cx)
+            .branch_points
+            .iter()
+            .map(|branch_point| branch_point.parent)
+            .collect::<Vec<_>>(),
+        [message_0]
+    );
+
+    // The fork is independent of the original context.
+    fork.update(cx, |fork, cx| {
+        fork.buffer
+            .update(cx, |buffer, cx| buffer.edit([(3..3, "e")], None, cx))
+    });
+    assert_eq!(buffer.read(cx).text(), "a\nb\nc");
+}
+
 fn messages(context: &Model<Context>, cx: &AppContext) -> Vec<(MessageId, Role, Range<usize>)> {
     context
         .read(cx)
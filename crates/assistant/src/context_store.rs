@@ -1,10 +1,12 @@
 use crate::{
     prompts::PromptBuilder, Context, ContextEvent, ContextId, ContextOperation, ContextVersion,
-    SavedContext, SavedContextMetadata,
+    MessageId, SavedContext, SavedContextMetadata,
 };
 use anyhow::{anyhow, Context as _, Result};
+use chrono::{DateTime, Local};
 use client::{proto, telemetry::Telemetry, Client, TypedEnvelope};
 use clock::ReplicaId;
+use collections::HashMap;
 use fs::Fs;
 use futures::StreamExt;
 use fuzzy::StringMatchCandidate;
@@ -12,6 +14,7 @@ use gpui::{
     AppContext, AsyncAppContext, Context as _, EventEmitter, Model, ModelContext, Task, WeakModel,
 };
 use language::LanguageRegistry;
+use parking_lot::Mutex;
 use paths::contexts_dir;
 use project::Project;
 use regex::Regex;
@@ -34,15 +37,27 @@ pub fn init(client: &AnyProtoClient) {
     client.add_model_request_handler(ContextStore::handle_synchronize_contexts);
 }
 
+/// The length, in characters, of the excerpts shown for contexts whose messages match a search.
+const MAX_EXCERPT_LEN: usize = 80;
+
 #[derive(Clone)]
 pub struct RemoteContextMetadata {
     pub id: ContextId,
     pub summary: Option<String>,
 }
 
+#[derive(Clone)]
+pub struct SavedContextMatch {
+    pub metadata: SavedContextMetadata,
+    /// The line of the context's messages that contains the query, if its title doesn't match.
+    pub excerpt: Option<String>,
+}
+
 pub struct ContextStore {
     contexts: Vec<ContextHandle>,
     contexts_metadata: Vec<SavedContextMetadata>,
+    /// The text of saved contexts that have been searched, keyed by path, with their mtime.
+    saved_context_texts: Arc<Mutex<HashMap<PathBuf, (DateTime<Local>, Arc<str>)>>>,
     host_contexts: Vec<RemoteContextMetadata>,
     fs: Arc<dyn Fs>,
     languages: Arc<LanguageRegistry>,
@@ -100,6 +115,7 @@ impl ContextStore {
                 let mut this = Self {
                     contexts: Vec::new(),
                     contexts_metadata: Vec::new(),
+                    saved_context_texts: Default::default(),
                     host_contexts: Vec::new(),
                     fs,
                     languages,
@@ -349,6 +365,29 @@ impl ContextStore {
         context
     }
 
+    /// Creates a new context containing the given context's messages up to and including
+    /// `message_id`.
+    pub fn fork_context(
+        &mut self,
+        context: &Model<Context>,
+        message_id: MessageId,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<Model<Context>> {
+        let saved_context = context.read(cx).fork(message_id, cx)?;
+        let context = cx.new_model(|cx| {
+            Context::unsaved(
+                saved_context,
+                self.languages.clone(),
+                self.prompt_builder.clone(),
+                Some(self.project.clone()),
+                Some(self.telemetry.clone()),
+                cx,
+            )
+        });
+        self.register_context(&context, cx);
+        Some(context)
+    }
+
     pub fn create_remote_context(
         &mut self,
         cx: &mut ModelContext<Self>,
@@ -667,33 +706,89 @@ impl ContextStore {
         .detach_and_log_err(cx);
     }
 
-    pub fn search(&self, query: String, cx: &AppContext) -> Task<Vec<SavedContextMetadata>> {
+    /// Searches the saved contexts whose title matches the query, followed by those with a
+    /// message that contains it.
+    pub fn search(&self, query: String, cx: &AppContext) -> Task<Vec<SavedContextMatch>> {
         let metadata = self.contexts_metadata.clone();
+        let fs = self.fs.clone();
+        let texts = self.saved_context_texts.clone();
         let executor = cx.background_executor().clone();
         cx.background_executor().spawn(async move {
-            if query.is_empty() {
+            if query.trim().is_empty() {
+                return metadata
+                    .into_iter()
+                    .map(|metadata| SavedContextMatch {
+                        metadata,
+                        excerpt: None,
+                    })
+                    .collect();
+            }
+
+            let candidates = metadata
+                .iter()
+                .enumerate()
+                .map(|(id, metadata)| StringMatchCandidate::new(id, metadata.title.clone()))
+                .collect::<Vec<_>>();
+            let title_matches = fuzzy::match_strings(
+                &candidates,
+                &query,
+                false,
+                100,
+                &Default::default(),
+                executor,
+            )
+            .await;
+
+            let mut matches = title_matches
+                .iter()
+                .map(|mat| SavedContextMatch {
+                    metadata: metadata[mat.candidate_id].clone(),
+                    excerpt: None,
+                })
+                .collect::<Vec<_>>();
+            let query = query.trim().to_lowercase();
+            texts.lock().retain(|path, _| {
                 metadata
-            } else {
-                let candidates = metadata
                     .iter()
-                    .enumerate()
-                    .map(|(id, metadata)| StringMatchCandidate::new(id, metadata.title.clone()))
-                    .collect::<Vec<_>>();
-                let matches = fuzzy::match_strings(
-                    &candidates,
-                    &query,
-                    false,
-                    100,
-                    &Default::default(),
-                    executor,
-                )
-                .await;
+                    .any(|metadata| metadata.path.as_path() == path.as_path())
+            });
+            for (ix, metadata) in metadata.iter().enumerate() {
+                if title_matches.iter().any(|mat| mat.candidate_id == ix) {
+                    continue;
+                }
 
-                matches
-                    .into_iter()
-                    .map(|mat| metadata[mat.candidate_id].clone())
-                    .collect()
+                let cached_text = texts
+                    .lock()
+                    .get(&metadata.path)
+                    .filter(|(mtime, _)| *mtime == metadata.mtime)
+                    .map(|(_, text)| text.clone());
+                let text = match cached_text {
+                    Some(text) => text,
+                    None => {
+                        let Some(saved_context) = fs
+                            .load(&metadata.path)
+                            .await
+                            .and_then(|json| SavedContext::from_json(&json))
+                            .log_err()
+                        else {
+                            continue;
+                        };
+                        let text = Arc::<str>::from(saved_context.text);
+                        texts
+                            .lock()
+                            .insert(metadata.path.clone(), (metadata.mtime, text.clone()));
+                        text
+                    }
+                };
+
+                if let Some(excerpt) = find_excerpt(&text, &query) {
+                    matches.push(SavedContextMatch {
+                        metadata: metadata.clone(),
+                        excerpt: Some(excerpt),
+                    });
+                }
             }
+            matches
         })
     }
 
@@ -746,3 +841,58 @@ impl ContextStore {
         })
     }
 }
+
+/// Returns the line of `text` that contains the lowercase `query`, shortened around the match.
+fn find_excerpt(text: &str, query: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let line = line.trim();
+        let match_ix = line.to_lowercase().find(query)?;
+        // Lowercasing can change the length of some characters, so only use the match's
+        // position when it still falls on a character boundary.
+        let match_start = if line.is_char_boundary(match_ix) {
+            line[..match_ix].chars().count()
+        } else {
+            0
+        };
+        let char_count = line.chars().count();
+        if char_count <= MAX_EXCERPT_LEN {
+            return Some(line.to_string());
+        }
+
+        let start = match_start
+            .saturating_sub(MAX_EXCERPT_LEN / 4)
+            .min(char_count - MAX_EXCERPT_LEN);
+        let end = start + MAX_EXCERPT_LEN;
+        let mut excerpt = String::new();
+        if start > 0 {
+            excerpt.push('…');
+        }
+        excerpt.extend(line.chars().skip(start).take(MAX_EXCERPT_LEN));
+        if end < char_count {
+            excerpt.push('…');
+        }
+        Some(excerpt)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_excerpt() {
+        let text = "Explain this code\nThe `Fuzzy` matcher ranks candidates.\n";
+        assert_eq!(
+            find_excerpt(text, "fuzzy"),
+            Some("The `Fuzzy` matcher ranks candidates.".to_string())
+        );
+        assert_eq!(find_excerpt(text, "missing"), None);
+
+        let long_line = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let excerpt = find_excerpt(&long_line, "needle").unwrap();
+        assert_eq!(
+            excerpt,
+            format!("…{}needle{}…", "a".repeat(20), "b".repeat(54))
+        );
+    }
+}
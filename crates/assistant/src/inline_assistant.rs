@@ -1,5 +1,5 @@
 use crate::{
-    assistant_settings::AssistantSettings, humanize_token_count, prompts::PromptBuilder,
+    assistant_settings::AssistantSettings, humanize_token_count, prompts::PromptBuilder, usage,
     AssistantPanel, AssistantPanelEvent, CharOperation, CycleNextInlineAssist,
     CyclePreviousInlineAssist, LineDiff, LineOperation, ModelSelector, StreamingDiff,
 };
@@ -2657,8 +2657,9 @@ impl CodegenAlternative {
                 let request = self.build_request(user_prompt, assistant_panel_context, cx)?;
                 self.request = Some(request.clone());
 
-                let chunks = cx
-                    .spawn(|_, cx| async move { model.stream_completion_text(request, &cx).await });
+                let chunks = cx.spawn(|_, cx| async move {
+                    usage::stream_completion_text(model, request, &cx).await
+                });
                 async move { Ok(chunks.await?.boxed()) }.boxed_local()
             };
         self.handle_stream(telemetry_id, provider_id.to_string(), chunks, cx);
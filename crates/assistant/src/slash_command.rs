@@ -21,6 +21,7 @@ use workspace::Workspace;
 pub mod auto_command;
 pub mod cargo_workspace_command;
 pub mod context_server_command;
+pub mod context_server_resource_command;
 pub mod default_command;
 pub mod delta_command;
 pub mod diagnostics_command;
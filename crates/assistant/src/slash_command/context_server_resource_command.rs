@@ -0,0 +1,286 @@
+use anyhow::{anyhow, Context as _, Result};
+use assistant_slash_command::{
+    AfterCompletion, ArgumentCompletion, SlashCommand, SlashCommandOutput,
+    SlashCommandOutputSection, SlashCommandResult,
+};
+use context_servers::{
+    manager::{ContextServer, ContextServerManager},
+    protocol::ServerCapability,
+};
+use fuzzy::StringMatchCandidate;
+use gpui::{AppContext, Task, WeakView, WindowContext};
+use language::{BufferSnapshot, CodeLabel, LspAdapterDelegate};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use text::LineEnding;
+use ui::{IconName, SharedString};
+use util::ResultExt;
+use workspace::Workspace;
+
+/// Inserts a resource from one of the running context servers.
+pub struct ContextServerResourceSlashCommand;
+
+/// Identifies the resource shown in an output section, so that the section can be refreshed
+/// when the server reports that the resource changed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ResourceSectionMetadata {
+    server_id: String,
+    uri: String,
+}
+
+/// A resource or resource template offered as the command's argument.
+struct ResourceEntry {
+    label: String,
+    uri: String,
+    is_template: bool,
+}
+
+impl ContextServerResourceSlashCommand {
+    /// Returns whether the section was inserted for the given resource.
+    pub fn section_shows_resource(
+        section: &SlashCommandOutputSection<language::Anchor>,
+        server_id: &str,
+        uri: &str,
+    ) -> bool {
+        section
+            .metadata
+            .clone()
+            .and_then(|metadata| serde_json::from_value::<ResourceSectionMetadata>(metadata).ok())
+            .map_or(false, |metadata| {
+                metadata.server_id == server_id && metadata.uri == uri
+            })
+    }
+
+    /// Reads the resource again, for replacing a section that shows it.
+    pub fn refresh(server_id: &str, uri: &str, cx: &mut WindowContext) -> Task<SlashCommandResult> {
+        let manager = ContextServerManager::global(cx);
+        let manager = manager.read(cx);
+        if let Some(server) = manager.get_server(server_id) {
+            let uri = uri.to_string();
+            cx.foreground_executor()
+                .spawn(async move { read_resource(&server, &uri).await })
+        } else {
+            Task::ready(Err(anyhow!("Context server not found")))
+        }
+    }
+}
+
+impl SlashCommand for ContextServerResourceSlashCommand {
+    fn name(&self) -> String {
+        "resource".into()
+    }
+
+    fn description(&self) -> String {
+        "Insert a context server resource".into()
+    }
+
+    fn menu_text(&self) -> String {
+        self.description()
+    }
+
+    fn requires_argument(&self) -> bool {
+        true
+    }
+
+    fn complete_argument(
+        self: Arc<Self>,
+        arguments: &[String],
+        cancel: Arc<AtomicBool>,
+        _workspace: Option<WeakView<Workspace>>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<Vec<ArgumentCompletion>>> {
+        let query = arguments.last().cloned().unwrap_or_default();
+        let servers = resource_servers(cx);
+        let executor = cx.background_executor().clone();
+        cx.foreground_executor().spawn(async move {
+            let mut entries = Vec::new();
+            for server in servers {
+                entries.extend(resource_entries(&server).await);
+            }
+
+            let candidates = entries
+                .iter()
+                .enumerate()
+                .map(|(ix, entry)| StringMatchCandidate::new(ix, entry.label.clone()))
+                .collect::<Vec<_>>();
+            let matches =
+                fuzzy::match_strings(&candidates, &query, false, 100, &cancel, executor).await;
+
+            Ok(matches
+                .into_iter()
+                .map(|mat| {
+                    let entry = &entries[mat.candidate_id];
+                    ArgumentCompletion {
+                        label: CodeLabel::plain(entry.label.clone(), None),
+                        new_text: entry.uri.clone(),
+                        // Templates still need their variables filled in.
+                        after_completion: if entry.is_template {
+                            AfterCompletion::Compose
+                        } else {
+                            AfterCompletion::Run
+                        },
+                        replace_previous_arguments: true,
+                    }
+                })
+                .collect())
+        })
+    }
+
+    fn run(
+        self: Arc<Self>,
+        arguments: &[String],
+        _context_slash_command_output_sections: &[SlashCommandOutputSection<language::Anchor>],
+        _context_buffer: BufferSnapshot,
+        _workspace: WeakView<Workspace>,
+        _delegate: Option<Arc<dyn LspAdapterDelegate>>,
+        cx: &mut WindowContext,
+    ) -> Task<SlashCommandResult> {
+        let Some(uri) = arguments.first().cloned() else {
+            return Task::ready(Err(anyhow!("missing resource URI")));
+        };
+
+        let servers = resource_servers(cx);
+        cx.foreground_executor().spawn(async move {
+            let server = server_for_uri(servers, &uri)
+                .await
+                .with_context(|| format!("no context server provides {uri}"))?;
+            let output = read_resource(&server, &uri).await?;
+
+            let protocol = server.client.read().clone();
+            if let Some(protocol) = protocol {
+                if protocol.supports_resource_subscriptions() {
+                    protocol.subscribe_resource(&uri).await.log_err();
+                }
+            }
+
+            Ok(output)
+        })
+    }
+}
+
+fn resource_servers(cx: &AppContext) -> Vec<Arc<ContextServer>> {
+    ContextServerManager::global(cx)
+        .read(cx)
+        .servers()
+        .into_iter()
+        .filter(|server| {
+            server.client.read().as_ref().map_or(false, |protocol| {
+                protocol.capable(ServerCapability::Resources)
+            })
+        })
+        .collect()
+}
+
+async fn resource_entries(server: &ContextServer) -> Vec<ResourceEntry> {
+    let Some(protocol) = server.client.read().clone() else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    if let Some(response) = protocol.list_resources().await.log_err() {
+        entries.extend(
+            response
+                .resources
+                .into_iter()
+                .map(|resource| ResourceEntry {
+                    label: format!("{} ({})", resource.name, resource.uri),
+                    uri: resource.uri.to_string(),
+                    is_template: false,
+                }),
+        );
+    }
+    // Not every server lists resource templates, so failing to list them isn't an error.
+    if let Ok(response) = protocol.list_resource_templates().await {
+        entries.extend(
+            response
+                .resource_templates
+                .into_iter()
+                .map(|template| ResourceEntry {
+                    label: format!("{} ({})", template.name, template.uri_template),
+                    uri: template.uri_template,
+                    is_template: true,
+                }),
+        );
+    }
+    entries
+}
+
+async fn server_for_uri(servers: Vec<Arc<ContextServer>>, uri: &str) -> Option<Arc<ContextServer>> {
+    for server in servers {
+        let provides_uri = resource_entries(&server).await.iter().any(|entry| {
+            if entry.is_template {
+                template_matches(&entry.uri, uri)
+            } else {
+                entry.uri == uri
+            }
+        });
+        if provides_uri {
+            return Some(server);
+        }
+    }
+    None
+}
+
+/// Returns whether the URI could be an expansion of the URI template, judging by the part of
+/// the template that precedes its first variable.
+fn template_matches(template: &str, uri: &str) -> bool {
+    match template.find('{') {
+        Some(variable_start) => uri.starts_with(&template[..variable_start]),
+        None => template == uri,
+    }
+}
+
+async fn read_resource(server: &ContextServer, uri: &str) -> SlashCommandResult {
+    let Some(protocol) = server.client.read().clone() else {
+        return Err(anyhow!("Context server not initialized"));
+    };
+    let response = protocol.read_resource(uri).await?;
+
+    let mut text = response
+        .contents
+        .iter()
+        .filter_map(|content| content.text.as_deref())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.is_empty() && !response.contents.is_empty() {
+        return Err(anyhow!("{uri} has no text contents"));
+    }
+    // We must normalize the line endings here, since servers might return CR characters.
+    LineEnding::normalize(&mut text);
+
+    let metadata = serde_json::to_value(ResourceSectionMetadata {
+        server_id: server.id.clone(),
+        uri: uri.to_string(),
+    })?;
+    Ok(SlashCommandOutput {
+        sections: vec![SlashCommandOutputSection {
+            range: 0..text.len(),
+            icon: IconName::FileText,
+            label: SharedString::from(uri.to_string()),
+            metadata: Some(metadata),
+        }],
+        text,
+        run_commands_in_text: false,
+    }
+    .to_event_stream())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_matches() {
+        assert!(template_matches(
+            "file:///logs/{name}",
+            "file:///logs/app.log"
+        ));
+        assert!(!template_matches(
+            "file:///logs/{name}",
+            "file:///tmp/app.log"
+        ));
+        assert!(template_matches("db://tables", "db://tables"));
+        assert!(!template_matches("db://tables", "db://tables/users"));
+    }
+}
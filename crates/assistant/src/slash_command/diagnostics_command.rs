@@ -189,15 +189,15 @@ impl SlashCommand for DiagnosticsSlashCommand {
 }
 
 #[derive(Default)]
-struct Options {
+pub(crate) struct Options {
     include_warnings: bool,
     path_matcher: Option<PathMatcher>,
 }
 
-const INCLUDE_WARNINGS_ARGUMENT: &str = "--include-warnings";
+pub(crate) const INCLUDE_WARNINGS_ARGUMENT: &str = "--include-warnings";
 
 impl Options {
-    fn parse(arguments: &[String]) -> Self {
+    pub(crate) fn parse(arguments: &[String]) -> Self {
         let mut include_warnings = false;
         let mut path_matcher = None;
         for arg in arguments {
@@ -221,7 +221,7 @@ impl Options {
     }
 }
 
-fn collect_diagnostics(
+pub(crate) fn collect_diagnostics(
     project: Model<Project>,
     options: Options,
     cx: &mut AppContext,
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Duration;
@@ -10,15 +10,19 @@ use assistant_slash_command::{
 };
 use gpui::{AppContext, BackgroundExecutor, Model, Task, WeakView};
 use indexed_docs::{
-    DocsDotRsProvider, IndexedDocsRegistry, IndexedDocsStore, LocalRustdocProvider, PackageName,
-    ProviderId,
+    DocsDotRsProvider, GoDocsProvider, IndexedDocsProvider, IndexedDocsRegistry, IndexedDocsStore,
+    LocalHtmlDocsProvider, LocalRustdocProvider, PackageName, ProviderId, PythonDocsProvider,
+    TypeScriptDocsProvider,
 };
 use language::{BufferSnapshot, LspAdapterDelegate};
 use project::{Project, ProjectPath};
+use settings::Settings;
 use ui::prelude::*;
 use util::{maybe, ResultExt};
 use workspace::Workspace;
 
+use crate::slash_command_settings::SlashCommandSettings;
+
 pub(crate) struct DocsSlashCommand;
 
 impl DocsSlashCommand {
@@ -93,6 +97,81 @@ impl DocsSlashCommand {
         }
     }
 
+    /// Returns the absolute path of the first worktree root that contains any of the given files.
+    fn worktree_root_containing(
+        project: &Model<Project>,
+        file_names: &[&str],
+        cx: &AppContext,
+    ) -> Option<PathBuf> {
+        project.read(cx).worktrees(cx).find_map(|worktree| {
+            let worktree = worktree.read(cx);
+            file_names
+                .iter()
+                .any(|file_name| worktree.entry_for_path(file_name).is_some())
+                .then(|| worktree.abs_path().to_path_buf())
+        })
+    }
+
+    /// Ensures that the indexed doc providers for locally available docs are registered.
+    ///
+    /// The Python, Go, and TypeScript providers are registered when the project
+    /// looks like it uses them, and the HTML providers come from the settings.
+    fn ensure_local_doc_providers_are_registered(
+        &self,
+        workspace: Option<WeakView<Workspace>>,
+        cx: &mut AppContext,
+    ) {
+        let Some(project) = workspace
+            .and_then(|workspace| workspace.upgrade())
+            .map(|workspace| workspace.read(cx).project().clone())
+        else {
+            return;
+        };
+        let fs = project.read(cx).fs().clone();
+        let indexed_docs_registry = IndexedDocsRegistry::global(cx);
+
+        let mut providers: Vec<Box<dyn IndexedDocsProvider + Send + Sync + 'static>> = Vec::new();
+        if let Some(project_root) = Self::worktree_root_containing(
+            &project,
+            &[
+                ".venv",
+                "venv",
+                "pyproject.toml",
+                "setup.py",
+                "requirements.txt",
+            ],
+            cx,
+        ) {
+            providers.push(Box::new(PythonDocsProvider::new(fs.clone(), project_root)));
+        }
+        if let Some(go_module_root) = Self::worktree_root_containing(&project, &["go.mod"], cx) {
+            providers.push(Box::new(GoDocsProvider::new(fs.clone(), go_module_root)));
+        }
+        if let Some(project_root) = Self::worktree_root_containing(&project, &["package.json"], cx)
+        {
+            providers.push(Box::new(TypeScriptDocsProvider::new(
+                fs.clone(),
+                project_root,
+            )));
+        }
+        for (id, root) in &SlashCommandSettings::get_global(cx).docs.html_directories {
+            providers.push(Box::new(LocalHtmlDocsProvider::new(
+                ProviderId(id.as_str().into()),
+                fs.clone(),
+                root.clone(),
+            )));
+        }
+
+        for provider in providers {
+            if indexed_docs_registry
+                .get_provider_store(provider.id())
+                .is_none()
+            {
+                indexed_docs_registry.register_provider(provider);
+            }
+        }
+    }
+
     /// Runs just-in-time indexing for a given package, in case the slash command
     /// is run without any entries existing in the index.
     fn run_just_in_time_indexing(
@@ -167,7 +246,8 @@ impl SlashCommand for DocsSlashCommand {
         workspace: Option<WeakView<Workspace>>,
         cx: &mut WindowContext,
     ) -> Task<Result<Vec<ArgumentCompletion>>> {
-        self.ensure_rust_doc_providers_are_registered(workspace, cx);
+        self.ensure_rust_doc_providers_are_registered(workspace.clone(), cx);
+        self.ensure_local_doc_providers_are_registered(workspace, cx);
 
         let indexed_docs_registry = IndexedDocsRegistry::global(cx);
         let args = DocsSlashCommandArgs::parse(arguments);
@@ -362,7 +442,12 @@ impl SlashCommand for DocsSlashCommand {
     }
 }
 
-fn is_item_path_delimiter(char: char) -> bool {
+fn is_item_path_delimiter(provider: &ProviderId, char: char) -> bool {
+    // Go and npm package names are paths, so their items are separated with a `#`.
+    if provider == &GoDocsProvider::id() || provider == &TypeScriptDocsProvider::id() {
+        return char == '#';
+    }
+
     !char.is_alphanumeric() && char != '-' && char != '_'
 }
 
@@ -395,7 +480,9 @@ impl DocsSlashCommandArgs {
             return Self::NoProvider;
         };
 
-        if let Some((package, rest)) = argument.split_once(is_item_path_delimiter) {
+        if let Some((package, rest)) =
+            argument.split_once(|char| is_item_path_delimiter(&provider, char))
+        {
             if rest.trim().is_empty() {
                 Self::SearchPackageDocs {
                     provider,
@@ -443,6 +530,10 @@ fn package_term(provider: &ProviderId) -> &'static str {
         return "crate";
     }
 
+    if provider == &GoDocsProvider::id() {
+        return "module";
+    }
+
     "package"
 }
 
@@ -535,5 +626,34 @@ mod tests {
                 item_path: "gleam_stdlib/gleam/int".into()
             }
         );
+
+        // Go and npm package names are paths, so only a `#` starts an item path.
+        assert_eq!(
+            DocsSlashCommandArgs::parse(&["go".to_string(), "golang.org/x/text".to_string()]),
+            DocsSlashCommandArgs::SearchPackageDocs {
+                provider: ProviderId("go".into()),
+                package: "golang.org/x/text".into(),
+                index: false
+            }
+        );
+        assert_eq!(
+            DocsSlashCommandArgs::parse(&["typescript".to_string(), "@babel/core#".to_string()]),
+            DocsSlashCommandArgs::SearchPackageDocs {
+                provider: ProviderId("typescript".into()),
+                package: "@babel/core".into(),
+                index: true
+            }
+        );
+        assert_eq!(
+            DocsSlashCommandArgs::parse(&[
+                "go".to_string(),
+                "golang.org/x/text#encoding".to_string()
+            ]),
+            DocsSlashCommandArgs::SearchItemDocs {
+                provider: ProviderId("go".into()),
+                package: "golang.org/x/text".into(),
+                item_path: "golang.org/x/text#encoding".into()
+            }
+        );
     }
 }
@@ -1,17 +1,18 @@
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use assistant_slash_command::{
     ArgumentCompletion, SlashCommand, SlashCommandOutput, SlashCommandOutputSection,
     SlashCommandResult,
 };
 use feature_flags::FeatureFlag;
 use gpui::{AppContext, Task, WeakView};
-use language::{CodeLabel, LspAdapterDelegate};
-use semantic_index::{LoadedSearchResult, SemanticDb};
+use language::{CodeLabel, LanguageName, LspAdapterDelegate};
+use semantic_index::{LoadedSearchResult, SearchMode, SearchOptions, SemanticDb};
 use std::{
     fmt::Write,
     sync::{atomic::AtomicBool, Arc},
 };
 use ui::{prelude::*, IconName};
+use util::paths::PathMatcher;
 use workspace::Workspace;
 
 use crate::slash_command::create_label_for_command;
@@ -31,7 +32,17 @@ impl SlashCommand for SearchSlashCommand {
     }
 
     fn label(&self, cx: &AppContext) -> CodeLabel {
-        create_label_for_command("search", &["--n"], cx)
+        create_label_for_command(
+            "search",
+            &[
+                "--n",
+                "--keyword",
+                "--semantic",
+                "--path=<glob>",
+                "--lang=<language>",
+            ],
+            cx,
+        )
     }
 
     fn description(&self) -> String {
@@ -72,24 +83,14 @@ impl SlashCommand for SearchSlashCommand {
             return Task::ready(Err(anyhow::anyhow!("missing search query")));
         };
 
-        let mut limit = None;
-        let mut query = String::new();
-        for part in arguments {
-            if let Some(parameter) = part.strip_prefix("--") {
-                if let Ok(count) = parameter.parse::<usize>() {
-                    limit = Some(count);
-                    continue;
-                }
-            }
-
-            query.push_str(part);
-            query.push(' ');
-        }
-        query.pop();
-
-        if query.is_empty() {
-            return Task::ready(Err(anyhow::anyhow!("missing search query")));
-        }
+        let SearchArguments {
+            query,
+            limit,
+            options,
+        } = match parse_search_arguments(arguments) {
+            Ok(arguments) => arguments,
+            Err(error) => return Task::ready(Err(error)),
+        };
 
         let project = workspace.read(cx).project().clone();
         let fs = project.read(cx).fs().clone();
@@ -102,7 +103,7 @@ impl SlashCommand for SearchSlashCommand {
         cx.spawn(|cx| async move {
             let results = project_index
                 .read_with(&cx, |project_index, cx| {
-                    project_index.search(vec![query.clone()], limit.unwrap_or(5), cx)
+                    project_index.search_with_options(vec![query.clone()], limit, options, cx)
                 })?
                 .await?;
 
@@ -139,6 +140,74 @@ impl SlashCommand for SearchSlashCommand {
     }
 }
 
+struct SearchArguments {
+    query: String,
+    limit: usize,
+    options: SearchOptions,
+}
+
+/// Parses the arguments of `/search`, which are the query interspersed with flags:
+///
+/// - `--<n>` limits the number of results.
+/// - `--keyword` and `--semantic` rank results by keyword or by meaning only,
+///   instead of by both.
+/// - `--path=<glob>` only searches the matching paths, and can be repeated.
+/// - `--lang=<language>` only searches files in the given language, and can be repeated.
+fn parse_search_arguments(arguments: &[String]) -> Result<SearchArguments> {
+    let mut limit = None;
+    let mut mode = SearchMode::Hybrid;
+    let mut path_globs = Vec::new();
+    let mut languages = Vec::new();
+    let mut query = String::new();
+    for part in arguments {
+        if let Some(parameter) = part.strip_prefix("--") {
+            if let Ok(count) = parameter.parse::<usize>() {
+                limit = Some(count);
+                continue;
+            }
+
+            match parameter.split_once('=') {
+                None if parameter == "keyword" => {
+                    mode = SearchMode::Keyword;
+                    continue;
+                }
+                None if parameter == "semantic" => {
+                    mode = SearchMode::Semantic;
+                    continue;
+                }
+                Some(("path", glob)) if !glob.is_empty() => {
+                    path_globs.push(glob.to_string());
+                    continue;
+                }
+                Some(("lang", language)) if !language.is_empty() => {
+                    languages.push(LanguageName::new(language));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        query.push_str(part);
+        query.push(' ');
+    }
+    query.pop();
+
+    if query.is_empty() {
+        return Err(anyhow::anyhow!("missing search query"));
+    }
+
+    Ok(SearchArguments {
+        query,
+        limit: limit.unwrap_or(5),
+        options: SearchOptions {
+            mode,
+            files_to_include: PathMatcher::new(&path_globs).context("invalid path glob")?,
+            files_to_exclude: PathMatcher::default(),
+            languages,
+        },
+    })
+}
+
 pub fn add_search_result_section(
     loaded_result: &LoadedSearchResult,
     text: &mut String,
@@ -170,3 +239,50 @@ pub fn add_search_result_section(
         Some(row_range.start() + 1..row_range.end() + 1),
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(arguments: &[&str]) -> Vec<String> {
+        arguments
+            .iter()
+            .map(|argument| argument.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_search_arguments() {
+        let parsed = parse_search_arguments(&arguments(&["open", "a", "--3", "file"])).unwrap();
+        assert_eq!(parsed.query, "open a file");
+        assert_eq!(parsed.limit, 3);
+        assert_eq!(parsed.options.mode, SearchMode::Hybrid);
+        assert!(parsed.options.files_to_include.sources().is_empty());
+        assert!(parsed.options.languages.is_empty());
+
+        let parsed = parse_search_arguments(&arguments(&[
+            "--keyword",
+            "--path=crates/editor/**",
+            "--lang=Rust",
+            "--path=*.md",
+            "ProjectIndex",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.query, "ProjectIndex");
+        assert_eq!(parsed.limit, 5);
+        assert_eq!(parsed.options.mode, SearchMode::Keyword);
+        assert_eq!(
+            parsed.options.files_to_include.sources(),
+            &["crates/editor/**".to_string(), "*.md".to_string()]
+        );
+        assert_eq!(parsed.options.languages, vec![LanguageName::new("Rust")]);
+
+        // Unknown flags are part of the query.
+        let parsed = parse_search_arguments(&arguments(&["--semantic", "--verbose"])).unwrap();
+        assert_eq!(parsed.query, "--verbose");
+        assert_eq!(parsed.options.mode, SearchMode::Semantic);
+
+        assert!(parse_search_arguments(&arguments(&["--keyword", "--10"])).is_err());
+        assert!(parse_search_arguments(&arguments(&["--path=[", "query"])).is_err());
+    }
+}
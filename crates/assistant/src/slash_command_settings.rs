@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
 use anyhow::Result;
 use gpui::AppContext;
 use schemars::JsonSchema;
@@ -21,6 +24,12 @@ pub struct DocsCommandSettings {
     /// Whether `/docs` is enabled.
     #[serde(default)]
     pub enabled: bool,
+    /// Directories of HTML documentation to make available in `/docs`, keyed by
+    /// the name of their docs provider.
+    ///
+    /// Each directory within them is indexed as a package.
+    #[serde(default)]
+    pub html_directories: BTreeMap<String, PathBuf>,
 }
 
 /// Settings for the `/cargo-workspace` slash command.
@@ -1 +1,8 @@
+pub mod context_server_tool;
+pub mod diagnostics_tool;
+pub mod edit_file_tool;
+pub mod list_directory_tool;
 pub mod now_tool;
+pub mod project_search_tool;
+pub mod read_file_tool;
+pub mod run_task_tool;
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use assistant_tool::Tool;
+use context_servers::{
+    manager::{ContextServer, ContextServerManager},
+    types,
+};
+use gpui::{Task, WeakView, WindowContext};
+use text::LineEnding;
+use workspace::Workspace;
+
+pub struct ContextServerTool {
+    name: String,
+    server_id: String,
+    tool: types::Tool,
+}
+
+impl ContextServerTool {
+    pub fn new(server: &Arc<ContextServer>, tool: types::Tool) -> Self {
+        Self {
+            name: qualified_tool_name(&server.id, &tool.name),
+            server_id: server.id.clone(),
+            tool,
+        }
+    }
+}
+
+/// Returns the name a context server's tool is registered under. It's prefixed with the
+/// server's ID, so that tools with the same name from different servers, or from the
+/// assistant itself, don't replace each other.
+///
+/// Model providers only accept letters, digits, `_` and `-` in tool names, so any other
+/// characters are replaced with `_`.
+pub fn qualified_tool_name(server_id: &str, tool_name: &str) -> String {
+    format!("{server_id}__{tool_name}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl Tool for ContextServerTool {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn description(&self) -> String {
+        match &self.tool.description {
+            Some(description) => description.clone(),
+            None => format!("Run '{}' from {}", self.tool.name, self.server_id),
+        }
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        self.tool.input_schema.clone()
+    }
+
+    fn needs_confirmation(&self) -> bool {
+        // Only tools the server marks as read-only are run without asking.
+        !self
+            .tool
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.read_only_hint)
+            .unwrap_or(false)
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        _workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let arguments = match input {
+            serde_json::Value::Object(arguments) => Some(arguments.into_iter().collect()),
+            serde_json::Value::Null => None,
+            _ => return Task::ready(Err(anyhow!("Tool input must be a JSON object"))),
+        };
+
+        let manager = ContextServerManager::global(cx);
+        let manager = manager.read(cx);
+        if let Some(server) = manager.get_server(&self.server_id) {
+            cx.foreground_executor().spawn(async move {
+                let Some(protocol) = server.client.read().clone() else {
+                    return Err(anyhow!("Context server not initialized"));
+                };
+                let response = protocol.run_tool(&self.tool.name, arguments).await?;
+                tool_output(response)
+            })
+        } else {
+            Task::ready(Err(anyhow!("Context server not found")))
+        }
+    }
+}
+
+/// Joins the text parts of a tool's response into the output shown to the model.
+fn tool_output(response: types::CallToolResponse) -> Result<String> {
+    let mut output = response
+        .content
+        .into_iter()
+        .filter_map(|content| match content {
+            types::ToolResponseContent::Text { text } => Some(text),
+            types::ToolResponseContent::Resource { resource } => resource.text,
+            types::ToolResponseContent::Image { .. } => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    // We must normalize the line endings here, since servers might return CR characters.
+    LineEnding::normalize(&mut output);
+
+    if response.is_error == Some(true) {
+        Err(anyhow!(output))
+    } else {
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qualified_tool_name() {
+        assert_eq!(
+            qualified_tool_name("github", "read_file"),
+            "github__read_file"
+        );
+        assert_eq!(
+            qualified_tool_name("my.server", "files/read"),
+            "my_server__files_read"
+        );
+    }
+
+    #[test]
+    fn test_tool_output() {
+        let response: types::CallToolResponse = serde_json::from_value(serde_json::json!({
+            "content": [
+                { "type": "text", "text": "first\r\nline" },
+                { "type": "image", "data": "AAAA", "mimeType": "image/png" },
+                {
+                    "type": "resource",
+                    "resource": { "uri": "file:///notes.txt", "text": "second" },
+                },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(tool_output(response).unwrap(), "first\nline\n\nsecond");
+
+        let response: types::CallToolResponse = serde_json::from_value(serde_json::json!({
+            "content": [{ "type": "text", "text": "no such file" }],
+            "isError": true,
+        }))
+        .unwrap();
+        assert_eq!(
+            tool_output(response).unwrap_err().to_string(),
+            "no such file"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use assistant_tool::Tool;
+use gpui::{Task, WeakView, WindowContext};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+use crate::slash_command::diagnostics_command::{
+    collect_diagnostics, Options, INCLUDE_WARNINGS_ARGUMENT,
+};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DiagnosticsToolInput {
+    /// A glob matching the paths to report diagnostics for. All paths are included when omitted.
+    pub path: Option<String>,
+    /// Whether to report warnings in addition to errors.
+    #[serde(default)]
+    pub include_warnings: bool,
+}
+
+pub struct DiagnosticsTool;
+
+impl Tool for DiagnosticsTool {
+    fn name(&self) -> String {
+        "diagnostics".into()
+    }
+
+    fn description(&self) -> String {
+        "Returns the errors, and optionally the warnings, reported by language servers \
+        for the files of the project."
+            .into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(DiagnosticsToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let input: DiagnosticsToolInput = match serde_json::from_value(input) {
+            Ok(input) => input,
+            Err(err) => return Task::ready(Err(anyhow!(err))),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+
+        let mut arguments = Vec::new();
+        if input.include_warnings {
+            arguments.push(INCLUDE_WARNINGS_ARGUMENT.to_string());
+        }
+        arguments.extend(input.path);
+
+        let project = workspace.read(cx).project().clone();
+        let diagnostics = collect_diagnostics(project, Options::parse(&arguments), cx);
+        cx.background_executor().spawn(async move {
+            match diagnostics.await? {
+                Some(output) => Ok(output.text),
+                None => Ok("No diagnostics found.".into()),
+            }
+        })
+    }
+}
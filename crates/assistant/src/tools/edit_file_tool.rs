@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use assistant_tool::Tool;
+use gpui::{Task, WeakView, WindowContext};
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+use crate::AssistantEdit;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EditOperation {
+    /// Replaces `old_text` with `new_text`.
+    Update,
+    /// Creates a new file containing `new_text`.
+    Create,
+    /// Inserts `new_text` before `old_text`.
+    InsertBefore,
+    /// Inserts `new_text` after `old_text`.
+    InsertAfter,
+    /// Deletes `old_text`.
+    Delete,
+}
+
+impl EditOperation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            EditOperation::Update => "update",
+            EditOperation::Create => "create",
+            EditOperation::InsertBefore => "insert_before",
+            EditOperation::InsertAfter => "insert_after",
+            EditOperation::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct EditFileToolInput {
+    /// The path of the file to edit, starting with the name of the worktree it's in.
+    pub path: String,
+    /// The kind of edit to make.
+    pub operation: EditOperation,
+    /// The existing text the edit applies to. It must match the file exactly, and include
+    /// enough surrounding lines to be unique within it. Not used when creating a file.
+    pub old_text: Option<String>,
+    /// The text to insert. Not used when deleting text.
+    pub new_text: Option<String>,
+    /// A short description of the edit. Not used when deleting text.
+    pub description: Option<String>,
+}
+
+impl EditFileToolInput {
+    fn into_edit(self) -> Result<AssistantEdit> {
+        AssistantEdit::new(
+            Some(self.path),
+            Some(self.operation.as_str().to_string()),
+            self.old_text,
+            self.new_text,
+            self.description,
+        )
+    }
+}
+
+/// Records edits to the project's files. The edits aren't applied right away: they're shown
+/// to the user for review once the agent is done.
+pub(crate) struct EditFileTool {
+    edits: Arc<Mutex<Vec<AssistantEdit>>>,
+}
+
+impl EditFileTool {
+    pub fn new(edits: Arc<Mutex<Vec<AssistantEdit>>>) -> Self {
+        Self { edits }
+    }
+}
+
+impl Tool for EditFileTool {
+    fn name(&self) -> String {
+        "edit_file".into()
+    }
+
+    fn description(&self) -> String {
+        "Edits a file in the project, or creates a new one. \
+        All edits are reviewed by the user once you are done."
+            .into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(EditFileToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        _workspace: WeakView<Workspace>,
+        _cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let edit = serde_json::from_value::<EditFileToolInput>(input)
+            .map_err(|err| anyhow!(err))
+            .and_then(EditFileToolInput::into_edit);
+        let edit = match edit {
+            Ok(edit) => edit,
+            Err(err) => return Task::ready(Err(err)),
+        };
+
+        let text = format!(
+            "Recorded the edit to {}. It will be shown to the user for review.",
+            edit.path
+        );
+        self.edits.lock().push(edit);
+        Task::ready(Ok(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AssistantEditKind;
+
+    #[test]
+    fn test_edit_file_tool_input() {
+        let input: EditFileToolInput = serde_json::from_value(serde_json::json!({
+            "path": "zed/src/main.rs",
+            "operation": "insert_after",
+            "old_text": "fn main() {",
+            "new_text": "    println!(\"hello\");",
+            "description": "Say hello",
+        }))
+        .unwrap();
+        assert_eq!(
+            input.into_edit().unwrap(),
+            AssistantEdit {
+                path: "zed/src/main.rs".into(),
+                kind: AssistantEditKind::InsertAfter {
+                    old_text: "fn main() {".into(),
+                    new_text: "    println!(\"hello\");".into(),
+                    description: "Say hello".into(),
+                },
+            }
+        );
+
+        let input: EditFileToolInput = serde_json::from_value(serde_json::json!({
+            "path": "zed/src/main.rs",
+            "operation": "update",
+            "new_text": "fn main() {}",
+        }))
+        .unwrap();
+        assert_eq!(
+            input.into_edit().unwrap_err().to_string(),
+            "missing old_text"
+        );
+    }
+}
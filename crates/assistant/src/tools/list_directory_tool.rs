@@ -0,0 +1,88 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use assistant_tool::Tool;
+use gpui::{Task, WeakView, WindowContext};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ListDirectoryToolInput {
+    /// The path of the directory to list, starting with the name of the worktree it's in.
+    /// Use "." to list the worktrees of the project.
+    pub path: String,
+}
+
+pub struct ListDirectoryTool;
+
+impl Tool for ListDirectoryTool {
+    fn name(&self) -> String {
+        "list_directory".into()
+    }
+
+    fn description(&self) -> String {
+        "Lists the files and directories in a directory of the project. \
+        Directories are listed with a trailing slash."
+            .into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(ListDirectoryToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let input: ListDirectoryToolInput = match serde_json::from_value(input) {
+            Ok(input) => input,
+            Err(err) => return Task::ready(Err(anyhow!(err))),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+
+        let project = workspace.read(cx).project().read(cx);
+        if input.path == "." {
+            let text = project
+                .visible_worktrees(cx)
+                .map(|worktree| format!("{}/", worktree.read(cx).root_name()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Task::ready(Ok(text));
+        }
+
+        let Some(project_path) = project.find_project_path(Path::new(&input.path), cx) else {
+            return Task::ready(Err(anyhow!("no such directory: {}", input.path)));
+        };
+        let Some(worktree) = project.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no such directory: {}", input.path)));
+        };
+        let worktree = worktree.read(cx);
+        match worktree.entry_for_path(&project_path.path) {
+            Some(entry) if entry.is_dir() => {}
+            Some(_) => return Task::ready(Err(anyhow!("{} is not a directory", input.path))),
+            None => return Task::ready(Err(anyhow!("no such directory: {}", input.path))),
+        }
+
+        let root_name = Path::new(worktree.root_name());
+        let text = worktree
+            .child_entries(&project_path.path)
+            .map(|entry| {
+                let path = root_name.join(&entry.path);
+                if entry.is_dir() {
+                    format!("{}/", path.display())
+                } else {
+                    path.display().to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Task::ready(Ok(text))
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use assistant_tool::Tool;
+use gpui::{Task, WeakView, WindowContext};
+use project::search::{SearchQuery, SearchResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use text::{Point, ToPoint as _};
+use util::paths::PathMatcher;
+use workspace::Workspace;
+
+/// The number of matching lines reported to the model, at most.
+const MAX_MATCHES: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectSearchToolInput {
+    /// The text to search for.
+    pub query: String,
+    /// Whether the query is a regular expression.
+    #[serde(default)]
+    pub regex: bool,
+    /// Whether the search is case sensitive.
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+pub struct ProjectSearchTool;
+
+impl Tool for ProjectSearchTool {
+    fn name(&self) -> String {
+        "search".into()
+    }
+
+    fn description(&self) -> String {
+        "Searches the files of the project for a text or a regular expression, \
+        returning each matching line prefixed with its path and line number."
+            .into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(ProjectSearchToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let input: ProjectSearchToolInput = match serde_json::from_value(input) {
+            Ok(input) => input,
+            Err(err) => return Task::ready(Err(anyhow!(err))),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+
+        let query = if input.regex {
+            SearchQuery::regex(
+                &input.query,
+                false,
+                input.case_sensitive,
+                false,
+                PathMatcher::default(),
+                PathMatcher::default(),
+                None,
+            )
+        } else {
+            SearchQuery::text(
+                &input.query,
+                false,
+                input.case_sensitive,
+                false,
+                PathMatcher::default(),
+                PathMatcher::default(),
+                None,
+            )
+        };
+        let query = match query {
+            Ok(query) => query,
+            Err(err) => return Task::ready(Err(err)),
+        };
+
+        let project = workspace.read(cx).project().clone();
+        let results = project.update(cx, |project, cx| project.search(query, cx));
+        cx.spawn(|cx| async move {
+            let mut output = String::new();
+            let mut match_count = 0;
+            'results: while let Ok(result) = results.recv().await {
+                let SearchResult::Buffer { buffer, ranges } = result else {
+                    break;
+                };
+                let lines = buffer.read_with(&cx, |buffer, cx| {
+                    let path = buffer
+                        .file()
+                        .map(|file| file.full_path(cx).display().to_string())
+                        .unwrap_or_else(|| "untitled".into());
+                    let mut last_row = None;
+                    let mut lines = Vec::new();
+                    for range in ranges {
+                        let row = range.start.to_point(buffer).row;
+                        if last_row == Some(row) {
+                            continue;
+                        }
+                        last_row = Some(row);
+                        let line_end = Point::new(row, buffer.line_len(row));
+                        let line = buffer
+                            .text_for_range(Point::new(row, 0)..line_end)
+                            .collect::<String>();
+                        lines.push(format!("{path}:{}: {}", row + 1, line.trim()));
+                    }
+                    lines
+                })?;
+
+                for line in lines {
+                    if match_count == MAX_MATCHES {
+                        writeln!(output, "(more matches omitted)")?;
+                        break 'results;
+                    }
+                    writeln!(output, "{line}")?;
+                    match_count += 1;
+                }
+            }
+
+            if output.is_empty() {
+                Ok("No matches found.".into())
+            } else {
+                Ok(output)
+            }
+        })
+    }
+}
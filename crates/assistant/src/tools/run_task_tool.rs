@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _, Result};
+use assistant_tool::Tool;
+use editor::Editor;
+use gpui::{Task, View, WeakView, WindowContext};
+use language::Language;
+use project::WorktreeId;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use terminal::TaskStatus;
+use terminal_view::terminal_panel::TerminalPanel;
+use workspace::Workspace;
+
+/// The number of trailing output lines reported back to the model.
+const OUTPUT_LINES: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RunTaskToolInput {
+    /// The label of the task to run. When omitted, the labels of the available tasks are
+    /// returned instead.
+    pub label: Option<String>,
+}
+
+pub struct RunTaskTool;
+
+impl Tool for RunTaskTool {
+    fn name(&self) -> String {
+        "run_task".into()
+    }
+
+    fn description(&self) -> String {
+        "Runs one of the project's tasks in a terminal and returns whether it succeeded, \
+        along with the end of its output. Call it without a label to list the tasks."
+            .into()
+    }
+
+    fn input_schema(&self) -> serde_json::Value {
+        let schema = schemars::schema_for!(RunTaskToolInput);
+        serde_json::to_value(&schema).unwrap()
+    }
+
+    fn needs_confirmation(&self) -> bool {
+        true
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        workspace: WeakView<Workspace>,
+        cx: &mut WindowContext,
+    ) -> Task<Result<String>> {
+        let input: RunTaskToolInput = match serde_json::from_value(input) {
+            Ok(input) => input,
+            Err(err) => return Task::ready(Err(anyhow!(err))),
+        };
+        let Some(workspace) = workspace.upgrade() else {
+            return Task::ready(Err(anyhow!("workspace was dropped")));
+        };
+
+        let (worktree_id, file, language) = active_location(&workspace, cx);
+        let project = workspace.read(cx).project().read(cx);
+        let Some(inventory) = project.task_store().read(cx).task_inventory().cloned() else {
+            return Task::ready(Err(anyhow!("tasks are not available in this project")));
+        };
+        let worktree = worktree_id
+            .and_then(|worktree_id| project.worktree_for_id(worktree_id, cx))
+            .or_else(|| project.visible_worktrees(cx).next());
+        let worktree_id = worktree.as_ref().map(|worktree| worktree.read(cx).id());
+        let worktree_abs_path = worktree.map(|worktree| worktree.read(cx).abs_path().to_path_buf());
+        let tasks = inventory
+            .read(cx)
+            .list_tasks(file, language, worktree_id, cx);
+
+        let Some(label) = input.label else {
+            let labels = tasks
+                .iter()
+                .map(|(_, template)| template.label.as_str())
+                .collect::<Vec<_>>();
+            if labels.is_empty() {
+                return Task::ready(Ok("The project has no tasks.".into()));
+            }
+            return Task::ready(Ok(labels.join("\n")));
+        };
+        let Some((task_source_kind, template)) = tasks
+            .into_iter()
+            .find(|(_, template)| template.label == label)
+        else {
+            return Task::ready(Err(anyhow!("no task labeled {label:?}")));
+        };
+
+        // Resolve the task like the tasks modal does, so that variables describing the active
+        // editor's location, such as `$ZED_FILE` or `$ZED_SYMBOL`, are available.
+        let task_context = workspace.update(cx, |workspace, cx| {
+            editor::tasks::task_context(workspace, cx)
+        });
+        cx.spawn(|mut cx| async move {
+            let mut task_context = task_context.await;
+            if task_context.cwd.is_none() {
+                task_context.cwd = worktree_abs_path;
+            }
+            let spawn_in_terminal = template
+                .resolve_task(&task_source_kind.to_id_base(), &task_context)
+                .and_then(|resolved_task| resolved_task.resolved)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "the task {label:?} uses variables that aren't available for the active \
+                        editor location, such as the current file or symbol"
+                    )
+                })?;
+
+            let terminal = cx.update(|cx| {
+                let terminal_panel = workspace
+                    .read(cx)
+                    .panel::<TerminalPanel>(cx)
+                    .ok_or_else(|| anyhow!("no terminal panel"))?;
+                anyhow::Ok(terminal_panel.update(cx, |terminal_panel, cx| {
+                    terminal_panel.spawn_in_new_terminal(spawn_in_terminal, cx)
+                }))
+            })??;
+            let terminal = terminal.await.context("failed to spawn the task")?;
+            terminal
+                .update(&mut cx, |terminal, cx| terminal.wait_for_completed_task(cx))?
+                .await;
+
+            terminal.read_with(&cx, |terminal, _| {
+                let status = match terminal.task().map(|task| task.status) {
+                    Some(TaskStatus::Completed { success: true }) => "succeeded",
+                    Some(TaskStatus::Completed { success: false }) => "failed",
+                    _ => "stopped without reporting an exit code",
+                };
+                let output = terminal.last_n_non_empty_lines(OUTPUT_LINES).join("\n");
+                format!("The task {label:?} {status}. Its output ended with:\n\n{output}")
+            })
+        })
+    }
+}
+
+/// Returns the worktree, file and language of the active editor's cursor, which determine
+/// the tasks that are available.
+fn active_location(
+    workspace: &View<Workspace>,
+    cx: &WindowContext,
+) -> (
+    Option<WorktreeId>,
+    Option<Arc<dyn language::File>>,
+    Option<Arc<Language>>,
+) {
+    let Some(active_item) = workspace.read(cx).active_item(cx) else {
+        return (None, None, None);
+    };
+    let worktree_id = active_item
+        .project_path(cx)
+        .map(|project_path| project_path.worktree_id);
+    let Some(editor) = active_item.act_as::<Editor>(cx) else {
+        return (worktree_id, None, None);
+    };
+    let editor = editor.read(cx);
+    let head = editor.selections.newest_anchor().head();
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let Some((buffer, offset)) = snapshot.point_to_buffer_offset(head) else {
+        return (worktree_id, None, None);
+    };
+    (
+        worktree_id,
+        buffer.file().cloned(),
+        buffer.language_at(offset).cloned(),
+    )
+}
@@ -0,0 +1,741 @@
+use crate::assistant_settings::AssistantSettings;
+use anyhow::Result;
+use chrono::{Days, Local, NaiveDate};
+use db::{define_connection, query, sqlez_macros::sql};
+use futures::{
+    channel::oneshot,
+    future::BoxFuture,
+    stream::{self, BoxStream},
+    FutureExt, StreamExt,
+};
+use gpui::{AppContext, AsyncAppContext, EventEmitter, FocusHandle, FocusableView, Subscription};
+use language_model::{
+    LanguageModel, LanguageModelCompletionEvent, LanguageModelRequest, LanguageModelRequestMessage,
+    Role, TokenUsage,
+};
+use settings::{Settings, SettingsStore};
+use std::sync::Arc;
+use ui::prelude::*;
+use util::ResultExt;
+use workspace::item::Item;
+
+/// The number of days of usage shown in the usage view.
+const USAGE_DAYS: u64 = 30;
+
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+define_connection!(pub static ref USAGE_DB: UsageDb<()> =
+    &[sql!(
+        CREATE TABLE assistant_usage(
+            day TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            requests INTEGER NOT NULL,
+            estimated_requests INTEGER NOT NULL,
+            input_tokens INTEGER NOT NULL,
+            output_tokens INTEGER NOT NULL,
+            cache_creation_input_tokens INTEGER NOT NULL,
+            cache_read_input_tokens INTEGER NOT NULL,
+            PRIMARY KEY(day, provider, model)
+        ) STRICT;
+    )];
+);
+
+/// The tokens used by the requests to a model on one day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsageRecord {
+    pub day: NaiveDate,
+    pub provider: String,
+    pub model: String,
+    pub requests: u32,
+    /// The number of requests whose usage wasn't reported by the provider, and was estimated
+    /// by counting tokens instead.
+    pub estimated_requests: u32,
+    pub usage: TokenUsage,
+}
+
+impl UsageRecord {
+    /// Returns the cost of the usage in dollars, if the model's price is configured.
+    pub fn cost(&self, settings: &AssistantSettings) -> Option<f64> {
+        let price = settings.model_price(&self.provider, &self.model)?;
+        Some(price.cost(&self.usage))
+    }
+}
+
+impl UsageDb {
+    query! {
+        async fn add_usage(
+            day: String,
+            provider: String,
+            model: String,
+            estimated: u32,
+            input_tokens: u32,
+            output_tokens: u32,
+            cache_creation_input_tokens: u32,
+            cache_read_input_tokens: u32
+        ) -> Result<()> {
+            INSERT INTO assistant_usage(
+                day,
+                provider,
+                model,
+                requests,
+                estimated_requests,
+                input_tokens,
+                output_tokens,
+                cache_creation_input_tokens,
+                cache_read_input_tokens
+            )
+            VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(day, provider, model) DO UPDATE SET
+                requests = requests + 1,
+                estimated_requests = estimated_requests + ?4,
+                input_tokens = input_tokens + ?5,
+                output_tokens = output_tokens + ?6,
+                cache_creation_input_tokens = cache_creation_input_tokens + ?7,
+                cache_read_input_tokens = cache_read_input_tokens + ?8
+        }
+    }
+
+    query! {
+        fn usage_rows(since: String) -> Result<Vec<(String, String, String, u32, u32, u32, u32, u32, u32)>> {
+            SELECT
+                day,
+                provider,
+                model,
+                requests,
+                estimated_requests,
+                input_tokens,
+                output_tokens,
+                cache_creation_input_tokens,
+                cache_read_input_tokens
+            FROM assistant_usage
+            WHERE day >= ?
+            ORDER BY day DESC, provider, model
+        }
+    }
+
+    /// Adds the usage of a request to the totals of its model on the given day.
+    pub async fn record_usage(
+        &self,
+        day: NaiveDate,
+        provider: String,
+        model: String,
+        usage: TokenUsage,
+        estimated: bool,
+    ) -> Result<()> {
+        self.add_usage(
+            day.format(DAY_FORMAT).to_string(),
+            provider,
+            model,
+            estimated as u32,
+            usage.input_tokens,
+            usage.output_tokens,
+            usage.cache_creation_input_tokens,
+            usage.cache_read_input_tokens,
+        )
+        .await
+    }
+
+    /// Returns the usage recorded on the given day and the days after it, most recent first.
+    pub fn usage_since(&self, day: NaiveDate) -> Result<Vec<UsageRecord>> {
+        Ok(self
+            .usage_rows(day.format(DAY_FORMAT).to_string())?
+            .into_iter()
+            .filter_map(
+                |(
+                    day,
+                    provider,
+                    model,
+                    requests,
+                    estimated_requests,
+                    input_tokens,
+                    output_tokens,
+                    cache_creation_input_tokens,
+                    cache_read_input_tokens,
+                )| {
+                    Some(UsageRecord {
+                        day: NaiveDate::parse_from_str(&day, DAY_FORMAT).log_err()?,
+                        provider,
+                        model,
+                        requests,
+                        estimated_requests,
+                        usage: TokenUsage {
+                            input_tokens,
+                            output_tokens,
+                            cache_creation_input_tokens,
+                            cache_read_input_tokens,
+                        },
+                    })
+                },
+            )
+            .collect())
+    }
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Records the tokens used by a request to the given model.
+pub(crate) fn record_usage(
+    model: &dyn LanguageModel,
+    usage: TokenUsage,
+    estimated: bool,
+    cx: &AppContext,
+) {
+    let provider = model.provider_id().0.to_string();
+    let model = model.id().0.to_string();
+    db::write_and_log(cx, move || async move {
+        USAGE_DB
+            .record_usage(today(), provider, model, usage, estimated)
+            .await
+    });
+}
+
+/// Records the usage of a request whose usage the provider didn't report, estimated by counting
+/// the tokens of its response.
+pub(crate) async fn record_estimated_usage(
+    model: &dyn LanguageModel,
+    input_tokens: usize,
+    response_text: String,
+    cx: &AsyncAppContext,
+) {
+    let response = LanguageModelRequest {
+        messages: vec![LanguageModelRequestMessage {
+            role: Role::Assistant,
+            content: vec![response_text.into()],
+            cache: false,
+        }],
+        ..Default::default()
+    };
+    let output_tokens = async { cx.update(|cx| model.count_tokens(response, cx))?.await }
+        .await
+        .log_err();
+    let usage = TokenUsage {
+        input_tokens: input_tokens as u32,
+        output_tokens: output_tokens.unwrap_or_default() as u32,
+        ..Default::default()
+    };
+    cx.update(|cx| record_usage(model, usage, true, cx)).ok();
+}
+
+/// Streams the text of a completion, like [`LanguageModel::stream_completion_text`], and
+/// records the request's usage once the completion ends.
+pub(crate) fn stream_completion_text(
+    model: Arc<dyn LanguageModel>,
+    request: LanguageModelRequest,
+    cx: &AsyncAppContext,
+) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+    let events = model.stream_completion(request.clone(), cx);
+    let (completion_tx, completion_rx) = oneshot::channel::<CompletionUsage>();
+    cx.spawn(|cx| async move {
+        let Ok(completion) = completion_rx.await else {
+            return;
+        };
+        if let Some(usage) = completion.reported {
+            cx.update(|cx| record_usage(model.as_ref(), usage, false, cx))
+                .ok();
+        } else if completion.succeeded {
+            // The provider didn't report the request's usage, so we estimate it.
+            let input_tokens = async { cx.update(|cx| model.count_tokens(request, cx))?.await }
+                .await
+                .log_err();
+            record_estimated_usage(
+                model.as_ref(),
+                input_tokens.unwrap_or_default(),
+                completion.response_text,
+                &cx,
+            )
+            .await;
+        }
+    })
+    .detach();
+
+    async move {
+        let events = events.await?;
+        let completion = CompletionUsage {
+            reported: None,
+            response_text: String::new(),
+            succeeded: false,
+        };
+        Ok(stream::unfold(
+            (events, Some((completion, completion_tx))),
+            |(mut events, mut state)| async move {
+                loop {
+                    let event = events.next().await;
+                    let Some((completion, _)) = state.as_mut() else {
+                        return None;
+                    };
+                    match event {
+                        Some(Ok(LanguageModelCompletionEvent::Text(text))) => {
+                            completion.response_text.push_str(&text);
+                            return Some((Ok(text), (events, state)));
+                        }
+                        Some(Ok(LanguageModelCompletionEvent::UsageUpdate(usage))) => {
+                            completion.reported = Some(usage);
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(error)) => {
+                            if let Some((completion, completion_tx)) = state.take() {
+                                completion_tx.send(completion).ok();
+                            }
+                            return Some((Err(error), (events, state)));
+                        }
+                        None => {
+                            if let Some((mut completion, completion_tx)) = state.take() {
+                                completion.succeeded = true;
+                                completion_tx.send(completion).ok();
+                            }
+                            return None;
+                        }
+                    }
+                }
+            },
+        )
+        .boxed())
+    }
+    .boxed()
+}
+
+/// The usage of a completion streamed by [`stream_completion_text`].
+struct CompletionUsage {
+    reported: Option<TokenUsage>,
+    response_text: String,
+    succeeded: bool,
+}
+
+/// Returns a warning if sending a request with the given number of input tokens to the model
+/// would exceed the usage budget.
+pub(crate) fn budget_warning(
+    model: &dyn LanguageModel,
+    input_tokens: usize,
+    cx: &AppContext,
+) -> Option<String> {
+    let settings = AssistantSettings::get_global(cx);
+    if settings.usage_budget.per_request.is_none() && settings.usage_budget.daily.is_none() {
+        return None;
+    }
+
+    let todays_usage = USAGE_DB.usage_since(today()).log_err().unwrap_or_default();
+    check_budget(
+        settings,
+        &todays_usage,
+        &model.provider_id().0,
+        &model.id().0,
+        input_tokens,
+    )
+}
+
+fn check_budget(
+    settings: &AssistantSettings,
+    todays_usage: &[UsageRecord],
+    provider: &str,
+    model: &str,
+    input_tokens: usize,
+) -> Option<String> {
+    let price = settings.model_price(provider, model)?;
+    // Only the input is known before the request is sent, so this is a lower bound.
+    let request_cost = price.cost(&TokenUsage {
+        input_tokens: input_tokens as u32,
+        ..Default::default()
+    });
+
+    if let Some(per_request) = settings.usage_budget.per_request {
+        if request_cost > per_request {
+            return Some(format!(
+                "This request will cost at least {}, more than your budget of {} per request.",
+                format_cost(request_cost),
+                format_cost(per_request)
+            ));
+        }
+    }
+
+    if let Some(daily) = settings.usage_budget.daily {
+        let spent: f64 = todays_usage
+            .iter()
+            .filter_map(|record| record.cost(settings))
+            .sum();
+        if spent + request_cost > daily {
+            return Some(format!(
+                "You've spent {} today, and this request will cost at least {}, which exceeds your daily budget of {}.",
+                format_cost(spent),
+                format_cost(request_cost),
+                format_cost(daily)
+            ));
+        }
+    }
+
+    None
+}
+
+fn format_cost(cost: f64) -> String {
+    if cost > 0. && cost < 0.01 {
+        "<$0.01".to_string()
+    } else {
+        format!("${cost:.2}")
+    }
+}
+
+fn format_tokens(tokens: u64) -> String {
+    if tokens >= 1_000_000 {
+        format!("{:.1}M", tokens as f64 / 1_000_000.)
+    } else if tokens >= 1_000 {
+        format!("{:.1}k", tokens as f64 / 1_000.)
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// Shows the tokens used by the assistant over the last days, and their cost.
+pub struct UsageView {
+    focus_handle: FocusHandle,
+    records: Vec<UsageRecord>,
+    _settings_subscription: Subscription,
+}
+
+impl UsageView {
+    pub fn new(cx: &mut ViewContext<Self>) -> Self {
+        let mut this = Self {
+            focus_handle: cx.focus_handle(),
+            records: Vec::new(),
+            // Prices may change.
+            _settings_subscription: cx.observe_global::<SettingsStore>(|_, cx| cx.notify()),
+        };
+        this.reload(cx);
+        this
+    }
+
+    pub fn reload(&mut self, cx: &mut ViewContext<Self>) {
+        let since = today()
+            .checked_sub_days(Days::new(USAGE_DAYS - 1))
+            .unwrap_or(NaiveDate::MIN);
+        self.records = USAGE_DB.usage_since(since).log_err().unwrap_or_default();
+        cx.notify();
+    }
+
+    fn render_cell(text: impl Into<SharedString>, width: f32) -> impl IntoElement {
+        div()
+            .w(rems(width))
+            .flex_none()
+            .child(Label::new(text).size(LabelSize::Small))
+    }
+
+    fn render_header(&self) -> impl IntoElement {
+        h_flex()
+            .gap_2()
+            .child(
+                div()
+                    .flex_1()
+                    .child(Label::new("Model").color(Color::Muted)),
+            )
+            .children(
+                [
+                    "Requests",
+                    "Input",
+                    "Output",
+                    "Cache Write",
+                    "Cache Read",
+                    "Cost",
+                ]
+                .map(|title| {
+                    div()
+                        .w(rems(6.))
+                        .flex_none()
+                        .child(Label::new(title).color(Color::Muted))
+                }),
+            )
+    }
+
+    fn render_record(
+        &self,
+        record: &UsageRecord,
+        settings: &AssistantSettings,
+    ) -> impl IntoElement {
+        let estimated = if record.estimated_requests > 0 {
+            "~"
+        } else {
+            ""
+        };
+        let cost = record
+            .cost(settings)
+            .map_or_else(|| "—".to_string(), format_cost);
+
+        h_flex()
+            .gap_2()
+            .child(
+                h_flex()
+                    .flex_1()
+                    .gap_1()
+                    .child(Label::new(record.model.clone()).size(LabelSize::Small))
+                    .child(
+                        Label::new(record.provider.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .child(Self::render_cell(record.requests.to_string(), 6.))
+            .child(Self::render_cell(
+                format!(
+                    "{estimated}{}",
+                    format_tokens(record.usage.input_tokens as u64)
+                ),
+                6.,
+            ))
+            .child(Self::render_cell(
+                format!(
+                    "{estimated}{}",
+                    format_tokens(record.usage.output_tokens as u64)
+                ),
+                6.,
+            ))
+            .child(Self::render_cell(
+                format_tokens(record.usage.cache_creation_input_tokens as u64),
+                6.,
+            ))
+            .child(Self::render_cell(
+                format_tokens(record.usage.cache_read_input_tokens as u64),
+                6.,
+            ))
+            .child(Self::render_cell(format!("{estimated}{cost}"), 6.))
+    }
+
+    fn render_summary(&self, settings: &AssistantSettings) -> impl IntoElement {
+        let today = today();
+        let todays_cost: f64 = self
+            .records
+            .iter()
+            .filter(|record| record.day == today)
+            .filter_map(|record| record.cost(settings))
+            .sum();
+        let total_cost: f64 = self
+            .records
+            .iter()
+            .filter_map(|record| record.cost(settings))
+            .sum();
+        let total_tokens: u64 = self
+            .records
+            .iter()
+            .map(|record| record.usage.total_tokens() as u64)
+            .sum();
+        let total_requests: u64 = self
+            .records
+            .iter()
+            .map(|record| record.requests as u64)
+            .sum();
+
+        let mut daily_budget = format!("Today: {}", format_cost(todays_cost));
+        if let Some(daily) = settings.usage_budget.daily {
+            daily_budget.push_str(&format!(" of your {} daily budget", format_cost(daily)));
+        }
+
+        v_flex()
+            .gap_1()
+            .child(Label::new(daily_budget))
+            .child(
+                Label::new(format!(
+                    "Last {USAGE_DAYS} days: {} for {} tokens in {} requests",
+                    format_cost(total_cost),
+                    format_tokens(total_tokens),
+                    total_requests
+                ))
+                .color(Color::Muted),
+            )
+            .when(
+                self.records
+                    .iter()
+                    .any(|record| record.cost(settings).is_none()),
+                |this| {
+                    this.child(
+                        Label::new(
+                            "Set \"model_prices\" in the assistant settings to see the cost of each model.",
+                        )
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                    )
+                },
+            )
+    }
+}
+
+impl Render for UsageView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let settings = AssistantSettings::get_global(cx);
+
+        let mut days = Vec::<(NaiveDate, Vec<&UsageRecord>)>::new();
+        for record in &self.records {
+            match days.last_mut() {
+                Some((day, records)) if *day == record.day => records.push(record),
+                _ => days.push((record.day, vec![record])),
+            }
+        }
+
+        v_flex()
+            .id("assistant-usage-view")
+            .track_focus(&self.focus_handle)
+            .bg(cx.theme().colors().editor_background)
+            .size_full()
+            .overflow_y_scroll()
+            .p(Spacing::XXLarge.rems(cx))
+            .gap_6()
+            .child(
+                v_flex()
+                    .gap_2()
+                    .child(Headline::new("Assistant Usage").size(HeadlineSize::Medium))
+                    .child(self.render_summary(settings)),
+            )
+            .when(days.is_empty(), |this| {
+                this.child(Label::new("No requests yet.").color(Color::Muted))
+            })
+            .children(days.into_iter().map(|(day, records)| {
+                v_flex()
+                    .gap_1()
+                    .child(
+                        Headline::new(day.format("%A, %B %-d").to_string())
+                            .size(HeadlineSize::XSmall),
+                    )
+                    .child(self.render_header())
+                    .children(
+                        records
+                            .into_iter()
+                            .map(|record| self.render_record(record, settings)),
+                    )
+            }))
+    }
+}
+
+impl FocusableView for UsageView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<()> for UsageView {}
+
+impl Item for UsageView {
+    type Event = ();
+
+    fn tab_content_text(&self, _cx: &WindowContext) -> Option<SharedString> {
+        Some("Usage".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assistant_settings::{ModelPrice, UsageBudget};
+
+    #[test]
+    fn test_record_usage() {
+        let day = NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+        let usage = TokenUsage {
+            input_tokens: 100,
+            output_tokens: 10,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 50,
+        };
+        smol::block_on(async {
+            for estimated in [false, true] {
+                USAGE_DB
+                    .record_usage(day, "usage-test".into(), "claude".into(), usage, estimated)
+                    .await
+                    .unwrap();
+            }
+            USAGE_DB
+                .record_usage(
+                    day.pred_opt().unwrap(),
+                    "usage-test".into(),
+                    "claude".into(),
+                    usage,
+                    false,
+                )
+                .await
+                .unwrap();
+        });
+
+        // Other tests may record usage for today.
+        let usage_since = |day| {
+            USAGE_DB
+                .usage_since(day)
+                .unwrap()
+                .into_iter()
+                .filter(|record| record.provider == "usage-test")
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            usage_since(day),
+            vec![UsageRecord {
+                day,
+                provider: "usage-test".into(),
+                model: "claude".into(),
+                requests: 2,
+                estimated_requests: 1,
+                usage: TokenUsage {
+                    input_tokens: 200,
+                    output_tokens: 20,
+                    cache_creation_input_tokens: 0,
+                    cache_read_input_tokens: 100,
+                },
+            }]
+        );
+        assert_eq!(usage_since(day.pred_opt().unwrap()).len(), 2);
+    }
+
+    #[test]
+    fn test_check_budget() {
+        let mut settings = AssistantSettings::default();
+        settings.model_prices.insert(
+            "anthropic/claude".into(),
+            ModelPrice {
+                input: 3.,
+                output: 15.,
+                cache_write: None,
+                cache_read: None,
+            },
+        );
+        let todays_usage = [UsageRecord {
+            day: today(),
+            provider: "anthropic".into(),
+            model: "claude".into(),
+            requests: 1,
+            estimated_requests: 0,
+            usage: TokenUsage {
+                input_tokens: 1_000_000,
+                output_tokens: 100_000,
+                ..Default::default()
+            },
+        }];
+
+        // Without budgets, nothing is checked.
+        assert_eq!(
+            check_budget(&settings, &todays_usage, "anthropic", "claude", 1_000_000),
+            None
+        );
+
+        settings.usage_budget = UsageBudget {
+            per_request: Some(1.),
+            daily: Some(5.),
+        };
+        assert_eq!(
+            check_budget(&settings, &todays_usage, "anthropic", "claude", 100_000),
+            None
+        );
+        assert!(
+            check_budget(&settings, &todays_usage, "anthropic", "claude", 500_000)
+                .unwrap()
+                .contains("per request")
+        );
+
+        settings.usage_budget.per_request = None;
+        assert!(
+            check_budget(&settings, &todays_usage, "anthropic", "claude", 500_000)
+                .unwrap()
+                .contains("daily budget")
+        );
+
+        // Models without a price can't be checked.
+        assert_eq!(
+            check_budget(&settings, &todays_usage, "openai", "gpt-4o", 10_000_000),
+            None
+        );
+    }
+}
@@ -25,6 +25,12 @@ pub trait Tool: 'static + Send + Sync {
         serde_json::Value::Object(serde_json::Map::default())
     }
 
+    /// Returns whether the tool can have side effects, in which case the user
+    /// is asked to confirm each use of it before it runs.
+    fn needs_confirmation(&self) -> bool {
+        false
+    }
+
     /// Runs the tool with the provided input.
     fn run(
         self: Arc<Self>,
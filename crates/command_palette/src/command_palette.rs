@@ -7,7 +7,7 @@ use std::{
 use client::{parse_zed_link, telemetry::Telemetry};
 use collections::HashMap;
 use command_palette_hooks::{
-    CommandInterceptResult, CommandPaletteFilter, CommandPaletteInterceptor,
+    CommandInterceptResult, CommandPaletteCommands, CommandPaletteFilter, CommandPaletteInterceptor,
 };
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
@@ -79,7 +79,7 @@ impl CommandPalette {
     ) -> Self {
         let filter = CommandPaletteFilter::try_global(cx);
 
-        let commands = cx
+        let mut commands = cx
             .available_actions()
             .into_iter()
             .filter_map(|action| {
@@ -92,7 +92,22 @@ impl CommandPalette {
                     action,
                 })
             })
-            .collect();
+            .collect::<Vec<_>>();
+
+        if let Some(extra_commands) = CommandPaletteCommands::try_global(cx) {
+            commands.extend(extra_commands.commands().filter_map(|(name, action)| {
+                if filter.is_some_and(|filter| filter.is_hidden(action))
+                    || !cx.is_action_available(action)
+                {
+                    return None;
+                }
+
+                Some(Command {
+                    name: name.to_string(),
+                    action: action.boxed_clone(),
+                })
+            }));
+        }
 
         let delegate = CommandPaletteDelegate::new(
             cx.view().downgrade(),
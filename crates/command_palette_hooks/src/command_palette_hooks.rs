@@ -12,6 +12,7 @@ use gpui::{Action, AppContext, BorrowAppContext, Global};
 pub fn init(cx: &mut AppContext) {
     cx.set_global(GlobalCommandPaletteFilter::default());
     cx.set_global(GlobalCommandPaletteInterceptor::default());
+    cx.set_global(GlobalCommandPaletteCommands::default());
 }
 
 /// A filter for the command palette.
@@ -78,6 +79,55 @@ impl CommandPaletteFilter {
     }
 }
 
+/// Commands shown in the command palette alongside the available actions, such
+/// as those provided by extensions.
+///
+/// A command is only shown when its action can be dispatched from the focused element.
+#[derive(Default)]
+pub struct CommandPaletteCommands {
+    commands: Vec<(String, Box<dyn Action>)>,
+}
+
+#[derive(Deref, DerefMut, Default)]
+struct GlobalCommandPaletteCommands(CommandPaletteCommands);
+
+impl Global for GlobalCommandPaletteCommands {}
+
+impl CommandPaletteCommands {
+    /// Returns the global [`CommandPaletteCommands`], if they are set.
+    pub fn try_global(cx: &AppContext) -> Option<&CommandPaletteCommands> {
+        cx.try_global::<GlobalCommandPaletteCommands>()
+            .map(|commands| &commands.0)
+    }
+
+    /// Updates the global [`CommandPaletteCommands`] using the given closure.
+    pub fn update_global<F, R>(cx: &mut AppContext, update: F) -> R
+    where
+        F: FnOnce(&mut Self, &mut AppContext) -> R,
+    {
+        cx.update_global(|this: &mut GlobalCommandPaletteCommands, cx| update(&mut this.0, cx))
+    }
+
+    /// Returns the commands, along with the names to show them under.
+    pub fn commands(&self) -> impl Iterator<Item = (&str, &dyn Action)> {
+        self.commands
+            .iter()
+            .map(|(name, action)| (name.as_str(), action.as_ref()))
+    }
+
+    /// Adds a command that dispatches the given action, shown under the given name.
+    pub fn add(&mut self, name: impl Into<String>, action: Box<dyn Action>) {
+        self.remove(action.as_ref());
+        self.commands.push((name.into(), action));
+    }
+
+    /// Removes the command that dispatches the given action.
+    pub fn remove(&mut self, action: &dyn Action) {
+        self.commands
+            .retain(|(_, command_action)| !command_action.partial_eq(action));
+    }
+}
+
 /// The result of intercepting a command palette command.
 pub struct CommandInterceptResult {
     /// The action produced as a result of the interception.
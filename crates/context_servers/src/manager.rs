@@ -14,9 +14,13 @@
 //! The module also includes initialization logic to set up the context server system
 //! and react to changes in settings.
 
+use anyhow::Context as _;
 use collections::{HashMap, HashSet};
 use command_palette_hooks::CommandPaletteFilter;
-use gpui::{AppContext, AsyncAppContext, Context, EventEmitter, Global, Model, ModelContext, Task};
+use gpui::{
+    AppContext, AsyncAppContext, Context, EventEmitter, Global, Model, ModelContext, Task,
+    WeakModel,
+};
 use log;
 use parking_lot::RwLock;
 use schemars::JsonSchema;
@@ -24,10 +28,13 @@ use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources, SettingsStore};
 use std::path::Path;
 use std::sync::Arc;
+use url::Url;
+use util::ResultExt;
 
 use crate::CONTEXT_SERVERS_NAMESPACE;
 use crate::{
     client::{self, Client},
+    transport::{HttpTransport, HttpTransportKind},
     types,
 };
 
@@ -39,9 +46,21 @@ pub struct ContextServerSettings {
 #[derive(Deserialize, Serialize, Clone, PartialEq, Eq, JsonSchema, Debug)]
 pub struct ServerConfig {
     pub id: String,
-    pub executable: String,
+    /// The command that starts a server which talks over stdio.
+    #[serde(default)]
+    pub executable: Option<String>,
+    #[serde(default)]
     pub args: Vec<String>,
     pub env: Option<HashMap<String, String>>,
+    /// The URL of a server which talks over HTTP, used instead of `executable`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// The protocol spoken by the server at `url`.
+    #[serde(default)]
+    pub transport: HttpTransportKind,
+    /// Headers sent with every request to the server at `url`, such as `Authorization`.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
 }
 
 impl Settings for ContextServerSettings {
@@ -74,15 +93,37 @@ impl ContextServer {
 
     async fn start(&self, cx: &AsyncAppContext) -> anyhow::Result<()> {
         log::info!("starting context server {}", self.config.id,);
-        let client = Client::new(
-            client::ContextServerId(self.config.id.clone()),
-            client::ModelContextServerBinary {
-                executable: Path::new(&self.config.executable).to_path_buf(),
-                args: self.config.args.clone(),
-                env: self.config.env.clone(),
-            },
-            cx.clone(),
-        )?;
+        let server_id = client::ContextServerId(self.config.id.clone());
+        let client = if let Some(url) = &self.config.url {
+            let url =
+                Url::parse(url).with_context(|| format!("invalid context server URL {url}"))?;
+            let http_client = cx.update(|cx| cx.http_client())?;
+            let transport = HttpTransport::new(
+                url.clone(),
+                self.config.transport,
+                self.config.headers.clone(),
+                http_client,
+                cx,
+            );
+            let name = url.host_str().unwrap_or_default().into();
+            Client::with_transport(server_id, name, Arc::new(transport), cx.clone())
+        } else {
+            let executable = self.config.executable.as_ref().with_context(|| {
+                format!(
+                    "context server {} has neither an executable nor a URL",
+                    self.config.id
+                )
+            })?;
+            Client::new(
+                server_id,
+                client::ModelContextServerBinary {
+                    executable: Path::new(executable).to_path_buf(),
+                    args: self.config.args.clone(),
+                    env: self.config.env.clone(),
+                },
+                cx.clone(),
+            )?
+        };
 
         let protocol = crate::protocol::ModelContextProtocol::new(client);
         let client_info = types::Implementation {
@@ -122,6 +163,7 @@ pub struct ContextServerManager {
 pub enum Event {
     ServerStarted { server_id: String },
     ServerStopped { server_id: String },
+    ResourceUpdated { server_id: String, uri: String },
 }
 
 impl Global for ContextServerManager {}
@@ -144,6 +186,11 @@ impl ContextServerManager {
         cx.global::<GlobalContextServerManager>().0.clone()
     }
 
+    pub fn try_global(cx: &AppContext) -> Option<Model<Self>> {
+        cx.try_global::<GlobalContextServerManager>()
+            .map(|manager| manager.0.clone())
+    }
+
     pub fn add_server(
         &mut self,
         config: ServerConfig,
@@ -160,6 +207,7 @@ impl ContextServerManager {
             cx.spawn(|this, mut cx| async move {
                 let server = Arc::new(ContextServer::new(config));
                 server.start(&cx).await?;
+                Self::forward_resource_updates(&server, this.clone());
                 this.update(&mut cx, |this, cx| {
                     this.servers.insert(server_id.clone(), server);
                     this.pending_servers.remove(&server_id);
@@ -211,6 +259,7 @@ impl ContextServerManager {
                 let config = server.config.clone();
                 let new_server = Arc::new(ContextServer::new(config));
                 new_server.start(&cx).await?;
+                Self::forward_resource_updates(&new_server, this.clone());
                 this.update(&mut cx, |this, cx| {
                     this.servers.insert(id.clone(), new_server);
                     cx.emit(Event::ServerStopped {
@@ -225,6 +274,35 @@ impl ContextServerManager {
         })
     }
 
+    /// Re-emits the server's resource update notifications as [`Event::ResourceUpdated`].
+    fn forward_resource_updates(server: &ContextServer, this: WeakModel<Self>) {
+        let Some(protocol) = server.client.read().clone() else {
+            return;
+        };
+        if !protocol.supports_resource_subscriptions() {
+            return;
+        }
+
+        let server_id = server.id.clone();
+        protocol.on_notification(
+            types::NotificationType::ResourcesUpdated.as_str(),
+            move |params, mut cx| {
+                let Some(params) =
+                    serde_json::from_value::<types::ResourcesUpdatedParams>(params).log_err()
+                else {
+                    return;
+                };
+                this.update(&mut cx, |_, cx| {
+                    cx.emit(Event::ResourceUpdated {
+                        server_id: server_id.clone(),
+                        uri: params.uri.to_string(),
+                    })
+                })
+                .ok();
+            },
+        );
+    }
+
     pub fn servers(&self) -> Vec<Arc<ContextServer>> {
         self.servers.values().cloned().collect()
     }
@@ -7,6 +7,7 @@
 
 use anyhow::Result;
 use collections::HashMap;
+use url::Url;
 
 use crate::client::Client;
 use crate::types;
@@ -131,6 +132,59 @@ impl InitializedContextServerProtocol {
         Ok(response)
     }
 
+    /// List the MCP resource templates.
+    pub async fn list_resource_templates(&self) -> Result<types::ResourceTemplatesListResponse> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let response: types::ResourceTemplatesListResponse = self
+            .inner
+            .request(types::RequestType::ResourcesTemplatesList.as_str(), ())
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Reads the contents of the resource with the given URI.
+    pub async fn read_resource(&self, uri: &str) -> Result<types::ResourcesReadResponse> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let params = types::ResourcesReadParams {
+            uri: Url::parse(uri)?,
+        };
+        let response: types::ResourcesReadResponse = self
+            .inner
+            .request(types::RequestType::ResourcesRead.as_str(), params)
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Whether the server notifies subscribers when a resource changes.
+    pub fn supports_resource_subscriptions(&self) -> bool {
+        self.initialize
+            .capabilities
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false)
+    }
+
+    /// Asks the server to send a `notifications/resources/updated` notification whenever the
+    /// resource with the given URI changes.
+    pub async fn subscribe_resource(&self, uri: &str) -> Result<()> {
+        self.check_capability(ServerCapability::Resources)?;
+
+        let params = types::ResourcesSubscribeParams {
+            uri: Url::parse(uri)?,
+        };
+        let _: serde_json::Value = self
+            .inner
+            .request(types::RequestType::ResourcesSubscribe.as_str(), params)
+            .await?;
+
+        Ok(())
+    }
+
     /// Executes a prompt with the given arguments and returns the result.
     pub async fn run_prompt<P: AsRef<str>>(
         &self,
@@ -152,6 +206,60 @@ impl InitializedContextServerProtocol {
         Ok(response)
     }
 
+    /// List the MCP tools, reading every page of them if the server paginates the list.
+    pub async fn list_tools(&self) -> Result<types::ListToolsResponse> {
+        self.check_capability(ServerCapability::Tools)?;
+
+        let mut tools = Vec::new();
+        let mut cursor = None;
+        loop {
+            let response: types::ListToolsResponse = self
+                .inner
+                .request(
+                    types::RequestType::ListTools.as_str(),
+                    types::ListToolsParams {
+                        cursor: cursor.clone(),
+                    },
+                )
+                .await?;
+            tools.extend(response.tools);
+
+            // Stop if the server hands out the same cursor again, rather than looping forever.
+            match response.next_cursor {
+                Some(next_cursor) if cursor.as_ref() != Some(&next_cursor) => {
+                    cursor = Some(next_cursor)
+                }
+                _ => break,
+            }
+        }
+
+        Ok(types::ListToolsResponse {
+            tools,
+            next_cursor: None,
+        })
+    }
+
+    /// Executes a tool with the given arguments and returns the result.
+    pub async fn run_tool<P: AsRef<str>>(
+        &self,
+        tool: P,
+        arguments: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<types::CallToolResponse> {
+        self.check_capability(ServerCapability::Tools)?;
+
+        let params = types::CallToolParams {
+            name: tool.as_ref().to_string(),
+            arguments,
+        };
+
+        let response: types::CallToolResponse = self
+            .inner
+            .request(types::RequestType::CallTool.as_str(), params)
+            .await?;
+
+        Ok(response)
+    }
+
     pub async fn completion<P: Into<String>>(
         &self,
         reference: types::CompletionReference,
@@ -190,4 +298,11 @@ impl InitializedContextServerProtocol {
     ) -> Result<R> {
         self.inner.request(method, params).await
     }
+
+    pub fn on_notification<F>(&self, method: &'static str, f: F)
+    where
+        F: 'static + Send + FnMut(serde_json::Value, gpui::AsyncAppContext),
+    {
+        self.inner.on_notification(method, f)
+    }
 }
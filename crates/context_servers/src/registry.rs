@@ -10,6 +10,7 @@ impl Global for GlobalContextServerRegistry {}
 
 pub struct ContextServerRegistry {
     registry: RwLock<HashMap<String, Vec<Arc<str>>>>,
+    tools: RwLock<HashMap<String, Vec<Arc<str>>>>,
 }
 
 impl ContextServerRegistry {
@@ -21,6 +22,7 @@ impl ContextServerRegistry {
         cx.set_global(GlobalContextServerRegistry(Arc::new(
             ContextServerRegistry {
                 registry: RwLock::new(HashMap::default()),
+                tools: RwLock::new(HashMap::default()),
             },
         )))
     }
@@ -44,4 +46,21 @@ impl ContextServerRegistry {
         let registry = self.registry.read();
         registry.get(server_id).cloned()
     }
+
+    pub fn register_tool(&self, server_id: String, tool_name: &str) {
+        let mut tools = self.tools.write();
+        tools.entry(server_id).or_default().push(tool_name.into());
+    }
+
+    pub fn unregister_tool(&self, server_id: &str, tool_name: &str) {
+        let mut tools = self.tools.write();
+        if let Some(server_tools) = tools.get_mut(server_id) {
+            server_tools.retain(|name| name.as_ref() != tool_name);
+        }
+    }
+
+    pub fn get_tools(&self, server_id: &str) -> Option<Vec<Arc<str>>> {
+        let tools = self.tools.read();
+        tools.get(server_id).cloned()
+    }
 }
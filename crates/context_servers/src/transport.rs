@@ -0,0 +1,22 @@
+mod http;
+mod stdio;
+
+pub use http::{HttpTransport, HttpTransportKind};
+pub use stdio::StdioTransport;
+
+use anyhow::Result;
+use futures::{future::BoxFuture, stream::BoxStream};
+
+/// Carries serialized JSON-RPC messages between a [`Client`](crate::client::Client) and a
+/// context server.
+pub trait Transport: Send + Sync {
+    /// Delivers a message to the server.
+    fn send(&self, message: String) -> BoxFuture<'static, Result<()>>;
+
+    /// Returns the messages sent by the server. The stream ends once the server can no longer
+    /// be reached.
+    fn receive(&self) -> BoxStream<'static, String>;
+
+    /// Returns diagnostic output from the server, such as its stderr.
+    fn receive_err(&self) -> BoxStream<'static, String>;
+}
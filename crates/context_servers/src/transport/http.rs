@@ -0,0 +1,685 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use futures::{
+    future::BoxFuture, io::BufReader, stream::BoxStream, AsyncBufReadExt, AsyncReadExt, FutureExt,
+    StreamExt,
+};
+use gpui::{AsyncAppContext, BackgroundExecutor, Task};
+use http_client::{
+    http::{header, request::Builder},
+    AsyncBody, HttpClient, Method, Request, Response, StatusCode, Url,
+};
+use parking_lot::Mutex;
+use postage::{stream::Stream as _, watch};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use smol::channel;
+use util::ResultExt;
+
+use super::Transport;
+
+const SESSION_ID_HEADER: &str = "Mcp-Session-Id";
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+const EVENT_STREAM_CONTENT_TYPE: &str = "text/event-stream";
+const JSON_CONTENT_TYPE: &str = "application/json";
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// The protocols a context server can speak over HTTP.
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpTransportKind {
+    /// Each message is POSTed to the server's URL, which answers with either JSON or an event
+    /// stream.
+    #[default]
+    StreamableHttp,
+    /// The server's URL is an event stream, which announces where messages should be POSTed.
+    Sse,
+}
+
+/// Talks to a context server over HTTP, reconnecting to its event stream when it drops.
+pub struct HttpTransport {
+    connection: Connection,
+    mode: Mode,
+    messages_rx: channel::Receiver<String>,
+    errors_rx: channel::Receiver<String>,
+    _event_stream: Task<()>,
+}
+
+enum Mode {
+    StreamableHttp {
+        /// Signals that the server answered a message, after which its event stream for
+        /// server-initiated messages can be opened.
+        connected_tx: channel::Sender<()>,
+    },
+    Sse {
+        /// Where to POST messages, as last announced on the event stream.
+        endpoint_rx: watch::Receiver<Option<Url>>,
+    },
+}
+
+/// What's needed to make requests to the server and to forward what it sends.
+#[derive(Clone)]
+struct Connection {
+    http_client: Arc<dyn HttpClient>,
+    url: Url,
+    headers: Arc<HashMap<String, String>>,
+    session_id: Arc<Mutex<Option<String>>>,
+    messages_tx: channel::Sender<String>,
+    errors_tx: channel::Sender<String>,
+    executor: BackgroundExecutor,
+}
+
+/// An event read from a `text/event-stream` response.
+#[derive(Debug, PartialEq)]
+struct SseEvent {
+    event: Option<String>,
+    data: String,
+    id: Option<String>,
+}
+
+/// Assembles events from the lines of a `text/event-stream` response.
+#[derive(Default)]
+struct SseParser {
+    event: Option<String>,
+    data: Option<String>,
+    last_event_id: Option<String>,
+}
+
+enum EventStreamEnd {
+    Closed,
+    Unsupported,
+}
+
+impl HttpTransport {
+    pub fn new(
+        url: Url,
+        kind: HttpTransportKind,
+        headers: HashMap<String, String>,
+        http_client: Arc<dyn HttpClient>,
+        cx: &AsyncAppContext,
+    ) -> Self {
+        let (messages_tx, messages_rx) = channel::unbounded();
+        let (errors_tx, errors_rx) = channel::unbounded();
+        let connection = Connection {
+            http_client,
+            url,
+            headers: Arc::new(headers),
+            session_id: Default::default(),
+            messages_tx,
+            errors_tx,
+            executor: cx.background_executor().clone(),
+        };
+
+        let (mode, event_stream) = match kind {
+            HttpTransportKind::StreamableHttp => {
+                let (connected_tx, connected_rx) = channel::bounded(1);
+                let event_stream = connection
+                    .executor
+                    .spawn(connection.clone().listen_for_server_messages(connected_rx));
+                (Mode::StreamableHttp { connected_tx }, event_stream)
+            }
+            HttpTransportKind::Sse => {
+                let (endpoint_tx, endpoint_rx) = watch::channel();
+                let event_stream = connection
+                    .executor
+                    .spawn(connection.clone().listen_for_sse_messages(endpoint_tx));
+                (Mode::Sse { endpoint_rx }, event_stream)
+            }
+        };
+
+        Self {
+            connection,
+            mode,
+            messages_rx,
+            errors_rx,
+            _event_stream: event_stream,
+        }
+    }
+}
+
+impl Transport for HttpTransport {
+    fn send(&self, message: String) -> BoxFuture<'static, Result<()>> {
+        let connection = self.connection.clone();
+        match &self.mode {
+            Mode::StreamableHttp { connected_tx } => {
+                let connected_tx = connected_tx.clone();
+                async move {
+                    connection.post_streamable(message).await?;
+                    connected_tx.try_send(()).ok();
+                    Ok(())
+                }
+                .boxed()
+            }
+            Mode::Sse { endpoint_rx } => {
+                let mut endpoint_rx = endpoint_rx.clone();
+                async move {
+                    let endpoint = loop {
+                        if let Some(endpoint) = endpoint_rx.borrow().clone() {
+                            break endpoint;
+                        }
+                        endpoint_rx
+                            .recv()
+                            .await
+                            .context("context server's event stream was closed")?;
+                    };
+                    connection.post_sse(endpoint, message).await
+                }
+                .boxed()
+            }
+        }
+    }
+
+    fn receive(&self) -> BoxStream<'static, String> {
+        self.messages_rx.clone().boxed()
+    }
+
+    fn receive_err(&self) -> BoxStream<'static, String> {
+        self.errors_rx.clone().boxed()
+    }
+}
+
+impl Drop for HttpTransport {
+    fn drop(&mut self) {
+        // Let the server know it can discard the session, as streamable HTTP clients should.
+        if self.connection.session_id.lock().is_some() {
+            let connection = self.connection.clone();
+            self.connection
+                .executor
+                .spawn(async move { connection.end_session().await.log_err() })
+                .detach();
+        }
+    }
+}
+
+impl Connection {
+    fn request(&self, method: Method, url: &Url) -> Builder {
+        let mut request = Request::builder().method(method).uri(url.as_str());
+        for (name, value) in self.headers.iter() {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(session_id) = self.session_id.lock().as_ref() {
+            request = request.header(SESSION_ID_HEADER, session_id.as_str());
+        }
+        request
+    }
+
+    /// Sends a message using the streamable HTTP transport, forwarding whatever the server
+    /// answers with.
+    async fn post_streamable(&self, message: String) -> Result<()> {
+        let request = self
+            .request(Method::POST, &self.url)
+            .header(header::CONTENT_TYPE, JSON_CONTENT_TYPE)
+            .header(
+                header::ACCEPT,
+                format!("{JSON_CONTENT_TYPE}, {EVENT_STREAM_CONTENT_TYPE}"),
+            )
+            .body(AsyncBody::from(message))?;
+        let mut response = self.http_client.send(request).await?;
+
+        if response.status() == StatusCode::NOT_FOUND && self.session_id.lock().take().is_some() {
+            return Err(anyhow!("context server session expired"));
+        }
+        check_status(&mut response).await?;
+
+        if let Some(session_id) = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            *self.session_id.lock() = Some(session_id.to_string());
+        }
+
+        match content_type(&response).as_deref() {
+            Some(EVENT_STREAM_CONTENT_TYPE) => {
+                // The stream stays open until the server has answered, which can take a while,
+                // so read it in the background instead of holding up the next message.
+                let this = self.clone();
+                self.executor
+                    .spawn(async move {
+                        read_events(response.into_body(), |event| this.forward(event.data))
+                            .await
+                            .log_err();
+                    })
+                    .detach();
+            }
+            Some(JSON_CONTENT_TYPE) => {
+                let mut body = String::new();
+                response.body_mut().read_to_string(&mut body).await?;
+                for message in split_json_messages(&body)? {
+                    self.forward(message)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Sends a message using the HTTP+SSE transport. Responses arrive on the event stream.
+    async fn post_sse(&self, endpoint: Url, message: String) -> Result<()> {
+        let request = self
+            .request(Method::POST, &endpoint)
+            .header(header::CONTENT_TYPE, JSON_CONTENT_TYPE)
+            .body(AsyncBody::from(message))?;
+        let mut response = self.http_client.send(request).await?;
+        check_status(&mut response).await
+    }
+
+    async fn end_session(&self) -> Result<()> {
+        let request = self
+            .request(Method::DELETE, &self.url)
+            .body(AsyncBody::empty())?;
+        let mut response = self.http_client.send(request).await?;
+        // Servers that don't let clients end sessions answer with 405.
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(());
+        }
+        check_status(&mut response).await
+    }
+
+    /// Keeps the streamable HTTP event stream for server-initiated messages open, once the
+    /// server has answered a message.
+    async fn listen_for_server_messages(self, connected_rx: channel::Receiver<()>) {
+        if connected_rx.recv().await.is_err() {
+            return;
+        }
+
+        let mut last_event_id = None;
+        let mut delay = MIN_RECONNECT_DELAY;
+        loop {
+            match self.stream_server_messages(&mut last_event_id).await {
+                Ok(EventStreamEnd::Unsupported) => return,
+                Ok(EventStreamEnd::Closed) => delay = MIN_RECONNECT_DELAY,
+                Err(error) => {
+                    self.report(format!("event stream failed: {error:#}"));
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+            if self.messages_tx.is_closed() {
+                return;
+            }
+            self.executor.timer(delay).await;
+        }
+    }
+
+    async fn stream_server_messages(
+        &self,
+        last_event_id: &mut Option<String>,
+    ) -> Result<EventStreamEnd> {
+        let mut request = self
+            .request(Method::GET, &self.url)
+            .header(header::ACCEPT, EVENT_STREAM_CONTENT_TYPE);
+        if let Some(last_event_id) = last_event_id.as_ref() {
+            request = request.header(LAST_EVENT_ID_HEADER, last_event_id.as_str());
+        }
+        let mut response = self
+            .http_client
+            .send(request.body(AsyncBody::empty())?)
+            .await?;
+
+        // Servers aren't required to offer this stream.
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(EventStreamEnd::Unsupported);
+        }
+        check_status(&mut response).await?;
+
+        read_events(response.into_body(), |event| {
+            if event.id.is_some() {
+                *last_event_id = event.id;
+            }
+            self.forward(event.data)
+        })
+        .await?;
+        Ok(EventStreamEnd::Closed)
+    }
+
+    /// Keeps the HTTP+SSE event stream open, publishing the endpoint it announces.
+    async fn listen_for_sse_messages(self, mut endpoint_tx: watch::Sender<Option<Url>>) {
+        let mut delay = MIN_RECONNECT_DELAY;
+        loop {
+            match self.stream_sse_messages(&mut endpoint_tx).await {
+                Ok(()) => delay = MIN_RECONNECT_DELAY,
+                Err(error) => {
+                    self.report(format!("event stream failed: {error:#}"));
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+            // A new connection announces a new endpoint, so hold messages until then.
+            *endpoint_tx.borrow_mut() = None;
+            if self.messages_tx.is_closed() {
+                return;
+            }
+            self.executor.timer(delay).await;
+        }
+    }
+
+    async fn stream_sse_messages(
+        &self,
+        endpoint_tx: &mut watch::Sender<Option<Url>>,
+    ) -> Result<()> {
+        let request = self
+            .request(Method::GET, &self.url)
+            .header(header::ACCEPT, EVENT_STREAM_CONTENT_TYPE)
+            .body(AsyncBody::empty())?;
+        let mut response = self.http_client.send(request).await?;
+        check_status(&mut response).await?;
+
+        read_events(response.into_body(), |event| match event.event.as_deref() {
+            Some("endpoint") => {
+                let endpoint = self
+                    .url
+                    .join(&event.data)
+                    .with_context(|| format!("invalid endpoint {:?}", event.data))?;
+                *endpoint_tx.borrow_mut() = Some(endpoint);
+                Ok(())
+            }
+            None | Some("message") => self.forward(event.data),
+            Some(_) => Ok(()),
+        })
+        .await
+    }
+
+    fn forward(&self, message: String) -> Result<()> {
+        self.messages_tx
+            .try_send(message)
+            .context("context server client was dropped")
+    }
+
+    fn report(&self, error: String) {
+        self.errors_tx.try_send(error).ok();
+    }
+}
+
+impl SseParser {
+    /// Consumes a line of the response, returning the event it completes, if any.
+    fn push_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            let event = self.event.take();
+            return self.data.take().map(|data| SseEvent {
+                event,
+                data,
+                id: self.last_event_id.clone(),
+            });
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match self.data.as_mut() {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            "id" => self.last_event_id = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Reads a `text/event-stream` body until it ends, passing each event to the given callback.
+async fn read_events(
+    body: AsyncBody,
+    mut on_event: impl FnMut(SseEvent) -> Result<()>,
+) -> Result<()> {
+    let mut lines = BufReader::new(body).lines();
+    let mut parser = SseParser::default();
+    while let Some(line) = lines.next().await {
+        if let Some(event) = parser.push_line(&line?) {
+            on_event(event)?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits a JSON response, which can hold a single message or a batch of them.
+fn split_json_messages(body: &str) -> Result<Vec<String>> {
+    Ok(match serde_json::from_str(body)? {
+        serde_json::Value::Array(messages) => {
+            messages.iter().map(|message| message.to_string()).collect()
+        }
+        message => vec![message.to_string()],
+    })
+}
+
+fn content_type(response: &Response<AsyncBody>) -> Option<String> {
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)?
+        .to_str()
+        .ok()?;
+    let mime_type = content_type.split(';').next().unwrap_or_default();
+    Some(mime_type.trim().to_ascii_lowercase())
+}
+
+async fn check_status(response: &mut Response<AsyncBody>) -> Result<()> {
+    if response.status().is_success() {
+        return Ok(());
+    }
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await.ok();
+    Err(anyhow!(
+        "context server responded with {}: {}",
+        response.status(),
+        body.trim()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{stream, TryStreamExt};
+    use gpui::TestAppContext;
+    use http_client::FakeHttpClient;
+
+    #[test]
+    fn test_sse_parser() {
+        let mut parser = SseParser::default();
+        let events = [
+            ": keep-alive",
+            "event: endpoint",
+            "data: /messages?session=1",
+            "",
+            "",
+            "id: 7",
+            "data:{\"first\":",
+            "data: \"line\"}",
+            "retry: 1000",
+            "",
+            "data",
+            "",
+        ]
+        .into_iter()
+        .filter_map(|line| parser.push_line(line))
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            [
+                SseEvent {
+                    event: Some("endpoint".into()),
+                    data: "/messages?session=1".into(),
+                    id: None,
+                },
+                SseEvent {
+                    event: None,
+                    data: "{\"first\":\n\"line\"}".into(),
+                    id: Some("7".into()),
+                },
+                SseEvent {
+                    event: None,
+                    data: "".into(),
+                    id: Some("7".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_json_messages() {
+        assert_eq!(
+            split_json_messages(r#"{"jsonrpc":"2.0","id":1,"result":{}}"#).unwrap(),
+            [r#"{"id":1,"jsonrpc":"2.0","result":{}}"#]
+        );
+        assert_eq!(
+            split_json_messages(r#"[{"id":1},{"id":2}]"#).unwrap(),
+            [r#"{"id":1}"#, r#"{"id":2}"#]
+        );
+        assert!(split_json_messages("not json").is_err());
+    }
+
+    #[gpui::test]
+    async fn test_streamable_http_transport(cx: &mut TestAppContext) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let http_client = FakeHttpClient::create({
+            let requests = requests.clone();
+            move |mut request| {
+                let requests = requests.clone();
+                async move {
+                    let mut body = String::new();
+                    request.body_mut().read_to_string(&mut body).await?;
+                    let session_id = request
+                        .headers()
+                        .get(SESSION_ID_HEADER)
+                        .map(|value| value.to_str().unwrap().to_string());
+                    let authorization = request
+                        .headers()
+                        .get(header::AUTHORIZATION)
+                        .map(|value| value.to_str().unwrap().to_string());
+                    requests.lock().push((
+                        request.method().clone(),
+                        session_id,
+                        authorization,
+                        body.clone(),
+                    ));
+
+                    let response = Response::builder();
+                    if request.method() == Method::GET {
+                        return anyhow::Ok(response.status(405).body(AsyncBody::empty())?);
+                    }
+                    anyhow::Ok(match body.as_str() {
+                        r#"{"id":0}"# => response
+                            .header(SESSION_ID_HEADER, "session-1")
+                            .header(header::CONTENT_TYPE, "application/json")
+                            .body(AsyncBody::from(r#"{"id":0,"result":{}}"#.to_string()))?,
+                        r#"{"id":1}"# => response
+                            .header(header::CONTENT_TYPE, "text/event-stream; charset=utf-8")
+                            .body(AsyncBody::from(
+                                "data: {\"method\":\"progress\"}\n\nid: 1\ndata: {\"id\":1}\n\n"
+                                    .to_string(),
+                            ))?,
+                        _ => response.status(202).body(AsyncBody::empty())?,
+                    })
+                }
+            }
+        });
+
+        let transport = HttpTransport::new(
+            "http://localhost:3000/mcp".parse().unwrap(),
+            HttpTransportKind::StreamableHttp,
+            HashMap::from_iter([("Authorization".to_string(), "Bearer token".to_string())]),
+            http_client,
+            &cx.to_async(),
+        );
+        let mut messages = transport.receive();
+
+        transport.send(r#"{"id":0}"#.into()).await.unwrap();
+        assert_eq!(messages.next().await.unwrap(), r#"{"id":0,"result":{}}"#);
+        transport
+            .send(r#"{"method":"ready"}"#.into())
+            .await
+            .unwrap();
+        transport.send(r#"{"id":1}"#.into()).await.unwrap();
+        assert_eq!(messages.next().await.unwrap(), r#"{"method":"progress"}"#);
+        assert_eq!(messages.next().await.unwrap(), r#"{"id":1}"#);
+        cx.run_until_parked();
+
+        let bearer = Some("Bearer token".to_string());
+        let session = Some("session-1".to_string());
+        let mut requests = requests.lock().clone();
+        // The event stream for server-initiated messages is opened concurrently.
+        let get_ix = requests
+            .iter()
+            .position(|(method, ..)| method == Method::GET)
+            .unwrap();
+        assert_eq!(
+            requests.remove(get_ix),
+            (Method::GET, session.clone(), bearer.clone(), String::new())
+        );
+        assert_eq!(
+            requests,
+            [
+                (
+                    Method::POST,
+                    None,
+                    bearer.clone(),
+                    r#"{"id":0}"#.to_string()
+                ),
+                (
+                    Method::POST,
+                    session.clone(),
+                    bearer.clone(),
+                    r#"{"method":"ready"}"#.to_string()
+                ),
+                (Method::POST, session, bearer, r#"{"id":1}"#.to_string()),
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_sse_transport(cx: &mut TestAppContext) {
+        let posted = Arc::new(Mutex::new(Vec::new()));
+        let http_client = FakeHttpClient::create({
+            let posted = posted.clone();
+            move |mut request| {
+                let posted = posted.clone();
+                async move {
+                    if request.method() == Method::GET {
+                        // The event stream stays open after announcing the endpoint.
+                        let events = "event: endpoint\ndata: /messages?session=1\n\n\
+                                      event: message\ndata: {\"id\":0}\n\n";
+                        let body =
+                            stream::iter([Ok::<_, std::io::Error>(events.as_bytes().to_vec())])
+                                .chain(stream::pending())
+                                .into_async_read();
+                        return anyhow::Ok(Response::builder().body(AsyncBody::from_reader(body))?);
+                    }
+
+                    let mut body = String::new();
+                    request.body_mut().read_to_string(&mut body).await?;
+                    posted.lock().push((request.uri().to_string(), body));
+                    anyhow::Ok(Response::builder().status(202).body(AsyncBody::empty())?)
+                }
+            }
+        });
+
+        let transport = HttpTransport::new(
+            "http://localhost:3000/sse".parse().unwrap(),
+            HttpTransportKind::Sse,
+            HashMap::default(),
+            http_client,
+            &cx.to_async(),
+        );
+        let mut messages = transport.receive();
+
+        transport.send(r#"{"id":0}"#.into()).await.unwrap();
+        assert_eq!(messages.next().await.unwrap(), r#"{"id":0}"#);
+        assert_eq!(
+            posted.lock().as_slice(),
+            [(
+                "http://localhost:3000/messages?session=1".to_string(),
+                r#"{"id":0}"#.to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::{Context as _, Result};
+use futures::{
+    future::BoxFuture, io::BufWriter, stream::BoxStream, AsyncRead, AsyncWrite, FutureExt,
+    StreamExt,
+};
+use gpui::{AsyncAppContext, Task};
+use parking_lot::Mutex;
+use smol::{
+    channel,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{self, Child},
+};
+use util::TryFutureExt;
+
+use super::Transport;
+use crate::client::ModelContextServerBinary;
+
+/// Talks to a context server running as a child process, one message per line of its stdio.
+pub struct StdioTransport {
+    stdin_tx: channel::Sender<String>,
+    stdout_rx: channel::Receiver<String>,
+    stderr_rx: channel::Receiver<String>,
+    server: Mutex<Option<Child>>,
+    _io_tasks: [Task<Option<()>>; 3],
+}
+
+impl StdioTransport {
+    pub fn new(binary: ModelContextServerBinary, cx: &AsyncAppContext) -> Result<Self> {
+        let mut command = process::Command::new(&binary.executable);
+        command
+            .args(&binary.args)
+            .envs(binary.env.unwrap_or_default())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut server = command.spawn().with_context(|| {
+            format!(
+                "failed to spawn command. (path={:?}, args={:?})",
+                binary.executable, &binary.args
+            )
+        })?;
+
+        let stdin = server.stdin.take().unwrap();
+        let stdout = server.stdout.take().unwrap();
+        let stderr = server.stderr.take().unwrap();
+
+        let (stdin_tx, stdin_rx) = channel::unbounded::<String>();
+        let (stdout_tx, stdout_rx) = channel::unbounded::<String>();
+        let (stderr_tx, stderr_rx) = channel::unbounded::<String>();
+
+        let executor = cx.background_executor();
+        let io_tasks = [
+            executor.spawn(Self::write_lines(stdin, stdin_rx).log_err()),
+            executor.spawn(Self::read_lines(stdout, stdout_tx).log_err()),
+            executor.spawn(Self::read_lines(stderr, stderr_tx).log_err()),
+        ];
+
+        Ok(Self {
+            stdin_tx,
+            stdout_rx,
+            stderr_rx,
+            server: Mutex::new(Some(server)),
+            _io_tasks: io_tasks,
+        })
+    }
+
+    /// Forwards the non-empty lines of the given output until it is closed.
+    async fn read_lines<Output>(output: Output, lines_tx: channel::Sender<String>) -> Result<()>
+    where
+        Output: AsyncRead + Unpin + Send + 'static,
+    {
+        let mut output = BufReader::new(output);
+        let mut buffer = String::new();
+
+        loop {
+            buffer.clear();
+            if output.read_line(&mut buffer).await? == 0 {
+                return Ok(());
+            }
+
+            let line = buffer.trim();
+            if !line.is_empty() && lines_tx.send(line.to_string()).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Writes each received message to the given input, followed by a newline.
+    async fn write_lines<Input>(input: Input, lines_rx: channel::Receiver<String>) -> Result<()>
+    where
+        Input: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut input = BufWriter::new(input);
+        while let Ok(message) = lines_rx.recv().await {
+            input.write_all(message.as_bytes()).await?;
+            input.write_all(b"\n").await?;
+            input.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+impl Transport for StdioTransport {
+    fn send(&self, message: String) -> BoxFuture<'static, Result<()>> {
+        let stdin_tx = self.stdin_tx.clone();
+        async move {
+            stdin_tx
+                .send(message)
+                .await
+                .context("failed to write to context server's stdin")
+        }
+        .boxed()
+    }
+
+    fn receive(&self) -> BoxStream<'static, String> {
+        self.stdout_rx.clone().boxed()
+    }
+
+    fn receive_err(&self) -> BoxStream<'static, String> {
+        self.stderr_rx.clone().boxed()
+    }
+}
+
+impl Drop for StdioTransport {
+    fn drop(&mut self) {
+        if let Some(mut server) = self.server.lock().take() {
+            let _ = server.kill();
+        }
+    }
+}
@@ -44,7 +44,7 @@ use util::{paths::PathExt, ResultExt, TryFutureExt};
 use workspace::item::{BreadcrumbText, FollowEvent};
 use workspace::{
     item::{FollowableItem, Item, ItemEvent, ProjectItem},
-    searchable::{Direction, SearchEvent, SearchableItem, SearchableItemHandle},
+    searchable::{Direction, SearchEvent, SearchOptions, SearchableItem, SearchableItemHandle},
     ItemId, ItemNavHistory, Pane, ToolbarItemLocation, ViewId, Workspace, WorkspaceId,
 };
 
@@ -1184,6 +1184,17 @@ pub(crate) enum BufferSearchHighlights {}
 impl SearchableItem for Editor {
     type Match = Range<Anchor>;
 
+    fn supported_options() -> SearchOptions {
+        SearchOptions {
+            case: true,
+            word: true,
+            regex: true,
+            structural: true,
+            replacement: true,
+            selection: true,
+        }
+    }
+
     fn get_matches(&self, _: &mut WindowContext) -> Vec<Range<Anchor>> {
         self.background_highlights
             .get(&TypeId::of::<BufferSearchHighlights>())
@@ -1300,6 +1311,14 @@ impl SearchableItem for Editor {
     ) {
         let text = self.buffer.read(cx);
         let text = text.snapshot(cx);
+        if query.replacement_needs_context() {
+            if let Some(replacement) = contextual_replacement_for(&text, identifier, query) {
+                self.transact(cx, |this, cx| {
+                    this.edit([(identifier.clone(), replacement)], cx);
+                });
+            }
+            return;
+        }
         let text = text.text_for_range(identifier.clone()).collect::<Vec<_>>();
         let text: Cow<_> = if text.len() == 1 {
             text.first().cloned().unwrap().into()
@@ -1324,6 +1343,12 @@ impl SearchableItem for Editor {
         let text = text.snapshot(cx);
         let mut edits = vec![];
         for m in matches {
+            if query.replacement_needs_context() {
+                if let Some(replacement) = contextual_replacement_for(&text, m, query) {
+                    edits.push((m.clone(), replacement));
+                }
+                continue;
+            }
             let text = text.text_for_range(m.clone()).collect::<Vec<_>>();
             let text: Cow<_> = if text.len() == 1 {
                 text.first().cloned().unwrap().into()
@@ -1482,6 +1507,17 @@ impl SearchableItem for Editor {
     }
 }
 
+/// Computes replacements that depend on more than the text of the match, such as its syntax
+/// tree or the text around it.
+fn contextual_replacement_for(
+    snapshot: &MultiBufferSnapshot,
+    range: &Range<Anchor>,
+    query: &SearchQuery,
+) -> Option<Arc<str>> {
+    let (_, buffer, range) = snapshot.excerpts_in_ranges([range.clone()]).next()?;
+    query.replacement_in_buffer(buffer, range).map(Arc::from)
+}
+
 pub fn active_match_index(
     ranges: &[Range<Anchor>],
     cursor: &Anchor,
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// The capabilities a Wasm extension requires, declared in the
+/// `[capabilities]` section of its manifest and approved by the user when it
+/// is installed.
+///
+/// The approved capabilities are stored in the `extension_capabilities`
+/// setting, and extensions without an entry there have none.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExtensionCapabilities {
+    /// The hosts the extension may make HTTP requests to and download files
+    /// from, such as `github.com` or `*.githubusercontent.com`.
+    ///
+    /// `*` allows any host.
+    #[serde(default)]
+    pub network_hosts: Vec<String>,
+    /// Whether the extension may read files in worktrees.
+    #[serde(default)]
+    pub read_files: bool,
+    /// The executables the extension may run, such as its language servers
+    /// or `npm`, by file name.
+    ///
+    /// `*` allows any executable.
+    #[serde(default)]
+    pub process_commands: Vec<String>,
+}
+
+impl ExtensionCapabilities {
+    /// Returns the capabilities requested by extensions that don't declare
+    /// any, which predate capabilities being enforced.
+    pub fn unrestricted() -> Self {
+        Self {
+            network_hosts: vec!["*".to_string()],
+            read_files: true,
+            process_commands: vec!["*".to_string()],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.network_hosts.is_empty() && !self.read_files && self.process_commands.is_empty()
+    }
+
+    /// Checks that the extension may make requests to the given URL.
+    pub fn check_url(&self, url: &str) -> Result<(), ExtensionViolation> {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_lowercase()));
+        match host {
+            Some(host) if self.allows_host(&host) => Ok(()),
+            host => Err(ExtensionViolation::Network {
+                host: host.unwrap_or_else(|| url.to_string()),
+            }),
+        }
+    }
+
+    fn allows_host(&self, host: &str) -> bool {
+        self.network_hosts.iter().any(|pattern| {
+            let pattern = pattern.to_lowercase();
+            if pattern == "*" {
+                true
+            } else if let Some(domain) = pattern.strip_prefix("*.") {
+                host.strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+            } else {
+                host == pattern
+            }
+        })
+    }
+
+    /// Checks that the extension may read the given file in a worktree.
+    pub fn check_file_read(&self, path: &str) -> Result<(), ExtensionViolation> {
+        if self.read_files {
+            Ok(())
+        } else {
+            Err(ExtensionViolation::ReadFile {
+                path: path.to_string(),
+            })
+        }
+    }
+
+    /// Checks that the extension may run the given command.
+    pub fn check_process(&self, command: &str) -> Result<(), ExtensionViolation> {
+        let path = Path::new(command);
+        let names = [path.file_name(), path.file_stem()];
+        let allowed = self.process_commands.iter().any(|allowed| {
+            allowed == "*"
+                || names
+                    .iter()
+                    .flatten()
+                    .any(|name| name.to_str() == Some(allowed.as_str()))
+        });
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(ExtensionViolation::Process {
+                command: command.to_string(),
+            })
+        }
+    }
+
+    /// Returns descriptions of the capabilities, to show to the user.
+    pub fn descriptions(&self) -> Vec<String> {
+        let mut descriptions = Vec::new();
+        if self.network_hosts.iter().any(|host| host == "*") {
+            descriptions.push("Access the network".to_string());
+        } else if !self.network_hosts.is_empty() {
+            descriptions.push(format!(
+                "Access the network at {}",
+                self.network_hosts.join(", ")
+            ));
+        }
+        if self.read_files {
+            descriptions.push("Read files in your projects".to_string());
+        }
+        if self.process_commands.iter().any(|command| command == "*") {
+            descriptions.push("Run any program".to_string());
+        } else if !self.process_commands.is_empty() {
+            descriptions.push(format!("Run {}", self.process_commands.join(", ")));
+        }
+        descriptions
+    }
+}
+
+/// An extension exceeding its capabilities or resource limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtensionViolation {
+    Network { host: String },
+    ReadFile { path: String },
+    Process { command: String },
+    CpuLimit,
+}
+
+impl fmt::Display for ExtensionViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network { host } => write!(f, "network access to {host} was blocked"),
+            Self::ReadFile { path } => write!(f, "reading {path} was blocked"),
+            Self::Process { command } => write!(f, "running {command} was blocked"),
+            Self::CpuLimit => write!(f, "a call was stopped for exceeding the CPU limit"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_capabilities() {
+        let capabilities = ExtensionCapabilities {
+            network_hosts: vec!["github.com".into(), "*.githubusercontent.com".into()],
+            read_files: false,
+            process_commands: vec!["gopls".into()],
+        };
+
+        assert!(capabilities
+            .check_url("https://github.com/golang/tools")
+            .is_ok());
+        assert!(capabilities
+            .check_url("https://objects.githubusercontent.com/release.tar.gz")
+            .is_ok());
+        assert_eq!(
+            capabilities.check_url("https://githubusercontent.com/"),
+            Err(ExtensionViolation::Network {
+                host: "githubusercontent.com".into()
+            })
+        );
+        assert_eq!(
+            capabilities.check_url("https://evilgithub.com/"),
+            Err(ExtensionViolation::Network {
+                host: "evilgithub.com".into()
+            })
+        );
+        assert!(capabilities.check_url("not a url").is_err());
+
+        assert!(capabilities.check_file_read("go.mod").is_err());
+
+        assert!(capabilities.check_process("gopls").is_ok());
+        assert!(capabilities
+            .check_process("/home/user/.local/share/zed/extensions/work/go/gopls")
+            .is_ok());
+        assert!(capabilities.check_process("gopls.exe").is_ok());
+        assert!(capabilities.check_process("/usr/bin/curl").is_err());
+
+        let unrestricted = ExtensionCapabilities::unrestricted();
+        assert!(unrestricted.check_url("https://example.com").is_ok());
+        assert!(unrestricted.check_file_read("go.mod").is_ok());
+        assert!(unrestricted.check_process("/usr/bin/curl").is_ok());
+    }
+}
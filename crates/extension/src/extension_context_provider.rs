@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use futures::FutureExt;
+use gpui::{AppContext, Task};
+use language::{ContextProvider, LanguageName, Location};
+use parking_lot::RwLock;
+use task::{TaskTemplates, TaskVariables, VariableName};
+use util::ResultExt;
+
+use crate::wasm_host::WasmExtension;
+
+/// The Wasm extensions providing tasks, by the language they provide them for.
+///
+/// Languages are registered before Wasm extensions are loaded, so the
+/// [`ExtensionContextProvider`]s of the languages look their extensions up
+/// here whenever they are used.
+#[derive(Default)]
+pub(crate) struct ExtensionContextProviders {
+    providers: RwLock<HashMap<LanguageName, Vec<ExtensionTaskProvider>>>,
+}
+
+struct ExtensionTaskProvider {
+    extension: WasmExtension,
+    /// The templates the extension returned when it was loaded, as the
+    /// templates of a language are listed synchronously.
+    templates: TaskTemplates,
+}
+
+impl ExtensionContextProviders {
+    pub(crate) fn register(
+        &self,
+        language: LanguageName,
+        extension: WasmExtension,
+        templates: TaskTemplates,
+    ) {
+        self.providers
+            .write()
+            .entry(language)
+            .or_default()
+            .push(ExtensionTaskProvider {
+                extension,
+                templates,
+            });
+    }
+
+    pub(crate) fn remove_extension(&self, extension_id: &str) {
+        self.providers.write().retain(|_, providers| {
+            providers.retain(|provider| provider.extension.manifest.id.as_ref() != extension_id);
+            !providers.is_empty()
+        });
+    }
+}
+
+/// Provides the tasks of a language registered by an extension: those in the
+/// language's `tasks.json`, and those of the Wasm extensions providing tasks
+/// for the language.
+pub(crate) struct ExtensionContextProvider {
+    language: LanguageName,
+    templates: Option<TaskTemplates>,
+    providers: Arc<ExtensionContextProviders>,
+}
+
+impl ExtensionContextProvider {
+    pub(crate) fn new(
+        language: LanguageName,
+        templates: Option<TaskTemplates>,
+        providers: Arc<ExtensionContextProviders>,
+    ) -> Self {
+        Self {
+            language,
+            templates,
+            providers,
+        }
+    }
+}
+
+impl ContextProvider for ExtensionContextProvider {
+    fn build_context(
+        &self,
+        variables: &TaskVariables,
+        _: &Location,
+        _: Option<&HashMap<String, String>>,
+        cx: &mut AppContext,
+    ) -> Task<Result<TaskVariables>> {
+        let extensions = self
+            .providers
+            .providers
+            .read()
+            .get(&self.language)
+            .into_iter()
+            .flatten()
+            .map(|provider| provider.extension.clone())
+            .collect::<Vec<_>>();
+        if extensions.is_empty() {
+            return Task::ready(Ok(TaskVariables::default()));
+        }
+
+        let language = self.language.clone();
+        let variables = variables
+            .clone()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            let mut task_variables = TaskVariables::default();
+            for extension in extensions {
+                let extension_variables = extension
+                    .call({
+                        let language = language.clone();
+                        let variables = variables.clone();
+                        |extension, store| {
+                            async move {
+                                extension
+                                    .call_task_context_variables(store, &language, &variables)
+                                    .await?
+                                    .map_err(|err| anyhow!("{err:?}"))
+                            }
+                            .boxed()
+                        }
+                    })
+                    .await;
+
+                // An extension failing to provide its variables shouldn't
+                // prevent tasks from being spawned.
+                for (name, value) in extension_variables.log_err().into_iter().flatten() {
+                    task_variables.insert(VariableName::Custom(name.into()), value);
+                }
+            }
+            Ok(task_variables)
+        })
+    }
+
+    fn associated_tasks(
+        &self,
+        _: Option<Arc<dyn language::File>>,
+        _: &AppContext,
+    ) -> Option<TaskTemplates> {
+        let providers = self.providers.providers.read();
+        let templates = self
+            .templates
+            .iter()
+            .chain(
+                providers
+                    .get(&self.language)
+                    .into_iter()
+                    .flatten()
+                    .map(|provider| &provider.templates),
+            )
+            .flat_map(|templates| templates.0.iter().cloned())
+            .collect::<Vec<_>>();
+        (!templates.is_empty()).then_some(TaskTemplates(templates))
+    }
+}
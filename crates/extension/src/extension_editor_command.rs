@@ -0,0 +1,139 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use gpui::impl_actions;
+use language::{LanguageName, LanguageServerName};
+use serde::Deserialize;
+
+use crate::wit;
+
+/// Runs an editor command provided by an extension on the active editor.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct RunEditorCommand {
+    pub extension_id: Arc<str>,
+    pub command_name: Arc<str>,
+}
+
+/// Sends a custom request or notification provided by an extension to its
+/// language server for the active editor.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct RunLanguageServerRequest {
+    pub extension_id: Arc<str>,
+    pub language_server_id: LanguageServerName,
+    pub request_name: Arc<str>,
+}
+
+impl_actions!(zed, [RunEditorCommand, RunLanguageServerRequest]);
+
+/// The buffer of the editor an extension's editor command is run in.
+#[derive(Debug, Clone)]
+pub struct EditorCommandBuffer {
+    pub text: String,
+    /// The byte ranges of the editor's selections.
+    pub selections: Vec<Range<usize>>,
+    pub path: Option<String>,
+    pub language_name: Option<LanguageName>,
+}
+
+/// The result of running an extension's editor command.
+#[derive(Debug, Default, PartialEq)]
+pub struct EditorCommandOutput {
+    /// The edits to apply to the buffer, sorted by the byte ranges they
+    /// replace in the text the command was run on.
+    pub edits: Vec<(Range<usize>, String)>,
+    pub message: Option<String>,
+    pub content: Option<EditorCommandContent>,
+}
+
+/// Text returned by an extension's editor command, to open in a new editor.
+#[derive(Debug, PartialEq)]
+pub struct EditorCommandContent {
+    pub title: String,
+    pub text: String,
+    pub language_name: Option<LanguageName>,
+}
+
+impl EditorCommandOutput {
+    /// Converts the output returned by an extension, checking that its edits
+    /// apply to the text the command was run on.
+    pub(crate) fn from_wit(text: &str, output: wit::EditorCommandOutput) -> Result<Self> {
+        let mut edits = output
+            .edits
+            .into_iter()
+            .map(|edit| {
+                (
+                    edit.range.start as usize..edit.range.end as usize,
+                    edit.new_text,
+                )
+            })
+            .collect::<Vec<_>>();
+        edits.sort_by_key(|(range, _)| (range.start, range.end));
+
+        let mut previous_end = 0;
+        for (range, _) in &edits {
+            if range.start > range.end
+                || range.end > text.len()
+                || !text.is_char_boundary(range.start)
+                || !text.is_char_boundary(range.end)
+            {
+                return Err(anyhow!("invalid edit range {range:?}"));
+            }
+            if range.start < previous_end {
+                return Err(anyhow!("overlapping edit range {range:?}"));
+            }
+            previous_end = range.end;
+        }
+
+        Ok(Self {
+            edits,
+            message: output.message,
+            content: output.content.map(|content| EditorCommandContent {
+                title: content.title,
+                text: content.text,
+                language_name: content.language_name.as_deref().map(LanguageName::new),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(edits: &[(Range<u32>, &str)]) -> wit::EditorCommandOutput {
+        wit::EditorCommandOutput {
+            edits: edits
+                .iter()
+                .map(|(range, new_text)| wit::BufferEdit {
+                    range: wit::Range {
+                        start: range.start,
+                        end: range.end,
+                    },
+                    new_text: new_text.to_string(),
+                })
+                .collect(),
+            message: None,
+            content: None,
+        }
+    }
+
+    #[test]
+    fn test_editor_command_output_edits() {
+        let text = "{\"b\": 1, \"a\": 2}";
+
+        assert_eq!(
+            EditorCommandOutput::from_wit(text, output(&[(9..15, "\"b\": 1"), (1..7, "\"a\": 2")]))
+                .unwrap()
+                .edits,
+            vec![
+                (1..7, "\"a\": 2".to_string()),
+                (9..15, "\"b\": 1".to_string())
+            ]
+        );
+
+        assert!(EditorCommandOutput::from_wit(text, output(&[(0..17, "")])).is_err());
+        assert!(EditorCommandOutput::from_wit(text, output(&[(3..5, ""), (4..6, "")])).is_err());
+        assert!(EditorCommandOutput::from_wit("é", output(&[(1..2, "")])).is_err());
+    }
+}
@@ -55,6 +55,7 @@ impl LspAdapter for ExtensionLspAdapter {
                                 )
                                 .await?
                                 .map_err(|e| anyhow!("{}", e))?;
+                            store.data().check_process(&command.command)?;
                             anyhow::Ok(command)
                         }
                         .boxed()
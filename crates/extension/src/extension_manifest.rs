@@ -1,3 +1,4 @@
+use crate::ExtensionCapabilities;
 use anyhow::{anyhow, Context, Result};
 use collections::{BTreeMap, HashMap};
 use fs::Fs;
@@ -79,7 +80,15 @@ pub struct ExtensionManifest {
     #[serde(default)]
     pub indexed_docs_providers: BTreeMap<Arc<str>, IndexedDocsProviderEntry>,
     #[serde(default)]
+    pub context_providers: BTreeMap<LanguageName, ContextProviderManifestEntry>,
+    #[serde(default)]
+    pub editor_commands: BTreeMap<Arc<str>, EditorCommandManifestEntry>,
+    #[serde(default)]
     pub snippets: Option<PathBuf>,
+    /// The capabilities the extension requires, which the user is asked to
+    /// approve before the extension is granted them.
+    #[serde(default)]
+    pub capabilities: Option<ExtensionCapabilities>,
 }
 
 #[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
@@ -114,6 +123,10 @@ pub struct LanguageServerManifestEntry {
     pub language_ids: HashMap<String, String>,
     #[serde(default)]
     pub code_action_kinds: Option<Vec<lsp::CodeActionKind>>,
+    /// The custom requests and notifications the extension can send to the
+    /// language server, which are offered as commands in editors it runs in.
+    #[serde(default)]
+    pub requests: BTreeMap<Arc<str>, LanguageServerRequestManifestEntry>,
 }
 
 impl LanguageServerManifestEntry {
@@ -143,7 +156,37 @@ pub struct SlashCommandManifestEntry {
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 pub struct IndexedDocsProviderEntry {}
 
+/// Declares that the extension provides task templates and task variables for a language.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct ContextProviderManifestEntry {}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct LanguageServerRequestManifestEntry {
+    /// The name to show the request's command under in the command palette.
+    pub description: String,
+    /// The LSP method of the request, such as `rust-analyzer/expandMacro`.
+    pub method: String,
+    /// Whether to send a notification, which has no response, rather than a
+    /// request.
+    #[serde(default)]
+    pub notification: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct EditorCommandManifestEntry {
+    /// The name to show the command under in the command palette.
+    pub description: String,
+}
+
 impl ExtensionManifest {
+    /// Returns the capabilities the user is asked to approve for the
+    /// extension, which are unrestricted if it doesn't declare any.
+    pub fn requested_capabilities(&self) -> ExtensionCapabilities {
+        self.capabilities
+            .clone()
+            .unwrap_or_else(ExtensionCapabilities::unrestricted)
+    }
+
     pub async fn load(fs: Arc<dyn Fs>, extension_dir: &Path) -> Result<Self> {
         let extension_name = extension_dir
             .file_name()
@@ -207,6 +250,9 @@ fn manifest_from_old_manifest(
         language_servers: Default::default(),
         slash_commands: BTreeMap::default(),
         indexed_docs_providers: BTreeMap::default(),
+        context_providers: BTreeMap::default(),
+        editor_commands: BTreeMap::default(),
         snippets: None,
+        capabilities: None,
     }
 }
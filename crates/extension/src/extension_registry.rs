@@ -0,0 +1,377 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use client::ExtensionMetadata;
+use fs::Fs;
+use futures::AsyncReadExt as _;
+use http_client::{AsyncBody, HttpClient};
+use release_channel::ReleaseChannel;
+use semantic_version::SemanticVersion;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::is_version_compatible;
+
+/// The name of the index file at the root of a static extension registry.
+pub const REGISTRY_INDEX_FILE_NAME: &str = "extensions.json";
+
+/// A registry to fetch extensions from instead of Zed's extensions API, such
+/// as a mirror created with `zed-extension mirror`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtensionRegistry {
+    /// A registry in a local directory.
+    Local(PathBuf),
+    /// A registry served over HTTP.
+    Remote(Url),
+}
+
+impl ExtensionRegistry {
+    /// Parses the registry location from the `extension_registry` setting,
+    /// which is either a URL or an absolute path.
+    pub fn parse(location: &str) -> Result<Self> {
+        match Url::parse(location) {
+            Ok(url) if url.scheme() == "file" => url
+                .to_file_path()
+                .map(Self::Local)
+                .map_err(|_| anyhow!("invalid extension registry path {location:?}")),
+            Ok(mut url) if matches!(url.scheme(), "http" | "https") => {
+                // Make sure the registry's files are resolved relative to
+                // the registry, rather than to its parent.
+                if !url.path().ends_with('/') {
+                    url.set_path(&format!("{}/", url.path()));
+                }
+                Ok(Self::Remote(url))
+            }
+            _ if PathBuf::from(location).is_absolute() => Ok(Self::Local(location.into())),
+            _ => Err(anyhow!("invalid extension registry {location:?}")),
+        }
+    }
+
+    /// Returns the registry's location, which identifies it.
+    pub fn location(&self) -> String {
+        match self {
+            Self::Local(dir) => dir.to_string_lossy().into_owned(),
+            Self::Remote(url) => url.to_string(),
+        }
+    }
+
+    /// Returns the URL of a file in the registry.
+    pub fn url(&self, path: &str) -> Result<Url> {
+        let root = match self {
+            Self::Local(dir) => Url::from_directory_path(dir)
+                .map_err(|_| anyhow!("invalid extension registry path {dir:?}"))?,
+            Self::Remote(url) => url.clone(),
+        };
+        Ok(root.join(path)?)
+    }
+
+    /// Fetches the registry's index.
+    pub(crate) async fn fetch_index(
+        &self,
+        fs: &dyn Fs,
+        http_client: &dyn HttpClient,
+    ) -> Result<RegistryIndex> {
+        let url = self.url(REGISTRY_INDEX_FILE_NAME)?;
+        let bytes = download(&url, fs, http_client).await?;
+        serde_json::from_slice(&bytes)
+            .with_context(|| format!("error parsing extension registry index {url}"))
+    }
+}
+
+/// Downloads the file at the given URL, which may be a `file://` URL.
+pub async fn download(url: &Url, fs: &dyn Fs, http_client: &dyn HttpClient) -> Result<Vec<u8>> {
+    if url.scheme() == "file" {
+        let path = url
+            .to_file_path()
+            .map_err(|_| anyhow!("invalid file URL {url}"))?;
+        return fs.load_bytes(&path).await;
+    }
+
+    let mut response = http_client
+        .get(url.as_str(), AsyncBody::empty(), true)
+        .await
+        .with_context(|| format!("error downloading {url}"))?;
+
+    let content_length = response
+        .headers()
+        .get(http_client::http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+
+    let mut body = Vec::new();
+    response
+        .body_mut()
+        .read_to_end(&mut body)
+        .await
+        .with_context(|| format!("error reading {url}"))?;
+
+    if !response.status().is_success() {
+        bail!(
+            "status error {} downloading {url}: {:?}",
+            response.status().as_u16(),
+            String::from_utf8_lossy(&body)
+        );
+    }
+
+    if let Some(content_length) = content_length {
+        let actual_len = body.len();
+        if content_length != actual_len {
+            bail!("downloaded size {actual_len} does not match content length {content_length}");
+        }
+    }
+
+    Ok(body)
+}
+
+/// The index of a static extension registry.
+///
+/// This has the same format as the responses of Zed's extensions API, with
+/// the checksums of the extensions' archives added.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    pub data: Vec<RegistryExtension>,
+}
+
+/// A version of an extension in a static extension registry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryExtension {
+    #[serde(flatten)]
+    pub metadata: ExtensionMetadata,
+    /// The SHA-256 checksum of the extension's archive, in hexadecimal.
+    pub sha256: String,
+}
+
+impl RegistryExtension {
+    /// Returns the path of the extension's archive, relative to the root of the registry.
+    pub fn archive_path(&self) -> String {
+        archive_path(&self.metadata.id, &self.metadata.manifest.version)
+    }
+}
+
+impl RegistryIndex {
+    /// Adds a version of an extension to the index, replacing any existing
+    /// entry for the same version.
+    pub fn insert(&mut self, extension: RegistryExtension) {
+        self.data.retain(|existing| {
+            existing.metadata.id != extension.metadata.id
+                || existing.metadata.manifest.version != extension.metadata.manifest.version
+        });
+        self.data.push(extension);
+        self.data.sort_by(|a, b| {
+            a.metadata
+                .id
+                .cmp(&b.metadata.id)
+                .then_with(|| version(b).cmp(&version(a)))
+        });
+    }
+
+    /// Returns the given version of an extension.
+    pub fn get(&self, extension_id: &str, version: &str) -> Option<&RegistryExtension> {
+        self.data.iter().find(|extension| {
+            extension.metadata.id.as_ref() == extension_id
+                && extension.metadata.manifest.version.as_ref() == version
+        })
+    }
+
+    /// Returns all versions of an extension, latest first.
+    pub fn versions(&self, extension_id: &str) -> Vec<&RegistryExtension> {
+        let mut versions = self
+            .data
+            .iter()
+            .filter(|extension| extension.metadata.id.as_ref() == extension_id)
+            .collect::<Vec<_>>();
+        versions.sort_by_key(|extension| std::cmp::Reverse(version(extension)));
+        versions
+    }
+
+    /// Returns the latest version of each extension that matches the given
+    /// predicate and is compatible with this version of Zed.
+    pub fn latest_compatible_versions(
+        &self,
+        release_channel: ReleaseChannel,
+        predicate: impl Fn(&ExtensionMetadata) -> bool,
+    ) -> Vec<&RegistryExtension> {
+        let mut latest_versions: Vec<&RegistryExtension> = Vec::new();
+        for extension in &self.data {
+            if !predicate(&extension.metadata)
+                || !is_version_compatible(release_channel, &extension.metadata)
+            {
+                continue;
+            }
+
+            match latest_versions
+                .iter_mut()
+                .find(|latest| latest.metadata.id == extension.metadata.id)
+            {
+                Some(latest) => {
+                    if version(extension) > version(latest) {
+                        *latest = extension;
+                    }
+                }
+                None => latest_versions.push(extension),
+            }
+        }
+
+        latest_versions.sort_by(|a, b| {
+            b.metadata
+                .download_count
+                .cmp(&a.metadata.download_count)
+                .then_with(|| a.metadata.id.cmp(&b.metadata.id))
+        });
+        latest_versions
+    }
+}
+
+/// Returns whether an extension matches a search of the extensions in a registry.
+pub fn matches_search(extension: &ExtensionMetadata, search: &str) -> bool {
+    let search = search.to_lowercase();
+    [
+        Some(extension.id.as_ref()),
+        Some(extension.manifest.name.as_str()),
+        extension.manifest.description.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|text| text.to_lowercase().contains(&search))
+}
+
+/// Returns the path of the archive for a version of an extension, relative to
+/// the root of a static extension registry.
+pub fn archive_path(extension_id: &str, version: &str) -> String {
+    format!("{extension_id}/{version}/archive.tar.gz")
+}
+
+/// Returns the SHA-256 checksum of an extension archive, in hexadecimal.
+pub fn archive_checksum(archive: &[u8]) -> String {
+    hex::encode(Sha256::digest(archive))
+}
+
+/// Checks that an extension archive has the expected SHA-256 checksum.
+pub fn verify_archive_checksum(archive: &[u8], expected_sha256: &str) -> Result<()> {
+    let actual_sha256 = archive_checksum(archive);
+    if !actual_sha256.eq_ignore_ascii_case(expected_sha256) {
+        bail!("extension archive checksum {actual_sha256} does not match expected checksum {expected_sha256}");
+    }
+    Ok(())
+}
+
+fn version(extension: &RegistryExtension) -> Option<SemanticVersion> {
+    SemanticVersion::from_str(&extension.metadata.manifest.version).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn extension(id: &str, version: &str, wasm_api_version: Option<&str>) -> RegistryExtension {
+        serde_json::from_value(json!({
+            "id": id,
+            "name": id,
+            "version": version,
+            "description": format!("The {id} extension"),
+            "authors": [],
+            "repository": format!("https://example.com/{id}"),
+            "schema_version": 1,
+            "wasm_api_version": wasm_api_version,
+            "published_at": "2024-01-01T00:00:00Z",
+            "download_count": 0,
+            "sha256": archive_checksum(id.as_bytes()),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_registry_index_versions() {
+        let mut index = RegistryIndex::default();
+        index.insert(extension("toml", "0.1.0", None));
+        index.insert(extension("toml", "0.10.0", Some("0.1.0")));
+        index.insert(extension("toml", "0.2.0", None));
+        index.insert(extension("zig", "1.0.0", Some("99.0.0")));
+        index.insert(extension("zig", "0.9.0", Some("0.1.0")));
+        index.insert(extension("toml", "0.2.0", Some("0.0.6")));
+
+        let versions = |extensions: Vec<&RegistryExtension>| {
+            extensions
+                .into_iter()
+                .map(|extension| {
+                    format!(
+                        "{} {}",
+                        extension.metadata.id, extension.metadata.manifest.version
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            versions(index.versions("toml")),
+            ["toml 0.10.0", "toml 0.2.0", "toml 0.1.0"]
+        );
+        assert_eq!(
+            index
+                .get("toml", "0.2.0")
+                .unwrap()
+                .metadata
+                .manifest
+                .wasm_api_version,
+            Some("0.0.6".to_string())
+        );
+
+        // Extensions requiring a newer extension API than Zed supports are skipped.
+        assert_eq!(
+            versions(index.latest_compatible_versions(ReleaseChannel::Stable, |_| true)),
+            ["toml 0.10.0", "zig 0.9.0"]
+        );
+        assert_eq!(
+            versions(
+                index.latest_compatible_versions(ReleaseChannel::Stable, |extension| {
+                    matches_search(extension, "ZIG")
+                })
+            ),
+            ["zig 0.9.0"]
+        );
+    }
+
+    #[test]
+    fn test_verify_archive_checksum() {
+        let checksum = archive_checksum(b"archive");
+        assert_eq!(
+            checksum,
+            "0eb3e36bfb24dcd9bb1d1bece1531216b59539a8fde17ee80224af0653c92aa3"
+        );
+        assert!(verify_archive_checksum(b"archive", &checksum).is_ok());
+        assert!(verify_archive_checksum(b"archive", &checksum.to_uppercase()).is_ok());
+        assert!(verify_archive_checksum(b"tampered archive", &checksum).is_err());
+    }
+
+    #[test]
+    fn test_parse_extension_registry() {
+        assert_eq!(
+            ExtensionRegistry::parse("https://example.com/zed/extensions").unwrap(),
+            ExtensionRegistry::Remote(Url::parse("https://example.com/zed/extensions/").unwrap())
+        );
+        assert_eq!(
+            ExtensionRegistry::parse("https://example.com/zed/extensions")
+                .unwrap()
+                .url(&archive_path("toml", "0.1.0"))
+                .unwrap()
+                .as_str(),
+            "https://example.com/zed/extensions/toml/0.1.0/archive.tar.gz"
+        );
+        assert!(ExtensionRegistry::parse("extensions").is_err());
+        assert!(ExtensionRegistry::parse("ftp://example.com/extensions").is_err());
+
+        #[cfg(not(windows))]
+        {
+            assert_eq!(
+                ExtensionRegistry::parse("file:///opt/zed/extensions").unwrap(),
+                ExtensionRegistry::Local("/opt/zed/extensions".into())
+            );
+            assert_eq!(
+                ExtensionRegistry::parse("/opt/zed/extensions").unwrap(),
+                ExtensionRegistry::Local("/opt/zed/extensions".into())
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{ExtensionCapabilities, ExtensionManifest};
 use anyhow::Result;
 use collections::HashMap;
 use gpui::AppContext;
@@ -16,6 +17,20 @@ pub struct ExtensionSettings {
     pub auto_install_extensions: HashMap<Arc<str>, bool>,
     #[serde(default)]
     pub auto_update_extensions: HashMap<Arc<str>, bool>,
+    /// The registry to fetch extensions from, instead of Zed's extensions API.
+    ///
+    /// This is either the URL of a static registry served over HTTP, or the
+    /// path of one in a local directory, such as a mirror created with
+    /// `zed-extension mirror`.
+    #[serde(default)]
+    pub extension_registry: Option<String>,
+    /// The capabilities granted to extensions. Wasm extensions without an
+    /// entry run without any capabilities.
+    ///
+    /// This is updated when approving or restricting the capabilities of an
+    /// extension after installing it.
+    #[serde(default)]
+    pub extension_capabilities: HashMap<Arc<str>, ExtensionCapabilities>,
 }
 
 impl ExtensionSettings {
@@ -33,6 +48,15 @@ impl ExtensionSettings {
             .copied()
             .unwrap_or(true)
     }
+
+    /// Returns the capabilities granted to the given extension, which has none
+    /// until the user approves the capabilities it requests.
+    pub fn capabilities(&self, manifest: &ExtensionManifest) -> ExtensionCapabilities {
+        self.extension_capabilities
+            .get(&manifest.id)
+            .cloned()
+            .unwrap_or_default()
+    }
 }
 
 impl Settings for ExtensionSettings {
@@ -1,7 +1,11 @@
 pub mod extension_builder;
+mod extension_capabilities;
+mod extension_context_provider;
+mod extension_editor_command;
 mod extension_indexed_docs_provider;
 mod extension_lsp_adapter;
 mod extension_manifest;
+pub mod extension_registry;
 mod extension_settings;
 mod extension_slash_command;
 mod wasm_host;
@@ -9,8 +13,10 @@ mod wasm_host;
 #[cfg(test)]
 mod extension_store_test;
 
+use crate::extension_context_provider::{ExtensionContextProvider, ExtensionContextProviders};
 use crate::extension_indexed_docs_provider::ExtensionIndexedDocsProvider;
 use crate::extension_manifest::SchemaVersion;
+use crate::extension_registry::{ExtensionRegistry, RegistryExtension, RegistryIndex};
 use crate::extension_slash_command::ExtensionSlashCommand;
 use crate::{extension_lsp_adapter::ExtensionLspAdapter, wasm_host::wit};
 use anyhow::{anyhow, bail, Context as _, Result};
@@ -18,9 +24,10 @@ use assistant_slash_command::SlashCommandRegistry;
 use async_compression::futures::bufread::GzipDecoder;
 use async_tar::Archive;
 use client::{telemetry::Telemetry, Client, ExtensionMetadata, GetExtensionsResponse};
-use collections::{btree_map, BTreeMap, HashSet};
+use collections::{btree_map, BTreeMap, BTreeSet, HashMap, HashSet};
+use command_palette_hooks::CommandPaletteCommands;
 use extension_builder::{CompileExtensionOptions, ExtensionBuilder};
-use fs::{Fs, RemoveOptions};
+use fs::{CopyOptions, Fs, RemoveOptions, RenameOptions};
 use futures::{
     channel::{
         mpsc::{unbounded, UnboundedSender},
@@ -39,21 +46,23 @@ use language::{
     LanguageConfig, LanguageMatcher, LanguageName, LanguageQueries, LanguageRegistry,
     QUERY_FILENAME_PREFIXES,
 };
+use lsp::LanguageServer;
 use node_runtime::NodeRuntime;
-use project::ContextProviderWithTasks;
 use release_channel::ReleaseChannel;
 use semantic_version::SemanticVersion;
 use serde::{Deserialize, Serialize};
-use settings::Settings;
+use settings::{Settings, SettingsStore};
 use snippet_provider::SnippetRegistry;
 use std::ops::RangeInclusive;
 use std::str::FromStr;
 use std::{
     cmp::Ordering,
+    mem,
     path::{self, Path, PathBuf},
     sync::Arc,
     time::{Duration, Instant},
 };
+use task::TaskTemplates;
 use theme::{ThemeRegistry, ThemeSettings};
 use url::Url;
 use util::{maybe, ResultExt};
@@ -62,14 +71,24 @@ use wasm_host::{
     WasmExtension, WasmHost,
 };
 
+pub use extension_capabilities::{ExtensionCapabilities, ExtensionViolation};
+pub use extension_editor_command::{
+    EditorCommandBuffer, EditorCommandContent, EditorCommandOutput, RunEditorCommand,
+    RunLanguageServerRequest,
+};
 pub use extension_manifest::{
-    ExtensionLibraryKind, ExtensionManifest, GrammarManifestEntry, OldExtensionManifest,
+    ExtensionLibraryKind, ExtensionManifest, GrammarManifestEntry,
+    LanguageServerRequestManifestEntry, OldExtensionManifest,
 };
 pub use extension_settings::ExtensionSettings;
 
 const RELOAD_DEBOUNCE_DURATION: Duration = Duration::from_millis(200);
 const FS_WATCH_LATENCY: Duration = Duration::from_millis(100);
 
+/// The name of the file that records where an installed extension was
+/// installed from.
+const EXTENSION_SOURCE_FILE_NAME: &str = "installed_from.json";
+
 /// The current extension [`SchemaVersion`] supported by Zed.
 const CURRENT_SCHEMA_VERSION: SchemaVersion = SchemaVersion(1);
 
@@ -111,6 +130,7 @@ pub struct ExtensionStore {
     reload_tx: UnboundedSender<Option<Arc<str>>>,
     reload_complete_senders: Vec<oneshot::Sender<()>>,
     installed_dir: PathBuf,
+    staging_dir: PathBuf,
     outstanding_operations: BTreeMap<Arc<str>, ExtensionOperation>,
     index_path: PathBuf,
     language_registry: Arc<LanguageRegistry>,
@@ -121,9 +141,19 @@ pub struct ExtensionStore {
     modified_extensions: HashSet<Arc<str>>,
     wasm_host: Arc<WasmHost>,
     wasm_extensions: Vec<(Arc<ExtensionManifest>, WasmExtension)>,
+    context_providers: Arc<ExtensionContextProviders>,
+    extension_capabilities: HashMap<Arc<str>, ExtensionCapabilities>,
+    /// The extensions whose capabilities the user should be asked to approve.
+    capability_requests: BTreeSet<Arc<str>>,
+    /// The extensions whose capabilities have been requested since Zed started.
+    requested_capabilities: HashSet<Arc<str>>,
+    violations: BTreeMap<Arc<str>, Vec<ExtensionViolation>>,
     tasks: Vec<Task<()>>,
 }
 
+/// The number of violations that are kept for each extension.
+const MAX_VIOLATIONS_PER_EXTENSION: usize = 10;
+
 #[derive(Clone, Copy)]
 pub enum ExtensionOperation {
     Upgrade,
@@ -137,6 +167,8 @@ pub enum Event {
     StartedReloading,
     ExtensionInstalled(Arc<str>),
     ExtensionFailedToLoad(Arc<str>),
+    ExtensionViolation(Arc<str>),
+    CapabilitiesRequested,
 }
 
 impl EventEmitter<Event> for ExtensionStore {}
@@ -156,6 +188,30 @@ pub struct ExtensionIndex {
 pub struct ExtensionIndexEntry {
     pub manifest: Arc<ExtensionManifest>,
     pub dev: bool,
+    #[serde(default)]
+    pub source: ExtensionSource,
+}
+
+/// Where an extension was installed from, which is the only place it is
+/// automatically updated from.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExtensionSource {
+    /// Zed's extensions API.
+    #[default]
+    Api,
+    /// A registry configured with the `extension_registry` setting.
+    Registry { location: String },
+    /// A local archive or directory, which is never updated automatically.
+    Path,
+}
+
+impl ExtensionSource {
+    fn registry(registry: &ExtensionRegistry) -> Self {
+        Self::Registry {
+            location: registry.location(),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Deserialize, Serialize)]
@@ -239,12 +295,15 @@ impl ExtensionStore {
         let work_dir = extensions_dir.join("work");
         let build_dir = build_dir.unwrap_or_else(|| extensions_dir.join("build"));
         let installed_dir = extensions_dir.join("installed");
+        let staging_dir = extensions_dir.join("staging");
         let index_path = extensions_dir.join("index.json");
 
         let (reload_tx, mut reload_rx) = unbounded();
+        let (violation_tx, mut violation_rx) = unbounded();
         let mut this = Self {
             extension_index: Default::default(),
             installed_dir,
+            staging_dir,
             index_path,
             builder: Arc::new(ExtensionBuilder::new(builder_client, build_dir)),
             outstanding_operations: Default::default(),
@@ -256,9 +315,17 @@ impl ExtensionStore {
                 node_runtime,
                 language_registry.clone(),
                 work_dir,
+                violation_tx,
                 cx,
             ),
             wasm_extensions: Vec::new(),
+            context_providers: Default::default(),
+            extension_capabilities: ExtensionSettings::get_global(cx)
+                .extension_capabilities
+                .clone(),
+            capability_requests: Default::default(),
+            requested_capabilities: Default::default(),
+            violations: Default::default(),
             fs,
             http_client,
             telemetry,
@@ -385,6 +452,21 @@ impl ExtensionStore {
             }
         }));
 
+        // Record the capabilities and resource limits that extensions exceed,
+        // so that they can be shown to the user.
+        this.tasks.push(cx.spawn(|this, mut cx| async move {
+            while let Some((extension_id, violation)) = violation_rx.next().await {
+                this.update(&mut cx, |this, cx| {
+                    this.record_violation(extension_id, violation, cx)
+                })
+                .ok();
+            }
+        }));
+
+        // Reload extensions when the capabilities granted to them change.
+        cx.observe_global::<SettingsStore>(|this, cx| this.extension_capabilities_changed(cx))
+            .detach();
+
         this
     }
 
@@ -409,6 +491,62 @@ impl ExtensionStore {
         self.installed_dir.clone()
     }
 
+    /// Returns the most recent capabilities and resource limits that the given
+    /// extension exceeded, oldest first.
+    pub fn violations(&self, extension_id: &str) -> &[ExtensionViolation] {
+        self.violations
+            .get(extension_id)
+            .map_or(&[], |violations| violations.as_slice())
+    }
+
+    fn record_violation(
+        &mut self,
+        extension_id: Arc<str>,
+        violation: ExtensionViolation,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let violations = self.violations.entry(extension_id.clone()).or_default();
+        if violations.len() == MAX_VIOLATIONS_PER_EXTENSION {
+            violations.remove(0);
+        }
+        violations.push(violation);
+        cx.emit(Event::ExtensionViolation(extension_id));
+        cx.notify();
+    }
+
+    /// Returns the extensions whose capabilities the user should be asked to
+    /// approve, which run without any capabilities until they are approved.
+    pub fn take_capability_requests(&mut self) -> Vec<Arc<str>> {
+        mem::take(&mut self.capability_requests)
+            .into_iter()
+            .collect()
+    }
+
+    fn extension_capabilities_changed(&mut self, cx: &mut ModelContext<Self>) {
+        let extension_capabilities = &ExtensionSettings::get_global(cx).extension_capabilities;
+        if *extension_capabilities == self.extension_capabilities {
+            return;
+        }
+
+        let changed_extensions = extension_capabilities
+            .keys()
+            .chain(self.extension_capabilities.keys())
+            .filter(|extension_id| {
+                extension_capabilities.get(*extension_id)
+                    != self.extension_capabilities.get(*extension_id)
+            })
+            .cloned()
+            .collect::<HashSet<_>>();
+        self.extension_capabilities = extension_capabilities.clone();
+
+        for extension_id in changed_extensions {
+            if self.extension_index.extensions.contains_key(&extension_id) {
+                self.violations.remove(&extension_id);
+                drop(self.reload(Some(extension_id), cx));
+            }
+        }
+    }
+
     pub fn outstanding_operations(&self) -> &BTreeMap<Arc<str>, ExtensionOperation> {
         &self.outstanding_operations
     }
@@ -440,6 +578,22 @@ impl ExtensionStore {
         search: Option<&str>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Vec<ExtensionMetadata>>> {
+        if let Some(registry) = self.configured_registry(cx) {
+            let release_channel = ReleaseChannel::global(cx);
+            let search = search.map(|search| search.to_string());
+            return self.fetch_from_registry(registry, cx, move |index| {
+                index
+                    .latest_compatible_versions(release_channel, |extension| {
+                        search.as_deref().map_or(true, |search| {
+                            extension_registry::matches_search(extension, search)
+                        })
+                    })
+                    .into_iter()
+                    .map(|extension| extension.metadata.clone())
+                    .collect()
+            });
+        }
+
         let version = CURRENT_SCHEMA_VERSION.to_string();
         let mut query = vec![("max_schema_version", version.as_str())];
         if let Some(search) = search {
@@ -456,28 +610,50 @@ impl ExtensionStore {
         let schema_versions = schema_version_range();
         let wasm_api_versions = wasm_api_version_range(ReleaseChannel::global(cx));
         let extension_settings = ExtensionSettings::get_global(cx);
+        let registry = self.configured_registry(cx);
+        let update_source = registry
+            .as_ref()
+            .map_or(ExtensionSource::Api, ExtensionSource::registry);
+        // Only update extensions from where they were installed from, so that
+        // an extension from elsewhere with the same ID doesn't replace them.
         let extension_ids = self
             .extension_index
             .extensions
             .iter()
-            .filter(|(id, entry)| !entry.dev && extension_settings.should_auto_update(id))
-            .map(|(id, _)| id.as_ref())
-            .collect::<Vec<_>>()
-            .join(",");
-        let task = self.fetch_extensions_from_api(
-            "/extensions/updates",
-            &[
-                ("min_schema_version", &schema_versions.start().to_string()),
-                ("max_schema_version", &schema_versions.end().to_string()),
-                (
-                    "min_wasm_api_version",
-                    &wasm_api_versions.start().to_string(),
-                ),
-                ("max_wasm_api_version", &wasm_api_versions.end().to_string()),
-                ("ids", &extension_ids),
-            ],
-            cx,
-        );
+            .filter(|(id, entry)| {
+                !entry.dev
+                    && entry.source == update_source
+                    && extension_settings.should_auto_update(id)
+            })
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        let task = if let Some(registry) = registry {
+            let release_channel = ReleaseChannel::global(cx);
+            self.fetch_from_registry(registry, cx, move |index| {
+                index
+                    .latest_compatible_versions(release_channel, |extension| {
+                        extension_ids.contains(&extension.id)
+                    })
+                    .into_iter()
+                    .map(|extension| extension.metadata.clone())
+                    .collect()
+            })
+        } else {
+            self.fetch_extensions_from_api(
+                "/extensions/updates",
+                &[
+                    ("min_schema_version", &schema_versions.start().to_string()),
+                    ("max_schema_version", &schema_versions.end().to_string()),
+                    (
+                        "min_wasm_api_version",
+                        &wasm_api_versions.start().to_string(),
+                    ),
+                    ("max_wasm_api_version", &wasm_api_versions.end().to_string()),
+                    ("ids", &extension_ids.join(",")),
+                ],
+                cx,
+            )
+        };
         cx.spawn(move |this, mut cx| async move {
             let extensions = task.await?;
             this.update(&mut cx, |this, _cx| {
@@ -501,9 +677,45 @@ impl ExtensionStore {
         extension_id: &str,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Vec<ExtensionMetadata>>> {
+        if let Some(registry) = self.configured_registry(cx) {
+            let extension_id = extension_id.to_string();
+            return self.fetch_from_registry(registry, cx, move |index| {
+                index
+                    .versions(&extension_id)
+                    .into_iter()
+                    .map(|extension| extension.metadata.clone())
+                    .collect()
+            });
+        }
+
         self.fetch_extensions_from_api(&format!("/extensions/{extension_id}"), &[], cx)
     }
 
+    /// Returns the registry configured to fetch extensions from instead of
+    /// Zed's extensions API, if any.
+    fn configured_registry(&self, cx: &AppContext) -> Option<ExtensionRegistry> {
+        let registry = ExtensionSettings::get_global(cx)
+            .extension_registry
+            .as_deref()?;
+        ExtensionRegistry::parse(registry).log_err()
+    }
+
+    fn fetch_from_registry<T: Send + 'static>(
+        &self,
+        registry: ExtensionRegistry,
+        cx: &mut ModelContext<Self>,
+        f: impl FnOnce(&RegistryIndex) -> T + Send + 'static,
+    ) -> Task<Result<T>> {
+        let fs = self.fs.clone();
+        let http_client = self.http_client.clone();
+        cx.background_executor().spawn(async move {
+            let index = registry
+                .fetch_index(fs.as_ref(), http_client.as_ref())
+                .await?;
+            Ok(f(&index))
+        })
+    }
+
     /// Installs any extensions that should be included with Zed by default.
     ///
     /// This can be used to make certain functionality provided by extensions
@@ -617,10 +829,14 @@ impl ExtensionStore {
             .detach_and_log_err(cx);
     }
 
+    /// Downloads and installs the extension archive at the given URL, which
+    /// may be a `file://` URL, checking its checksum if one is given.
     fn install_or_upgrade_extension_at_endpoint(
         &mut self,
         extension_id: Arc<str>,
         url: Url,
+        sha256: Option<String>,
+        source: ExtensionSource,
         operation: ExtensionOperation,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<()>> {
@@ -648,11 +864,16 @@ impl ExtensionStore {
                 }
             });
 
-            let mut response = http_client
-                .get(url.as_ref(), Default::default(), true)
-                .await
-                .map_err(|err| anyhow!("error downloading extension: {}", err))?;
+            let tar_gz_bytes =
+                extension_registry::download(&url, fs.as_ref(), http_client.as_ref())
+                    .await
+                    .map_err(|err| anyhow!("error downloading extension: {:?}", err))?;
+            if let Some(sha256) = sha256 {
+                extension_registry::verify_archive_checksum(&tar_gz_bytes, &sha256)?;
+            }
 
+            // Only remove the installed version once the new one has been
+            // downloaded and verified.
             fs.remove_dir(
                 &extension_dir,
                 RemoveOptions {
@@ -662,24 +883,10 @@ impl ExtensionStore {
             )
             .await?;
 
-            let content_length = response
-                .headers()
-                .get(http_client::http::header::CONTENT_LENGTH)
-                .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
-
-            let mut body = BufReader::new(response.body_mut());
-            let mut tar_gz_bytes = Vec::new();
-            body.read_to_end(&mut tar_gz_bytes).await?;
-
-            if let Some(content_length) = content_length {
-                let actual_len = tar_gz_bytes.len();
-                if content_length != actual_len {
-                    bail!("downloaded extension size {actual_len} does not match content length {content_length}");
-                }
-            }
             let decompressed_bytes = GzipDecoder::new(BufReader::new(tar_gz_bytes.as_slice()));
             let archive = Archive::new(decompressed_bytes);
-            archive.unpack(extension_dir).await?;
+            archive.unpack(&extension_dir).await?;
+            write_extension_source(fs.as_ref(), &extension_dir, &source).await?;
             this.update(&mut cx, |this, cx| {
                 this.reload(Some(extension_id.clone()), cx)
             })?
@@ -703,6 +910,18 @@ impl ExtensionStore {
     ) {
         log::info!("installing extension {extension_id} latest version");
 
+        if let Some(registry) = self.configured_registry(cx) {
+            self.install_from_registry(
+                registry,
+                extension_id,
+                None,
+                ExtensionOperation::Install,
+                cx,
+            )
+            .detach_and_log_err(cx);
+            return;
+        }
+
         let schema_versions = schema_version_range();
         let wasm_api_versions = wasm_api_version_range(ReleaseChannel::global(cx));
 
@@ -728,6 +947,8 @@ impl ExtensionStore {
         self.install_or_upgrade_extension_at_endpoint(
             extension_id,
             url,
+            None,
+            ExtensionSource::Api,
             ExtensionOperation::Install,
             cx,
         )
@@ -751,6 +972,17 @@ impl ExtensionStore {
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<()>> {
         log::info!("installing extension {extension_id} {version}");
+
+        if let Some(registry) = self.configured_registry(cx) {
+            return self.install_from_registry(
+                registry,
+                extension_id,
+                Some(version),
+                operation,
+                cx,
+            );
+        }
+
         let Some(url) = self
             .http_client
             .build_zed_api_url(
@@ -762,7 +994,60 @@ impl ExtensionStore {
             return Task::ready(Ok(()));
         };
 
-        self.install_or_upgrade_extension_at_endpoint(extension_id, url, operation, cx)
+        self.install_or_upgrade_extension_at_endpoint(
+            extension_id,
+            url,
+            None,
+            ExtensionSource::Api,
+            operation,
+            cx,
+        )
+    }
+
+    /// Installs the given version of an extension from a registry, or the
+    /// latest version compatible with this version of Zed.
+    fn install_from_registry(
+        &mut self,
+        registry: ExtensionRegistry,
+        extension_id: Arc<str>,
+        version: Option<Arc<str>>,
+        operation: ExtensionOperation,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let release_channel = ReleaseChannel::global(cx);
+        let extension = self.fetch_from_registry(registry.clone(), cx, {
+            let extension_id = extension_id.clone();
+            move |index| -> Option<RegistryExtension> {
+                match version {
+                    Some(version) => index.get(&extension_id, &version).cloned(),
+                    None => index
+                        .latest_compatible_versions(release_channel, |extension| {
+                            extension.id == extension_id
+                        })
+                        .into_iter()
+                        .next()
+                        .cloned(),
+                }
+            }
+        });
+
+        cx.spawn(move |this, mut cx| async move {
+            let extension = extension
+                .await?
+                .with_context(|| format!("extension {extension_id} not found in registry"))?;
+            let url = registry.url(&extension.archive_path())?;
+            this.update(&mut cx, |this, cx| {
+                this.install_or_upgrade_extension_at_endpoint(
+                    extension_id,
+                    url,
+                    Some(extension.sha256),
+                    ExtensionSource::registry(&registry),
+                    operation,
+                    cx,
+                )
+            })?
+            .await
+        })
     }
 
     pub fn uninstall_extension(&mut self, extension_id: Arc<str>, cx: &mut ModelContext<Self>) {
@@ -892,6 +1177,121 @@ impl ExtensionStore {
         })
     }
 
+    /// Installs an extension from a packaged archive (`archive.tar.gz`) or
+    /// from a directory containing a built extension, without fetching it
+    /// from the extensions API or a registry.
+    pub fn install_extension_from_path(
+        &mut self,
+        path: PathBuf,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let installed_dir = self.installed_dir.clone();
+        let staging_dir = self.staging_dir.clone();
+        let fs = self.fs.clone();
+
+        cx.spawn(move |this, mut cx| async move {
+            let is_archive = fs.is_file(&path).await;
+            let source_dir = if is_archive {
+                let file_name = path
+                    .file_name()
+                    .with_context(|| format!("invalid extension archive path {path:?}"))?;
+                let source_dir = staging_dir.join(file_name);
+                fs.remove_dir(
+                    &source_dir,
+                    RemoveOptions {
+                        recursive: true,
+                        ignore_if_not_exists: true,
+                    },
+                )
+                .await?;
+                fs.create_dir(&source_dir).await?;
+
+                let tar_gz_bytes = fs.load_bytes(&path).await?;
+                let decompressed_bytes = GzipDecoder::new(BufReader::new(tar_gz_bytes.as_slice()));
+                let archive = Archive::new(decompressed_bytes);
+                archive
+                    .unpack(&source_dir)
+                    .await
+                    .with_context(|| format!("error unpacking extension archive {path:?}"))?;
+                source_dir
+            } else {
+                path.clone()
+            };
+
+            let manifest = ExtensionManifest::load(fs.clone(), &source_dir).await?;
+            let extension_id = manifest.id.clone();
+            if manifest.lib.kind.is_some() && !fs.is_file(&source_dir.join("extension.wasm")).await
+            {
+                bail!("extension {extension_id} has not been built; install it as a dev extension to build it from source");
+            }
+
+            if !this.update(&mut cx, |this, cx| {
+                match this.outstanding_operations.entry(extension_id.clone()) {
+                    btree_map::Entry::Occupied(_) => return false,
+                    btree_map::Entry::Vacant(e) => e.insert(ExtensionOperation::Install),
+                };
+                cx.notify();
+                true
+            })? {
+                return Ok(());
+            }
+
+            let _finish = util::defer({
+                let this = this.clone();
+                let mut cx = cx.clone();
+                let extension_id = extension_id.clone();
+                move || {
+                    this.update(&mut cx, |this, cx| {
+                        this.outstanding_operations.remove(extension_id.as_ref());
+                        cx.notify();
+                    })
+                    .ok();
+                }
+            });
+
+            let extension_dir = installed_dir.join(extension_id.as_ref());
+            if let Some(metadata) = fs.metadata(&extension_dir).await? {
+                if metadata.is_symlink {
+                    bail!("extension {extension_id} is already installed as a dev extension");
+                }
+            }
+            fs.remove_dir(
+                &extension_dir,
+                RemoveOptions {
+                    recursive: true,
+                    ignore_if_not_exists: true,
+                },
+            )
+            .await?;
+
+            if is_archive {
+                fs.rename(&source_dir, &extension_dir, RenameOptions::default())
+                    .await?;
+            } else {
+                fs::copy_recursive(
+                    fs.as_ref(),
+                    &source_dir,
+                    &extension_dir,
+                    CopyOptions::default(),
+                )
+                .await?;
+            }
+            write_extension_source(fs.as_ref(), &extension_dir, &ExtensionSource::Path).await?;
+
+            this.update(&mut cx, |this, cx| {
+                this.reload(Some(extension_id.clone()), cx)
+            })?
+            .await;
+
+            this.update(&mut cx, |_, cx| {
+                cx.emit(Event::ExtensionInstalled(extension_id));
+            })
+            .ok();
+
+            anyhow::Ok(())
+        })
+    }
+
     pub fn rebuild_dev_extension(&mut self, extension_id: Arc<str>, cx: &mut ModelContext<Self>) {
         let path = self.installed_dir.join(extension_id.as_ref());
         let builder = self.builder.clone();
@@ -932,6 +1332,152 @@ impl ExtensionStore {
         .detach_and_log_err(cx)
     }
 
+    /// Runs an editor command provided by the given extension on the given buffer.
+    pub fn run_editor_command(
+        &self,
+        extension_id: &str,
+        command_name: &str,
+        buffer: EditorCommandBuffer,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<EditorCommandOutput>> {
+        let Some((_, extension)) = self
+            .wasm_extensions
+            .iter()
+            .find(|(manifest, _)| manifest.id.as_ref() == extension_id)
+        else {
+            return Task::ready(Err(anyhow!("extension {extension_id} is not loaded")));
+        };
+
+        let extension = extension.clone();
+        let command_name = command_name.to_string();
+        cx.background_executor().spawn(async move {
+            let buffer = Arc::new(buffer);
+            let output = extension
+                .call({
+                    let buffer = buffer.clone();
+                    |extension, store| {
+                        async move {
+                            let buffer_resource = store.data_mut().table().push(buffer)?;
+                            extension
+                                .call_run_editor_command(store, &command_name, buffer_resource)
+                                .await?
+                                .map_err(|err| anyhow!("{err}"))
+                        }
+                        .boxed()
+                    }
+                })
+                .await?;
+            EditorCommandOutput::from_wit(&buffer.text, output)
+        })
+    }
+
+    /// Sends a custom request or notification provided by an extension to the
+    /// given language server, at a position in the given buffer, and returns
+    /// the output the extension produces from the server's response.
+    pub fn run_language_server_request(
+        &self,
+        request: &RunLanguageServerRequest,
+        language_server: Arc<LanguageServer>,
+        uri: lsp::Url,
+        position: lsp::Position,
+        buffer: EditorCommandBuffer,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<EditorCommandOutput>> {
+        let RunLanguageServerRequest {
+            extension_id,
+            language_server_id,
+            request_name,
+        } = request.clone();
+        let Some((manifest, extension)) = self
+            .wasm_extensions
+            .iter()
+            .find(|(manifest, _)| manifest.id == extension_id)
+        else {
+            return Task::ready(Err(anyhow!("extension {extension_id} is not loaded")));
+        };
+        let Some(request) = manifest
+            .language_servers
+            .get(&language_server_id)
+            .and_then(|language_server| language_server.requests.get(&request_name))
+            .cloned()
+        else {
+            return Task::ready(Err(anyhow!(
+                "extension {extension_id} has no request {request_name} for {}",
+                language_server_id.0
+            )));
+        };
+
+        let extension = extension.clone();
+        let location = wit::RequestLocation {
+            uri: uri.to_string(),
+            position: wit::Position {
+                line: position.line,
+                character: position.character,
+            },
+        };
+        cx.background_executor().spawn(async move {
+            let buffer = Arc::new(buffer);
+            let params = extension
+                .call({
+                    let language_server_id = language_server_id.clone();
+                    let request_name = request_name.clone();
+                    let buffer = buffer.clone();
+                    |extension, store| {
+                        async move {
+                            let buffer_resource = store.data_mut().table().push(buffer)?;
+                            extension
+                                .call_language_server_request_params(
+                                    store,
+                                    &language_server_id,
+                                    &request_name,
+                                    &location,
+                                    buffer_resource,
+                                )
+                                .await?
+                                .map_err(|err| anyhow!("{err}"))
+                        }
+                        .boxed()
+                    }
+                })
+                .await?;
+            let params = serde_json::from_str::<serde_json::Value>(&params)
+                .with_context(|| format!("invalid parameters for {}", request.method))?;
+
+            if request.notification {
+                language_server.notify_custom(&request.method, params)?;
+                return Ok(EditorCommandOutput::default());
+            }
+
+            let response = language_server
+                .request_custom(&request.method, params)
+                .await
+                .with_context(|| format!("{} request failed", request.method))?
+                .to_string();
+            let output = extension
+                .call({
+                    let buffer = buffer.clone();
+                    |extension, store| {
+                        async move {
+                            let buffer_resource = store.data_mut().table().push(buffer)?;
+                            extension
+                                .call_language_server_request_output(
+                                    store,
+                                    &language_server_id,
+                                    &request_name,
+                                    &response,
+                                    buffer_resource,
+                                )
+                                .await?
+                                .map_err(|err| anyhow!("{err}"))
+                        }
+                        .boxed()
+                    }
+                })
+                .await?;
+            EditorCommandOutput::from_wit(&buffer.text, output)
+        })
+    }
+
     /// Updates the set of installed extensions.
     ///
     /// First, this unloads any themes, languages, or grammars that are
@@ -1049,6 +1595,35 @@ impl ExtensionStore {
 
         self.wasm_extensions
             .retain(|(extension, _)| !extensions_to_unload.contains(&extension.id));
+        for extension_id in &extensions_to_unload {
+            self.context_providers.remove_extension(extension_id);
+        }
+        if CommandPaletteCommands::try_global(cx).is_some() {
+            CommandPaletteCommands::update_global(cx, |commands, _| {
+                for extension_id in &extensions_to_unload {
+                    let Some(extension) = old_index.extensions.get(extension_id) else {
+                        continue;
+                    };
+                    for command_name in extension.manifest.editor_commands.keys() {
+                        commands.remove(&RunEditorCommand {
+                            extension_id: extension_id.clone(),
+                            command_name: command_name.clone(),
+                        });
+                    }
+                    for (language_server_id, language_server) in
+                        &extension.manifest.language_servers
+                    {
+                        for request_name in language_server.requests.keys() {
+                            commands.remove(&RunLanguageServerRequest {
+                                extension_id: extension_id.clone(),
+                                language_server_id: language_server_id.clone(),
+                                request_name: request_name.clone(),
+                            });
+                        }
+                    }
+                }
+            });
+        }
         self.theme_registry.remove_user_themes(&themes_to_remove);
         self.language_registry
             .remove_languages(&languages_to_remove, &grammars_to_remove);
@@ -1094,6 +1669,7 @@ impl ExtensionStore {
                 Path::new(language.extension.as_ref()),
                 language.path.as_path(),
             ]);
+            let context_providers = self.context_providers.clone();
             self.language_registry.register_language(
                 language_name.clone(),
                 language.grammar.clone(),
@@ -1102,23 +1678,27 @@ impl ExtensionStore {
                     let config = std::fs::read_to_string(language_path.join("config.toml"))?;
                     let config: LanguageConfig = ::toml::from_str(&config)?;
                     let queries = load_plugin_queries(&language_path);
-                    let tasks = std::fs::read_to_string(language_path.join("tasks.json"))
+                    let templates = std::fs::read_to_string(language_path.join("tasks.json"))
                         .ok()
-                        .and_then(|contents| {
-                            let definitions = serde_json_lenient::from_str(&contents).log_err()?;
-                            Some(Arc::new(ContextProviderWithTasks::new(definitions)) as Arc<_>)
-                        });
-
-                    Ok((config, queries, tasks))
+                        .and_then(|contents| serde_json_lenient::from_str(&contents).log_err());
+                    let tasks = Arc::new(ExtensionContextProvider::new(
+                        config.name.clone(),
+                        templates,
+                        context_providers.clone(),
+                    )) as Arc<_>;
+
+                    Ok((config, queries, Some(tasks)))
                 },
             );
         }
 
         let fs = self.fs.clone();
         let wasm_host = self.wasm_host.clone();
+        let extension_settings = ExtensionSettings::get_global(cx).clone();
         let root_dir = self.installed_dir.clone();
         let theme_registry = self.theme_registry.clone();
         let snippet_registry = self.snippet_registry.clone();
+        let context_providers = self.context_providers.clone();
         let extension_entries = extensions_to_load
             .iter()
             .filter_map(|name| new_index.extensions.get(name).cloned())
@@ -1175,6 +1755,7 @@ impl ExtensionStore {
                         .load_extension(
                             wasm_bytes,
                             extension.manifest.clone().clone(),
+                            extension_settings.capabilities(&extension.manifest),
                             cx.background_executor().clone(),
                         )
                         .await
@@ -1185,6 +1766,35 @@ impl ExtensionStore {
                 .await;
 
                 if let Some(wasm_extension) = wasm_extension.log_err() {
+                    for language in extension.manifest.context_providers.keys() {
+                        let templates = wasm_extension
+                            .call({
+                                let language = language.clone();
+                                |extension, store| {
+                                    async move {
+                                        extension
+                                            .call_task_templates(store, &language)
+                                            .await?
+                                            .map_err(|err| anyhow!("{err:?}"))
+                                    }
+                                    .boxed()
+                                }
+                            })
+                            .await
+                            .with_context(|| {
+                                format!(
+                                    "failed to get {language} task templates from extension {}",
+                                    extension.manifest.id
+                                )
+                            })
+                            .log_err()
+                            .unwrap_or_default();
+                        context_providers.register(
+                            language.clone(),
+                            wasm_extension.clone(),
+                            TaskTemplates(templates.into_iter().map(Into::into).collect()),
+                        );
+                    }
                     wasm_extensions.push((extension.manifest.clone(), wasm_extension));
                 } else {
                     this.update(&mut cx, |_, cx| {
@@ -1243,6 +1853,51 @@ impl ExtensionStore {
                             },
                         ));
                     }
+
+                    if CommandPaletteCommands::try_global(cx).is_some() {
+                        CommandPaletteCommands::update_global(cx, |commands, _| {
+                            for (command_name, command) in &manifest.editor_commands {
+                                commands.add(
+                                    format!("{}: {}", manifest.name, command.description)
+                                        .to_lowercase(),
+                                    Box::new(RunEditorCommand {
+                                        extension_id: manifest.id.clone(),
+                                        command_name: command_name.clone(),
+                                    }),
+                                );
+                            }
+                            for (language_server_id, language_server) in &manifest.language_servers
+                            {
+                                for (request_name, request) in &language_server.requests {
+                                    commands.add(
+                                        format!("{}: {}", manifest.name, request.description)
+                                            .to_lowercase(),
+                                        Box::new(RunLanguageServerRequest {
+                                            extension_id: manifest.id.clone(),
+                                            language_server_id: language_server_id.clone(),
+                                            request_name: request_name.clone(),
+                                        }),
+                                    );
+                                }
+                            }
+                        });
+                    }
+                }
+
+                // Ask for the capabilities of newly loaded extensions that
+                // haven't been approved or restricted yet.
+                let extension_capabilities =
+                    &ExtensionSettings::get_global(cx).extension_capabilities;
+                for (manifest, _) in &wasm_extensions {
+                    if !extension_capabilities.contains_key(&manifest.id)
+                        && !manifest.requested_capabilities().is_empty()
+                        && this.requested_capabilities.insert(manifest.id.clone())
+                    {
+                        this.capability_requests.insert(manifest.id.clone());
+                    }
+                }
+                if !this.capability_requests.is_empty() {
+                    cx.emit(Event::CapabilitiesRequested);
                 }
 
                 this.wasm_extensions.extend(wasm_extensions);
@@ -1311,6 +1966,15 @@ impl ExtensionStore {
             .await?
             .ok_or_else(|| anyhow!("directory does not exist"))?
             .is_symlink;
+        let source = if is_dev {
+            ExtensionSource::default()
+        } else {
+            fs.load(&extension_dir.join(EXTENSION_SOURCE_FILE_NAME))
+                .await
+                .ok()
+                .and_then(|source| serde_json::from_str(&source).log_err())
+                .unwrap_or_default()
+        };
 
         if let Ok(mut language_paths) = fs.read_dir(&extension_dir.join("languages")).await {
             while let Some(language_path) = language_paths.next().await {
@@ -1387,6 +2051,7 @@ impl ExtensionStore {
             extension_id.clone(),
             ExtensionIndexEntry {
                 dev: is_dev,
+                source,
                 manifest: Arc::new(extension_manifest),
             },
         );
@@ -1395,6 +2060,22 @@ impl ExtensionStore {
     }
 }
 
+/// Records where the extension in the given directory was installed from.
+async fn write_extension_source(
+    fs: &dyn Fs,
+    extension_dir: &Path,
+    source: &ExtensionSource,
+) -> Result<()> {
+    let source_json = serde_json::to_string(source)?;
+    fs.save(
+        &extension_dir.join(EXTENSION_SOURCE_FILE_NAME),
+        &source_json.as_str().into(),
+        Default::default(),
+    )
+    .await
+    .context("failed to record where the extension was installed from")
+}
+
 fn load_plugin_queries(root_path: &Path) -> LanguageQueries {
     let mut result = LanguageQueries::default();
     if let Some(entries) = std::fs::read_dir(root_path).log_err() {
@@ -1,9 +1,9 @@
 use crate::extension_manifest::SchemaVersion;
 use crate::extension_settings::ExtensionSettings;
 use crate::{
-    Event, ExtensionIndex, ExtensionIndexEntry, ExtensionIndexLanguageEntry,
-    ExtensionIndexThemeEntry, ExtensionManifest, ExtensionStore, GrammarManifestEntry,
-    RELOAD_DEBOUNCE_DURATION,
+    Event, ExtensionCapabilities, ExtensionIndex, ExtensionIndexEntry, ExtensionIndexLanguageEntry,
+    ExtensionIndexThemeEntry, ExtensionManifest, ExtensionSource, ExtensionStore,
+    GrammarManifestEntry, RELOAD_DEBOUNCE_DURATION,
 };
 use assistant_slash_command::SlashCommandRegistry;
 use async_compression::futures::bufread::GzipEncoder;
@@ -163,9 +163,13 @@ async fn test_extension_store(cx: &mut TestAppContext) {
                         language_servers: BTreeMap::default(),
                         slash_commands: BTreeMap::default(),
                         indexed_docs_providers: BTreeMap::default(),
+                        context_providers: BTreeMap::default(),
+                        editor_commands: BTreeMap::default(),
                         snippets: None,
+                        capabilities: None,
                     }),
                     dev: false,
+                    source: ExtensionSource::Api,
                 },
             ),
             (
@@ -189,9 +193,13 @@ async fn test_extension_store(cx: &mut TestAppContext) {
                         language_servers: BTreeMap::default(),
                         slash_commands: BTreeMap::default(),
                         indexed_docs_providers: BTreeMap::default(),
+                        context_providers: BTreeMap::default(),
+                        editor_commands: BTreeMap::default(),
                         snippets: None,
+                        capabilities: None,
                     }),
                     dev: false,
+                    source: ExtensionSource::Api,
                 },
             ),
         ]
@@ -353,9 +361,13 @@ async fn test_extension_store(cx: &mut TestAppContext) {
                 language_servers: BTreeMap::default(),
                 slash_commands: BTreeMap::default(),
                 indexed_docs_providers: BTreeMap::default(),
+                context_providers: BTreeMap::default(),
+                editor_commands: BTreeMap::default(),
                 snippets: None,
+                capabilities: None,
             }),
             dev: false,
+            source: ExtensionSource::Api,
         },
     );
     expected_index.themes.insert(
@@ -471,6 +483,18 @@ async fn test_extension_store_with_test_extension(cx: &mut TestAppContext) {
     let test_extension_id = "test-extension";
     let test_extension_dir = root_dir.join("extensions").join(test_extension_id);
 
+    // Wasm extensions have no capabilities until the user approves them.
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<ExtensionSettings>(cx, |settings| {
+                settings.extension_capabilities.insert(
+                    test_extension_id.into(),
+                    ExtensionCapabilities::unrestricted(),
+                );
+            });
+        });
+    });
+
     let fs = Arc::new(RealFs::default());
     let extensions_dir = temp_tree(json!({
         "installed": {},
@@ -623,6 +647,9 @@ async fn test_extension_store_with_test_extension(cx: &mut TestAppContext) {
         })
         .await
         .unwrap();
+    assert!(extension_store
+        .update(cx, |store, _| store.take_capability_requests())
+        .is_empty());
 
     let mut fake_servers = language_registry.register_fake_language_server(
         LanguageServerName("gleam".into()),
@@ -1,6 +1,6 @@
 pub(crate) mod wit;
 
-use crate::ExtensionManifest;
+use crate::{ExtensionCapabilities, ExtensionManifest, ExtensionViolation};
 use anyhow::{anyhow, bail, Context as _, Result};
 use fs::{normalize_path, Fs};
 use futures::future::LocalBoxFuture;
@@ -22,13 +22,24 @@ use std::{
     path::{Path, PathBuf},
     sync::{Arc, OnceLock},
 };
+use util::ResultExt as _;
 use wasmtime::{
     component::{Component, ResourceTable},
-    Engine, Store,
+    Engine, Store, StoreLimits, StoreLimitsBuilder,
 };
 use wasmtime_wasi as wasi;
 use wit::Extension;
 
+/// The fuel, roughly the number of Wasm instructions, an extension may use in
+/// a single call before it is stopped.
+const FUEL_PER_CALL: u64 = 20_000_000_000;
+
+/// How often, in units of fuel, a running extension yields to other tasks.
+const FUEL_YIELD_INTERVAL: u64 = 10_000_000;
+
+/// The maximum size of an extension's memory, in bytes.
+const MEMORY_LIMIT: usize = 1 << 30;
+
 pub(crate) struct WasmHost {
     engine: Engine,
     release_channel: ReleaseChannel,
@@ -39,6 +50,7 @@ pub(crate) struct WasmHost {
     pub(crate) work_dir: PathBuf,
     _main_thread_message_task: Task<()>,
     main_thread_message_tx: mpsc::UnboundedSender<MainThreadCall>,
+    violation_tx: mpsc::UnboundedSender<(Arc<str>, ExtensionViolation)>,
 }
 
 #[derive(Clone)]
@@ -51,6 +63,8 @@ pub struct WasmExtension {
 
 pub(crate) struct WasmState {
     manifest: Arc<ExtensionManifest>,
+    capabilities: ExtensionCapabilities,
+    limits: StoreLimits,
     pub(crate) table: ResourceTable,
     ctx: wasi::WasiCtx,
     pub(crate) host: Arc<WasmHost>,
@@ -71,6 +85,7 @@ fn wasm_engine() -> wasmtime::Engine {
             let mut config = wasmtime::Config::new();
             config.wasm_component_model(true);
             config.async_support(true);
+            config.consume_fuel(true);
             wasmtime::Engine::new(&config).unwrap()
         })
         .clone()
@@ -83,6 +98,7 @@ impl WasmHost {
         node_runtime: NodeRuntime,
         language_registry: Arc<LanguageRegistry>,
         work_dir: PathBuf,
+        violation_tx: mpsc::UnboundedSender<(Arc<str>, ExtensionViolation)>,
         cx: &mut AppContext,
     ) -> Arc<Self> {
         let (tx, mut rx) = mpsc::unbounded::<MainThreadCall>();
@@ -101,6 +117,7 @@ impl WasmHost {
             release_channel: ReleaseChannel::global(cx),
             _main_thread_message_task: task,
             main_thread_message_tx: tx,
+            violation_tx,
         })
     }
 
@@ -108,6 +125,7 @@ impl WasmHost {
         self: &Arc<Self>,
         wasm_bytes: Vec<u8>,
         manifest: Arc<ExtensionManifest>,
+        capabilities: ExtensionCapabilities,
         executor: BackgroundExecutor,
     ) -> Task<Result<WasmExtension>> {
         let this = self.clone();
@@ -122,10 +140,18 @@ impl WasmHost {
                 WasmState {
                     ctx: this.build_wasi_ctx(&manifest).await?,
                     manifest: manifest.clone(),
+                    capabilities,
+                    limits: StoreLimitsBuilder::new()
+                        .memory_size(MEMORY_LIMIT)
+                        .trap_on_grow_failure(true)
+                        .build(),
                     table: ResourceTable::new(),
                     host: this.clone(),
                 },
             );
+            store.limiter(|state| &mut state.limits);
+            store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL))?;
+            store.set_fuel(FUEL_PER_CALL)?;
 
             let mut extension = Extension::instantiate_async(
                 &mut store,
@@ -144,7 +170,11 @@ impl WasmHost {
             executor
                 .spawn(async move {
                     while let Some(call) = rx.next().await {
+                        store.set_fuel(FUEL_PER_CALL).log_err();
                         (call)(&mut extension, &mut store).await;
+                        if store.get_fuel().is_ok_and(|fuel| fuel == 0) {
+                            store.data().report_violation(ExtensionViolation::CpuLimit);
+                        }
                     }
                 })
                 .detach();
@@ -287,6 +317,37 @@ impl WasmState {
     fn work_dir(&self) -> PathBuf {
         self.host.work_dir.join(self.manifest.id.as_ref())
     }
+
+    /// Checks that the extension may make requests to the given URL.
+    pub(crate) fn check_url(&self, url: &str) -> Result<()> {
+        self.check_capability(self.capabilities.check_url(url))
+    }
+
+    /// Checks that the extension may read the given file in a worktree.
+    pub(crate) fn check_file_read(&self, path: &str) -> Result<()> {
+        self.check_capability(self.capabilities.check_file_read(path))
+    }
+
+    /// Checks that the extension may run the given command.
+    pub(crate) fn check_process(&self, command: &str) -> Result<()> {
+        self.check_capability(self.capabilities.check_process(command))
+    }
+
+    fn check_capability(&self, result: Result<(), ExtensionViolation>) -> Result<()> {
+        result.map_err(|violation| {
+            let error = anyhow!("{violation} by the capabilities of the extension");
+            self.report_violation(violation);
+            error
+        })
+    }
+
+    fn report_violation(&self, violation: ExtensionViolation) {
+        log::warn!("extension {}: {violation}", self.manifest.id);
+        self.host
+            .violation_tx
+            .unbounded_send((self.manifest.id.clone(), violation))
+            .ok();
+    }
 }
 
 impl wasi::WasiView for WasmState {
@@ -3,13 +3,14 @@ mod since_v0_0_4;
 mod since_v0_0_6;
 mod since_v0_1_0;
 mod since_v0_2_0;
+use crate::extension_editor_command::EditorCommandBuffer;
 use indexed_docs::IndexedDocsDatabase;
 use release_channel::ReleaseChannel;
 use since_v0_2_0 as latest;
 
 use super::{wasm_engine, WasmState};
 use anyhow::{anyhow, Context, Result};
-use language::{LanguageServerName, LspAdapterDelegate};
+use language::{LanguageName, LanguageServerName, LspAdapterDelegate};
 use semantic_version::SemanticVersion;
 use std::{ops::RangeInclusive, sync::Arc};
 use wasmtime::{
@@ -20,8 +21,12 @@ use wasmtime::{
 #[cfg(test)]
 pub use latest::CodeLabelSpanLiteral;
 pub use latest::{
-    zed::extension::lsp::{Completion, CompletionKind, InsertTextFormat, Symbol, SymbolKind},
+    zed::extension::editor::{BufferEdit, EditorCommandOutput},
+    zed::extension::lsp::{
+        Completion, CompletionKind, InsertTextFormat, Position, RequestLocation, Symbol, SymbolKind,
+    },
     zed::extension::slash_command::{SlashCommandArgumentCompletion, SlashCommandOutput},
+    zed::extension::tasks::TaskTemplate,
     CodeLabel, CodeLabelSpan, Command, Range, SlashCommand,
 };
 pub use since_v0_0_4::LanguageServerConfig;
@@ -392,6 +397,109 @@ impl Extension {
             }
         }
     }
+
+    pub async fn call_task_templates(
+        &self,
+        store: &mut Store<WasmState>,
+        language_name: &LanguageName,
+    ) -> Result<Result<Vec<TaskTemplate>, String>> {
+        match self {
+            Extension::V020(ext) => ext.call_task_templates(store, &language_name.0).await,
+            Extension::V001(_) | Extension::V004(_) | Extension::V006(_) | Extension::V010(_) => {
+                Ok(Ok(Vec::new()))
+            }
+        }
+    }
+
+    pub async fn call_task_context_variables(
+        &self,
+        store: &mut Store<WasmState>,
+        language_name: &LanguageName,
+        variables: &[(String, String)],
+    ) -> Result<Result<Vec<(String, String)>, String>> {
+        match self {
+            Extension::V020(ext) => {
+                ext.call_task_context_variables(store, &language_name.0, variables)
+                    .await
+            }
+            Extension::V001(_) | Extension::V004(_) | Extension::V006(_) | Extension::V010(_) => {
+                Ok(Ok(Vec::new()))
+            }
+        }
+    }
+
+    pub async fn call_run_editor_command(
+        &self,
+        store: &mut Store<WasmState>,
+        command_name: &str,
+        buffer: Resource<Arc<EditorCommandBuffer>>,
+    ) -> Result<Result<EditorCommandOutput, String>> {
+        match self {
+            Extension::V020(ext) => {
+                ext.call_run_editor_command(store, command_name, buffer)
+                    .await
+            }
+            Extension::V001(_) | Extension::V004(_) | Extension::V006(_) | Extension::V010(_) => {
+                Err(anyhow!(
+                    "`run_editor_command` not available prior to v0.2.0"
+                ))
+            }
+        }
+    }
+
+    pub async fn call_language_server_request_params(
+        &self,
+        store: &mut Store<WasmState>,
+        language_server_id: &LanguageServerName,
+        request_name: &str,
+        location: &RequestLocation,
+        buffer: Resource<Arc<EditorCommandBuffer>>,
+    ) -> Result<Result<String, String>> {
+        match self {
+            Extension::V020(ext) => {
+                ext.call_language_server_request_params(
+                    store,
+                    &language_server_id.0,
+                    request_name,
+                    location,
+                    buffer,
+                )
+                .await
+            }
+            Extension::V001(_) | Extension::V004(_) | Extension::V006(_) | Extension::V010(_) => {
+                Err(anyhow!(
+                    "`language_server_request_params` not available prior to v0.2.0"
+                ))
+            }
+        }
+    }
+
+    pub async fn call_language_server_request_output(
+        &self,
+        store: &mut Store<WasmState>,
+        language_server_id: &LanguageServerName,
+        request_name: &str,
+        response: &str,
+        buffer: Resource<Arc<EditorCommandBuffer>>,
+    ) -> Result<Result<EditorCommandOutput, String>> {
+        match self {
+            Extension::V020(ext) => {
+                ext.call_language_server_request_output(
+                    store,
+                    &language_server_id.0,
+                    request_name,
+                    response,
+                    buffer,
+                )
+                .await
+            }
+            Extension::V001(_) | Extension::V004(_) | Extension::V006(_) | Extension::V010(_) => {
+                Err(anyhow!(
+                    "`language_server_request_output` not available prior to v0.2.0"
+                ))
+            }
+        }
+    }
 }
 
 trait ToWasmtimeResult<T> {
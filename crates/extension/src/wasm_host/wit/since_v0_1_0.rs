@@ -176,6 +176,9 @@ impl HostWorktree for WasmState {
         delegate: Resource<Arc<dyn LspAdapterDelegate>>,
         path: String,
     ) -> wasmtime::Result<Result<String, String>> {
+        if let Err(error) = self.check_file_read(&path) {
+            return Err(error).to_wasmtime_result();
+        }
         let delegate = self.table.get(&delegate)?;
         Ok(delegate
             .read_text_file(path.into())
@@ -220,6 +223,7 @@ impl http_client::Host for WasmState {
     ) -> wasmtime::Result<Result<http_client::HttpResponse, String>> {
         maybe!(async {
             let url = &request.url;
+            self.check_url(url)?;
             let request = convert_request(&request)?;
             let mut response = self.host.http_client.send(request).await?;
 
@@ -236,6 +240,9 @@ impl http_client::Host for WasmState {
         &mut self,
         request: http_client::HttpRequest,
     ) -> wasmtime::Result<Result<Resource<ExtensionHttpResponseStream>, String>> {
+        if let Err(error) = self.check_url(&request.url) {
+            return Err(error).to_wasmtime_result();
+        }
         let request = convert_request(&request)?;
         let response = self.host.http_client.send(request);
         maybe!(async {
@@ -425,6 +432,7 @@ impl ExtensionImports for WasmState {
         file_type: DownloadedFileType,
     ) -> wasmtime::Result<Result<(), String>> {
         maybe!(async {
+            self.check_url(&url)?;
             let path = PathBuf::from(path);
             let extension_work_dir = self.host.work_dir.join(self.manifest.id.as_ref());
 
@@ -1,3 +1,4 @@
+use crate::extension_editor_command::EditorCommandBuffer;
 use crate::wasm_host::{wit::ToWasmtimeResult, WasmState};
 use ::http_client::{AsyncBody, HttpRequestExt};
 use ::settings::{Settings, WorktreeId};
@@ -32,6 +33,7 @@ wasmtime::component::bindgen!({
     with: {
          "worktree": ExtensionWorktree,
          "key-value-store": ExtensionKeyValueStore,
+         "editor-buffer": ExtensionEditorBuffer,
          "zed:extension/http-client/http-response-stream": ExtensionHttpResponseStream
     },
 });
@@ -44,6 +46,7 @@ mod settings {
 
 pub type ExtensionWorktree = Arc<dyn LspAdapterDelegate>;
 pub type ExtensionKeyValueStore = Arc<IndexedDocsDatabase>;
+pub type ExtensionEditorBuffer = Arc<EditorCommandBuffer>;
 pub type ExtensionHttpResponseStream = Arc<Mutex<::http_client::Response<AsyncBody>>>;
 
 pub fn linker() -> &'static Linker<WasmState> {
@@ -69,6 +72,53 @@ impl HostKeyValueStore for WasmState {
     }
 }
 
+#[async_trait]
+impl HostEditorBuffer for WasmState {
+    async fn text(&mut self, buffer: Resource<ExtensionEditorBuffer>) -> wasmtime::Result<String> {
+        let buffer = self.table.get(&buffer)?;
+        Ok(buffer.text.clone())
+    }
+
+    async fn selections(
+        &mut self,
+        buffer: Resource<ExtensionEditorBuffer>,
+    ) -> wasmtime::Result<Vec<Range>> {
+        let buffer = self.table.get(&buffer)?;
+        Ok(buffer
+            .selections
+            .iter()
+            .map(|selection| Range {
+                start: selection.start as u32,
+                end: selection.end as u32,
+            })
+            .collect())
+    }
+
+    async fn path(
+        &mut self,
+        buffer: Resource<ExtensionEditorBuffer>,
+    ) -> wasmtime::Result<Option<String>> {
+        let buffer = self.table.get(&buffer)?;
+        Ok(buffer.path.clone())
+    }
+
+    async fn language_name(
+        &mut self,
+        buffer: Resource<ExtensionEditorBuffer>,
+    ) -> wasmtime::Result<Option<String>> {
+        let buffer = self.table.get(&buffer)?;
+        Ok(buffer
+            .language_name
+            .as_ref()
+            .map(|language_name| language_name.to_string()))
+    }
+
+    fn drop(&mut self, _buffer: Resource<ExtensionEditorBuffer>) -> Result<()> {
+        // We only ever hand out borrows of editor buffers.
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl HostWorktree for WasmState {
     async fn id(
@@ -92,6 +142,9 @@ impl HostWorktree for WasmState {
         delegate: Resource<Arc<dyn LspAdapterDelegate>>,
         path: String,
     ) -> wasmtime::Result<Result<String, String>> {
+        if let Err(error) = self.check_file_read(&path) {
+            return Err(error).to_wasmtime_result();
+        }
         let delegate = self.table.get(&delegate)?;
         Ok(delegate
             .read_text_file(path.into())
@@ -136,6 +189,7 @@ impl http_client::Host for WasmState {
     ) -> wasmtime::Result<Result<http_client::HttpResponse, String>> {
         maybe!(async {
             let url = &request.url;
+            self.check_url(url)?;
             let request = convert_request(&request)?;
             let mut response = self.host.http_client.send(request).await?;
 
@@ -152,6 +206,9 @@ impl http_client::Host for WasmState {
         &mut self,
         request: http_client::HttpRequest,
     ) -> wasmtime::Result<Result<Resource<ExtensionHttpResponseStream>, String>> {
+        if let Err(error) = self.check_url(&request.url) {
+            return Err(error).to_wasmtime_result();
+        }
         let request = convert_request(&request)?;
         let response = self.host.http_client.send(request);
         maybe!(async {
@@ -267,6 +324,9 @@ impl nodejs::Host for WasmState {
         &mut self,
         package_name: String,
     ) -> wasmtime::Result<Result<String, String>> {
+        if let Err(error) = self.check_process("npm") {
+            return Err(error).to_wasmtime_result();
+        }
         self.host
             .node_runtime
             .npm_package_latest_version(&package_name)
@@ -290,6 +350,9 @@ impl nodejs::Host for WasmState {
         package_name: String,
         version: String,
     ) -> wasmtime::Result<Result<(), String>> {
+        if let Err(error) = self.check_process("npm") {
+            return Err(error).to_wasmtime_result();
+        }
         self.host
             .node_runtime
             .npm_install_packages(&self.work_dir(), &[(&package_name, &version)])
@@ -319,6 +382,10 @@ impl From<::http_client::github::GithubReleaseAsset> for github::GithubReleaseAs
     }
 }
 
+/// The URL that GitHub releases are fetched from, for checking the extension's
+/// network capabilities.
+const GITHUB_API_URL: &str = "https://api.github.com/";
+
 #[async_trait]
 impl github::Host for WasmState {
     async fn latest_github_release(
@@ -327,6 +394,7 @@ impl github::Host for WasmState {
         options: github::GithubReleaseOptions,
     ) -> wasmtime::Result<Result<github::GithubRelease, String>> {
         maybe!(async {
+            self.check_url(GITHUB_API_URL)?;
             let release = ::http_client::github::latest_github_release(
                 &repo,
                 options.require_assets,
@@ -346,6 +414,7 @@ impl github::Host for WasmState {
         tag: String,
     ) -> wasmtime::Result<Result<github::GithubRelease, String>> {
         maybe!(async {
+            self.check_url(GITHUB_API_URL)?;
             let release = ::http_client::github::get_release_by_tag_name(
                 &repo,
                 &tag,
@@ -382,6 +451,26 @@ impl platform::Host for WasmState {
 #[async_trait]
 impl slash_command::Host for WasmState {}
 
+#[async_trait]
+impl tasks::Host for WasmState {}
+
+#[async_trait]
+impl editor::Host for WasmState {}
+
+impl From<tasks::TaskTemplate> for ::task::TaskTemplate {
+    fn from(value: tasks::TaskTemplate) -> Self {
+        Self {
+            label: value.label,
+            command: value.command,
+            args: value.args,
+            env: value.env.into_iter().collect(),
+            cwd: value.cwd,
+            tags: value.tags,
+            ..Default::default()
+        }
+    }
+}
+
 #[async_trait]
 impl ExtensionImports for WasmState {
     async fn get_settings(
@@ -471,6 +560,7 @@ impl ExtensionImports for WasmState {
         file_type: DownloadedFileType,
     ) -> wasmtime::Result<Result<(), String>> {
         maybe!(async {
+            self.check_url(&url)?;
             let path = PathBuf::from(path);
             let extension_work_dir = self.host.work_dir.join(self.manifest.id.as_ref());
 
@@ -15,6 +15,7 @@ pub use serde_json;
 // that we may want to shadow to provide a cleaner Rust API.
 pub use wit::{
     download_file, make_file_executable,
+    zed::extension::editor::{BufferEdit, EditorCommandOutput, EditorContent},
     zed::extension::github::{
         github_release_by_tag_name, latest_github_release, GithubRelease, GithubReleaseAsset,
         GithubReleaseOptions,
@@ -27,8 +28,9 @@ pub use wit::{
     zed::extension::slash_command::{
         SlashCommand, SlashCommandArgumentCompletion, SlashCommandOutput, SlashCommandOutputSection,
     },
-    CodeLabel, CodeLabelSpan, CodeLabelSpanLiteral, Command, DownloadedFileType, EnvVars,
-    KeyValueStore, LanguageServerInstallationStatus, Range, Worktree,
+    zed::extension::tasks::TaskTemplate,
+    CodeLabel, CodeLabelSpan, CodeLabelSpanLiteral, Command, DownloadedFileType, EditorBuffer,
+    EnvVars, KeyValueStore, LanguageServerInstallationStatus, Range, Worktree,
 };
 
 // Undocumented WIT re-exports.
@@ -42,7 +44,7 @@ pub use wit::Guest;
 /// Language Server Protocol (LSP).
 pub mod lsp {
     pub use crate::wit::zed::extension::lsp::{
-        Completion, CompletionKind, InsertTextFormat, Symbol, SymbolKind,
+        Completion, CompletionKind, InsertTextFormat, Position, RequestLocation, Symbol, SymbolKind,
     };
 }
 
@@ -147,6 +149,72 @@ pub trait Extension: Send + Sync {
     ) -> Result<(), String> {
         Err("`index_docs` not implemented".to_string())
     }
+
+    /// Returns the task templates to offer for files in the specified language.
+    ///
+    /// The language must be listed under `context_providers` in the extension's manifest.
+    fn task_templates(&self, _language_name: String) -> Result<Vec<TaskTemplate>, String> {
+        Ok(Vec::new())
+    }
+
+    /// Returns additional variables for resolving tasks in the specified language,
+    /// given the variables Zed has gathered for the location a task is spawned from.
+    ///
+    /// The returned variables are available to task templates as `$ZED_CUSTOM_<name>`.
+    fn task_context_variables(
+        &self,
+        _language_name: String,
+        _variables: EnvVars,
+    ) -> Result<EnvVars, String> {
+        Ok(Vec::new())
+    }
+
+    /// Runs the specified editor command on the buffer of the active editor.
+    ///
+    /// The command must be listed under `editor_commands` in the extension's manifest.
+    fn run_editor_command(
+        &self,
+        _command_name: String,
+        _buffer: &EditorBuffer,
+    ) -> Result<EditorCommandOutput, String> {
+        Err("`run_editor_command` not implemented".to_string())
+    }
+
+    /// Returns the JSON-encoded parameters of the specified custom request or
+    /// notification, to send to the language server at the given location.
+    ///
+    /// The request must be listed under `requests` in the language server's
+    /// entry in the extension's manifest. By default, the parameters are the
+    /// `TextDocumentPositionParams` of the location, which many custom requests
+    /// take.
+    fn language_server_request_params(
+        &self,
+        _language_server_id: &LanguageServerId,
+        _request_name: String,
+        location: lsp::RequestLocation,
+        _buffer: &EditorBuffer,
+    ) -> Result<String, String> {
+        Ok(serde_json::json!({
+            "textDocument": { "uri": location.uri },
+            "position": {
+                "line": location.position.line,
+                "character": location.position.character,
+            },
+        })
+        .to_string())
+    }
+
+    /// Returns the output of the specified custom request, given the language
+    /// server's JSON-encoded response.
+    fn language_server_request_output(
+        &self,
+        _language_server_id: &LanguageServerId,
+        _request_name: String,
+        _response: String,
+        _buffer: &EditorBuffer,
+    ) -> Result<EditorCommandOutput, String> {
+        Err("`language_server_request_output` not implemented".to_string())
+    }
 }
 
 /// Registers the provided type as a Zed extension.
@@ -281,6 +349,54 @@ impl wit::Guest for Component {
     ) -> Result<(), String> {
         extension().index_docs(provider, package, database)
     }
+
+    fn task_templates(language_name: String) -> Result<Vec<TaskTemplate>, String> {
+        extension().task_templates(language_name)
+    }
+
+    fn task_context_variables(
+        language_name: String,
+        variables: EnvVars,
+    ) -> Result<EnvVars, String> {
+        extension().task_context_variables(language_name, variables)
+    }
+
+    fn run_editor_command(
+        command_name: String,
+        buffer: &EditorBuffer,
+    ) -> Result<EditorCommandOutput, String> {
+        extension().run_editor_command(command_name, buffer)
+    }
+
+    fn language_server_request_params(
+        language_server_id: String,
+        request_name: String,
+        location: lsp::RequestLocation,
+        buffer: &EditorBuffer,
+    ) -> Result<String, String> {
+        let language_server_id = LanguageServerId(language_server_id);
+        extension().language_server_request_params(
+            &language_server_id,
+            request_name,
+            location,
+            buffer,
+        )
+    }
+
+    fn language_server_request_output(
+        language_server_id: String,
+        request_name: String,
+        response: String,
+        buffer: &EditorBuffer,
+    ) -> Result<EditorCommandOutput, String> {
+        let language_server_id = LanguageServerId(language_server_id);
+        extension().language_server_request_output(
+            &language_server_id,
+            request_name,
+            response,
+            buffer,
+        )
+    }
 }
 
 /// The ID of a language server.
@@ -0,0 +1,525 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use assets::Assets;
+use language::LanguageConfig;
+use theme::ThemeRegistry;
+use tree_sitter::{Language, Parser, Point, Query, QueryCursor, WasmStore};
+
+/// Returns the syntax styles that every theme bundled with Zed defines.
+pub(crate) fn theme_syntax_keys() -> Vec<String> {
+    let registry = ThemeRegistry::new(Box::new(Assets));
+    // Only consider the bundled themes, not the fallback ones.
+    registry.clear();
+    registry.load_bundled_themes();
+
+    let mut keys: Option<HashSet<String>> = None;
+    for name in registry.list_names(false) {
+        let Some(theme) = registry.get(&name).ok() else {
+            continue;
+        };
+        let theme_keys = theme
+            .syntax()
+            .highlights
+            .iter()
+            .map(|(key, _)| key.clone())
+            .collect::<HashSet<_>>();
+        keys = Some(match keys {
+            Some(keys) => keys.intersection(&theme_keys).cloned().collect(),
+            None => theme_keys,
+        });
+    }
+
+    let mut keys = keys.unwrap_or_default().into_iter().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// A language provided by the extension, along with the grammar and
+/// highlights query it was loaded with.
+pub(crate) struct TestLanguage {
+    pub config: LanguageConfig,
+    pub grammar: Option<Language>,
+    pub highlights_query: Option<Query>,
+}
+
+/// Returns the captures of a highlights query that don't match any syntax
+/// style of Zed's themes, and so leave the text they capture unstyled.
+///
+/// Like in Zed, a capture matches a style if it contains every part of its
+/// name, so `@function.method.call` is styled as `function`.
+pub(crate) fn unknown_highlight_captures<'a>(
+    query: &'a Query,
+    theme_syntax_keys: &[String],
+) -> Vec<&'a str> {
+    query
+        .capture_names()
+        .iter()
+        .copied()
+        .filter(|capture_name| {
+            // Captures starting with an underscore are only used by predicates.
+            if capture_name.starts_with('_') {
+                return false;
+            }
+            let capture_parts = capture_name.split('.').collect::<Vec<_>>();
+            !theme_syntax_keys
+                .iter()
+                .any(|key| key.split('.').all(|part| capture_parts.contains(&part)))
+        })
+        .collect()
+}
+
+/// Runs the corpus tests in the `test/corpus` directory of the extension and
+/// the highlight tests in its `test/highlight` directory.
+pub(crate) fn run_tests(
+    extension_path: &Path,
+    engine: &wasmtime::Engine,
+    grammars: &HashMap<String, Language>,
+    languages: &[TestLanguage],
+) -> Result<()> {
+    let test_dir = extension_path.join("test");
+    if !test_dir.is_dir() {
+        return Ok(());
+    }
+
+    let mut parser = Parser::new();
+    parser.set_wasm_store(WasmStore::new(engine)?)?;
+
+    let mut test_count = 0;
+    let mut failures = Vec::new();
+
+    let corpus_dir = test_dir.join("corpus");
+    for (grammar_name, grammar) in grammars {
+        let mut paths = test_files(&corpus_dir.join(grammar_name))?;
+        // The tests of an extension's only grammar can be placed directly in
+        // the corpus directory.
+        if grammars.len() == 1 {
+            paths.extend(test_files(&corpus_dir)?);
+        }
+        if paths.is_empty() {
+            continue;
+        }
+
+        parser.set_language(grammar)?;
+        for path in paths {
+            let relative_path = path.strip_prefix(extension_path).unwrap_or(&path);
+            let source = fs::read_to_string(&path)?;
+            for test in parse_corpus_tests(&source) {
+                test_count += 1;
+                let tree = parser
+                    .parse(&test.input, None)
+                    .ok_or_else(|| anyhow!("failed to parse test '{}'", test.name))?;
+                let strip_fields = !has_fields(&test.expected);
+                let actual = normalize_sexp(&tree.root_node().to_sexp(), strip_fields);
+                let expected = normalize_sexp(&test.expected, false);
+                if actual != expected {
+                    failures.push(format!(
+                        "{}: {}\n  expected: {expected}\n  actual:   {actual}",
+                        relative_path.display(),
+                        test.name,
+                    ));
+                }
+            }
+        }
+    }
+
+    for path in test_files(&test_dir.join("highlight"))? {
+        let relative_path = path.strip_prefix(extension_path).unwrap_or(&path);
+        let Some(language) = languages
+            .iter()
+            .find(|language| matches_path_suffix(&language.config, &path))
+        else {
+            failures.push(format!(
+                "{}: no language matches the file",
+                relative_path.display()
+            ));
+            continue;
+        };
+        let (Some(grammar), Some(query)) = (&language.grammar, &language.highlights_query) else {
+            failures.push(format!(
+                "{}: language {} has no highlights query",
+                relative_path.display(),
+                language.config.name
+            ));
+            continue;
+        };
+
+        let source = fs::read_to_string(&path)?;
+        let assertions = parse_highlight_assertions(&source, &language.config.line_comments);
+        if assertions.is_empty() {
+            failures.push(format!("{}: no assertions", relative_path.display()));
+            continue;
+        }
+
+        parser.set_language(grammar)?;
+        let tree = parser
+            .parse(&source, None)
+            .with_context(|| format!("failed to parse {}", relative_path.display()))?;
+        let mut cursor = QueryCursor::new();
+        let captures = cursor
+            .captures(query, tree.root_node(), source.as_bytes())
+            .map(|(query_match, capture_ix)| {
+                let capture = query_match.captures[capture_ix];
+                (
+                    capture.node.start_position()..capture.node.end_position(),
+                    query.capture_names()[capture.index as usize],
+                )
+            })
+            .collect::<Vec<(Range<Point>, &str)>>();
+
+        for assertion in assertions {
+            test_count += 1;
+            let capture_names = captures
+                .iter()
+                .filter(|(range, _)| {
+                    range.start <= assertion.position && assertion.position < range.end
+                })
+                .map(|(_, capture_name)| *capture_name)
+                .collect::<Vec<_>>();
+            if capture_names.contains(&assertion.capture_name.as_str()) == assertion.negative {
+                failures.push(format!(
+                    "{}:{}:{}: expected {}@{}, found {}",
+                    relative_path.display(),
+                    assertion.position.row + 1,
+                    assertion.position.column + 1,
+                    if assertion.negative { "no " } else { "" },
+                    assertion.capture_name,
+                    if capture_names.is_empty() {
+                        "no captures".to_string()
+                    } else {
+                        capture_names
+                            .iter()
+                            .map(|capture_name| format!("@{capture_name}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    }
+                ));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "{} of {test_count} tests failed:\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+    log::info!("passed {test_count} tests");
+
+    Ok(())
+}
+
+/// Returns the files in the given directory, sorted by path.
+fn test_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn matches_path_suffix(config: &LanguageConfig, path: &Path) -> bool {
+    let file_name = path.file_name().and_then(|name| name.to_str());
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    config
+        .matcher
+        .path_suffixes
+        .iter()
+        .any(|suffix| Some(suffix.as_str()) == file_name || Some(suffix.as_str()) == extension)
+}
+
+#[derive(Debug, PartialEq)]
+struct CorpusTest {
+    name: String,
+    input: String,
+    expected: String,
+}
+
+/// Parses the tests of a tree-sitter corpus file.
+///
+/// Each test starts with its name between two lines of `=`, followed by its
+/// input, a line of `-` and the expected syntax tree as an S-expression.
+fn parse_corpus_tests(source: &str) -> Vec<CorpusTest> {
+    fn is_rule(line: &str, char: char) -> bool {
+        let line = line.trim_end();
+        line.len() >= 3 && line.chars().all(|c| c == char)
+    }
+
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut tests = Vec::new();
+    let mut ix = 0;
+    while ix < lines.len() {
+        if !is_rule(lines[ix], '=') {
+            ix += 1;
+            continue;
+        }
+        let Some(name_end) = (ix + 1..lines.len()).find(|&ix| is_rule(lines[ix], '=')) else {
+            break;
+        };
+        let name = lines[ix + 1..name_end].join(" ").trim().to_string();
+
+        let body_start = name_end + 1;
+        let body_end = (body_start..lines.len())
+            .find(|&ix| {
+                is_rule(lines[ix], '=') && (ix + 2..lines.len()).any(|end| is_rule(lines[end], '='))
+            })
+            .unwrap_or(lines.len());
+        let Some(divider) = (body_start..body_end)
+            .rev()
+            .find(|&ix| is_rule(lines[ix], '-'))
+        else {
+            ix = body_end;
+            continue;
+        };
+
+        let input = lines[body_start..divider].join("\n");
+        tests.push(CorpusTest {
+            name,
+            input: format!("{}\n", input.trim_matches('\n')),
+            expected: lines[divider + 1..body_end].join("\n").trim().to_string(),
+        });
+        ix = body_end;
+    }
+    tests
+}
+
+fn has_fields(sexp: &str) -> bool {
+    sexp.split_whitespace().any(|token| token.ends_with(':'))
+}
+
+/// Normalizes the whitespace of an S-expression, optionally removing its field
+/// names, so that it can be compared with another one.
+fn normalize_sexp(sexp: &str, strip_fields: bool) -> String {
+    sexp.split_whitespace()
+        .filter(|token| !(strip_fields && token.ends_with(':')))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" )", ")")
+}
+
+#[derive(Debug, PartialEq)]
+struct HighlightAssertion {
+    position: Point,
+    capture_name: String,
+    negative: bool,
+}
+
+/// Parses the assertions of a highlight test.
+///
+/// Assertions are comments below the line they test. A `^` asserts the
+/// capture of the text in its column, and `<-` that of the text in the column
+/// the comment starts in:
+///
+/// ```text
+/// let x = 1;
+/// //  ^ variable
+/// // <- keyword
+/// ```
+///
+/// A capture name prefixed with `!` asserts that the text isn't captured with
+/// that name.
+fn parse_highlight_assertions<T: AsRef<str>>(
+    source: &str,
+    line_comments: &[T],
+) -> Vec<HighlightAssertion> {
+    let mut assertions = Vec::new();
+    let mut tested_row = None;
+    for (row, line) in source.lines().enumerate() {
+        let comment_start = line.len() - line.trim_start().len();
+        let comment_body = line_comments.iter().find_map(|prefix| {
+            line[comment_start..]
+                .strip_prefix(prefix.as_ref().trim_end())
+                .map(|body| (line.len() - body.len(), body))
+        });
+
+        let mut columns = Vec::new();
+        let mut capture_name = "";
+        if let Some((body_start, body)) = comment_body {
+            let trimmed_body = body.trim_start();
+            let assertion_start = body_start + body.len() - trimmed_body.len();
+            if let Some(rest) = trimmed_body.strip_prefix("<-") {
+                columns.push(comment_start);
+                capture_name = rest.trim();
+            } else if trimmed_body.starts_with('^') {
+                let carets = trimmed_body.trim_start_matches(['^', ' ']);
+                let carets_len = trimmed_body.len() - carets.len();
+                columns.extend(
+                    trimmed_body[..carets_len]
+                        .match_indices('^')
+                        .map(|(ix, _)| assertion_start + ix),
+                );
+                capture_name = carets.trim();
+            }
+        }
+
+        if columns.is_empty() || capture_name.is_empty() {
+            tested_row = Some(row);
+            continue;
+        }
+        let Some(tested_row) = tested_row else {
+            continue;
+        };
+        let (negative, capture_name) = match capture_name.strip_prefix('!') {
+            Some(capture_name) => (true, capture_name),
+            None => (false, capture_name),
+        };
+        for column in columns {
+            assertions.push(HighlightAssertion {
+                position: Point::new(tested_row, column),
+                capture_name: capture_name.to_string(),
+                negative,
+            });
+        }
+    }
+    assertions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_syntax_keys() {
+        let keys = theme_syntax_keys();
+        for key in ["comment", "function", "keyword", "string", "type"] {
+            assert!(keys.iter().any(|k| k == key), "missing syntax key {key}");
+        }
+    }
+
+    #[test]
+    fn test_parse_corpus_tests() {
+        let source = "\
+==================
+Function
+==================
+
+fn main() {}
+
+---
+
+(source_file
+  (function_item
+    name: (identifier)
+    parameters: (parameters)
+    body: (block)))
+
+==================
+Empty file
+==================
+---
+
+(source_file)
+";
+
+        assert_eq!(
+            parse_corpus_tests(source),
+            vec![
+                CorpusTest {
+                    name: "Function".into(),
+                    input: "fn main() {}\n".into(),
+                    expected: "(source_file\n  (function_item\n    name: (identifier)\n    parameters: (parameters)\n    body: (block)))".into(),
+                },
+                CorpusTest {
+                    name: "Empty file".into(),
+                    input: "\n".into(),
+                    expected: "(source_file)".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_sexp() {
+        let expected = "(source_file\n  (function_item\n    (identifier)\n  )\n)";
+        let actual = "(source_file (function_item name: (identifier)))";
+
+        assert!(!has_fields(expected));
+        assert!(has_fields(actual));
+        assert_eq!(
+            normalize_sexp(expected, false),
+            "(source_file (function_item (identifier)))"
+        );
+        assert_eq!(
+            normalize_sexp(actual, true),
+            normalize_sexp(expected, false)
+        );
+        assert_eq!(
+            normalize_sexp(actual, false),
+            "(source_file (function_item name: (identifier)))"
+        );
+    }
+
+    #[test]
+    fn test_parse_highlight_assertions() {
+        let source = "\
+// <- ignored
+fn main() {
+// <- keyword
+// ^ !keyword
+//  ^^^^ function
+    let x = 1;
+    // <- !variable
+    //  ^ variable
+";
+
+        assert_eq!(
+            parse_highlight_assertions(source, &["// "]),
+            vec![
+                HighlightAssertion {
+                    position: Point::new(1, 0),
+                    capture_name: "keyword".into(),
+                    negative: false,
+                },
+                HighlightAssertion {
+                    position: Point::new(1, 3),
+                    capture_name: "keyword".into(),
+                    negative: true,
+                },
+                HighlightAssertion {
+                    position: Point::new(1, 4),
+                    capture_name: "function".into(),
+                    negative: false,
+                },
+                HighlightAssertion {
+                    position: Point::new(1, 5),
+                    capture_name: "function".into(),
+                    negative: false,
+                },
+                HighlightAssertion {
+                    position: Point::new(1, 6),
+                    capture_name: "function".into(),
+                    negative: false,
+                },
+                HighlightAssertion {
+                    position: Point::new(1, 7),
+                    capture_name: "function".into(),
+                    negative: false,
+                },
+                HighlightAssertion {
+                    position: Point::new(5, 4),
+                    capture_name: "variable".into(),
+                    negative: true,
+                },
+                HighlightAssertion {
+                    position: Point::new(5, 8),
+                    capture_name: "variable".into(),
+                    negative: false,
+                },
+            ]
+        );
+    }
+}
@@ -1,3 +1,6 @@
+mod language_tests;
+mod mirror;
+
 use std::{
     collections::HashMap,
     env, fs,
@@ -8,28 +11,42 @@ use std::{
 
 use ::fs::{copy_recursive, CopyOptions, Fs, RealFs};
 use anyhow::{anyhow, bail, Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use extension::{
     extension_builder::{CompileExtensionOptions, ExtensionBuilder},
     ExtensionManifest,
 };
 use language::LanguageConfig;
+use language_tests::TestLanguage;
 use reqwest_client::ReqwestClient;
 use theme::ThemeRegistry;
 use tree_sitter::{Language, Query, WasmStore};
 
 #[derive(Parser, Debug)]
-#[command(name = "zed-extension")]
+#[command(
+    name = "zed-extension",
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
 struct Args {
     /// The path to the extension directory
-    #[arg(long)]
-    source_dir: PathBuf,
+    #[arg(long, required = true)]
+    source_dir: Option<PathBuf>,
     /// The output directory to place the packaged extension.
-    #[arg(long)]
-    output_dir: PathBuf,
+    #[arg(long, required = true)]
+    output_dir: Option<PathBuf>,
     /// The path to a directory where build dependencies are downloaded
-    #[arg(long)]
-    scratch_dir: PathBuf,
+    #[arg(long, required = true)]
+    scratch_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// Creates or updates a static extension registry, which Zed can install
+    /// extensions from using the `extension_registry` setting.
+    Mirror(mirror::MirrorArgs),
 }
 
 #[tokio::main]
@@ -38,21 +55,29 @@ async fn main() -> Result<()> {
 
     let args = Args::parse();
     let fs = Arc::new(RealFs::default());
+
+    if let Some(CliCommand::Mirror(args)) = args.command {
+        return mirror::mirror(args, fs).await;
+    }
+    let (Some(source_dir), Some(output_dir), Some(scratch_dir)) =
+        (args.source_dir, args.output_dir, args.scratch_dir)
+    else {
+        bail!("--source-dir, --output-dir and --scratch-dir are required");
+    };
+
     let engine = wasmtime::Engine::default();
     let mut wasm_store = WasmStore::new(&engine)?;
 
-    let extension_path = args
-        .source_dir
+    let extension_path = source_dir
         .canonicalize()
         .context("failed to canonicalize source_dir")?;
-    let scratch_dir = args
-        .scratch_dir
+    let scratch_dir = scratch_dir
         .canonicalize()
         .context("failed to canonicalize scratch_dir")?;
-    let output_dir = if args.output_dir.is_relative() {
-        env::current_dir()?.join(&args.output_dir)
+    let output_dir = if output_dir.is_relative() {
+        env::current_dir()?.join(&output_dir)
     } else {
-        args.output_dir
+        output_dir
     };
 
     log::info!("loading extension manifest");
@@ -60,13 +85,7 @@ async fn main() -> Result<()> {
 
     log::info!("compiling extension");
 
-    let user_agent = format!(
-        "Zed Extension CLI/{} ({}; {})",
-        env!("CARGO_PKG_VERSION"),
-        std::env::consts::OS,
-        std::env::consts::ARCH
-    );
-    let http_client = Arc::new(ReqwestClient::user_agent(&user_agent)?);
+    let http_client = Arc::new(ReqwestClient::user_agent(&user_agent())?);
 
     let builder = ExtensionBuilder::new(http_client, scratch_dir);
     builder
@@ -79,7 +98,8 @@ async fn main() -> Result<()> {
         .context("failed to compile extension")?;
 
     let grammars = test_grammars(&manifest, &extension_path, &mut wasm_store)?;
-    test_languages(&manifest, &extension_path, &grammars)?;
+    let languages = test_languages(&manifest, &extension_path, &grammars)?;
+    language_tests::run_tests(&extension_path, &engine, &grammars, &languages)?;
     test_themes(&manifest, &extension_path, fs.clone()).await?;
 
     let archive_dir = output_dir.join("archive");
@@ -100,7 +120,25 @@ async fn main() -> Result<()> {
         );
     }
 
-    let manifest_json = serde_json::to_string(&rpc::ExtensionApiManifest {
+    let manifest_json = serde_json::to_string(&api_manifest(manifest)?)?;
+    fs::remove_dir_all(&archive_dir)?;
+    fs::write(output_dir.join("manifest.json"), manifest_json.as_bytes())?;
+
+    Ok(())
+}
+
+fn user_agent() -> String {
+    format!(
+        "Zed Extension CLI/{} ({}; {})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+/// Returns the manifest of an extension, as it is served by the extensions API.
+fn api_manifest(manifest: ExtensionManifest) -> Result<rpc::ExtensionApiManifest> {
+    Ok(rpc::ExtensionApiManifest {
         name: manifest.name,
         version: manifest.version,
         description: manifest.description,
@@ -110,11 +148,7 @@ async fn main() -> Result<()> {
             .repository
             .ok_or_else(|| anyhow!("missing repository in extension manifest"))?,
         wasm_api_version: manifest.lib.version.map(|version| version.to_string()),
-    })?;
-    fs::remove_dir_all(&archive_dir)?;
-    fs::write(output_dir.join("manifest.json"), manifest_json.as_bytes())?;
-
-    Ok(())
+    })
 }
 
 async fn copy_extension_resources(
@@ -220,7 +254,10 @@ fn test_languages(
     manifest: &ExtensionManifest,
     extension_path: &Path,
     grammars: &HashMap<String, Language>,
-) -> Result<()> {
+) -> Result<Vec<TestLanguage>> {
+    let theme_syntax_keys = language_tests::theme_syntax_keys();
+    let mut languages = Vec::new();
+    let mut query_errors = Vec::new();
     for relative_language_dir in &manifest.languages {
         let language_dir = extension_path.join(relative_language_dir);
         let config_path = language_dir.join("config.toml");
@@ -236,10 +273,12 @@ fn test_languages(
             None
         };
 
-        let query_entries = fs::read_dir(&language_dir)?;
-        for entry in query_entries {
-            let entry = entry?;
-            let query_path = entry.path();
+        let mut highlights_query = None;
+        let mut query_paths = fs::read_dir(&language_dir)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        query_paths.sort();
+        for query_path in query_paths {
             if query_path.extension() == Some("scm".as_ref()) {
                 let grammar = grammar.ok_or_else(|| {
                     anyhow!(
@@ -249,15 +288,45 @@ fn test_languages(
                     )
                 })?;
 
+                let relative_query_path = query_path
+                    .strip_prefix(extension_path)
+                    .unwrap_or(&query_path);
                 let query_source = fs::read_to_string(&query_path)?;
-                let _query = Query::new(grammar, &query_source)?;
+                let query = match Query::new(grammar, &query_source) {
+                    Ok(query) => query,
+                    Err(error) => {
+                        query_errors.push(format!("{}: {error}", relative_query_path.display()));
+                        continue;
+                    }
+                };
+
+                if query_path.file_name() == Some("highlights.scm".as_ref()) {
+                    for capture_name in
+                        language_tests::unknown_highlight_captures(&query, &theme_syntax_keys)
+                    {
+                        log::warn!(
+                            "{}: @{capture_name} doesn't match any syntax style of Zed's themes",
+                            relative_query_path.display()
+                        );
+                    }
+                    highlights_query = Some(query);
+                }
             }
         }
 
         log::info!("loaded language {}", config.name);
+        languages.push(TestLanguage {
+            grammar: grammar.cloned(),
+            config,
+            highlights_query,
+        });
     }
 
-    Ok(())
+    if !query_errors.is_empty() {
+        bail!("invalid queries:\n{}", query_errors.join("\n"));
+    }
+
+    Ok(languages)
 }
 
 async fn test_themes(
@@ -0,0 +1,165 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+    sync::Arc,
+};
+
+use ::fs::Fs;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use extension::{
+    extension_registry::{
+        self, archive_checksum, RegistryExtension, RegistryIndex, REGISTRY_INDEX_FILE_NAME,
+    },
+    ExtensionManifest,
+};
+use reqwest_client::ReqwestClient;
+use rpc::{ExtensionMetadata, GetExtensionsResponse};
+use semantic_version::SemanticVersion;
+use url::Url;
+
+#[derive(clap::Args, Debug)]
+pub struct MirrorArgs {
+    /// The directory of the registry to create or update.
+    #[arg(long)]
+    output_dir: PathBuf,
+    /// A packaged extension archive (`archive.tar.gz`) to add to the registry.
+    #[arg(long = "archive")]
+    archives: Vec<PathBuf>,
+    /// An extension to download from the extensions API and add to the
+    /// registry, as `<id>` for its latest version or `<id>@<version>`.
+    #[arg(long = "extension")]
+    extensions: Vec<String>,
+    /// The URL of the extensions API to download extensions from.
+    #[arg(long, default_value = "https://api.zed.dev")]
+    api_url: Url,
+}
+
+/// Adds extensions to the static extension registry in the output directory,
+/// creating it if needed.
+pub async fn mirror(args: MirrorArgs, fs: Arc<dyn Fs>) -> Result<()> {
+    if args.archives.is_empty() && args.extensions.is_empty() {
+        bail!("no extensions to mirror; pass --archive or --extension");
+    }
+
+    fs::create_dir_all(&args.output_dir).context("failed to create output dir")?;
+    let index_path = args.output_dir.join(REGISTRY_INDEX_FILE_NAME);
+    let mut index = if index_path.exists() {
+        let index_json = fs::read(&index_path).context("failed to read registry index")?;
+        serde_json::from_slice::<RegistryIndex>(&index_json)
+            .context("failed to parse registry index")?
+    } else {
+        RegistryIndex::default()
+    };
+
+    for archive_path in &args.archives {
+        log::info!("adding {archive_path:?}");
+        let archive = fs::read(archive_path)
+            .with_context(|| format!("failed to read archive {archive_path:?}"))?;
+        let manifest = load_archive_manifest(archive_path, fs.clone()).await?;
+        let metadata = ExtensionMetadata {
+            id: manifest.id.clone(),
+            manifest: crate::api_manifest(manifest)?,
+            published_at: Utc::now(),
+            download_count: 0,
+        };
+        add_extension(&mut index, &args.output_dir, metadata, &archive)?;
+    }
+
+    if !args.extensions.is_empty() {
+        let http_client = ReqwestClient::user_agent(&crate::user_agent())?;
+        for extension in &args.extensions {
+            let (extension_id, version) = match extension.split_once('@') {
+                Some((extension_id, version)) => (extension_id, Some(version)),
+                None => (extension.as_str(), None),
+            };
+
+            let versions_url = args.api_url.join(&format!("extensions/{extension_id}"))?;
+            let versions = extension_registry::download(&versions_url, fs.as_ref(), &http_client)
+                .await
+                .with_context(|| format!("failed to fetch versions of {extension_id}"))?;
+            let versions = serde_json::from_slice::<GetExtensionsResponse>(&versions)
+                .with_context(|| format!("failed to parse versions of {extension_id}"))?
+                .data;
+            let metadata = match version {
+                Some(version) => versions
+                    .into_iter()
+                    .find(|metadata| metadata.manifest.version.as_ref() == version),
+                None => versions.into_iter().max_by_key(|metadata| {
+                    SemanticVersion::from_str(&metadata.manifest.version).ok()
+                }),
+            }
+            .ok_or_else(|| anyhow!("extension {extension} not found"))?;
+
+            let version = metadata.manifest.version.clone();
+            if index.get(extension_id, &version).is_some()
+                && args
+                    .output_dir
+                    .join(extension_registry::archive_path(extension_id, &version))
+                    .exists()
+            {
+                log::info!("{extension_id} {version} is already mirrored");
+                continue;
+            }
+
+            log::info!("downloading {extension_id} {version}");
+            let archive_url = args
+                .api_url
+                .join(&format!("extensions/{extension_id}/{version}/download"))?;
+            let archive = extension_registry::download(&archive_url, fs.as_ref(), &http_client)
+                .await
+                .with_context(|| format!("failed to download {extension_id} {version}"))?;
+            add_extension(&mut index, &args.output_dir, metadata, &archive)?;
+        }
+    }
+
+    let index_json = serde_json::to_string_pretty(&index)?;
+    fs::write(&index_path, index_json).context("failed to write registry index")?;
+
+    Ok(())
+}
+
+/// Loads the manifest of a packaged extension.
+async fn load_archive_manifest(archive_path: &Path, fs: Arc<dyn Fs>) -> Result<ExtensionManifest> {
+    let unpack_dir = tempfile::tempdir()?;
+    let tar_output = Command::new("tar")
+        .arg("-xzf")
+        .arg(archive_path)
+        .arg("-C")
+        .arg(unpack_dir.path())
+        .output()
+        .context("failed to run tar")?;
+    if !tar_output.status.success() {
+        bail!(
+            "failed to unpack {archive_path:?}: {}",
+            String::from_utf8_lossy(&tar_output.stderr)
+        );
+    }
+
+    ExtensionManifest::load(fs, unpack_dir.path()).await
+}
+
+/// Writes an extension's archive to the registry and adds it to the index.
+fn add_extension(
+    index: &mut RegistryIndex,
+    registry_dir: &Path,
+    metadata: ExtensionMetadata,
+    archive: &[u8],
+) -> Result<()> {
+    let extension = RegistryExtension {
+        metadata,
+        sha256: archive_checksum(archive),
+    };
+
+    let archive_path = registry_dir.join(extension.archive_path());
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&archive_path, archive)
+        .with_context(|| format!("failed to write archive {archive_path:?}"))?;
+
+    index.insert(extension);
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use std::borrow::Cow;
+
+use anyhow::Result;
+use editor::{Editor, MultiBuffer};
+use extension::{
+    EditorCommandBuffer, EditorCommandContent, EditorCommandOutput, ExtensionStore,
+    RunEditorCommand, RunLanguageServerRequest,
+};
+use gpui::{Context as _, Model, Task, View, ViewContext, VisualContext as _};
+use language::{point_to_lsp, Buffer, BufferSnapshot, PointUtf16};
+use util::ResultExt as _;
+use workspace::{
+    notifications::{NotificationId, Toast},
+    Workspace,
+};
+
+struct EditorCommandToast;
+
+/// Runs an extension's editor command on the buffer of the active editor,
+/// applying the edits it returns and showing its message.
+pub(crate) fn run_editor_command(
+    workspace: &mut Workspace,
+    action: &RunEditorCommand,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some((editor, buffer)) = active_buffer(workspace, cx) else {
+        return;
+    };
+
+    let snapshot = buffer.read(cx).snapshot();
+    let input = editor_command_buffer(&editor, &buffer, &snapshot, cx);
+    let output = ExtensionStore::global(cx).update(cx, |store, cx| {
+        store.run_editor_command(&action.extension_id, &action.command_name, input, cx)
+    });
+    apply_output(output, buffer, snapshot, cx);
+}
+
+/// Sends an extension's custom request or notification to its language server
+/// for the buffer of the active editor, at the newest cursor, and applies the
+/// output the extension produces from the response.
+pub(crate) fn run_language_server_request(
+    workspace: &mut Workspace,
+    action: &RunLanguageServerRequest,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some((editor, buffer)) = active_buffer(workspace, cx) else {
+        return;
+    };
+
+    let language_server = workspace
+        .project()
+        .read(cx)
+        .language_servers_for_buffer(buffer.read(cx), cx)
+        .find(|(adapter, _)| adapter.name == action.language_server_id)
+        .map(|(_, language_server)| language_server.clone());
+    let Some(language_server) = language_server else {
+        show_toast(
+            workspace,
+            format!(
+                "{} isn't running for this buffer.",
+                action.language_server_id.0
+            ),
+            cx,
+        );
+        return;
+    };
+    let uri = buffer
+        .read(cx)
+        .file()
+        .and_then(|file| file.as_local())
+        .and_then(|file| lsp::Url::from_file_path(file.abs_path(cx)).ok());
+    let Some(uri) = uri else {
+        show_toast(
+            workspace,
+            "Language server requests can only be sent for local files.",
+            cx,
+        );
+        return;
+    };
+
+    // The selections of a singleton multi-buffer have the same positions as
+    // those of its buffer.
+    let position = editor.read(cx).selections.newest::<PointUtf16>(cx).head();
+    let snapshot = buffer.read(cx).snapshot();
+    let input = editor_command_buffer(&editor, &buffer, &snapshot, cx);
+    let output = ExtensionStore::global(cx).update(cx, |store, cx| {
+        store.run_language_server_request(
+            action,
+            language_server,
+            uri,
+            point_to_lsp(position),
+            input,
+            cx,
+        )
+    });
+    apply_output(output, buffer, snapshot, cx);
+}
+
+/// Returns the active editor and its buffer, if it has a single one.
+fn active_buffer(
+    workspace: &mut Workspace,
+    cx: &mut ViewContext<Workspace>,
+) -> Option<(View<Editor>, Model<Buffer>)> {
+    let editor = workspace.active_item_as::<Editor>(cx)?;
+    let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+        show_toast(
+            workspace,
+            "Extension commands can only be run in editors with a single buffer.",
+            cx,
+        );
+        return None;
+    };
+    Some((editor, buffer))
+}
+
+fn editor_command_buffer(
+    editor: &View<Editor>,
+    buffer: &Model<Buffer>,
+    snapshot: &BufferSnapshot,
+    cx: &mut ViewContext<Workspace>,
+) -> EditorCommandBuffer {
+    // The selections of a singleton multi-buffer have the same offsets as
+    // those of its buffer.
+    let selections = editor
+        .read(cx)
+        .selections
+        .all::<usize>(cx)
+        .into_iter()
+        .map(|selection| selection.range())
+        .collect();
+    let path = buffer.read(cx).file().map(|file| {
+        file.as_local()
+            .map(|file| file.abs_path(cx))
+            .unwrap_or_else(|| file.full_path(cx))
+            .to_string_lossy()
+            .to_string()
+    });
+    EditorCommandBuffer {
+        text: snapshot.text(),
+        selections,
+        path,
+        language_name: snapshot.language().map(|language| language.name()),
+    }
+}
+
+/// Applies the edits an extension returned to the buffer it was run on, shows
+/// its message and opens its content.
+fn apply_output(
+    output: Task<Result<EditorCommandOutput>>,
+    buffer: Model<Buffer>,
+    snapshot: BufferSnapshot,
+    cx: &mut ViewContext<Workspace>,
+) {
+    cx.spawn(|workspace, mut cx| async move {
+        let output = output.await;
+        workspace.update(&mut cx, |workspace, cx| match output {
+            Ok(output) => {
+                if !output.edits.is_empty() {
+                    // The edits are relative to the text the command was run
+                    // on, which may have been edited since.
+                    let edits = output
+                        .edits
+                        .into_iter()
+                        .map(|(range, new_text)| {
+                            (
+                                snapshot.anchor_before(range.start)
+                                    ..snapshot.anchor_after(range.end),
+                                new_text,
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    buffer.update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+                }
+
+                if let Some(message) = output.message {
+                    show_toast(workspace, message, cx);
+                }
+
+                if let Some(content) = output.content {
+                    open_content(workspace, content, cx);
+                }
+            }
+            Err(error) => workspace.show_error(&error, cx),
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Opens the content returned by an extension in a new editor.
+fn open_content(
+    workspace: &mut Workspace,
+    content: EditorCommandContent,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let project = workspace.project().clone();
+    let language = content.language_name.as_ref().map(|language_name| {
+        project
+            .read(cx)
+            .languages()
+            .language_for_name(&language_name.0)
+    });
+    let buffer = project.update(cx, |project, cx| project.create_buffer(cx));
+    cx.spawn(|workspace, mut cx| async move {
+        let buffer = buffer.await?;
+        let language = match language {
+            Some(language) => language.await.log_err(),
+            None => None,
+        };
+        workspace.update(&mut cx, |workspace, cx| {
+            buffer.update(cx, |buffer, cx| {
+                buffer.edit([(0..0, content.text)], None, cx);
+                buffer.set_language(language, cx);
+            });
+            let multibuffer =
+                cx.new_model(|cx| MultiBuffer::singleton(buffer, cx).with_title(content.title));
+            let editor =
+                cx.new_view(|cx| Editor::for_multibuffer(multibuffer, Some(project), true, cx));
+            workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+fn show_toast(
+    workspace: &mut Workspace,
+    message: impl Into<Cow<'static, str>>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    workspace.show_toast(
+        Toast::new(NotificationId::unique::<EditorCommandToast>(), message).autohide(),
+        cx,
+    );
+}
@@ -1,4 +1,5 @@
 mod components;
+mod extension_editor_command;
 mod extension_suggest;
 mod extension_version_selector;
 
@@ -11,12 +12,15 @@ use client::telemetry::Telemetry;
 use client::ExtensionMetadata;
 use collections::{BTreeMap, BTreeSet};
 use editor::{Editor, EditorElement, EditorStyle};
-use extension::{ExtensionManifest, ExtensionOperation, ExtensionStore};
+use extension::{
+    ExtensionCapabilities, ExtensionManifest, ExtensionOperation, ExtensionSettings,
+    ExtensionStore, RunEditorCommand,
+};
 use fuzzy::{match_strings, StringMatchCandidate};
 use gpui::{
     actions, uniform_list, AppContext, EventEmitter, Flatten, FocusableView, InteractiveElement,
-    KeyContext, ParentElement, Render, Styled, Task, TextStyle, UniformListScrollHandle, View,
-    ViewContext, VisualContext, WeakView, WindowContext,
+    KeyContext, ParentElement, PromptLevel, Render, Styled, Task, TextStyle,
+    UniformListScrollHandle, View, ViewContext, VisualContext, WeakView, WindowContext,
 };
 use num_format::{Locale, ToFormattedString};
 use project::DirectoryLister;
@@ -35,7 +39,10 @@ use crate::extension_version_selector::{
     ExtensionVersionSelector, ExtensionVersionSelectorDelegate,
 };
 
-actions!(zed, [Extensions, InstallDevExtension]);
+actions!(
+    zed,
+    [Extensions, InstallDevExtension, InstallLocalExtension]
+);
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(move |workspace: &mut Workspace, cx| {
@@ -93,7 +100,52 @@ pub fn init(cx: &mut AppContext) {
                         Some(())
                     })
                     .detach();
-            });
+            })
+            .register_action(move |workspace, _: &InstallLocalExtension, cx| {
+                let store = ExtensionStore::global(cx);
+                let prompt = workspace.prompt_for_open_path(
+                    gpui::PathPromptOptions {
+                        files: true,
+                        directories: true,
+                        multiple: false,
+                    },
+                    DirectoryLister::Local(workspace.app_state().fs.clone()),
+                    cx,
+                );
+
+                let workspace_handle = cx.view().downgrade();
+                cx.deref_mut()
+                    .spawn(|mut cx| async move {
+                        let extension_path =
+                            match Flatten::flatten(prompt.await.map_err(|e| e.into())) {
+                                Ok(Some(mut paths)) => paths.pop()?,
+                                Ok(None) => return None,
+                                Err(err) => {
+                                    workspace_handle
+                                        .update(&mut cx, |workspace, cx| {
+                                            workspace.show_portal_error(err.to_string(), cx);
+                                        })
+                                        .ok();
+                                    return None;
+                                }
+                            };
+
+                        let install = store
+                            .update(&mut cx, |store, cx| {
+                                store.install_extension_from_path(extension_path, cx)
+                            })
+                            .ok()?;
+                        if let Err(err) = install.await {
+                            workspace_handle
+                                .update(&mut cx, |workspace, cx| workspace.show_error(&err, cx))
+                                .ok();
+                        }
+                        Some(())
+                    })
+                    .detach();
+            })
+            .register_action(extension_editor_command::run_editor_command)
+            .register_action(extension_editor_command::run_language_server_request);
 
         cx.subscribe(workspace.project(), |_, _, event, cx| {
             if let project::Event::LanguageNotFound(buffer) = event {
@@ -101,6 +153,112 @@ pub fn init(cx: &mut AppContext) {
             }
         })
         .detach();
+
+        // Extensions may have been loaded before any workspace was open.
+        prompt_for_extension_capabilities(workspace, cx);
+    })
+    .detach();
+
+    if let Some(extension_store) = ExtensionStore::try_global(cx) {
+        cx.subscribe(&extension_store, |_, event, cx| {
+            if let extension::Event::CapabilitiesRequested = event {
+                let workspace = cx
+                    .active_window()
+                    .and_then(|window| window.downcast::<Workspace>())
+                    .or_else(|| {
+                        cx.windows()
+                            .into_iter()
+                            .find_map(|window| window.downcast::<Workspace>())
+                    });
+                if let Some(workspace) = workspace {
+                    workspace
+                        .update(cx, |workspace, cx| {
+                            prompt_for_extension_capabilities(workspace, cx)
+                        })
+                        .ok();
+                }
+            }
+        })
+        .detach();
+    }
+}
+
+/// Asks the user to approve the capabilities of the Wasm extensions that were
+/// loaded without them, such as when they were installed, one at a time.
+fn prompt_for_extension_capabilities(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    let Some(extension_store) = ExtensionStore::try_global(cx) else {
+        return;
+    };
+    let extension_ids = extension_store.update(cx, |store, _| store.take_capability_requests());
+    if extension_ids.is_empty() {
+        return;
+    }
+
+    let fs = workspace.app_state().fs.clone();
+    cx.spawn(|workspace, mut cx| async move {
+        for extension_id in extension_ids {
+            let Some(manifest) = extension_store
+                .read_with(&cx, |store, _| {
+                    store
+                        .installed_extensions()
+                        .get(&extension_id)
+                        .map(|extension| extension.manifest.clone())
+                })
+                .ok()
+                .flatten()
+            else {
+                continue;
+            };
+            let already_granted = cx
+                .update(|cx| {
+                    ExtensionSettings::get_global(cx)
+                        .extension_capabilities
+                        .contains_key(&extension_id)
+                })
+                .unwrap_or(true);
+            if already_granted {
+                continue;
+            }
+
+            let capabilities = manifest.requested_capabilities();
+            let message = format!("{} requests the following capabilities", manifest.name);
+            let mut detail = capabilities.descriptions().join("\n");
+            if manifest.capabilities.is_none() {
+                detail.insert_str(
+                    0,
+                    "This extension doesn't declare its capabilities, so it asks to be unrestricted.\n\n",
+                );
+            }
+            detail.push_str("\n\nThe extension runs without these capabilities until you allow them.");
+            let Ok(answer) = workspace.update(&mut cx, |_, cx| {
+                cx.prompt(
+                    PromptLevel::Info,
+                    &message,
+                    Some(&detail),
+                    &["Allow", "Restrict"],
+                )
+            }) else {
+                return;
+            };
+
+            let granted_capabilities = match answer.await {
+                Ok(0) => capabilities,
+                Ok(_) => ExtensionCapabilities::default(),
+                Err(_) => continue,
+            };
+            cx.update(|cx| {
+                settings::update_settings_file::<ExtensionSettings>(
+                    fs.clone(),
+                    cx,
+                    move |settings, _| {
+                        settings
+                            .extension_capabilities
+                            .insert(manifest.id.clone(), granted_capabilities);
+                    },
+                );
+            })
+            .ok();
+        }
     })
     .detach();
 }
@@ -264,6 +422,33 @@ impl ExtensionsPage {
         }
     }
 
+    /// Renders the most recent capability or resource limit that the given
+    /// extension exceeded, if any.
+    fn render_extension_violation(
+        extension_id: &str,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<impl IntoElement> {
+        let extension_store = ExtensionStore::global(cx).read(cx);
+        let violation = extension_store.violations(extension_id).last()?;
+
+        Some(
+            h_flex()
+                .gap_2()
+                .child(
+                    Icon::new(IconName::Warning)
+                        .size(IconSize::Small)
+                        .color(Color::Warning),
+                )
+                .child(
+                    div().overflow_x_hidden().text_ellipsis().child(
+                        Label::new(format!("Restricted: {violation}"))
+                            .size(LabelSize::Small)
+                            .color(Color::Warning),
+                    ),
+                ),
+        )
+    }
+
     /// Returns whether a dev extension currently exists for the extension with the given ID.
     fn dev_extension_exists(extension_id: &str, cx: &mut ViewContext<Self>) -> bool {
         let extension_store = ExtensionStore::global(cx).read(cx);
@@ -497,6 +682,7 @@ impl ExtensionsPage {
                         .tooltip(move |cx| Tooltip::text(repository_url.clone(), cx))
                     })),
             )
+            .children(Self::render_extension_violation(&extension.id, cx))
     }
 
     fn render_remote_extension(
@@ -630,6 +816,7 @@ impl ExtensionsPage {
                             ),
                     ),
             )
+            .children(Self::render_extension_violation(&extension.id, cx))
     }
 
     fn render_remote_extension_context_menu(
@@ -1062,12 +1249,34 @@ impl Render for ExtensionsPage {
                             .justify_between()
                             .child(Headline::new("Extensions").size(HeadlineSize::XLarge))
                             .child(
-                                Button::new("install-dev-extension", "Install Dev Extension")
-                                    .style(ButtonStyle::Filled)
-                                    .size(ButtonSize::Large)
-                                    .on_click(|_event, cx| {
-                                        cx.dispatch_action(Box::new(InstallDevExtension))
-                                    }),
+                                h_flex()
+                                    .gap_2()
+                                    .child(
+                                        Button::new(
+                                            "install-local-extension",
+                                            "Install Local Extension",
+                                        )
+                                        .style(ButtonStyle::Subtle)
+                                        .size(ButtonSize::Large)
+                                        .on_click(
+                                            |_event, cx| {
+                                                cx.dispatch_action(Box::new(InstallLocalExtension))
+                                            },
+                                        ),
+                                    )
+                                    .child(
+                                        Button::new(
+                                            "install-dev-extension",
+                                            "Install Dev Extension",
+                                        )
+                                        .style(ButtonStyle::Filled)
+                                        .size(ButtonSize::Large)
+                                        .on_click(
+                                            |_event, cx| {
+                                                cx.dispatch_action(Box::new(InstallDevExtension))
+                                            },
+                                        ),
+                                    ),
                             ),
                     )
                     .child(
@@ -1274,10 +1274,15 @@ impl Image {
                     .svg_renderer()
                     .render_pixmap(&self.bytes, SvgSize::ScaleFactor(1.0))?;
 
-                let buffer =
+                let mut buffer =
                     image::ImageBuffer::from_raw(pixmap.width(), pixmap.height(), pixmap.take())
                         .unwrap();
 
+                // Convert from RGBA to BGRA.
+                for pixel in buffer.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+
                 SmallVec::from_elem(Frame::new(buffer), 1)
             }
         };
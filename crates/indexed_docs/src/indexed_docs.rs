@@ -2,6 +2,10 @@ mod providers;
 mod registry;
 mod store;
 
+pub use crate::providers::go::*;
+pub use crate::providers::local_html::*;
+pub use crate::providers::python::*;
 pub use crate::providers::rustdoc::*;
+pub use crate::providers::typescript::*;
 pub use crate::registry::*;
 pub use crate::store::*;
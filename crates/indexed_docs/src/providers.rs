@@ -1 +1,68 @@
+pub mod go;
+pub mod local_html;
+pub mod python;
 pub mod rustdoc;
+pub mod typescript;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Result;
+use fs::Fs;
+use futures::StreamExt;
+
+/// Returns the paths of all of the files under `root` that satisfy `include`,
+/// in a stable order.
+///
+/// Directories named in `skip_dirs` are not descended into.
+pub(crate) async fn collect_files(
+    fs: &Arc<dyn Fs>,
+    root: &Path,
+    skip_dirs: &[&str],
+    include: impl Fn(&Path) -> bool,
+) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs_to_visit = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs_to_visit.pop() {
+        let mut entries = fs.read_dir(&dir).await?;
+        while let Some(entry) = entries.next().await {
+            let entry = entry?;
+            if fs.is_dir(&entry).await {
+                let is_skipped = entry
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| skip_dirs.contains(&name));
+                if !is_skipped {
+                    dirs_to_visit.push(entry);
+                }
+            } else if include(&entry) {
+                files.push(entry);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the path of `path` relative to `root`, using `/` as the separator
+/// regardless of the platform.
+pub(crate) fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let relative_path = path.strip_prefix(root).ok()?;
+    let components = relative_path
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(components.join("/"))
+}
+
+/// Returns the Markdown for the root entry of a package that has been indexed
+/// as a set of files, listing the keys of its entries.
+pub(crate) fn package_index_markdown(package: &str, keys: &[String]) -> String {
+    let mut markdown = format!("# {package}\n\n");
+    for key in keys {
+        markdown.push_str(&format!("- `{key}`\n"));
+    }
+    markdown
+}
@@ -0,0 +1,198 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use fs::Fs;
+
+use crate::providers::package_index_markdown;
+use crate::{IndexedDocsDatabase, IndexedDocsProvider, PackageName, ProviderId};
+
+/// Indexes the documentation of Go packages using `go doc`.
+///
+/// The packages are resolved from the project's module, so dependencies are
+/// read from the module cache.
+pub struct GoDocsProvider {
+    fs: Arc<dyn Fs>,
+    go_module_root: PathBuf,
+}
+
+impl GoDocsProvider {
+    pub fn id() -> ProviderId {
+        ProviderId("go".into())
+    }
+
+    pub fn new(fs: Arc<dyn Fs>, go_module_root: PathBuf) -> Self {
+        Self { fs, go_module_root }
+    }
+
+    async fn run_go(&self, args: &[&str]) -> Result<String> {
+        let output = smol::process::Command::new("go")
+            .args(args)
+            .current_dir(&self.go_module_root)
+            .output()
+            .await
+            .context("failed to run `go`. is Go installed?")?;
+
+        if !output.status.success() {
+            bail!(
+                "`go {}` failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+#[async_trait]
+impl IndexedDocsProvider for GoDocsProvider {
+    fn id(&self) -> ProviderId {
+        Self::id()
+    }
+
+    fn database_path(&self) -> PathBuf {
+        paths::support_dir().join("docs/go/go-db.0.mdb")
+    }
+
+    async fn suggest_packages(&self) -> Result<Vec<PackageName>> {
+        let go_mod = self.fs.load(&self.go_module_root.join("go.mod")).await?;
+
+        Ok(parse_go_mod_modules(&go_mod)
+            .into_iter()
+            .map(|module| PackageName::from(module.as_str()))
+            .collect())
+    }
+
+    async fn index(&self, package: PackageName, database: Arc<IndexedDocsDatabase>) -> Result<()> {
+        let import_paths = self
+            .run_go(&["list", &format!("{package}/...")])
+            .await
+            .with_context(|| format!("failed to list the packages in '{package}'"))?;
+
+        let mut keys = Vec::new();
+        for import_path in import_paths.lines().map(str::trim) {
+            if import_path.is_empty() {
+                continue;
+            }
+
+            let docs = self.run_go(&["doc", "-all", import_path]).await?;
+            let key = go_package_key(&package, import_path);
+            database
+                .insert(key.clone(), go_doc_to_markdown(import_path, &docs))
+                .await?;
+            keys.push(key);
+        }
+
+        // Modules don't need to have a package at their root, in which case we
+        // list the packages they contain instead.
+        if !keys.iter().any(|key| key.as_str() == package.as_ref()) {
+            database
+                .insert(package.to_string(), package_index_markdown(&package, &keys))
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the key for the package with the given import path, within `package`.
+///
+/// The package itself is keyed by its name, and its sub-packages by their path
+/// relative to it, such as `golang.org/x/text#encoding/json`.
+fn go_package_key(package: &str, import_path: &str) -> String {
+    match import_path
+        .strip_prefix(package)
+        .and_then(|path| path.strip_prefix('/'))
+    {
+        Some(relative_path) => format!("{package}#{relative_path}"),
+        None => package.to_string(),
+    }
+}
+
+/// Converts the output of `go doc -all` into Markdown.
+fn go_doc_to_markdown(import_path: &str, docs: &str) -> String {
+    format!("# {import_path}\n\n```go\n{}\n```\n", docs.trim_end())
+}
+
+/// Returns the paths of the module and its requirements declared in a `go.mod` file.
+fn parse_go_mod_modules(go_mod: &str) -> Vec<String> {
+    let mut modules = Vec::new();
+    let mut in_require_block = false;
+
+    for line in go_mod.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if in_require_block {
+            if line == ")" {
+                in_require_block = false;
+            } else if let Some(module) = line.split_whitespace().next() {
+                modules.push(module.to_string());
+            }
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("module"), Some(module)) => modules.push(module.to_string()),
+            (Some("require"), Some("(")) => in_require_block = true,
+            (Some("require"), Some(module)) => modules.push(module.to_string()),
+            _ => {}
+        }
+    }
+
+    modules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_go_mod_modules() {
+        let go_mod = indoc! {r#"
+            module github.com/example/app // The application.
+
+            go 1.22
+
+            require github.com/pkg/errors v0.9.1
+
+            require (
+                golang.org/x/text v0.14.0
+                // A comment.
+                gopkg.in/yaml.v3 v3.0.1 // indirect
+            )
+
+            replace golang.org/x/text => ../text
+        "#};
+
+        assert_eq!(
+            parse_go_mod_modules(go_mod),
+            vec![
+                "github.com/example/app",
+                "github.com/pkg/errors",
+                "golang.org/x/text",
+                "gopkg.in/yaml.v3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_go_package_key() {
+        assert_eq!(
+            go_package_key("golang.org/x/text", "golang.org/x/text"),
+            "golang.org/x/text"
+        );
+        assert_eq!(
+            go_package_key("golang.org/x/text", "golang.org/x/text/encoding/json"),
+            "golang.org/x/text#encoding/json"
+        );
+        assert_eq!(
+            go_package_key("net/http", "net/http/httptest"),
+            "net/http#httptest"
+        );
+    }
+}
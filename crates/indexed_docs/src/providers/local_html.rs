@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use fs::Fs;
+use futures::StreamExt;
+use html_to_markdown::{convert_html_to_markdown, markdown, TagHandler};
+
+use crate::providers::{collect_files, package_index_markdown, relative_key};
+use crate::{IndexedDocsDatabase, IndexedDocsProvider, PackageName, ProviderId};
+
+/// Indexes directories of HTML documentation, such as the output of Sphinx or
+/// Javadoc, by converting each page to Markdown.
+///
+/// Each directory under the provider's root is a package.
+pub struct LocalHtmlDocsProvider {
+    id: ProviderId,
+    fs: Arc<dyn Fs>,
+    root: PathBuf,
+}
+
+impl LocalHtmlDocsProvider {
+    pub fn new(id: ProviderId, fs: Arc<dyn Fs>, root: PathBuf) -> Self {
+        Self { id, fs, root }
+    }
+}
+
+#[async_trait]
+impl IndexedDocsProvider for LocalHtmlDocsProvider {
+    fn id(&self) -> ProviderId {
+        self.id.clone()
+    }
+
+    fn database_path(&self) -> PathBuf {
+        paths::support_dir().join(format!("docs/html/{}-db.0.mdb", self.id))
+    }
+
+    async fn suggest_packages(&self) -> Result<Vec<PackageName>> {
+        let mut packages = Vec::new();
+        let mut entries = self.fs.read_dir(&self.root).await?;
+        while let Some(entry) = entries.next().await {
+            let entry = entry?;
+            if !self.fs.is_dir(&entry).await {
+                continue;
+            }
+            if let Some(name) = entry.file_name().and_then(|name| name.to_str()) {
+                packages.push(PackageName::from(name));
+            }
+        }
+
+        packages.sort();
+        Ok(packages)
+    }
+
+    async fn index(&self, package: PackageName, database: Arc<IndexedDocsDatabase>) -> Result<()> {
+        let package_dir = self.root.join(package.as_ref());
+        if !self.fs.is_dir(&package_dir).await {
+            bail!("no directory named '{package}' in {}", self.root.display());
+        }
+
+        let files = collect_files(&self.fs, &package_dir, &["_static", "_sources"], |path| {
+            path.extension()
+                .map_or(false, |extension| extension == "html" || extension == "htm")
+        })
+        .await?;
+
+        let mut keys = Vec::new();
+        let mut root_markdown = None;
+        for file in files {
+            let Some(relative_path) = relative_key(&package_dir, &file) else {
+                continue;
+            };
+            let html = self.fs.load(&file).await?;
+            let markdown = convert_html_to_markdown(html.as_bytes(), &mut html_handlers())?;
+
+            if relative_path == "index.html" {
+                root_markdown = Some(markdown.clone());
+            }
+
+            let key = format!("{package}#{relative_path}");
+            database.insert(key.clone(), markdown).await?;
+            keys.push(key);
+        }
+
+        let root_markdown =
+            root_markdown.unwrap_or_else(|| package_index_markdown(&package, &keys));
+        database.insert(package.to_string(), root_markdown).await?;
+
+        Ok(())
+    }
+}
+
+fn html_handlers() -> Vec<TagHandler> {
+    vec![
+        Rc::new(RefCell::new(markdown::WebpageChromeRemover)),
+        Rc::new(RefCell::new(markdown::ParagraphHandler)),
+        Rc::new(RefCell::new(markdown::HeadingHandler)),
+        Rc::new(RefCell::new(markdown::ListHandler)),
+        Rc::new(RefCell::new(markdown::TableHandler::new())),
+        Rc::new(RefCell::new(markdown::StyledTextHandler)),
+        Rc::new(RefCell::new(markdown::CodeHandler)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[gpui::test]
+    async fn test_local_html_docs_provider_suggestions(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/docs",
+            json!({
+                "requests": {
+                    "index.html": "<h1>Requests</h1>",
+                },
+                "numpy": {
+                    "index.html": "<h1>NumPy</h1>",
+                },
+                "README.md": "",
+            }),
+        )
+        .await;
+
+        let provider =
+            LocalHtmlDocsProvider::new(ProviderId("html".into()), fs, PathBuf::from("/docs"));
+        assert_eq!(
+            provider.suggest_packages().await.unwrap(),
+            vec![PackageName::from("numpy"), PackageName::from("requests")]
+        );
+    }
+}
@@ -0,0 +1,507 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use fs::Fs;
+use futures::StreamExt;
+
+use crate::providers::{collect_files, relative_key};
+use crate::{IndexedDocsDatabase, IndexedDocsProvider, PackageName, ProviderId};
+
+/// The names of the virtual environment directories we look for in the project.
+const VIRTUAL_ENV_DIRS: &[&str] = &[".venv", "venv", "env"];
+
+/// Indexes the docstrings of the Python packages installed in the project's
+/// virtual environment.
+pub struct PythonDocsProvider {
+    fs: Arc<dyn Fs>,
+    project_root: PathBuf,
+}
+
+impl PythonDocsProvider {
+    pub fn id() -> ProviderId {
+        ProviderId("python".into())
+    }
+
+    pub fn new(fs: Arc<dyn Fs>, project_root: PathBuf) -> Self {
+        Self { fs, project_root }
+    }
+
+    /// Returns the `site-packages` directories of the project's virtual environments.
+    async fn site_packages_dirs(&self) -> Vec<PathBuf> {
+        let mut site_packages_dirs = Vec::new();
+        for virtual_env_dir in VIRTUAL_ENV_DIRS {
+            let virtual_env_dir = self.project_root.join(virtual_env_dir);
+
+            // Windows virtual environments aren't versioned.
+            let windows_site_packages = virtual_env_dir.join("Lib").join("site-packages");
+            if self.fs.is_dir(&windows_site_packages).await {
+                site_packages_dirs.push(windows_site_packages);
+                continue;
+            }
+
+            let Ok(mut entries) = self.fs.read_dir(&virtual_env_dir.join("lib")).await else {
+                continue;
+            };
+            while let Some(Ok(entry)) = entries.next().await {
+                let is_python_dir = entry
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| name.starts_with("python"));
+                let site_packages = entry.join("site-packages");
+                if is_python_dir && self.fs.is_dir(&site_packages).await {
+                    site_packages_dirs.push(site_packages);
+                }
+            }
+        }
+
+        site_packages_dirs.sort();
+        site_packages_dirs
+    }
+}
+
+#[async_trait]
+impl IndexedDocsProvider for PythonDocsProvider {
+    fn id(&self) -> ProviderId {
+        Self::id()
+    }
+
+    fn database_path(&self) -> PathBuf {
+        paths::support_dir().join("docs/python/python-db.0.mdb")
+    }
+
+    async fn suggest_packages(&self) -> Result<Vec<PackageName>> {
+        let mut packages = BTreeSet::default();
+        for site_packages in self.site_packages_dirs().await {
+            let mut entries = self.fs.read_dir(&site_packages).await?;
+            while let Some(entry) = entries.next().await {
+                let entry = entry?;
+                let Some(name) = entry.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                if name.starts_with('_') {
+                    continue;
+                }
+
+                if self.fs.is_file(&entry.join("__init__.py")).await {
+                    packages.insert(PackageName::from(name));
+                } else if let Some(module) = name.strip_suffix(".py") {
+                    packages.insert(PackageName::from(module));
+                }
+            }
+        }
+
+        Ok(packages.into_iter().collect())
+    }
+
+    async fn index(&self, package: PackageName, database: Arc<IndexedDocsDatabase>) -> Result<()> {
+        for site_packages in self.site_packages_dirs().await {
+            let package_dir = site_packages.join(package.as_ref());
+            if self.fs.is_dir(&package_dir).await {
+                let files = collect_files(&self.fs, &package_dir, &["__pycache__"], |path| {
+                    path.extension()
+                        .map_or(false, |extension| extension == "py")
+                })
+                .await?;
+
+                for file in files {
+                    let Some(module) = module_name(&package, &package_dir, &file) else {
+                        continue;
+                    };
+                    let source = self.fs.load(&file).await?;
+                    database
+                        .insert(module.clone(), python_docs_to_markdown(&module, &source))
+                        .await?;
+                }
+
+                return Ok(());
+            }
+
+            let module_file = site_packages.join(format!("{package}.py"));
+            if self.fs.is_file(&module_file).await {
+                let source = self.fs.load(&module_file).await?;
+                database
+                    .insert(
+                        package.to_string(),
+                        python_docs_to_markdown(&package, &source),
+                    )
+                    .await?;
+
+                return Ok(());
+            }
+        }
+
+        bail!("no installed package named '{package}'. is the project's virtual environment in `.venv`?")
+    }
+}
+
+/// Returns the dotted name of the module defined in `file`.
+fn module_name(package: &str, package_dir: &Path, file: &Path) -> Option<String> {
+    let relative_path = relative_key(package_dir, file)?;
+    let relative_path = relative_path.strip_suffix(".py")?;
+    let relative_path = relative_path
+        .strip_suffix("__init__")
+        .unwrap_or(relative_path)
+        .trim_end_matches('/');
+
+    if relative_path.is_empty() {
+        Some(package.to_string())
+    } else {
+        Some(format!("{package}.{}", relative_path.replace('/', ".")))
+    }
+}
+
+/// Converts the docstrings of a Python module into Markdown.
+///
+/// This only looks at the module's source, so it includes the module docstring
+/// and the signatures and docstrings of its public classes, functions, and methods.
+pub fn python_docs_to_markdown(module: &str, source: &str) -> String {
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut markdown = format!("# {module}\n");
+
+    let first_statement = lines
+        .iter()
+        .position(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+    if let Some((docstring, _)) = first_statement.and_then(|ix| parse_docstring(&lines[ix..])) {
+        markdown.push_str(&format!("\n{docstring}\n"));
+    }
+
+    // The indentation of the class we're in, along with the indentation of its body.
+    let mut current_class: Option<(usize, String, Option<usize>)> = None;
+    let mut ix = 0;
+    while ix < lines.len() {
+        let line = lines[ix];
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            ix += 1;
+            continue;
+        }
+
+        let indent = line.len() - trimmed.len();
+        if let Some((class_indent, _, body_indent)) = current_class.as_mut() {
+            if indent <= *class_indent {
+                current_class = None;
+            } else if body_indent.is_none() {
+                *body_indent = Some(indent);
+            }
+        }
+
+        let trimmed = trimmed.strip_prefix("async ").unwrap_or(trimmed);
+        let kind = if trimmed.starts_with("class ") {
+            "class"
+        } else if trimmed.starts_with("def ") {
+            "def"
+        } else {
+            ix += 1;
+            continue;
+        };
+
+        let class_name = match &current_class {
+            None if indent == 0 => None,
+            Some((_, class_name, Some(body_indent))) if indent == *body_indent => {
+                Some(class_name.clone())
+            }
+            _ => {
+                ix += 1;
+                continue;
+            }
+        };
+
+        let Some((signature, header_lines)) = parse_header(&lines[ix..]) else {
+            ix += 1;
+            continue;
+        };
+        let name = signature[kind.len()..]
+            .trim_start()
+            .split(|char: char| !char.is_alphanumeric() && char != '_')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        ix += header_lines;
+
+        if kind == "class" && class_name.is_none() {
+            current_class = Some((indent, name.clone(), None));
+        }
+
+        let is_public = !name.starts_with('_') || name == "__init__";
+        if !is_public {
+            continue;
+        }
+
+        let (heading_level, title) = match class_name {
+            Some(class_name) => ("###", format!("{class_name}.{name}")),
+            None => ("##", name),
+        };
+        markdown.push_str(&format!(
+            "\n{heading_level} {title}\n\n```python\n{signature}\n```\n"
+        ));
+
+        let body_start = lines[ix..]
+            .iter()
+            .position(|line| !line.trim().is_empty())
+            .map(|offset| ix + offset);
+        if let Some((docstring, _)) = body_start.and_then(|ix| parse_docstring(&lines[ix..])) {
+            markdown.push_str(&format!("\n{docstring}\n"));
+        }
+    }
+
+    markdown
+}
+
+/// Parses the header of a class or function definition, which may span multiple lines.
+///
+/// Returns the signature, without the trailing colon, and the number of lines the header spans.
+fn parse_header(lines: &[&str]) -> Option<(String, usize)> {
+    const MAX_HEADER_LINES: usize = 32;
+
+    let mut header = String::new();
+    let mut depth = 0;
+    for (ix, line) in lines.iter().take(MAX_HEADER_LINES).enumerate() {
+        let mut code = *line;
+        for (char_ix, char) in line.char_indices() {
+            match char {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                '#' => {
+                    code = &line[..char_ix];
+                    break;
+                }
+                ':' if depth == 0 => {
+                    if !header.is_empty() {
+                        header.push(' ');
+                    }
+                    header.push_str(line[..char_ix].trim());
+                    let signature = header
+                        .trim_start_matches("async ")
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .replace("( ", "(")
+                        .replace(" )", ")")
+                        .replace(",)", ")");
+                    return Some((signature, ix + 1));
+                }
+                _ => {}
+            }
+        }
+
+        if !header.is_empty() {
+            header.push(' ');
+        }
+        header.push_str(code.trim());
+    }
+
+    None
+}
+
+/// Parses the docstring at the start of `lines`, if there is one.
+///
+/// Returns the docstring, with its indentation removed, and the number of lines it spans.
+fn parse_docstring(lines: &[&str]) -> Option<(String, usize)> {
+    let first_line = lines.first()?.trim_start();
+    let first_line = first_line.trim_start_matches(['r', 'R', 'u', 'U']);
+    let quote = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|quote| first_line.starts_with(quote))?;
+    let first_line = &first_line[quote.len()..];
+
+    if let Some(end) = first_line.find(quote) {
+        return Some((first_line[..end].trim().to_string(), 1));
+    }
+
+    let mut docstring_lines = vec![first_line.trim()];
+    for (ix, line) in lines.iter().enumerate().skip(1) {
+        if let Some(end) = line.find(quote) {
+            docstring_lines.push(&line[..end]);
+            return Some((dedent(&docstring_lines), ix + 1));
+        }
+        docstring_lines.push(line);
+    }
+
+    None
+}
+
+/// Removes the common indentation of all but the first line, like Python's `inspect.cleandoc`.
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(ix, line)| {
+            if ix == 0 {
+                line.trim()
+            } else {
+                line.get(indent..).unwrap_or_else(|| line.trim()).trim_end()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_python_docs_to_markdown() {
+        let source = indoc! {r#"
+            # Copyright notice.
+            """A module for greeting people.
+
+            It has a single class.
+            """
+
+            import os
+
+
+            class Greeter(object):
+                """Greets people."""
+
+                def __init__(self, name: str = "world"):
+                    self.name = name
+
+                def greet(
+                    self,
+                    punctuation: str = "!",
+                ) -> str:
+                    """Returns the greeting.
+
+                    Args:
+                        punctuation: What to end the greeting with.
+                    """
+
+                    def helper():
+                        """Not included, as it's nested."""
+
+                    return "Hello, " + self.name + punctuation
+
+                def _private(self):
+                    """Not included, as it's private."""
+
+
+            async def greet_everyone(names):  # A comment.
+                r'''Greets everyone in `names`.'''
+        "#};
+
+        assert_eq!(
+            python_docs_to_markdown("greeter", source),
+            indoc! {r#"
+                # greeter
+
+                A module for greeting people.
+
+                It has a single class.
+
+                ## Greeter
+
+                ```python
+                class Greeter(object)
+                ```
+
+                Greets people.
+
+                ### Greeter.__init__
+
+                ```python
+                def __init__(self, name: str = "world")
+                ```
+
+                ### Greeter.greet
+
+                ```python
+                def greet(self, punctuation: str = "!") -> str
+                ```
+
+                Returns the greeting.
+
+                Args:
+                    punctuation: What to end the greeting with.
+
+                ## greet_everyone
+
+                ```python
+                def greet_everyone(names)
+                ```
+
+                Greets everyone in `names`.
+            "#}
+        );
+    }
+
+    #[gpui::test]
+    async fn test_python_docs_provider(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/project",
+            json!({
+                ".venv": {
+                    "lib": {
+                        "python3.12": {
+                            "site-packages": {
+                                "greeter": {
+                                    "__init__.py": "\"\"\"Greets people.\"\"\"",
+                                    "__pycache__": {
+                                        "cached.py": "",
+                                    },
+                                    "formal": {
+                                        "__init__.py": "",
+                                        "titles.py": "def title():\n    \"\"\"Returns a title.\"\"\"\n",
+                                    },
+                                },
+                                "greeter-1.0.dist-info": {},
+                                "single.py": "",
+                                "_internal.py": "",
+                            },
+                        },
+                    },
+                },
+            }),
+        )
+        .await;
+
+        let provider = PythonDocsProvider::new(fs.clone(), PathBuf::from("/project"));
+        assert_eq!(
+            provider.suggest_packages().await.unwrap(),
+            vec![PackageName::from("greeter"), PackageName::from("single")]
+        );
+
+        let package_dir = PathBuf::from("/project/.venv/lib/python3.12/site-packages/greeter");
+        assert_eq!(
+            module_name("greeter", &package_dir, &package_dir.join("__init__.py")),
+            Some("greeter".to_string())
+        );
+        assert_eq!(
+            module_name(
+                "greeter",
+                &package_dir,
+                &package_dir.join("formal/__init__.py")
+            ),
+            Some("greeter.formal".to_string())
+        );
+        assert_eq!(
+            module_name(
+                "greeter",
+                &package_dir,
+                &package_dir.join("formal/titles.py")
+            ),
+            Some("greeter.formal.titles".to_string())
+        );
+    }
+}
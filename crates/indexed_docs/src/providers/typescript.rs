@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use fs::Fs;
+use serde::Deserialize;
+
+use crate::providers::{collect_files, package_index_markdown, relative_key};
+use crate::{IndexedDocsDatabase, IndexedDocsProvider, PackageName, ProviderId};
+
+/// Declaration files larger than this are skipped, as they're usually generated
+/// bundles that wouldn't fit in a context anyway.
+const MAX_DECLARATION_FILE_LEN: usize = 256 * 1024;
+
+/// Indexes the TypeScript declaration files (`.d.ts`) of the packages installed
+/// in the project's `node_modules`, including their `@types` packages.
+pub struct TypeScriptDocsProvider {
+    fs: Arc<dyn Fs>,
+    project_root: PathBuf,
+}
+
+impl TypeScriptDocsProvider {
+    pub fn id() -> ProviderId {
+        ProviderId("typescript".into())
+    }
+
+    pub fn new(fs: Arc<dyn Fs>, project_root: PathBuf) -> Self {
+        Self { fs, project_root }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageJson {
+    #[serde(default)]
+    dependencies: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, serde_json::Value>,
+}
+
+#[async_trait]
+impl IndexedDocsProvider for TypeScriptDocsProvider {
+    fn id(&self) -> ProviderId {
+        Self::id()
+    }
+
+    fn database_path(&self) -> PathBuf {
+        paths::support_dir().join("docs/typescript/typescript-db.0.mdb")
+    }
+
+    async fn suggest_packages(&self) -> Result<Vec<PackageName>> {
+        let package_json = self
+            .fs
+            .load(&self.project_root.join("package.json"))
+            .await?;
+        let package_json: PackageJson =
+            serde_json::from_str(&package_json).context("failed to parse package.json")?;
+
+        Ok(package_json
+            .dependencies
+            .into_keys()
+            .chain(package_json.dev_dependencies.into_keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|package| PackageName::from(package.as_str()))
+            .collect())
+    }
+
+    async fn index(&self, package: PackageName, database: Arc<IndexedDocsDatabase>) -> Result<()> {
+        let node_modules = self.project_root.join("node_modules");
+        let package_dirs = [
+            node_modules.join(package.as_ref()),
+            node_modules
+                .join("@types")
+                .join(types_package_name(&package)),
+        ];
+
+        let mut keys = Vec::new();
+        for package_dir in package_dirs {
+            if !self.fs.is_dir(&package_dir).await {
+                continue;
+            }
+
+            let files = collect_files(&self.fs, &package_dir, &["node_modules"], |path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| name.ends_with(".d.ts"))
+            })
+            .await?;
+
+            for file in files {
+                let Some(relative_path) = relative_key(&node_modules, &file) else {
+                    continue;
+                };
+                let source = self.fs.load(&file).await?;
+                if source.len() > MAX_DECLARATION_FILE_LEN {
+                    continue;
+                }
+
+                let key = format!("{package}#{relative_path}");
+                database
+                    .insert(
+                        key.clone(),
+                        format!(
+                            "# {relative_path}\n\n```typescript\n{}\n```\n",
+                            source.trim_end()
+                        ),
+                    )
+                    .await?;
+                keys.push(key);
+            }
+        }
+
+        if keys.is_empty() {
+            bail!("no type declarations found for '{package}'. try running `npm install`");
+        }
+
+        database
+            .insert(package.to_string(), package_index_markdown(&package, &keys))
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Returns the name of the `@types` package for the given package, such as
+/// `babel__core` for `@babel/core`.
+fn types_package_name(package: &str) -> String {
+    match package.strip_prefix('@') {
+        Some(scoped_package) => scoped_package.replacen('/', "__", 1),
+        None => package.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_types_package_name() {
+        assert_eq!(types_package_name("react"), "react");
+        assert_eq!(types_package_name("@babel/core"), "babel__core");
+    }
+
+    #[gpui::test]
+    async fn test_typescript_docs_provider_suggestions(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/project",
+            json!({
+                "package.json": r#"{
+                    "name": "app",
+                    "dependencies": { "react": "^18.0.0", "@babel/core": "^7.0.0" },
+                    "devDependencies": { "@types/react": "^18.0.0", "react": "^18.0.0" }
+                }"#,
+            }),
+        )
+        .await;
+
+        let provider = TypeScriptDocsProvider::new(fs, PathBuf::from("/project"));
+        assert_eq!(
+            provider.suggest_packages().await.unwrap(),
+            vec![
+                PackageName::from("@babel/core"),
+                PackageName::from("@types/react"),
+                PackageName::from("react"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,644 @@
+//! Structural search: finding code by the shape of its syntax tree rather than by its text.
+//!
+//! A pattern is a snippet of code containing placeholders: `$name` stands for any single
+//! syntax node, and `$$$name` for any run of sibling nodes, including none. Placeholders
+//! that share a name must capture the same text, while `$_` and a bare `$$$` capture
+//! anything. For example, `$a == $a` finds comparisons of an expression with itself.
+//!
+//! Patterns are parsed with the grammar of each syntax layer being searched, after
+//! replacing the placeholders with identifiers, so a pattern only applies to languages in
+//! which it is valid code. Comments and whitespace are ignored when matching.
+
+use crate::{with_parser, BufferSnapshot, Grammar, GrammarId};
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use parking_lot::Mutex;
+use std::{ops::Range, sync::Arc};
+use tree_sitter::Node;
+
+const PLACEHOLDER_PREFIX: &str = "zed_placeholder_";
+
+/// A compiled structural search pattern, see the [module documentation](self).
+pub struct StructuralPattern {
+    source: Arc<str>,
+    placeholders: Vec<Placeholder>,
+    /// The pattern's source, with every placeholder replaced by an identifier.
+    parseable: String,
+    required_words: Vec<String>,
+    trees: Mutex<HashMap<GrammarId, Option<Arc<PatternNode>>>>,
+}
+
+/// A range of a buffer matched by a [`StructuralPattern`], along with the text captured by
+/// its placeholders.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructuralMatch {
+    pub range: Range<usize>,
+    captures: Vec<Option<Range<usize>>>,
+}
+
+#[derive(Debug)]
+struct Placeholder {
+    /// The placeholder's name, or `None` for placeholders that capture anything.
+    name: Option<String>,
+    multiple: bool,
+}
+
+#[derive(Debug)]
+enum PatternNode {
+    Placeholder(usize),
+    /// A token inserted by error recovery, such as a missing semicolon, which matches any
+    /// token of its kind.
+    Missing {
+        kind_id: u16,
+    },
+    Leaf {
+        kind_id: u16,
+        text: String,
+    },
+    Branch {
+        kind_id: u16,
+        children: Vec<PatternNode>,
+    },
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Placeholder {
+        name: &'a str,
+        multiple: bool,
+        text: &'a str,
+    },
+}
+
+impl std::fmt::Debug for StructuralPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StructuralPattern")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl StructuralPattern {
+    pub fn new(source: &str) -> Result<Self> {
+        let mut placeholders = Vec::<Placeholder>::new();
+        let mut parseable = String::new();
+        let mut literal = String::new();
+        for token in tokenize(source) {
+            match token {
+                Token::Text(text) => {
+                    parseable.push_str(text);
+                    literal.push_str(text);
+                }
+                Token::Placeholder { name, multiple, .. } => {
+                    let name = (!name.is_empty() && name != "_").then(|| name.to_string());
+                    let existing = name.as_ref().and_then(|name| {
+                        placeholders
+                            .iter()
+                            .position(|placeholder| placeholder.name.as_ref() == Some(name))
+                    });
+                    let ix = match existing {
+                        Some(ix) if placeholders[ix].multiple != multiple => {
+                            return Err(anyhow!(
+                                "placeholder `{}` is used both for one node and for many",
+                                name.unwrap_or_default()
+                            ));
+                        }
+                        Some(ix) => ix,
+                        None => {
+                            placeholders.push(Placeholder { name, multiple });
+                            placeholders.len() - 1
+                        }
+                    };
+                    parseable.push_str(PLACEHOLDER_PREFIX);
+                    parseable.push_str(&ix.to_string());
+                    literal.push(' ');
+                }
+            }
+        }
+
+        if literal.trim().is_empty() {
+            return Err(anyhow!(
+                "structural patterns must contain code besides placeholders"
+            ));
+        }
+
+        let mut required_words = Vec::<String>::new();
+        for word in literal.split(|c: char| !c.is_alphanumeric() && c != '_') {
+            if !word.is_empty() && !required_words.iter().any(|existing| existing == word) {
+                required_words.push(word.to_string());
+            }
+        }
+
+        Ok(Self {
+            source: source.into(),
+            placeholders,
+            parseable,
+            required_words,
+            trees: Default::default(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Words that appear verbatim in every match of the pattern, which can be used to
+    /// skip files that cannot contain a match without parsing them.
+    pub fn required_words(&self) -> &[String] {
+        &self.required_words
+    }
+
+    /// Finds the outermost, non-overlapping matches contained in the given range of the
+    /// buffer, across all of its syntax layers.
+    pub fn find_matches(
+        &self,
+        buffer: &BufferSnapshot,
+        range: Range<usize>,
+    ) -> Vec<StructuralMatch> {
+        let text = |range: Range<usize>| buffer.text_for_range(range).collect::<String>();
+        let mut matches = Vec::new();
+        for layer in buffer.syntax_layers() {
+            let Some(pattern) = layer
+                .language
+                .grammar()
+                .and_then(|grammar| self.tree_for_grammar(grammar))
+            else {
+                continue;
+            };
+            self.find_matches_in_node(&pattern, layer.node(), &range, &text, &mut matches);
+        }
+
+        matches.sort_by_key(|mat| (mat.range.start, std::cmp::Reverse(mat.range.end)));
+        let mut last_end = 0;
+        matches.retain(|mat| {
+            if mat.range.start < last_end {
+                return false;
+            }
+            last_end = mat.range.end.max(last_end);
+            true
+        });
+        matches
+    }
+
+    /// Matches the pattern against the syntax nodes spanning exactly the given range.
+    pub fn match_at(
+        &self,
+        buffer: &BufferSnapshot,
+        range: Range<usize>,
+    ) -> Option<StructuralMatch> {
+        let text = |range: Range<usize>| buffer.text_for_range(range).collect::<String>();
+        buffer.syntax_layers().find_map(|layer| {
+            let pattern = self.tree_for_grammar(layer.language.grammar()?)?;
+            let mut node = layer
+                .node()
+                .descendant_for_byte_range(range.start, range.end)?;
+            loop {
+                if node.byte_range() == range {
+                    if let Some(mat) = self.match_root(&pattern, node, &text) {
+                        return Some(mat);
+                    }
+                }
+                node = node
+                    .parent()
+                    .filter(|parent| parent.byte_range() == range)?;
+            }
+        })
+    }
+
+    /// Expands the placeholders in a replacement template with the text they captured.
+    /// Placeholders the pattern does not define are kept verbatim.
+    pub fn replacement(
+        &self,
+        template: &str,
+        mat: &StructuralMatch,
+        buffer: &BufferSnapshot,
+    ) -> String {
+        self.expand_template(template, mat, &|range| {
+            buffer.text_for_range(range).collect::<String>()
+        })
+    }
+
+    fn expand_template(
+        &self,
+        template: &str,
+        mat: &StructuralMatch,
+        text: &dyn Fn(Range<usize>) -> String,
+    ) -> String {
+        let mut result = String::new();
+        for token in tokenize(template) {
+            match token {
+                Token::Text(token_text) => result.push_str(token_text),
+                Token::Placeholder {
+                    name,
+                    multiple,
+                    text: token_text,
+                } => {
+                    let capture = self
+                        .placeholders
+                        .iter()
+                        .position(|placeholder| {
+                            placeholder.multiple == multiple
+                                && placeholder.name.as_deref() == Some(name)
+                        })
+                        .and_then(|ix| mat.captures[ix].clone());
+                    match capture {
+                        Some(range) => result.push_str(&text(range)),
+                        None => result.push_str(token_text),
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    fn tree_for_grammar(&self, grammar: &Grammar) -> Option<Arc<PatternNode>> {
+        self.trees
+            .lock()
+            .entry(grammar.id())
+            .or_insert_with(|| self.parse(grammar).map(Arc::new))
+            .clone()
+    }
+
+    /// Parses the pattern with the given grammar. Statements that need a terminator, such
+    /// as Rust's `let` declarations, can be written without one.
+    fn parse(&self, grammar: &Grammar) -> Option<PatternNode> {
+        let pattern_range = trimmed_range(&self.parseable);
+        ["", ";"].into_iter().find_map(|suffix| {
+            let text = format!("{}{suffix}", self.parseable);
+            let tree = with_parser(|parser| {
+                parser.set_language(&grammar.ts_language).ok()?;
+                parser.parse(&text, None)
+            })?;
+            let root = tree.root_node();
+            if contains_error(root) {
+                return None;
+            }
+
+            // Descend to the pattern through the nodes wrapping it, such as the source file
+            // or a statement terminated by the suffix.
+            let mut node = root;
+            while node.byte_range() != pattern_range {
+                match significant_children(node).into_iter().find(|child| {
+                    child.start_byte() <= pattern_range.start
+                        && child.end_byte() >= pattern_range.end
+                }) {
+                    Some(child) => node = child,
+                    None => break,
+                }
+            }
+            while let [child] = significant_children(node)
+                .into_iter()
+                .filter(|child| !child.is_missing())
+                .collect::<Vec<_>>()[..]
+            {
+                if child.byte_range() != node.byte_range() {
+                    break;
+                }
+                node = child;
+            }
+            if node == root
+                || (node.byte_range() != pattern_range && node.byte_range() != trimmed_range(&text))
+            {
+                return None;
+            }
+
+            match self.pattern_node(node, &text) {
+                PatternNode::Placeholder(_) | PatternNode::Missing { .. } => None,
+                pattern => Some(pattern),
+            }
+        })
+    }
+
+    fn pattern_node(&self, node: Node, text: &str) -> PatternNode {
+        let node_text = &text[node.byte_range()];
+        if let Some(ix) = node_text
+            .strip_prefix(PLACEHOLDER_PREFIX)
+            .and_then(|ix| ix.parse::<usize>().ok())
+            .filter(|ix| *ix < self.placeholders.len())
+        {
+            return PatternNode::Placeholder(ix);
+        }
+
+        if node.is_missing() {
+            PatternNode::Missing {
+                kind_id: node.kind_id(),
+            }
+        } else if node.child_count() == 0 {
+            PatternNode::Leaf {
+                kind_id: node.kind_id(),
+                text: node_text.to_string(),
+            }
+        } else {
+            PatternNode::Branch {
+                kind_id: node.kind_id(),
+                children: significant_children(node)
+                    .into_iter()
+                    .map(|child| self.pattern_node(child, text))
+                    .collect(),
+            }
+        }
+    }
+
+    fn find_matches_in_node(
+        &self,
+        pattern: &PatternNode,
+        root: Node,
+        range: &Range<usize>,
+        text: &dyn Fn(Range<usize>) -> String,
+        matches: &mut Vec<StructuralMatch>,
+    ) {
+        let mut cursor = root.walk();
+        loop {
+            let node = cursor.node();
+            let mut descend = node.start_byte() < range.end && node.end_byte() > range.start;
+            if descend && node.start_byte() >= range.start && node.end_byte() <= range.end {
+                if let Some(mat) = self.match_root(pattern, node, text) {
+                    matches.push(mat);
+                    descend = false;
+                }
+            }
+
+            if descend && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn match_root(
+        &self,
+        pattern: &PatternNode,
+        node: Node,
+        text: &dyn Fn(Range<usize>) -> String,
+    ) -> Option<StructuralMatch> {
+        let kind_id = match pattern {
+            PatternNode::Leaf { kind_id, .. } | PatternNode::Branch { kind_id, .. } => *kind_id,
+            PatternNode::Placeholder(_) | PatternNode::Missing { .. } => return None,
+        };
+        if node.kind_id() != kind_id || node.is_extra() {
+            return None;
+        }
+
+        let mut captures = vec![None; self.placeholders.len()];
+        self.match_node(pattern, node, text, &mut captures)
+            .then(|| StructuralMatch {
+                range: node.byte_range(),
+                captures,
+            })
+    }
+
+    fn match_node(
+        &self,
+        pattern: &PatternNode,
+        node: Node,
+        text: &dyn Fn(Range<usize>) -> String,
+        captures: &mut Vec<Option<Range<usize>>>,
+    ) -> bool {
+        match pattern {
+            PatternNode::Placeholder(ix) => {
+                node.is_named() && self.capture(*ix, node.byte_range(), text, captures)
+            }
+            PatternNode::Missing { kind_id } => node.kind_id() == *kind_id,
+            PatternNode::Leaf {
+                kind_id,
+                text: leaf_text,
+            } => {
+                node.kind_id() == *kind_id
+                    && node.byte_range().len() == leaf_text.len()
+                    && text(node.byte_range()) == *leaf_text
+            }
+            PatternNode::Branch { kind_id, children } => {
+                node.kind_id() == *kind_id
+                    && self.match_sequence(children, &significant_children(node), text, captures)
+            }
+        }
+    }
+
+    fn match_sequence(
+        &self,
+        patterns: &[PatternNode],
+        nodes: &[Node],
+        text: &dyn Fn(Range<usize>) -> String,
+        captures: &mut Vec<Option<Range<usize>>>,
+    ) -> bool {
+        let Some((pattern, remaining_patterns)) = patterns.split_first() else {
+            return nodes.is_empty();
+        };
+
+        if let PatternNode::Placeholder(ix) = pattern {
+            if self.placeholders[*ix].multiple {
+                let start = nodes.first().map_or(0, |node| node.start_byte());
+                for count in 0..=nodes.len() {
+                    let range = match count {
+                        0 => start..start,
+                        _ => start..nodes[count - 1].end_byte(),
+                    };
+                    let saved_captures = captures.clone();
+                    if self.capture(*ix, range, text, captures)
+                        && self.match_sequence(remaining_patterns, &nodes[count..], text, captures)
+                    {
+                        return true;
+                    }
+                    *captures = saved_captures;
+                }
+                return false;
+            }
+        }
+
+        let Some((node, remaining_nodes)) = nodes.split_first() else {
+            return false;
+        };
+        let saved_captures = captures.clone();
+        if self.match_node(pattern, *node, text, captures)
+            && self.match_sequence(remaining_patterns, remaining_nodes, text, captures)
+        {
+            return true;
+        }
+        *captures = saved_captures;
+        false
+    }
+
+    /// Records the text captured by a placeholder, returning false if a placeholder with the
+    /// same name already captured different text.
+    fn capture(
+        &self,
+        ix: usize,
+        range: Range<usize>,
+        text: &dyn Fn(Range<usize>) -> String,
+        captures: &mut [Option<Range<usize>>],
+    ) -> bool {
+        if self.placeholders[ix].name.is_some() {
+            if let Some(previous) = captures[ix].clone() {
+                return previous.len() == range.len() && text(previous) == text(range);
+            }
+        }
+        captures[ix] = Some(range);
+        true
+    }
+}
+
+/// Splits text into literal runs and `$name` or `$$$name` placeholders. A `$` that is not
+/// followed by a name, like the one in `$1`, is literal text.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+    let mut offset = 0;
+    while let Some(dollar) = source[offset..].find('$') {
+        let dollar = offset + dollar;
+        let multiple = source[dollar..].starts_with("$$$");
+        let name_start = dollar + if multiple { 3 } else { 1 };
+        let name_len = source[name_start..]
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(source.len() - name_start);
+        let name = &source[name_start..name_start + name_len];
+        let is_name = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_');
+
+        if is_name || multiple {
+            if text_start < dollar {
+                tokens.push(Token::Text(&source[text_start..dollar]));
+            }
+            let name = if is_name { name } else { "" };
+            offset = name_start + name.len();
+            tokens.push(Token::Placeholder {
+                name,
+                multiple,
+                text: &source[dollar..offset],
+            });
+            text_start = offset;
+        } else {
+            offset = dollar + 1;
+        }
+    }
+    if text_start < source.len() {
+        tokens.push(Token::Text(&source[text_start..]));
+    }
+    tokens
+}
+
+fn trimmed_range(text: &str) -> Range<usize> {
+    let start = text.len() - text.trim_start().len();
+    start..text.trim_end().len().max(start)
+}
+
+/// The children that take part in matching, skipping comments.
+fn significant_children(node: Node) -> Vec<Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| !child.is_extra())
+        .collect()
+}
+
+fn contains_error(node: Node) -> bool {
+    if !node.has_error() {
+        return false;
+    }
+    if node.is_error() {
+        return true;
+    }
+    let mut cursor = node.walk();
+    let has_error = node.children(&mut cursor).any(contains_error);
+    has_error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Buffer, Language, LanguageConfig};
+    use gpui::{Context as _, TestAppContext};
+    use unindent::Unindent as _;
+
+    #[gpui::test]
+    async fn test_structural_search(cx: &mut TestAppContext) {
+        let text = r#"
+            fn main() {
+                let a = foo.unwrap();
+                let b = bar(1, 2).unwrap( /* ignored */ );
+                x == x;
+                x == y;
+            }
+        "#
+        .unindent();
+        let buffer = cx.new_model(|cx| Buffer::local(text, cx).with_language(rust_lang(), cx));
+        let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+        let matches = |pattern: &str| {
+            let pattern = StructuralPattern::new(pattern).unwrap();
+            pattern
+                .find_matches(&snapshot, 0..snapshot.len())
+                .into_iter()
+                .map(|mat| snapshot.text_for_range(mat.range).collect::<String>())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            matches("$x.unwrap()"),
+            ["foo.unwrap()", "bar(1, 2).unwrap( /* ignored */ )"]
+        );
+        assert_eq!(matches("$a == $a"), ["x == x"]);
+        assert_eq!(matches("$_ == $_"), ["x == x", "x == y"]);
+        assert_eq!(matches("bar($$$args)"), ["bar(1, 2)"]);
+        assert_eq!(
+            matches("let $name = $value"),
+            [
+                "let a = foo.unwrap();",
+                "let b = bar(1, 2).unwrap( /* ignored */ );"
+            ]
+        );
+        assert_eq!(matches("fn $name() { $$$body }").len(), 1);
+        assert!(matches("x != x").is_empty());
+    }
+
+    #[gpui::test]
+    async fn test_structural_replacement(cx: &mut TestAppContext) {
+        let text = "fn f() { a.unwrap(); b(1, 2).unwrap(); }";
+        let buffer = cx.new_model(|cx| Buffer::local(text, cx).with_language(rust_lang(), cx));
+        let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+
+        let pattern = StructuralPattern::new("$x.unwrap()").unwrap();
+        let replacements = pattern
+            .find_matches(&snapshot, 0..snapshot.len())
+            .into_iter()
+            .map(|mat| {
+                assert_eq!(
+                    pattern.match_at(&snapshot, mat.range.clone()),
+                    Some(mat.clone())
+                );
+                pattern.replacement("$x.expect(\"$x\") $y $1", &mat, &snapshot)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            replacements,
+            ["a.expect(\"a\") $y $1", "b(1, 2).expect(\"b(1, 2)\") $y $1"]
+        );
+        assert_eq!(pattern.match_at(&snapshot, 9..10), None);
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(StructuralPattern::new("").is_err());
+        assert!(StructuralPattern::new("$x").is_err());
+        assert!(StructuralPattern::new("$x + $$$x").is_err());
+        assert_eq!(
+            StructuralPattern::new("$x.unwrap_or($y + 1)")
+                .unwrap()
+                .required_words(),
+            ["unwrap_or", "1"]
+        );
+    }
+
+    fn rust_lang() -> Arc<Language> {
+        Arc::new(Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                ..Default::default()
+            },
+            Some(tree_sitter_rust::LANGUAGE.into()),
+        ))
+    }
+}
@@ -4,7 +4,7 @@ use crate::{Location, Runnable};
 
 use anyhow::Result;
 use collections::HashMap;
-use gpui::AppContext;
+use gpui::{AppContext, Task};
 use task::{TaskTemplates, TaskVariables};
 use text::BufferId;
 
@@ -21,14 +21,16 @@ pub struct RunnableRange {
 /// The context will be used to fill data for the tasks, and filter out the ones that do not have the variables required.
 pub trait ContextProvider: Send + Sync {
     /// Builds a specific context to be placed on top of the basic one (replacing all conflicting entries) and to be used for task resolving later.
+    ///
+    /// The context is built asynchronously, so that providers can compute it out of process, as extensions do.
     fn build_context(
         &self,
         _variables: &TaskVariables,
         _location: &Location,
         _project_env: Option<&HashMap<String, String>>,
         _cx: &mut AppContext,
-    ) -> Result<TaskVariables> {
-        Ok(TaskVariables::default())
+    ) -> Task<Result<TaskVariables>> {
+        Task::ready(Ok(TaskVariables::default()))
     }
 
     /// Provides all tasks, associated with the current language.
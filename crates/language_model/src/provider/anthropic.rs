@@ -3,8 +3,8 @@ use crate::{
     LanguageModelId, LanguageModelName, LanguageModelProvider, LanguageModelProviderId,
     LanguageModelProviderName, LanguageModelProviderState, LanguageModelRequest, RateLimiter, Role,
 };
-use crate::{LanguageModelCompletionEvent, LanguageModelToolUse, StopReason};
-use anthropic::{AnthropicError, ContentDelta, Event, ResponseContent};
+use crate::{LanguageModelCompletionEvent, LanguageModelToolUse, StopReason, TokenUsage};
+use anthropic::{AnthropicError, ContentDelta, Event, ResponseContent, Usage};
 use anyhow::{anyhow, Context as _, Result};
 use collections::{BTreeMap, HashMap};
 use editor::{Editor, EditorElement, EditorStyle};
@@ -437,6 +437,23 @@ impl LanguageModel for AnthropicModel {
     }
 }
 
+/// Updates the usage with the counts that are present in a usage report. Anthropic reports
+/// the input tokens when the message starts, and the output tokens as it's streamed.
+fn update_usage(usage: &mut TokenUsage, new: &Usage) {
+    if let Some(input_tokens) = new.input_tokens {
+        usage.input_tokens = input_tokens;
+    }
+    if let Some(output_tokens) = new.output_tokens {
+        usage.output_tokens = output_tokens;
+    }
+    if let Some(cache_creation_input_tokens) = new.cache_creation_input_tokens {
+        usage.cache_creation_input_tokens = cache_creation_input_tokens;
+    }
+    if let Some(cache_read_input_tokens) = new.cache_read_input_tokens {
+        usage.cache_read_input_tokens = cache_read_input_tokens;
+    }
+}
+
 pub fn map_to_language_model_completion_events(
     events: Pin<Box<dyn Send + Stream<Item = Result<Event, AnthropicError>>>>,
 ) -> impl Stream<Item = Result<LanguageModelCompletionEvent>> {
@@ -449,17 +466,36 @@ pub fn map_to_language_model_completion_events(
     struct State {
         events: Pin<Box<dyn Send + Stream<Item = Result<Event, AnthropicError>>>>,
         tool_uses_by_index: HashMap<usize, RawToolUse>,
+        usage: TokenUsage,
+        pending_stop_reason: Option<StopReason>,
     }
 
     futures::stream::unfold(
         State {
             events,
             tool_uses_by_index: HashMap::default(),
+            usage: TokenUsage::default(),
+            pending_stop_reason: None,
         },
         |mut state| async move {
+            // The stop reason arrives together with the final usage, which is reported first.
+            if let Some(stop_reason) = state.pending_stop_reason.take() {
+                return Some((
+                    Some(Ok(LanguageModelCompletionEvent::Stop(stop_reason))),
+                    state,
+                ));
+            }
+
             while let Some(event) = state.events.next().await {
                 match event {
                     Ok(event) => match event {
+                        Event::MessageStart { message } => {
+                            update_usage(&mut state.usage, &message.usage);
+                            return Some((
+                                Some(Ok(LanguageModelCompletionEvent::UsageUpdate(state.usage))),
+                                state,
+                            ));
+                        }
                         Event::ContentBlockStart {
                             index,
                             content_block,
@@ -516,20 +552,23 @@ pub fn map_to_language_model_completion_events(
                                 ));
                             }
                         }
-                        Event::MessageDelta { delta, .. } => {
-                            if let Some(stop_reason) = delta.stop_reason.as_deref() {
-                                let stop_reason = match stop_reason {
-                                    "end_turn" => StopReason::EndTurn,
-                                    "max_tokens" => StopReason::MaxTokens,
-                                    "tool_use" => StopReason::ToolUse,
-                                    _ => StopReason::EndTurn,
-                                };
+                        Event::MessageDelta { delta, usage } => {
+                            update_usage(&mut state.usage, &usage);
+                            state.pending_stop_reason =
+                                delta
+                                    .stop_reason
+                                    .as_deref()
+                                    .map(|stop_reason| match stop_reason {
+                                        "end_turn" => StopReason::EndTurn,
+                                        "max_tokens" => StopReason::MaxTokens,
+                                        "tool_use" => StopReason::ToolUse,
+                                        _ => StopReason::EndTurn,
+                                    });
 
-                                return Some((
-                                    Some(Ok(LanguageModelCompletionEvent::Stop(stop_reason))),
-                                    state,
-                                ));
-                            }
+                            return Some((
+                                Some(Ok(LanguageModelCompletionEvent::UsageUpdate(state.usage))),
+                                state,
+                            ));
                         }
                         Event::Error { error } => {
                             return Some((
@@ -733,3 +772,68 @@ impl Render for ConfigurationView {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_usage_events() {
+        let events = [
+            json!({
+                "type": "message_start",
+                "message": {
+                    "id": "msg_1",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [],
+                    "model": "claude-3-5-sonnet-20240620",
+                    "usage": {
+                        "input_tokens": 100,
+                        "output_tokens": 1,
+                        "cache_read_input_tokens": 50
+                    }
+                }
+            }),
+            json!({
+                "type": "content_block_start",
+                "index": 0,
+                "content_block": { "type": "text", "text": "Hi" }
+            }),
+            json!({
+                "type": "message_delta",
+                "delta": { "stop_reason": "end_turn", "stop_sequence": null },
+                "usage": { "output_tokens": 12 }
+            }),
+        ]
+        .into_iter()
+        .map(|event| Ok::<_, AnthropicError>(serde_json::from_value::<Event>(event).unwrap()))
+        .collect::<Vec<_>>();
+
+        let events = smol::block_on(
+            map_to_language_model_completion_events(Box::pin(futures::stream::iter(events)))
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(
+            events,
+            vec![
+                LanguageModelCompletionEvent::UsageUpdate(TokenUsage {
+                    input_tokens: 100,
+                    output_tokens: 1,
+                    cache_creation_input_tokens: 0,
+                    cache_read_input_tokens: 50,
+                }),
+                LanguageModelCompletionEvent::Text("Hi".into()),
+                LanguageModelCompletionEvent::UsageUpdate(TokenUsage {
+                    input_tokens: 100,
+                    output_tokens: 12,
+                    cache_creation_input_tokens: 0,
+                    cache_read_input_tokens: 50,
+                }),
+                LanguageModelCompletionEvent::Stop(StopReason::EndTurn),
+            ]
+        );
+    }
+}
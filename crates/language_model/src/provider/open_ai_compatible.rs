@@ -0,0 +1,1232 @@
+use anyhow::{anyhow, Result};
+use collections::{BTreeMap, HashMap, HashSet};
+use editor::{Editor, EditorElement, EditorStyle};
+use futures::{future::BoxFuture, stream::BoxStream, FutureExt, StreamExt};
+use gpui::{
+    AnyView, AppContext, AsyncAppContext, Empty, FontStyle, ModelContext, Subscription, Task,
+    TextStyle, View, WhiteSpace,
+};
+use http_client::HttpClient;
+use open_ai::{
+    list_models, stream_completion, FunctionContent, FunctionDefinition, RequestMessage,
+    ResponseStreamEvent, ToolCall, ToolCallContent, ToolChoice, ToolDefinition,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsStore};
+use std::{sync::Arc, time::Duration};
+use theme::ThemeSettings;
+use ui::{prelude::*, Icon, IconName, Indicator};
+use util::ResultExt;
+
+use crate::{
+    provider::open_ai::count_open_ai_tokens, settings::AllLanguageModelSettings, LanguageModel,
+    LanguageModelCompletionEvent, LanguageModelId, LanguageModelName, LanguageModelProvider,
+    LanguageModelProviderId, LanguageModelProviderName, LanguageModelProviderState,
+    LanguageModelRequest, LanguageModelToolUse, MessageContent, RateLimiter, Role, StopReason,
+    TokenUsage,
+};
+
+const PROVIDER_ID: &str = "openai_compatible";
+const PROVIDER_NAME: &str = "OpenAI Compatible";
+
+/// The context length assumed for models that are discovered from an endpoint, unless the
+/// endpoint specifies one.
+const DEFAULT_MAX_TOKENS: usize = 8192;
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct OpenAiCompatibleSettings {
+    pub endpoints: Vec<OpenAiCompatibleEndpoint>,
+}
+
+impl OpenAiCompatibleSettings {
+    fn endpoint(&self, name: &str) -> Option<&OpenAiCompatibleEndpoint> {
+        self.endpoints.iter().find(|endpoint| endpoint.name == name)
+    }
+}
+
+/// A server that implements OpenAI's chat completions API, such as vLLM, llama.cpp's server
+/// or LM Studio.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OpenAiCompatibleEndpoint {
+    /// The name of the endpoint, shown next to its models in Zed's UI.
+    pub name: String,
+    /// The base URL of the API, such as "http://localhost:8000/v1".
+    pub api_url: String,
+    /// The environment variable to read the API key from. Keys can also be entered in the
+    /// assistant's configuration, and endpoints that don't need one can go without.
+    pub api_key_env_var: Option<String>,
+    pub low_speed_timeout_in_seconds: Option<u64>,
+    /// The models served by the endpoint.
+    #[serde(default)]
+    pub available_models: Vec<AvailableModel>,
+    /// Whether to list the endpoint's models through its `/models` API.
+    ///
+    /// Default: true when no `available_models` are given.
+    pub discover_models: Option<bool>,
+    /// The context length of discovered models.
+    ///
+    /// Default: 8192
+    pub max_tokens: Option<usize>,
+    /// Whether discovered models can use tools.
+    ///
+    /// Default: false
+    pub supports_tools: Option<bool>,
+}
+
+impl OpenAiCompatibleEndpoint {
+    fn discovers_models(&self) -> bool {
+        self.discover_models
+            .unwrap_or(self.available_models.is_empty())
+    }
+
+    fn low_speed_timeout(&self) -> Option<Duration> {
+        self.low_speed_timeout_in_seconds.map(Duration::from_secs)
+    }
+
+    /// The URL the endpoint's API key is stored under in the keychain. It includes the
+    /// endpoint's name, so that endpoints sharing an API URL can have different keys.
+    fn credentials_url(&self) -> String {
+        format!("{}#{}", self.api_url, self.name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AvailableModel {
+    /// The model's name in the endpoint's API.
+    pub name: String,
+    /// The model's name in Zed's UI, such as in the model selector dropdown menu in the assistant panel.
+    pub display_name: Option<String>,
+    /// The model's context length.
+    pub max_tokens: usize,
+    pub max_output_tokens: Option<u32>,
+    /// Whether the model can use tools.
+    #[serde(default)]
+    pub supports_tools: bool,
+}
+
+/// A model served by one of the endpoints.
+#[derive(Clone, Debug, PartialEq)]
+struct EndpointModel {
+    endpoint: String,
+    name: String,
+    display_name: Option<String>,
+    max_tokens: usize,
+    max_output_tokens: Option<u32>,
+    supports_tools: bool,
+}
+
+impl EndpointModel {
+    fn id(&self) -> String {
+        format!("{}/{}", self.endpoint, self.name)
+    }
+
+    fn display_name(&self) -> String {
+        format!(
+            "{} ({})",
+            self.display_name.as_deref().unwrap_or(&self.name),
+            self.endpoint
+        )
+    }
+}
+
+pub struct OpenAiCompatibleLanguageModelProvider {
+    http_client: Arc<dyn HttpClient>,
+    state: gpui::Model<State>,
+}
+
+pub struct State {
+    http_client: Arc<dyn HttpClient>,
+    /// API keys by endpoint name.
+    api_keys: HashMap<String, String>,
+    /// The endpoints whose API key was read from an environment variable.
+    api_keys_from_env: HashSet<String>,
+    /// Models listed by the endpoints' APIs, by endpoint name.
+    discovered_models: HashMap<String, Vec<String>>,
+    /// Whether the API keys have been loaded for the configured endpoints.
+    loaded: bool,
+    /// The endpoints that were configured when the API keys were last loaded.
+    loaded_endpoints: Vec<OpenAiCompatibleEndpoint>,
+    _subscription: Subscription,
+}
+
+impl State {
+    fn is_authenticated(&self, cx: &AppContext) -> bool {
+        self.loaded
+            && !AllLanguageModelSettings::get_global(cx)
+                .openai_compatible
+                .endpoints
+                .is_empty()
+    }
+
+    /// Loads the endpoints' API keys, and lists the models of the endpoints that discover them.
+    fn load(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let endpoints = AllLanguageModelSettings::get_global(cx)
+            .openai_compatible
+            .endpoints
+            .clone();
+        self.loaded_endpoints = endpoints.clone();
+        let http_client = self.http_client.clone();
+
+        cx.spawn(|this, mut cx| async move {
+            let mut api_keys = HashMap::default();
+            let mut api_keys_from_env = HashSet::default();
+            for endpoint in &endpoints {
+                let env_api_key = endpoint
+                    .api_key_env_var
+                    .as_ref()
+                    .and_then(|var| std::env::var(var).ok());
+                if let Some(api_key) = env_api_key {
+                    api_keys.insert(endpoint.name.clone(), api_key);
+                    api_keys_from_env.insert(endpoint.name.clone());
+                } else if let Some((_, api_key)) = cx
+                    .update(|cx| cx.read_credentials(&endpoint.credentials_url()))?
+                    .await
+                    .log_err()
+                    .flatten()
+                {
+                    api_keys.insert(endpoint.name.clone(), String::from_utf8(api_key)?);
+                }
+            }
+
+            let mut discovered_models = HashMap::default();
+            for endpoint in endpoints
+                .iter()
+                .filter(|endpoint| endpoint.discovers_models())
+            {
+                let api_key = api_keys.get(&endpoint.name).map_or("", String::as_str);
+                // An endpoint that can't be reached shouldn't hide the others' models.
+                if let Some(models) = list_models(http_client.as_ref(), &endpoint.api_url, api_key)
+                    .await
+                    .log_err()
+                {
+                    discovered_models.insert(
+                        endpoint.name.clone(),
+                        models.into_iter().map(|model| model.id).collect(),
+                    );
+                }
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.api_keys = api_keys;
+                this.api_keys_from_env = api_keys_from_env;
+                this.discovered_models = discovered_models;
+                this.loaded = true;
+                cx.notify();
+            })
+        })
+    }
+
+    fn authenticate(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        if self.loaded {
+            Task::ready(Ok(()))
+        } else {
+            self.load(cx)
+        }
+    }
+
+    fn set_api_key(
+        &mut self,
+        endpoint: &OpenAiCompatibleEndpoint,
+        api_key: String,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let write_credentials =
+            cx.write_credentials(&endpoint.credentials_url(), "Bearer", api_key.as_bytes());
+        let endpoint_name = endpoint.name.clone();
+        cx.spawn(|this, mut cx| async move {
+            write_credentials.await?;
+            this.update(&mut cx, |this, cx| {
+                this.api_keys.insert(endpoint_name, api_key);
+                // The endpoint may only list its models once it has a key.
+                this.load(cx)
+            })?
+            .await
+        })
+    }
+
+    fn reset_api_key(
+        &mut self,
+        endpoint: &OpenAiCompatibleEndpoint,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let delete_credentials = cx.delete_credentials(&endpoint.credentials_url());
+        let endpoint_name = endpoint.name.clone();
+        cx.spawn(|this, mut cx| async move {
+            delete_credentials.await.log_err();
+            this.update(&mut cx, |this, cx| {
+                this.api_keys.remove(&endpoint_name);
+                cx.notify();
+            })
+        })
+    }
+
+    fn models(&self, cx: &AppContext) -> Vec<EndpointModel> {
+        let mut models = Vec::new();
+        for endpoint in &AllLanguageModelSettings::get_global(cx)
+            .openai_compatible
+            .endpoints
+        {
+            let mut endpoint_models = BTreeMap::default();
+
+            for name in self
+                .discovered_models
+                .get(&endpoint.name)
+                .into_iter()
+                .flatten()
+            {
+                endpoint_models.insert(
+                    name.clone(),
+                    EndpointModel {
+                        endpoint: endpoint.name.clone(),
+                        name: name.clone(),
+                        display_name: None,
+                        max_tokens: endpoint.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+                        max_output_tokens: None,
+                        supports_tools: endpoint.supports_tools.unwrap_or(false),
+                    },
+                );
+            }
+
+            // Override with the models configured for the endpoint.
+            for model in &endpoint.available_models {
+                endpoint_models.insert(
+                    model.name.clone(),
+                    EndpointModel {
+                        endpoint: endpoint.name.clone(),
+                        name: model.name.clone(),
+                        display_name: model.display_name.clone(),
+                        max_tokens: model.max_tokens,
+                        max_output_tokens: model.max_output_tokens,
+                        supports_tools: model.supports_tools,
+                    },
+                );
+            }
+
+            models.extend(endpoint_models.into_values());
+        }
+        models
+    }
+}
+
+impl OpenAiCompatibleLanguageModelProvider {
+    pub fn new(http_client: Arc<dyn HttpClient>, cx: &mut AppContext) -> Self {
+        let state = cx.new_model(|cx| State {
+            http_client: http_client.clone(),
+            api_keys: HashMap::default(),
+            api_keys_from_env: HashSet::default(),
+            discovered_models: HashMap::default(),
+            loaded: false,
+            loaded_endpoints: Vec::new(),
+            _subscription: cx.observe_global::<SettingsStore>(|this: &mut State, cx| {
+                let endpoints_changed = this.loaded_endpoints
+                    != AllLanguageModelSettings::get_global(cx)
+                        .openai_compatible
+                        .endpoints;
+                if !endpoints_changed {
+                    return;
+                }
+                if this.loaded {
+                    this.load(cx).detach_and_log_err(cx);
+                }
+                cx.notify();
+            }),
+        });
+
+        Self { http_client, state }
+    }
+}
+
+impl LanguageModelProviderState for OpenAiCompatibleLanguageModelProvider {
+    type ObservableEntity = State;
+
+    fn observable_entity(&self) -> Option<gpui::Model<Self::ObservableEntity>> {
+        Some(self.state.clone())
+    }
+}
+
+impl LanguageModelProvider for OpenAiCompatibleLanguageModelProvider {
+    fn id(&self) -> LanguageModelProviderId {
+        LanguageModelProviderId(PROVIDER_ID.into())
+    }
+
+    fn name(&self) -> LanguageModelProviderName {
+        LanguageModelProviderName(PROVIDER_NAME.into())
+    }
+
+    fn icon(&self) -> IconName {
+        IconName::Ai
+    }
+
+    fn provided_models(&self, cx: &AppContext) -> Vec<Arc<dyn LanguageModel>> {
+        self.state
+            .read(cx)
+            .models(cx)
+            .into_iter()
+            .map(|model| {
+                Arc::new(OpenAiCompatibleLanguageModel {
+                    id: LanguageModelId::from(model.id()),
+                    model,
+                    state: self.state.clone(),
+                    http_client: self.http_client.clone(),
+                    request_limiter: RateLimiter::new(4),
+                }) as Arc<dyn LanguageModel>
+            })
+            .collect()
+    }
+
+    fn is_authenticated(&self, cx: &AppContext) -> bool {
+        self.state.read(cx).is_authenticated(cx)
+    }
+
+    fn authenticate(&self, cx: &mut AppContext) -> Task<Result<()>> {
+        self.state.update(cx, |state, cx| state.authenticate(cx))
+    }
+
+    fn configuration_view(&self, cx: &mut WindowContext) -> AnyView {
+        cx.new_view(|cx| ConfigurationView::new(self.state.clone(), cx))
+            .into()
+    }
+
+    fn reset_credentials(&self, cx: &mut AppContext) -> Task<Result<()>> {
+        let endpoints = AllLanguageModelSettings::get_global(cx)
+            .openai_compatible
+            .endpoints
+            .clone();
+        let tasks = self.state.update(cx, |state, cx| {
+            endpoints
+                .iter()
+                .map(|endpoint| state.reset_api_key(endpoint, cx))
+                .collect::<Vec<_>>()
+        });
+        cx.spawn(|_| async move {
+            for task in tasks {
+                task.await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+pub struct OpenAiCompatibleLanguageModel {
+    id: LanguageModelId,
+    model: EndpointModel,
+    state: gpui::Model<State>,
+    http_client: Arc<dyn HttpClient>,
+    request_limiter: RateLimiter,
+}
+
+impl OpenAiCompatibleLanguageModel {
+    fn stream_completion(
+        &self,
+        request: open_ai::Request,
+        cx: &AsyncAppContext,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<ResponseStreamEvent>>>> {
+        let http_client = self.http_client.clone();
+        let Ok(endpoint) = cx.read_model(&self.state, |state, cx| {
+            let endpoint = AllLanguageModelSettings::get_global(cx)
+                .openai_compatible
+                .endpoint(&self.model.endpoint)?;
+            let api_key = state.api_keys.get(&endpoint.name).cloned();
+            Some((
+                endpoint.api_url.clone(),
+                api_key,
+                endpoint.low_speed_timeout(),
+            ))
+        }) else {
+            return futures::future::ready(Err(anyhow!("App state dropped"))).boxed();
+        };
+        let Some((api_url, api_key, low_speed_timeout)) = endpoint else {
+            let error = anyhow!("No endpoint named {:?}", self.model.endpoint);
+            return futures::future::ready(Err(error)).boxed();
+        };
+
+        let future = self.request_limiter.stream(async move {
+            let request = stream_completion(
+                http_client.as_ref(),
+                &api_url,
+                api_key.as_deref().unwrap_or_default(),
+                request,
+                low_speed_timeout,
+            );
+            let response = request.await?;
+            Ok(response)
+        });
+
+        async move { Ok(future.await?.boxed()) }.boxed()
+    }
+}
+
+impl LanguageModel for OpenAiCompatibleLanguageModel {
+    fn id(&self) -> LanguageModelId {
+        self.id.clone()
+    }
+
+    fn name(&self) -> LanguageModelName {
+        LanguageModelName::from(self.model.display_name())
+    }
+
+    fn provider_id(&self) -> LanguageModelProviderId {
+        LanguageModelProviderId(PROVIDER_ID.into())
+    }
+
+    fn provider_name(&self) -> LanguageModelProviderName {
+        LanguageModelProviderName(PROVIDER_NAME.into())
+    }
+
+    fn telemetry_id(&self) -> String {
+        format!("openai_compatible/{}", self.model.name)
+    }
+
+    fn max_token_count(&self) -> usize {
+        self.model.max_tokens
+    }
+
+    fn max_output_tokens(&self) -> Option<u32> {
+        self.model.max_output_tokens
+    }
+
+    fn count_tokens(
+        &self,
+        request: LanguageModelRequest,
+        cx: &AppContext,
+    ) -> BoxFuture<'static, Result<usize>> {
+        // The models' tokenizers are unknown, so we estimate with OpenAI's.
+        let model = open_ai::Model::Custom {
+            name: self.model.name.clone(),
+            display_name: None,
+            max_tokens: self.model.max_tokens,
+            max_output_tokens: self.model.max_output_tokens,
+            max_completion_tokens: None,
+        };
+        count_open_ai_tokens(request, model, cx)
+    }
+
+    fn stream_completion(
+        &self,
+        request: LanguageModelRequest,
+        cx: &AsyncAppContext,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<LanguageModelCompletionEvent>>>> {
+        let request = into_open_ai_request(request, &self.model);
+        let completions = self.stream_completion(request, cx);
+        async move {
+            let mut mapper = EventMapper::default();
+            Ok(completions
+                .await?
+                .flat_map(move |event| futures::stream::iter(mapper.map_event(event)))
+                .boxed())
+        }
+        .boxed()
+    }
+
+    fn use_any_tool(
+        &self,
+        request: LanguageModelRequest,
+        tool_name: String,
+        tool_description: String,
+        schema: serde_json::Value,
+        cx: &AsyncAppContext,
+    ) -> BoxFuture<'static, Result<BoxStream<'static, Result<String>>>> {
+        if !self.model.supports_tools {
+            let error = anyhow!("{} does not support tools", self.model.display_name());
+            return futures::future::ready(Err(error)).boxed();
+        }
+
+        let mut request = into_open_ai_request(request, &self.model);
+        request.tool_choice = Some(ToolChoice::Other(ToolDefinition::Function {
+            function: FunctionDefinition {
+                name: tool_name.clone(),
+                description: None,
+                parameters: None,
+            },
+        }));
+        request.tools = vec![ToolDefinition::Function {
+            function: FunctionDefinition {
+                name: tool_name.clone(),
+                description: Some(tool_description),
+                parameters: Some(schema),
+            },
+        }];
+
+        let response = self.stream_completion(request, cx);
+        self.request_limiter
+            .run(async move {
+                let response = response.await?;
+                Ok(
+                    open_ai::extract_tool_args_from_events(tool_name, Box::pin(response))
+                        .await?
+                        .boxed(),
+                )
+            })
+            .boxed()
+    }
+}
+
+/// Converts the request for the given model, passing tools and their results along only if
+/// the model supports them.
+fn into_open_ai_request(request: LanguageModelRequest, model: &EndpointModel) -> open_ai::Request {
+    if !model.supports_tools {
+        return request.into_open_ai(model.name.clone(), model.max_output_tokens);
+    }
+
+    let mut messages = Vec::new();
+    for message in request.messages {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for content in message.content {
+            match content {
+                MessageContent::Text(chunk) => text.push_str(&chunk),
+                MessageContent::Image(_) => {}
+                MessageContent::ToolUse(tool_use) => tool_calls.push(ToolCall {
+                    id: tool_use.id,
+                    content: ToolCallContent::Function {
+                        function: FunctionContent {
+                            name: tool_use.name,
+                            arguments: tool_use.input.to_string(),
+                        },
+                    },
+                }),
+                // Tool results have to directly follow the message that used the tools.
+                MessageContent::ToolResult(tool_result) => {
+                    messages.push(RequestMessage::Tool {
+                        content: tool_result.content,
+                        tool_call_id: tool_result.tool_use_id,
+                    });
+                }
+            }
+        }
+
+        match message.role {
+            Role::User => {
+                if !text.is_empty() {
+                    messages.push(RequestMessage::User { content: text });
+                }
+            }
+            Role::Assistant => messages.push(RequestMessage::Assistant {
+                content: (!text.is_empty()).then_some(text),
+                tool_calls,
+            }),
+            Role::System => messages.push(RequestMessage::System { content: text }),
+        }
+    }
+
+    open_ai::Request {
+        model: model.name.clone(),
+        messages,
+        stream: true,
+        max_tokens: model.max_output_tokens,
+        stop: request.stop,
+        temperature: request.temperature.unwrap_or(1.0),
+        tool_choice: None,
+        tools: request
+            .tools
+            .into_iter()
+            .map(|tool| ToolDefinition::Function {
+                function: FunctionDefinition {
+                    name: tool.name,
+                    description: Some(tool.description),
+                    parameters: Some(tool.input_schema),
+                },
+            })
+            .collect(),
+    }
+}
+
+#[derive(Default)]
+struct RawToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Turns streamed chat completion chunks into completion events, assembling the tool calls
+/// that are streamed in pieces.
+#[derive(Default)]
+struct EventMapper {
+    tool_calls_by_index: BTreeMap<usize, RawToolCall>,
+}
+
+impl EventMapper {
+    fn map_event(
+        &mut self,
+        event: Result<ResponseStreamEvent>,
+    ) -> Vec<Result<LanguageModelCompletionEvent>> {
+        let event = match event {
+            Ok(event) => event,
+            Err(error) => return vec![Err(error)],
+        };
+
+        let mut events = Vec::new();
+        for choice in event.choices {
+            if let Some(content) = choice.delta.content {
+                if !content.is_empty() {
+                    events.push(Ok(LanguageModelCompletionEvent::Text(content)));
+                }
+            }
+
+            for chunk in choice.delta.tool_calls.into_iter().flatten() {
+                let tool_call = self.tool_calls_by_index.entry(chunk.index).or_default();
+                if let Some(id) = chunk.id {
+                    tool_call.id = id;
+                }
+                if let Some(function) = chunk.function {
+                    if let Some(name) = function.name {
+                        tool_call.name = name;
+                    }
+                    if let Some(arguments) = function.arguments {
+                        tool_call.arguments.push_str(&arguments);
+                    }
+                }
+            }
+
+            if let Some(finish_reason) = choice.finish_reason.as_deref() {
+                // Some servers finish with "stop" even when the model called tools.
+                let stop_reason = if !self.tool_calls_by_index.is_empty() {
+                    StopReason::ToolUse
+                } else if finish_reason == "length" {
+                    StopReason::MaxTokens
+                } else {
+                    StopReason::EndTurn
+                };
+
+                for (_, tool_call) in std::mem::take(&mut self.tool_calls_by_index) {
+                    let arguments = if tool_call.arguments.trim().is_empty() {
+                        "{}"
+                    } else {
+                        tool_call.arguments.as_str()
+                    };
+                    events.push(
+                        serde_json::from_str(arguments)
+                            .map(|input| {
+                                LanguageModelCompletionEvent::ToolUse(LanguageModelToolUse {
+                                    id: tool_call.id,
+                                    name: tool_call.name,
+                                    input,
+                                })
+                            })
+                            .map_err(|error| anyhow!(error)),
+                    );
+                }
+                events.push(Ok(LanguageModelCompletionEvent::Stop(stop_reason)));
+            }
+        }
+
+        if let Some(usage) = event.usage {
+            events.push(Ok(LanguageModelCompletionEvent::UsageUpdate(TokenUsage {
+                input_tokens: usage.prompt_tokens,
+                output_tokens: usage.completion_tokens,
+                ..Default::default()
+            })));
+        }
+        events
+    }
+}
+
+struct ConfigurationView {
+    state: gpui::Model<State>,
+    api_key_editors: HashMap<String, View<Editor>>,
+    load_credentials_task: Option<Task<()>>,
+}
+
+impl ConfigurationView {
+    fn new(state: gpui::Model<State>, cx: &mut ViewContext<Self>) -> Self {
+        cx.observe(&state, |this, _, cx| {
+            this.sync_api_key_editors(cx);
+            cx.notify();
+        })
+        .detach();
+
+        let load_credentials_task = Some(cx.spawn({
+            let state = state.clone();
+            |this, mut cx| async move {
+                if let Some(task) = state
+                    .update(&mut cx, |state, cx| state.authenticate(cx))
+                    .log_err()
+                {
+                    task.await.log_err();
+                }
+
+                this.update(&mut cx, |this, cx| {
+                    this.load_credentials_task = None;
+                    cx.notify();
+                })
+                .log_err();
+            }
+        }));
+
+        let mut this = Self {
+            state,
+            api_key_editors: HashMap::default(),
+            load_credentials_task,
+        };
+        this.sync_api_key_editors(cx);
+        this
+    }
+
+    /// Creates an API key editor for each configured endpoint that doesn't have one yet.
+    fn sync_api_key_editors(&mut self, cx: &mut ViewContext<Self>) {
+        let endpoints = &AllLanguageModelSettings::get_global(cx)
+            .openai_compatible
+            .endpoints;
+        let names = endpoints
+            .iter()
+            .map(|endpoint| endpoint.name.clone())
+            .collect::<Vec<_>>();
+
+        self.api_key_editors.retain(|name, _| names.contains(name));
+        for name in names {
+            self.api_key_editors.entry(name).or_insert_with(|| {
+                cx.new_view(|cx| {
+                    let mut editor = Editor::single_line(cx);
+                    editor.set_placeholder_text("API key (optional)", cx);
+                    editor
+                })
+            });
+        }
+    }
+
+    fn save_api_key(&mut self, endpoint: OpenAiCompatibleEndpoint, cx: &mut ViewContext<Self>) {
+        let Some(editor) = self.api_key_editors.get(&endpoint.name) else {
+            return;
+        };
+        let api_key = editor.read(cx).text(cx);
+        if api_key.is_empty() {
+            return;
+        }
+        editor.update(cx, |editor, cx| editor.set_text("", cx));
+
+        let state = self.state.clone();
+        cx.spawn(|_, mut cx| async move {
+            state
+                .update(&mut cx, |state, cx| {
+                    state.set_api_key(&endpoint, api_key, cx)
+                })?
+                .await
+        })
+        .detach_and_log_err(cx);
+
+        cx.notify();
+    }
+
+    fn reset_api_key(&mut self, endpoint: OpenAiCompatibleEndpoint, cx: &mut ViewContext<Self>) {
+        let state = self.state.clone();
+        cx.spawn(|_, mut cx| async move {
+            state
+                .update(&mut cx, |state, cx| state.reset_api_key(&endpoint, cx))?
+                .await
+        })
+        .detach_and_log_err(cx);
+
+        cx.notify();
+    }
+
+    fn render_api_key_editor(
+        &self,
+        editor: &View<Editor>,
+        cx: &ViewContext<Self>,
+    ) -> EditorElement {
+        let settings = ThemeSettings::get_global(cx);
+        let text_style = TextStyle {
+            color: cx.theme().colors().text,
+            font_family: settings.ui_font.family.clone(),
+            font_features: settings.ui_font.features.clone(),
+            font_fallbacks: settings.ui_font.fallbacks.clone(),
+            font_size: rems(0.875).into(),
+            font_weight: settings.ui_font.weight,
+            font_style: FontStyle::Normal,
+            line_height: relative(1.3),
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+            white_space: WhiteSpace::Normal,
+            truncate: None,
+        };
+        EditorElement::new(
+            editor,
+            EditorStyle {
+                background: cx.theme().colors().editor_background,
+                local_player: cx.theme().players().local(),
+                text: text_style,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn render_endpoint(
+        &self,
+        endpoint: &OpenAiCompatibleEndpoint,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let state = self.state.read(cx);
+        let model_count = state
+            .models(cx)
+            .iter()
+            .filter(|model| model.endpoint == endpoint.name)
+            .count();
+        let has_api_key = state.api_keys.contains_key(&endpoint.name);
+        let api_key_from_env = state.api_keys_from_env.contains(&endpoint.name);
+
+        let api_key = if api_key_from_env {
+            let var = endpoint.api_key_env_var.clone().unwrap_or_default();
+            Label::new(format!("API key set in {var} environment variable."))
+                .size(LabelSize::Small)
+                .color(Color::Muted)
+                .into_any_element()
+        } else if has_api_key {
+            let endpoint = endpoint.clone();
+            h_flex()
+                .gap_1()
+                .child(Icon::new(IconName::Check).color(Color::Success))
+                .child(Label::new("API key configured.").size(LabelSize::Small))
+                .child(
+                    Button::new(
+                        SharedString::from(format!("reset-{}", endpoint.name)),
+                        "Reset",
+                    )
+                    .icon(Some(IconName::Trash))
+                    .icon_size(IconSize::Small)
+                    .icon_position(IconPosition::Start)
+                    .on_click(
+                        cx.listener(move |this, _, cx| this.reset_api_key(endpoint.clone(), cx)),
+                    ),
+                )
+                .into_any_element()
+        } else if let Some(editor) = self.api_key_editors.get(&endpoint.name) {
+            let endpoint = endpoint.clone();
+            h_flex()
+                .w_full()
+                .px_2()
+                .py_1()
+                .bg(cx.theme().colors().editor_background)
+                .rounded_md()
+                .on_action(cx.listener(move |this, _: &menu::Confirm, cx| {
+                    this.save_api_key(endpoint.clone(), cx)
+                }))
+                .child(self.render_api_key_editor(editor, cx))
+                .into_any_element()
+        } else {
+            Empty.into_any_element()
+        };
+
+        v_flex()
+            .gap_1()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Indicator::dot().color(if model_count > 0 {
+                        Color::Success
+                    } else {
+                        Color::Warning
+                    }))
+                    .child(Label::new(endpoint.name.clone()))
+                    .child(
+                        Label::new(endpoint.api_url.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .child(
+                Label::new(match model_count {
+                    0 => "No models found.".to_string(),
+                    1 => "1 model".to_string(),
+                    count => format!("{count} models"),
+                })
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+            )
+            .child(api_key)
+    }
+}
+
+impl Render for ConfigurationView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        if self.load_credentials_task.is_some() {
+            return div().child(Label::new("Loading credentials...")).into_any();
+        }
+
+        let endpoints = AllLanguageModelSettings::get_global(cx)
+            .openai_compatible
+            .endpoints
+            .clone();
+        if endpoints.is_empty() {
+            return v_flex()
+                .gap_1()
+                .child(Label::new(
+                    "To use servers that implement OpenAI's API, such as vLLM, llama.cpp or LM Studio, \
+                    add them to your settings:",
+                ))
+                .child(
+                    Label::new("\"language_models\": { \"openai_compatible\": [{ \"name\": \"local\", \"api_url\": \"http://localhost:8000/v1\" }] }")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .into_any();
+        }
+
+        v_flex()
+            .size_full()
+            .gap_3()
+            .children(
+                endpoints
+                    .iter()
+                    .map(|endpoint| self.render_endpoint(endpoint, cx))
+                    .collect::<Vec<_>>(),
+            )
+            .into_any()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageModelRequestMessage, LanguageModelRequestTool, LanguageModelToolResult};
+    use gpui::TestAppContext;
+    use http_client::{AsyncBody, FakeHttpClient, Method, Response};
+    use open_ai::{ChoiceDelta, FunctionChunk, ResponseMessageDelta, ToolCallChunk};
+    use serde_json::json;
+
+    fn delta_event(
+        content: Option<&str>,
+        tool_calls: Option<Vec<ToolCallChunk>>,
+        finish_reason: Option<&str>,
+    ) -> Result<ResponseStreamEvent> {
+        Ok(ResponseStreamEvent {
+            created: 0,
+            model: "llama".into(),
+            choices: vec![ChoiceDelta {
+                index: 0,
+                delta: ResponseMessageDelta {
+                    role: None,
+                    content: content.map(Into::into),
+                    tool_calls,
+                },
+                finish_reason: finish_reason.map(Into::into),
+            }],
+            usage: None,
+        })
+    }
+
+    fn tool_call_chunk(
+        id: Option<&str>,
+        name: Option<&str>,
+        arguments: Option<&str>,
+    ) -> ToolCallChunk {
+        ToolCallChunk {
+            index: 0,
+            id: id.map(Into::into),
+            function: Some(FunctionChunk {
+                name: name.map(Into::into),
+                arguments: arguments.map(Into::into),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_event_mapper() {
+        let mut mapper = EventMapper::default();
+        let mut events = Vec::new();
+        for event in [
+            delta_event(Some("Let me look."), None, None),
+            delta_event(
+                None,
+                Some(vec![tool_call_chunk(
+                    Some("call_1"),
+                    Some("read_file"),
+                    Some("{\"path\":"),
+                )]),
+                None,
+            ),
+            delta_event(
+                None,
+                Some(vec![tool_call_chunk(None, None, Some(" \"a.rs\"}"))]),
+                None,
+            ),
+            delta_event(None, None, Some("stop")),
+        ] {
+            events.extend(mapper.map_event(event).into_iter().map(Result::unwrap));
+        }
+
+        assert_eq!(
+            events,
+            vec![
+                LanguageModelCompletionEvent::Text("Let me look.".into()),
+                LanguageModelCompletionEvent::ToolUse(LanguageModelToolUse {
+                    id: "call_1".into(),
+                    name: "read_file".into(),
+                    input: json!({ "path": "a.rs" }),
+                }),
+                LanguageModelCompletionEvent::Stop(StopReason::ToolUse),
+            ]
+        );
+
+        let events = mapper
+            .map_event(delta_event(Some("Done"), None, Some("length")))
+            .into_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                LanguageModelCompletionEvent::Text("Done".into()),
+                LanguageModelCompletionEvent::Stop(StopReason::MaxTokens),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_into_open_ai_request() {
+        let mut model = EndpointModel {
+            endpoint: "local".into(),
+            name: "llama".into(),
+            display_name: None,
+            max_tokens: 8192,
+            max_output_tokens: None,
+            supports_tools: true,
+        };
+        let request = LanguageModelRequest {
+            messages: vec![
+                LanguageModelRequestMessage {
+                    role: Role::User,
+                    content: vec!["What's in a.rs?".into()],
+                    cache: false,
+                },
+                LanguageModelRequestMessage {
+                    role: Role::Assistant,
+                    content: vec![MessageContent::ToolUse(LanguageModelToolUse {
+                        id: "call_1".into(),
+                        name: "read_file".into(),
+                        input: json!({ "path": "a.rs" }),
+                    })],
+                    cache: false,
+                },
+                LanguageModelRequestMessage {
+                    role: Role::User,
+                    content: vec![MessageContent::ToolResult(LanguageModelToolResult {
+                        tool_use_id: "call_1".into(),
+                        is_error: false,
+                        content: "fn main() {}".into(),
+                    })],
+                    cache: false,
+                },
+            ],
+            tools: vec![LanguageModelRequestTool {
+                name: "read_file".into(),
+                description: "Reads a file".into(),
+                input_schema: json!({ "type": "object" }),
+            }],
+            stop: Vec::new(),
+            temperature: None,
+        };
+
+        let open_ai_request = into_open_ai_request(request.clone(), &model);
+        assert_eq!(
+            open_ai_request.messages,
+            vec![
+                RequestMessage::User {
+                    content: "What's in a.rs?".into()
+                },
+                RequestMessage::Assistant {
+                    content: None,
+                    tool_calls: vec![ToolCall {
+                        id: "call_1".into(),
+                        content: ToolCallContent::Function {
+                            function: FunctionContent {
+                                name: "read_file".into(),
+                                arguments: "{\"path\":\"a.rs\"}".into(),
+                            },
+                        },
+                    }],
+                },
+                RequestMessage::Tool {
+                    content: "fn main() {}".into(),
+                    tool_call_id: "call_1".into(),
+                },
+            ]
+        );
+        assert_eq!(open_ai_request.tools.len(), 1);
+
+        model.supports_tools = false;
+        let open_ai_request = into_open_ai_request(request, &model);
+        assert!(open_ai_request.tools.is_empty());
+        assert_eq!(open_ai_request.messages.len(), 3);
+    }
+
+    #[gpui::test]
+    async fn test_discover_and_stream(cx: &mut TestAppContext) {
+        let http_client = FakeHttpClient::create(|request| async move {
+            let path = request.uri().path().to_string();
+            let body = if request.method() == Method::GET && path == "/v1/models" {
+                json!({ "object": "list", "data": [{ "id": "llama", "object": "model" }] })
+                    .to_string()
+            } else if request.method() == Method::POST && path == "/v1/chat/completions" {
+                [
+                    json!({
+                        "created": 0,
+                        "model": "llama",
+                        "choices": [{ "index": 0, "delta": { "content": "Hello" } }],
+                    }),
+                    json!({
+                        "created": 0,
+                        "model": "llama",
+                        "choices": [{ "index": 0, "delta": {}, "finish_reason": "stop" }],
+                    }),
+                ]
+                .iter()
+                .map(|event| format!("data: {event}\n\n"))
+                .chain(["data: [DONE]\n\n".to_string()])
+                .collect()
+            } else {
+                return anyhow::Ok(Response::builder().status(404).body(AsyncBody::empty())?);
+            };
+            anyhow::Ok(
+                Response::builder()
+                    .status(200)
+                    .body(AsyncBody::from(body))?,
+            )
+        });
+
+        cx.update(|cx| {
+            let store = SettingsStore::test(cx);
+            cx.set_global(store);
+            AllLanguageModelSettings::register(cx);
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<AllLanguageModelSettings>(cx, |settings| {
+                    settings.openai_compatible = Some(vec![OpenAiCompatibleEndpoint {
+                        name: "local".into(),
+                        api_url: "http://localhost:8000/v1".into(),
+                        api_key_env_var: None,
+                        low_speed_timeout_in_seconds: None,
+                        available_models: Vec::new(),
+                        discover_models: None,
+                        max_tokens: Some(4096),
+                        supports_tools: None,
+                    }]);
+                });
+            });
+        });
+
+        let provider = cx.update(|cx| OpenAiCompatibleLanguageModelProvider::new(http_client, cx));
+        cx.update(|cx| provider.authenticate(cx)).await.unwrap();
+
+        let models = cx.update(|cx| provider.provided_models(cx));
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id().0.as_ref(), "local/llama");
+        assert_eq!(models[0].name().0.as_ref(), "llama (local)");
+        assert_eq!(models[0].max_token_count(), 4096);
+
+        let events = models[0]
+            .stream_completion(LanguageModelRequest::default(), &cx.to_async())
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            events,
+            vec![
+                LanguageModelCompletionEvent::Text("Hello".into()),
+                LanguageModelCompletionEvent::Stop(StopReason::EndTurn),
+            ]
+        );
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
         anthropic::AnthropicLanguageModelProvider, cloud::CloudLanguageModelProvider,
         copilot_chat::CopilotChatLanguageModelProvider, google::GoogleLanguageModelProvider,
         ollama::OllamaLanguageModelProvider, open_ai::OpenAiLanguageModelProvider,
+        open_ai_compatible::OpenAiCompatibleLanguageModelProvider,
     },
     LanguageModel, LanguageModelId, LanguageModelProvider, LanguageModelProviderId,
     LanguageModelProviderState,
@@ -45,6 +46,10 @@ fn register_language_model_providers(
         OllamaLanguageModelProvider::new(client.http_client(), cx),
         cx,
     );
+    registry.register_provider(
+        OpenAiCompatibleLanguageModelProvider::new(client.http_client(), cx),
+        cx,
+    );
     registry.register_provider(
         GoogleLanguageModelProvider::new(client.http_client(), cx),
         cx,
@@ -16,6 +16,7 @@ use crate::{
         google::GoogleSettings,
         ollama::OllamaSettings,
         open_ai::OpenAiSettings,
+        open_ai_compatible::{OpenAiCompatibleEndpoint, OpenAiCompatibleSettings},
     },
     LanguageModelCacheConfiguration,
 };
@@ -58,6 +59,7 @@ pub struct AllLanguageModelSettings {
     pub anthropic: AnthropicSettings,
     pub ollama: OllamaSettings,
     pub openai: OpenAiSettings,
+    pub openai_compatible: OpenAiCompatibleSettings,
     pub zed_dot_dev: ZedDotDevSettings,
     pub google: GoogleSettings,
     pub copilot_chat: CopilotChatSettings,
@@ -68,6 +70,7 @@ pub struct AllLanguageModelSettingsContent {
     pub anthropic: Option<AnthropicSettingsContent>,
     pub ollama: Option<OllamaSettingsContent>,
     pub openai: Option<OpenAiSettingsContent>,
+    pub openai_compatible: Option<Vec<OpenAiCompatibleEndpoint>>,
     #[serde(rename = "zed.dev")]
     pub zed_dot_dev: Option<ZedDotDevSettingsContent>,
     pub google: Option<GoogleSettingsContent>,
@@ -329,6 +332,12 @@ impl settings::Settings for AllLanguageModelSettings {
                 openai.as_ref().and_then(|s| s.available_models.clone()),
             );
 
+            // OpenAI-compatible endpoints
+            merge(
+                &mut settings.openai_compatible.endpoints,
+                value.openai_compatible.clone(),
+            );
+
             merge(
                 &mut settings.zed_dot_dev.available_models,
                 value
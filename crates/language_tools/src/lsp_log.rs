@@ -1090,6 +1090,7 @@ impl SearchableItem for LspLogView {
             case: true,
             word: true,
             regex: true,
+            structural: false,
             // LSP log is read-only.
             replacement: false,
             selection: false,
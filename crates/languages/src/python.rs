@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use collections::HashMap;
 use gpui::AppContext;
 use gpui::AsyncAppContext;
+use gpui::Task;
 use language::{ContextProvider, LanguageServerName, LspAdapter, LspAdapterDelegate};
 use lsp::LanguageServerBinary;
 use node_runtime::NodeRuntime;
@@ -239,7 +240,7 @@ impl ContextProvider for PythonContextProvider {
         _location: &project::Location,
         _: Option<&HashMap<String, String>>,
         _cx: &mut gpui::AppContext,
-    ) -> Result<task::TaskVariables> {
+    ) -> Task<Result<task::TaskVariables>> {
         let python_module_name = python_module_name_from_relative_path(
             variables.get(&VariableName::RelativeFile).unwrap_or(""),
         );
@@ -255,7 +256,7 @@ impl ContextProvider for PythonContextProvider {
             }
             (Some(class_name), None) => format!("{}.{}", python_module_name, class_name),
             (None, None) => python_module_name,
-            (None, Some(_)) => return Ok(task::TaskVariables::default()), // should never happen, a TestCase class is the unit of testing
+            (None, Some(_)) => return Task::ready(Ok(task::TaskVariables::default())), // should never happen, a TestCase class is the unit of testing
         };
 
         let unittest_target = (
@@ -263,7 +264,7 @@ impl ContextProvider for PythonContextProvider {
             unittest_target_str,
         );
 
-        Ok(task::TaskVariables::from_iter([unittest_target]))
+        Task::ready(Ok(task::TaskVariables::from_iter([unittest_target])))
     }
 
     fn associated_tasks(
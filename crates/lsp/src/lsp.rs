@@ -21,6 +21,7 @@ use smol::{
 use smol::process::windows::CommandExt;
 
 use std::{
+    borrow::Cow,
     ffi::OsString,
     fmt,
     io::Write,
@@ -988,6 +989,24 @@ impl LanguageServer {
         )
     }
 
+    /// Sends a RPC request with the given method to the language server, such
+    /// as a request specific to the language server that isn't part of the
+    /// protocol.
+    pub fn request_custom(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> impl LspRequestFuture<Result<Value>> {
+        Self::request_with_method(
+            &self.next_id,
+            &self.response_handlers,
+            &self.outbound_tx,
+            &self.executor,
+            Cow::Owned(method.to_string()),
+            params,
+        )
+    }
+
     fn request_internal<T: request::Request>(
         next_id: &AtomicI32,
         response_handlers: &Mutex<Option<HashMap<RequestId, ResponseHandler>>>,
@@ -997,12 +1016,34 @@ impl LanguageServer {
     ) -> impl LspRequestFuture<Result<T::Result>>
     where
         T::Result: 'static + Send,
+    {
+        Self::request_with_method(
+            next_id,
+            response_handlers,
+            outbound_tx,
+            executor,
+            Cow::Borrowed(T::METHOD),
+            params,
+        )
+    }
+
+    fn request_with_method<Params, Response>(
+        next_id: &AtomicI32,
+        response_handlers: &Mutex<Option<HashMap<RequestId, ResponseHandler>>>,
+        outbound_tx: &channel::Sender<String>,
+        executor: &BackgroundExecutor,
+        method: Cow<'static, str>,
+        params: Params,
+    ) -> impl LspRequestFuture<Result<Response>>
+    where
+        Params: Serialize,
+        Response: 'static + Send + DeserializeOwned,
     {
         let id = next_id.fetch_add(1, SeqCst);
         let message = serde_json::to_string(&Request {
             jsonrpc: JSON_RPC_VERSION,
             id: RequestId::Int(id),
-            method: T::METHOD,
+            method: &method,
             params,
         })
         .unwrap();
@@ -1059,7 +1100,6 @@ impl LanguageServer {
                 }
             });
 
-            let method = T::METHOD;
             select! {
                 response = rx.fuse() => {
                     let elapsed = started.elapsed();
@@ -1083,13 +1123,28 @@ impl LanguageServer {
         Self::notify_internal::<T>(&self.outbound_tx, params)
     }
 
+    /// Sends a RPC notification with the given method to the language server,
+    /// such as a notification specific to the language server that isn't part
+    /// of the protocol.
+    pub fn notify_custom(&self, method: &str, params: Value) -> Result<()> {
+        Self::notify_with_method(&self.outbound_tx, method, params)
+    }
+
     fn notify_internal<T: notification::Notification>(
         outbound_tx: &channel::Sender<String>,
         params: T::Params,
+    ) -> Result<()> {
+        Self::notify_with_method(outbound_tx, T::METHOD, params)
+    }
+
+    fn notify_with_method<Params: Serialize>(
+        outbound_tx: &channel::Sender<String>,
+        method: &str,
+        params: Params,
     ) -> Result<()> {
         let message = serde_json::to_string(&Notification {
             jsonrpc: JSON_RPC_VERSION,
-            method: T::METHOD,
+            method,
             params,
         })
         .unwrap();
@@ -1468,6 +1523,60 @@ mod tests {
         fake.receive_notification::<notification::Exit>().await;
     }
 
+    #[gpui::test]
+    async fn test_custom_requests(cx: &mut TestAppContext) {
+        enum ExpandMacro {}
+
+        impl request::Request for ExpandMacro {
+            type Params = Value;
+            type Result = Value;
+            const METHOD: &'static str = "test/expandMacro";
+        }
+
+        enum ReloadWorkspace {}
+
+        impl notification::Notification for ReloadWorkspace {
+            type Params = Value;
+            const METHOD: &'static str = "test/reloadWorkspace";
+        }
+
+        cx.update(|cx| {
+            release_channel::init(SemanticVersion::default(), cx);
+        });
+        let (server, mut fake) = FakeLanguageServer::new(
+            LanguageServerId(0),
+            LanguageServerBinary {
+                path: "path/to/language-server".into(),
+                arguments: vec![],
+                env: None,
+            },
+            "the-lsp".to_string(),
+            Default::default(),
+            cx.to_async(),
+        );
+        let server = cx.update(|cx| server.initialize(None, cx)).await.unwrap();
+
+        fake.handle_request::<ExpandMacro, _, _>(|params, _| async move {
+            assert_eq!(params, json!({ "position": 1 }));
+            Ok(json!({ "name": "println", "expansion": "{}" }))
+        });
+        assert_eq!(
+            server
+                .request_custom("test/expandMacro", json!({ "position": 1 }))
+                .await
+                .unwrap(),
+            json!({ "name": "println", "expansion": "{}" })
+        );
+
+        server
+            .notify_custom("test/reloadWorkspace", json!({ "force": true }))
+            .unwrap();
+        assert_eq!(
+            fake.receive_notification::<ReloadWorkspace>().await,
+            json!({ "force": true })
+        );
+    }
+
     #[gpui::test]
     fn test_deserialize_string_digit_id() {
         let json = r#"{"jsonrpc":"2.0","id":"2","method":"workspace/configuration","params":{"items":[{"scopeUri":"file:///Users/mph/Devel/personal/hello-scala/","section":"metals"}]}}"#;
@@ -314,8 +314,11 @@ pub async fn complete(
     let mut request_builder = HttpRequest::builder()
         .method(Method::POST)
         .uri(uri)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", api_key));
+        .header("Content-Type", "application/json");
+    // Local OpenAI-compatible servers may not require an API key.
+    if !api_key.is_empty() {
+        request_builder = request_builder.header("Authorization", format!("Bearer {}", api_key));
+    }
     if let Some(low_speed_timeout) = low_speed_timeout {
         request_builder = request_builder.read_timeout(low_speed_timeout);
     };
@@ -408,8 +411,10 @@ pub async fn stream_completion(
     let mut request_builder = HttpRequest::builder()
         .method(Method::POST)
         .uri(uri)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", api_key));
+        .header("Content-Type", "application/json");
+    if !api_key.is_empty() {
+        request_builder = request_builder.header("Authorization", format!("Bearer {}", api_key));
+    }
 
     if let Some(low_speed_timeout) = low_speed_timeout {
         request_builder = request_builder.read_timeout(low_speed_timeout);
@@ -470,6 +475,50 @@ pub async fn stream_completion(
     }
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ModelListing {
+    pub id: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ListModelsResponse {
+    data: Vec<ModelListing>,
+}
+
+/// Lists the models served at the given URL, such as by an OpenAI-compatible server.
+pub async fn list_models(
+    client: &dyn HttpClient,
+    api_url: &str,
+    api_key: &str,
+) -> Result<Vec<ModelListing>> {
+    let uri = format!("{api_url}/models");
+    let mut request_builder = HttpRequest::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .header("Accept", "application/json");
+    if !api_key.is_empty() {
+        request_builder = request_builder.header("Authorization", format!("Bearer {}", api_key));
+    }
+
+    let request = request_builder.body(AsyncBody::default())?;
+    let mut response = client.send(request).await?;
+
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await?;
+
+    if response.status().is_success() {
+        let response: ListModelsResponse =
+            serde_json::from_str(&body).context("Unable to parse model listing")?;
+        Ok(response.data)
+    } else {
+        Err(anyhow!(
+            "Failed to list models: {} {}",
+            response.status(),
+            body,
+        ))
+    }
+}
+
 #[derive(Copy, Clone, Serialize, Deserialize)]
 pub enum OpenAiEmbeddingModel {
     #[serde(rename = "text-embedding-3-small")]
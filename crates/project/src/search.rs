@@ -2,7 +2,7 @@ use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
 use anyhow::Result;
 use client::proto;
 use gpui::Model;
-use language::{Buffer, BufferSnapshot};
+use language::{structural_search::StructuralPattern, Bias, Buffer, BufferSnapshot, Point};
 use regex::{Captures, Regex, RegexBuilder};
 use smol::future::yield_now;
 use std::{
@@ -13,10 +13,18 @@ use std::{
     sync::{Arc, OnceLock},
 };
 use text::Anchor;
-use util::paths::PathMatcher;
+use util::{paths::PathMatcher, ResultExt};
 
 static TEXT_REPLACEMENT_SPECIAL_CHARACTERS_REGEX: OnceLock<Regex> = OnceLock::new();
 
+/// How many times the advanced regex engine may backtrack while looking for a match, so that
+/// catastrophic patterns fail instead of stalling the search.
+const ADVANCED_REGEX_BACKTRACK_LIMIT: usize = 1_000_000;
+
+/// How much text on either side of a match is given to the advanced regex engine when
+/// computing its replacement, so that lookarounds see the text around the match.
+const ADVANCED_REGEX_REPLACEMENT_CONTEXT: usize = 1024;
+
 pub enum SearchResult {
     Buffer {
         buffer: Model<Buffer>,
@@ -54,6 +62,14 @@ impl SearchInputs {
         &self.buffers
     }
 }
+/// A compiled regular expression, using either the `regex` crate or, when advanced regular
+/// expressions are enabled, `fancy-regex`, which supports backreferences and lookarounds.
+#[derive(Clone, Debug)]
+pub enum SearchRegex {
+    Standard(Regex),
+    Advanced(fancy_regex::Regex),
+}
+
 #[derive(Clone, Debug)]
 pub enum SearchQuery {
     Text {
@@ -66,7 +82,7 @@ pub enum SearchQuery {
     },
 
     Regex {
-        regex: Regex,
+        regex: SearchRegex,
         replacement: Option<String>,
         multiline: bool,
         whole_word: bool,
@@ -74,6 +90,13 @@ pub enum SearchQuery {
         include_ignored: bool,
         inner: SearchInputs,
     },
+
+    Structural {
+        pattern: Arc<StructuralPattern>,
+        replacement: Option<String>,
+        include_ignored: bool,
+        inner: SearchInputs,
+    },
 }
 
 impl SearchQuery {
@@ -114,6 +137,52 @@ impl SearchQuery {
         files_to_include: PathMatcher,
         files_to_exclude: PathMatcher,
         buffers: Option<Vec<Model<Buffer>>>,
+    ) -> Result<Self> {
+        Self::build_regex(
+            query,
+            whole_word,
+            case_sensitive,
+            include_ignored,
+            files_to_include,
+            files_to_exclude,
+            buffers,
+            false,
+        )
+    }
+
+    /// Like [`Self::regex`], but using an engine that supports backreferences and
+    /// lookarounds. Matches are looked for in whole buffers rather than line by line.
+    pub fn advanced_regex(
+        query: impl ToString,
+        whole_word: bool,
+        case_sensitive: bool,
+        include_ignored: bool,
+        files_to_include: PathMatcher,
+        files_to_exclude: PathMatcher,
+        buffers: Option<Vec<Model<Buffer>>>,
+    ) -> Result<Self> {
+        Self::build_regex(
+            query,
+            whole_word,
+            case_sensitive,
+            include_ignored,
+            files_to_include,
+            files_to_exclude,
+            buffers,
+            true,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_regex(
+        query: impl ToString,
+        whole_word: bool,
+        case_sensitive: bool,
+        include_ignored: bool,
+        files_to_include: PathMatcher,
+        files_to_exclude: PathMatcher,
+        buffers: Option<Vec<Model<Buffer>>>,
+        advanced: bool,
     ) -> Result<Self> {
         let mut query = query.to_string();
         let initial_query = Arc::from(query.as_str());
@@ -125,11 +194,21 @@ impl SearchQuery {
             query = word_query
         }
 
-        let multiline = query.contains('\n') || query.contains("\\n") || query.contains("\\s");
-        let regex = RegexBuilder::new(&query)
-            .case_insensitive(!case_sensitive)
-            .multi_line(multiline)
-            .build()?;
+        let (regex, multiline) = if advanced {
+            // Match `^` and `$` at line boundaries, as when searching line by line.
+            let flags = if case_sensitive { "(?m)" } else { "(?im)" };
+            let regex = fancy_regex::RegexBuilder::new(&format!("{flags}{query}"))
+                .backtrack_limit(ADVANCED_REGEX_BACKTRACK_LIMIT)
+                .build()?;
+            (SearchRegex::Advanced(regex), true)
+        } else {
+            let multiline = query.contains('\n') || query.contains("\\n") || query.contains("\\s");
+            let regex = RegexBuilder::new(&query)
+                .case_insensitive(!case_sensitive)
+                .multi_line(multiline)
+                .build()?;
+            (SearchRegex::Standard(regex), multiline)
+        };
         let inner = SearchInputs {
             query: initial_query,
             files_to_exclude,
@@ -147,9 +226,42 @@ impl SearchQuery {
         })
     }
 
+    /// Creates a query matching code by the shape of its syntax tree, see
+    /// [`language::structural_search`].
+    pub fn structural(
+        query: impl ToString,
+        include_ignored: bool,
+        files_to_include: PathMatcher,
+        files_to_exclude: PathMatcher,
+        buffers: Option<Vec<Model<Buffer>>>,
+    ) -> Result<Self> {
+        let query = query.to_string();
+        let pattern = StructuralPattern::new(&query)?;
+        let inner = SearchInputs {
+            query: query.into(),
+            files_to_exclude,
+            files_to_include,
+            buffers,
+        };
+        Ok(Self::Structural {
+            pattern: Arc::new(pattern),
+            replacement: None,
+            include_ignored,
+            inner,
+        })
+    }
+
     pub fn from_proto(message: proto::SearchQuery) -> Result<Self> {
-        if message.regex {
-            Self::regex(
+        if message.structural {
+            Self::structural(
+                message.query,
+                message.include_ignored,
+                deserialize_path_matches(&message.files_to_include)?,
+                deserialize_path_matches(&message.files_to_exclude)?,
+                None, // search opened only don't need search remote
+            )
+        } else if message.regex {
+            Self::build_regex(
                 message.query,
                 message.whole_word,
                 message.case_sensitive,
@@ -157,6 +269,7 @@ impl SearchQuery {
                 deserialize_path_matches(&message.files_to_include)?,
                 deserialize_path_matches(&message.files_to_exclude)?,
                 None, // search opened only don't need search remote
+                message.advanced_regex,
             )
         } else {
             Self::text(
@@ -180,6 +293,10 @@ impl SearchQuery {
             | Self::Regex {
                 ref mut replacement,
                 ..
+            }
+            | Self::Structural {
+                ref mut replacement,
+                ..
             } => {
                 *replacement = Some(new_replacement);
                 self
@@ -191,6 +308,8 @@ impl SearchQuery {
         proto::SearchQuery {
             query: self.as_str().to_string(),
             regex: self.is_regex(),
+            advanced_regex: self.is_advanced_regex(),
+            structural: self.is_structural(),
             whole_word: self.whole_word(),
             case_sensitive: self.case_sensitive(),
             include_ignored: self.include_ignored(),
@@ -214,7 +333,17 @@ impl SearchQuery {
                 }
             }
             Self::Regex {
-                regex, multiline, ..
+                regex: SearchRegex::Advanced(regex),
+                ..
+            } => {
+                let mut text = String::new();
+                BufReader::new(stream).read_to_string(&mut text)?;
+                Ok(regex.is_match(&text)?)
+            }
+            Self::Regex {
+                regex: SearchRegex::Standard(regex),
+                multiline,
+                ..
             } => {
                 let mut reader = BufReader::new(stream);
                 if *multiline {
@@ -234,38 +363,121 @@ impl SearchQuery {
                     Ok(false)
                 }
             }
+            Self::Structural { pattern, .. } => {
+                // Files are parsed only once opened, so look for the pattern's words instead.
+                let mut text = String::new();
+                BufReader::new(stream).read_to_string(&mut text)?;
+                Ok(pattern
+                    .required_words()
+                    .iter()
+                    .all(|word| text.contains(word.as_str())))
+            }
         }
     }
     /// Returns the replacement text for this `SearchQuery`.
     pub fn replacement(&self) -> Option<&str> {
         match self {
-            SearchQuery::Text { replacement, .. } | SearchQuery::Regex { replacement, .. } => {
-                replacement.as_deref()
-            }
+            SearchQuery::Text { replacement, .. }
+            | SearchQuery::Regex { replacement, .. }
+            | SearchQuery::Structural { replacement, .. } => replacement.as_deref(),
         }
     }
     /// Replaces search hits if replacement is set. `text` is assumed to be a string that matches this `SearchQuery` exactly, without any leftovers on either side.
+    ///
+    /// Queries whose [replacements need context](Self::replacement_needs_context) are better
+    /// served by [`Self::replacement_in_buffer`].
     pub fn replacement_for<'a>(&self, text: &'a str) -> Option<Cow<'a, str>> {
         match self {
             SearchQuery::Text { replacement, .. } => replacement.clone().map(Cow::from),
+            SearchQuery::Structural { .. } => None,
             SearchQuery::Regex {
                 regex, replacement, ..
             } => {
-                if let Some(replacement) = replacement {
-                    let replacement = TEXT_REPLACEMENT_SPECIAL_CHARACTERS_REGEX
-                        .get_or_init(|| Regex::new(r"\\\\|\\n|\\t").unwrap())
-                        .replace_all(replacement, |c: &Captures| {
-                            match c.get(0).unwrap().as_str() {
-                                r"\\" => "\\",
-                                r"\n" => "\n",
-                                r"\t" => "\t",
-                                x => unreachable!("Unexpected escape sequence: {}", x),
-                            }
-                        });
-                    Some(regex.replace(text, replacement))
-                } else {
-                    None
+                let replacement = unescape_replacement(replacement.as_ref()?);
+                match regex {
+                    SearchRegex::Standard(regex) => Some(regex.replace(text, replacement)),
+                    SearchRegex::Advanced(regex) => {
+                        let captures = regex.captures(text).log_err()??;
+                        let mat = captures.get(0)?;
+                        let mut result = text[..mat.start()].to_string();
+                        captures.expand(&replacement, &mut result);
+                        result.push_str(&text[mat.end()..]);
+                        Some(Cow::Owned(result))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether replacing a match requires more than its text, because the query matches
+    /// syntax trees or looks around the match. Such replacements are computed with
+    /// [`Self::replacement_in_buffer`].
+    pub fn replacement_needs_context(&self) -> bool {
+        self.is_structural() || self.is_advanced_regex()
+    }
+
+    /// Replaces a search hit spanning the given range of the buffer, if replacement is set.
+    pub fn replacement_in_buffer(
+        &self,
+        buffer: &BufferSnapshot,
+        range: Range<usize>,
+    ) -> Option<String> {
+        match self {
+            SearchQuery::Structural {
+                pattern,
+                replacement,
+                ..
+            } => {
+                let replacement = replacement.as_ref()?;
+                let mat = pattern.match_at(buffer, range)?;
+                Some(pattern.replacement(replacement, &mat, buffer))
+            }
+            SearchQuery::Regex {
+                regex: SearchRegex::Advanced(regex),
+                replacement,
+                ..
+            } => {
+                let replacement = unescape_replacement(replacement.as_ref()?);
+
+                // Rematch with the surrounding lines, so that lookarounds behave as they did
+                // during the search.
+                let context_start = buffer.clip_offset(
+                    range
+                        .start
+                        .saturating_sub(ADVANCED_REGEX_REPLACEMENT_CONTEXT),
+                    Bias::Left,
+                );
+                let context_start = buffer
+                    .point_to_offset(Point::new(buffer.offset_to_point(context_start).row, 0));
+                let context_end = buffer.clip_offset(
+                    (range.end + ADVANCED_REGEX_REPLACEMENT_CONTEXT).min(buffer.len()),
+                    Bias::Right,
+                );
+                let context_end_row = buffer.offset_to_point(context_end).row;
+                let context_end = buffer.point_to_offset(Point::new(
+                    context_end_row,
+                    buffer.line_len(context_end_row),
+                ));
+                let text = buffer
+                    .text_for_range(context_start..context_end)
+                    .collect::<String>();
+
+                let captures = regex
+                    .captures_from_pos(&text, range.start - context_start)
+                    .log_err()??;
+                let mat = captures.get(0)?;
+                if context_start + mat.start() != range.start
+                    || context_start + mat.end() != range.end
+                {
+                    return None;
                 }
+                let mut result = String::new();
+                captures.expand(&replacement, &mut result);
+                Some(result)
+            }
+            _ => {
+                let text = buffer.text_for_range(range).collect::<String>();
+                self.replacement_for(&text).map(Cow::into_owned)
             }
         }
     }
@@ -281,6 +493,16 @@ impl SearchQuery {
             return Default::default();
         }
 
+        if let Self::Structural { pattern, .. } = self {
+            let range = subrange.unwrap_or(0..buffer.len());
+            let range_offset = range.start;
+            return pattern
+                .find_matches(buffer, range)
+                .into_iter()
+                .map(|mat| mat.range.start - range_offset..mat.range.end - range_offset)
+                .collect();
+        }
+
         let range_offset = subrange.as_ref().map(|r| r.start).unwrap_or(0);
         let rope = if let Some(range) = subrange {
             buffer.as_rope().slice(range)
@@ -323,7 +545,30 @@ impl SearchQuery {
             }
 
             Self::Regex {
-                regex, multiline, ..
+                regex: SearchRegex::Advanced(regex),
+                ..
+            } => {
+                let text = rope.to_string();
+                for (ix, mat) in regex.find_iter(&text).enumerate() {
+                    if (ix + 1) % YIELD_INTERVAL == 0 {
+                        yield_now().await;
+                    }
+
+                    match mat {
+                        Ok(mat) => matches.push(mat.start()..mat.end()),
+                        Err(error) => {
+                            // Keep the matches found before the engine gave up.
+                            log::warn!("stopped searching for {:?}: {error}", self.as_str());
+                            break;
+                        }
+                    }
+                }
+            }
+
+            Self::Regex {
+                regex: SearchRegex::Standard(regex),
+                multiline,
+                ..
             } => {
                 if *multiline {
                     let text = rope.to_string();
@@ -358,6 +603,7 @@ impl SearchQuery {
                     }
                 }
             }
+            Self::Structural { .. } => {}
         }
 
         matches
@@ -375,6 +621,7 @@ impl SearchQuery {
         match self {
             Self::Text { whole_word, .. } => *whole_word,
             Self::Regex { whole_word, .. } => *whole_word,
+            Self::Structural { .. } => false,
         }
     }
 
@@ -382,6 +629,7 @@ impl SearchQuery {
         match self {
             Self::Text { case_sensitive, .. } => *case_sensitive,
             Self::Regex { case_sensitive, .. } => *case_sensitive,
+            Self::Structural { .. } => true,
         }
     }
 
@@ -392,6 +640,9 @@ impl SearchQuery {
             } => *include_ignored,
             Self::Regex {
                 include_ignored, ..
+            }
+            | Self::Structural {
+                include_ignored, ..
             } => *include_ignored,
         }
     }
@@ -400,6 +651,20 @@ impl SearchQuery {
         matches!(self, Self::Regex { .. })
     }
 
+    pub fn is_advanced_regex(&self) -> bool {
+        matches!(
+            self,
+            Self::Regex {
+                regex: SearchRegex::Advanced(_),
+                ..
+            }
+        )
+    }
+
+    pub fn is_structural(&self) -> bool {
+        matches!(self, Self::Structural { .. })
+    }
+
     pub fn files_to_include(&self) -> &PathMatcher {
         self.as_inner().files_to_include()
     }
@@ -437,11 +702,27 @@ impl SearchQuery {
     }
     pub fn as_inner(&self) -> &SearchInputs {
         match self {
-            Self::Regex { inner, .. } | Self::Text { inner, .. } => inner,
+            Self::Regex { inner, .. }
+            | Self::Text { inner, .. }
+            | Self::Structural { inner, .. } => inner,
         }
     }
 }
 
+/// Expands the `\\`, `\n` and `\t` escapes in a regex replacement.
+fn unescape_replacement(replacement: &str) -> Cow<'_, str> {
+    TEXT_REPLACEMENT_SPECIAL_CHARACTERS_REGEX
+        .get_or_init(|| Regex::new(r"\\\\|\\n|\\t").unwrap())
+        .replace_all(replacement, |c: &Captures| {
+            match c.get(0).unwrap().as_str() {
+                r"\\" => "\\",
+                r"\n" => "\n",
+                r"\t" => "\t",
+                x => unreachable!("Unexpected escape sequence: {}", x),
+            }
+        })
+}
+
 pub fn deserialize_path_matches(glob_set: &str) -> anyhow::Result<PathMatcher> {
     let globs = glob_set
         .split(',')
@@ -455,6 +736,7 @@ pub fn deserialize_path_matches(glob_set: &str) -> anyhow::Result<PathMatcher> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use gpui::Context as _;
 
     #[test]
     fn path_matcher_creation_for_valid_paths() {
@@ -477,6 +759,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn structural_query_round_trip() {
+        let query = SearchQuery::structural(
+            "$x.unwrap()",
+            true,
+            PathMatcher::new(&["*.rs".to_owned()]).unwrap(),
+            PathMatcher::default(),
+            None,
+        )
+        .unwrap();
+        let query = SearchQuery::from_proto(query.to_proto()).unwrap();
+        assert!(query.is_structural());
+        assert!(query.include_ignored());
+        assert_eq!(query.as_str(), "$x.unwrap()");
+
+        assert!(query.detect("let a = b.unwrap();".as_bytes()).unwrap());
+        assert!(!query.detect("let a = b.expect(c);".as_bytes()).unwrap());
+        assert!(SearchQuery::structural(
+            "$x",
+            false,
+            PathMatcher::default(),
+            PathMatcher::default(),
+            None
+        )
+        .is_err());
+    }
+
+    #[gpui::test]
+    async fn advanced_regex_search(cx: &mut gpui::TestAppContext) {
+        let advanced_regex = |query: &str| {
+            SearchQuery::advanced_regex(
+                query,
+                false,
+                true,
+                false,
+                PathMatcher::default(),
+                PathMatcher::default(),
+                None,
+            )
+            .unwrap()
+        };
+        let buffer = cx.new_model(|cx| Buffer::local("foo = 1\nbar = foo\nfoo foo\n", cx));
+        let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+
+        let query = advanced_regex(r"(?<=bar = )\w+");
+        assert_eq!(query.search(&snapshot, None).await, [14..17]);
+        assert!(query.detect("bar = baz".as_bytes()).unwrap());
+        assert!(!query.detect("baz = bar".as_bytes()).unwrap());
+
+        let query = advanced_regex(r"(\w+) \1$").with_replacement("$1!".into());
+        assert_eq!(query.search(&snapshot, None).await, [18..25]);
+        assert_eq!(
+            query.replacement_in_buffer(&snapshot, 18..25).as_deref(),
+            Some("foo!")
+        );
+
+        // Lookarounds see the text around the match when replacing it.
+        let query = advanced_regex(r"^foo(?= )").with_replacement("baz".into());
+        assert!(query.replacement_needs_context());
+        assert_eq!(query.search(&snapshot, None).await, [0..3, 18..21]);
+        assert_eq!(query.replacement_for("foo"), None);
+        assert_eq!(
+            query.replacement_in_buffer(&snapshot, 18..21).as_deref(),
+            Some("baz")
+        );
+
+        let query = SearchQuery::from_proto(query.to_proto()).unwrap();
+        assert!(query.is_advanced_regex());
+
+        // Catastrophic patterns give up rather than stalling the search.
+        let buffer = cx.new_model(|cx| Buffer::local(format!("{}c", "a".repeat(30)), cx));
+        let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+        let query = advanced_regex(r"(a+)+\1b");
+        assert!(query.search(&snapshot, None).await.is_empty());
+    }
+
     #[test]
     fn path_matcher_creation_for_globs() {
         for invalid_glob in ["dir/[].txt", "dir/[a-z.txt", "dir/{file"] {
@@ -10,7 +10,7 @@ use std::{
 
 use anyhow::{Context, Result};
 use collections::{HashMap, HashSet, VecDeque};
-use gpui::{AppContext, Context as _, Model};
+use gpui::{AppContext, Context as _, Model, Task};
 use itertools::Itertools;
 use language::{ContextProvider, File, Language, Location};
 use settings::{parse_json_with_comments, SettingsLocation};
@@ -439,7 +439,7 @@ impl ContextProvider for BasicContextProvider {
         location: &Location,
         _: Option<&HashMap<String, String>>,
         cx: &mut AppContext,
-    ) -> Result<TaskVariables> {
+    ) -> Task<Result<TaskVariables>> {
         let buffer = location.buffer.read(cx);
         let buffer_snapshot = buffer.snapshot();
         let symbols = buffer_snapshot.symbols_containing(location.range.start, None);
@@ -517,7 +517,7 @@ impl ContextProvider for BasicContextProvider {
             task_variables.insert(VariableName::File, path_as_string);
         }
 
-        Ok(task_variables)
+        Task::ready(Ok(task_variables))
     }
 }
 
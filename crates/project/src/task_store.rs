@@ -333,19 +333,15 @@ fn local_task_context_for_location(
             .ok()?
             .await;
 
-        let mut task_variables = cx
-            .update(|cx| {
-                combine_task_variables(
-                    captured_variables,
-                    location,
-                    project_env.as_ref(),
-                    BasicContextProvider::new(worktree_store),
-                    cx,
-                )
-                .log_err()
-            })
-            .ok()
-            .flatten()?;
+        let mut task_variables = combine_task_variables(
+            captured_variables,
+            location,
+            project_env.as_ref(),
+            BasicContextProvider::new(worktree_store),
+            &mut cx,
+        )
+        .await
+        .log_err()?;
         // Remove all custom entries starting with _, as they're not intended for use by the end user.
         task_variables.sweep();
 
@@ -366,26 +362,33 @@ fn remote_task_context_for_location(
     cx: &mut AppContext,
 ) -> Task<Option<TaskContext>> {
     // We need to gather a client context, as the headless one may lack certain information (e.g. tree-sitter parsing is disabled there, so symbols are not available).
-    let mut remote_context = BasicContextProvider::new(worktree_store)
-        .build_context(&TaskVariables::default(), &location, None, cx)
-        .log_err()
-        .unwrap_or_default();
-    remote_context.extend(captured_variables);
-
-    let context_task = upstream_client.request(proto::TaskContextForLocation {
-        project_id,
-        location: Some(proto::Location {
-            buffer_id: location.buffer.read(cx).remote_id().into(),
-            start: Some(serialize_anchor(&location.range.start)),
-            end: Some(serialize_anchor(&location.range.end)),
-        }),
-        task_variables: remote_context
-            .into_iter()
-            .map(|(k, v)| (k.to_string(), v))
-            .collect(),
-    });
+    let remote_context = BasicContextProvider::new(worktree_store).build_context(
+        &TaskVariables::default(),
+        &location,
+        None,
+        cx,
+    );
+    let proto_location = proto::Location {
+        buffer_id: location.buffer.read(cx).remote_id().into(),
+        start: Some(serialize_anchor(&location.range.start)),
+        end: Some(serialize_anchor(&location.range.end)),
+    };
+    let upstream_client = upstream_client.clone();
     cx.spawn(|_| async move {
-        let task_context = context_task.await.log_err()?;
+        let mut remote_context = remote_context.await.log_err().unwrap_or_default();
+        remote_context.extend(captured_variables);
+
+        let task_context = upstream_client
+            .request(proto::TaskContextForLocation {
+                project_id,
+                location: Some(proto_location),
+                task_variables: remote_context
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect(),
+            })
+            .await
+            .log_err()?;
         Some(TaskContext {
             cwd: task_context.cwd.map(PathBuf::from),
             task_variables: task_context
@@ -406,27 +409,30 @@ fn remote_task_context_for_location(
     })
 }
 
-fn combine_task_variables(
+async fn combine_task_variables(
     mut captured_variables: TaskVariables,
     location: Location,
     project_env: Option<&HashMap<String, String>>,
     baseline: BasicContextProvider,
-    cx: &mut AppContext,
+    cx: &mut AsyncAppContext,
 ) -> anyhow::Result<TaskVariables> {
-    let language_context_provider = location
-        .buffer
-        .read(cx)
-        .language()
-        .and_then(|language| language.context_provider());
-    let baseline = baseline
-        .build_context(&captured_variables, &location, project_env, cx)
+    let language_context_provider = location.buffer.read_with(cx, |buffer, _| {
+        buffer
+            .language()
+            .and_then(|language| language.context_provider())
+    })?;
+    let baseline = cx
+        .update(|cx| baseline.build_context(&captured_variables, &location, project_env, cx))?
+        .await
         .context("building basic default context")?;
     captured_variables.extend(baseline);
     if let Some(provider) = language_context_provider {
         captured_variables.extend(
-            provider
-                .build_context(&captured_variables, &location, project_env, cx)
-                .context("building provider context")?,
+            cx.update(|cx| {
+                provider.build_context(&captured_variables, &location, project_env, cx)
+            })?
+            .await
+            .context("building provider context")?,
         );
     }
     Ok(captured_variables)
@@ -0,0 +1,511 @@
+//! Renames, moves, deletes and creates many project entries at once, by editing a buffer that
+//! lists them one per line.
+//!
+//! Each listed entry is shown as its number, a tab and its path relative to its worktree, with
+//! directories ending in `/`. On save, edited paths are applied as renames (which move the entry
+//! when the directory changes), removed lines as moves to the trash, and lines without a number as
+//! new files, or directories when they end in `/`.
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::{HashMap, HashSet};
+use editor::{Editor, EditorEvent};
+use gpui::{
+    AnyView, AppContext, EventEmitter, FocusHandle, FocusableView, Model, Render, Task, View,
+    ViewContext, VisualContext as _,
+};
+use language::Buffer;
+use project::{Entry, Project, ProjectEntryId, ProjectPath, WorktreeId};
+use std::{
+    any::TypeId,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use ui::prelude::*;
+use workspace::{item::Item, searchable::SearchableItemHandle};
+use worktree::CreatedEntry;
+
+pub struct BulkRenameEditor {
+    editor: View<Editor>,
+    buffer: Model<Buffer>,
+    project: Model<Project>,
+    entries: Vec<ListedEntry>,
+}
+
+#[derive(Clone, Debug)]
+struct ListedEntry {
+    id: ProjectEntryId,
+    worktree_id: WorktreeId,
+    path: Arc<Path>,
+    is_dir: bool,
+}
+
+impl ListedEntry {
+    fn new(worktree_id: WorktreeId, entry: &Entry) -> Self {
+        Self {
+            id: entry.id,
+            worktree_id,
+            path: entry.path.clone(),
+            is_dir: entry.is_dir(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum BulkEdit {
+    Delete {
+        ix: usize,
+    },
+    Rename {
+        ix: usize,
+        old_path: PathBuf,
+        new_path: PathBuf,
+    },
+    Create {
+        path: PathBuf,
+        is_dir: bool,
+    },
+}
+
+impl BulkRenameEditor {
+    pub fn new(
+        entries: Vec<(WorktreeId, Entry)>,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let entries = entries
+            .iter()
+            .map(|(worktree_id, entry)| ListedEntry::new(*worktree_id, entry))
+            .collect::<Vec<_>>();
+        let buffer = cx.new_model(|cx| Buffer::local(listing_text(&entries), cx));
+        let editor = cx.new_view(|cx| Editor::for_buffer(buffer.clone(), None, cx));
+        cx.subscribe(&editor, |_, _, event: &EditorEvent, cx| {
+            cx.emit(event.clone())
+        })
+        .detach();
+        Self {
+            editor,
+            buffer,
+            project,
+            entries,
+        }
+    }
+
+    fn listed_paths(&self) -> Vec<Arc<Path>> {
+        self.entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+
+    /// Applies the edits one at a time, then lists the resulting entries. An edit that fails
+    /// stops the remaining ones from being applied.
+    fn apply_edits(
+        &mut self,
+        edits: Vec<BulkEdit>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let project = self.project.clone();
+        let entries = self.entries.clone();
+        let worktree_ids = entries
+            .iter()
+            .map(|entry| entry.worktree_id)
+            .collect::<HashSet<_>>();
+        let creation_worktree_id = (worktree_ids.len() == 1)
+            .then(|| worktree_ids.into_iter().next())
+            .flatten();
+        cx.spawn(|this, mut cx| async move {
+            let mut results: HashMap<usize, Option<ListedEntry>> = HashMap::default();
+            let mut created = Vec::new();
+            for edit in edits {
+                match edit {
+                    BulkEdit::Delete { ix } => {
+                        let entry = &entries[ix];
+                        project
+                            .update(&mut cx, |project, cx| {
+                                project.delete_entry(entry.id, true, cx)
+                            })?
+                            .with_context(|| format!("no such entry: {:?}", entry.path))?
+                            .await?;
+                        results.insert(ix, None);
+                    }
+                    BulkEdit::Rename {
+                        ix,
+                        old_path,
+                        new_path,
+                    } => {
+                        let entry = &entries[ix];
+                        let created_entry = project
+                            .update(&mut cx, |project, cx| {
+                                // The entry may have moved along with a renamed directory.
+                                let entry_id = project
+                                    .entry_for_path(
+                                        &ProjectPath {
+                                            worktree_id: entry.worktree_id,
+                                            path: old_path.into(),
+                                        },
+                                        cx,
+                                    )
+                                    .map_or(entry.id, |entry| entry.id);
+                                project.rename_entry(entry_id, new_path, cx)
+                            })?
+                            .await?;
+                        let new_entry = match created_entry {
+                            CreatedEntry::Included(new_entry) => {
+                                Some(ListedEntry::new(entry.worktree_id, &new_entry))
+                            }
+                            CreatedEntry::Excluded { .. } => None,
+                        };
+                        results.insert(ix, new_entry);
+                    }
+                    BulkEdit::Create { path, is_dir } => {
+                        let worktree_id = creation_worktree_id.ok_or_else(|| {
+                            anyhow!("can't tell which worktree to create {path:?} in")
+                        })?;
+                        let created_entry = project
+                            .update(&mut cx, |project, cx| {
+                                project.create_entry(
+                                    ProjectPath {
+                                        worktree_id,
+                                        path: path.into(),
+                                    },
+                                    is_dir,
+                                    cx,
+                                )
+                            })?
+                            .await?;
+                        if let CreatedEntry::Included(new_entry) = created_entry {
+                            created.push(ListedEntry::new(worktree_id, &new_entry));
+                        }
+                    }
+                }
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.entries = entries
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(ix, entry)| results.remove(&ix).unwrap_or(Some(entry)))
+                    .chain(created)
+                    .collect();
+                let text = listing_text(&this.entries);
+                this.buffer.update(cx, |buffer, cx| {
+                    buffer.set_text(text, cx);
+                    buffer.did_save(buffer.version(), None, cx);
+                });
+                cx.notify();
+            })
+        })
+    }
+}
+
+fn listing_text(entries: &[ListedEntry]) -> String {
+    let mut text = String::new();
+    for (ix, entry) in entries.iter().enumerate() {
+        text.push_str(&format!("{}\t{}", ix + 1, entry.path.to_string_lossy()));
+        if entry.is_dir {
+            text.push('/');
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Compares the edited listing with the paths that were listed, and returns the edits to apply:
+/// deletions first, then renames, and finally new entries. Deletions start with the most deeply
+/// nested entries, so that deleting a directory doesn't affect entries in it that are still to be
+/// deleted. Renames start with the least nested entries, and the entries in a renamed directory
+/// are renamed from their path in the directory's new location.
+fn plan_edits(listed_paths: &[Arc<Path>], text: &str) -> Result<Vec<BulkEdit>> {
+    let mut new_paths: Vec<Option<PathBuf>> = vec![None; listed_paths.len()];
+    let mut created = Vec::new();
+    for (row, line) in text.lines().enumerate() {
+        let line_number = row + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let numbered_path = line.split_once('\t').and_then(|(number, path)| {
+            let ix = number.trim().parse::<usize>().ok()?.checked_sub(1)?;
+            Some((ix, path))
+        });
+        match numbered_path {
+            Some((ix, path)) => {
+                let new_path = new_paths.get_mut(ix).with_context(|| {
+                    format!("line {line_number}: no entry is numbered {}", ix + 1)
+                })?;
+                if new_path.is_some() {
+                    return Err(anyhow!(
+                        "line {line_number}: entry {} is listed more than once",
+                        ix + 1
+                    ));
+                }
+                let (path, _) = parse_path(path)
+                    .with_context(|| format!("line {line_number}: invalid path {path:?}"))?;
+                *new_path = Some(path);
+            }
+            None => {
+                let (path, is_dir) = parse_path(line)
+                    .with_context(|| format!("line {line_number}: invalid path {line:?}"))?;
+                created.push(BulkEdit::Create { path, is_dir });
+            }
+        }
+    }
+
+    let mut deletions = Vec::new();
+    let mut renames = Vec::new();
+    for (ix, new_path) in new_paths.into_iter().enumerate() {
+        match new_path {
+            None => deletions.push(ix),
+            Some(new_path) if new_path.as_path() != listed_paths[ix].as_ref() => {
+                renames.push((ix, new_path))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let renamed_paths = renames
+        .iter()
+        .map(|(ix, _)| listed_paths[*ix].as_ref())
+        .collect::<HashSet<_>>();
+    for (ix, new_path) in &renames {
+        if renamed_paths.contains(new_path.as_path()) {
+            return Err(anyhow!(
+                "entry {} is renamed to {new_path:?}, which is the current name of another \
+                 renamed entry; swap names in two separate saves",
+                ix + 1
+            ));
+        }
+    }
+    let depth = |ix: usize| listed_paths[ix].components().count();
+    deletions.sort_by_key(|ix| std::cmp::Reverse(depth(*ix)));
+    renames.sort_by_key(|(ix, _)| depth(*ix));
+
+    let mut renamed_dirs: Vec<(&Path, PathBuf)> = Vec::new();
+    let mut rename_edits = Vec::new();
+    for (ix, new_path) in renames {
+        let listed_path = listed_paths[ix].as_ref();
+        let old_path = renamed_dirs
+            .iter()
+            .rev()
+            .find_map(|(dir_path, new_dir_path)| {
+                let suffix = listed_path.strip_prefix(dir_path).ok()?;
+                Some(new_dir_path.join(suffix))
+            })
+            .unwrap_or_else(|| listed_path.to_path_buf());
+        renamed_dirs.push((listed_path, new_path.clone()));
+        // The entry already moved along with its directory.
+        if old_path == new_path {
+            continue;
+        }
+        rename_edits.push(BulkEdit::Rename {
+            ix,
+            old_path,
+            new_path,
+        });
+    }
+
+    Ok(deletions
+        .into_iter()
+        .map(|ix| BulkEdit::Delete { ix })
+        .chain(rename_edits)
+        .chain(created)
+        .collect())
+}
+
+/// Parses a worktree-relative path, returning whether it denotes a directory.
+fn parse_path(text: &str) -> Result<(PathBuf, bool)> {
+    let text = text.trim_end();
+    let is_dir = text.ends_with('/');
+    let path = PathBuf::from(text.trim_end_matches('/'));
+    if path.as_os_str().is_empty() {
+        return Err(anyhow!("path is empty"));
+    }
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(anyhow!("path must be relative to its worktree"));
+    }
+    Ok((path, is_dir))
+}
+
+impl EventEmitter<EditorEvent> for BulkRenameEditor {}
+
+impl FocusableView for BulkRenameEditor {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl Render for BulkRenameEditor {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        div()
+            .size_full()
+            .key_context("BulkRenameEditor")
+            .child(self.editor.clone())
+    }
+}
+
+impl Item for BulkRenameEditor {
+    type Event = EditorEvent;
+
+    fn tab_icon(&self, _: &WindowContext) -> Option<Icon> {
+        Some(Icon::new(IconName::Pencil))
+    }
+
+    fn tab_content_text(&self, _: &WindowContext) -> Option<SharedString> {
+        Some("Bulk Rename".into())
+    }
+
+    fn to_item_events(event: &EditorEvent, f: impl FnMut(workspace::item::ItemEvent)) {
+        Editor::to_item_events(event, f)
+    }
+
+    fn as_searchable(&self, _: &View<Self>) -> Option<Box<dyn SearchableItemHandle>> {
+        Some(Box::new(self.editor.clone()))
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a View<Self>,
+        _: &'a AppContext,
+    ) -> Option<AnyView> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.to_any())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.editor.to_any())
+        } else {
+            None
+        }
+    }
+
+    fn deactivated(&mut self, cx: &mut ViewContext<Self>) {
+        self.editor.update(cx, Item::deactivated);
+    }
+
+    fn is_dirty(&self, cx: &AppContext) -> bool {
+        self.buffer.read(cx).is_dirty()
+    }
+
+    fn can_save(&self, _: &AppContext) -> bool {
+        true
+    }
+
+    fn save(
+        &mut self,
+        _format: bool,
+        _project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let text = self.buffer.read(cx).text();
+        match plan_edits(&self.listed_paths(), &text) {
+            Ok(edits) => self.apply_edits(edits, cx),
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_edits() {
+        let listed_paths: Vec<Arc<Path>> = ["a", "a/one.rs", "a/two.rs", "b.txt"]
+            .into_iter()
+            .map(|path| Arc::from(Path::new(path)))
+            .collect();
+
+        assert_eq!(
+            plan_edits(&listed_paths, "1\ta/\n2\ta/one.rs\n3\ta/two.rs\n4\tb.txt\n").unwrap(),
+            Vec::new()
+        );
+        assert_eq!(
+            plan_edits(
+                &listed_paths,
+                "1\ta/\n3\ta/three.rs\n2\ta/one.rs\nnew/\nnew/file.rs\n"
+            )
+            .unwrap(),
+            vec![
+                BulkEdit::Delete { ix: 3 },
+                BulkEdit::Rename {
+                    ix: 2,
+                    old_path: PathBuf::from("a/two.rs"),
+                    new_path: PathBuf::from("a/three.rs")
+                },
+                BulkEdit::Create {
+                    path: PathBuf::from("new"),
+                    is_dir: true
+                },
+                BulkEdit::Create {
+                    path: PathBuf::from("new/file.rs"),
+                    is_dir: false
+                },
+            ]
+        );
+
+        assert_eq!(
+            plan_edits(&listed_paths, "4\tb.txt\n").unwrap(),
+            vec![
+                BulkEdit::Delete { ix: 1 },
+                BulkEdit::Delete { ix: 2 },
+                BulkEdit::Delete { ix: 0 },
+            ]
+        );
+
+        assert!(plan_edits(&listed_paths, "5\tc.txt\n").is_err());
+        assert!(plan_edits(&listed_paths, "1\tc\n1\td\n").is_err());
+        assert!(plan_edits(&listed_paths, "4\t../b.txt\n").is_err());
+        assert!(plan_edits(&listed_paths, "/etc/passwd\n").is_err());
+        assert!(plan_edits(&listed_paths, "2\ta/two.rs\n3\ta/one.rs\n").is_err());
+    }
+
+    #[test]
+    fn test_plan_edits_in_renamed_directory() {
+        let listed_paths: Vec<Arc<Path>> = ["a", "a/b", "a/b/one.rs", "a/two.rs"]
+            .into_iter()
+            .map(|path| Arc::from(Path::new(path)))
+            .collect();
+
+        assert_eq!(
+            plan_edits(
+                &listed_paths,
+                "1\tc/\n2\tc/d/\n3\tc/d/one.rs\n4\tc/three.rs\n"
+            )
+            .unwrap(),
+            vec![
+                BulkEdit::Rename {
+                    ix: 0,
+                    old_path: PathBuf::from("a"),
+                    new_path: PathBuf::from("c")
+                },
+                BulkEdit::Rename {
+                    ix: 1,
+                    old_path: PathBuf::from("c/b"),
+                    new_path: PathBuf::from("c/d")
+                },
+                BulkEdit::Rename {
+                    ix: 3,
+                    old_path: PathBuf::from("c/two.rs"),
+                    new_path: PathBuf::from("c/three.rs")
+                },
+            ]
+        );
+
+        assert_eq!(
+            plan_edits(&listed_paths, "1\tc/\n2\tc/b/\n3\tone.rs\n4\ta/two.rs\n").unwrap(),
+            vec![
+                BulkEdit::Rename {
+                    ix: 0,
+                    old_path: PathBuf::from("a"),
+                    new_path: PathBuf::from("c")
+                },
+                BulkEdit::Rename {
+                    ix: 2,
+                    old_path: PathBuf::from("c/b/one.rs"),
+                    new_path: PathBuf::from("one.rs")
+                },
+            ]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+mod bulk_rename;
 mod project_panel_settings;
 
 use client::{ErrorCode, ErrorExt};
@@ -8,11 +9,12 @@ use db::kvp::KEY_VALUE_STORE;
 use editor::{
     items::entry_git_aware_label_color,
     scroll::{Autoscroll, ScrollbarAutoHide},
-    Editor, EditorEvent, EditorSettings, ShowScrollbar,
+    Editor, EditorEvent, EditorSettings, MultiBuffer, ShowScrollbar,
 };
 use file_icons::FileIcons;
 
 use anyhow::{anyhow, Context as _, Result};
+use bulk_rename::BulkRenameEditor;
 use collections::{hash_map, BTreeSet, HashMap};
 use git::repository::GitFileStatus;
 use gpui::{
@@ -25,6 +27,7 @@ use gpui::{
     WeakView, WindowContext,
 };
 use indexmap::IndexMap;
+use language::{Buffer, Capability};
 use menu::{Confirm, SelectFirst, SelectLast, SelectNext, SelectPrev};
 use project::{
     relativize_path, Entry, EntryKind, Fs, Project, ProjectEntryId, ProjectPath, Worktree,
@@ -170,6 +173,8 @@ actions!(
         UnfoldDirectory,
         FoldDirectory,
         SelectParent,
+        CompareSelectedFiles,
+        BulkRename,
     ]
 );
 
@@ -512,6 +517,7 @@ impl ProjectPanel {
             let is_read_only = project.is_read_only(cx);
             let is_remote = project.is_via_collab();
             let is_local = project.is_local();
+            let can_compare = self.files_to_compare(cx).is_some();
 
             let context_menu = ContextMenu::build(cx, |menu, cx| {
                 menu.context(self.focus_handle.clone()).map(|menu| {
@@ -519,6 +525,9 @@ impl ProjectPanel {
                         menu.when(is_dir, |menu| {
                             menu.action("Search Inside", Box::new(NewSearchInDirectory))
                         })
+                        .when(can_compare, |menu| {
+                            menu.action("Compare Selected Files", Box::new(CompareSelectedFiles))
+                        })
                     } else {
                         menu.action("New File", Box::new(NewFile))
                             .action("New Folder", Box::new(NewDirectory))
@@ -555,11 +564,18 @@ impl ProjectPanel {
                                     menu.disabled_action("Paste", Box::new(Paste))
                                 }
                             })
+                            .when(can_compare, |menu| {
+                                menu.action(
+                                    "Compare Selected Files",
+                                    Box::new(CompareSelectedFiles),
+                                )
+                            })
                             .separator()
                             .action("Copy Path", Box::new(CopyPath))
                             .action("Copy Relative Path", Box::new(CopyRelativePath))
                             .separator()
                             .action("Rename", Box::new(Rename))
+                            .action("Bulk Rename…", Box::new(BulkRename))
                             .when(!is_root, |menu| {
                                 menu.action("Trash", Box::new(Trash { skip_prompt: false }))
                                     .action("Delete", Box::new(Delete { skip_prompt: false }))
@@ -1621,6 +1637,123 @@ impl ProjectPanel {
         }
     }
 
+    /// Returns the two marked files in the order they appear in the panel, if exactly two files are marked.
+    fn files_to_compare(&self, cx: &AppContext) -> Option<[ProjectPath; 2]> {
+        let project = self.project.read(cx);
+        let mut files = self
+            .marked_entries()
+            .into_iter()
+            .map(|selection| {
+                let worktree = project.worktree_for_id(selection.worktree_id, cx)?;
+                let entry = worktree.read(cx).entry_for_id(selection.entry_id)?;
+                let project_path = ProjectPath {
+                    worktree_id: selection.worktree_id,
+                    path: entry.path.clone(),
+                };
+                entry
+                    .is_file()
+                    .then(|| (self.index_for_selection(selection), project_path))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        files.sort_by_key(|(ix, _)| ix.map(|(_, _, visible_ix)| visible_ix));
+        match files.as_slice() {
+            [(_, old_path), (_, new_path)] => Some([old_path.clone(), new_path.clone()]),
+            _ => None,
+        }
+    }
+
+    fn compare_selected_files(&mut self, _: &CompareSelectedFiles, cx: &mut ViewContext<Self>) {
+        let Some([old_path, new_path]) = self.files_to_compare(cx) else {
+            return;
+        };
+        let title = format!(
+            "{} ↔ {}",
+            old_path.path.to_string_lossy(),
+            new_path.path.to_string_lossy()
+        );
+        let (old_buffer, new_buffer) = self.project.update(cx, |project, cx| {
+            (
+                project.open_buffer(old_path, cx),
+                project.open_buffer(new_path, cx),
+            )
+        });
+        let project = self.project.clone();
+        let workspace = self.workspace.clone();
+        cx.spawn(|_, mut cx| async move {
+            let old_buffer = old_buffer.await?;
+            let new_buffer = new_buffer.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let old_text = old_buffer.read(cx).text();
+                let (new_text, language) = {
+                    let new_buffer = new_buffer.read(cx);
+                    (new_buffer.text(), new_buffer.language().cloned())
+                };
+                let language_registry = project.read(cx).languages().clone();
+                // The comparison is shown as the second file's contents, diffed against the first
+                // file as if it were the second file's previous version.
+                let diff_buffer = cx.new_model(|cx| {
+                    let mut buffer = Buffer::local(new_text, cx);
+                    buffer.set_language_registry(language_registry);
+                    buffer.set_language(language, cx);
+                    buffer.set_diff_base(Some(old_text), cx);
+                    buffer.set_capability(Capability::ReadOnly, cx);
+                    buffer
+                });
+                let multibuffer =
+                    cx.new_model(|cx| MultiBuffer::singleton(diff_buffer, cx).with_title(title));
+                let editor = cx.new_view(|cx| {
+                    let mut editor = Editor::for_multibuffer(multibuffer, Some(project), true, cx);
+                    editor.set_expand_all_diff_hunks();
+                    editor
+                });
+                workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn bulk_rename(&mut self, _: &BulkRename, cx: &mut ViewContext<Self>) {
+        let project = self.project.read(cx);
+        let mut selections = self.marked_entries().into_iter().collect::<Vec<_>>();
+        selections.sort_by_key(|selection| {
+            self.index_for_selection(*selection)
+                .map(|(_, _, visible_ix)| visible_ix)
+        });
+
+        let mut entries = Vec::new();
+        let list_children = selections.len() == 1;
+        for selection in selections {
+            let Some(worktree) = project.worktree_for_id(selection.worktree_id, cx) else {
+                continue;
+            };
+            let worktree = worktree.read(cx);
+            let Some(entry) = worktree.entry_for_id(selection.entry_id) else {
+                continue;
+            };
+            // A single selected directory is renamed by editing its contents.
+            if list_children && entry.is_dir() {
+                entries.extend(
+                    worktree
+                        .child_entries(&entry.path)
+                        .map(|child| (worktree.id(), child.clone())),
+                );
+            } else if Some(entry) != worktree.root_entry() {
+                entries.push((worktree.id(), entry.clone()));
+            }
+        }
+        if entries.is_empty() {
+            return;
+        }
+
+        let project = self.project.clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                let editor = cx.new_view(|cx| BulkRenameEditor::new(entries, project, cx));
+                workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+            })
+            .ok();
+    }
+
     fn move_entry(
         &mut self,
         entry_to_move: ProjectEntryId,
@@ -1669,28 +1802,79 @@ impl ProjectPanel {
         destination_is_file: bool,
         cx: &mut ViewContext<Self>,
     ) {
-        let destination_worktree = self.project.update(cx, |project, cx| {
+        let Some((entry_path, new_path, destination_worktree_id)) = maybe!({
+            let project = self.project.read(cx);
             let entry_path = project.path_for_entry(entry_to_move, cx)?;
-            let destination_entry_path = project.path_for_entry(destination, cx)?.path.clone();
+            let destination_entry_path = project.path_for_entry(destination, cx)?.path;
 
             let mut destination_path = destination_entry_path.as_ref();
             if destination_is_file {
                 destination_path = destination_path.parent()?;
             }
 
-            let mut new_path = destination_path.to_path_buf();
-            new_path.push(entry_path.path.file_name()?);
-            if new_path != entry_path.path.as_ref() {
-                let task = project.rename_entry(entry_to_move, new_path, cx);
-                cx.foreground_executor().spawn(task).detach_and_log_err(cx);
-            }
-
-            project.worktree_id_for_entry(destination, cx)
-        });
+            let new_path = destination_path.join(entry_path.path.file_name()?);
+            let destination_worktree_id = project.worktree_id_for_entry(destination, cx)?;
+            Some((entry_path, new_path, destination_worktree_id))
+        }) else {
+            return;
+        };
 
-        if let Some(destination_worktree) = destination_worktree {
-            self.expand_entry(destination_worktree, destination, cx);
+        if entry_path.worktree_id != destination_worktree_id {
+            // Entries can't be renamed across worktrees, so copy the entry over and trash the original.
+            let copy_task = self.copy_entry_to(entry_to_move, destination, new_path, cx);
+            let project = self.project.clone();
+            cx.spawn(|_, mut cx| async move {
+                copy_task.await?;
+                let delete_task = project.update(&mut cx, |project, cx| {
+                    project.delete_entry(entry_to_move, true, cx)
+                })?;
+                if let Some(delete_task) = delete_task {
+                    delete_task.await?;
+                }
+                anyhow::Ok(())
+            })
+            .detach_and_log_err(cx);
+        } else if new_path != entry_path.path.as_ref() {
+            let task = self.project.update(cx, |project, cx| {
+                project.rename_entry(entry_to_move, new_path, cx)
+            });
+            cx.foreground_executor().spawn(task).detach_and_log_err(cx);
         }
+
+        self.expand_entry(destination_worktree_id, destination, cx);
+    }
+
+    /// Copies an entry to `new_path` in the worktree containing `destination`,
+    /// which may be a different worktree than the one the entry belongs to.
+    fn copy_entry_to(
+        &self,
+        entry_to_copy: ProjectEntryId,
+        destination: ProjectEntryId,
+        new_path: PathBuf,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Option<Entry>>> {
+        self.project.update(cx, |project, cx| {
+            let Some(destination_worktree) = project.worktree_for_entry(destination, cx) else {
+                return Task::ready(Ok(None));
+            };
+            if project.worktree_id_for_entry(entry_to_copy, cx)
+                == Some(destination_worktree.read(cx).id())
+            {
+                return project.copy_entry(entry_to_copy, None, new_path, cx);
+            }
+
+            // Worktrees copy from paths relative to themselves, so copying across worktrees
+            // goes through the destination worktree.
+            let Some(source_abs_path) = project
+                .path_for_entry(entry_to_copy, cx)
+                .and_then(|source_path| project.absolute_path(&source_path, cx))
+            else {
+                return Task::ready(Err(anyhow!("no path for entry {entry_to_copy:?}")));
+            };
+            let relative_source_path =
+                relativize_path(&destination_worktree.read(cx).abs_path(), &source_abs_path);
+            project.copy_entry(destination, Some(relative_source_path), new_path, cx)
+        })
     }
 
     fn index_for_selection(&self, selection: SelectedEntry) -> Option<(usize, usize, usize)> {
@@ -2165,10 +2349,7 @@ impl ProjectPanel {
                         (target_worktree.clone(), &target_entry),
                         cx,
                     )?;
-                    self.project
-                        .update(cx, |project, cx| {
-                            project.copy_entry(selection.entry_id, None, new_path, cx)
-                        })
+                    self.copy_entry_to(selection.entry_id, target_entry_id, new_path, cx)
                         .detach_and_log_err(cx)
                 }
 
@@ -3074,6 +3255,7 @@ impl Render for ProjectPanel {
                 .on_action(cx.listener(Self::new_search_in_directory))
                 .on_action(cx.listener(Self::unfold_directory))
                 .on_action(cx.listener(Self::fold_directory))
+                .on_action(cx.listener(Self::compare_selected_files))
                 .when(!project.is_read_only(cx), |el| {
                     el.on_action(cx.listener(Self::new_file))
                         .on_action(cx.listener(Self::new_directory))
@@ -3084,6 +3266,7 @@ impl Render for ProjectPanel {
                         .on_action(cx.listener(Self::copy))
                         .on_action(cx.listener(Self::paste))
                         .on_action(cx.listener(Self::duplicate))
+                        .on_action(cx.listener(Self::bulk_rename))
                         .on_click(cx.listener(|this, event: &gpui::ClickEvent, cx| {
                             if event.up.click_count > 1 {
                                 if let Some(entry_id) = this.last_worktree_root_id {
@@ -5307,6 +5490,208 @@ mod tests {
             &["v project_root", "    v dir_1", "        v nested_dir",]
         );
     }
+    #[gpui::test]
+    async fn test_drag_entries_between_worktrees(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/root1",
+            json!({
+                "one.txt": "",
+                "two.txt": "",
+            }),
+        )
+        .await;
+        fs.insert_tree(
+            "/root2",
+            json!({
+                "dir": { "three.txt": "" },
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/root1".as_ref(), "/root2".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace.update(cx, ProjectPanel::new).unwrap();
+
+        let drag_onto = |path: &str, target: &str, cx: &mut VisualTestContext| {
+            select_path(&panel, path, cx);
+            let target_entry_id = find_project_entry(&panel, target, cx).unwrap();
+            panel.update(cx, |panel, cx| {
+                let drag = DraggedSelection {
+                    active_selection: panel.selection.unwrap(),
+                    marked_selections: Arc::new(panel.marked_entries.clone()),
+                };
+                panel.drag_onto(&drag, target_entry_id, false, cx);
+            });
+            cx.run_until_parked();
+        };
+
+        drag_onto("root1/one.txt", "root2/dir", cx);
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..50, cx),
+            &[
+                "v root1",
+                "      two.txt",
+                "v root2",
+                "    v dir",
+                "          one.txt",
+                "          three.txt",
+            ]
+        );
+        assert!(!fs.is_file(Path::new("/root1/one.txt")).await);
+        assert!(fs.is_file(Path::new("/root2/dir/one.txt")).await);
+
+        cx.simulate_modifiers_change(gpui::Modifiers {
+            alt: true,
+            ..Default::default()
+        });
+        drag_onto("root1/two.txt", "root2/dir", cx);
+        cx.simulate_modifiers_change(Default::default());
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..50, cx),
+            &[
+                "v root1",
+                "      two.txt  <== selected",
+                "v root2",
+                "    v dir",
+                "          one.txt",
+                "          three.txt",
+                "          two.txt",
+            ]
+        );
+        assert!(fs.is_file(Path::new("/root2/dir/two.txt")).await);
+    }
+
+    #[gpui::test]
+    async fn test_compare_selected_files(cx: &mut gpui::TestAppContext) {
+        init_test_with_editor(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/root",
+            json!({
+                "a.txt": "one\ntwo\nthree\n",
+                "b.txt": "one\nTWO\nthree\n",
+                "dir": {},
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/root".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace.update(cx, ProjectPanel::new).unwrap();
+
+        let mark_path = |path: &str, cx: &mut VisualTestContext| {
+            select_path(&panel, path, cx);
+            panel.update(cx, |panel, _| {
+                let selection = panel.selection.unwrap();
+                panel.marked_entries.insert(selection);
+            });
+        };
+
+        mark_path("root/a.txt", cx);
+        mark_path("root/dir", cx);
+        panel.update(cx, |panel, cx| {
+            assert!(panel.files_to_compare(cx).is_none())
+        });
+
+        panel.update(cx, |panel, _| panel.marked_entries.clear());
+        mark_path("root/b.txt", cx);
+        mark_path("root/a.txt", cx);
+        panel.update(cx, |panel, cx| {
+            panel.compare_selected_files(&CompareSelectedFiles, cx)
+        });
+        cx.run_until_parked();
+
+        let editor = workspace
+            .update(cx, |workspace, cx| workspace.active_item_as::<Editor>(cx))
+            .unwrap()
+            .unwrap();
+        editor.update(cx, |editor, cx| {
+            assert_eq!(editor.buffer().read(cx).title(cx), "a.txt ↔ b.txt");
+            assert_eq!(editor.text(cx), "one\nTWO\nthree\n");
+            assert!(editor.read_only(cx));
+            let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+            assert_eq!(
+                buffer
+                    .read(cx)
+                    .diff_base()
+                    .map(|diff_base| diff_base.to_string()),
+                Some("one\ntwo\nthree\n".to_string())
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_bulk_rename(cx: &mut gpui::TestAppContext) {
+        init_test_with_editor(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/root",
+            json!({
+                "src": {
+                    "a.rs": "",
+                    "b.rs": "",
+                },
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/root".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace.update(cx, ProjectPanel::new).unwrap();
+
+        toggle_expand_dir(&panel, "root/src", cx);
+        select_path(&panel, "root/src", cx);
+        panel.update(cx, |panel, cx| panel.bulk_rename(&BulkRename, cx));
+        cx.run_until_parked();
+
+        let editor = workspace
+            .update(cx, |workspace, cx| {
+                workspace.active_item(cx).unwrap().act_as::<Editor>(cx)
+            })
+            .unwrap()
+            .unwrap();
+        editor.update(cx, |editor, cx| {
+            assert_eq!(editor.text(cx), "1\tsrc/a.rs\n2\tsrc/b.rs\n");
+            editor.set_text("1\tsrc/c.rs\nsrc/d.rs\n", cx);
+        });
+
+        let save_task = workspace
+            .update(cx, |workspace, cx| {
+                let item = workspace.active_item(cx).unwrap();
+                assert!(item.is_dirty(cx));
+                item.save(false, project.clone(), cx)
+            })
+            .unwrap();
+        save_task.await.unwrap();
+        cx.run_until_parked();
+
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..50, cx),
+            &[
+                "v root",
+                "    v src  <== selected",
+                "          c.rs",
+                "          d.rs",
+            ]
+        );
+        editor.update(cx, |editor, cx| {
+            assert_eq!(editor.text(cx), "1\tsrc/c.rs\n2\tsrc/d.rs\n");
+        });
+        workspace
+            .update(cx, |workspace, cx| {
+                assert!(!workspace.active_item(cx).unwrap().is_dirty(cx));
+            })
+            .unwrap();
+    }
+
     #[gpui::test]
     async fn test_autoreveal_and_gitignored_files(cx: &mut gpui::TestAppContext) {
         init_test_with_editor(cx);
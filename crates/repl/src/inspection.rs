@@ -0,0 +1,345 @@
+//! # Kernel Inspection
+//!
+//! Besides running the code the user asks it to, a kernel can be asked about
+//! its state. Documentation for the name under the cursor comes from an
+//! `inspect_request`, and the variables in the kernel's namespace are listed
+//! by running a short snippet of code that prints them as JSON, since the
+//! Jupyter protocol has no message for listing them.
+
+use std::ops::Range;
+use std::rc::Rc;
+
+use anyhow::{Context as _, Result};
+use editor::{GotoDefinitionKind, SemanticsProvider};
+use gpui::{AnyWindowHandle, AppContext, Model, Task, WeakView};
+use language::{Anchor, Buffer, CharKind, LanguageServerId, Point, ToPoint as _};
+use project::{
+    DocumentHighlight, Hover, HoverBlock, HoverBlockKind, InlayHint, LocationLink,
+    ProjectTransaction,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use util::ResultExt as _;
+
+use crate::Session;
+
+/// A variable defined in a kernel's namespace.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Variable {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// The shape of arrays and DataFrames, or the length of collections.
+    #[serde(default)]
+    pub shape: Option<String>,
+    /// A shortened `repr` of the value.
+    pub preview: String,
+}
+
+/// Prints the user's variables as JSON and cleans up after itself, so that
+/// nothing is left in the namespace being listed.
+const PYTHON_VARIABLES_CODE: &str = r#"
+def _zed_variables():
+    import json, types
+    hidden = {"In", "Out", "exit", "quit", "get_ipython"}
+    skipped = (types.ModuleType, types.FunctionType, types.BuiltinFunctionType, type)
+    variables = []
+    for name, value in list(globals().items()):
+        if name.startswith("_") or name in hidden or isinstance(value, skipped):
+            continue
+        shape = getattr(value, "shape", None)
+        if isinstance(shape, tuple):
+            shape = " × ".join(str(size) for size in shape)
+        elif shape is not None:
+            shape = str(shape)
+        elif hasattr(value, "__len__") and not isinstance(value, (str, bytes)):
+            try:
+                shape = str(len(value))
+            except Exception:
+                shape = None
+        try:
+            preview = " ".join(repr(value).split())
+        except Exception:
+            preview = "<unavailable>"
+        if len(preview) > 100:
+            preview = preview[:99] + "…"
+        variables.append({
+            "name": name,
+            "type": type(value).__name__,
+            "shape": shape,
+            "preview": preview,
+        })
+    print(json.dumps(variables))
+
+_zed_variables()
+del _zed_variables
+"#;
+
+/// Returns the code that lists the variables of a kernel for `language`, if
+/// listing them is supported for that language.
+pub(crate) fn variables_code(language: &str) -> Option<&'static str> {
+    match language.to_lowercase().as_str() {
+        "python" => Some(PYTHON_VARIABLES_CODE),
+        _ => None,
+    }
+}
+
+/// Parses the output of the code returned by [`variables_code`].
+///
+/// Only the last line is parsed, as the code may run alongside other output,
+/// such as that of background threads.
+pub(crate) fn parse_variables(stdout: &str) -> Result<Vec<Variable>> {
+    let line = stdout
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .context("the kernel didn't list any variables")?;
+    serde_json::from_str(line).context("failed to parse the kernel's variables")
+}
+
+/// Returns the plain text documentation in the content of an `inspect_reply`.
+pub(crate) fn inspect_reply_text(content: &Value) -> Option<String> {
+    if content["found"].as_bool() != Some(true) {
+        return None;
+    }
+
+    let text = strip_ansi_escapes(content["data"]["text/plain"].as_str()?);
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Removes the escape sequences kernels use to color their output, such as
+/// those in IPython's documentation.
+fn strip_ansi_escapes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '\x1b' {
+            result.push(char);
+            continue;
+        }
+
+        if chars.next_if_eq(&'[').is_some() {
+            // Skip the parameters up to and including the final byte.
+            for char in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&char) {
+                    break;
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Adds the documentation a session's kernel has for the name under the cursor
+/// to the hovers of the editor the session is attached to.
+///
+/// Everything else is left to the editor's original provider, which is
+/// restored when the session shuts down.
+pub(crate) struct KernelSemanticsProvider {
+    inner: Option<Rc<dyn SemanticsProvider>>,
+    session: WeakView<Session>,
+    window: AnyWindowHandle,
+}
+
+impl KernelSemanticsProvider {
+    pub(crate) fn new(
+        inner: Option<Rc<dyn SemanticsProvider>>,
+        session: WeakView<Session>,
+        window: AnyWindowHandle,
+    ) -> Self {
+        Self {
+            inner,
+            session,
+            window,
+        }
+    }
+}
+
+impl SemanticsProvider for KernelSemanticsProvider {
+    fn hover(
+        &self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        cx: &mut AppContext,
+    ) -> Option<Task<Vec<Hover>>> {
+        let inner = self
+            .inner
+            .as_ref()
+            .and_then(|inner| inner.hover(buffer, position, cx));
+
+        let snapshot = buffer.read(cx).snapshot();
+        let (word_range, kind) = snapshot.surrounding_word(position);
+        if kind != Some(CharKind::Word) {
+            return inner;
+        }
+
+        // Kernels are sent the line being hovered, with the cursor position
+        // counted in characters as the Jupyter protocol expects.
+        let point = position.to_point(&snapshot);
+        let line_start = Point::new(point.row, 0);
+        let line_end = Point::new(point.row, snapshot.line_len(point.row));
+        let code = snapshot
+            .text_for_range(line_start..line_end)
+            .collect::<String>();
+        let cursor_position = snapshot
+            .text_for_range(line_start..point)
+            .flat_map(|chunk| chunk.chars())
+            .count();
+        let range = snapshot.anchor_before(word_range.start)..snapshot.anchor_after(word_range.end);
+
+        let session = self.session.clone();
+        let window = self.window;
+        Some(cx.spawn(|mut cx| async move {
+            let documentation = cx.update_window(window, |_, cx| {
+                session.update(cx, |session, cx| session.inspect(code, cursor_position, cx))
+            });
+
+            let mut hovers = match inner {
+                Some(inner) => inner.await,
+                None => Vec::new(),
+            };
+
+            if let Ok(Ok(documentation)) = documentation {
+                if let Some(text) = documentation.await.log_err().flatten() {
+                    hovers.push(Hover {
+                        contents: vec![HoverBlock {
+                            text,
+                            kind: HoverBlockKind::PlainText,
+                        }],
+                        range: Some(range),
+                        language: None,
+                    });
+                }
+            }
+
+            hovers
+        }))
+    }
+
+    fn inlay_hints(
+        &self,
+        buffer: Model<Buffer>,
+        range: Range<Anchor>,
+        cx: &mut AppContext,
+    ) -> Option<Task<Result<Vec<InlayHint>>>> {
+        self.inner.as_ref()?.inlay_hints(buffer, range, cx)
+    }
+
+    fn resolve_inlay_hint(
+        &self,
+        hint: InlayHint,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: &mut AppContext,
+    ) -> Option<Task<Result<InlayHint>>> {
+        self.inner
+            .as_ref()?
+            .resolve_inlay_hint(hint, buffer, server_id, cx)
+    }
+
+    fn supports_inlay_hints(&self, buffer: &Model<Buffer>, cx: &AppContext) -> bool {
+        self.inner
+            .as_ref()
+            .is_some_and(|inner| inner.supports_inlay_hints(buffer, cx))
+    }
+
+    fn document_highlights(
+        &self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        cx: &mut AppContext,
+    ) -> Option<Task<Result<Vec<DocumentHighlight>>>> {
+        self.inner
+            .as_ref()?
+            .document_highlights(buffer, position, cx)
+    }
+
+    fn definitions(
+        &self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        kind: GotoDefinitionKind,
+        cx: &mut AppContext,
+    ) -> Option<Task<Result<Vec<LocationLink>>>> {
+        self.inner.as_ref()?.definitions(buffer, position, kind, cx)
+    }
+
+    fn range_for_rename(
+        &self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        cx: &mut AppContext,
+    ) -> Option<Task<Result<Option<Range<Anchor>>>>> {
+        self.inner.as_ref()?.range_for_rename(buffer, position, cx)
+    }
+
+    fn perform_rename(
+        &self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        new_name: String,
+        cx: &mut AppContext,
+    ) -> Option<Task<Result<ProjectTransaction>>> {
+        self.inner
+            .as_ref()?
+            .perform_rename(buffer, position, new_name, cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_variables() {
+        let stdout = concat!(
+            "progress from a thread\n",
+            r#"[{"name": "df", "type": "DataFrame", "shape": "3 × 2", "preview": "a b"}, "#,
+            r#"{"name": "x", "type": "int", "shape": null, "preview": "1"}]"#,
+            "\n",
+        );
+
+        assert_eq!(
+            parse_variables(stdout).unwrap(),
+            vec![
+                Variable {
+                    name: "df".into(),
+                    type_name: "DataFrame".into(),
+                    shape: Some("3 × 2".into()),
+                    preview: "a b".into(),
+                },
+                Variable {
+                    name: "x".into(),
+                    type_name: "int".into(),
+                    shape: None,
+                    preview: "1".into(),
+                },
+            ]
+        );
+
+        assert!(parse_variables("").is_err());
+        assert!(parse_variables("Traceback (most recent call last):").is_err());
+    }
+
+    #[test]
+    fn test_inspect_reply_text() {
+        let reply = json!({
+            "status": "ok",
+            "found": true,
+            "data": {
+                "text/plain": "\u{1b}[0;31mSignature:\u{1b}[0m len(obj, /)\n\u{1b}[0;31mDocstring:\u{1b}[0m Return the number of items in a container.\n",
+            },
+            "metadata": {},
+        });
+        assert_eq!(
+            inspect_reply_text(&reply).as_deref(),
+            Some("Signature: len(obj, /)\nDocstring: Return the number of items in a container.")
+        );
+
+        let not_found = json!({"status": "ok", "found": false, "data": {}, "metadata": {}});
+        assert_eq!(inspect_reply_text(&not_found), None);
+    }
+}
@@ -1,14 +1,28 @@
 use std::collections::HashMap;
 
 use editor::EditorSettings;
-use gpui::AppContext;
+use gpui::{AppContext, Pixels};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
+use workspace::dock::DockPosition;
 
 #[derive(Debug, Default)]
 pub struct JupyterSettings {
     pub kernel_selections: HashMap<String, String>,
+    pub servers: Vec<JupyterServer>,
+}
+
+/// A Jupyter server, such as a JupyterHub user server or one running in a
+/// container, whose kernels can be used alongside local ones.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq, Hash)]
+pub struct JupyterServer {
+    /// The name the server's kernels are listed under, as in `name/python3`.
+    pub name: String,
+    /// The URL of the server, as in `http://localhost:8888`.
+    pub url: String,
+    /// The token used to authenticate with the server.
+    pub token: Option<String>,
 }
 
 impl JupyterSettings {
@@ -26,12 +40,17 @@ pub struct JupyterSettingsContent {
     ///
     /// Default: `{}`
     pub kernel_selections: Option<HashMap<String, String>>,
+    /// Jupyter servers to list kernels from, in addition to the ones installed locally.
+    ///
+    /// Default: `[]`
+    pub servers: Option<Vec<JupyterServer>>,
 }
 
 impl Default for JupyterSettingsContent {
     fn default() -> Self {
         JupyterSettingsContent {
             kernel_selections: Some(HashMap::new()),
+            servers: Some(Vec::new()),
         }
     }
 }
@@ -56,8 +75,48 @@ impl Settings for JupyterSettings {
                     settings.kernel_selections.insert(k.clone(), v.clone());
                 }
             }
+
+            if let Some(servers) = &value.servers {
+                settings.servers = servers.clone();
+            }
         }
 
         Ok(settings)
     }
 }
+
+#[derive(Deserialize, Debug)]
+pub struct VariablesPanelSettings {
+    pub button: bool,
+    pub dock: DockPosition,
+    pub default_width: Pixels,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct VariablesPanelSettingsContent {
+    /// Whether to show the variables panel button in the status bar.
+    ///
+    /// Default: true
+    pub button: Option<bool>,
+    /// Where to dock the variables panel.
+    ///
+    /// Default: right
+    pub dock: Option<DockPosition>,
+    /// Default width of the variables panel in pixels.
+    ///
+    /// Default: 320
+    pub default_width: Option<f32>,
+}
+
+impl Settings for VariablesPanelSettings {
+    const KEY: Option<&'static str> = Some("variables_panel");
+
+    type FileContent = VariablesPanelSettingsContent;
+
+    fn load(
+        sources: SettingsSources<Self::FileContent>,
+        _cx: &mut gpui::AppContext,
+    ) -> anyhow::Result<Self> {
+        sources.json_merge()
+    }
+}
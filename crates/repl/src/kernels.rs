@@ -1,3 +1,5 @@
+mod jupyter_server;
+
 use anyhow::{Context as _, Result};
 use futures::{
     channel::mpsc::{self, Receiver},
@@ -5,7 +7,7 @@ use futures::{
     stream::{self, SelectAll, StreamExt},
     SinkExt as _,
 };
-use gpui::{AppContext, EntityId, Task};
+use gpui::{AppContext, BackgroundExecutor, EntityId, Task};
 use project::Fs;
 use runtimelib::{
     dirs, ConnectionInfo, ExecutionState, JupyterKernelspec, JupyterMessage, JupyterMessageContent,
@@ -16,19 +18,50 @@ use std::{
     env,
     fmt::Debug,
     net::{IpAddr, Ipv4Addr, SocketAddr},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 use uuid::Uuid;
 
+use crate::JupyterServer;
+pub use jupyter_server::jupyter_server_kernel_specifications;
+
 #[derive(Debug, Clone)]
 pub struct KernelSpecification {
     pub name: String,
+    /// The kernelspec's directory for local kernels, or the connection file for running ones.
     pub path: PathBuf,
     pub kernelspec: JupyterKernelspec,
+    pub source: KernelSource,
+}
+
+/// Where a kernel runs, and so how a session starts or connects to it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KernelSource {
+    /// A kernelspec installed on this machine, launched as a child process.
+    Local,
+    /// A kernel that's already running, connected to with its connection file.
+    Running,
+    /// A kernelspec on a Jupyter server, launched through the server's REST API
+    /// and connected to over a WebSocket.
+    JupyterServer {
+        server: JupyterServer,
+        kernelspec_name: String,
+    },
 }
 
 impl KernelSpecification {
+    /// Describes where the kernel comes from, for showing alongside its name.
+    pub fn location(&self) -> String {
+        match &self.source {
+            KernelSource::Local | KernelSource::Running => self.path.to_string_lossy().to_string(),
+            KernelSource::JupyterServer {
+                server,
+                kernelspec_name,
+            } => format!("{kernelspec_name} on {}", server.url),
+        }
+    }
+
     #[must_use]
     fn command(&self, connection_path: &PathBuf) -> Result<Command> {
         let argv = &self.kernelspec.argv;
@@ -168,12 +201,12 @@ impl Kernel {
 }
 
 pub struct RunningKernel {
-    pub process: smol::process::Child,
-    _shell_task: Task<Result<()>>,
-    _iopub_task: Task<Result<()>>,
-    _control_task: Task<Result<()>>,
-    _routing_task: Task<Result<()>>,
-    connection_path: PathBuf,
+    /// The kernel's process, when it was launched by Zed on this machine.
+    pub process: Option<smol::process::Child>,
+    _tasks: Vec<Task<Result<()>>>,
+    /// The connection file written for a launched kernel, which is removed along with it.
+    connection_path: Option<PathBuf>,
+    _server_kernel: Option<jupyter_server::ServerKernel>,
     pub working_directory: PathBuf,
     pub request_tx: mpsc::Sender<JupyterMessage>,
     pub execution_state: ExecutionState,
@@ -197,6 +230,75 @@ impl RunningKernel {
         working_directory: PathBuf,
         fs: Arc<dyn Fs>,
         cx: &mut AppContext,
+    ) -> Task<Result<(Self, JupyterMessageChannel)>> {
+        match kernel_specification.source.clone() {
+            KernelSource::Local => {
+                Self::launch(kernel_specification, entity_id, working_directory, fs, cx)
+            }
+            KernelSource::Running => {
+                let connection_path = kernel_specification.path;
+                cx.spawn(|cx| async move {
+                    let connection_info = fs.load(&connection_path).await?;
+                    let connection_info = serde_json::from_str::<ConnectionInfo>(&connection_info)
+                        .with_context(|| {
+                            format!("Invalid kernel connection file {connection_path:?}")
+                        })?;
+                    let (tasks, request_tx, messages_rx) =
+                        connect(&connection_info, cx.background_executor()).await?;
+
+                    anyhow::Ok((
+                        Self {
+                            process: None,
+                            _tasks: tasks,
+                            connection_path: None,
+                            _server_kernel: None,
+                            working_directory,
+                            request_tx,
+                            execution_state: ExecutionState::Idle,
+                            kernel_info: None,
+                        },
+                        messages_rx,
+                    ))
+                })
+            }
+            KernelSource::JupyterServer {
+                server,
+                kernelspec_name,
+            } => {
+                let http_client = cx.http_client();
+                cx.spawn(|cx| async move {
+                    let (server_kernel, tasks, request_tx, messages_rx) = jupyter_server::launch(
+                        server,
+                        kernelspec_name,
+                        http_client,
+                        cx.background_executor(),
+                    )
+                    .await?;
+
+                    anyhow::Ok((
+                        Self {
+                            process: None,
+                            _tasks: tasks,
+                            connection_path: None,
+                            _server_kernel: Some(server_kernel),
+                            working_directory,
+                            request_tx,
+                            execution_state: ExecutionState::Idle,
+                            kernel_info: None,
+                        },
+                        messages_rx,
+                    ))
+                })
+            }
+        }
+    }
+
+    fn launch(
+        kernel_specification: KernelSpecification,
+        entity_id: EntityId,
+        working_directory: PathBuf,
+        fs: Arc<dyn Fs>,
+        cx: &mut AppContext,
     ) -> Task<Result<(Self, JupyterMessageChannel)>> {
         cx.spawn(|cx| async move {
             let ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
@@ -234,94 +336,17 @@ impl RunningKernel {
                 .spawn()
                 .context("failed to start the kernel process")?;
 
-            let session_id = Uuid::new_v4().to_string();
-
-            let mut iopub_socket = connection_info
-                .create_client_iopub_connection("", &session_id)
-                .await?;
-            let mut shell_socket = connection_info
-                .create_client_shell_connection(&session_id)
-                .await?;
-            let mut control_socket = connection_info
-                .create_client_control_connection(&session_id)
-                .await?;
-
-            let (mut iopub, iosub) = futures::channel::mpsc::channel(100);
-
-            let (request_tx, mut request_rx) =
-                futures::channel::mpsc::channel::<JupyterMessage>(100);
-
-            let (mut control_reply_tx, control_reply_rx) = futures::channel::mpsc::channel(100);
-            let (mut shell_reply_tx, shell_reply_rx) = futures::channel::mpsc::channel(100);
-
-            let mut messages_rx = SelectAll::new();
-            messages_rx.push(iosub);
-            messages_rx.push(control_reply_rx);
-            messages_rx.push(shell_reply_rx);
-
-            let iopub_task = cx.background_executor().spawn({
-                async move {
-                    while let Ok(message) = iopub_socket.read().await {
-                        iopub.send(message).await?;
-                    }
-                    anyhow::Ok(())
-                }
-            });
-
-            let (mut control_request_tx, mut control_request_rx) =
-                futures::channel::mpsc::channel(100);
-            let (mut shell_request_tx, mut shell_request_rx) = futures::channel::mpsc::channel(100);
-
-            let routing_task = cx.background_executor().spawn({
-                async move {
-                    while let Some(message) = request_rx.next().await {
-                        match message.content {
-                            JupyterMessageContent::DebugRequest(_)
-                            | JupyterMessageContent::InterruptRequest(_)
-                            | JupyterMessageContent::ShutdownRequest(_) => {
-                                control_request_tx.send(message).await?;
-                            }
-                            _ => {
-                                shell_request_tx.send(message).await?;
-                            }
-                        }
-                    }
-                    anyhow::Ok(())
-                }
-            });
-
-            let shell_task = cx.background_executor().spawn({
-                async move {
-                    while let Some(message) = shell_request_rx.next().await {
-                        shell_socket.send(message).await.ok();
-                        let reply = shell_socket.read().await?;
-                        shell_reply_tx.send(reply).await?;
-                    }
-                    anyhow::Ok(())
-                }
-            });
-
-            let control_task = cx.background_executor().spawn({
-                async move {
-                    while let Some(message) = control_request_rx.next().await {
-                        control_socket.send(message).await.ok();
-                        let reply = control_socket.read().await?;
-                        control_reply_tx.send(reply).await?;
-                    }
-                    anyhow::Ok(())
-                }
-            });
+            let (tasks, request_tx, messages_rx) =
+                connect(&connection_info, cx.background_executor()).await?;
 
             anyhow::Ok((
                 Self {
-                    process,
-                    request_tx,
+                    process: Some(process),
+                    _tasks: tasks,
+                    connection_path: Some(connection_path),
+                    _server_kernel: None,
                     working_directory,
-                    _shell_task: shell_task,
-                    _iopub_task: iopub_task,
-                    _control_task: control_task,
-                    _routing_task: routing_task,
-                    connection_path,
+                    request_tx,
                     execution_state: ExecutionState::Idle,
                     kernel_info: None,
                 },
@@ -329,13 +354,120 @@ impl RunningKernel {
             ))
         })
     }
+
+    /// Stops the kernel's process, if Zed launched it.
+    pub fn kill(&mut self) {
+        if let Some(process) = &mut self.process {
+            process.kill().ok();
+        }
+    }
+}
+
+/// Connects to a kernel's ZeroMQ sockets, returning the tasks that route
+/// requests to and messages from it.
+async fn connect(
+    connection_info: &ConnectionInfo,
+    executor: &BackgroundExecutor,
+) -> Result<(
+    Vec<Task<Result<()>>>,
+    mpsc::Sender<JupyterMessage>,
+    JupyterMessageChannel,
+)> {
+    let session_id = Uuid::new_v4().to_string();
+
+    let mut iopub_socket = connection_info
+        .create_client_iopub_connection("", &session_id)
+        .await?;
+    let mut shell_socket = connection_info
+        .create_client_shell_connection(&session_id)
+        .await?;
+    let mut control_socket = connection_info
+        .create_client_control_connection(&session_id)
+        .await?;
+
+    let (mut iopub, iosub) = futures::channel::mpsc::channel(100);
+
+    let (request_tx, mut request_rx) = futures::channel::mpsc::channel::<JupyterMessage>(100);
+
+    let (mut control_reply_tx, control_reply_rx) = futures::channel::mpsc::channel(100);
+    let (mut shell_reply_tx, shell_reply_rx) = futures::channel::mpsc::channel(100);
+
+    let mut messages_rx = SelectAll::new();
+    messages_rx.push(iosub);
+    messages_rx.push(control_reply_rx);
+    messages_rx.push(shell_reply_rx);
+
+    let iopub_task = executor.spawn({
+        async move {
+            while let Ok(message) = iopub_socket.read().await {
+                iopub.send(message).await?;
+            }
+            anyhow::Ok(())
+        }
+    });
+
+    let (mut control_request_tx, mut control_request_rx) = futures::channel::mpsc::channel(100);
+    let (mut shell_request_tx, mut shell_request_rx) = futures::channel::mpsc::channel(100);
+
+    let routing_task = executor.spawn({
+        async move {
+            while let Some(message) = request_rx.next().await {
+                if is_control_request(&message) {
+                    control_request_tx.send(message).await?;
+                } else {
+                    shell_request_tx.send(message).await?;
+                }
+            }
+            anyhow::Ok(())
+        }
+    });
+
+    let shell_task = executor.spawn({
+        async move {
+            while let Some(message) = shell_request_rx.next().await {
+                shell_socket.send(message).await.ok();
+                let reply = shell_socket.read().await?;
+                shell_reply_tx.send(reply).await?;
+            }
+            anyhow::Ok(())
+        }
+    });
+
+    let control_task = executor.spawn({
+        async move {
+            while let Some(message) = control_request_rx.next().await {
+                control_socket.send(message).await.ok();
+                let reply = control_socket.read().await?;
+                control_reply_tx.send(reply).await?;
+            }
+            anyhow::Ok(())
+        }
+    });
+
+    Ok((
+        vec![iopub_task, routing_task, shell_task, control_task],
+        request_tx,
+        messages_rx,
+    ))
+}
+
+/// Whether a request is sent on the kernel's control channel, rather than its shell channel.
+fn is_control_request(message: &JupyterMessage) -> bool {
+    matches!(
+        message.content,
+        JupyterMessageContent::DebugRequest(_)
+            | JupyterMessageContent::InterruptRequest(_)
+            | JupyterMessageContent::ShutdownRequest(_)
+    )
 }
 
 impl Drop for RunningKernel {
     fn drop(&mut self) {
-        std::fs::remove_file(&self.connection_path).ok();
+        if let Some(connection_path) = &self.connection_path {
+            std::fs::remove_file(connection_path).ok();
+        }
         self.request_tx.close_channel();
-        self.process.kill().ok();
+        self.kill();
     }
 }
 
@@ -364,6 +496,7 @@ async fn read_kernelspec_at(
         name: kernel_name,
         path,
         kernelspec: spec,
+        source: KernelSource::Local,
     })
 }
 
@@ -438,6 +571,87 @@ pub async fn kernel_specifications(fs: Arc<dyn Fs>) -> Result<Vec<KernelSpecific
     Ok(kernel_dirs)
 }
 
+/// Finds kernels that are already running, such as ones started by `jupyter console`
+/// or in a container, from the connection files they write to the Jupyter runtime directory.
+///
+/// The language of a running kernel is taken from the local kernelspec it was started
+/// from, when there is one.
+pub async fn running_kernel_specifications(
+    fs: Arc<dyn Fs>,
+    local_specifications: &[KernelSpecification],
+) -> Result<Vec<KernelSpecification>> {
+    read_running_kernels_dir(&dirs::runtime_dir(), fs.as_ref(), local_specifications).await
+}
+
+async fn read_running_kernels_dir(
+    runtime_dir: &Path,
+    fs: &dyn Fs,
+    local_specifications: &[KernelSpecification],
+) -> Result<Vec<KernelSpecification>> {
+    let mut paths = fs.read_dir(runtime_dir).await?;
+
+    let mut specifications = Vec::new();
+    while let Some(path) = paths.next().await {
+        let Ok(path) = path else {
+            continue;
+        };
+        let Some(name) = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        // Skip the connection files of the kernels Zed launches itself.
+        if !name.starts_with("kernel-")
+            || name.starts_with("kernel-zed-")
+            || path
+                .extension()
+                .map_or(true, |extension| extension != "json")
+        {
+            continue;
+        }
+
+        let connection_info = fs.load(&path).await.and_then(|content| {
+            serde_json::from_str::<ConnectionInfo>(&content).context("invalid connection file")
+        });
+        let connection_info = match connection_info {
+            Ok(connection_info) => connection_info,
+            Err(err) => {
+                log::warn!("Error reading kernel connection file {path:?}: {err:?}");
+                continue;
+            }
+        };
+
+        let local_kernelspec = connection_info
+            .kernel_name
+            .as_ref()
+            .and_then(|kernel_name| {
+                local_specifications
+                    .iter()
+                    .find(|specification| &specification.name == kernel_name)
+                    .map(|specification| specification.kernelspec.clone())
+            });
+        let kernelspec = match local_kernelspec {
+            Some(kernelspec) => kernelspec,
+            None => serde_json::from_value(serde_json::json!({
+                "argv": [],
+                "display_name": name,
+                "language": "unknown",
+            }))?,
+        };
+
+        specifications.push(KernelSpecification {
+            name,
+            path,
+            kernelspec,
+            source: KernelSource::Running,
+        });
+    }
+
+    Ok(specifications)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -494,4 +708,82 @@ mod test {
             vec!["deno", "python"]
         );
     }
+
+    #[gpui::test]
+    async fn test_get_running_kernels(cx: &mut TestAppContext) {
+        let connection_file = |kernel_name: &str| {
+            json!({
+                "transport": "tcp",
+                "ip": "127.0.0.1",
+                "stdin_port": 51001,
+                "control_port": 51002,
+                "hb_port": 51003,
+                "shell_port": 51004,
+                "iopub_port": 51005,
+                "signature_scheme": "hmac-sha256",
+                "key": "a0436f6c-1916-498b-8eb9-e81ab9368e84",
+                "kernel_name": kernel_name,
+            })
+            .to_string()
+        };
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/jupyter",
+            json!({
+                "kernels": {
+                    "python3": {
+                        "kernel.json": r#"{
+                            "display_name": "Python 3",
+                            "language": "python",
+                            "argv": ["python3", "-m", "ipykernel_launcher", "-f", "{connection_file}"]
+                        }"#
+                    },
+                },
+                "runtime": {
+                    "kernel-1234.json": connection_file("python3"),
+                    "kernel-5678.json": connection_file("ir"),
+                    "kernel-zed-12v1.json": connection_file("zed-python3"),
+                    "jpserver-4321.json": "{}",
+                    "kernel-broken.json": "{",
+                },
+            }),
+        )
+        .await;
+
+        let local_kernels = read_kernels_dir(PathBuf::from("/jupyter/kernels"), fs.as_ref())
+            .await
+            .unwrap();
+        let mut kernels =
+            read_running_kernels_dir(Path::new("/jupyter/runtime"), fs.as_ref(), &local_kernels)
+                .await
+                .unwrap();
+        kernels.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(
+            kernels
+                .iter()
+                .map(|kernel| (
+                    kernel.name.as_str(),
+                    kernel.kernelspec.language.as_str(),
+                    kernel.path.clone(),
+                    kernel.source.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "kernel-1234",
+                    "python",
+                    PathBuf::from("/jupyter/runtime/kernel-1234.json"),
+                    KernelSource::Running
+                ),
+                (
+                    "kernel-5678",
+                    "unknown",
+                    PathBuf::from("/jupyter/runtime/kernel-5678.json"),
+                    KernelSource::Running
+                ),
+            ]
+        );
+    }
 }
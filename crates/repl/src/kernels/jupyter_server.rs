@@ -0,0 +1,426 @@
+//! Kernels on a [Jupyter Server](https://jupyter-server.readthedocs.io/en/latest/developers/rest-api.html),
+//! such as a JupyterHub user server or one running in a container.
+//!
+//! Kernels are listed and started through the server's REST API, and messages are
+//! exchanged with them as JSON over the server's WebSocket channels endpoint.
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, Context as _, Result};
+use async_tungstenite::tungstenite::{client::IntoClientRequest as _, http::HeaderValue, Message};
+use futures::{channel::mpsc, stream::SelectAll, AsyncReadExt as _, SinkExt as _, StreamExt as _};
+use gpui::{BackgroundExecutor, Task};
+use http_client::{AsyncBody, HttpClient, Method, Request, Url};
+use runtimelib::{Header, JupyterKernelspec, JupyterMessage, JupyterMessageContent};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use util::ResultExt as _;
+use uuid::Uuid;
+
+use super::{is_control_request, JupyterMessageChannel, KernelSource, KernelSpecification};
+use crate::JupyterServer;
+
+#[derive(Deserialize)]
+struct KernelspecsResponse {
+    kernelspecs: HashMap<String, ServerKernelspec>,
+}
+
+#[derive(Deserialize)]
+struct ServerKernelspec {
+    name: String,
+    spec: JupyterKernelspec,
+}
+
+#[derive(Deserialize)]
+struct ServerKernelModel {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct IncomingMessage {
+    header: Header,
+    #[serde(default)]
+    parent_header: Value,
+    #[serde(default)]
+    metadata: Value,
+    content: Value,
+}
+
+/// Lists the kernelspecs available on a Jupyter server.
+pub async fn jupyter_server_kernel_specifications(
+    server: &JupyterServer,
+    http_client: Arc<dyn HttpClient>,
+) -> Result<Vec<KernelSpecification>> {
+    let response = send(
+        server,
+        Method::GET,
+        "api/kernelspecs",
+        AsyncBody::empty(),
+        http_client.as_ref(),
+    )
+    .await?;
+    let response = serde_json::from_str::<KernelspecsResponse>(&response)
+        .with_context(|| format!("Invalid kernelspecs from Jupyter server {}", server.url))?;
+
+    let mut specifications = response
+        .kernelspecs
+        .into_values()
+        .map(|kernelspec| KernelSpecification {
+            name: format!("{}/{}", server.name, kernelspec.name),
+            path: PathBuf::new(),
+            kernelspec: kernelspec.spec,
+            source: KernelSource::JupyterServer {
+                server: server.clone(),
+                kernelspec_name: kernelspec.name,
+            },
+        })
+        .collect::<Vec<_>>();
+    specifications.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(specifications)
+}
+
+/// A kernel started on a Jupyter server, which is shut down when this is dropped.
+pub(super) struct ServerKernel {
+    server: JupyterServer,
+    id: String,
+    http_client: Arc<dyn HttpClient>,
+    executor: BackgroundExecutor,
+}
+
+impl Drop for ServerKernel {
+    fn drop(&mut self) {
+        let server = self.server.clone();
+        let path = format!("api/kernels/{}", self.id);
+        let http_client = self.http_client.clone();
+        self.executor
+            .spawn(async move {
+                send(
+                    &server,
+                    Method::DELETE,
+                    &path,
+                    AsyncBody::empty(),
+                    http_client.as_ref(),
+                )
+                .await
+                .log_err();
+            })
+            .detach();
+    }
+}
+
+/// Starts a kernel on a Jupyter server and connects to its channels.
+pub(super) async fn launch(
+    server: JupyterServer,
+    kernelspec_name: String,
+    http_client: Arc<dyn HttpClient>,
+    executor: &BackgroundExecutor,
+) -> Result<(
+    ServerKernel,
+    Vec<Task<Result<()>>>,
+    mpsc::Sender<JupyterMessage>,
+    JupyterMessageChannel,
+)> {
+    let body = json!({ "name": kernelspec_name }).to_string();
+    let response = send(
+        &server,
+        Method::POST,
+        "api/kernels",
+        body.into(),
+        http_client.as_ref(),
+    )
+    .await?;
+    let kernel = serde_json::from_str::<ServerKernelModel>(&response)
+        .with_context(|| format!("Invalid kernel from Jupyter server {}", server.url))?;
+    let server_kernel = ServerKernel {
+        server: server.clone(),
+        id: kernel.id.clone(),
+        http_client,
+        executor: executor.clone(),
+    };
+
+    let url = channels_url(&server, &kernel.id, &Uuid::new_v4().to_string())?;
+    let mut request = url.as_str().into_client_request()?;
+    if let Some(token) = &server.token {
+        request.headers_mut().insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("token {token}"))?,
+        );
+    }
+    let host = url.host_str().context("Jupyter server URL has no host")?;
+    let port = url
+        .port_or_known_default()
+        .context("Jupyter server URL has no port")?;
+    let stream = smol::net::TcpStream::connect((host, port)).await?;
+    let (socket, _) = async_tungstenite::async_tls::client_async_tls(request, stream)
+        .await
+        .with_context(|| format!("Failed to connect to kernel on {}", server.url))?;
+    let (mut socket_tx, mut socket_rx) = socket.split();
+
+    let (request_tx, mut request_rx) = mpsc::channel::<JupyterMessage>(100);
+    let (mut messages_tx, messages) = mpsc::channel(100);
+    let mut messages_rx = SelectAll::new();
+    messages_rx.push(messages);
+
+    let send_task = executor.spawn(async move {
+        while let Some(message) = request_rx.next().await {
+            socket_tx
+                .send(Message::Text(encode_message(&message)?))
+                .await?;
+        }
+        anyhow::Ok(())
+    });
+
+    let receive_task = executor.spawn(async move {
+        while let Some(message) = socket_rx.next().await {
+            match message? {
+                Message::Text(text) => match decode_message(&text) {
+                    Ok(message) => messages_tx.send(message).await?,
+                    Err(err) => log::warn!("Invalid message from Jupyter server kernel: {err:?}"),
+                },
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        anyhow::Ok(())
+    });
+
+    Ok((
+        server_kernel,
+        vec![send_task, receive_task],
+        request_tx,
+        messages_rx,
+    ))
+}
+
+async fn send(
+    server: &JupyterServer,
+    method: Method,
+    path: &str,
+    body: AsyncBody,
+    http_client: &dyn HttpClient,
+) -> Result<String> {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(api_url(server, path))
+        .header("Content-Type", "application/json");
+    if let Some(token) = &server.token {
+        request = request.header("Authorization", format!("token {token}"));
+    }
+
+    let mut response = http_client.send(request.body(body)?).await?;
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await?;
+    anyhow::ensure!(
+        response.status().is_success(),
+        "Jupyter server {} responded with {}: {body}",
+        server.url,
+        response.status(),
+    );
+
+    Ok(body)
+}
+
+fn api_url(server: &JupyterServer, path: &str) -> String {
+    format!("{}/{path}", server.url.trim_end_matches('/'))
+}
+
+fn channels_url(server: &JupyterServer, kernel_id: &str, session_id: &str) -> Result<Url> {
+    let mut url = Url::parse(&api_url(
+        server,
+        &format!("api/kernels/{kernel_id}/channels"),
+    ))?;
+    let scheme = match url.scheme() {
+        "http" => "ws",
+        "https" => "wss",
+        scheme => anyhow::bail!("Unsupported Jupyter server URL scheme: {scheme}"),
+    };
+    url.set_scheme(scheme)
+        .map_err(|_| anyhow!("Invalid Jupyter server URL: {}", server.url))?;
+    url.query_pairs_mut().append_pair("session_id", session_id);
+
+    Ok(url)
+}
+
+/// Encodes a message in the JSON format of the channels WebSocket, which
+/// carries the channel the message is sent on alongside it.
+fn encode_message(message: &JupyterMessage) -> Result<String> {
+    let channel = if is_control_request(message) {
+        "control"
+    } else {
+        "shell"
+    };
+    let parent_header = match &message.parent_header {
+        Some(parent_header) => serde_json::to_value(parent_header)?,
+        None => json!({}),
+    };
+
+    Ok(json!({
+        "header": serde_json::to_value(&message.header)?,
+        "parent_header": parent_header,
+        "metadata": message.metadata,
+        "content": serde_json::to_value(&message.content)?,
+        "buffers": [],
+        "channel": channel,
+    })
+    .to_string())
+}
+
+fn decode_message(text: &str) -> Result<JupyterMessage> {
+    let incoming = serde_json::from_str::<IncomingMessage>(text)?;
+    let content =
+        JupyterMessageContent::from_type_and_content(&incoming.header.msg_type, incoming.content)?;
+
+    let mut message = JupyterMessage::new(content, None);
+    message.parent_header = match incoming.parent_header {
+        Value::Object(parent_header) if parent_header.is_empty() => None,
+        Value::Null => None,
+        parent_header => Some(serde_json::from_value(parent_header)?),
+    };
+    message.header = incoming.header;
+    message.metadata = incoming.metadata;
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_client::{FakeHttpClient, Response};
+    use runtimelib::ExecuteRequest;
+
+    fn server() -> JupyterServer {
+        JupyterServer {
+            name: "hub".into(),
+            url: "https://hub.example.com/user/someone/".into(),
+            token: Some("secret".into()),
+        }
+    }
+
+    #[gpui::test]
+    async fn test_jupyter_server_kernel_specifications() {
+        let http_client = FakeHttpClient::create(|request| async move {
+            assert_eq!(
+                request.uri().to_string(),
+                "https://hub.example.com/user/someone/api/kernelspecs"
+            );
+            assert_eq!(request.headers()["Authorization"], "token secret");
+
+            let body = json!({
+                "default": "python3",
+                "kernelspecs": {
+                    "python3": {
+                        "name": "python3",
+                        "spec": {
+                            "argv": ["python", "-m", "ipykernel_launcher", "-f", "{connection_file}"],
+                            "display_name": "Python 3 (ipykernel)",
+                            "language": "python",
+                        },
+                        "resources": {},
+                    },
+                    "ir": {
+                        "name": "ir",
+                        "spec": {
+                            "argv": ["R", "--slave", "-e", "IRkernel::main()", "--args", "{connection_file}"],
+                            "display_name": "R",
+                            "language": "R",
+                        },
+                        "resources": {},
+                    },
+                },
+            });
+            Ok(Response::builder()
+                .status(200)
+                .body(body.to_string().into())
+                .unwrap())
+        });
+
+        let specifications = jupyter_server_kernel_specifications(&server(), http_client)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            specifications
+                .iter()
+                .map(|specification| (
+                    specification.name.as_str(),
+                    specification.kernelspec.language.as_str(),
+                    specification.source.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "hub/ir",
+                    "R",
+                    KernelSource::JupyterServer {
+                        server: server(),
+                        kernelspec_name: "ir".into()
+                    }
+                ),
+                (
+                    "hub/python3",
+                    "python",
+                    KernelSource::JupyterServer {
+                        server: server(),
+                        kernelspec_name: "python3".into()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            specifications[1].location(),
+            "python3 on https://hub.example.com/user/someone/"
+        );
+
+        let http_client = FakeHttpClient::create(|_| async move {
+            Ok(Response::builder()
+                .status(403)
+                .body("Forbidden".into())
+                .unwrap())
+        });
+        assert!(jupyter_server_kernel_specifications(&server(), http_client)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_channels_url() {
+        assert_eq!(
+            channels_url(&server(), "4a6e", "f00d").unwrap().as_str(),
+            "wss://hub.example.com/user/someone/api/kernels/4a6e/channels?session_id=f00d"
+        );
+
+        let server = JupyterServer {
+            name: "local".into(),
+            url: "http://localhost:8888".into(),
+            token: None,
+        };
+        assert_eq!(
+            channels_url(&server, "4a6e", "f00d").unwrap().as_str(),
+            "ws://localhost:8888/api/kernels/4a6e/channels?session_id=f00d"
+        );
+    }
+
+    #[test]
+    fn test_channel_messages() {
+        let request: JupyterMessage = ExecuteRequest {
+            code: "print(1)".into(),
+            ..ExecuteRequest::default()
+        }
+        .into();
+
+        let encoded = encode_message(&request).unwrap();
+        let json = serde_json::from_str::<Value>(&encoded).unwrap();
+        assert_eq!(json["channel"], "shell");
+        assert_eq!(json["parent_header"], json!({}));
+        assert_eq!(json["content"]["code"], "print(1)");
+
+        let decoded = decode_message(&encoded).unwrap();
+        assert_eq!(decoded.header.msg_id, request.header.msg_id);
+        assert!(decoded.parent_header.is_none());
+        assert!(matches!(
+            decoded.content,
+            JupyterMessageContent::ExecuteRequest(ExecuteRequest { ref code, .. }) if code == "print(1)"
+        ));
+    }
+}
@@ -0,0 +1,971 @@
+//! A workspace item for editing and running Jupyter notebooks.
+
+pub mod nbformat;
+
+use std::{ffi::OsStr, mem, path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use editor::{Editor, EditorEvent, EditorMode, MultiBuffer};
+use futures::{io::BufReader, AsyncBufReadExt as _, FutureExt as _, StreamExt as _};
+use gpui::{
+    prelude::*, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView, Model,
+    Subscription, Task, View, ViewContext, WeakView,
+};
+use language::{Buffer, LanguageRegistry};
+use project::{Project, ProjectEntryId, ProjectPath};
+use runtimelib::{
+    ExecuteRequest, ExecutionState, InterruptRequest, JupyterMessage, JupyterMessageContent,
+    ShutdownRequest,
+};
+use serde_json::{Map, Value};
+use theme::ActiveTheme;
+use ui::{prelude::*, Tooltip};
+use util::ResultExt as _;
+use workspace::{
+    item::{Item, ItemEvent, ProjectItem},
+    Workspace,
+};
+
+use crate::kernels::{Kernel, KernelSource, KernelSpecification, RunningKernel};
+use crate::outputs::{ExecutionStatus, ExecutionView};
+use crate::repl_store::ReplStore;
+use crate::{ClearOutputs, Interrupt, JupyterSettings, Restart, Run, RunInPlace, Shutdown};
+use nbformat::{Cell, MultilineString, Notebook};
+
+/// The maximum number of lines shown by a cell's editor before it scrolls.
+const MAX_CELL_LINES: usize = 64;
+
+pub fn init(cx: &mut AppContext) {
+    workspace::register_project_item::<NotebookEditor>(cx);
+}
+
+pub struct NotebookItem {
+    id: Option<ProjectEntryId>,
+    abs_path: PathBuf,
+    project_path: ProjectPath,
+    notebook: Notebook,
+}
+
+impl project::Item for NotebookItem {
+    fn try_open(
+        project: &Model<Project>,
+        path: &ProjectPath,
+        cx: &mut AppContext,
+    ) -> Option<Task<Result<Model<Self>>>> {
+        let is_notebook = path
+            .path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("ipynb"));
+        if !is_notebook || !JupyterSettings::enabled(cx) || !project.read(cx).is_local() {
+            return None;
+        }
+
+        let path = path.clone();
+        let project = project.clone();
+        let fs = ReplStore::global(cx).read(cx).fs().clone();
+        Some(cx.spawn(|mut cx| async move {
+            let abs_path = project
+                .read_with(&cx, |project, cx| project.absolute_path(&path, cx))?
+                .context("failed to find the absolute path of the notebook")?;
+            let id = project
+                .update(&mut cx, |project, cx| project.entry_for_path(&path, cx))?
+                .context("entry not found")?
+                .id;
+
+            let json = fs.load(&abs_path).await?;
+            let notebook = Notebook::from_json(&json)
+                .with_context(|| format!("failed to parse notebook {abs_path:?}"))?;
+
+            cx.new_model(|_| NotebookItem {
+                id: Some(id),
+                abs_path,
+                project_path: path,
+                notebook,
+            })
+        }))
+    }
+
+    fn entry_id(&self, _: &AppContext) -> Option<ProjectEntryId> {
+        self.id
+    }
+
+    fn project_path(&self, _: &AppContext) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+}
+
+struct NotebookCell {
+    cell: Cell,
+    editor: View<Editor>,
+    outputs: Option<View<ExecutionView>>,
+    clear_outputs_on_next_output: bool,
+}
+
+impl NotebookCell {
+    fn sync_source(&mut self, cx: &AppContext) {
+        let text = self.editor.read(cx).text(cx);
+        self.cell.source_mut().set_text(&text);
+    }
+
+    fn clear_outputs(&mut self) {
+        if let Cell::Code {
+            outputs,
+            execution_count,
+            ..
+        } = &mut self.cell
+        {
+            outputs.clear();
+            *execution_count = None;
+        }
+        self.outputs = None;
+        self.clear_outputs_on_next_output = false;
+    }
+
+    /// Records a message produced by running the cell in the cell's saved outputs,
+    /// returning whether the cell has changed.
+    fn record_message(&mut self, message: &JupyterMessage) -> bool {
+        let Cell::Code {
+            outputs,
+            execution_count,
+            ..
+        } = &mut self.cell
+        else {
+            return false;
+        };
+        let Some(content) = serde_json::to_value(&message.content).log_err() else {
+            return false;
+        };
+
+        match message.header.msg_type.as_str() {
+            "execute_input" => {
+                *execution_count = content["execution_count"].as_u64();
+                true
+            }
+            "clear_output" => {
+                if content["wait"].as_bool() == Some(true) {
+                    self.clear_outputs_on_next_output = true;
+                } else {
+                    outputs.clear();
+                }
+                true
+            }
+            message_type => match nbformat::output_from_message(message_type, content) {
+                Some(output) => {
+                    if mem::take(&mut self.clear_outputs_on_next_output) {
+                        outputs.clear();
+                    }
+                    nbformat::push_output(outputs, output);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+pub enum NotebookEvent {
+    Edited,
+    Saved,
+}
+
+pub struct NotebookEditor {
+    project: Model<Project>,
+    item: Model<NotebookItem>,
+    workspace: Option<WeakView<Workspace>>,
+    cells: Vec<NotebookCell>,
+    selected_cell: usize,
+    dirty: bool,
+    kernel: Kernel,
+    kernel_specification: Option<KernelSpecification>,
+    /// The cells being run, by the id of the execute request that's running them.
+    executions: HashMap<String, usize>,
+    messaging_task: Option<Task<()>>,
+    focus_handle: FocusHandle,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl NotebookEditor {
+    fn new(project: Model<Project>, item: Model<NotebookItem>, cx: &mut ViewContext<Self>) -> Self {
+        let mut this = Self {
+            project,
+            item,
+            workspace: None,
+            cells: Vec::new(),
+            selected_cell: 0,
+            dirty: false,
+            kernel: Kernel::Shutdown,
+            kernel_specification: None,
+            executions: HashMap::default(),
+            messaging_task: None,
+            focus_handle: cx.focus_handle(),
+            _subscriptions: Vec::new(),
+        };
+        this.load_cells(cx);
+        this
+    }
+
+    fn load_cells(&mut self, cx: &mut ViewContext<Self>) {
+        let notebook = self.item.read(cx).notebook.clone();
+        let language_registry = self.project.read(cx).languages().clone();
+
+        self.cells.clear();
+        self._subscriptions.clear();
+        for cell in notebook.cells {
+            self.push_cell(cell, &language_registry, cx);
+        }
+        self.selected_cell = 0;
+        self.dirty = false;
+        cx.notify();
+    }
+
+    fn push_cell(
+        &mut self,
+        cell: Cell,
+        language_registry: &Arc<LanguageRegistry>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let language_name = match &cell {
+            Cell::Code { .. } => self.item.read(cx).notebook.language_name().map(Into::into),
+            Cell::Markdown { .. } => Some("Markdown".to_string()),
+            Cell::Raw { .. } => None,
+        };
+
+        let text = cell.source().text();
+        let buffer = cx.new_model(|cx| {
+            let buffer = Buffer::local(text, cx);
+            buffer.set_language_registry(language_registry.clone());
+            buffer
+        });
+        if let Some(language_name) = language_name {
+            let language = language_registry.language_for_name(&language_name);
+            let buffer = buffer.downgrade();
+            cx.spawn(|_, mut cx| async move {
+                let language = language.await?;
+                buffer.update(&mut cx, |buffer, cx| {
+                    buffer.set_language(Some(language), cx)
+                })
+            })
+            .detach_and_log_err(cx);
+        }
+
+        let multibuffer = cx.new_model(|cx| MultiBuffer::singleton(buffer, cx));
+        let project = self.project.clone();
+        let editor = cx.new_view(|cx| {
+            let mut editor = Editor::new(
+                EditorMode::AutoHeight {
+                    max_lines: MAX_CELL_LINES,
+                },
+                multibuffer,
+                Some(project),
+                false,
+                cx,
+            );
+            editor.set_show_gutter(false, cx);
+            editor
+        });
+
+        let ix = self.cells.len();
+        self._subscriptions.push(
+            cx.subscribe(&editor, move |this, _, event, cx| match event {
+                EditorEvent::Focused => {
+                    this.selected_cell = ix;
+                    cx.notify();
+                }
+                EditorEvent::BufferEdited => this.mark_dirty(cx),
+                _ => {}
+            }),
+        );
+
+        let outputs = match (&cell, &self.workspace) {
+            (Cell::Code { outputs, .. }, Some(workspace)) if !outputs.is_empty() => {
+                Some(stored_outputs_view(outputs, workspace.clone(), cx))
+            }
+            _ => None,
+        };
+
+        self.cells.push(NotebookCell {
+            cell,
+            editor,
+            outputs,
+            clear_outputs_on_next_output: false,
+        });
+    }
+
+    fn mark_dirty(&mut self, cx: &mut ViewContext<Self>) {
+        self.dirty = true;
+        cx.emit(NotebookEvent::Edited);
+        cx.notify();
+    }
+
+    fn select_cell(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        if ix == self.cells.len() {
+            self.append_code_cell(cx);
+        }
+
+        if let Some(cell) = self.cells.get(ix) {
+            self.selected_cell = ix;
+            cell.editor.focus_handle(cx).focus(cx);
+            cx.notify();
+        }
+    }
+
+    fn append_code_cell(&mut self, cx: &mut ViewContext<Self>) {
+        // Cell ids are required from version 4.5 of the notebook format onwards.
+        let id = (self.item.read(cx).notebook.nbformat_minor >= 5)
+            .then(|| uuid::Uuid::new_v4().to_string());
+        let cell = Cell::Code {
+            id,
+            metadata: Map::new(),
+            source: MultilineString::Lines(Vec::new()),
+            execution_count: None,
+            outputs: Vec::new(),
+        };
+        let language_registry = self.project.read(cx).languages().clone();
+        self.push_cell(cell, &language_registry, cx);
+        self.mark_dirty(cx);
+    }
+
+    fn run(&mut self, _: &Run, cx: &mut ViewContext<Self>) {
+        self.run_selected_cell(true, cx);
+    }
+
+    fn run_in_place(&mut self, _: &RunInPlace, cx: &mut ViewContext<Self>) {
+        self.run_selected_cell(false, cx);
+    }
+
+    fn run_selected_cell(&mut self, move_down: bool, cx: &mut ViewContext<Self>) {
+        if !JupyterSettings::enabled(cx) {
+            return;
+        }
+
+        let ix = self.selected_cell;
+        self.execute_cell(ix, cx);
+        if move_down {
+            self.select_cell(ix + 1, cx);
+        }
+    }
+
+    fn execute_cell(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.clone() else {
+            return;
+        };
+        let Some(cell) = self.cells.get(ix) else {
+            return;
+        };
+        if !matches!(cell.cell, Cell::Code { .. }) {
+            return;
+        }
+
+        let code = cell.editor.read(cx).text(cx);
+        self.executions.retain(|_, cell_ix| *cell_ix != ix);
+        self.cells[ix].clear_outputs();
+        self.mark_dirty(cx);
+        if code.trim().is_empty() {
+            return;
+        }
+
+        if matches!(self.kernel, Kernel::Shutdown | Kernel::ErroredLaunch(_)) {
+            self.start_kernel(cx);
+        }
+
+        let message: JupyterMessage = ExecuteRequest {
+            code,
+            ..ExecuteRequest::default()
+        }
+        .into();
+        let status = execution_status(&self.kernel);
+        self.cells[ix].outputs = Some(cx.new_view(|cx| ExecutionView::new(status, workspace, cx)));
+        self.executions.insert(message.header.msg_id.clone(), ix);
+        self.send(message, cx);
+    }
+
+    fn clear_outputs(&mut self, _: &ClearOutputs, cx: &mut ViewContext<Self>) {
+        self.executions.clear();
+        for cell in &mut self.cells {
+            cell.clear_outputs();
+        }
+        self.mark_dirty(cx);
+    }
+
+    fn interrupt(&mut self, _: &Interrupt, cx: &mut ViewContext<Self>) {
+        if let Kernel::RunningKernel(_) = self.kernel {
+            self.send(InterruptRequest {}.into(), cx);
+        }
+    }
+
+    fn shutdown(&mut self, _: &Shutdown, cx: &mut ViewContext<Self>) {
+        self.shutdown_kernel(cx);
+        cx.notify();
+    }
+
+    fn restart(&mut self, _: &Restart, cx: &mut ViewContext<Self>) {
+        self.shutdown_kernel(cx);
+        self.start_kernel(cx);
+    }
+
+    /// Finds the kernel the notebook was last run with, or else a kernel for its language.
+    fn find_kernel_specification(&self, cx: &AppContext) -> Option<KernelSpecification> {
+        let notebook = &self.item.read(cx).notebook;
+        let store = ReplStore::global(cx);
+        let store = store.read(cx);
+        notebook
+            .kernel_name()
+            .and_then(|kernel_name| {
+                store
+                    .kernel_specifications()
+                    .find(|specification| specification.name == kernel_name)
+                    .cloned()
+            })
+            .or_else(|| store.kernelspec(notebook.language_name()?, cx))
+    }
+
+    fn start_kernel(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(kernel_specification) = self.find_kernel_specification(cx) else {
+            let language_name = self
+                .item
+                .read(cx)
+                .notebook
+                .language_name()
+                .unwrap_or("unknown")
+                .to_string();
+            self.kernel =
+                Kernel::ErroredLaunch(format!("No kernel found for language: {language_name}"));
+            cx.notify();
+            return;
+        };
+
+        let working_directory = self
+            .item
+            .read(cx)
+            .abs_path
+            .parent()
+            .map(|path| path.to_path_buf())
+            .unwrap_or_else(std::env::temp_dir);
+        let fs = ReplStore::global(cx).read(cx).fs().clone();
+        self.kernel_specification = Some(kernel_specification.clone());
+        let kernel = RunningKernel::new(
+            kernel_specification,
+            cx.entity_id(),
+            working_directory,
+            fs,
+            cx,
+        );
+
+        let pending_kernel = cx
+            .spawn(|this, mut cx| async move {
+                let result = kernel.await;
+                this.update(&mut cx, |this, cx| {
+                    match result {
+                        Ok((mut kernel, mut messages_rx)) => {
+                            log_kernel_output(&mut kernel, cx);
+                            this.kernel = Kernel::RunningKernel(kernel);
+                            this.messaging_task = Some(cx.spawn(|this, mut cx| async move {
+                                while let Some(message) = messages_rx.next().await {
+                                    this.update(&mut cx, |this, cx| this.route(&message, cx))
+                                        .ok();
+                                }
+                            }));
+                        }
+                        Err(error) => {
+                            let error = error.to_string();
+                            for ix in mem::take(&mut this.executions).into_values() {
+                                if let Some(outputs) = &this.cells[ix].outputs {
+                                    outputs.update(cx, |outputs, cx| {
+                                        outputs.status =
+                                            ExecutionStatus::KernelErrored(error.clone());
+                                        cx.notify();
+                                    });
+                                }
+                            }
+                            this.kernel = Kernel::ErroredLaunch(error);
+                        }
+                    }
+                    cx.notify();
+                })
+                .ok();
+            })
+            .shared();
+
+        self.kernel = Kernel::StartingKernel(pending_kernel);
+        cx.notify();
+    }
+
+    fn shutdown_kernel(&mut self, cx: &mut ViewContext<Self>) {
+        self.messaging_task.take();
+        self.executions.clear();
+
+        if let Kernel::RunningKernel(mut kernel) = mem::replace(&mut self.kernel, Kernel::Shutdown)
+        {
+            let is_attached = self
+                .kernel_specification
+                .as_ref()
+                .map_or(false, |specification| {
+                    specification.source == KernelSource::Running
+                });
+            if is_attached {
+                // Zed didn't start this kernel, so it only disconnects from it by dropping its
+                // channels, leaving the kernel running.
+                drop(kernel);
+                return;
+            }
+
+            let message: JupyterMessage = ShutdownRequest { restart: false }.into();
+            kernel.request_tx.try_send(message).ok();
+
+            cx.spawn(|_, cx| async move {
+                // Give the kernel a moment to shut down cleanly before stopping it.
+                cx.background_executor().timer(Duration::from_secs(1)).await;
+                kernel.kill();
+            })
+            .detach();
+        }
+    }
+
+    fn send(&mut self, message: JupyterMessage, cx: &mut ViewContext<Self>) {
+        match &mut self.kernel {
+            Kernel::RunningKernel(kernel) => {
+                kernel.request_tx.try_send(message).ok();
+            }
+            Kernel::StartingKernel(task) => {
+                // Send the message once the kernel has started.
+                let task = task.clone();
+                cx.spawn(|this, mut cx| async move {
+                    task.await;
+                    this.update(&mut cx, |this, _| {
+                        if let Kernel::RunningKernel(kernel) = &mut this.kernel {
+                            kernel.request_tx.try_send(message).ok();
+                        }
+                    })
+                    .ok();
+                })
+                .detach();
+            }
+            _ => {}
+        }
+    }
+
+    fn route(&mut self, message: &JupyterMessage, cx: &mut ViewContext<Self>) {
+        match &message.content {
+            JupyterMessageContent::Status(status) => {
+                self.kernel.set_execution_state(&status.execution_state);
+                cx.notify();
+            }
+            JupyterMessageContent::KernelInfoReply(reply) => {
+                self.kernel.set_kernel_info(reply);
+                cx.notify();
+            }
+            JupyterMessageContent::UpdateDisplayData(update) => {
+                let Some(display_id) = update.transient.display_id.as_ref() else {
+                    return;
+                };
+                for cell in &self.cells {
+                    if let Some(outputs) = &cell.outputs {
+                        outputs.update(cx, |outputs, cx| {
+                            outputs.update_display_data(&update.data, display_id, cx);
+                        });
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let Some(parent_message_id) = message.parent_header.as_ref().map(|h| &h.msg_id) else {
+            return;
+        };
+        let Some(&ix) = self.executions.get(parent_message_id) else {
+            return;
+        };
+        if let JupyterMessageContent::Status(status) = &message.content {
+            if let ExecutionState::Idle = status.execution_state {
+                self.executions.remove(parent_message_id);
+            }
+        }
+
+        let cell = &mut self.cells[ix];
+        if let Some(outputs) = &cell.outputs {
+            outputs.update(cx, |outputs, cx| outputs.push_message(&message.content, cx));
+        }
+        if cell.record_message(message) {
+            self.mark_dirty(cx);
+        }
+    }
+
+    fn write_notebook(
+        &mut self,
+        project_path: Option<ProjectPath>,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        for cell in &mut self.cells {
+            cell.sync_source(cx);
+        }
+        let cells = self.cells.iter().map(|cell| cell.cell.clone()).collect();
+
+        let abs_path = match &project_path {
+            Some(project_path) => project.read(cx).absolute_path(project_path, cx),
+            None => Some(self.item.read(cx).abs_path.clone()),
+        };
+        let Some(abs_path) = abs_path else {
+            return Task::ready(Err(anyhow!("failed to find the absolute path to save to")));
+        };
+
+        let json = self.item.update(cx, |item, _| {
+            item.notebook.cells = cells;
+            item.notebook.to_json()
+        });
+        let json = match json {
+            Ok(json) => json,
+            Err(error) => return Task::ready(Err(error)),
+        };
+
+        let fs = ReplStore::global(cx).read(cx).fs().clone();
+        cx.spawn(|this, mut cx| async move {
+            fs.atomic_write(abs_path.clone(), json).await?;
+            this.update(&mut cx, |this, cx| {
+                if let Some(project_path) = project_path {
+                    let id = project
+                        .read(cx)
+                        .entry_for_path(&project_path, cx)
+                        .map(|entry| entry.id);
+                    this.item.update(cx, |item, _| {
+                        item.id = id;
+                        item.abs_path = abs_path;
+                        item.project_path = project_path;
+                    });
+                }
+                this.dirty = false;
+                cx.emit(NotebookEvent::Saved);
+                cx.notify();
+            })
+        })
+    }
+
+    fn render_kernel_header(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let kernel_name: SharedString = match &self.kernel_specification {
+            Some(specification) => specification.kernelspec.display_name.clone().into(),
+            None => self
+                .item
+                .read(cx)
+                .notebook
+                .metadata
+                .get("kernelspec")
+                .and_then(|kernelspec| kernelspec.get("display_name"))
+                .and_then(Value::as_str)
+                .unwrap_or("No kernel")
+                .to_string()
+                .into(),
+        };
+        let status = match &self.kernel {
+            Kernel::ErroredLaunch(error) => Label::new(format!("Kernel error: {error}"))
+                .size(LabelSize::Small)
+                .color(Color::Error),
+            kernel => Label::new(kernel.status().to_string())
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+        };
+
+        h_flex()
+            .justify_between()
+            .px_4()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(Label::new(kernel_name).size(LabelSize::Small))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(status)
+                    .child(
+                        IconButton::new("interrupt-kernel", IconName::Stop)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::for_action("Interrupt Kernel", &Interrupt, cx))
+                            .on_click(cx.listener(|this, _, cx| this.interrupt(&Interrupt, cx))),
+                    )
+                    .child(
+                        IconButton::new("restart-kernel", IconName::RotateCw)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::for_action("Restart Kernel", &Restart, cx))
+                            .on_click(cx.listener(|this, _, cx| this.restart(&Restart, cx))),
+                    )
+                    .child(
+                        IconButton::new("clear-outputs", IconName::Trash)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::for_action("Clear Outputs", &ClearOutputs, cx))
+                            .on_click(
+                                cx.listener(|this, _, cx| this.clear_outputs(&ClearOutputs, cx)),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_cell(&self, ix: usize, cell: &NotebookCell, cx: &ViewContext<Self>) -> AnyElement {
+        let colors = cx.theme().colors();
+        let prompt = match &cell.cell {
+            Cell::Code {
+                execution_count, ..
+            } => {
+                if self.executions.values().any(|&cell_ix| cell_ix == ix) {
+                    "[*]:".to_string()
+                } else if let Some(execution_count) = execution_count {
+                    format!("[{execution_count}]:")
+                } else {
+                    "[ ]:".to_string()
+                }
+            }
+            Cell::Markdown { .. } | Cell::Raw { .. } => String::new(),
+        };
+        let border_color = if ix == self.selected_cell {
+            colors.border_focused
+        } else {
+            colors.border_variant
+        };
+
+        h_flex()
+            .id(("notebook-cell", ix))
+            .items_start()
+            .gap_2()
+            .child(
+                h_flex()
+                    .w(rems(3.5))
+                    .flex_none()
+                    .pt_2()
+                    .justify_end()
+                    .child(
+                        Label::new(prompt)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .child(
+                v_flex()
+                    .flex_1()
+                    .gap_1()
+                    .child(
+                        div()
+                            .p_2()
+                            .rounded_md()
+                            .border_1()
+                            .border_color(border_color)
+                            .child(cell.editor.clone()),
+                    )
+                    .children(cell.outputs.clone()),
+            )
+            .on_click(cx.listener(move |this, _, cx| {
+                if this.selected_cell != ix {
+                    this.select_cell(ix, cx);
+                }
+            }))
+            .into_any_element()
+    }
+}
+
+fn stored_outputs_view(
+    outputs: &[Value],
+    workspace: WeakView<Workspace>,
+    cx: &mut ViewContext<NotebookEditor>,
+) -> View<ExecutionView> {
+    cx.new_view(|cx| {
+        let mut execution_view = ExecutionView::new(ExecutionStatus::Finished, workspace, cx);
+        for output in outputs {
+            let Some((message_type, content)) = nbformat::output_to_message(output) else {
+                continue;
+            };
+            if let Some(content) =
+                JupyterMessageContent::from_type_and_content(&message_type, content).log_err()
+            {
+                execution_view.push_message(&content, cx);
+            }
+        }
+        execution_view
+    })
+}
+
+fn execution_status(kernel: &Kernel) -> ExecutionStatus {
+    match kernel {
+        Kernel::Restarting => ExecutionStatus::Restarting,
+        Kernel::RunningKernel(_) => ExecutionStatus::Queued,
+        Kernel::StartingKernel(_) => ExecutionStatus::ConnectingToKernel,
+        Kernel::ErroredLaunch(error) => ExecutionStatus::KernelErrored(error.clone()),
+        Kernel::ShuttingDown => ExecutionStatus::ShuttingDown,
+        Kernel::Shutdown => ExecutionStatus::Shutdown,
+    }
+}
+
+fn log_kernel_output(kernel: &mut RunningKernel, cx: &mut ViewContext<NotebookEditor>) {
+    let Some(process) = kernel.process.as_mut() else {
+        return;
+    };
+
+    if let Some(stderr) = process.stderr.take() {
+        cx.background_executor()
+            .spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Some(Ok(line)) = lines.next().await {
+                    log::error!("kernel: {}", line);
+                }
+            })
+            .detach();
+    }
+
+    if let Some(stdout) = process.stdout.take() {
+        cx.background_executor()
+            .spawn(async move {
+                let mut lines = BufReader::new(stdout).lines();
+                while let Some(Ok(line)) = lines.next().await {
+                    log::info!("kernel: {}", line);
+                }
+            })
+            .detach();
+    }
+}
+
+impl EventEmitter<NotebookEvent> for NotebookEditor {}
+
+impl FocusableView for NotebookEditor {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        match self.cells.get(self.selected_cell) {
+            Some(cell) => cell.editor.focus_handle(cx),
+            None => self.focus_handle.clone(),
+        }
+    }
+}
+
+impl Render for NotebookEditor {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let cells = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(ix, cell)| self.render_cell(ix, cell, cx))
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .key_context("NotebookEditor")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::run))
+            .on_action(cx.listener(Self::run_in_place))
+            .on_action(cx.listener(Self::clear_outputs))
+            .on_action(cx.listener(Self::interrupt))
+            .on_action(cx.listener(Self::shutdown))
+            .on_action(cx.listener(Self::restart))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_kernel_header(cx))
+            .child(
+                div()
+                    .id("notebook-cells")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .child(v_flex().py_4().pr_4().gap_3().children(cells)),
+            )
+    }
+}
+
+impl Item for NotebookEditor {
+    type Event = NotebookEvent;
+
+    fn tab_content_text(&self, cx: &WindowContext) -> Option<SharedString> {
+        let path = &self.item.read(cx).abs_path;
+        let title = path
+            .file_name()
+            .unwrap_or_else(|| path.as_os_str())
+            .to_string_lossy()
+            .to_string();
+        Some(title.into())
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        match event {
+            NotebookEvent::Edited => {
+                f(ItemEvent::Edit);
+                f(ItemEvent::UpdateTab);
+            }
+            NotebookEvent::Saved => f(ItemEvent::UpdateTab),
+        }
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &AppContext,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::Item),
+    ) {
+        f(self.item.entity_id(), self.item.read(cx))
+    }
+
+    fn is_singleton(&self, _cx: &AppContext) -> bool {
+        true
+    }
+
+    fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
+        if self.workspace.is_some() {
+            return;
+        }
+
+        let workspace = workspace.weak_handle();
+        for cell in &mut self.cells {
+            if let Cell::Code { outputs, .. } = &cell.cell {
+                if !outputs.is_empty() {
+                    cell.outputs = Some(stored_outputs_view(outputs, workspace.clone(), cx));
+                }
+            }
+        }
+        self.workspace = Some(workspace);
+    }
+
+    fn is_dirty(&self, _: &AppContext) -> bool {
+        self.dirty
+    }
+
+    fn can_save(&self, _: &AppContext) -> bool {
+        true
+    }
+
+    fn save(
+        &mut self,
+        _format: bool,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        self.write_notebook(None, project, cx)
+    }
+
+    fn save_as(
+        &mut self,
+        project: Model<Project>,
+        path: ProjectPath,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        self.write_notebook(Some(path), project, cx)
+    }
+
+    fn reload(&mut self, _project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        let abs_path = self.item.read(cx).abs_path.clone();
+        let fs = ReplStore::global(cx).read(cx).fs().clone();
+        cx.spawn(|this, mut cx| async move {
+            let json = fs.load(&abs_path).await?;
+            let notebook = Notebook::from_json(&json)
+                .with_context(|| format!("failed to parse notebook {abs_path:?}"))?;
+            this.update(&mut cx, |this, cx| {
+                this.item.update(cx, |item, _| item.notebook = notebook);
+                this.executions.clear();
+                this.load_cells(cx);
+                cx.emit(NotebookEvent::Saved);
+            })
+        })
+    }
+}
+
+impl ProjectItem for NotebookEditor {
+    type Item = NotebookItem;
+
+    fn for_project_item(
+        project: Model<Project>,
+        item: Model<Self::Item>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        Self::new(project, item, cx)
+    }
+}
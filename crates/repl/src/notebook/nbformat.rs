@@ -0,0 +1,400 @@
+//! # Notebook Format
+//!
+//! Reading and writing Jupyter notebooks (`.ipynb` files) in version 4 of the
+//! [notebook format](https://nbformat.readthedocs.io/en/latest/format_description.html).
+//!
+//! Fields that Zed doesn't use, such as notebook and cell metadata, are kept as
+//! JSON so that saving a notebook doesn't lose anything written by other tools.
+//! Outputs are kept in their saved form too, and are converted to and from
+//! Jupyter messages when they're displayed or produced by a kernel.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The major version of the notebook format that can be read and written.
+pub const NBFORMAT_VERSION: u32 = 4;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Notebook {
+    pub cells: Vec<Cell>,
+    pub metadata: Map<String, Value>,
+    pub nbformat: u32,
+    pub nbformat_minor: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cell_type", rename_all = "lowercase")]
+pub enum Cell {
+    Code {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(default)]
+        metadata: Map<String, Value>,
+        source: MultilineString,
+        execution_count: Option<u64>,
+        #[serde(default)]
+        outputs: Vec<Value>,
+    },
+    Markdown {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(default)]
+        metadata: Map<String, Value>,
+        source: MultilineString,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attachments: Option<Value>,
+    },
+    Raw {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        #[serde(default)]
+        metadata: Map<String, Value>,
+        source: MultilineString,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attachments: Option<Value>,
+    },
+}
+
+/// Text that's saved either as a string or as a list of lines, each of which
+/// ends with a newline except for the last one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MultilineString {
+    Single(String),
+    Lines(Vec<String>),
+}
+
+impl Notebook {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let notebook = serde_json::from_str::<Value>(json)?;
+        let version = notebook
+            .get("nbformat")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| anyhow!("notebook has no nbformat version"))?;
+        if version != NBFORMAT_VERSION as u64 {
+            return Err(anyhow!(
+                "unsupported notebook format version {version}, only version {NBFORMAT_VERSION} is supported"
+            ));
+        }
+
+        Ok(serde_json::from_value(notebook)?)
+    }
+
+    /// Serializes the notebook the way Jupyter does, with sorted keys and one
+    /// space of indentation, so that saving an unchanged notebook doesn't change it.
+    pub fn to_json(&self) -> Result<String> {
+        let notebook = sort_keys(serde_json::to_value(self)?);
+        let mut json = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut json, formatter);
+        notebook.serialize(&mut serializer)?;
+        json.push(b'\n');
+        Ok(String::from_utf8(json)?)
+    }
+
+    /// Returns the name of the language the notebook's code cells are written in.
+    pub fn language_name(&self) -> Option<&str> {
+        let metadata = &self.metadata;
+        metadata
+            .get("language_info")
+            .and_then(|language_info| language_info.get("name"))
+            .or_else(|| {
+                metadata
+                    .get("kernelspec")
+                    .and_then(|kernelspec| kernelspec.get("language"))
+            })
+            .and_then(Value::as_str)
+    }
+
+    /// Returns the name of the kernel the notebook was last run with.
+    pub fn kernel_name(&self) -> Option<&str> {
+        self.metadata
+            .get("kernelspec")
+            .and_then(|kernelspec| kernelspec.get("name"))
+            .and_then(Value::as_str)
+    }
+}
+
+impl Cell {
+    pub fn source(&self) -> &MultilineString {
+        match self {
+            Cell::Code { source, .. }
+            | Cell::Markdown { source, .. }
+            | Cell::Raw { source, .. } => source,
+        }
+    }
+
+    pub fn source_mut(&mut self) -> &mut MultilineString {
+        match self {
+            Cell::Code { source, .. }
+            | Cell::Markdown { source, .. }
+            | Cell::Raw { source, .. } => source,
+        }
+    }
+}
+
+impl MultilineString {
+    pub fn text(&self) -> String {
+        match self {
+            MultilineString::Single(text) => text.clone(),
+            MultilineString::Lines(lines) => lines.concat(),
+        }
+    }
+
+    /// Replaces the text, keeping the way it's saved.
+    pub fn set_text(&mut self, text: &str) {
+        if self.text() == text {
+            return;
+        }
+
+        *self = match self {
+            MultilineString::Single(_) => MultilineString::Single(text.to_string()),
+            MultilineString::Lines(_) => MultilineString::Lines(split_lines(text)),
+        };
+    }
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    text.split_inclusive('\n').map(str::to_string).collect()
+}
+
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+/// Whether the values of a MIME type are text, which notebooks save as lists of lines.
+fn is_text_mime_type(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || mime_type == "image/svg+xml"
+        || mime_type == "application/javascript"
+}
+
+/// Whether the values of a MIME type are JSON, which is saved as it is.
+fn is_json_mime_type(mime_type: &str) -> bool {
+    mime_type == "application/json" || mime_type.ends_with("+json")
+}
+
+/// Converts a saved output into the type and content of the Jupyter message
+/// that would have produced it.
+pub fn output_to_message(output: &Value) -> Option<(String, Value)> {
+    let mut content = output.as_object()?.clone();
+    let output_type = content.remove("output_type")?.as_str()?.to_string();
+
+    match output_type.as_str() {
+        "stream" => {
+            let text = join_lines(content.get("text")?);
+            content.insert("text".into(), text);
+        }
+        "display_data" | "execute_result" => {
+            if let Some(Value::Object(data)) = content.get_mut("data") {
+                for (mime_type, value) in data.iter_mut() {
+                    if !is_json_mime_type(mime_type) {
+                        *value = join_lines(value);
+                    }
+                }
+            }
+            content
+                .entry("metadata")
+                .or_insert_with(|| Value::Object(Map::new()));
+            content
+                .entry("transient")
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        "error" => {}
+        _ => return None,
+    }
+
+    Some((output_type, Value::Object(content)))
+}
+
+/// Converts the content of a Jupyter message into the output that's saved for it.
+pub fn output_from_message(message_type: &str, content: Value) -> Option<Value> {
+    let Value::Object(mut content) = content else {
+        return None;
+    };
+
+    match message_type {
+        "stream" => {
+            let text = split_text(content.get("text")?);
+            content.insert("text".into(), text);
+        }
+        "display_data" | "execute_result" => {
+            content.remove("transient");
+            if let Some(Value::Object(data)) = content.get_mut("data") {
+                for (mime_type, value) in data.iter_mut() {
+                    if is_text_mime_type(mime_type) {
+                        *value = split_text(value);
+                    }
+                }
+            }
+        }
+        "error" => {}
+        _ => return None,
+    }
+
+    content.insert("output_type".into(), Value::String(message_type.into()));
+    Some(Value::Object(content))
+}
+
+/// Appends an output to a cell's outputs, combining consecutive output to the same stream.
+pub fn push_output(outputs: &mut Vec<Value>, output: Value) {
+    if output["output_type"] == "stream" {
+        if let Some(last_output) = outputs.last_mut() {
+            if last_output["output_type"] == "stream" && last_output["name"] == output["name"] {
+                let text = join_lines(&last_output["text"])
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string()
+                    + join_lines(&output["text"]).as_str().unwrap_or_default();
+                last_output["text"] = split_text(&Value::String(text));
+                return;
+            }
+        }
+    }
+
+    outputs.push(output);
+}
+
+fn join_lines(value: &Value) -> Value {
+    match value {
+        Value::Array(lines) if lines.iter().all(Value::is_string) => Value::String(
+            lines
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .concat(),
+        ),
+        value => value.clone(),
+    }
+}
+
+fn split_text(value: &Value) -> Value {
+    match value {
+        Value::String(text) => {
+            Value::Array(split_lines(text).into_iter().map(Value::String).collect())
+        }
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    const PYTHON_NOTEBOOK: &str = include_str!("../../test_data/python.ipynb");
+    const LEGACY_NOTEBOOK: &str = include_str!("../../test_data/legacy.ipynb");
+
+    #[test]
+    fn test_round_trip() {
+        let notebook = Notebook::from_json(PYTHON_NOTEBOOK).unwrap();
+        assert_eq!(notebook.cells.len(), 5);
+        assert_eq!(notebook.language_name(), Some("python"));
+        assert_eq!(notebook.kernel_name(), Some("python3"));
+        assert_eq!(notebook.to_json().unwrap(), PYTHON_NOTEBOOK);
+
+        // Notebooks written by other tools may be formatted differently, but
+        // their contents are preserved.
+        let notebook = Notebook::from_json(LEGACY_NOTEBOOK).unwrap();
+        assert_eq!(notebook.language_name(), Some("python"));
+        assert_eq!(notebook.cells[0].source().text(), "import os\nos.getcwd()");
+        assert_eq!(
+            serde_json::from_str::<Value>(&notebook.to_json().unwrap()).unwrap(),
+            serde_json::from_str::<Value>(LEGACY_NOTEBOOK).unwrap()
+        );
+
+        assert!(Notebook::from_json(r#"{"nbformat": 3, "worksheets": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_editing_cells() {
+        let mut notebook = Notebook::from_json(PYTHON_NOTEBOOK).unwrap();
+        notebook.cells[1].source_mut().set_text("x = 2\nprint(x)\n");
+        assert_eq!(
+            notebook.cells[1].source(),
+            &MultilineString::Lines(vec!["x = 2\n".into(), "print(x)\n".into()])
+        );
+
+        let mut notebook = Notebook::from_json(LEGACY_NOTEBOOK).unwrap();
+        notebook.cells[0].source_mut().set_text("import sys");
+        assert_eq!(
+            notebook.cells[0].source(),
+            &MultilineString::Single("import sys".into())
+        );
+    }
+
+    #[test]
+    fn test_output_conversion() {
+        let notebook = Notebook::from_json(PYTHON_NOTEBOOK).unwrap();
+        let Cell::Code { outputs, .. } = &notebook.cells[2] else {
+            panic!("expected a code cell");
+        };
+
+        let (message_type, content) = output_to_message(&outputs[0]).unwrap();
+        assert_eq!(message_type, "execute_result");
+        assert_eq!(
+            content["data"]["text/plain"],
+            json!("   a  b\n0  1  3\n1  2  4")
+        );
+        assert_eq!(content["transient"], json!({}));
+        assert_eq!(
+            output_from_message(&message_type, content).unwrap(),
+            outputs[0]
+        );
+
+        let (message_type, content) = output_to_message(&outputs[1]).unwrap();
+        assert_eq!(message_type, "display_data");
+        assert_eq!(
+            output_from_message(&message_type, content).unwrap(),
+            outputs[1]
+        );
+
+        // JSON data is kept as it is, even when it's a list of strings.
+        let output = json!({
+            "data": {"application/json": ["a\n", "b"], "text/plain": ["['a\\n', 'b']"]},
+            "metadata": {},
+            "output_type": "display_data",
+        });
+        let (message_type, content) = output_to_message(&output).unwrap();
+        assert_eq!(content["data"]["application/json"], json!(["a\n", "b"]));
+        assert_eq!(output_from_message(&message_type, content).unwrap(), output);
+    }
+
+    #[test]
+    fn test_push_output() {
+        let mut outputs = Vec::new();
+        for text in ["one\ntw", "o\n", "three"] {
+            push_output(
+                &mut outputs,
+                output_from_message("stream", json!({"name": "stdout", "text": text})).unwrap(),
+            );
+        }
+        push_output(
+            &mut outputs,
+            output_from_message("stream", json!({"name": "stderr", "text": "oops\n"})).unwrap(),
+        );
+
+        assert_eq!(
+            outputs,
+            vec![
+                json!({"name": "stdout", "output_type": "stream", "text": ["one\n", "two\n", "three"]}),
+                json!({"name": "stderr", "output_type": "stream", "text": ["oops\n"]}),
+            ]
+        );
+    }
+}
@@ -14,7 +14,10 @@
 //! The module supports several output types, including:
 //! - Plain text
 //! - Markdown
-//! - Images (PNG and JPEG)
+//! - HTML, converted to Markdown
+//! - Images (PNG, JPEG and SVG)
+//! - LaTeX math
+//! - JSON
 //! - Tables
 //! - Error messages
 //!
@@ -44,9 +47,17 @@ use language::Buffer;
 use runtimelib::{ExecutionState, JupyterMessageContent, MimeBundle, MimeType};
 use ui::{div, prelude::*, v_flex, IntoElement, Styled, Tooltip, ViewContext};
 
+mod html;
+
 mod image;
 use image::ImageView;
 
+mod json;
+use json::JsonView;
+
+mod latex;
+use latex::LatexView;
+
 mod markdown;
 use markdown::MarkdownView;
 
@@ -63,10 +74,14 @@ use workspace::Workspace;
 /// When deciding what to render from a collection of mediatypes, we need to rank them in order of importance
 fn rank_mime_type(mimetype: &MimeType) -> usize {
     match mimetype {
-        MimeType::DataTable(_) => 6,
-        MimeType::Png(_) => 4,
-        MimeType::Jpeg(_) => 3,
-        MimeType::Markdown(_) => 2,
+        MimeType::DataTable(_) => 9,
+        MimeType::Png(_) => 8,
+        MimeType::Jpeg(_) => 7,
+        MimeType::Svg(_) => 6,
+        MimeType::Markdown(_) => 5,
+        MimeType::Latex(_) => 4,
+        MimeType::Html(_) => 3,
+        MimeType::Json(_) => 2,
         MimeType::Plain(_) => 1,
         // All other media types are not supported in Zed at this time
         _ => 0,
@@ -126,6 +141,14 @@ pub enum Output {
         content: View<MarkdownView>,
         display_id: Option<String>,
     },
+    Latex {
+        content: View<LatexView>,
+        display_id: Option<String>,
+    },
+    Json {
+        content: View<JsonView>,
+        display_id: Option<String>,
+    },
     ClearOutputWaitMarker,
 }
 
@@ -214,6 +237,8 @@ impl Output {
             Self::Image { content, .. } => Some(content.clone().into_any_element()),
             Self::Message(message) => Some(div().child(message.clone()).into_any_element()),
             Self::Table { content, .. } => Some(content.clone().into_any_element()),
+            Self::Latex { content, .. } => Some(content.clone().into_any_element()),
+            Self::Json { content, .. } => Some(content.clone().into_any_element()),
             Self::ErrorOutput(error_view) => error_view.render(cx),
             Self::ClearOutputWaitMarker => None,
         };
@@ -242,6 +267,12 @@ impl Output {
                 Self::Table { content, .. } => {
                     Self::render_output_controls(content.clone(), workspace.clone(), cx)
                 }
+                Self::Latex { content, .. } => {
+                    Self::render_output_controls(content.clone(), workspace.clone(), cx)
+                }
+                Self::Json { content, .. } => {
+                    Self::render_output_controls(content.clone(), workspace.clone(), cx)
+                }
                 Self::ClearOutputWaitMarker => None,
             })
     }
@@ -255,6 +286,8 @@ impl Output {
             Output::Message(_) => None,
             Output::Table { display_id, .. } => display_id.clone(),
             Output::Markdown { display_id, .. } => display_id.clone(),
+            Output::Latex { display_id, .. } => display_id.clone(),
+            Output::Json { display_id, .. } => display_id.clone(),
             Output::ClearOutputWaitMarker => None,
         }
     }
@@ -272,6 +305,22 @@ impl Output {
                     display_id,
                 }
             }
+            Some(MimeType::Html(html)) => match html::html_to_markdown(html) {
+                Ok(markdown) if !markdown.is_empty() => Output::Markdown {
+                    content: cx.new_view(|cx| MarkdownView::from(markdown, cx)),
+                    display_id,
+                },
+                // HTML that's only scripts or styles, such as a widget or an
+                // interactive plot, is shown as the plain text that comes with it.
+                _ => match data.richest(|mimetype| matches!(mimetype, MimeType::Plain(_)) as usize)
+                {
+                    Some(MimeType::Plain(text)) => Output::Plain {
+                        content: cx.new_view(|cx| TerminalOutput::from(text, cx)),
+                        display_id,
+                    },
+                    _ => Output::Message("Unsupported HTML output".to_string()),
+                },
+            },
             Some(MimeType::Png(data)) | Some(MimeType::Jpeg(data)) => match ImageView::from(data) {
                 Ok(view) => Output::Image {
                     content: cx.new_view(|_| view),
@@ -279,6 +328,21 @@ impl Output {
                 },
                 Err(error) => Output::Message(format!("Failed to load image: {}", error)),
             },
+            Some(MimeType::Svg(svg)) => match ImageView::from_svg(svg, cx) {
+                Ok(view) => Output::Image {
+                    content: cx.new_view(|_| view),
+                    display_id,
+                },
+                Err(error) => Output::Message(format!("Failed to load image: {}", error)),
+            },
+            Some(MimeType::Latex(text)) => Output::Latex {
+                content: cx.new_view(|_| LatexView::from(text)),
+                display_id,
+            },
+            Some(MimeType::Json(json)) => Output::Json {
+                content: cx.new_view(|_| JsonView::new(json.clone().into())),
+                display_id,
+            },
             Some(MimeType::DataTable(data)) => Output::Table {
                 content: cx.new_view(|cx| TableView::new(data, cx)),
                 display_id,
@@ -0,0 +1,189 @@
+//! # HTML Output for REPL
+//!
+//! Kernels commonly send HTML alongside plain text, most notably pandas and
+//! polars DataFrames, which are displayed as HTML tables. Rather than render
+//! arbitrary HTML, the supported subset (tables, headings, lists, paragraphs,
+//! code and emphasis) is converted to Markdown and shown with the Markdown
+//! renderer. Styles and scripts are dropped.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::Result;
+use html_to_markdown::{
+    convert_html_to_markdown, markdown, HandleTag, HandlerOutcome, HtmlElement, MarkdownWriter,
+    StartTagOutcome, TagHandler,
+};
+
+/// Converts the HTML sent by a kernel to Markdown.
+pub fn html_to_markdown(html: &str) -> Result<String> {
+    let mut handlers: Vec<TagHandler> = vec![
+        Rc::new(RefCell::new(markdown::WebpageChromeRemover)),
+        Rc::new(RefCell::new(markdown::ParagraphHandler)),
+        Rc::new(RefCell::new(markdown::HeadingHandler)),
+        Rc::new(RefCell::new(markdown::ListHandler)),
+        Rc::new(RefCell::new(TableHandler::default())),
+        Rc::new(RefCell::new(markdown::StyledTextHandler)),
+        Rc::new(RefCell::new(markdown::CodeHandler)),
+    ];
+
+    convert_html_to_markdown(html.as_bytes(), &mut handlers)
+}
+
+/// Writes tables as Markdown tables.
+///
+/// Unlike `markdown::TableHandler`, this handles the tables written by
+/// DataFrame libraries, which have header cells in their body for the index
+/// and may have more than one header row. Markdown tables only have a single
+/// header row, so the first row of the table is used as the header.
+#[derive(Default)]
+struct TableHandler {
+    /// The number of cells written in the current row.
+    row_cells: usize,
+    /// Whether the current table's header row has been written.
+    has_header: bool,
+}
+
+impl HandleTag for TableHandler {
+    fn should_handle(&self, tag: &str) -> bool {
+        matches!(tag, "table" | "tr" | "th" | "td")
+    }
+
+    fn handle_tag_start(
+        &mut self,
+        tag: &HtmlElement,
+        writer: &mut MarkdownWriter,
+    ) -> StartTagOutcome {
+        match tag.tag() {
+            "table" => {
+                self.has_header = false;
+                writer.push_blank_line();
+            }
+            "tr" => {
+                self.row_cells = 0;
+                writer.push_newline();
+            }
+            "th" | "td" => {
+                writer.push_str(if self.row_cells == 0 { "| " } else { " | " });
+                self.row_cells += 1;
+            }
+            _ => {}
+        }
+
+        StartTagOutcome::Continue
+    }
+
+    fn handle_tag_end(&mut self, tag: &HtmlElement, writer: &mut MarkdownWriter) {
+        match tag.tag() {
+            "tr" => {
+                writer.push_str(" |");
+                if !self.has_header {
+                    self.has_header = true;
+                    writer.push_newline();
+                    writer.push_str(&vec!["| ---"; self.row_cells.max(1)].join(" "));
+                    writer.push_str(" |");
+                }
+            }
+            "table" => writer.push_blank_line(),
+            _ => {}
+        }
+    }
+
+    fn handle_text(&mut self, text: &str, writer: &mut MarkdownWriter) -> HandlerOutcome {
+        if !writer.is_inside("table") {
+            return HandlerOutcome::NoOp;
+        }
+
+        // The whitespace used to indent the table's markup isn't part of its
+        // content, and would otherwise be written at the start of each row.
+        if !writer.is_inside("th") && !writer.is_inside("td") {
+            return HandlerOutcome::Handled;
+        }
+
+        let text = text
+            .trim_matches(|char| char == '\n' || char == '\r' || char == '\t')
+            .replace('\n', " ")
+            .replace('|', "\\|");
+        writer.push_str(&text);
+        HandlerOutcome::Handled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_dataframe_html() {
+        let html = indoc! {r#"
+            <div>
+            <style scoped>
+                .dataframe tbody tr th:only-of-type {
+                    vertical-align: middle;
+                }
+            </style>
+            <table border="1" class="dataframe">
+              <thead>
+                <tr style="text-align: right;">
+                  <th></th>
+                  <th>name</th>
+                  <th>score</th>
+                </tr>
+                <tr>
+                  <th>id</th>
+                  <th></th>
+                  <th></th>
+                </tr>
+              </thead>
+              <tbody>
+                <tr>
+                  <th>0</th>
+                  <td>Alice</td>
+                  <td>0.5</td>
+                </tr>
+                <tr>
+                  <th>1</th>
+                  <td>Bob | Carol</td>
+                  <td>1.25</td>
+                </tr>
+              </tbody>
+            </table>
+            <p>2 rows × 2 columns</p>
+            </div>
+        "#};
+
+        assert_eq!(
+            html_to_markdown(html).unwrap(),
+            indoc! {r#"
+                |  | name | score |
+                | --- | --- | --- |
+                | id |  |  |
+                | 0 | Alice | 0.5 |
+                | 1 | Bob \| Carol | 1.25 |
+
+                2 rows × 2 columns"#}
+        );
+    }
+
+    #[test]
+    fn test_formatted_html() {
+        let html = indoc! {r#"
+            <h3>Summary</h3>
+            <p>Training <strong>finished</strong> in <code>12s</code>.</p>
+            <ul><li>loss: 0.12</li><li>accuracy: 0.97</li></ul>
+            <script>console.log("ignored")</script>
+        "#};
+
+        assert_eq!(
+            html_to_markdown(html).unwrap(),
+            indoc! {r#"
+                ### Summary
+
+                Training **finished** in `12s`.
+                - loss: 0.12
+                - accuracy: 0.97"#}
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::Result;
 use base64::prelude::*;
-use gpui::{img, ClipboardItem, Image, ImageFormat, Pixels, RenderImage, WindowContext};
+use gpui::{
+    img, AppContext, ClipboardItem, Image, ImageFormat, Pixels, RenderImage, WindowContext,
+};
 use std::sync::Arc;
 use ui::{div, prelude::*, IntoElement, Styled};
 
@@ -57,6 +59,25 @@ impl ImageView {
             image: Arc::new(gpui_image_data),
         })
     }
+
+    /// Rasterizes an SVG image, such as a plot from matplotlib's SVG backend.
+    pub fn from_svg(svg: &str, cx: &AppContext) -> Result<Self> {
+        let mut clipboard_image = Image {
+            format: ImageFormat::Svg,
+            bytes: svg.as_bytes().to_vec(),
+            id: 0,
+        };
+        let image = clipboard_image.to_image_data(cx)?;
+        clipboard_image.id = image.id.0 as u64;
+
+        let size = image.size(0);
+        Ok(ImageView {
+            clipboard_image: Arc::new(clipboard_image),
+            height: size.height.0 as u32,
+            width: size.width.0 as u32,
+            image,
+        })
+    }
 }
 
 impl Render for ImageView {
@@ -0,0 +1,188 @@
+//! # JSON Output for REPL
+//!
+//! Shows `application/json` outputs, such as those displayed with
+//! `IPython.display.JSON`, as a tree whose objects and arrays can be expanded
+//! and collapsed. Only the top level is expanded to begin with.
+
+use collections::HashSet;
+use gpui::{AnyElement, ClipboardItem, Hsla, Model};
+use language::Buffer;
+use serde_json::Value;
+use ui::{div, prelude::*, v_flex, Disclosure, IntoElement, Styled};
+
+use crate::outputs::OutputContent;
+
+pub struct JsonView {
+    value: Value,
+    /// The paths of the objects and arrays that are expanded, as JSON pointers.
+    expanded: HashSet<String>,
+}
+
+impl JsonView {
+    pub fn new(value: Value) -> Self {
+        let mut expanded = HashSet::default();
+        expanded.insert(String::new());
+        Self { value, expanded }
+    }
+
+    fn toggle(&mut self, path: &str, cx: &mut ViewContext<Self>) {
+        if !self.expanded.remove(path) {
+            self.expanded.insert(path.to_string());
+        }
+        cx.notify();
+    }
+
+    fn render_value(
+        &self,
+        key: Option<String>,
+        value: &Value,
+        path: String,
+        cx: &mut ViewContext<Self>,
+    ) -> AnyElement {
+        let key = key.map(|key| {
+            div()
+                .text_color(Color::Muted.color(cx))
+                .child(format!("{key}:"))
+        });
+
+        let children: Vec<(String, &Value)> = match value {
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, value)| (key.clone(), value))
+                .collect(),
+            Value::Array(values) => values
+                .iter()
+                .enumerate()
+                .map(|(ix, value)| (ix.to_string(), value))
+                .collect(),
+            value => {
+                return h_flex()
+                    .gap_1()
+                    .pl_5()
+                    .children(key)
+                    .child(
+                        div()
+                            .text_color(scalar_color(value, cx))
+                            .child(value.to_string()),
+                    )
+                    .into_any_element();
+            }
+        };
+
+        let is_expanded = self.expanded.contains(&path);
+        let header = h_flex()
+            .gap_1()
+            .child(
+                Disclosure::new(ElementId::Name(format!("json-{path}").into()), is_expanded)
+                    .on_click(cx.listener({
+                        let path = path.clone();
+                        move |this, _, cx| this.toggle(&path, cx)
+                    })),
+            )
+            .children(key)
+            .child(
+                div()
+                    .when(!is_expanded, |this| this.text_color(Color::Muted.color(cx)))
+                    .child(summary(value)),
+            );
+
+        if !is_expanded {
+            return header.into_any_element();
+        }
+
+        v_flex()
+            .child(header)
+            .child(
+                v_flex().pl_4().children(
+                    children
+                        .into_iter()
+                        .map(|(key, value)| {
+                            let child_path = child_path(&path, &key);
+                            self.render_value(Some(key), value, child_path, cx)
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            )
+            .into_any_element()
+    }
+}
+
+/// Returns the JSON pointer to a key or index of the value at `path`.
+fn child_path(path: &str, key: &str) -> String {
+    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Describes the contents of an object or array, which is shown next to it.
+fn summary(value: &Value) -> String {
+    let (brackets, count, noun) = match value {
+        Value::Object(map) => ("{…}", map.len(), "key"),
+        Value::Array(values) => ("[…]", values.len(), "item"),
+        value => return value.to_string(),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{brackets} {count} {noun}{plural}")
+}
+
+fn scalar_color(value: &Value, cx: &WindowContext) -> Hsla {
+    let syntax = cx.theme().syntax();
+    match value {
+        Value::String(_) => syntax.color("string"),
+        Value::Number(_) => syntax.color("number"),
+        Value::Bool(_) | Value::Null => syntax.color("boolean"),
+        _ => cx.theme().colors().text,
+    }
+}
+
+impl Render for JsonView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let value = self.value.clone();
+        div()
+            .py_1()
+            .font_buffer(cx)
+            .child(self.render_value(None, &value, String::new(), cx))
+    }
+}
+
+impl OutputContent for JsonView {
+    fn clipboard_content(&self, _cx: &WindowContext) -> Option<ClipboardItem> {
+        let json = serde_json::to_string_pretty(&self.value).ok()?;
+        Some(ClipboardItem::new_string(json))
+    }
+
+    fn has_clipboard_content(&self, _cx: &WindowContext) -> bool {
+        true
+    }
+
+    fn has_buffer_content(&self, _cx: &WindowContext) -> bool {
+        true
+    }
+
+    fn buffer_content(&mut self, cx: &mut WindowContext) -> Option<Model<Buffer>> {
+        let json = serde_json::to_string_pretty(&self.value).ok()?;
+        let buffer = cx.new_model(|cx| {
+            let mut buffer =
+                Buffer::local(json, cx).with_language(language::PLAIN_TEXT.clone(), cx);
+            buffer.set_capability(language::Capability::ReadOnly, cx);
+            buffer
+        });
+        Some(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_summary_and_paths() {
+        assert_eq!(summary(&json!({"a": 1, "b": [1, 2]})), "{…} 2 keys");
+        assert_eq!(summary(&json!(["x"])), "[…] 1 item");
+        assert_eq!(summary(&json!([])), "[…] 0 items");
+        assert_eq!(summary(&json!("text")), "\"text\"");
+
+        assert_eq!(child_path("", "a"), "/a");
+        assert_eq!(child_path("/a", "0"), "/a/0");
+        assert_eq!(child_path("/a", "b/c~d"), "/a/b~1c~0d");
+    }
+}
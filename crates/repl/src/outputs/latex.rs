@@ -0,0 +1,867 @@
+//! # LaTeX Output for REPL
+//!
+//! Renders the math sent as `text/latex` by kernels, such as the expressions
+//! and matrices displayed by sympy or `IPython.display.Math`.
+//!
+//! This isn't a TeX engine. The math is parsed into a small tree of the
+//! constructs kernels commonly produce (fractions, sub- and superscripts,
+//! roots, delimiters, matrices, accents and symbols), which is laid out with
+//! elements. Unknown commands are shown as they were written.
+
+use gpui::{AnyElement, ClipboardItem, Div, Model, Pixels};
+use language::Buffer;
+use settings::Settings;
+use theme::ThemeSettings;
+use ui::{div, prelude::*, v_flex, IntoElement, Styled};
+
+use crate::outputs::OutputContent;
+
+/// The size of sub- and superscripts, relative to the text they're attached to.
+const SCRIPT_SCALE: f32 = 0.7;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MathNode {
+    /// A variable, which is set in italics.
+    Identifier(String),
+    /// Text that's set upright, such as numbers, punctuation and function names.
+    Text(String),
+    /// A binary operator or relation, which is spaced from its operands.
+    Operator(String),
+    Space,
+    Group(Vec<MathNode>),
+    Fraction {
+        numerator: Vec<MathNode>,
+        denominator: Vec<MathNode>,
+    },
+    Scripts {
+        base: Box<MathNode>,
+        subscript: Option<Vec<MathNode>>,
+        superscript: Option<Vec<MathNode>>,
+    },
+    Root {
+        degree: Option<Vec<MathNode>>,
+        radicand: Vec<MathNode>,
+    },
+    Accent {
+        accent: String,
+        body: Vec<MathNode>,
+    },
+    Delimited {
+        left: String,
+        right: String,
+        body: Vec<MathNode>,
+    },
+    Matrix {
+        left: String,
+        right: String,
+        rows: Vec<Vec<Vec<MathNode>>>,
+    },
+}
+
+/// Parses the math in a `text/latex` output, which is usually wrapped in `$`
+/// or `$$` delimiters.
+pub fn parse_math(latex: &str) -> Vec<MathNode> {
+    let mut math = latex.trim();
+    for (open, close) in [("$$", "$$"), ("$", "$"), ("\\[", "\\]"), ("\\(", "\\)")] {
+        if let Some(inner) = math
+            .strip_prefix(open)
+            .and_then(|math| math.strip_suffix(close))
+        {
+            math = inner;
+            break;
+        }
+    }
+
+    let mut parser = MathParser {
+        chars: math.chars().collect(),
+        ix: 0,
+    };
+    let mut nodes = Vec::new();
+    loop {
+        nodes.extend(parser.parse_sequence());
+        match parser.next_token() {
+            // Line breaks outside of a matrix are shown as spaces.
+            Some(Token::Command(command)) if command == "\\" => nodes.push(MathNode::Space),
+            Some(token) => nodes.push(MathNode::Text(token.to_string())),
+            None => break,
+        }
+    }
+    nodes
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Char(char),
+    Command(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Char(char) => write!(f, "{char}"),
+            Token::Command(command) => write!(f, "\\{command}"),
+        }
+    }
+}
+
+struct MathParser {
+    chars: Vec<char>,
+    ix: usize,
+}
+
+impl MathParser {
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.ix)
+            .is_some_and(|char| char.is_whitespace())
+        {
+            self.ix += 1;
+        }
+    }
+
+    fn peek_token(&mut self) -> Option<Token> {
+        let ix = self.ix;
+        let token = self.next_token();
+        self.ix = ix;
+        token
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+        let char = *self.chars.get(self.ix)?;
+        self.ix += 1;
+        if char != '\\' {
+            return Some(Token::Char(char));
+        }
+
+        let start = self.ix;
+        while self
+            .chars
+            .get(self.ix)
+            .is_some_and(|char| char.is_ascii_alphabetic())
+        {
+            self.ix += 1;
+        }
+        if self.ix == start && self.ix < self.chars.len() {
+            self.ix += 1;
+        }
+        Some(Token::Command(self.chars[start..self.ix].iter().collect()))
+    }
+
+    /// Parses nodes up to the end of the input or the token that ends the
+    /// current group, which isn't consumed.
+    fn parse_sequence(&mut self) -> Vec<MathNode> {
+        let mut nodes = Vec::new();
+        loop {
+            match self.peek_token() {
+                None | Some(Token::Char('}' | '&')) => break,
+                Some(Token::Command(command))
+                    if matches!(command.as_str(), "\\" | "right" | "end") =>
+                {
+                    break
+                }
+                Some(Token::Char('^' | '_')) => {
+                    let base = nodes.pop().unwrap_or(MathNode::Group(Vec::new()));
+                    nodes.push(self.parse_scripts(base));
+                }
+                Some(_) => {
+                    if let Some(node) = self.parse_atom(false) {
+                        nodes.push(node);
+                    }
+                }
+            }
+        }
+        nodes
+    }
+
+    fn parse_scripts(&mut self, base: MathNode) -> MathNode {
+        let (base, mut subscript, mut superscript) = match base {
+            MathNode::Scripts {
+                base,
+                subscript,
+                superscript,
+            } => (base, subscript, superscript),
+            base => (Box::new(base), None, None),
+        };
+
+        loop {
+            match self.peek_token() {
+                Some(Token::Char('^')) if superscript.is_none() => {
+                    self.next_token();
+                    superscript = Some(self.parse_argument());
+                }
+                Some(Token::Char('_')) if subscript.is_none() => {
+                    self.next_token();
+                    subscript = Some(self.parse_argument());
+                }
+                _ => break,
+            }
+        }
+
+        MathNode::Scripts {
+            base,
+            subscript,
+            superscript,
+        }
+    }
+
+    /// Parses the argument of a command or script, which is either a group
+    /// in braces or a single token.
+    fn parse_argument(&mut self) -> Vec<MathNode> {
+        if self.peek_token() == Some(Token::Char('{')) {
+            self.next_token();
+            let nodes = self.parse_sequence();
+            self.expect('}');
+            nodes
+        } else {
+            self.parse_atom(true).into_iter().collect()
+        }
+    }
+
+    /// Returns the text of an argument in braces without parsing it.
+    fn parse_text_argument(&mut self) -> String {
+        if self.peek_token() != Some(Token::Char('{')) {
+            return self
+                .next_token()
+                .map(|token| token.to_string())
+                .unwrap_or_default();
+        }
+
+        self.next_token();
+        let start = self.ix;
+        let mut depth = 0;
+        while let Some(char) = self.chars.get(self.ix) {
+            match char {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            self.ix += 1;
+        }
+        let text = self.chars[start..self.ix].iter().collect();
+        self.ix = (self.ix + 1).min(self.chars.len());
+        text
+    }
+
+    fn expect(&mut self, expected: char) {
+        if self.peek_token() == Some(Token::Char(expected)) {
+            self.next_token();
+        }
+    }
+
+    fn parse_atom(&mut self, is_argument: bool) -> Option<MathNode> {
+        match self.next_token()? {
+            Token::Char('{') => {
+                let nodes = self.parse_sequence();
+                self.expect('}');
+                Some(MathNode::Group(nodes))
+            }
+            Token::Char(char) if char.is_ascii_digit() => {
+                let mut number = char.to_string();
+                // As in TeX, only the first digit of a number is used as an argument.
+                if !is_argument {
+                    while let Some(char) = self
+                        .chars
+                        .get(self.ix)
+                        .filter(|char| char.is_ascii_digit() || **char == '.')
+                    {
+                        number.push(*char);
+                        self.ix += 1;
+                    }
+                }
+                Some(MathNode::Text(number))
+            }
+            Token::Char(char) if char.is_alphabetic() => {
+                Some(MathNode::Identifier(char.to_string()))
+            }
+            Token::Char(char @ ('+' | '=' | '<' | '>')) => {
+                Some(MathNode::Operator(char.to_string()))
+            }
+            Token::Char('-') => Some(MathNode::Operator("−".into())),
+            Token::Char('*') => Some(MathNode::Operator("∗".into())),
+            Token::Char('\'') => Some(MathNode::Scripts {
+                base: Box::new(MathNode::Group(Vec::new())),
+                subscript: None,
+                superscript: Some(vec![MathNode::Text("′".into())]),
+            }),
+            Token::Char('~') => Some(MathNode::Space),
+            Token::Char(char) => Some(MathNode::Text(char.to_string())),
+            Token::Command(command) => self.parse_command(&command),
+        }
+    }
+
+    fn parse_command(&mut self, command: &str) -> Option<MathNode> {
+        let node = match command {
+            "frac" | "dfrac" | "tfrac" => MathNode::Fraction {
+                numerator: self.parse_argument(),
+                denominator: self.parse_argument(),
+            },
+            "sqrt" => {
+                let degree = if self.peek_token() == Some(Token::Char('[')) {
+                    self.next_token();
+                    let mut degree = Vec::new();
+                    while !matches!(self.peek_token(), None | Some(Token::Char(']'))) {
+                        degree.extend(self.parse_atom(false));
+                    }
+                    self.next_token();
+                    Some(degree)
+                } else {
+                    None
+                };
+                MathNode::Root {
+                    degree,
+                    radicand: self.parse_argument(),
+                }
+            }
+            "left" => {
+                let left = self.parse_delimiter();
+                let body = self.parse_sequence();
+                let right = if self.peek_token() == Some(Token::Command("right".into())) {
+                    self.next_token();
+                    self.parse_delimiter()
+                } else {
+                    String::new()
+                };
+                MathNode::Delimited { left, right, body }
+            }
+            "begin" => {
+                let environment = self.parse_text_argument();
+                if environment == "array" {
+                    // Skip the column specification.
+                    self.parse_text_argument();
+                }
+                let (left, right) = match environment.as_str() {
+                    "pmatrix" => ("(", ")"),
+                    "bmatrix" => ("[", "]"),
+                    "Bmatrix" => ("{", "}"),
+                    "cases" => ("{", ""),
+                    "vmatrix" => ("|", "|"),
+                    "Vmatrix" => ("‖", "‖"),
+                    _ => ("", ""),
+                };
+                MathNode::Matrix {
+                    left: left.into(),
+                    right: right.into(),
+                    rows: self.parse_rows(),
+                }
+            }
+            "text" | "textrm" | "textit" | "textbf" | "mathrm" | "operatorname" | "mbox" => {
+                MathNode::Text(self.parse_text_argument())
+            }
+            "mathbb" => {
+                let text = self.parse_text_argument();
+                MathNode::Text(text.chars().map(double_struck).collect())
+            }
+            "mathbf" | "mathit" | "mathcal" | "mathsf" | "mathtt" | "boldsymbol" => {
+                MathNode::Group(self.parse_argument())
+            }
+            "hat" | "widehat" | "bar" | "overline" | "vec" | "dot" | "ddot" | "tilde"
+            | "widetilde" => MathNode::Accent {
+                accent: match command {
+                    "hat" | "widehat" => "^",
+                    "bar" | "overline" => "¯",
+                    "vec" => "→",
+                    "dot" => "˙",
+                    "ddot" => "¨",
+                    _ => "~",
+                }
+                .into(),
+                body: self.parse_argument(),
+            },
+            "displaystyle" | "textstyle" | "scriptstyle" | "limits" | "nolimits" | "!" => {
+                return None
+            }
+            "," | ":" | ";" | " " | "quad" | "qquad" => MathNode::Space,
+            "{" | "}" | "|" | "%" | "$" | "#" | "&" | "_" => MathNode::Text(command.into()),
+            "sin" | "cos" | "tan" | "cot" | "sec" | "csc" | "arcsin" | "arccos" | "arctan"
+            | "sinh" | "cosh" | "tanh" | "log" | "ln" | "exp" | "lim" | "max" | "min" | "det"
+            | "gcd" | "sup" | "inf" | "arg" | "deg" | "dim" | "ker" | "Pr" | "Re" | "Im" => {
+                MathNode::Text(command.into())
+            }
+            _ => match symbol(command) {
+                Some((symbol, SymbolKind::Identifier)) => MathNode::Identifier(symbol.into()),
+                Some((symbol, SymbolKind::Operator)) => MathNode::Operator(symbol.into()),
+                Some((symbol, SymbolKind::Text)) => MathNode::Text(symbol.into()),
+                None => MathNode::Text(format!("\\{command}")),
+            },
+        };
+        Some(node)
+    }
+
+    fn parse_delimiter(&mut self) -> String {
+        match self.next_token() {
+            Some(Token::Char('.')) | None => String::new(),
+            Some(Token::Char(char)) => char.to_string(),
+            Some(Token::Command(command)) => match command.as_str() {
+                "{" | "lbrace" => "{".into(),
+                "}" | "rbrace" => "}".into(),
+                "|" => "‖".into(),
+                command => symbol(command)
+                    .map(|(symbol, _)| symbol.to_string())
+                    .unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Parses the cells of an environment, which are separated by `&` and
+    /// `\\`, up to and including its `\end`.
+    fn parse_rows(&mut self) -> Vec<Vec<Vec<MathNode>>> {
+        let mut rows = vec![Vec::new()];
+        loop {
+            let cell = self.parse_sequence();
+            rows.last_mut().unwrap().push(cell);
+            match self.next_token() {
+                Some(Token::Char('&')) => {}
+                Some(Token::Command(command)) if command == "\\" => rows.push(Vec::new()),
+                Some(Token::Command(command)) if command == "end" => {
+                    self.parse_text_argument();
+                    break;
+                }
+                // Stop at the end of the input or an unbalanced group.
+                _ => break,
+            }
+        }
+
+        // A trailing `\\` doesn't start another row.
+        if rows.len() > 1 && rows.last() == Some(&vec![Vec::new()]) {
+            rows.pop();
+        }
+        rows
+    }
+}
+
+enum SymbolKind {
+    Identifier,
+    Operator,
+    Text,
+}
+
+fn symbol(command: &str) -> Option<(&'static str, SymbolKind)> {
+    use SymbolKind::*;
+
+    let symbol = match command {
+        "alpha" => ("α", Identifier),
+        "beta" => ("β", Identifier),
+        "gamma" => ("γ", Identifier),
+        "delta" => ("δ", Identifier),
+        "epsilon" => ("ϵ", Identifier),
+        "varepsilon" => ("ε", Identifier),
+        "zeta" => ("ζ", Identifier),
+        "eta" => ("η", Identifier),
+        "theta" => ("θ", Identifier),
+        "vartheta" => ("ϑ", Identifier),
+        "iota" => ("ι", Identifier),
+        "kappa" => ("κ", Identifier),
+        "lambda" => ("λ", Identifier),
+        "mu" => ("μ", Identifier),
+        "nu" => ("ν", Identifier),
+        "xi" => ("ξ", Identifier),
+        "pi" => ("π", Identifier),
+        "rho" => ("ρ", Identifier),
+        "sigma" => ("σ", Identifier),
+        "tau" => ("τ", Identifier),
+        "upsilon" => ("υ", Identifier),
+        "phi" => ("ϕ", Identifier),
+        "varphi" => ("φ", Identifier),
+        "chi" => ("χ", Identifier),
+        "psi" => ("ψ", Identifier),
+        "omega" => ("ω", Identifier),
+        "ell" => ("ℓ", Identifier),
+        "hbar" => ("ℏ", Identifier),
+        "Gamma" => ("Γ", Text),
+        "Delta" => ("Δ", Text),
+        "Theta" => ("Θ", Text),
+        "Lambda" => ("Λ", Text),
+        "Xi" => ("Ξ", Text),
+        "Pi" => ("Π", Text),
+        "Sigma" => ("Σ", Text),
+        "Upsilon" => ("Υ", Text),
+        "Phi" => ("Φ", Text),
+        "Psi" => ("Ψ", Text),
+        "Omega" => ("Ω", Text),
+        "cdot" => ("⋅", Operator),
+        "times" => ("×", Operator),
+        "div" => ("÷", Operator),
+        "pm" => ("±", Operator),
+        "mp" => ("∓", Operator),
+        "circ" => ("∘", Operator),
+        "leq" | "le" => ("≤", Operator),
+        "geq" | "ge" => ("≥", Operator),
+        "neq" | "ne" => ("≠", Operator),
+        "approx" => ("≈", Operator),
+        "equiv" => ("≡", Operator),
+        "sim" => ("∼", Operator),
+        "simeq" => ("≃", Operator),
+        "propto" => ("∝", Operator),
+        "ll" => ("≪", Operator),
+        "gg" => ("≫", Operator),
+        "to" | "rightarrow" => ("→", Operator),
+        "leftarrow" | "gets" => ("←", Operator),
+        "Rightarrow" | "implies" => ("⇒", Operator),
+        "Leftarrow" => ("⇐", Operator),
+        "Leftrightarrow" | "iff" => ("⇔", Operator),
+        "mapsto" => ("↦", Operator),
+        "in" => ("∈", Operator),
+        "notin" => ("∉", Operator),
+        "ni" => ("∋", Operator),
+        "subset" => ("⊂", Operator),
+        "subseteq" => ("⊆", Operator),
+        "supset" => ("⊃", Operator),
+        "supseteq" => ("⊇", Operator),
+        "cup" => ("∪", Operator),
+        "cap" => ("∩", Operator),
+        "setminus" => ("∖", Operator),
+        "wedge" | "land" => ("∧", Operator),
+        "vee" | "lor" => ("∨", Operator),
+        "otimes" => ("⊗", Operator),
+        "oplus" => ("⊕", Operator),
+        "mid" => ("∣", Operator),
+        "neg" | "lnot" => ("¬", Text),
+        "forall" => ("∀", Text),
+        "exists" => ("∃", Text),
+        "emptyset" | "varnothing" => ("∅", Text),
+        "infty" => ("∞", Text),
+        "partial" => ("∂", Text),
+        "nabla" => ("∇", Text),
+        "sum" => ("∑", Text),
+        "prod" => ("∏", Text),
+        "coprod" => ("∐", Text),
+        "int" => ("∫", Text),
+        "iint" => ("∬", Text),
+        "oint" => ("∮", Text),
+        "ldots" | "dots" => ("…", Text),
+        "cdots" => ("⋯", Text),
+        "vdots" => ("⋮", Text),
+        "ddots" => ("⋱", Text),
+        "prime" => ("′", Text),
+        "langle" => ("⟨", Text),
+        "rangle" => ("⟩", Text),
+        "lfloor" => ("⌊", Text),
+        "rfloor" => ("⌋", Text),
+        "lceil" => ("⌈", Text),
+        "rceil" => ("⌉", Text),
+        "lvert" | "rvert" | "vert" => ("|", Text),
+        "lVert" | "rVert" | "Vert" => ("‖", Text),
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+fn double_struck(char: char) -> char {
+    match char {
+        'C' => 'ℂ',
+        'H' => 'ℍ',
+        'N' => 'ℕ',
+        'P' => 'ℙ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'Z' => 'ℤ',
+        char => char,
+    }
+}
+
+/// Returns the approximate height of the nodes, in lines, which is used to
+/// size the delimiters around them.
+fn height(nodes: &[MathNode]) -> f32 {
+    nodes
+        .iter()
+        .map(|node| match node {
+            MathNode::Group(nodes) => height(nodes),
+            MathNode::Fraction {
+                numerator,
+                denominator,
+            } => height(numerator) + height(denominator),
+            MathNode::Scripts {
+                base,
+                subscript,
+                superscript,
+            } => {
+                let scripts = subscript.is_some() as u8 + superscript.is_some() as u8;
+                height(std::slice::from_ref(base.as_ref())).max(1. + 0.5 * scripts as f32)
+            }
+            MathNode::Root { radicand, .. } => height(radicand),
+            MathNode::Accent { body, .. } => height(body) + 0.3,
+            MathNode::Delimited { body, .. } => height(body),
+            MathNode::Matrix { rows, .. } => rows
+                .iter()
+                .map(|row| row.iter().map(|cell| height(cell)).fold(1., f32::max))
+                .sum(),
+            _ => 1.,
+        })
+        .fold(1., f32::max)
+}
+
+pub struct LatexView {
+    raw_text: String,
+    nodes: Vec<MathNode>,
+}
+
+impl LatexView {
+    pub fn from(text: &str) -> Self {
+        Self {
+            raw_text: text.to_string(),
+            nodes: parse_math(text),
+        }
+    }
+}
+
+fn render_nodes(nodes: &[MathNode], font_size: Pixels, cx: &WindowContext) -> Div {
+    h_flex()
+        .items_center()
+        .children(nodes.iter().map(|node| render_node(node, font_size, cx)))
+}
+
+fn render_node(node: &MathNode, font_size: Pixels, cx: &WindowContext) -> AnyElement {
+    let text = |text: &str| div().text_size(font_size).child(text.to_string());
+    let script_size = font_size * SCRIPT_SCALE;
+
+    match node {
+        MathNode::Identifier(identifier) => text(identifier).italic().into_any_element(),
+        MathNode::Text(content) => text(content).into_any_element(),
+        MathNode::Operator(operator) => text(operator).px(font_size * 0.25).into_any_element(),
+        MathNode::Space => div().w(font_size * 0.25).into_any_element(),
+        MathNode::Group(nodes) => render_nodes(nodes, font_size, cx).into_any_element(),
+        MathNode::Fraction {
+            numerator,
+            denominator,
+        } => v_flex()
+            .items_center()
+            .px(font_size * 0.15)
+            .child(render_nodes(numerator, font_size * 0.9, cx))
+            .child(div().w_full().h_px().bg(cx.theme().colors().text))
+            .child(render_nodes(denominator, font_size * 0.9, cx))
+            .into_any_element(),
+        MathNode::Scripts {
+            base,
+            subscript,
+            superscript,
+        } => {
+            // Scripts without the other are offset by half a line, so
+            // that they're raised or lowered relative to the base.
+            let spacer = || div().h(font_size * 0.5);
+            let scripts = match (superscript, subscript) {
+                (Some(superscript), Some(subscript)) => v_flex()
+                    .child(render_nodes(superscript, script_size, cx))
+                    .child(render_nodes(subscript, script_size, cx)),
+                (Some(superscript), None) => v_flex()
+                    .child(render_nodes(superscript, script_size, cx))
+                    .child(spacer()),
+                (None, Some(subscript)) => {
+                    v_flex()
+                        .child(spacer())
+                        .child(render_nodes(subscript, script_size, cx))
+                }
+                (None, None) => v_flex(),
+            };
+            h_flex()
+                .items_center()
+                .child(render_node(base, font_size, cx))
+                .child(scripts)
+                .into_any_element()
+        }
+        MathNode::Root { degree, radicand } => h_flex()
+            .items_end()
+            .children(
+                degree
+                    .as_ref()
+                    .map(|degree| render_nodes(degree, script_size, cx).mb(font_size * 0.5)),
+            )
+            .child(div().text_size(font_size * height(radicand)).child("√"))
+            .child(
+                render_nodes(radicand, font_size, cx)
+                    .border_t_1()
+                    .border_color(cx.theme().colors().text),
+            )
+            .into_any_element(),
+        MathNode::Accent { accent, body } => v_flex()
+            .items_center()
+            .child(
+                div()
+                    .h(font_size * 0.4)
+                    .text_size(script_size)
+                    .child(accent.clone()),
+            )
+            .child(render_nodes(body, font_size, cx))
+            .into_any_element(),
+        MathNode::Delimited { left, right, body } => {
+            let delimiter_size = font_size * height(body);
+            h_flex()
+                .items_center()
+                .child(div().text_size(delimiter_size).child(left.clone()))
+                .child(render_nodes(body, font_size, cx))
+                .child(div().text_size(delimiter_size).child(right.clone()))
+                .into_any_element()
+        }
+        MathNode::Matrix { left, right, rows } => {
+            let delimiter_size = font_size * height(std::slice::from_ref(node));
+            // The cases of a `cases` environment are aligned to the left.
+            let is_cases = !left.is_empty() && right.is_empty();
+            let column_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+            let columns = (0..column_count).map(|column| {
+                v_flex()
+                    .when(is_cases, |this| this.items_start())
+                    .when(!is_cases, |this| this.items_center())
+                    .children(rows.iter().map(|row| {
+                        let cell = row.get(column).map(Vec::as_slice).unwrap_or_default();
+                        render_nodes(cell, font_size, cx).min_h(font_size * 1.5)
+                    }))
+            });
+            h_flex()
+                .items_center()
+                .child(div().text_size(delimiter_size).child(left.clone()))
+                .child(
+                    h_flex()
+                        .gap(font_size)
+                        .px(font_size * 0.25)
+                        .children(columns),
+                )
+                .child(div().text_size(delimiter_size).child(right.clone()))
+                .into_any_element()
+        }
+    }
+}
+
+impl Render for LatexView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let font_size = ThemeSettings::get_global(cx).buffer_font_size(cx) * 1.2;
+
+        div()
+            .id("latex")
+            .py_2()
+            .overflow_x_scroll()
+            .child(render_nodes(&self.nodes, font_size, cx))
+    }
+}
+
+impl OutputContent for LatexView {
+    fn clipboard_content(&self, _cx: &WindowContext) -> Option<ClipboardItem> {
+        Some(ClipboardItem::new_string(self.raw_text.clone()))
+    }
+
+    fn has_clipboard_content(&self, _cx: &WindowContext) -> bool {
+        true
+    }
+
+    fn has_buffer_content(&self, _cx: &WindowContext) -> bool {
+        true
+    }
+
+    fn buffer_content(&mut self, cx: &mut WindowContext) -> Option<Model<Buffer>> {
+        let buffer = cx.new_model(|cx| {
+            let mut buffer = Buffer::local(self.raw_text.clone(), cx)
+                .with_language(language::PLAIN_TEXT.clone(), cx);
+            buffer.set_capability(language::Capability::ReadOnly, cx);
+            buffer
+        });
+        Some(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use MathNode::*;
+
+    fn identifier(text: &str) -> MathNode {
+        Identifier(text.into())
+    }
+
+    fn text(text: &str) -> MathNode {
+        Text(text.into())
+    }
+
+    fn operator(text: &str) -> MathNode {
+        Operator(text.into())
+    }
+
+    #[test]
+    fn test_parse_sympy_expressions() {
+        assert_eq!(
+            parse_math(r"$\displaystyle x^{2} + 2 x + 1$"),
+            vec![
+                Scripts {
+                    base: Box::new(identifier("x")),
+                    subscript: None,
+                    superscript: Some(vec![text("2")]),
+                },
+                operator("+"),
+                text("2"),
+                identifier("x"),
+                operator("+"),
+                text("1"),
+            ]
+        );
+
+        assert_eq!(
+            parse_math(r"$\displaystyle \frac{\sqrt{\pi}}{2} \leq \sin{\left(\alpha_1 \right)}$"),
+            vec![
+                Fraction {
+                    numerator: vec![Root {
+                        degree: None,
+                        radicand: vec![identifier("π")],
+                    }],
+                    denominator: vec![text("2")],
+                },
+                operator("≤"),
+                text("sin"),
+                Group(vec![Delimited {
+                    left: "(".into(),
+                    right: ")".into(),
+                    body: vec![Scripts {
+                        base: Box::new(identifier("α")),
+                        subscript: Some(vec![text("1")]),
+                        superscript: None,
+                    }],
+                }]),
+            ]
+        );
+
+        // Scripts take a single digit unless they're grouped, and unknown
+        // commands are kept as they were written.
+        assert_eq!(
+            parse_math(r"$$e^12 \foo$$"),
+            vec![
+                Scripts {
+                    base: Box::new(identifier("e")),
+                    subscript: None,
+                    superscript: Some(vec![text("1")]),
+                },
+                text("2"),
+                text("\\foo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_matrix() {
+        assert_eq!(
+            parse_math(
+                r"$\displaystyle \left[\begin{matrix}1 & -x\\0 & \mathbb{R}\end{matrix}\right]$"
+            ),
+            vec![Delimited {
+                left: "[".into(),
+                right: "]".into(),
+                body: vec![Matrix {
+                    left: "".into(),
+                    right: "".into(),
+                    rows: vec![
+                        vec![vec![text("1")], vec![operator("−"), identifier("x")]],
+                        vec![vec![text("0")], vec![text("ℝ")]],
+                    ],
+                }],
+            }]
+        );
+
+        assert_eq!(
+            parse_math(r"\begin{pmatrix} a \\ b \\ \end{pmatrix}"),
+            vec![Matrix {
+                left: "(".into(),
+                right: ")".into(),
+                rows: vec![vec![vec![identifier("a")]], vec![vec![identifier("b")]]],
+            }]
+        );
+    }
+}
@@ -1,11 +1,14 @@
 mod components;
+mod inspection;
 mod jupyter_settings;
 mod kernels;
+mod notebook;
 mod outputs;
 mod repl_editor;
 mod repl_sessions_ui;
 mod repl_store;
 mod session;
+mod variables_panel;
 
 use std::{sync::Arc, time::Duration};
 
@@ -15,14 +18,17 @@ use project::Fs;
 pub use runtimelib::ExecutionState;
 use settings::Settings as _;
 
-pub use crate::jupyter_settings::JupyterSettings;
-pub use crate::kernels::{Kernel, KernelSpecification, KernelStatus};
+pub use crate::inspection::Variable;
+pub use crate::jupyter_settings::{JupyterServer, JupyterSettings};
+pub use crate::kernels::{Kernel, KernelSource, KernelSpecification, KernelStatus};
+pub use crate::notebook::{NotebookEditor, NotebookItem};
 pub use crate::repl_editor::*;
 pub use crate::repl_sessions_ui::{
     ClearOutputs, Interrupt, ReplSessionsPage, Restart, Run, Sessions, Shutdown,
 };
 use crate::repl_store::ReplStore;
 pub use crate::session::Session;
+pub use crate::variables_panel::{RefreshVariables, ToggleVariablesPanel, VariablesPanel};
 use client::telemetry::Telemetry;
 
 pub fn init(fs: Arc<dyn Fs>, telemetry: Arc<Telemetry>, cx: &mut AppContext) {
@@ -30,6 +36,8 @@ pub fn init(fs: Arc<dyn Fs>, telemetry: Arc<Telemetry>, cx: &mut AppContext) {
     JupyterSettings::register(cx);
     ::editor::init_settings(cx);
     repl_sessions_ui::init(cx);
+    notebook::init(cx);
+    variables_panel::init(cx);
     ReplStore::init(fs, telemetry, cx);
 }
 
@@ -275,12 +275,12 @@ impl Render for ReplSessionsPage {
                     .children(specs.into_iter().map(|spec| {
                         let is_choice = if let Some(chosen_kernel) = &chosen_kernel {
                             chosen_kernel.name.to_lowercase() == spec.name.to_lowercase()
-                                && chosen_kernel.path == spec.path
+                                && chosen_kernel.location() == spec.location()
                         } else {
                             false
                         };
 
-                        let path = SharedString::from(spec.path.to_string_lossy().to_string());
+                        let path = SharedString::from(spec.location());
 
                         ListItem::new(path.clone())
                             .selectable(false)
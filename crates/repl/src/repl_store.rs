@@ -10,8 +10,10 @@ use gpui::{
 use project::Fs;
 use settings::{Settings, SettingsStore};
 
-use crate::kernels::kernel_specifications;
-use crate::{JupyterSettings, KernelSpecification, Session};
+use crate::kernels::{
+    jupyter_server_kernel_specifications, kernel_specifications, running_kernel_specifications,
+};
+use crate::{JupyterServer, JupyterSettings, KernelSpecification, Session};
 
 struct GlobalReplStore(Model<ReplStore>);
 
@@ -22,6 +24,7 @@ pub struct ReplStore {
     enabled: bool,
     sessions: HashMap<EntityId, View<Session>>,
     kernel_specifications: Vec<KernelSpecification>,
+    servers: Vec<JupyterServer>,
     telemetry: Arc<Telemetry>,
     _subscriptions: Vec<Subscription>,
 }
@@ -46,6 +49,12 @@ impl ReplStore {
     pub fn new(fs: Arc<dyn Fs>, telemetry: Arc<Telemetry>, cx: &mut ModelContext<Self>) -> Self {
         let subscriptions = vec![cx.observe_global::<SettingsStore>(move |this, cx| {
             this.set_enabled(JupyterSettings::enabled(cx), cx);
+
+            let servers = &JupyterSettings::get_global(cx).servers;
+            if &this.servers != servers {
+                this.servers = servers.clone();
+                this.refresh_kernelspecs(cx).detach_and_log_err(cx);
+            }
         })];
 
         let this = Self {
@@ -54,6 +63,7 @@ impl ReplStore {
             enabled: JupyterSettings::enabled(cx),
             sessions: HashMap::default(),
             kernel_specifications: Vec::new(),
+            servers: JupyterSettings::get_global(cx).servers.clone(),
             _subscriptions: subscriptions,
         };
         this.on_enabled_changed(cx);
@@ -106,9 +116,35 @@ impl ReplStore {
     }
 
     pub fn refresh_kernelspecs(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
-        let kernel_specifications = kernel_specifications(self.fs.clone());
+        let fs = self.fs.clone();
+        let servers = self.servers.clone();
+        let http_client = cx.http_client();
         cx.spawn(|this, mut cx| async move {
-            let kernel_specifications = kernel_specifications.await?;
+            let mut kernel_specifications = kernel_specifications(fs.clone()).await?;
+
+            let running_kernel_specifications =
+                running_kernel_specifications(fs, &kernel_specifications).await;
+            // The runtime directory doesn't exist until a kernel has been started.
+            if let Ok(running_kernel_specifications) = running_kernel_specifications {
+                kernel_specifications.extend(running_kernel_specifications);
+            }
+
+            let server_kernel_specifications =
+                futures::future::join_all(servers.iter().map(|server| {
+                    jupyter_server_kernel_specifications(server, http_client.clone())
+                }))
+                .await;
+            for (server, specifications) in servers.iter().zip(server_kernel_specifications) {
+                match specifications {
+                    Ok(specifications) => kernel_specifications.extend(specifications),
+                    Err(err) => {
+                        log::error!(
+                            "Error listing kernels on Jupyter server {}: {err:?}",
+                            server.url
+                        )
+                    }
+                }
+            }
 
             this.update(&mut cx, |this, cx| {
                 this.kernel_specifications = kernel_specifications;
@@ -150,11 +186,18 @@ impl ReplStore {
         self.sessions.get(&entity_id)
     }
 
-    pub fn insert_session(&mut self, entity_id: EntityId, session: View<Session>) {
+    pub fn insert_session(
+        &mut self,
+        entity_id: EntityId,
+        session: View<Session>,
+        cx: &mut ModelContext<Self>,
+    ) {
         self.sessions.insert(entity_id, session);
+        cx.notify();
     }
 
-    pub fn remove_session(&mut self, entity_id: EntityId) {
+    pub fn remove_session(&mut self, entity_id: EntityId, cx: &mut ModelContext<Self>) {
         self.sessions.remove(&entity_id);
+        cx.notify();
     }
 }
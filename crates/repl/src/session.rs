@@ -1,9 +1,11 @@
 use crate::components::KernelListItem;
+use crate::inspection::{self, KernelSemanticsProvider, Variable};
 use crate::KernelStatus;
 use crate::{
-    kernels::{Kernel, KernelSpecification, RunningKernel},
+    kernels::{Kernel, KernelSource, KernelSpecification, RunningKernel},
     outputs::{ExecutionStatus, ExecutionView},
 };
+use anyhow::{anyhow, Result};
 use client::telemetry::Telemetry;
 use collections::{HashMap, HashSet};
 use editor::{
@@ -12,8 +14,9 @@ use editor::{
         RenderBlock,
     },
     scroll::Autoscroll,
-    Anchor, AnchorRangeExt as _, Editor, MultiBuffer, ToPoint,
+    Anchor, AnchorRangeExt as _, Editor, MultiBuffer, SemanticsProvider, ToPoint,
 };
+use futures::channel::oneshot;
 use futures::io::BufReader;
 use futures::{AsyncBufReadExt as _, FutureExt as _, StreamExt as _};
 use gpui::{
@@ -25,15 +28,21 @@ use runtimelib::{
     ExecuteRequest, ExecutionState, InterruptRequest, JupyterMessage, JupyterMessageContent,
     ShutdownRequest,
 };
-use std::{env::temp_dir, ops::Range, sync::Arc, time::Duration};
+use serde_json::{json, Value};
+use std::{env::temp_dir, ops::Range, rc::Rc, sync::Arc, time::Duration};
 use theme::ActiveTheme;
 use ui::{prelude::*, IconButtonShape, Tooltip};
+use util::ResultExt as _;
 
 pub struct Session {
     fs: Arc<dyn Fs>,
     editor: WeakView<Editor>,
     pub kernel: Kernel,
     blocks: HashMap<String, EditorBlock>,
+    pending_requests: HashMap<String, PendingRequest>,
+    /// The editor's semantics provider from before the session wrapped it to
+    /// add the kernel's documentation to hovers.
+    semantics_provider: Option<Rc<dyn SemanticsProvider>>,
     messaging_task: Option<Task<()>>,
     process_status_task: Option<Task<()>>,
     pub kernel_specification: KernelSpecification,
@@ -48,6 +57,66 @@ struct EditorBlock {
     execution_view: View<ExecutionView>,
 }
 
+/// How long to wait for the kernel to reply to a request made by Zed, which
+/// shouldn't keep the user waiting on a kernel that is busy or unresponsive.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request made of the kernel by Zed rather than the user, such as to
+/// inspect a name, whose reply is returned rather than shown in the editor.
+struct PendingRequest {
+    reply: Option<Value>,
+    stdout: String,
+    idle: bool,
+    reply_tx: oneshot::Sender<KernelReply>,
+}
+
+struct KernelReply {
+    /// The content of the reply, such as an `inspect_reply`.
+    content: Value,
+    /// The text printed to stdout while handling the request.
+    stdout: String,
+}
+
+impl PendingRequest {
+    /// Records a message sent by the kernel in response to the request,
+    /// returning whether the request has been handled.
+    ///
+    /// The reply and the request's outputs arrive on different channels, so
+    /// the request is only handled once the reply has arrived and the kernel
+    /// has gone idle, which it does after sending all of the outputs.
+    fn handle_message(&mut self, message: &JupyterMessage) -> bool {
+        let Some(content) = serde_json::to_value(&message.content).log_err() else {
+            return false;
+        };
+
+        match message.header.msg_type.as_str() {
+            "stream" => {
+                if content["name"] == "stdout" {
+                    if let Some(text) = content["text"].as_str() {
+                        self.stdout.push_str(text);
+                    }
+                }
+            }
+            "status" => self.idle |= content["execution_state"] == "idle",
+            message_type if message_type.ends_with("_reply") => self.reply = Some(content),
+            _ => {}
+        }
+
+        self.idle && self.reply.is_some()
+    }
+
+    fn finish(self) {
+        if let Some(content) = self.reply {
+            self.reply_tx
+                .send(KernelReply {
+                    content,
+                    stdout: self.stdout,
+                })
+                .ok();
+        }
+    }
+}
+
 type CloseBlockFn =
     Arc<dyn for<'a> Fn(CustomBlockId, &'a mut WindowContext) + Send + Sync + 'static>;
 
@@ -207,6 +276,20 @@ impl Session {
             None => Subscription::new(|| {}),
         };
 
+        let semantics_provider = editor.upgrade().and_then(|editor| {
+            let session = cx.view().downgrade();
+            let window = cx.window_handle();
+            editor.update(cx, |editor, _| {
+                let semantics_provider = editor.semantics_provider();
+                editor.set_semantics_provider(Some(Rc::new(KernelSemanticsProvider::new(
+                    semantics_provider.clone(),
+                    session,
+                    window,
+                ))));
+                semantics_provider
+            })
+        });
+
         let mut session = Self {
             fs,
             editor,
@@ -214,6 +297,8 @@ impl Session {
             messaging_task: None,
             process_status_task: None,
             blocks: HashMap::default(),
+            pending_requests: HashMap::default(),
+            semantics_provider,
             kernel_specification,
             _buffer_subscription: subscription,
             telemetry,
@@ -253,7 +338,10 @@ impl Session {
                 match kernel {
                     Ok((mut kernel, mut messages_rx)) => {
                         this.update(&mut cx, |session, cx| {
-                            let stderr = kernel.process.stderr.take();
+                            let stderr = kernel
+                                .process
+                                .as_mut()
+                                .and_then(|process| process.stderr.take());
 
                             cx.spawn(|_session, mut _cx| async move {
                                 if stderr.is_none() {
@@ -268,7 +356,10 @@ impl Session {
                             })
                             .detach();
 
-                            let stdout = kernel.process.stdout.take();
+                            let stdout = kernel
+                                .process
+                                .as_mut()
+                                .and_then(|process| process.stdout.take());
 
                             cx.spawn(|_session, mut _cx| async move {
                                 if stdout.is_none() {
@@ -282,10 +373,16 @@ impl Session {
                             })
                             .detach();
 
-                            let status = kernel.process.status();
+                            let status = kernel.process.as_mut().map(|process| process.status());
                             session.kernel(Kernel::RunningKernel(kernel), cx);
 
                             let process_status_task = cx.spawn(|session, mut cx| async move {
+                                // Kernels that Zed didn't launch, such as ones on a Jupyter
+                                // server, have no process to watch.
+                                let Some(status) = status else {
+                                    return;
+                                };
+
                                 let error_message = match status.await {
                                     Ok(status) => {
                                         if status.success() {
@@ -414,6 +511,114 @@ impl Session {
         anyhow::Ok(())
     }
 
+    /// Sends a request to the kernel on Zed's behalf, returning its reply.
+    fn request(
+        &mut self,
+        message: JupyterMessage,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<KernelReply>> {
+        let Kernel::RunningKernel(kernel) = &mut self.kernel else {
+            return Task::ready(Err(anyhow!("the kernel is not running")));
+        };
+
+        let message_id = message.header.msg_id.clone();
+        if let Err(error) = kernel.request_tx.try_send(message) {
+            return Task::ready(Err(anyhow!("failed to send request to kernel: {error}")));
+        }
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.pending_requests.insert(
+            message_id.clone(),
+            PendingRequest {
+                reply: None,
+                stdout: String::new(),
+                idle: false,
+                reply_tx,
+            },
+        );
+
+        let mut timeout = cx.background_executor().timer(REQUEST_TIMEOUT).fuse();
+        cx.spawn(|this, mut cx| async move {
+            futures::select_biased! {
+                reply = reply_rx.fuse() => {
+                    reply.map_err(|_| anyhow!("the kernel stopped before replying"))
+                }
+                () = timeout => {
+                    this.update(&mut cx, |session, _| {
+                        session.pending_requests.remove(&message_id);
+                    })
+                    .ok();
+                    Err(anyhow!("timed out waiting for the kernel to reply"))
+                }
+            }
+        })
+    }
+
+    /// Asks the kernel for the documentation of the name at `cursor_position`,
+    /// counted in characters, in `code`.
+    ///
+    /// Nothing is returned while the kernel is busy, as the request would have
+    /// to wait for the code it's running.
+    pub fn inspect(
+        &mut self,
+        code: String,
+        cursor_position: usize,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Option<String>>> {
+        match &self.kernel {
+            Kernel::RunningKernel(kernel)
+                if matches!(kernel.execution_state, ExecutionState::Idle) => {}
+            _ => return Task::ready(Ok(None)),
+        }
+
+        let content = json!({
+            "code": code,
+            "cursor_pos": cursor_position,
+            "detail_level": 0,
+        });
+        let message = match JupyterMessageContent::from_type_and_content("inspect_request", content)
+        {
+            Ok(content) => JupyterMessage::new(content, None),
+            Err(error) => return Task::ready(Err(error.into())),
+        };
+
+        let reply = self.request(message, cx);
+        cx.background_executor().spawn(async move {
+            let reply = reply.await?;
+            Ok(inspection::inspect_reply_text(&reply.content))
+        })
+    }
+
+    /// Lists the variables defined in the kernel, by running code that prints
+    /// them without adding to the kernel's history.
+    pub fn variables(&mut self, cx: &mut ViewContext<Self>) -> Task<Result<Vec<Variable>>> {
+        let language = &self.kernel_specification.kernelspec.language;
+        let Some(code) = inspection::variables_code(language) else {
+            return Task::ready(Err(anyhow!(
+                "Variables can't be listed for {language} kernels"
+            )));
+        };
+
+        let message: JupyterMessage = ExecuteRequest {
+            code: code.to_string(),
+            store_history: false,
+            ..ExecuteRequest::default()
+        }
+        .into();
+
+        let reply = self.request(message, cx);
+        cx.background_executor().spawn(async move {
+            let reply = reply.await?;
+            if reply.content["status"] == "error" {
+                return Err(anyhow!(
+                    "failed to list variables: {}",
+                    reply.content["evalue"].as_str().unwrap_or_default()
+                ));
+            }
+            inspection::parse_variables(&reply.stdout)
+        })
+    }
+
     pub fn clear_outputs(&mut self, cx: &mut ViewContext<Self>) {
         let blocks_to_remove: HashSet<CustomBlockId> =
             self.blocks.values().map(|block| block.block_id).collect();
@@ -561,6 +766,12 @@ impl Session {
                     cx.entity_id().to_string(),
                 );
 
+                if matches!(status.execution_state, ExecutionState::Idle)
+                    && self.blocks.contains_key(parent_message_id)
+                {
+                    cx.emit(SessionEvent::ExecutionFinished);
+                }
+
                 cx.notify();
             }
             JupyterMessageContent::KernelInfoReply(reply) => {
@@ -584,6 +795,15 @@ impl Session {
             _ => {}
         }
 
+        if let Some(request) = self.pending_requests.get_mut(parent_message_id) {
+            if request.handle_message(message) {
+                if let Some(request) = self.pending_requests.remove(parent_message_id) {
+                    request.finish();
+                }
+            }
+            return;
+        }
+
         if let Some(block) = self.blocks.get_mut(parent_message_id) {
             block.handle_message(message, cx);
         }
@@ -604,8 +824,18 @@ impl Session {
     pub fn kernel(&mut self, kernel: Kernel, cx: &mut ViewContext<Self>) {
         if let Kernel::Shutdown = kernel {
             cx.emit(SessionEvent::Shutdown(self.editor.clone()));
+
+            let semantics_provider = self.semantics_provider.take();
+            self.editor
+                .update(cx, |editor, _| {
+                    editor.set_semantics_provider(semantics_provider)
+                })
+                .ok();
         }
 
+        // Requests can only be answered by the kernel they were sent to.
+        self.pending_requests.clear();
+
         let kernel_status = KernelStatus::from(&kernel).to_string();
         let kernel_language = self.kernel_specification.kernelspec.language.clone();
 
@@ -622,6 +852,17 @@ impl Session {
         let kernel = std::mem::replace(&mut self.kernel, Kernel::ShuttingDown);
 
         match kernel {
+            Kernel::RunningKernel(kernel)
+                if self.kernel_specification.source == KernelSource::Running =>
+            {
+                // Zed didn't start this kernel, so it only disconnects from it by dropping its
+                // channels, leaving the kernel running.
+                drop(kernel);
+                self.messaging_task.take();
+                self.process_status_task.take();
+                self.clear_outputs(cx);
+                self.kernel(Kernel::Shutdown, cx);
+            }
             Kernel::RunningKernel(mut kernel) => {
                 let mut request_tx = kernel.request_tx.clone();
 
@@ -638,7 +879,7 @@ impl Session {
                     })
                     .ok();
 
-                    kernel.process.kill().ok();
+                    kernel.kill();
 
                     this.update(&mut cx, |session, cx| {
                         session.clear_outputs(cx);
@@ -659,6 +900,34 @@ impl Session {
     }
 
     pub fn restart(&mut self, cx: &mut ViewContext<Self>) {
+        // Restarting a kernel that Zed only connected to affects everyone else using it, so
+        // the user has to confirm it.
+        if self.kernel_specification.source == KernelSource::Running
+            && matches!(self.kernel, Kernel::RunningKernel(_))
+        {
+            let answer = cx.prompt(
+                gpui::PromptLevel::Warning,
+                "Restart a kernel that Zed didn't start?",
+                Some(
+                    "This kernel was already running when Zed connected to it. Restarting it \
+                    clears its state for every client connected to it.",
+                ),
+                &["Restart", "Cancel"],
+            );
+            cx.spawn(|this, mut cx| async move {
+                if answer.await == Ok(0) {
+                    this.update(&mut cx, |session, cx| session.restart_kernel(cx))
+                        .ok();
+                }
+            })
+            .detach();
+            return;
+        }
+
+        self.restart_kernel(cx);
+    }
+
+    fn restart_kernel(&mut self, cx: &mut ViewContext<Self>) {
         let kernel = std::mem::replace(&mut self.kernel, Kernel::Restarting);
 
         match kernel {
@@ -684,7 +953,7 @@ impl Session {
                     cx.background_executor().timer(Duration::from_secs(1)).await;
 
                     // Force kill the kernel if it hasn't shut down
-                    kernel.process.kill().ok();
+                    kernel.kill();
 
                     // Start a new kernel
                     this.update(&mut cx, |session, cx| {
@@ -710,6 +979,8 @@ impl Session {
 
 pub enum SessionEvent {
     Shutdown(WeakView<Editor>),
+    /// Code run in the editor has finished running.
+    ExecutionFinished,
 }
 
 impl EventEmitter<SessionEvent> for Session {}
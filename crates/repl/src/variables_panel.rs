@@ -0,0 +1,347 @@
+//! # Variables Panel
+//!
+//! Lists the variables defined in the kernel of the active editor's REPL
+//! session, with their types, shapes and values. The list is refreshed when
+//! code run in the editor finishes, while the panel is open.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use editor::Editor;
+use gpui::{
+    actions, AppContext, AsyncWindowContext, EventEmitter, FocusHandle, FocusableView, Pixels,
+    Subscription, Task, View, WeakView,
+};
+use project::Fs;
+use settings::Settings as _;
+use ui::{prelude::*, Tooltip};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+use crate::inspection::Variable;
+use crate::jupyter_settings::VariablesPanelSettings;
+use crate::kernels::{Kernel, KernelStatus};
+use crate::repl_store::ReplStore;
+use crate::session::{Session, SessionEvent};
+
+actions!(repl, [ToggleVariablesPanel, RefreshVariables]);
+
+pub fn init(cx: &mut AppContext) {
+    VariablesPanelSettings::register(cx);
+
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ToggleVariablesPanel, cx| {
+            workspace.toggle_panel_focus::<VariablesPanel>(cx);
+        });
+    })
+    .detach();
+}
+
+pub struct VariablesPanel {
+    fs: Arc<dyn Fs>,
+    workspace: WeakView<Workspace>,
+    focus_handle: FocusHandle,
+    width: Option<Pixels>,
+    active: bool,
+    session: Option<View<Session>>,
+    variables: Vec<Variable>,
+    error: Option<SharedString>,
+    loading: bool,
+    refresh_task: Task<()>,
+    _session_subscriptions: Vec<Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl VariablesPanel {
+    pub fn load(
+        workspace: WeakView<Workspace>,
+        cx: AsyncWindowContext,
+    ) -> Task<Result<View<Self>>> {
+        cx.spawn(|mut cx| async move {
+            workspace.update(&mut cx, |workspace, cx| Self::new(workspace, cx))
+        })
+    }
+
+    pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let fs = workspace.app_state().fs.clone();
+        let workspace_handle = cx.view().clone();
+
+        cx.new_view(|cx| {
+            let subscriptions = vec![
+                cx.subscribe(&workspace_handle, |this, _, event, cx| {
+                    if let workspace::Event::ActiveItemChanged = event {
+                        this.update_session(cx);
+                    }
+                }),
+                cx.observe(&ReplStore::global(cx), |this, _, cx| {
+                    this.update_session(cx)
+                }),
+            ];
+
+            let mut this = Self {
+                fs,
+                workspace: workspace_handle.downgrade(),
+                focus_handle: cx.focus_handle(),
+                width: None,
+                active: false,
+                session: None,
+                variables: Vec::new(),
+                error: None,
+                loading: false,
+                refresh_task: Task::ready(()),
+                _session_subscriptions: Vec::new(),
+                _subscriptions: subscriptions,
+            };
+            this.update_session(cx);
+            this
+        })
+    }
+
+    /// Shows the variables of the active editor's session, if it has one.
+    fn update_session(&mut self, cx: &mut ViewContext<Self>) {
+        let session = self.workspace.upgrade().and_then(|workspace| {
+            let editor = workspace.read(cx).active_item(cx)?.act_as::<Editor>(cx)?;
+            ReplStore::global(cx)
+                .read(cx)
+                .get_session(editor.entity_id())
+                .cloned()
+        });
+
+        if session.as_ref().map(|session| session.entity_id())
+            == self.session.as_ref().map(|session| session.entity_id())
+        {
+            return;
+        }
+
+        self._session_subscriptions = session
+            .iter()
+            .flat_map(|session| {
+                [
+                    cx.observe(session, |_, _, cx| cx.notify()),
+                    cx.subscribe(session, |this, _, event, cx| {
+                        if let SessionEvent::ExecutionFinished = event {
+                            this.refresh(cx);
+                        }
+                    }),
+                ]
+            })
+            .collect();
+        self.session = session;
+        self.variables.clear();
+        self.error = None;
+        self.loading = false;
+        self.refresh_task = Task::ready(());
+        self.refresh(cx);
+        cx.notify();
+    }
+
+    /// Lists the session's variables again, when the panel is open.
+    fn refresh(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session) = self.session.clone() else {
+            return;
+        };
+        if !self.active || !matches!(session.read(cx).kernel, Kernel::RunningKernel(_)) {
+            return;
+        }
+
+        let variables = session.update(cx, |session, cx| session.variables(cx));
+        self.loading = true;
+        self.refresh_task = cx.spawn(|this, mut cx| async move {
+            let variables = variables.await;
+            this.update(&mut cx, |this, cx| {
+                match variables {
+                    Ok(variables) => {
+                        this.variables = variables;
+                        this.error = None;
+                    }
+                    Err(error) => this.error = Some(error.to_string().into()),
+                }
+                this.loading = false;
+                cx.notify();
+            })
+            .ok();
+        });
+        cx.notify();
+    }
+
+    fn render_variable(
+        &self,
+        ix: usize,
+        variable: &Variable,
+        cx: &ViewContext<Self>,
+    ) -> impl IntoElement {
+        v_flex()
+            .id(ix)
+            .px_2()
+            .py_1()
+            .gap_0p5()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(variable.name.clone()))
+                    .child(
+                        Label::new(variable.type_name.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .children(
+                        variable.shape.clone().map(|shape| {
+                            Label::new(shape).size(LabelSize::Small).color(Color::Muted)
+                        }),
+                    ),
+            )
+            .child(
+                Label::new(variable.preview.clone())
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .single_line(),
+            )
+            .tooltip({
+                let preview = variable.preview.clone();
+                move |cx| Tooltip::text(preview.clone(), cx)
+            })
+    }
+
+    fn render_message(&self, message: impl Into<SharedString>) -> impl IntoElement {
+        v_flex().p_4().child(
+            Label::new(message)
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+        )
+    }
+}
+
+impl Render for VariablesPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let kernel_status = self
+            .session
+            .as_ref()
+            .map(|session| KernelStatus::from(&session.read(cx).kernel));
+
+        let header = h_flex()
+            .justify_between()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(Label::new("Variables"))
+            .child(
+                IconButton::new("refresh-variables", IconName::RotateCw)
+                    .icon_size(IconSize::Small)
+                    .disabled(!matches!(kernel_status, Some(KernelStatus::Idle)) || self.loading)
+                    .tooltip(|cx| Tooltip::for_action("Refresh Variables", &RefreshVariables, cx))
+                    .on_click(cx.listener(|this, _, cx| this.refresh(cx))),
+            );
+
+        let content = match kernel_status {
+            None => self
+                .render_message("Run code in an editor to list its kernel's variables.")
+                .into_any_element(),
+            Some(KernelStatus::Starting | KernelStatus::Restarting) => self
+                .render_message("Waiting for the kernel to start…")
+                .into_any_element(),
+            Some(KernelStatus::Error) => self
+                .render_message("The kernel failed to start.")
+                .into_any_element(),
+            Some(KernelStatus::ShuttingDown | KernelStatus::Shutdown) => self
+                .render_message("The kernel has been shut down.")
+                .into_any_element(),
+            Some(KernelStatus::Idle | KernelStatus::Busy) => {
+                if let Some(error) = self.error.clone() {
+                    self.render_message(error).into_any_element()
+                } else if self.variables.is_empty() {
+                    self.render_message(if self.loading {
+                        "Listing variables…"
+                    } else {
+                        "No variables are defined."
+                    })
+                    .into_any_element()
+                } else {
+                    v_flex()
+                        .id("variables")
+                        .flex_1()
+                        .overflow_y_scroll()
+                        .children(
+                            self.variables
+                                .iter()
+                                .enumerate()
+                                .map(|(ix, variable)| self.render_variable(ix, variable, cx)),
+                        )
+                        .into_any_element()
+                }
+            }
+        };
+
+        v_flex()
+            .key_context("VariablesPanel")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(|this, _: &RefreshVariables, cx| this.refresh(cx)))
+            .size_full()
+            .child(header)
+            .child(content)
+    }
+}
+
+impl FocusableView for VariablesPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for VariablesPanel {}
+
+impl Panel for VariablesPanel {
+    fn persistent_name() -> &'static str {
+        "VariablesPanel"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        VariablesPanelSettings::get_global(cx).dock
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<VariablesPanelSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings, _| settings.dock = Some(position),
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| VariablesPanelSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        cx.notify();
+    }
+
+    fn set_active(&mut self, active: bool, cx: &mut ViewContext<Self>) {
+        self.active = active;
+        if active {
+            self.refresh(cx);
+        }
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        let enabled = ReplStore::global(cx).read(cx).is_enabled();
+        (enabled && VariablesPanelSettings::get_global(cx).button).then_some(IconName::Code)
+    }
+
+    fn icon_tooltip(&self, _cx: &WindowContext) -> Option<&'static str> {
+        Some("Variables Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn gpui::Action> {
+        Box::new(ToggleVariablesPanel)
+    }
+}
@@ -3,7 +3,8 @@ mod registrar;
 use crate::{
     search_bar::render_nav_button, FocusSearch, NextHistoryQuery, PreviousHistoryQuery, ReplaceAll,
     ReplaceNext, SearchOptions, SelectAllMatches, SelectNextMatch, SelectPrevMatch,
-    ToggleCaseSensitive, ToggleRegex, ToggleReplace, ToggleSelection, ToggleWholeWord,
+    ToggleAdvancedRegex, ToggleCaseSensitive, ToggleRegex, ToggleReplace, ToggleSelection,
+    ToggleStructural, ToggleWholeWord,
 };
 use any_vec::AnyVec;
 use collections::HashMap;
@@ -241,6 +242,24 @@ impl Render for BufferSearchBar {
                                 cx.listener(|this, _, cx| this.toggle_regex(&ToggleRegex, cx)),
                             )
                         }))
+                        .children(supported_options.regex.then(|| {
+                            self.render_search_option_button(
+                                SearchOptions::ADVANCED_REGEX,
+                                focus_handle.clone(),
+                                cx.listener(|this, _, cx| {
+                                    this.toggle_advanced_regex(&ToggleAdvancedRegex, cx)
+                                }),
+                            )
+                        }))
+                        .children(supported_options.structural.then(|| {
+                            self.render_search_option_button(
+                                SearchOptions::STRUCTURAL,
+                                focus_handle.clone(),
+                                cx.listener(|this, _, cx| {
+                                    this.toggle_structural(&ToggleStructural, cx)
+                                }),
+                            )
+                        }))
                     }),
             )
             .child(
@@ -438,6 +457,10 @@ impl Render for BufferSearchBar {
             })
             .when(self.supported_options().regex, |this| {
                 this.on_action(cx.listener(Self::toggle_regex))
+                    .on_action(cx.listener(Self::toggle_advanced_regex))
+            })
+            .when(self.supported_options().structural, |this| {
+                this.on_action(cx.listener(Self::toggle_structural))
             })
             .when(self.supported_options().selection, |this| {
                 this.on_action(cx.listener(Self::toggle_selection))
@@ -797,7 +820,7 @@ impl BufferSearchBar {
         search_option: SearchOptions,
         cx: &mut ViewContext<Self>,
     ) {
-        self.search_options.toggle(search_option);
+        self.search_options.toggle_exclusive(search_option);
         self.default_options = self.search_options;
         drop(self.update_matches(false, cx));
         cx.notify();
@@ -967,6 +990,14 @@ impl BufferSearchBar {
         self.toggle_search_option(SearchOptions::REGEX, cx)
     }
 
+    fn toggle_advanced_regex(&mut self, _: &ToggleAdvancedRegex, cx: &mut ViewContext<Self>) {
+        self.toggle_search_option(SearchOptions::ADVANCED_REGEX, cx)
+    }
+
+    fn toggle_structural(&mut self, _: &ToggleStructural, cx: &mut ViewContext<Self>) {
+        self.toggle_search_option(SearchOptions::STRUCTURAL, cx)
+    }
+
     fn clear_active_searchable_item_matches(&mut self, cx: &mut WindowContext) {
         if let Some(active_searchable_item) = self.active_searchable_item.as_ref() {
             self.active_match_index = None;
@@ -1019,11 +1050,9 @@ impl BufferSearchBar {
                 {
                     search
                 } else {
-                    if self.search_options.contains(SearchOptions::REGEX) {
-                        match SearchQuery::regex(
+                    if self.search_options.contains(SearchOptions::STRUCTURAL) {
+                        match SearchQuery::structural(
                             query,
-                            self.search_options.contains(SearchOptions::WHOLE_WORD),
-                            self.search_options.contains(SearchOptions::CASE_SENSITIVE),
                             false,
                             Default::default(),
                             Default::default(),
@@ -1037,6 +1066,37 @@ impl BufferSearchBar {
                                 return done_rx;
                             }
                         }
+                    } else if self.search_options.contains(SearchOptions::REGEX) {
+                        let query = if self.search_options.contains(SearchOptions::ADVANCED_REGEX) {
+                            SearchQuery::advanced_regex(
+                                query,
+                                self.search_options.contains(SearchOptions::WHOLE_WORD),
+                                self.search_options.contains(SearchOptions::CASE_SENSITIVE),
+                                false,
+                                Default::default(),
+                                Default::default(),
+                                None,
+                            )
+                        } else {
+                            SearchQuery::regex(
+                                query,
+                                self.search_options.contains(SearchOptions::WHOLE_WORD),
+                                self.search_options.contains(SearchOptions::CASE_SENSITIVE),
+                                false,
+                                Default::default(),
+                                Default::default(),
+                                None,
+                            )
+                        };
+                        match query {
+                            Ok(query) => query.with_replacement(self.replacement(cx)),
+                            Err(_) => {
+                                self.query_contains_error = true;
+                                self.clear_active_searchable_item_matches(cx);
+                                cx.notify();
+                                return done_rx;
+                            }
+                        }
                     } else {
                         match SearchQuery::text(
                             query,
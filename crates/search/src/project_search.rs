@@ -1,7 +1,10 @@
 use crate::{
+    project_search_export::{self, ExportFormat, ExportedMatch},
+    saved_searches::{SavedSearch, SavedSearches},
     FocusSearch, NextHistoryQuery, PreviousHistoryQuery, ReplaceAll, ReplaceNext, SearchOptions,
-    SelectNextMatch, SelectPrevMatch, ToggleCaseSensitive, ToggleIncludeIgnored, ToggleRegex,
-    ToggleReplace, ToggleWholeWord,
+    SelectNextMatch, SelectPrevMatch, ToggleAdvancedRegex, ToggleCaseSensitive,
+    ToggleIncludeIgnored, ToggleRegex, ToggleReplace, ToggleSemantic, ToggleStructural,
+    ToggleWholeWord,
 };
 use collections::{HashMap, HashSet};
 use editor::{
@@ -18,13 +21,14 @@ use gpui::{
     ModelContext, ParentElement, Point, Render, SharedString, Styled, Subscription, Task,
     TextStyle, UpdateGlobal, View, ViewContext, VisualContext, WeakModel, WeakView, WindowContext,
 };
-use language::Buffer;
+use language::{Bias, Buffer, ToPoint as _};
 use menu::Confirm;
 use project::{
     search::{SearchInputKind, SearchQuery},
     search_history::SearchHistoryCursor,
     Project, ProjectPath,
 };
+use semantic_index::{ProjectIndex, SearchMode, SemanticDb};
 use settings::Settings;
 use std::{
     any::{Any, TypeId},
@@ -37,7 +41,7 @@ use ui::{
     h_flex, prelude::*, v_flex, Icon, IconButton, IconName, KeyBinding, Label, LabelCommon,
     LabelSize, Selectable, Tooltip,
 };
-use util::paths::PathMatcher;
+use util::{paths::PathMatcher, ResultExt as _};
 use workspace::{
     item::{BreadcrumbText, Item, ItemEvent, ItemHandle},
     searchable::{Direction, SearchableItem, SearchableItemHandle},
@@ -47,10 +51,21 @@ use workspace::{
 
 const MIN_INPUT_WIDTH_REMS: f32 = 15.;
 const MAX_INPUT_WIDTH_REMS: f32 = 30.;
+/// The number of chunks shown when searching by meaning.
+const SEMANTIC_SEARCH_LIMIT: usize = 32;
 
 actions!(
     project_search,
-    [SearchInNew, ToggleFocus, NextField, ToggleFilters]
+    [
+        SearchInNew,
+        ToggleFocus,
+        NextField,
+        ToggleFilters,
+        SaveSearch,
+        OpenSavedSearch,
+        ExportResults,
+        ExportQuickfixList
+    ]
 );
 
 #[derive(Default)]
@@ -76,9 +91,24 @@ pub fn init(cx: &mut AppContext) {
         register_workspace_action(workspace, move |search_bar, _: &ToggleRegex, cx| {
             search_bar.toggle_search_option(SearchOptions::REGEX, cx);
         });
+        register_workspace_action(workspace, move |search_bar, _: &ToggleAdvancedRegex, cx| {
+            search_bar.toggle_search_option(SearchOptions::ADVANCED_REGEX, cx);
+        });
+        register_workspace_action(workspace, move |search_bar, _: &ToggleStructural, cx| {
+            search_bar.toggle_search_option(SearchOptions::STRUCTURAL, cx);
+        });
+        register_workspace_action(workspace, move |search_bar, _: &ToggleSemantic, cx| {
+            search_bar.toggle_search_option(SearchOptions::SEMANTIC, cx);
+        });
         register_workspace_action(workspace, move |search_bar, action: &ToggleReplace, cx| {
             search_bar.toggle_replace(action, cx)
         });
+        register_workspace_action(workspace, move |search_bar, _: &ExportResults, cx| {
+            search_bar.export_results(ExportFormat::Grep, cx)
+        });
+        register_workspace_action(workspace, move |search_bar, _: &ExportQuickfixList, cx| {
+            search_bar.export_results(ExportFormat::Quickfix, cx)
+        });
         register_workspace_action(
             workspace,
             move |search_bar, action: &SelectPrevMatch, cx| {
@@ -96,6 +126,8 @@ pub fn init(cx: &mut AppContext) {
         register_workspace_action_for_present_search(workspace, |workspace, action, cx| {
             ProjectSearchView::search_in_new(workspace, action, cx)
         });
+        register_workspace_action_for_present_search(workspace, SavedSearches::save);
+        workspace.register_action(SavedSearches::open);
 
         // Both on present and dismissed search, we need to unconditionally handle those actions to focus from the editor.
         workspace.register_action(move |workspace, action: &DeploySearch, cx| {
@@ -227,8 +259,8 @@ impl ProjectSearch {
         }
     }
 
-    fn search(&mut self, query: SearchQuery, cx: &mut ModelContext<Self>) {
-        let search = self.project.update(cx, |project, cx| {
+    fn add_to_search_history(&mut self, query: &SearchQuery, cx: &mut ModelContext<Self>) {
+        self.project.update(cx, |project, _| {
             project
                 .search_history_mut(SearchInputKind::Query)
                 .add(&mut self.search_history_cursor, query.as_str().to_string());
@@ -244,8 +276,14 @@ impl ProjectSearch {
                     .search_history_mut(SearchInputKind::Exclude)
                     .add(&mut self.search_excluded_history_cursor, excluded);
             }
-            project.search(query.clone(), cx)
         });
+    }
+
+    fn search(&mut self, query: SearchQuery, cx: &mut ModelContext<Self>) {
+        self.add_to_search_history(&query, cx);
+        let search = self
+            .project
+            .update(cx, |project, cx| project.search(query.clone(), cx));
         self.last_search_query_text = Some(query.as_str().to_string());
         self.search_id += 1;
         self.active_query = Some(query);
@@ -309,6 +347,117 @@ impl ProjectSearch {
         }));
         cx.notify();
     }
+
+    /// Searches the project's semantic index for the chunks that best match the query, by both
+    /// meaning and keywords, and shows them in order of relevance. Projects that haven't been
+    /// indexed are searched for the query's text instead.
+    fn search_semantically(&mut self, query: SearchQuery, cx: &mut ModelContext<Self>) {
+        let Some(project_index) = project_semantic_index(&self.project, cx) else {
+            self.search(query, cx);
+            return;
+        };
+
+        self.add_to_search_history(&query, cx);
+        let options = semantic_index::SearchOptions {
+            mode: SearchMode::Hybrid,
+            files_to_include: query.as_inner().files_to_include().clone(),
+            files_to_exclude: query.as_inner().files_to_exclude().clone(),
+            languages: Vec::new(),
+        };
+        let search = project_index.read(cx).search_with_options(
+            vec![query.as_str().to_string()],
+            SEMANTIC_SEARCH_LIMIT,
+            options,
+            cx,
+        );
+        self.last_search_query_text = Some(query.as_str().to_string());
+        self.search_id += 1;
+        self.active_query = Some(query);
+        self.match_ranges.clear();
+        self.pending_search = Some(cx.spawn(|this, mut cx| async move {
+            let this = this.upgrade()?;
+            this.update(&mut cx, |this, cx| {
+                this.match_ranges.clear();
+                this.excerpts.update(cx, |this, cx| this.clear(cx));
+                this.no_results = Some(true);
+                this.limit_reached = false;
+            })
+            .ok()?;
+
+            let results = search.await.log_err().unwrap_or_default();
+            let project = this.update(&mut cx, |this, _| this.project.clone()).ok()?;
+            let mut buffers_with_ranges =
+                Vec::<(Model<Buffer>, Vec<Range<language::Anchor>>)>::new();
+            for result in results {
+                let project_path = ProjectPath {
+                    worktree_id: result
+                        .worktree
+                        .read_with(&cx, |worktree, _| worktree.id())
+                        .ok()?,
+                    path: result.path,
+                };
+                let Some(buffer) = project
+                    .update(&mut cx, |project, cx| project.open_buffer(project_path, cx))
+                    .ok()?
+                    .await
+                    .log_err()
+                else {
+                    continue;
+                };
+                let range = buffer
+                    .read_with(&cx, |buffer, _| {
+                        let start = buffer.clip_offset(result.range.start, Bias::Left);
+                        let end = buffer.clip_offset(result.range.end, Bias::Right);
+                        buffer.anchor_before(start)..buffer.anchor_after(end)
+                    })
+                    .ok()?;
+
+                // Results are sorted by relevance, so keep each buffer where its best result is.
+                match buffers_with_ranges
+                    .iter_mut()
+                    .find(|(existing, _)| *existing == buffer)
+                {
+                    Some((_, ranges)) => ranges.push(range),
+                    None => buffers_with_ranges.push((buffer, vec![range])),
+                }
+            }
+
+            let match_ranges = this
+                .update(&mut cx, |this, cx| {
+                    this.excerpts.update(cx, |excerpts, cx| {
+                        excerpts.push_multiple_excerpts_with_context_lines(
+                            buffers_with_ranges,
+                            editor::DEFAULT_MULTIBUFFER_CONTEXT,
+                            cx,
+                        )
+                    })
+                })
+                .ok()?
+                .await;
+
+            this.update(&mut cx, |this, cx| {
+                this.match_ranges.extend(match_ranges);
+                if !this.match_ranges.is_empty() {
+                    this.no_results = Some(false);
+                }
+                this.pending_search.take();
+                cx.notify();
+            })
+            .ok()?;
+
+            None
+        }));
+        cx.notify();
+    }
+}
+
+/// Returns the semantic index of the project, if it has been indexed.
+fn project_semantic_index(
+    project: &Model<Project>,
+    cx: &AppContext,
+) -> Option<Model<ProjectIndex>> {
+    cx.try_global::<SemanticDb>()?
+        .project_index(project.clone(), cx)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -572,7 +721,7 @@ impl ProjectSearchView {
     }
 
     fn toggle_search_option(&mut self, option: SearchOptions, cx: &mut ViewContext<Self>) {
-        self.search_options.toggle(option);
+        self.search_options.toggle_exclusive(option);
         ActiveSettings::update_global(cx, |settings, cx| {
             settings.0.insert(
                 self.model.read(cx).project.downgrade(),
@@ -585,6 +734,97 @@ impl ProjectSearchView {
         self.included_opened_only = !self.included_opened_only;
     }
 
+    /// Captures the query, options and path filters currently entered in this view, so they can
+    /// be saved and run again later.
+    pub fn current_search(&self, cx: &AppContext) -> SavedSearch {
+        SavedSearch {
+            name: String::new(),
+            query: self.query_editor.read(cx).text(cx),
+            options: self.search_options,
+            files_to_include: self.included_files_editor.read(cx).text(cx),
+            files_to_exclude: self.excluded_files_editor.read(cx).text(cx),
+        }
+    }
+
+    fn apply_saved_search(&mut self, saved_search: &SavedSearch, cx: &mut ViewContext<Self>) {
+        self.set_search_editor(SearchInputKind::Query, &saved_search.query, cx);
+        self.set_search_editor(SearchInputKind::Include, &saved_search.files_to_include, cx);
+        self.set_search_editor(SearchInputKind::Exclude, &saved_search.files_to_exclude, cx);
+        if !saved_search.files_to_include.is_empty() || !saved_search.files_to_exclude.is_empty() {
+            self.filters_enabled = true;
+        }
+        self.search_options = saved_search.options;
+        self.search(cx);
+        cx.notify();
+    }
+
+    fn exported_matches(&self, cx: &AppContext) -> Vec<ExportedMatch> {
+        let model = self.model.read(cx);
+        let include_root = model.project.read(cx).visible_worktrees(cx).count() > 1;
+        let snapshot = model.excerpts.read(cx).snapshot(cx);
+        model
+            .match_ranges
+            .iter()
+            .filter_map(|range| {
+                let buffer = snapshot.buffer_for_excerpt(range.start.excerpt_id)?;
+                let file = buffer.file()?;
+                let path = if include_root {
+                    file.full_path(cx)
+                } else {
+                    file.path().to_path_buf()
+                };
+                let start = range.start.text_anchor.to_point(buffer);
+                let line_text = buffer
+                    .text_for_range(
+                        language::Point::new(start.row, 0)
+                            ..language::Point::new(start.row, buffer.line_len(start.row)),
+                    )
+                    .collect();
+                Some(ExportedMatch {
+                    path: path.to_string_lossy().into_owned(),
+                    line: start.row + 1,
+                    column: start.column + 1,
+                    line_text,
+                })
+            })
+            .collect()
+    }
+
+    /// Writes the current results to a file chosen by the user, and opens it.
+    fn export_results(&mut self, format: ExportFormat, cx: &mut ViewContext<Self>) {
+        let matches = self.exported_matches(cx);
+        if matches.is_empty() {
+            return;
+        }
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let text = project_search_export::format_matches(&matches, format);
+        let project = self.model.read(cx).project.clone();
+        let path = workspace.update(cx, |workspace, cx| workspace.prompt_for_new_path(cx));
+        cx.spawn(|_, mut cx| async move {
+            let Some(path) = path.await.ok().flatten() else {
+                return Ok(());
+            };
+            let buffer = project
+                .update(&mut cx, |project, cx| project.create_buffer(cx))?
+                .await?;
+            buffer.update(&mut cx, |buffer, cx| buffer.set_text(text, cx))?;
+            project
+                .update(&mut cx, |project, cx| {
+                    project.save_buffer_as(buffer, path.clone(), cx)
+                })?
+                .await?;
+            workspace
+                .update(&mut cx, |workspace, cx| {
+                    workspace.open_path(path, None, true, cx)
+                })?
+                .await?;
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn replace_next(&mut self, _: &ReplaceNext, cx: &mut ViewContext<Self>) {
         if self.model.read(cx).match_ranges.is_empty() {
             return;
@@ -814,6 +1054,31 @@ impl ProjectSearchView {
         Self::existing_or_new_search(workspace, existing, action, cx);
     }
 
+    /// Runs a saved search in the most recently activated search in this pane, creating a new
+    /// search if there is none.
+    pub fn run_saved_search(
+        workspace: &mut Workspace,
+        saved_search: &SavedSearch,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let existing = workspace
+            .active_pane()
+            .read(cx)
+            .items()
+            .find_map(|item| item.downcast::<ProjectSearchView>());
+        let search = if let Some(existing) = existing {
+            workspace.activate_item(&existing, true, true, cx);
+            existing
+        } else {
+            let weak_workspace = cx.view().downgrade();
+            let model = cx.new_model(|cx| ProjectSearch::new(workspace.project().clone(), cx));
+            let view = cx.new_view(|cx| ProjectSearchView::new(weak_workspace, model, cx, None));
+            workspace.add_item_to_active_pane(Box::new(view.clone()), None, true, cx);
+            view
+        };
+        search.update(cx, |search, cx| search.apply_saved_search(saved_search, cx));
+    }
+
     fn search_in_new(workspace: &mut Workspace, _: &SearchInNew, cx: &mut ViewContext<Workspace>) {
         if let Some(search_view) = workspace
             .active_item(cx)
@@ -907,7 +1172,14 @@ impl ProjectSearchView {
 
     fn search(&mut self, cx: &mut ViewContext<Self>) {
         if let Some(query) = self.build_search_query(cx) {
-            self.model.update(cx, |model, cx| model.search(query, cx));
+            let semantic = self.search_options.contains(SearchOptions::SEMANTIC);
+            self.model.update(cx, |model, cx| {
+                if semantic {
+                    model.search_semantically(query, cx)
+                } else {
+                    model.search(query, cx)
+                }
+            });
         }
     }
 
@@ -959,11 +1231,9 @@ impl ProjectSearchView {
                 }
             };
 
-        let query = if self.search_options.contains(SearchOptions::REGEX) {
-            match SearchQuery::regex(
+        let query = if self.search_options.contains(SearchOptions::STRUCTURAL) {
+            match SearchQuery::structural(
                 text,
-                self.search_options.contains(SearchOptions::WHOLE_WORD),
-                self.search_options.contains(SearchOptions::CASE_SENSITIVE),
                 self.search_options.contains(SearchOptions::INCLUDE_IGNORED),
                 included_files,
                 excluded_files,
@@ -986,6 +1256,46 @@ impl ProjectSearchView {
                     None
                 }
             }
+        } else if self.search_options.contains(SearchOptions::REGEX) {
+            let query = if self.search_options.contains(SearchOptions::ADVANCED_REGEX) {
+                SearchQuery::advanced_regex(
+                    text,
+                    self.search_options.contains(SearchOptions::WHOLE_WORD),
+                    self.search_options.contains(SearchOptions::CASE_SENSITIVE),
+                    self.search_options.contains(SearchOptions::INCLUDE_IGNORED),
+                    included_files,
+                    excluded_files,
+                    open_buffers,
+                )
+            } else {
+                SearchQuery::regex(
+                    text,
+                    self.search_options.contains(SearchOptions::WHOLE_WORD),
+                    self.search_options.contains(SearchOptions::CASE_SENSITIVE),
+                    self.search_options.contains(SearchOptions::INCLUDE_IGNORED),
+                    included_files,
+                    excluded_files,
+                    open_buffers,
+                )
+            };
+            match query {
+                Ok(query) => {
+                    let should_unmark_error = self.panels_with_errors.remove(&InputPanel::Query);
+                    if should_unmark_error {
+                        cx.notify();
+                    }
+
+                    Some(query)
+                }
+                Err(_e) => {
+                    let should_mark_error = self.panels_with_errors.insert(InputPanel::Query);
+                    if should_mark_error {
+                        cx.notify();
+                    }
+
+                    None
+                }
+            }
         } else {
             match SearchQuery::text(
                 text,
@@ -1192,6 +1502,18 @@ impl ProjectSearchView {
                     .key_binding(KeyBinding::for_action_in(&ToggleRegex, &focus_handle, cx))
                     .on_click(|_event, cx| cx.dispatch_action(ToggleRegex.boxed_clone())),
             )
+            .child(
+                Button::new("structural", "Match syntax structure")
+                    .icon(IconName::Code)
+                    .icon_position(IconPosition::Start)
+                    .icon_size(IconSize::Small)
+                    .key_binding(KeyBinding::for_action_in(
+                        &ToggleStructural,
+                        &focus_handle,
+                        cx,
+                    ))
+                    .on_click(|_event, cx| cx.dispatch_action(ToggleStructural.boxed_clone())),
+            )
             .child(
                 Button::new("match-case", "Match case")
                     .icon(IconName::CaseSensitive)
@@ -1216,6 +1538,18 @@ impl ProjectSearchView {
                     ))
                     .on_click(|_event, cx| cx.dispatch_action(ToggleWholeWord.boxed_clone())),
             )
+            .child(
+                Button::new("saved-searches", "Run a saved search")
+                    .icon(IconName::HistoryRerun)
+                    .icon_position(IconPosition::Start)
+                    .icon_size(IconSize::Small)
+                    .key_binding(KeyBinding::for_action_in(
+                        &OpenSavedSearch,
+                        &focus_handle,
+                        cx,
+                    ))
+                    .on_click(|_event, cx| cx.dispatch_action(OpenSavedSearch.boxed_clone())),
+            )
     }
 
     fn border_color_for(&self, panel: InputPanel, cx: &WindowContext) -> Hsla {
@@ -1377,6 +1711,12 @@ impl ProjectSearchBar {
         }
     }
 
+    fn export_results(&mut self, format: ExportFormat, cx: &mut ViewContext<Self>) {
+        if let Some(search_view) = self.active_project_search.as_ref() {
+            search_view.update(cx, |search_view, cx| search_view.export_results(format, cx));
+        }
+    }
+
     fn toggle_opened_only(&mut self, cx: &mut ViewContext<Self>) -> bool {
         if let Some(search_view) = self.active_project_search.as_ref() {
             search_view.update(cx, |search_view, cx| {
@@ -1552,6 +1892,8 @@ impl Render for ProjectSearchBar {
         let Some(search) = self.active_project_search.clone() else {
             return div();
         };
+        let project = search.read(cx).model.read(cx).project.clone();
+        let has_semantic_index = project_semantic_index(&project, cx).is_some();
         let search = search.read(cx);
         let focus_handle = search.focus_handle(cx);
 
@@ -1592,7 +1934,30 @@ impl Render for ProjectSearchBar {
                         cx.listener(|this, _, cx| {
                             this.toggle_search_option(SearchOptions::REGEX, cx);
                         }),
-                    )),
+                    ))
+                    .child(SearchOptions::ADVANCED_REGEX.as_button(
+                        self.is_option_enabled(SearchOptions::ADVANCED_REGEX, cx),
+                        focus_handle.clone(),
+                        cx.listener(|this, _, cx| {
+                            this.toggle_search_option(SearchOptions::ADVANCED_REGEX, cx);
+                        }),
+                    ))
+                    .child(SearchOptions::STRUCTURAL.as_button(
+                        self.is_option_enabled(SearchOptions::STRUCTURAL, cx),
+                        focus_handle.clone(),
+                        cx.listener(|this, _, cx| {
+                            this.toggle_search_option(SearchOptions::STRUCTURAL, cx);
+                        }),
+                    ))
+                    .when(has_semantic_index, |this| {
+                        this.child(SearchOptions::SEMANTIC.as_button(
+                            self.is_option_enabled(SearchOptions::SEMANTIC, cx),
+                            focus_handle.clone(),
+                            cx.listener(|this, _, cx| {
+                                this.toggle_search_option(SearchOptions::SEMANTIC, cx);
+                            }),
+                        ))
+                    }),
             );
 
         let mode_column = v_flex().items_start().justify_start().child(
@@ -1643,6 +2008,21 @@ impl Render for ProjectSearchBar {
                                 )
                             }
                         }),
+                )
+                .child(
+                    IconButton::new("project-search-save-search", IconName::Star)
+                        .on_click(|_, cx| cx.dispatch_action(SaveSearch.boxed_clone()))
+                        .tooltip({
+                            let focus_handle = focus_handle.clone();
+                            move |cx| {
+                                Tooltip::for_action_in(
+                                    "Save Search",
+                                    &SaveSearch,
+                                    &focus_handle,
+                                    cx,
+                                )
+                            }
+                        }),
                 ),
         );
 
@@ -3573,6 +3953,66 @@ pub mod tests {
             .expect("unable to update search view");
     }
 
+    #[gpui::test]
+    async fn test_run_saved_search_and_export_results(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "one.rs": "const ONE: usize = 1;",
+                "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+                "three.rs": "const THREE: usize = one::ONE + two::TWO;",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        let window = cx.add_window(|cx| Workspace::test_new(project, cx));
+
+        let saved_search = SavedSearch {
+            name: "ones".to_string(),
+            query: "one::ONE".to_string(),
+            options: SearchOptions::CASE_SENSITIVE,
+            files_to_include: String::new(),
+            files_to_exclude: "three.rs".to_string(),
+        };
+        window
+            .update(cx, |workspace, cx| {
+                ProjectSearchView::run_saved_search(workspace, &saved_search, cx)
+            })
+            .unwrap();
+        cx.background_executor.run_until_parked();
+
+        window
+            .update(cx, |workspace, cx| {
+                let search_view = workspace.active_item_as::<ProjectSearchView>(cx).unwrap();
+                let search_view = search_view.read(cx);
+                assert!(search_view.filters_enabled);
+                assert_eq!(
+                    search_view.current_search(cx),
+                    SavedSearch {
+                        name: String::new(),
+                        ..saved_search.clone()
+                    }
+                );
+
+                let matches = search_view.exported_matches(cx);
+                assert_eq!(
+                    project_search_export::format_matches(&matches, ExportFormat::Grep),
+                    "two.rs:1:const TWO: usize = one::ONE + one::ONE;\n"
+                );
+                assert_eq!(
+                    project_search_export::format_matches(&matches, ExportFormat::Quickfix),
+                    concat!(
+                        "two.rs:1:20:const TWO: usize = one::ONE + one::ONE;\n",
+                        "two.rs:1:31:const TWO: usize = one::ONE + one::ONE;\n",
+                    )
+                );
+            })
+            .unwrap();
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings = SettingsStore::test(cx);
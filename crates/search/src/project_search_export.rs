@@ -0,0 +1,86 @@
+use std::fmt::Write as _;
+
+/// The formats project search results can be exported in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ExportFormat {
+    /// One `path:line:text` entry per matching line, like `grep -n`.
+    Grep,
+    /// One `path:line:column:text` entry per match, which Vim's `:cfile` and other quickfix list
+    /// consumers can read.
+    Quickfix,
+}
+
+/// A single search match, with a one-based line and a one-based byte column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ExportedMatch {
+    pub path: String,
+    pub line: u32,
+    pub column: u32,
+    pub line_text: String,
+}
+
+pub(crate) fn format_matches(matches: &[ExportedMatch], format: ExportFormat) -> String {
+    let mut output = String::new();
+    let mut last_line = None;
+    for mat in matches {
+        match format {
+            ExportFormat::Grep => {
+                // Like grep, print each line once even if it contains several matches.
+                if last_line == Some((&mat.path, mat.line)) {
+                    continue;
+                }
+                last_line = Some((&mat.path, mat.line));
+                writeln!(output, "{}:{}:{}", mat.path, mat.line, mat.line_text).ok();
+            }
+            ExportFormat::Quickfix => {
+                writeln!(
+                    output,
+                    "{}:{}:{}:{}",
+                    mat.path, mat.line, mat.column, mat.line_text
+                )
+                .ok();
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_matches() {
+        let exported_match = |path: &str, line, column, line_text: &str| ExportedMatch {
+            path: path.to_string(),
+            line,
+            column,
+            line_text: line_text.to_string(),
+        };
+        let matches = [
+            exported_match("src/main.rs", 3, 5, "let one = ONE + ONE;"),
+            exported_match("src/main.rs", 3, 17, "let one = ONE + ONE;"),
+            exported_match("src/main.rs", 7, 1, "ONE"),
+            exported_match("src/lib.rs", 7, 12, "pub const ONE: usize = 1;"),
+        ];
+
+        assert_eq!(
+            format_matches(&matches, ExportFormat::Grep),
+            concat!(
+                "src/main.rs:3:let one = ONE + ONE;\n",
+                "src/main.rs:7:ONE\n",
+                "src/lib.rs:7:pub const ONE: usize = 1;\n",
+            )
+        );
+        assert_eq!(
+            format_matches(&matches, ExportFormat::Quickfix),
+            concat!(
+                "src/main.rs:3:5:let one = ONE + ONE;\n",
+                "src/main.rs:3:17:let one = ONE + ONE;\n",
+                "src/main.rs:7:1:ONE\n",
+                "src/lib.rs:7:12:pub const ONE: usize = 1;\n",
+            )
+        );
+        assert_eq!(format_matches(&[], ExportFormat::Quickfix), "");
+    }
+}
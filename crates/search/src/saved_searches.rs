@@ -0,0 +1,432 @@
+use crate::{
+    project_search::{OpenSavedSearch, ProjectSearchView, SaveSearch},
+    SearchOptions,
+};
+use anyhow::Result;
+use db::{define_connection, query, sqlez_macros::sql};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render, Subscription,
+    Task, View, ViewContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing, Tooltip};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+/// A project search stored under a name, so that it can be run again in later sessions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    pub options: SearchOptions,
+    pub files_to_include: String,
+    pub files_to_exclude: String,
+}
+
+define_connection!(pub static ref SAVED_SEARCHES: SavedSearchesDb<()> =
+    &[sql!(
+        CREATE TABLE saved_searches(
+            name TEXT PRIMARY KEY,
+            query TEXT NOT NULL,
+            options INTEGER NOT NULL,
+            files_to_include TEXT NOT NULL,
+            files_to_exclude TEXT NOT NULL
+        ) STRICT;
+    )];
+);
+
+impl SavedSearchesDb {
+    query! {
+        fn saved_search_rows() -> Result<Vec<(String, String, u32, String, String)>> {
+            SELECT name, query, options, files_to_include, files_to_exclude
+            FROM saved_searches
+            ORDER BY name
+        }
+    }
+
+    query! {
+        async fn write_saved_search(
+            name: String,
+            query: String,
+            options: u32,
+            files_to_include: String,
+            files_to_exclude: String
+        ) -> Result<()> {
+            INSERT OR REPLACE INTO saved_searches(name, query, options, files_to_include, files_to_exclude)
+            VALUES (?, ?, ?, ?, ?)
+        }
+    }
+
+    query! {
+        pub async fn delete_saved_search(name: String) -> Result<()> {
+            DELETE FROM saved_searches WHERE name = ?
+        }
+    }
+
+    /// Returns all saved searches, ordered by name.
+    pub fn saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        Ok(self
+            .saved_search_rows()?
+            .into_iter()
+            .map(
+                |(name, query, options, files_to_include, files_to_exclude)| SavedSearch {
+                    name,
+                    query,
+                    options: SearchOptions::from_bits_truncate(options as u8),
+                    files_to_include,
+                    files_to_exclude,
+                },
+            )
+            .collect())
+    }
+
+    /// Stores the search, replacing any saved search with the same name.
+    pub async fn save_search(&self, search: SavedSearch) -> Result<()> {
+        self.write_saved_search(
+            search.name,
+            search.query,
+            search.options.bits() as u32,
+            search.files_to_include,
+            search.files_to_exclude,
+        )
+        .await
+    }
+}
+
+pub struct SavedSearches {
+    picker: View<Picker<SavedSearchesDelegate>>,
+    _subscription: Subscription,
+}
+
+impl SavedSearches {
+    pub(crate) fn open(
+        workspace: &mut Workspace,
+        _: &OpenSavedSearch,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let current_search = Self::current_search(workspace, cx);
+        Self::toggle(workspace, current_search, None, cx);
+    }
+
+    pub(crate) fn save(workspace: &mut Workspace, _: &SaveSearch, cx: &mut ViewContext<Workspace>) {
+        let Some(current_search) = Self::current_search(workspace, cx) else {
+            return;
+        };
+        let name = current_search.query.clone();
+        Self::toggle(workspace, Some(current_search), Some(name), cx);
+    }
+
+    fn current_search(workspace: &Workspace, cx: &AppContext) -> Option<SavedSearch> {
+        let search_view = workspace.active_item_as::<ProjectSearchView>(cx)?;
+        let search = search_view.read(cx).current_search(cx);
+        (!search.query.is_empty()).then_some(search)
+    }
+
+    /// Shows the saved searches. When `save_as` is set, the picker starts out offering to save
+    /// the current search under that name.
+    fn toggle(
+        workspace: &mut Workspace,
+        current_search: Option<SavedSearch>,
+        save_as: Option<String>,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let saved_searches = SAVED_SEARCHES
+            .saved_searches()
+            .log_err()
+            .unwrap_or_default();
+        let delegate = SavedSearchesDelegate {
+            workspace: cx.view().downgrade(),
+            saved_searches,
+            current_search,
+            saving: save_as.is_some(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        workspace.toggle_modal(cx, |cx| {
+            let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+            if let Some(name) = save_as {
+                picker.update(cx, |picker, cx| picker.set_query(name, cx));
+            }
+            let _subscription = cx.subscribe(&picker, |_, _, _, cx| cx.emit(DismissEvent));
+            Self {
+                picker,
+                _subscription,
+            }
+        });
+    }
+}
+
+impl ModalView for SavedSearches {}
+
+impl EventEmitter<DismissEvent> for SavedSearches {}
+
+impl FocusableView for SavedSearches {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for SavedSearches {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+enum SavedSearchEntry {
+    Saved(StringMatch),
+    SaveCurrent {
+        name: String,
+        replaces_existing: bool,
+    },
+}
+
+pub struct SavedSearchesDelegate {
+    workspace: WeakView<Workspace>,
+    saved_searches: Vec<SavedSearch>,
+    /// The search in the active project search view, if any, which can be saved from the picker.
+    current_search: Option<SavedSearch>,
+    /// Whether the picker was opened to save the current search rather than to run a saved one.
+    saving: bool,
+    matches: Vec<SavedSearchEntry>,
+    selected_index: usize,
+}
+
+impl SavedSearchesDelegate {
+    fn delete(&mut self, ix: usize, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(SavedSearchEntry::Saved(mat)) = self.matches.get(ix) else {
+            return;
+        };
+        let removed_id = mat.candidate_id;
+        let search = self.saved_searches.remove(removed_id);
+        self.matches.retain_mut(|entry| match entry {
+            SavedSearchEntry::Saved(mat) if mat.candidate_id == removed_id => false,
+            SavedSearchEntry::Saved(mat) => {
+                if mat.candidate_id > removed_id {
+                    mat.candidate_id -= 1;
+                }
+                true
+            }
+            SavedSearchEntry::SaveCurrent { .. } => true,
+        });
+        self.selected_index = self
+            .selected_index
+            .min(self.matches.len().saturating_sub(1));
+        cx.background_executor()
+            .spawn(async move { SAVED_SEARCHES.delete_saved_search(search.name).await })
+            .detach_and_log_err(cx);
+    }
+}
+
+impl PickerDelegate for SavedSearchesDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _: &mut WindowContext) -> Arc<str> {
+        if self.saving {
+            "Name this search...".into()
+        } else {
+            "Run a saved search...".into()
+        }
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let candidates = self
+            .saved_searches
+            .iter()
+            .enumerate()
+            .map(|(id, search)| StringMatchCandidate::new(id, search.name.clone()))
+            .collect::<Vec<_>>();
+        cx.spawn(move |picker, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await
+            };
+            picker
+                .update(&mut cx, |picker, _| {
+                    let delegate = &mut picker.delegate;
+                    let name = query.trim();
+                    let save_entry =
+                        (delegate.current_search.is_some() && !name.is_empty()).then(|| {
+                            SavedSearchEntry::SaveCurrent {
+                                name: name.to_string(),
+                                replaces_existing: delegate
+                                    .saved_searches
+                                    .iter()
+                                    .any(|search| search.name == name),
+                            }
+                        });
+
+                    delegate.matches = matches.into_iter().map(SavedSearchEntry::Saved).collect();
+                    if let Some(save_entry) = save_entry {
+                        if delegate.saving {
+                            delegate.matches.insert(0, save_entry);
+                        } else if delegate.matches.is_empty() {
+                            delegate.matches.push(save_entry);
+                        }
+                    }
+                    delegate.selected_index = 0;
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(entry) = self.matches.get(self.selected_index) else {
+            return;
+        };
+        match entry {
+            SavedSearchEntry::Saved(mat) => {
+                let search = self.saved_searches[mat.candidate_id].clone();
+                self.workspace
+                    .update(cx, |workspace, cx| {
+                        ProjectSearchView::run_saved_search(workspace, &search, cx)
+                    })
+                    .log_err();
+            }
+            SavedSearchEntry::SaveCurrent { name, .. } => {
+                let Some(current_search) = self.current_search.clone() else {
+                    return;
+                };
+                let search = SavedSearch {
+                    name: name.clone(),
+                    ..current_search
+                };
+                cx.background_executor()
+                    .spawn(async move { SAVED_SEARCHES.save_search(search).await })
+                    .detach_and_log_err(cx);
+            }
+        }
+        cx.emit(DismissEvent);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let item = ListItem::new(ix)
+            .inset(true)
+            .spacing(ListItemSpacing::Sparse)
+            .selected(selected);
+        match self.matches.get(ix)? {
+            SavedSearchEntry::Saved(mat) => {
+                let search = &self.saved_searches[mat.candidate_id];
+                Some(
+                    item.child(
+                        v_flex()
+                            .child(HighlightedLabel::new(
+                                mat.string.clone(),
+                                mat.positions.clone(),
+                            ))
+                            .child(
+                                Label::new(search.query.clone())
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    )
+                    .end_slot(
+                        IconButton::new(("delete-saved-search", ix), IconName::Trash)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("Delete Saved Search", cx))
+                            .on_click(cx.listener(move |picker, _, cx| {
+                                cx.stop_propagation();
+                                picker.delegate.delete(ix, cx);
+                                picker.refresh(cx);
+                            })),
+                    ),
+                )
+            }
+            SavedSearchEntry::SaveCurrent {
+                name,
+                replaces_existing,
+            } => {
+                let label = if *replaces_existing {
+                    format!("Replace saved search '{name}' with the current search")
+                } else {
+                    format!("Save current search as '{name}'")
+                };
+                Some(item.child(Label::new(label)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    async fn test_saved_searches() {
+        let db = SavedSearchesDb(db::open_test_db("test_saved_searches").await);
+        assert_eq!(db.saved_searches().unwrap(), Vec::new());
+
+        let todos = SavedSearch {
+            name: "todos".to_string(),
+            query: "TODO|FIXME".to_string(),
+            options: SearchOptions::REGEX | SearchOptions::CASE_SENSITIVE,
+            files_to_include: "crates/**".to_string(),
+            files_to_exclude: "*.lock".to_string(),
+        };
+        let unwraps = SavedSearch {
+            name: "unwraps".to_string(),
+            query: "$x.unwrap()".to_string(),
+            options: SearchOptions::STRUCTURAL,
+            files_to_include: String::new(),
+            files_to_exclude: String::new(),
+        };
+        db.save_search(unwraps.clone()).await.unwrap();
+        db.save_search(todos.clone()).await.unwrap();
+        assert_eq!(
+            db.saved_searches().unwrap(),
+            vec![todos.clone(), unwraps.clone()]
+        );
+
+        let todos = SavedSearch {
+            options: SearchOptions::REGEX,
+            ..todos
+        };
+        db.save_search(todos.clone()).await.unwrap();
+        assert_eq!(
+            db.saved_searches().unwrap(),
+            vec![todos.clone(), unwraps.clone()]
+        );
+
+        db.delete_saved_search("unwraps".to_string()).await.unwrap();
+        assert_eq!(db.saved_searches().unwrap(), vec![todos]);
+    }
+}
@@ -11,6 +11,8 @@ use workspace::{Toast, Workspace};
 
 pub mod buffer_search;
 pub mod project_search;
+mod project_search_export;
+pub mod saved_searches;
 pub(crate) mod search_bar;
 
 pub fn init(cx: &mut AppContext) {
@@ -27,6 +29,9 @@ actions!(
         ToggleCaseSensitive,
         ToggleIncludeIgnored,
         ToggleRegex,
+        ToggleAdvancedRegex,
+        ToggleStructural,
+        ToggleSemantic,
         ToggleReplace,
         ToggleSelection,
         SelectNextMatch,
@@ -47,6 +52,9 @@ bitflags! {
         const CASE_SENSITIVE = 0b010;
         const INCLUDE_IGNORED = 0b100;
         const REGEX = 0b1000;
+        const STRUCTURAL = 0b10000;
+        const ADVANCED_REGEX = 0b100000;
+        const SEMANTIC = 0b1000000;
     }
 }
 
@@ -57,6 +65,9 @@ impl SearchOptions {
             SearchOptions::CASE_SENSITIVE => "Match Case Sensitively",
             SearchOptions::INCLUDE_IGNORED => "Also search files ignored by configuration",
             SearchOptions::REGEX => "Use Regular Expressions",
+            SearchOptions::STRUCTURAL => "Match Syntax Structure",
+            SearchOptions::ADVANCED_REGEX => "Allow Lookarounds and Backreferences in Regex",
+            SearchOptions::SEMANTIC => "Search by Meaning",
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -67,6 +78,9 @@ impl SearchOptions {
             SearchOptions::CASE_SENSITIVE => ui::IconName::CaseSensitive,
             SearchOptions::INCLUDE_IGNORED => ui::IconName::Sliders,
             SearchOptions::REGEX => ui::IconName::Regex,
+            SearchOptions::STRUCTURAL => ui::IconName::Code,
+            SearchOptions::ADVANCED_REGEX => ui::IconName::Microscope,
+            SearchOptions::SEMANTIC => ui::IconName::Sparkle,
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -77,6 +91,9 @@ impl SearchOptions {
             SearchOptions::CASE_SENSITIVE => Box::new(ToggleCaseSensitive),
             SearchOptions::INCLUDE_IGNORED => Box::new(ToggleIncludeIgnored),
             SearchOptions::REGEX => Box::new(ToggleRegex),
+            SearchOptions::STRUCTURAL => Box::new(ToggleStructural),
+            SearchOptions::ADVANCED_REGEX => Box::new(ToggleAdvancedRegex),
+            SearchOptions::SEMANTIC => Box::new(ToggleSemantic),
             _ => panic!("{:?} is not a named SearchOption", self),
         }
     }
@@ -91,6 +108,8 @@ impl SearchOptions {
         options.set(SearchOptions::CASE_SENSITIVE, query.case_sensitive());
         options.set(SearchOptions::INCLUDE_IGNORED, query.include_ignored());
         options.set(SearchOptions::REGEX, query.is_regex());
+        options.set(SearchOptions::STRUCTURAL, query.is_structural());
+        options.set(SearchOptions::ADVANCED_REGEX, query.is_advanced_regex());
         options
     }
 
@@ -103,6 +122,26 @@ impl SearchOptions {
         options
     }
 
+    /// Toggles an option along with the ones it implies or can't be combined with: a query is
+    /// either a regular expression, which may be advanced, a structural pattern, or a semantic
+    /// query.
+    pub fn toggle_exclusive(&mut self, option: SearchOptions) {
+        let regex = SearchOptions::REGEX | SearchOptions::ADVANCED_REGEX;
+        self.toggle(option);
+        if self.contains(option) {
+            if option == SearchOptions::STRUCTURAL {
+                self.remove(regex | SearchOptions::SEMANTIC);
+            } else if option == SearchOptions::SEMANTIC {
+                self.remove(regex | SearchOptions::STRUCTURAL);
+            } else if regex.contains(option) {
+                self.insert(SearchOptions::REGEX);
+                self.remove(SearchOptions::STRUCTURAL | SearchOptions::SEMANTIC);
+            }
+        } else if option == SearchOptions::REGEX {
+            self.remove(SearchOptions::ADVANCED_REGEX);
+        }
+    }
+
     pub fn as_button(
         &self,
         active: bool,
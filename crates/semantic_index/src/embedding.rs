@@ -1,8 +1,12 @@
 mod cloud;
+#[cfg(feature = "local-embeddings")]
+mod local;
 mod ollama;
 mod open_ai;
 
 pub use cloud::*;
+#[cfg(feature = "local-embeddings")]
+pub use local::*;
 pub use ollama::*;
 pub use open_ai::*;
 use sha2::{Digest, Sha256};
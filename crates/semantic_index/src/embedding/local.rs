@@ -0,0 +1,289 @@
+use anyhow::{anyhow, Context as _, Result};
+use futures::{future::BoxFuture, FutureExt};
+use ndarray::Array2;
+use ort::{GraphOptimizationLevel, Session};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+use crate::{Embedding, EmbeddingProvider, TextToEmbed};
+
+/// The number of tokens texts are truncated to, unless the model's tokenizer specifies it.
+const DEFAULT_MAX_TOKENS: usize = 512;
+/// The number of texts embedded together, which are padded to the length of the longest one.
+const BATCH_SIZE: usize = 32;
+
+/// Computes embeddings on the CPU with a sentence embedding model exported to ONNX, such as
+/// `all-MiniLM-L6-v2` or `bge-small-en-v1.5`, so that projects can be indexed without a network.
+pub struct LocalEmbeddingProvider {
+    model: Arc<LocalEmbeddingModel>,
+}
+
+struct LocalEmbeddingModel {
+    id: String,
+    session: Session,
+    tokenizer: Tokenizer,
+    takes_token_type_ids: bool,
+}
+
+impl LocalEmbeddingProvider {
+    /// Loads the model at the given path, which is either an `.onnx` file or a directory containing
+    /// a `model.onnx` file. The model's `tokenizer.json` must be in the same directory.
+    ///
+    /// This blocks while the model is read and optimized, so it should be run in the background.
+    pub fn load(path: &Path, threads: Option<usize>) -> Result<Self> {
+        let (model_path, tokenizer_path) = model_files(path);
+        let model_bytes = std::fs::read(&model_path)
+            .with_context(|| format!("failed to read embedding model {model_path:?}"))?;
+        let tokenizer_bytes = std::fs::read(&tokenizer_path)
+            .with_context(|| format!("failed to read tokenizer {tokenizer_path:?}"))?;
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&model_bytes);
+        hasher.update(&tokenizer_bytes);
+        let id = hasher.finalize().to_hex()[..16].to_string();
+
+        let mut tokenizer =
+            Tokenizer::from_bytes(&tokenizer_bytes).map_err(|error| anyhow!(error))?;
+        let padding = tokenizer.get_padding().cloned().unwrap_or_default();
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::BatchLongest,
+            ..padding
+        }));
+        if tokenizer.get_truncation().is_none() {
+            tokenizer
+                .with_truncation(Some(TruncationParams {
+                    max_length: DEFAULT_MAX_TOKENS,
+                    ..Default::default()
+                }))
+                .map_err(|error| anyhow!(error))?;
+        }
+
+        let mut session_builder =
+            Session::builder()?.with_optimization_level(GraphOptimizationLevel::Level3)?;
+        if let Some(threads) = threads {
+            session_builder = session_builder.with_intra_threads(threads.max(1))?;
+        }
+        let session = session_builder
+            .commit_from_memory(&model_bytes)
+            .with_context(|| format!("failed to load embedding model {model_path:?}"))?;
+        let takes_token_type_ids = session
+            .inputs
+            .iter()
+            .any(|input| input.name == "token_type_ids");
+
+        Ok(Self {
+            model: Arc::new(LocalEmbeddingModel {
+                id,
+                session,
+                tokenizer,
+                takes_token_type_ids,
+            }),
+        })
+    }
+
+    /// Identifies the model by its contents, so that indices built with a different model
+    /// can be told apart.
+    pub fn model_id(&self) -> &str {
+        &self.model.id
+    }
+}
+
+impl EmbeddingProvider for LocalEmbeddingProvider {
+    fn embed<'a>(&'a self, texts: &'a [TextToEmbed<'a>]) -> BoxFuture<'a, Result<Vec<Embedding>>> {
+        let model = self.model.clone();
+        let texts = texts
+            .iter()
+            .map(|to_embed| to_embed.text.to_string())
+            .collect::<Vec<_>>();
+        smol::unblock(move || {
+            let mut embeddings = Vec::with_capacity(texts.len());
+            for batch in texts.chunks(BATCH_SIZE) {
+                embeddings.extend(model.embed_batch(batch)?);
+            }
+            Ok(embeddings)
+        })
+        .boxed()
+    }
+
+    fn batch_size(&self) -> usize {
+        BATCH_SIZE
+    }
+}
+
+impl LocalEmbeddingModel {
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Embedding>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|error| anyhow!(error))?;
+        let batch_len = encodings.len();
+        let sequence_len = encodings
+            .first()
+            .map_or(0, |encoding| encoding.get_ids().len());
+
+        let mut input_ids = Vec::with_capacity(batch_len * sequence_len);
+        let mut attention_mask = Vec::with_capacity(batch_len * sequence_len);
+        let mut token_type_ids = Vec::with_capacity(batch_len * sequence_len);
+        for encoding in &encodings {
+            input_ids.extend(encoding.get_ids().iter().map(|&id| id as i64));
+            attention_mask.extend(
+                encoding
+                    .get_attention_mask()
+                    .iter()
+                    .map(|&mask| mask as i64),
+            );
+            token_type_ids.extend(encoding.get_type_ids().iter().map(|&id| id as i64));
+        }
+
+        let shape = (batch_len, sequence_len);
+        let input_ids = Array2::from_shape_vec(shape, input_ids)?;
+        let attention_mask_array = Array2::from_shape_vec(shape, attention_mask.clone())?;
+        let outputs = if self.takes_token_type_ids {
+            let token_type_ids = Array2::from_shape_vec(shape, token_type_ids)?;
+            self.session.run(ort::inputs![
+                "input_ids" => input_ids,
+                "attention_mask" => attention_mask_array,
+                "token_type_ids" => token_type_ids
+            ]?)?
+        } else {
+            self.session.run(ort::inputs![
+                "input_ids" => input_ids,
+                "attention_mask" => attention_mask_array
+            ]?)?
+        };
+
+        // Some exports include the pooling in the model, and others only output the embedding
+        // of each token, which we average.
+        if let Some(sentence_embeddings) = outputs.get("sentence_embedding") {
+            let sentence_embeddings = sentence_embeddings.try_extract_tensor::<f32>()?;
+            let dimensions = sentence_embeddings.shape().last().copied().unwrap_or(0);
+            let values = sentence_embeddings.as_standard_layout();
+            let values = values
+                .as_slice()
+                .context("sentence embeddings are not contiguous")?;
+            return Ok(values
+                .chunks(dimensions.max(1))
+                .map(|embedding| Embedding::new(embedding.to_vec()))
+                .collect());
+        }
+
+        let token_embeddings = outputs
+            .get("last_hidden_state")
+            .or_else(|| {
+                let first_output = &self.session.outputs.first()?.name;
+                outputs.get(first_output.as_str())
+            })
+            .context("embedding model has no outputs")?
+            .try_extract_tensor::<f32>()?;
+        let &[_, _, dimensions] = token_embeddings.shape() else {
+            return Err(anyhow!(
+                "expected token embeddings of shape [batch, sequence, dimensions], got {:?}",
+                token_embeddings.shape()
+            ));
+        };
+        let values = token_embeddings.as_standard_layout();
+        let values = values
+            .as_slice()
+            .context("token embeddings are not contiguous")?;
+
+        Ok(mean_pool(values, &attention_mask, sequence_len, dimensions)
+            .into_iter()
+            .map(Embedding::new)
+            .collect())
+    }
+}
+
+/// Returns the paths of the ONNX model and tokenizer for the given model path.
+fn model_files(path: &Path) -> (PathBuf, PathBuf) {
+    if path
+        .extension()
+        .map_or(false, |extension| extension == "onnx")
+    {
+        let directory = path.parent().unwrap_or(Path::new(""));
+        (path.to_path_buf(), directory.join("tokenizer.json"))
+    } else {
+        (path.join("model.onnx"), path.join("tokenizer.json"))
+    }
+}
+
+/// Averages the embeddings of each text's tokens, ignoring padding.
+///
+/// `token_embeddings` holds `sequence_len` embeddings of `dimensions` values for each text,
+/// and `attention_mask` is 1 for each of their tokens that isn't padding.
+fn mean_pool(
+    token_embeddings: &[f32],
+    attention_mask: &[i64],
+    sequence_len: usize,
+    dimensions: usize,
+) -> Vec<Vec<f32>> {
+    attention_mask
+        .chunks(sequence_len.max(1))
+        .zip(token_embeddings.chunks((sequence_len * dimensions).max(1)))
+        .map(|(mask, tokens)| {
+            let mut sum = vec![0f32; dimensions];
+            let mut token_count = 0;
+            for (token, _) in tokens
+                .chunks(dimensions.max(1))
+                .zip(mask)
+                .filter(|(_, &mask)| mask != 0)
+            {
+                for (sum, value) in sum.iter_mut().zip(token) {
+                    *sum += value;
+                }
+                token_count += 1;
+            }
+
+            if token_count > 0 {
+                for value in &mut sum {
+                    *value /= token_count as f32;
+                }
+            }
+            sum
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_files() {
+        assert_eq!(
+            model_files(Path::new("/models/bge-small")),
+            (
+                PathBuf::from("/models/bge-small/model.onnx"),
+                PathBuf::from("/models/bge-small/tokenizer.json")
+            )
+        );
+        assert_eq!(
+            model_files(Path::new("/models/bge-small/onnx/model_quantized.onnx")),
+            (
+                PathBuf::from("/models/bge-small/onnx/model_quantized.onnx"),
+                PathBuf::from("/models/bge-small/onnx/tokenizer.json")
+            )
+        );
+    }
+
+    #[test]
+    fn test_mean_pool() {
+        #[rustfmt::skip]
+        let token_embeddings = [
+            // The first text has two tokens.
+            1., 2.,
+            3., 4.,
+            // The second text has one token, followed by padding.
+            5., 6.,
+            100., 100.,
+        ];
+        let attention_mask = [1, 1, 1, 0];
+
+        assert_eq!(
+            mean_pool(&token_embeddings, &attention_mask, 2, 2),
+            vec![vec![2., 3.], vec![5., 6.]]
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::{
     chunking::{self, Chunk},
     embedding::{Embedding, EmbeddingProvider, TextToEmbed},
     indexing::{IndexingEntryHandle, IndexingEntrySet},
+    keyword_index::KeywordFile,
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::Bound;
@@ -32,6 +33,7 @@ pub struct EmbeddingIndex {
     worktree: Model<Worktree>,
     db_connection: heed::Env,
     db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+    keyword_db: heed::Database<Str, SerdeBincode<KeywordFile>>,
     fs: Arc<dyn Fs>,
     language_registry: Arc<LanguageRegistry>,
     embedding_provider: Arc<dyn EmbeddingProvider>,
@@ -44,6 +46,7 @@ impl EmbeddingIndex {
         fs: Arc<dyn Fs>,
         db_connection: heed::Env,
         embedding_db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+        keyword_db: heed::Database<Str, SerdeBincode<KeywordFile>>,
         language_registry: Arc<LanguageRegistry>,
         embedding_provider: Arc<dyn EmbeddingProvider>,
         entry_ids_being_indexed: Arc<IndexingEntrySet>,
@@ -53,6 +56,7 @@ impl EmbeddingIndex {
             fs,
             db_connection,
             db: embedding_db,
+            keyword_db,
             language_registry,
             embedding_provider,
             entry_ids_being_indexed,
@@ -63,6 +67,10 @@ impl EmbeddingIndex {
         &self.db
     }
 
+    pub fn keyword_db(&self) -> &heed::Database<Str, SerdeBincode<KeywordFile>> {
+        &self.keyword_db
+    }
+
     pub fn index_entries_changed_on_disk(
         &self,
         cx: &AppContext,
@@ -74,11 +82,13 @@ impl EmbeddingIndex {
         let worktree = self.worktree.read(cx).snapshot();
         let worktree_abs_path = worktree.abs_path().clone();
         let scan = self.scan_entries(worktree, cx);
+        let backfill =
+            self.backfill_keywords(worktree_abs_path.clone(), scan.keyword_backfills, cx);
         let chunk = self.chunk_files(worktree_abs_path, scan.updated_entries, cx);
         let embed = Self::embed_files(self.embedding_provider.clone(), chunk.files, cx);
         let persist = self.persist_embeddings(scan.deleted_entry_ranges, embed.files, cx);
         async move {
-            futures::try_join!(scan.task, chunk.task, embed.task, persist)?;
+            futures::try_join!(scan.task, backfill, chunk.task, embed.task, persist)?;
             Ok(())
         }
         .boxed()
@@ -109,8 +119,10 @@ impl EmbeddingIndex {
     fn scan_entries(&self, worktree: Snapshot, cx: &AppContext) -> ScanEntries {
         let (updated_entries_tx, updated_entries_rx) = channel::bounded(512);
         let (deleted_entry_ranges_tx, deleted_entry_ranges_rx) = channel::bounded(128);
+        let (keyword_backfills_tx, keyword_backfills_rx) = channel::bounded(512);
         let db_connection = self.db_connection.clone();
         let db = self.db;
+        let keyword_db = self.keyword_db;
         let entries_being_indexed = self.entry_ids_being_indexed.clone();
         let task = cx.background_executor().spawn(async move {
             let txn = db_connection
@@ -152,6 +164,15 @@ impl EmbeddingIndex {
                                         .await?;
                                 }
                                 saved_mtime = db_embedded_file.mtime;
+                                // Files embedded before the keyword index existed have
+                                // their keywords computed from their stored chunks.
+                                if entry.mtime == saved_mtime
+                                    && keyword_db.get(&txn, db_path)?.is_none()
+                                {
+                                    keyword_backfills_tx
+                                        .send((entry.path.clone(), db_embedded_file.chunks.clone()))
+                                        .await?;
+                                }
                                 db_entries.next();
                                 break;
                             }
@@ -182,6 +203,7 @@ impl EmbeddingIndex {
         ScanEntries {
             updated_entries: updated_entries_rx,
             deleted_entry_ranges: deleted_entry_ranges_rx,
+            keyword_backfills: keyword_backfills_rx,
             task,
         }
     }
@@ -194,6 +216,8 @@ impl EmbeddingIndex {
     ) -> ScanEntries {
         let (updated_entries_tx, updated_entries_rx) = channel::bounded(512);
         let (deleted_entry_ranges_tx, deleted_entry_ranges_rx) = channel::bounded(128);
+        // Updated files are indexed again, so they never need their keywords backfilled.
+        let (_, keyword_backfills_rx) = channel::bounded(1);
         let entries_being_indexed = self.entry_ids_being_indexed.clone();
         let task = cx.background_executor().spawn(async move {
             for (path, entry_id, status) in updated_entries.iter() {
@@ -226,10 +250,56 @@ impl EmbeddingIndex {
         ScanEntries {
             updated_entries: updated_entries_rx,
             deleted_entry_ranges: deleted_entry_ranges_rx,
+            keyword_backfills: keyword_backfills_rx,
             task,
         }
     }
 
+    /// Computes the keywords of files that were embedded before the keyword index existed,
+    /// using the chunks stored with their embeddings instead of embedding them again.
+    fn backfill_keywords(
+        &self,
+        worktree_abs_path: Arc<Path>,
+        files: channel::Receiver<(Arc<Path>, Vec<EmbeddedChunk>)>,
+        cx: &AppContext,
+    ) -> Task<Result<()>> {
+        let language_registry = self.language_registry.clone();
+        let fs = self.fs.clone();
+        let db_connection = self.db_connection.clone();
+        let keyword_db = self.keyword_db;
+        cx.background_executor().spawn(async move {
+            while let Ok((path, embedded_chunks)) = files.recv().await {
+                let Some(text) = fs.load(&worktree_abs_path.join(&path)).await.log_err() else {
+                    continue;
+                };
+                let chunks = embedded_chunks
+                    .into_iter()
+                    .map(|embedded_chunk| embedded_chunk.chunk)
+                    .collect::<Vec<_>>();
+                if chunks
+                    .iter()
+                    .any(|chunk| text.get(chunk.range.clone()).is_none())
+                {
+                    log::warn!("stored chunks don't match the contents of {path:?}");
+                    continue;
+                }
+
+                let language = language_registry
+                    .language_for_file_path(&path)
+                    .await
+                    .ok()
+                    .map(|language| language.name().0);
+                let keyword_file = KeywordFile::new(path.clone(), language, &text, &chunks);
+
+                let mut txn = db_connection.write_txn()?;
+                log::debug!("backfilling keywords for file {:?}", path);
+                keyword_db.put(&mut txn, &db_key_for_path(&path), &keyword_file)?;
+                txn.commit()?;
+            }
+            Ok(())
+        })
+    }
+
     fn chunk_files(
         &self,
         worktree_abs_path: Arc<Path>,
@@ -257,6 +327,9 @@ impl EmbeddingIndex {
                                             language.as_ref(),
                                             &entry.path,
                                         ),
+                                        language: language
+                                            .as_ref()
+                                            .map(|language| language.name().0),
                                         handle,
                                         path: entry.path,
                                         mtime: entry.mtime,
@@ -327,6 +400,12 @@ impl EmbeddingIndex {
 
                 let mut embeddings = embeddings.into_iter();
                 for chunked_file in chunked_files {
+                    let keyword_file = KeywordFile::new(
+                        chunked_file.path.clone(),
+                        chunked_file.language.clone(),
+                        &chunked_file.text,
+                        &chunked_file.chunks,
+                    );
                     let mut embedded_file = EmbeddedFile {
                         path: chunked_file.path,
                         mtime: chunked_file.mtime,
@@ -348,7 +427,7 @@ impl EmbeddingIndex {
 
                     if embedded_all_chunks {
                         embedded_files_tx
-                            .send((embedded_file, chunked_file.handle))
+                            .send((embedded_file, keyword_file, chunked_file.handle))
                             .await?;
                     }
                 }
@@ -365,11 +444,12 @@ impl EmbeddingIndex {
     fn persist_embeddings(
         &self,
         mut deleted_entry_ranges: channel::Receiver<(Bound<String>, Bound<String>)>,
-        mut embedded_files: channel::Receiver<(EmbeddedFile, IndexingEntryHandle)>,
+        mut embedded_files: channel::Receiver<(EmbeddedFile, KeywordFile, IndexingEntryHandle)>,
         cx: &AppContext,
     ) -> Task<Result<()>> {
         let db_connection = self.db_connection.clone();
         let db = self.db;
+        let keyword_db = self.keyword_db;
 
         cx.background_executor().spawn(async move {
             loop {
@@ -382,15 +462,17 @@ impl EmbeddingIndex {
                             let end = deletion_range.1.as_ref().map(|end| end.as_str());
                             log::debug!("deleting embeddings in range {:?}", &(start, end));
                             db.delete_range(&mut txn, &(start, end))?;
+                            keyword_db.delete_range(&mut txn, &(start, end))?;
                             txn.commit()?;
                         }
                     },
                     file = embedded_files.next() => {
-                        if let Some((file, _)) = file {
+                        if let Some((file, keyword_file, _)) = file {
                             let mut txn = db_connection.write_txn()?;
                             log::debug!("saving embedding for file {:?}", file.path);
                             let key = db_key_for_path(&file.path);
                             db.put(&mut txn, &key, &file)?;
+                            keyword_db.put(&mut txn, &key, &keyword_file)?;
                             txn.commit()?;
                         }
                     },
@@ -441,6 +523,7 @@ impl EmbeddingIndex {
 struct ScanEntries {
     updated_entries: channel::Receiver<(Entry, IndexingEntryHandle)>,
     deleted_entry_ranges: channel::Receiver<(Bound<String>, Bound<String>)>,
+    keyword_backfills: channel::Receiver<(Arc<Path>, Vec<EmbeddedChunk>)>,
     task: Task<Result<()>>,
 }
 
@@ -452,13 +535,14 @@ struct ChunkFiles {
 pub struct ChunkedFile {
     pub path: Arc<Path>,
     pub mtime: Option<SystemTime>,
+    pub language: Option<Arc<str>>,
     pub handle: IndexingEntryHandle,
     pub text: String,
     pub chunks: Vec<Chunk>,
 }
 
 pub struct EmbedFiles {
-    pub files: channel::Receiver<(EmbeddedFile, IndexingEntryHandle)>,
+    pub files: channel::Receiver<(EmbeddedFile, KeywordFile, IndexingEntryHandle)>,
     pub task: Task<Result<()>>,
 }
 
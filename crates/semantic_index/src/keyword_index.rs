@@ -0,0 +1,303 @@
+use crate::chunking::Chunk;
+use serde::{Deserialize, Serialize};
+use std::{ops::Range, path::Path, sync::Arc};
+
+/// BM25's term frequency saturation parameter.
+const BM25_K1: f32 = 1.2;
+/// BM25's document length normalization parameter.
+const BM25_B: f32 = 0.75;
+
+/// The terms of a file's chunks, stored alongside its embeddings to rank chunks by keyword.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeywordFile {
+    pub path: Arc<Path>,
+    pub language: Option<Arc<str>>,
+    pub chunks: Vec<KeywordChunk>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeywordChunk {
+    pub range: Range<usize>,
+    /// The number of terms in the chunk.
+    pub length: u32,
+    /// The number of occurrences of each term in the chunk, sorted by term.
+    pub terms: Vec<(String, u32)>,
+}
+
+impl KeywordFile {
+    pub fn new(path: Arc<Path>, language: Option<Arc<str>>, text: &str, chunks: &[Chunk]) -> Self {
+        Self {
+            path,
+            language,
+            chunks: chunks
+                .iter()
+                .map(|chunk| KeywordChunk::new(chunk.range.clone(), &text[chunk.range.clone()]))
+                .collect(),
+        }
+    }
+}
+
+impl KeywordChunk {
+    pub fn new(range: Range<usize>, text: &str) -> Self {
+        let mut terms = tokenize(text);
+        let length = terms.len() as u32;
+        terms.sort_unstable();
+
+        let mut term_counts = Vec::<(String, u32)>::new();
+        for term in terms {
+            match term_counts.last_mut() {
+                Some((last_term, count)) if *last_term == term => *count += 1,
+                _ => term_counts.push((term, 1)),
+            }
+        }
+
+        Self {
+            range,
+            length,
+            terms: term_counts,
+        }
+    }
+
+    pub fn term_frequency(&self, term: &str) -> u32 {
+        self.terms
+            .binary_search_by(|(probe, _)| probe.as_str().cmp(term))
+            .map_or(0, |ix| self.terms[ix].1)
+    }
+}
+
+/// Splits text into lowercase terms.
+///
+/// Identifiers produce a term for the whole identifier, so exact matches rank
+/// highest, as well as one for each of their words, so that `ProjectIndex` and
+/// `project_index` can be found by searching for `index`.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for identifier in text
+        .split(|char: char| !char.is_alphanumeric() && char != '_')
+        .filter(|identifier| !identifier.is_empty())
+    {
+        let words = identifier_words(identifier);
+        if words.len() != 1 {
+            terms.push(identifier.to_lowercase());
+        }
+        terms.extend(words.into_iter().map(|word| word.to_lowercase()));
+    }
+    terms
+}
+
+/// Splits an identifier at underscores and case changes.
+fn identifier_words(identifier: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in identifier.split('_').filter(|part| !part.is_empty()) {
+        let chars = part.char_indices().collect::<Vec<_>>();
+        let mut word_start = 0;
+        for window in 1..chars.len() {
+            let (ix, char) = chars[window];
+            let (_, prev_char) = chars[window - 1];
+            let next_char = chars.get(window + 1).map(|(_, char)| *char);
+
+            // Split `fooBar` before `B`, and `HTTPServer` before `S`.
+            let is_boundary = char.is_uppercase()
+                && (prev_char.is_lowercase()
+                    || prev_char.is_numeric()
+                    || (prev_char.is_uppercase()
+                        && next_char.is_some_and(|next| next.is_lowercase())));
+            if is_boundary {
+                words.push(&part[word_start..ix]);
+                word_start = ix;
+            }
+        }
+        words.push(&part[word_start..]);
+    }
+    words
+}
+
+/// The terms of a set of search queries.
+pub struct KeywordQuery {
+    /// The distinct terms across all of the queries.
+    pub terms: Vec<String>,
+    /// The indices into `terms` of each query's terms.
+    query_terms: Vec<Vec<usize>>,
+}
+
+impl KeywordQuery {
+    pub fn new(queries: &[String]) -> Self {
+        let mut terms = Vec::<String>::new();
+        let query_terms = queries
+            .iter()
+            .map(|query| {
+                let mut indices = Vec::new();
+                for term in tokenize(query) {
+                    let ix = match terms.iter().position(|existing| *existing == term) {
+                        Some(ix) => ix,
+                        None => {
+                            terms.push(term);
+                            terms.len() - 1
+                        }
+                    };
+                    if !indices.contains(&ix) {
+                        indices.push(ix);
+                    }
+                }
+                indices
+            })
+            .collect();
+
+        Self { terms, query_terms }
+    }
+
+    /// Returns the frequency of each of the query's terms in the given chunk.
+    pub fn term_frequencies(&self, chunk: &KeywordChunk) -> Vec<u32> {
+        self.terms
+            .iter()
+            .map(|term| chunk.term_frequency(term))
+            .collect()
+    }
+
+    /// Returns the BM25 score of a chunk for the query it matches best, along with the index of that query.
+    pub fn score(
+        &self,
+        term_frequencies: &[u32],
+        chunk_length: u32,
+        document_frequencies: &[u32],
+        stats: &CorpusStats,
+    ) -> (f32, usize) {
+        let chunk_count = stats.chunk_count as f32;
+        let average_length = stats.average_length();
+
+        self.query_terms
+            .iter()
+            .enumerate()
+            .map(|(query_index, term_indices)| {
+                let score = term_indices
+                    .iter()
+                    .map(|&term_ix| {
+                        let term_frequency = term_frequencies[term_ix] as f32;
+                        if term_frequency == 0. {
+                            return 0.;
+                        }
+
+                        let document_frequency = document_frequencies[term_ix] as f32;
+                        let inverse_document_frequency = ((chunk_count - document_frequency + 0.5)
+                            / (document_frequency + 0.5)
+                            + 1.)
+                            .ln();
+                        let length_ratio = chunk_length as f32 / average_length;
+                        inverse_document_frequency * term_frequency * (BM25_K1 + 1.)
+                            / (term_frequency + BM25_K1 * (1. - BM25_B + BM25_B * length_ratio))
+                    })
+                    .sum::<f32>();
+                (score, query_index)
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap_or((0., 0))
+    }
+}
+
+/// The statistics of the chunks being searched, which are used to weigh terms in BM25.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CorpusStats {
+    pub chunk_count: u64,
+    pub total_length: u64,
+}
+
+impl CorpusStats {
+    pub fn add_chunk(&mut self, chunk: &KeywordChunk) {
+        self.chunk_count += 1;
+        self.total_length += chunk.length as u64;
+    }
+
+    pub fn merge(&mut self, other: CorpusStats) {
+        self.chunk_count += other.chunk_count;
+        self.total_length += other.total_length;
+    }
+
+    fn average_length(&self) -> f32 {
+        if self.chunk_count == 0 {
+            1.
+        } else {
+            (self.total_length as f32 / self.chunk_count as f32).max(1.)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("let project_index = ProjectIndex::new(HTTPServer, x2);"),
+            vec![
+                "let",
+                "project_index",
+                "project",
+                "index",
+                "projectindex",
+                "project",
+                "index",
+                "new",
+                "httpserver",
+                "http",
+                "server",
+                "x2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keyword_chunk() {
+        let chunk = KeywordChunk::new(0..22, "foo bar foo_baz fooBar");
+        assert_eq!(chunk.length, 8);
+        assert_eq!(chunk.term_frequency("foo"), 3);
+        assert_eq!(chunk.term_frequency("foo_baz"), 1);
+        assert_eq!(chunk.term_frequency("foobar"), 1);
+        assert_eq!(chunk.term_frequency("qux"), 0);
+    }
+
+    #[test]
+    fn test_bm25_ranking() {
+        let chunks = [
+            KeywordChunk::new(
+                0..1,
+                "fn search_with_options(query: &str) { search(query) }",
+            ),
+            KeywordChunk::new(1..2, "fn search(query: &str) { todo!() }"),
+            KeywordChunk::new(2..3, "struct Options { limit: usize }"),
+        ];
+        let mut stats = CorpusStats::default();
+        for chunk in &chunks {
+            stats.add_chunk(chunk);
+        }
+
+        let query = KeywordQuery::new(&["search_with_options".to_string(), "limit".to_string()]);
+        let term_frequencies = chunks
+            .iter()
+            .map(|chunk| query.term_frequencies(chunk))
+            .collect::<Vec<_>>();
+        let document_frequencies = (0..query.terms.len())
+            .map(|term_ix| {
+                term_frequencies
+                    .iter()
+                    .filter(|frequencies| frequencies[term_ix] > 0)
+                    .count() as u32
+            })
+            .collect::<Vec<_>>();
+        let scores = chunks
+            .iter()
+            .zip(&term_frequencies)
+            .map(|(chunk, frequencies)| {
+                query.score(frequencies, chunk.length, &document_frequencies, &stats)
+            })
+            .collect::<Vec<_>>();
+
+        // The exact identifier ranks above the chunk that only shares some of its words.
+        assert_eq!(scores[0].1, 0);
+        assert!(scores[0].0 > scores[1].0);
+        assert!(scores[1].0 > 0.);
+        // The third chunk matches the second query.
+        assert_eq!(scores[2].1, 1);
+        assert!(scores[2].0 > 0.);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
-    embedding::{EmbeddingProvider, TextToEmbed},
+    embedding::{Embedding, EmbeddingProvider, TextToEmbed},
+    keyword_index::{CorpusStats, KeywordQuery},
     summary_index::FileSummary,
     worktree_index::{WorktreeIndex, WorktreeIndexHandle},
 };
@@ -10,7 +11,7 @@ use futures::{stream::StreamExt, FutureExt};
 use gpui::{
     AppContext, Entity, EntityId, EventEmitter, Model, ModelContext, Subscription, Task, WeakModel,
 };
-use language::LanguageRegistry;
+use language::{LanguageName, LanguageRegistry};
 use log;
 use project::{Project, Worktree, WorktreeId};
 use serde::{Deserialize, Serialize};
@@ -23,7 +24,7 @@ use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
-use util::ResultExt;
+use util::{paths::PathMatcher, ResultExt};
 
 #[derive(Debug)]
 pub struct SearchResult {
@@ -51,6 +52,160 @@ pub struct WorktreeSearchResult {
     pub score: f32,
 }
 
+/// How much the keyword score counts towards the score of a hybrid search
+/// result, with the rest coming from embedding similarity.
+const HYBRID_KEYWORD_WEIGHT: f32 = 0.3;
+
+/// How search results are ranked.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// Rank chunks by the similarity of their embedding to the query's.
+    #[default]
+    Semantic,
+    /// Rank chunks by the query's keywords, using BM25.
+    Keyword,
+    /// Rank chunks by a weighted sum of both their semantic and keyword scores.
+    Hybrid,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchOptions {
+    pub mode: SearchMode,
+    /// Only search the files matching these globs, if there are any.
+    pub files_to_include: PathMatcher,
+    /// Don't search the files matching these globs.
+    pub files_to_exclude: PathMatcher,
+    /// Only search files in these languages, if there are any.
+    pub languages: Vec<LanguageName>,
+}
+
+impl SearchOptions {
+    fn matches(&self, worktree_root_name: &str, path: &Path, language: Option<&str>) -> bool {
+        if !self.languages.is_empty()
+            && !language.map_or(false, |language| {
+                self.languages
+                    .iter()
+                    .any(|name| name.0.eq_ignore_ascii_case(language))
+            })
+        {
+            return false;
+        }
+
+        // Globs can be relative to the worktree's root or include its name, as in project search.
+        let full_path = Path::new(worktree_root_name).join(path);
+        let include_all = self.files_to_include.sources().is_empty();
+        !matches_any_ancestor(&self.files_to_exclude, path)
+            && !matches_any_ancestor(&self.files_to_exclude, &full_path)
+            && (include_all
+                || matches_any_ancestor(&self.files_to_include, path)
+                || matches_any_ancestor(&self.files_to_include, &full_path))
+    }
+}
+
+fn matches_any_ancestor(matcher: &PathMatcher, path: &Path) -> bool {
+    !matcher.sources().is_empty()
+        && path
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| matcher.is_match(ancestor))
+}
+
+struct ScannedChunk {
+    worktree_id: WorktreeId,
+    path: Arc<Path>,
+    range: Range<usize>,
+    embedding: Option<Embedding>,
+    length: u32,
+    term_frequencies: Vec<u32>,
+}
+
+struct SearchCandidate {
+    worktree_id: WorktreeId,
+    path: Arc<Path>,
+    range: Range<usize>,
+    vector_score: f32,
+    query_index: usize,
+    length: u32,
+    term_frequencies: Vec<u32>,
+}
+
+#[derive(Default)]
+struct WorkerSearchResults {
+    /// The chunks that don't match any keyword with the highest embedding similarity.
+    top_by_vector: Vec<SearchCandidate>,
+    /// The chunks that match at least one keyword.
+    keyword_matches: Vec<SearchCandidate>,
+}
+
+/// Scores search candidates according to the search mode.
+///
+/// Any chunk that isn't a candidate scores lower than all of the chunks in
+/// `top_by_vector`, as it has no keyword score either.
+fn score_candidates(
+    top_by_vector: Vec<SearchCandidate>,
+    keyword_matches: Vec<SearchCandidate>,
+    keyword_query: &KeywordQuery,
+    stats: &CorpusStats,
+    mode: SearchMode,
+) -> Vec<WorktreeSearchResult> {
+    let document_frequencies = (0..keyword_query.terms.len())
+        .map(|term_ix| {
+            keyword_matches
+                .iter()
+                .filter(|candidate| candidate.term_frequencies[term_ix] > 0)
+                .count() as u32
+        })
+        .collect::<Vec<_>>();
+    let keyword_scores = keyword_matches
+        .iter()
+        .map(|candidate| {
+            keyword_query.score(
+                &candidate.term_frequencies,
+                candidate.length,
+                &document_frequencies,
+                stats,
+            )
+        })
+        .collect::<Vec<_>>();
+    let max_keyword_score = keyword_scores
+        .iter()
+        .map(|(score, _)| *score)
+        .fold(0., f32::max);
+
+    let mut results = Vec::with_capacity(top_by_vector.len() + keyword_matches.len());
+    for (candidate, (keyword_score, keyword_query_index)) in
+        keyword_matches.into_iter().zip(keyword_scores).chain(
+            top_by_vector
+                .into_iter()
+                .map(|candidate| (candidate, (0., 0))),
+        )
+    {
+        let keyword_score = if max_keyword_score > 0. {
+            keyword_score / max_keyword_score
+        } else {
+            0.
+        };
+        let (score, query_index) = match mode {
+            SearchMode::Semantic => (candidate.vector_score, candidate.query_index),
+            SearchMode::Keyword => (keyword_score, keyword_query_index),
+            SearchMode::Hybrid => (
+                (1. - HYBRID_KEYWORD_WEIGHT) * candidate.vector_score
+                    + HYBRID_KEYWORD_WEIGHT * keyword_score,
+                candidate.query_index,
+            ),
+        };
+        results.push(WorktreeSearchResult {
+            worktree_id: candidate.worktree_id,
+            path: candidate.path,
+            range: candidate.range,
+            query_index,
+            score,
+        });
+    }
+    results
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Status {
     Idle,
@@ -234,11 +389,26 @@ impl ProjectIndex {
         limit: usize,
         cx: &AppContext,
     ) -> Task<Result<Vec<SearchResult>>> {
+        self.search_with_options(queries, limit, SearchOptions::default(), cx)
+    }
+
+    pub fn search_with_options(
+        &self,
+        queries: Vec<String>,
+        limit: usize,
+        options: SearchOptions,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<SearchResult>>> {
+        let mode = options.mode;
+        let keyword_query = Arc::new(KeywordQuery::new(&queries));
+        let options = Arc::new(options);
         let (chunks_tx, chunks_rx) = channel::bounded(1024);
         let mut worktree_scan_tasks = Vec::new();
         for worktree_index in self.worktree_indices.values() {
             let worktree_index = worktree_index.clone();
             let chunks_tx = chunks_tx.clone();
+            let keyword_query = keyword_query.clone();
+            let options = options.clone();
             worktree_scan_tasks.push(cx.spawn(|cx| async move {
                 let index = match worktree_index {
                     WorktreeIndexHandle::Loading { index } => {
@@ -249,23 +419,110 @@ impl ProjectIndex {
 
                 index
                     .read_with(&cx, |index, cx| {
-                        let worktree_id = index.worktree().read(cx).id();
+                        let worktree = index.worktree().read(cx);
+                        let worktree_id = worktree.id();
+                        let worktree_root_name = worktree.root_name().to_string();
                         let db_connection = index.db_connection().clone();
                         let db = *index.embedding_index().db();
+                        let keyword_db = *index.embedding_index().keyword_db();
                         cx.background_executor().spawn(async move {
                             let txn = db_connection
                                 .read_txn()
                                 .context("failed to create read transaction")?;
-                            let db_entries = db.iter(&txn).context("failed to iterate database")?;
-                            for db_entry in db_entries {
-                                let (_key, db_embedded_file) = db_entry?;
-                                for chunk in db_embedded_file.chunks {
-                                    chunks_tx
-                                        .send((worktree_id, db_embedded_file.path.clone(), chunk))
-                                        .await?;
+                            let mut stats = CorpusStats::default();
+
+                            if mode == SearchMode::Keyword {
+                                let db_entries = keyword_db
+                                    .iter(&txn)
+                                    .context("failed to iterate database")?;
+                                for db_entry in db_entries {
+                                    let (_key, keyword_file) = db_entry?;
+                                    if !options.matches(
+                                        &worktree_root_name,
+                                        &keyword_file.path,
+                                        keyword_file.language.as_deref(),
+                                    ) {
+                                        continue;
+                                    }
+
+                                    for chunk in keyword_file.chunks {
+                                        stats.add_chunk(&chunk);
+                                        let term_frequencies =
+                                            keyword_query.term_frequencies(&chunk);
+                                        if term_frequencies.iter().all(|&tf| tf == 0) {
+                                            continue;
+                                        }
+
+                                        chunks_tx
+                                            .send(ScannedChunk {
+                                                worktree_id,
+                                                path: keyword_file.path.clone(),
+                                                range: chunk.range,
+                                                embedding: None,
+                                                length: chunk.length,
+                                                term_frequencies,
+                                            })
+                                            .await?;
+                                    }
+                                }
+                            } else {
+                                let needs_keywords =
+                                    mode == SearchMode::Hybrid || !options.languages.is_empty();
+                                let db_entries =
+                                    db.iter(&txn).context("failed to iterate database")?;
+                                for db_entry in db_entries {
+                                    let (key, db_embedded_file) = db_entry?;
+                                    let keyword_file = if needs_keywords {
+                                        keyword_db.get(&txn, key)?
+                                    } else {
+                                        None
+                                    };
+                                    let language = keyword_file
+                                        .as_ref()
+                                        .and_then(|file| file.language.as_deref());
+                                    if !options.matches(
+                                        &worktree_root_name,
+                                        &db_embedded_file.path,
+                                        language,
+                                    ) {
+                                        continue;
+                                    }
+
+                                    for (chunk_ix, chunk) in
+                                        db_embedded_file.chunks.into_iter().enumerate()
+                                    {
+                                        let keyword_chunk = keyword_file
+                                            .as_ref()
+                                            .and_then(|file| file.chunks.get(chunk_ix))
+                                            .filter(|keyword_chunk| {
+                                                keyword_chunk.range == chunk.chunk.range
+                                            });
+                                        let (length, term_frequencies) = match keyword_chunk {
+                                            Some(keyword_chunk) if mode == SearchMode::Hybrid => {
+                                                stats.add_chunk(keyword_chunk);
+                                                (
+                                                    keyword_chunk.length,
+                                                    keyword_query.term_frequencies(keyword_chunk),
+                                                )
+                                            }
+                                            _ => (0, Vec::new()),
+                                        };
+
+                                        chunks_tx
+                                            .send(ScannedChunk {
+                                                worktree_id,
+                                                path: db_embedded_file.path.clone(),
+                                                range: chunk.chunk.range,
+                                                embedding: Some(chunk.embedding),
+                                                length,
+                                                term_frequencies,
+                                            })
+                                            .await?;
+                                    }
                                 }
                             }
-                            anyhow::Ok(())
+
+                            anyhow::Ok(stats)
                         })
                     })?
                     .await
@@ -278,22 +535,28 @@ impl ProjectIndex {
         cx.spawn(|cx| async move {
             #[cfg(debug_assertions)]
             let embedding_query_start = std::time::Instant::now();
-            log::info!("Searching for {queries:?}");
-            let queries: Vec<TextToEmbed> = queries
-                .iter()
-                .map(|s| TextToEmbed::new(s.as_str()))
-                .collect();
-
-            let query_embeddings = embedding_provider.embed(&queries[..]).await?;
-            if query_embeddings.len() != queries.len() {
-                return Err(anyhow!(
-                    "The number of query embeddings does not match the number of queries"
-                ));
-            }
+            log::info!("Searching for {queries:?} ({mode:?})");
+
+            let query_embeddings = if mode == SearchMode::Keyword {
+                Vec::new()
+            } else {
+                let queries: Vec<TextToEmbed> = queries
+                    .iter()
+                    .map(|s| TextToEmbed::new(s.as_str()))
+                    .collect();
+
+                let query_embeddings = embedding_provider.embed(&queries[..]).await?;
+                if query_embeddings.len() != queries.len() {
+                    return Err(anyhow!(
+                        "The number of query embeddings does not match the number of queries"
+                    ));
+                }
+                query_embeddings
+            };
 
             let mut results_by_worker = Vec::new();
             for _ in 0..cx.background_executor().num_cpus() {
-                results_by_worker.push(Vec::<WorktreeSearchResult>::new());
+                results_by_worker.push(WorkerSearchResults::default());
             }
 
             #[cfg(debug_assertions)]
@@ -302,28 +565,39 @@ impl ProjectIndex {
                 .scoped(|cx| {
                     for results in results_by_worker.iter_mut() {
                         cx.spawn(async {
-                            while let Ok((worktree_id, path, chunk)) = chunks_rx.recv().await {
-                                let (score, query_index) =
-                                    chunk.embedding.similarity(&query_embeddings);
+                            while let Ok(chunk) = chunks_rx.recv().await {
+                                let (score, query_index) = match &chunk.embedding {
+                                    Some(embedding) => embedding.similarity(&query_embeddings),
+                                    None => (0., 0),
+                                };
+                                let candidate = SearchCandidate {
+                                    worktree_id: chunk.worktree_id,
+                                    path: chunk.path,
+                                    range: chunk.range,
+                                    vector_score: score,
+                                    query_index,
+                                    length: chunk.length,
+                                    term_frequencies: chunk.term_frequencies,
+                                };
 
-                                let ix = match results.binary_search_by(|probe| {
-                                    score.partial_cmp(&probe.score).unwrap_or(Ordering::Equal)
+                                // Chunks matching any keyword are all kept, as we can only
+                                // score them once we know how many chunks each keyword is in.
+                                if candidate.term_frequencies.iter().any(|&tf| tf > 0) {
+                                    results.keyword_matches.push(candidate);
+                                    continue;
+                                }
+
+                                let ix = match results.top_by_vector.binary_search_by(|probe| {
+                                    score
+                                        .partial_cmp(&probe.vector_score)
+                                        .unwrap_or(Ordering::Equal)
                                 }) {
                                     Ok(ix) | Err(ix) => ix,
                                 };
                                 if ix < limit {
-                                    results.insert(
-                                        ix,
-                                        WorktreeSearchResult {
-                                            worktree_id,
-                                            path: path.clone(),
-                                            range: chunk.chunk.range.clone(),
-                                            query_index,
-                                            score,
-                                        },
-                                    );
-                                    if results.len() > limit {
-                                        results.pop();
+                                    results.top_by_vector.insert(ix, candidate);
+                                    if results.top_by_vector.len() > limit {
+                                        results.top_by_vector.pop();
                                     }
                                 }
                             }
@@ -332,14 +606,26 @@ impl ProjectIndex {
                 })
                 .await;
 
+            let mut stats = CorpusStats::default();
             for scan_task in futures::future::join_all(worktree_scan_tasks).await {
-                scan_task.log_err();
+                if let Some(worktree_stats) = scan_task.log_err() {
+                    stats.merge(worktree_stats);
+                }
             }
 
+            let mut candidates = Vec::new();
+            let mut keyword_matches = Vec::new();
+            for worker_results in results_by_worker {
+                candidates.extend(worker_results.top_by_vector);
+                keyword_matches.extend(worker_results.keyword_matches);
+            }
+            let scored_results =
+                score_candidates(candidates, keyword_matches, &keyword_query, &stats, mode);
+
             project.read_with(&cx, |project, cx| {
-                let mut search_results = Vec::with_capacity(results_by_worker.len() * limit);
-                for worker_results in results_by_worker {
-                    search_results.extend(worker_results.into_iter().filter_map(|result| {
+                let mut search_results = scored_results
+                    .into_iter()
+                    .filter_map(|result| {
                         Some(SearchResult {
                             worktree: project.worktree_for_id(result.worktree_id, cx)?,
                             path: result.path,
@@ -347,8 +633,8 @@ impl ProjectIndex {
                             score: result.score,
                             query_index: result.query_index,
                         })
-                    }));
-                }
+                    })
+                    .collect::<Vec<_>>();
                 search_results.sort_unstable_by(|a, b| {
                     b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
                 });
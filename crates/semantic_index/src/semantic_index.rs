@@ -2,18 +2,22 @@ mod chunking;
 mod embedding;
 mod embedding_index;
 mod indexing;
+mod keyword_index;
 mod project_index;
 mod project_index_debug_view;
+mod semantic_index_settings;
 mod summary_backlog;
 mod summary_index;
 mod worktree_index;
 
 use anyhow::{Context as _, Result};
+use client::Client;
 use collections::HashMap;
 use fs::Fs;
 use gpui::{AppContext, AsyncAppContext, BorrowAppContext, Context, Global, Model, WeakModel};
 use language::LineEnding;
 use project::{Project, Worktree};
+use settings::Settings;
 use std::{
     cmp::Ordering,
     path::{Path, PathBuf},
@@ -24,8 +28,11 @@ use util::ResultExt as _;
 use workspace::Workspace;
 
 pub use embedding::*;
-pub use project_index::{LoadedSearchResult, ProjectIndex, SearchResult, Status};
+pub use project_index::{
+    LoadedSearchResult, ProjectIndex, SearchMode, SearchOptions, SearchResult, Status,
+};
 pub use project_index_debug_view::ProjectIndexDebugView;
+pub use semantic_index_settings::{EmbeddingModelSettings, SemanticIndexSettings};
 pub use summary_index::FileSummary;
 
 pub struct SemanticDb {
@@ -36,7 +43,55 @@ pub struct SemanticDb {
 
 impl Global for SemanticDb {}
 
+/// The databases of local embedding models are named after the model's contents, so that
+/// changing the model starts a new index.
+#[cfg(feature = "local-embeddings")]
+const LOCAL_MODEL_DB_PREFIX: &str = "semantic-index-db.local-";
+
 impl SemanticDb {
+    /// Opens the semantic index in `embeddings_dir`, with the embedding model chosen in the settings.
+    pub async fn open(
+        embeddings_dir: PathBuf,
+        client: Arc<Client>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Self> {
+        let embedding_model = cx.update(|cx| {
+            SemanticIndexSettings::get_global(cx)
+                .embedding_model
+                .clone()
+        })?;
+        let (embedding_provider, db_path): (Arc<dyn EmbeddingProvider>, _) = match embedding_model {
+            EmbeddingModelSettings::ZedDotDev => (
+                Arc::new(CloudEmbeddingProvider::new(client)),
+                embeddings_dir.join("semantic-index-db.0.mdb"),
+            ),
+            #[cfg(feature = "local-embeddings")]
+            EmbeddingModelSettings::Local { path, threads } => {
+                let (embedding_provider, db_path) = cx
+                    .background_executor()
+                    .spawn(async move {
+                        let embedding_provider = LocalEmbeddingProvider::load(&path, threads)?;
+                        let db_name = format!(
+                            "{LOCAL_MODEL_DB_PREFIX}{}.0.mdb",
+                            embedding_provider.model_id()
+                        );
+                        remove_stale_local_model_dbs(&embeddings_dir, &db_name);
+                        anyhow::Ok((embedding_provider, embeddings_dir.join(db_name)))
+                    })
+                    .await?;
+                (Arc::new(embedding_provider), db_path)
+            }
+            #[cfg(not(feature = "local-embeddings"))]
+            EmbeddingModelSettings::Local { .. } => {
+                anyhow::bail!(
+                    "local embedding models require building with the `local-embeddings` feature"
+                );
+            }
+        };
+
+        Self::new(db_path, embedding_provider, cx).await
+    }
+
     pub async fn new(
         db_path: PathBuf,
         embedding_provider: Arc<dyn EmbeddingProvider>,
@@ -63,7 +118,9 @@ impl SemanticDb {
 
                     if cx.has_global::<SemanticDb>() {
                         cx.update_global::<SemanticDb, _>(|this, cx| {
-                            this.create_project_index(project, cx);
+                            if !this.project_indices.contains_key(&project.downgrade()) {
+                                this.create_project_index(project, cx);
+                            }
                         })
                     } else {
                         log::info!("No SemanticDb, skipping project index")
@@ -207,13 +264,30 @@ impl SemanticDb {
     }
 
     pub fn project_index(
-        &mut self,
+        &self,
         project: Model<Project>,
-        _cx: &mut AppContext,
+        _cx: &AppContext,
     ) -> Option<Model<ProjectIndex>> {
         self.project_indices.get(&project.downgrade()).cloned()
     }
 
+    /// Creates the indices of the projects that are already open, such as when the semantic
+    /// index is reopened with a different embedding model.
+    pub fn index_open_projects(&mut self, cx: &mut AppContext) {
+        let projects = cx
+            .windows()
+            .into_iter()
+            .filter_map(|window| window.downcast::<Workspace>())
+            .filter_map(|workspace| workspace.read(cx).ok())
+            .map(|workspace| workspace.project().clone())
+            .collect::<Vec<_>>();
+        for project in projects {
+            if !self.project_indices.contains_key(&project.downgrade()) {
+                self.create_project_index(project, cx);
+            }
+        }
+    }
+
     pub fn remaining_summaries(
         &self,
         project: &WeakModel<Project>,
@@ -257,6 +331,24 @@ impl SemanticDb {
     }
 }
 
+/// Removes the databases of the local embedding models other than the current one, as they
+/// would never be used again unless the model is changed back.
+#[cfg(feature = "local-embeddings")]
+fn remove_stale_local_model_dbs(embeddings_dir: &Path, current_db_name: &str) {
+    let Ok(entries) = std::fs::read_dir(embeddings_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        if file_name.starts_with(LOCAL_MODEL_DB_PREFIX) && file_name != current_db_name {
+            std::fs::remove_dir_all(entry.path()).log_err();
+        }
+    }
+}
+
 impl Drop for SemanticDb {
     fn drop(&mut self) {
         self.db_connection.take().unwrap().prepare_for_closing();
@@ -274,12 +366,14 @@ mod tests {
     use futures::{future::BoxFuture, FutureExt};
     use gpui::TestAppContext;
     use indexing::IndexingEntrySet;
-    use language::language_settings::AllLanguageSettings;
+    use keyword_index::KeywordChunk;
+    use language::{language_settings::AllLanguageSettings, LanguageName};
     use project::{Project, ProjectEntryId};
     use serde_json::json;
     use settings::SettingsStore;
     use smol::{channel, stream::StreamExt};
     use std::{future, path::Path, sync::Arc};
+    use util::paths::PathMatcher;
 
     fn init_test(cx: &mut TestAppContext) {
         env_logger::try_init().ok();
@@ -438,6 +532,65 @@ mod tests {
         let content = content[range.clone()].to_owned();
 
         assert!(content.contains("garbage in, garbage out"));
+
+        let keyword_results = cx
+            .update(|cx| {
+                project_index.read(cx).search_with_options(
+                    vec!["garbage".into()],
+                    4,
+                    SearchOptions {
+                        mode: SearchMode::Keyword,
+                        ..Default::default()
+                    },
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        assert_eq!(keyword_results.len(), 1);
+        assert_eq!(
+            keyword_results[0].path.to_string_lossy(),
+            "fixture/needle.md"
+        );
+
+        let filtered_results = cx
+            .update(|cx| {
+                project_index.read(cx).search_with_options(
+                    vec!["garbage in, garbage out".into()],
+                    4,
+                    SearchOptions {
+                        mode: SearchMode::Hybrid,
+                        files_to_exclude: PathMatcher::new(&["*.md".to_string()]).unwrap(),
+                        ..Default::default()
+                    },
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        assert!(!filtered_results.is_empty());
+        assert!(filtered_results
+            .iter()
+            .all(|result| result.path.to_string_lossy() == "fixture/main.rs"));
+
+        let rust_results = cx
+            .update(|cx| {
+                project_index.read(cx).search_with_options(
+                    vec!["garbage in, garbage out".into()],
+                    4,
+                    SearchOptions {
+                        languages: vec![LanguageName::new("Rust")],
+                        ..Default::default()
+                    },
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        assert!(!rust_results.is_empty());
+        assert!(rust_results
+            .iter()
+            .all(|result| result.path.to_string_lossy() == "fixture/main.rs"));
     }
 
     #[gpui::test]
@@ -464,6 +617,7 @@ mod tests {
             .send_blocking(ChunkedFile {
                 path: Path::new("test1.md").into(),
                 mtime: None,
+                language: None,
                 handle: indexing_entries.insert(ProjectEntryId::from_proto(0)),
                 text: "abcdefghijklmnop".to_string(),
                 chunks: [0..4, 4..8, 8..12, 12..16]
@@ -479,6 +633,7 @@ mod tests {
             .send_blocking(ChunkedFile {
                 path: Path::new("test2.md").into(),
                 mtime: None,
+                language: None,
                 handle: indexing_entries.insert(ProjectEntryId::from_proto(1)),
                 text: "qrstuvwxyz".to_string(),
                 chunks: [0..4, 4..8, 8..10]
@@ -498,12 +653,22 @@ mod tests {
 
         let mut embedded_files_rx = embed_files_task.files;
         let mut embedded_files = Vec::new();
-        while let Some((embedded_file, _)) = embedded_files_rx.next().await {
+        let mut keyword_files = Vec::new();
+        while let Some((embedded_file, keyword_file, _)) = embedded_files_rx.next().await {
             embedded_files.push(embedded_file);
+            keyword_files.push(keyword_file);
         }
 
         assert_eq!(embedded_files.len(), 1);
         assert_eq!(embedded_files[0].path.as_ref(), Path::new("test2.md"));
+        assert_eq!(
+            keyword_files[0].chunks,
+            vec![
+                KeywordChunk::new(0..4, "qrst"),
+                KeywordChunk::new(4..8, "uvwx"),
+                KeywordChunk::new(8..10, "yz"),
+            ]
+        );
         assert_eq!(
             embedded_files[0]
                 .chunks
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use gpui::AppContext;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+
+/// Settings for the semantic index, which is used to search projects by meaning.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, JsonSchema)]
+pub struct SemanticIndexSettings {
+    /// The model used to compute the embeddings of projects' files.
+    #[serde(default)]
+    pub embedding_model: EmbeddingModelSettings,
+}
+
+/// Where the embeddings of projects' files are computed.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, JsonSchema)]
+pub enum EmbeddingModelSettings {
+    /// Compute embeddings with Zed's servers.
+    #[default]
+    #[serde(rename = "zed.dev")]
+    ZedDotDev,
+    /// Compute embeddings on this machine, with a sentence embedding model exported to ONNX.
+    /// Only available in builds with the `local-embeddings` feature.
+    #[serde(rename = "local")]
+    Local {
+        /// The path of the model's `.onnx` file, or of a directory containing a `model.onnx`
+        /// file. The model's `tokenizer.json` must be in the same directory.
+        path: PathBuf,
+        /// The number of threads used to compute embeddings, or one per CPU core if unset.
+        #[serde(default)]
+        threads: Option<usize>,
+    },
+}
+
+impl Settings for SemanticIndexSettings {
+    const KEY: Option<&'static str> = Some("semantic_index");
+
+    type FileContent = Self;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _cx: &mut AppContext) -> Result<Self> {
+        SettingsSources::<Self::FileContent>::json_merge_with(
+            [sources.default]
+                .into_iter()
+                .chain(sources.user)
+                .chain(sources.server),
+        )
+    }
+}
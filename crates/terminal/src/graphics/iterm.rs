@@ -0,0 +1,105 @@
+//! iTerm2's [inline images protocol](https://iterm2.com/documentation-images.html):
+//! `OSC 1337 ; File=[arguments] : base64 data ST`.
+
+use anyhow::{anyhow, Context as _, Result};
+use base64::Engine as _;
+
+use super::{CursorMovement, DecodedImage, Extent, PlacementRequest, BASE64};
+
+/// Parses the part of the sequence following `File=`. Files sent without `inline=1` are
+/// downloads, which are ignored.
+pub(super) fn parse(sequence: &[u8]) -> Result<Option<PlacementRequest>> {
+    let separator = sequence
+        .iter()
+        .position(|byte| *byte == b':')
+        .ok_or_else(|| anyhow!("iTerm2 image is missing its data"))?;
+    let arguments = std::str::from_utf8(&sequence[..separator])?;
+
+    let mut inline = false;
+    let mut width = None;
+    let mut height = None;
+    let mut preserve_aspect_ratio = true;
+    for argument in arguments.split(';') {
+        let Some((key, value)) = argument.split_once('=') else {
+            continue;
+        };
+        match key {
+            "inline" => inline = value == "1",
+            "width" => width = parse_extent(value)?,
+            "height" => height = parse_extent(value)?,
+            "preserveAspectRatio" => preserve_aspect_ratio = value != "0",
+            _ => {}
+        }
+    }
+    if !inline {
+        return Ok(None);
+    }
+
+    let data = BASE64
+        .decode(&sequence[separator + 1..])
+        .context("invalid base64 in iTerm2 image")?;
+    let image = image::load_from_memory(&data)?.into_rgba8();
+    if image.width() == 0 || image.height() == 0 {
+        return Ok(None);
+    }
+
+    let mut request = PlacementRequest::new(DecodedImage::new(image), CursorMovement::AfterImage);
+    request.width = width;
+    request.height = height;
+    request.preserve_aspect_ratio = preserve_aspect_ratio;
+    Ok(Some(request))
+}
+
+/// Parses `N` (cells), `Npx`, `N%` or `auto`.
+fn parse_extent(value: &str) -> Result<Option<Extent>> {
+    let extent = if value == "auto" {
+        None
+    } else if let Some(pixels) = value.strip_suffix("px") {
+        Some(Extent::Pixels(pixels.parse()?))
+    } else if let Some(percent) = value.strip_suffix('%') {
+        Some(Extent::Percent(percent.parse()?))
+    } else {
+        Some(Extent::Cells(value.parse()?))
+    };
+    Ok(extent.filter(|extent| match extent {
+        Extent::Cells(cells) => *cells > 0,
+        Extent::Pixels(length) | Extent::Percent(length) => *length > 0.,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{ImageFormat, RgbaImage};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_extent() {
+        assert_eq!(parse_extent("auto").unwrap(), None);
+        assert_eq!(parse_extent("12").unwrap(), Some(Extent::Cells(12)));
+        assert_eq!(parse_extent("100px").unwrap(), Some(Extent::Pixels(100.)));
+        assert_eq!(parse_extent("50%").unwrap(), Some(Extent::Percent(50.)));
+        assert!(parse_extent("wide").is_err());
+    }
+
+    #[test]
+    fn test_parse_inline_image() {
+        let mut png = Vec::new();
+        RgbaImage::new(3, 2)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let data = BASE64.encode(&png);
+
+        let sequence = format!("name=aW1hZ2U=;width=4;preserveAspectRatio=0;inline=1:{data}");
+        let request = parse(sequence.as_bytes()).unwrap().unwrap();
+        assert_eq!((request.image.width, request.image.height), (3, 2));
+        assert_eq!(request.width, Some(Extent::Cells(4)));
+        assert_eq!(request.height, None);
+        assert!(!request.preserve_aspect_ratio);
+
+        let download = format!("name=aW1hZ2U=:{data}");
+        assert!(parse(download.as_bytes()).unwrap().is_none());
+    }
+}
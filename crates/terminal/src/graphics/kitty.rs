@@ -0,0 +1,481 @@
+//! The subset of the [Kitty graphics protocol](https://sw.kovidgoyal.net/kitty/graphics-protocol/)
+//! needed to show images: direct and file transmission of PNG, RGB and RGBA data, placing
+//! images, deleting them and answering queries.
+
+use std::{
+    collections::VecDeque,
+    io::{Read as _, Seek as _, SeekFrom},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use base64::Engine as _;
+use collections::HashMap;
+use image::{DynamicImage, ImageFormat, RgbImage, RgbaImage};
+
+use super::{
+    CursorMovement, DecodedImage, Extent, PlacementRequest, TerminalImages, BASE64,
+    MAX_SEQUENCE_LEN, MAX_STORED_BYTES,
+};
+
+/// Parsed control data of a graphics command, `key=value` pairs separated by commas.
+#[derive(Clone, Debug, PartialEq)]
+struct Command {
+    action: u8,
+    quiet: u8,
+    format: u32,
+    medium: u8,
+    compression: Option<u8>,
+    more: bool,
+    image_id: u32,
+    image_number: u32,
+    placement_id: u32,
+    /// Width and height of raw pixel data.
+    data_width: u32,
+    data_height: u32,
+    /// Size and offset of the data to read from a file.
+    data_size: usize,
+    data_offset: usize,
+    /// The part of the image to show.
+    source_x: u32,
+    source_y: u32,
+    source_width: u32,
+    source_height: u32,
+    columns: u32,
+    rows: u32,
+    cursor_movement: u32,
+    delete: u8,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self {
+            action: b't',
+            quiet: 0,
+            format: 32,
+            medium: b'd',
+            compression: None,
+            more: false,
+            image_id: 0,
+            image_number: 0,
+            placement_id: 0,
+            data_width: 0,
+            data_height: 0,
+            data_size: 0,
+            data_offset: 0,
+            source_x: 0,
+            source_y: 0,
+            source_width: 0,
+            source_height: 0,
+            columns: 0,
+            rows: 0,
+            cursor_movement: 0,
+            delete: b'a',
+        }
+    }
+}
+
+impl Command {
+    fn parse(control: &[u8]) -> Self {
+        let mut command = Self::default();
+        for pair in control.split(|byte| *byte == b',') {
+            let [key, b'=', value @ ..] = pair else {
+                continue;
+            };
+            let character = value.first().copied().unwrap_or_default();
+            let number = std::str::from_utf8(value)
+                .ok()
+                .and_then(|value| value.parse::<u32>().ok())
+                .unwrap_or_default();
+            match *key {
+                b'a' => command.action = character,
+                b'q' => command.quiet = number as u8,
+                b'f' => command.format = number,
+                b't' => command.medium = character,
+                b'o' => command.compression = Some(character),
+                b'm' => command.more = number == 1,
+                b'i' => command.image_id = number,
+                b'I' => command.image_number = number,
+                b'p' => command.placement_id = number,
+                b's' => command.data_width = number,
+                b'v' => command.data_height = number,
+                b'S' => command.data_size = number as usize,
+                b'O' => command.data_offset = number as usize,
+                b'x' => command.source_x = number,
+                b'y' => command.source_y = number,
+                b'w' => command.source_width = number,
+                b'h' => command.source_height = number,
+                b'c' => command.columns = number,
+                b'r' => command.rows = number,
+                b'C' => command.cursor_movement = number,
+                b'd' => command.delete = character,
+                _ => {}
+            }
+        }
+        command
+    }
+
+    fn ok_response(&self) -> Option<String> {
+        if self.quiet > 0 {
+            return None;
+        }
+        self.response("OK")
+    }
+
+    fn error_response(&self, code: &str, error: impl std::fmt::Display) -> Option<String> {
+        if self.quiet > 1 {
+            return None;
+        }
+        let message = error.to_string().replace(
+            |character: char| character.is_control() || character == ';',
+            " ",
+        );
+        self.response(&format!("{code}:{message}"))
+    }
+
+    fn response(&self, message: &str) -> Option<String> {
+        if self.image_id == 0 && self.image_number == 0 {
+            return None;
+        }
+
+        let mut keys = vec![format!("i={}", self.image_id)];
+        if self.image_number != 0 {
+            keys.push(format!("I={}", self.image_number));
+        }
+        if self.placement_id != 0 {
+            keys.push(format!("p={}", self.placement_id));
+        }
+        Some(format!("\x1b_G{};{message}\x1b\\", keys.join(",")))
+    }
+
+    fn placement(&self, image: DecodedImage) -> PlacementRequest {
+        let cursor_movement = if self.cursor_movement == 1 {
+            CursorMovement::Unchanged
+        } else {
+            CursorMovement::AfterImage
+        };
+        let mut request = PlacementRequest::new(image, cursor_movement);
+        request.width = (self.columns > 0).then_some(Extent::Cells(self.columns as usize));
+        request.height = (self.rows > 0).then_some(Extent::Cells(self.rows as usize));
+        request.preserve_aspect_ratio = self.columns == 0 || self.rows == 0;
+        request.kitty_ids = Some((self.image_id, self.placement_id));
+        request
+    }
+}
+
+struct Transmission {
+    command: Command,
+    payload: Vec<u8>,
+}
+
+/// Images transmitted by the application, which may be placed several times.
+#[derive(Default)]
+pub(super) struct KittyState {
+    images: HashMap<u32, DecodedImage>,
+    image_order: VecDeque<u32>,
+    numbers: HashMap<u32, u32>,
+    next_image_id: u32,
+    transmission: Option<Transmission>,
+}
+
+impl KittyState {
+    /// Handles the contents of a `ESC _ G ... ESC \` sequence, returning the image to place
+    /// and the response for the application, if any.
+    pub(super) fn handle(
+        &mut self,
+        sequence: &[u8],
+        images: &TerminalImages,
+        allow_file_transmission: bool,
+    ) -> (Option<PlacementRequest>, Option<String>) {
+        let (control, payload) = match sequence.iter().position(|byte| *byte == b';') {
+            Some(separator) => (&sequence[..separator], &sequence[separator + 1..]),
+            None => (sequence, &[][..]),
+        };
+        let command = Command::parse(control);
+
+        // Chunked transmissions only repeat the `m` key after the first chunk.
+        let (mut command, payload) = match self.transmission.take() {
+            Some(mut transmission) => {
+                transmission.payload.extend_from_slice(payload);
+                if transmission.payload.len() > MAX_SEQUENCE_LEN {
+                    let error = "transmission is too large";
+                    return (None, transmission.command.error_response("EFBIG", error));
+                }
+                if command.more {
+                    self.transmission = Some(transmission);
+                    return (None, None);
+                }
+                (transmission.command, transmission.payload)
+            }
+            None if command.more => {
+                self.transmission = Some(Transmission {
+                    command,
+                    payload: payload.to_vec(),
+                });
+                return (None, None);
+            }
+            None => (command, payload.to_vec()),
+        };
+
+        match command.action {
+            b'q' => match load_image(&command, &payload, allow_file_transmission) {
+                Ok(_) => (None, command.ok_response()),
+                Err(error) => (None, command.error_response("EINVAL", error)),
+            },
+            action @ (b't' | b'T') => {
+                let image = match load_image(&command, &payload, allow_file_transmission) {
+                    Ok(image) => image,
+                    Err(error) => return (None, command.error_response("EINVAL", error)),
+                };
+                if command.image_id == 0 && command.image_number != 0 {
+                    command.image_id = self.allocate_image_id();
+                }
+                if command.image_id != 0 {
+                    self.store(&command, image.clone());
+                }
+                let request = (action == b'T').then(|| command.placement(image));
+                (request, command.ok_response())
+            }
+            b'p' => {
+                if command.image_id == 0 {
+                    if let Some(image_id) = self.numbers.get(&command.image_number) {
+                        command.image_id = *image_id;
+                    }
+                }
+                match self.images.get(&command.image_id) {
+                    Some(image) => (
+                        Some(command.placement(image.clone())),
+                        command.ok_response(),
+                    ),
+                    None => (None, command.error_response("ENOENT", "image not found")),
+                }
+            }
+            b'd' => {
+                self.delete(&command, images);
+                (None, None)
+            }
+            _ => (None, None),
+        }
+    }
+
+    fn allocate_image_id(&mut self) -> u32 {
+        loop {
+            self.next_image_id = self.next_image_id.wrapping_add(1).max(1);
+            if !self.images.contains_key(&self.next_image_id) {
+                return self.next_image_id;
+            }
+        }
+    }
+
+    fn store(&mut self, command: &Command, image: DecodedImage) {
+        if command.image_number != 0 {
+            self.numbers.insert(command.image_number, command.image_id);
+        }
+        if self.images.insert(command.image_id, image).is_none() {
+            self.image_order.push_back(command.image_id);
+        }
+
+        let mut stored_bytes: usize = self.images.values().map(DecodedImage::byte_len).sum();
+        while stored_bytes > MAX_STORED_BYTES && self.image_order.len() > 1 {
+            if let Some(evicted) = self.image_order.pop_front() {
+                if let Some(image) = self.images.remove(&evicted) {
+                    stored_bytes -= image.byte_len();
+                }
+            }
+        }
+    }
+
+    fn delete(&mut self, command: &Command, images: &TerminalImages) {
+        // Upper case variants also free the image data.
+        let free_data = command.delete.is_ascii_uppercase();
+        let target = command.delete.to_ascii_lowercase();
+        match target {
+            b'a' => {
+                images.remove_kitty_placements(None);
+                if free_data {
+                    self.images.clear();
+                    self.image_order.clear();
+                    self.numbers.clear();
+                }
+            }
+            b'i' | b'n' => {
+                let image_id = if target == b'n' {
+                    self.numbers.get(&command.image_number).copied()
+                } else {
+                    Some(command.image_id)
+                };
+                let Some(image_id) = image_id.filter(|image_id| *image_id != 0) else {
+                    return;
+                };
+                images.remove_kitty_placements(Some(image_id));
+                if free_data {
+                    self.images.remove(&image_id);
+                    self.image_order.retain(|id| *id != image_id);
+                    self.numbers.retain(|_, id| *id != image_id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn load_image(
+    command: &Command,
+    payload: &[u8],
+    allow_file_transmission: bool,
+) -> Result<DecodedImage> {
+    let payload = BASE64.decode(payload).context("invalid base64 payload")?;
+    let data = match command.medium {
+        b'd' => payload,
+        b'f' | b't' if allow_file_transmission => {
+            let path = String::from_utf8(payload).context("invalid file path")?;
+            let data = read_file(Path::new(&path), command.data_offset, command.data_size)?;
+            if command.medium == b't' && is_temporary_file(Path::new(&path)) {
+                std::fs::remove_file(&path).ok();
+            }
+            data
+        }
+        b'f' | b't' => bail!("file transmission is not supported in this terminal"),
+        medium => bail!("unsupported transmission medium {}", medium as char),
+    };
+
+    let data = match command.compression {
+        None => data,
+        Some(b'z') => {
+            let mut decompressed = Vec::new();
+            flate2::read::ZlibDecoder::new(data.as_slice())
+                .take(MAX_STORED_BYTES as u64)
+                .read_to_end(&mut decompressed)
+                .context("invalid zlib data")?;
+            decompressed
+        }
+        Some(compression) => bail!("unsupported compression {}", compression as char),
+    };
+
+    let image = match command.format {
+        100 => image::load_from_memory_with_format(&data, ImageFormat::Png)?.into_rgba8(),
+        24 => {
+            let data = raw_pixels(command, data, 3)?;
+            let image = RgbImage::from_raw(command.data_width, command.data_height, data)
+                .ok_or_else(|| anyhow!("insufficient image data"))?;
+            DynamicImage::ImageRgb8(image).into_rgba8()
+        }
+        32 => {
+            let data = raw_pixels(command, data, 4)?;
+            RgbaImage::from_raw(command.data_width, command.data_height, data)
+                .ok_or_else(|| anyhow!("insufficient image data"))?
+        }
+        format => bail!("unsupported format {format}"),
+    };
+
+    let image = if command.source_width > 0 || command.source_height > 0 || command.source_x > 0 {
+        let width = match command.source_width {
+            0 => image.width().saturating_sub(command.source_x),
+            width => width,
+        };
+        let height = match command.source_height {
+            0 => image.height().saturating_sub(command.source_y),
+            height => height,
+        };
+        image::imageops::crop_imm(&image, command.source_x, command.source_y, width, height)
+            .to_image()
+    } else {
+        image
+    };
+
+    if image.width() == 0 || image.height() == 0 {
+        bail!("empty image");
+    }
+    Ok(DecodedImage::new(image))
+}
+
+fn raw_pixels(command: &Command, mut data: Vec<u8>, channels: usize) -> Result<Vec<u8>> {
+    let len = command.data_width as usize * command.data_height as usize * channels;
+    if len == 0 {
+        bail!("missing image dimensions");
+    }
+    if len > MAX_STORED_BYTES || data.len() < len {
+        bail!("image data does not match its dimensions");
+    }
+    data.truncate(len);
+    Ok(data)
+}
+
+fn read_file(path: &Path, offset: usize, size: usize) -> Result<Vec<u8>> {
+    let mut file = std::fs::File::open(path).with_context(|| format!("failed to open {path:?}"))?;
+    file.seek(SeekFrom::Start(offset as u64))?;
+    let limit = if size > 0 { size } else { MAX_STORED_BYTES };
+    let mut data = Vec::new();
+    file.take(limit as u64).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// The protocol only allows deleting temporary files that look like they were made for it.
+fn is_temporary_file(path: &Path) -> bool {
+    let in_temporary_directory = [std::env::temp_dir().as_path(), Path::new("/tmp")]
+        .into_iter()
+        .chain(cfg!(target_os = "linux").then(|| Path::new("/dev/shm")))
+        .any(|directory| path.starts_with(directory));
+    in_temporary_directory
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.contains("tty-graphics-protocol"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        let command = Command::parse(b"a=T,f=100,i=7,c=10,r=4,C=1,q=2,unknown,z=-1");
+        assert_eq!(
+            command,
+            Command {
+                action: b'T',
+                format: 100,
+                image_id: 7,
+                columns: 10,
+                rows: 4,
+                cursor_movement: 1,
+                quiet: 2,
+                ..Command::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_chunked_transmission() {
+        let images = TerminalImages::default();
+        let mut state = KittyState::default();
+        let payload = BASE64.encode([1u8, 2, 3, 4, 5, 6, 7, 8]);
+        let (first, rest) = payload.split_at(4);
+
+        let first_chunk = format!("a=t,i=3,s=2,v=1,m=1;{first}");
+        assert!(state
+            .handle(first_chunk.as_bytes(), &images, false)
+            .1
+            .is_none());
+        let last_chunk = format!("m=0;{rest}");
+        let (request, response) = state.handle(last_chunk.as_bytes(), &images, false);
+        assert!(request.is_none());
+        assert_eq!(response.as_deref(), Some("\x1b_Gi=3;OK\x1b\\"));
+
+        let (request, _) = state.handle(b"a=p,i=3,c=4", &images, false);
+        let request = request.unwrap();
+        assert_eq!(request.width, Some(Extent::Cells(4)));
+        assert_eq!((request.image.width, request.image.height), (2, 1));
+    }
+
+    #[test]
+    fn test_file_transmission_requires_permission() {
+        let images = TerminalImages::default();
+        let mut state = KittyState::default();
+        let path = BASE64.encode("/tmp/tty-graphics-protocol-image.png");
+        let (request, response) =
+            state.handle(format!("a=T,t=f,i=1;{path}").as_bytes(), &images, false);
+        assert!(request.is_none());
+        assert!(response.unwrap().starts_with("\x1b_Gi=1;EINVAL:"));
+    }
+}
@@ -0,0 +1,691 @@
+//! Inline images for the Kitty graphics protocol, iTerm2's `OSC 1337 ; File=` and Sixel.
+//!
+//! Alacritty's parser drops these sequences, so [`GraphicsPty`] intercepts them on the PTY
+//! reader before they reach it. Decoded images are kept in [`TerminalImages`], and each
+//! placement is replaced in the byte stream by blank cells carrying an OSC 8 hyperlink that
+//! points back at the placement. Those cells scroll, move into the history and get erased
+//! like any other cell, so rendering only needs to look up which placements are still on
+//! the grid.
+
+mod iterm;
+mod kitty;
+mod pty;
+mod sixel;
+
+use std::{collections::VecDeque, sync::Arc};
+
+use alacritty_terminal::event::{Event as AlacTermEvent, EventListener};
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use collections::{HashMap, HashSet};
+use gpui::{px, size, Pixels, RenderImage, Size};
+use image::RgbaImage;
+use parking_lot::Mutex;
+use util::ResultExt;
+
+use crate::{IndexedCell, TerminalSize, ZedListener};
+
+pub(crate) use pty::GraphicsPty;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+const CAN: u8 = 0x18;
+const SUB: u8 = 0x1a;
+
+/// Scheme of the hyperlinks that anchor image placements to grid cells.
+const IMAGE_URI_SCHEME: &str = "zed-terminal-image://";
+/// Upper bound for a single graphics escape sequence, larger ones are dropped.
+const MAX_SEQUENCE_LEN: usize = 64 * 1024 * 1024;
+/// Memory budget for decoded images, the oldest ones are evicted first.
+const MAX_STORED_BYTES: usize = 320 * 1024 * 1024;
+
+/// Base64 as used by the image protocols, where padding is optional.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// An image decoded from one of the graphics protocols, ready to be painted.
+#[derive(Clone, Debug)]
+struct DecodedImage {
+    data: Arc<RenderImage>,
+    width: u32,
+    height: u32,
+}
+
+impl DecodedImage {
+    fn new(mut image: RgbaImage) -> Self {
+        // GPUI expects BGRA.
+        for pixel in image.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        let (width, height) = image.dimensions();
+        Self {
+            data: Arc::new(RenderImage::new(vec![image::Frame::new(image)])),
+            width,
+            height,
+        }
+    }
+
+    fn byte_len(&self) -> usize {
+        self.width as usize * self.height as usize * 4
+    }
+}
+
+/// An image placed on the grid, visible in the current viewport.
+#[derive(Clone, Debug)]
+pub struct VisibleImage {
+    pub image: Arc<RenderImage>,
+    /// Viewport line of the image's top edge, negative when it is partially scrolled out.
+    pub line: i32,
+    pub column: usize,
+    /// Number of cells covered by the image horizontally.
+    pub columns: usize,
+    /// Number of cells covered by the image vertically.
+    pub rows: usize,
+    /// Whether the image should be fitted into its cells rather than stretched over them.
+    pub preserve_aspect_ratio: bool,
+}
+
+#[derive(Clone, Debug)]
+struct ImagePlacement {
+    image: DecodedImage,
+    columns: usize,
+    rows: usize,
+    preserve_aspect_ratio: bool,
+    /// Kitty image and placement ids, used to delete placements on request.
+    kitty_ids: Option<(u32, u32)>,
+}
+
+#[derive(Default)]
+struct ImageStore {
+    placements: HashMap<u64, ImagePlacement>,
+    insertion_order: VecDeque<u64>,
+    stored_bytes: usize,
+    next_placement_id: u64,
+    size: TerminalSize,
+}
+
+/// Images placed in a terminal, shared between the PTY reader thread and the [`crate::Terminal`].
+#[derive(Clone, Default)]
+pub struct TerminalImages(Arc<Mutex<ImageStore>>);
+
+impl TerminalImages {
+    pub(crate) fn set_size(&self, size: TerminalSize) {
+        self.0.lock().size = size;
+    }
+
+    fn size(&self) -> TerminalSize {
+        self.0.lock().size
+    }
+
+    fn insert(&self, placement: ImagePlacement) -> u64 {
+        let mut store = self.0.lock();
+        if let Some(ids) = placement
+            .kitty_ids
+            .filter(|(_, placement_id)| *placement_id != 0)
+        {
+            store.retain(|existing| existing.kitty_ids != Some(ids));
+        }
+
+        let id = store.next_placement_id;
+        store.next_placement_id += 1;
+        store.stored_bytes += placement.image.byte_len();
+        store.placements.insert(id, placement);
+        store.insertion_order.push_back(id);
+
+        while store.stored_bytes > MAX_STORED_BYTES && store.insertion_order.len() > 1 {
+            if let Some(evicted) = store.insertion_order.pop_front() {
+                if let Some(placement) = store.placements.remove(&evicted) {
+                    store.stored_bytes -= placement.image.byte_len();
+                }
+            }
+        }
+        id
+    }
+
+    /// Removes Kitty placements, either all of them or the ones showing the given image.
+    fn remove_kitty_placements(&self, image_id: Option<u32>) {
+        self.0
+            .lock()
+            .retain(|placement| match (placement.kitty_ids, image_id) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some((placed_id, _)), Some(image_id)) => placed_id != image_id,
+            });
+    }
+
+    /// Finds the placements anchored in the given cells, which are expected in grid order.
+    pub(crate) fn visible_images(
+        &self,
+        cells: &[IndexedCell],
+        display_offset: usize,
+    ) -> Vec<VisibleImage> {
+        let store = self.0.lock();
+        let mut images = Vec::new();
+        let mut seen = HashSet::default();
+        for cell in cells {
+            let Some(link) = cell.hyperlink() else {
+                continue;
+            };
+            let Some((placement_id, row)) = parse_image_uri(link.uri()) else {
+                continue;
+            };
+            if !seen.insert(placement_id) {
+                continue;
+            }
+            let Some(placement) = store.placements.get(&placement_id) else {
+                continue;
+            };
+            images.push(VisibleImage {
+                image: placement.image.data.clone(),
+                line: cell.point.line.0 + display_offset as i32 - row as i32,
+                column: cell.point.column.0,
+                columns: placement.columns,
+                rows: placement.rows,
+                preserve_aspect_ratio: placement.preserve_aspect_ratio,
+            });
+        }
+        images
+    }
+}
+
+impl ImageStore {
+    fn retain(&mut self, mut keep: impl FnMut(&ImagePlacement) -> bool) {
+        let mut removed_bytes = 0;
+        self.placements.retain(|_, placement| {
+            let retained = keep(placement);
+            if !retained {
+                removed_bytes += placement.image.byte_len();
+            }
+            retained
+        });
+        self.stored_bytes -= removed_bytes;
+        let placements = &self.placements;
+        self.insertion_order
+            .retain(|id| placements.contains_key(id));
+    }
+}
+
+/// Whether the hyperlink URI is an image anchor rather than a link the user can open.
+pub fn is_image_uri(uri: &str) -> bool {
+    uri.starts_with(IMAGE_URI_SCHEME)
+}
+
+fn parse_image_uri(uri: &str) -> Option<(u64, usize)> {
+    let (placement_id, row) = uri.strip_prefix(IMAGE_URI_SCHEME)?.split_once('/')?;
+    Some((placement_id.parse().ok()?, row.parse().ok()?))
+}
+
+/// A length requested for an image placement.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Extent {
+    Cells(usize),
+    Pixels(f32),
+    /// Percentage of the terminal's width or height.
+    Percent(f32),
+}
+
+impl Extent {
+    fn to_pixels(self, cell: Pixels, terminal: Pixels) -> Pixels {
+        match self {
+            Extent::Cells(cells) => cell * cells as f32,
+            Extent::Pixels(pixels) => px(pixels),
+            Extent::Percent(percent) => terminal * (percent / 100.),
+        }
+    }
+}
+
+/// Where the cursor ends up after an image has been placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CursorMovement {
+    /// Right after the image's bottom right cell, as in Kitty and iTerm2.
+    AfterImage,
+    /// At the image's left edge on the line below it, as in Sixel.
+    BelowImage,
+    /// Back at the image's top left cell, for Kitty's `C=1`.
+    Unchanged,
+}
+
+#[derive(Debug)]
+struct PlacementRequest {
+    image: DecodedImage,
+    width: Option<Extent>,
+    height: Option<Extent>,
+    preserve_aspect_ratio: bool,
+    cursor_movement: CursorMovement,
+    kitty_ids: Option<(u32, u32)>,
+}
+
+impl PlacementRequest {
+    fn new(image: DecodedImage, cursor_movement: CursorMovement) -> Self {
+        Self {
+            image,
+            width: None,
+            height: None,
+            preserve_aspect_ratio: true,
+            cursor_movement,
+            kitty_ids: None,
+        }
+    }
+
+    /// The area reserved for the image, in pixels.
+    fn display_size(&self, terminal: &TerminalSize) -> Size<Pixels> {
+        let natural_width = px(self.image.width as f32);
+        let natural_height = px(self.image.height as f32);
+        let width = self
+            .width
+            .map(|width| width.to_pixels(terminal.cell_width, terminal.width()));
+        let height = self
+            .height
+            .map(|height| height.to_pixels(terminal.line_height, terminal.height()));
+        match (width, height) {
+            (None, None) => size(natural_width, natural_height),
+            (Some(width), None) => size(width, width * (natural_height / natural_width)),
+            (None, Some(height)) => size(height * (natural_width / natural_height), height),
+            (Some(width), Some(height)) => size(width, height),
+        }
+    }
+}
+
+/// Scans PTY output for graphics sequences, decoding them and replacing them with
+/// placeholder cells.
+pub(crate) struct GraphicsParser {
+    state: ScanState,
+    sequence: Vec<u8>,
+    overflowed: bool,
+    kitty: kitty::KittyState,
+    images: TerminalImages,
+    listener: ZedListener,
+    allow_file_transmission: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Introducer {
+    Apc,
+    Osc,
+    Dcs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SequenceKind {
+    Kitty,
+    ITerm,
+    Sixel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScanState {
+    Ground,
+    Escape,
+    /// Collecting the start of a string sequence until it's known whether it carries an image.
+    Introducer(Introducer),
+    Capture {
+        kind: SequenceKind,
+        escape: bool,
+    },
+}
+
+enum Classification {
+    Pending,
+    Graphics(SequenceKind),
+    Other,
+}
+
+const ITERM_PREFIX: &[u8] = b"1337;File=";
+
+impl Introducer {
+    fn classify(self, prefix: &[u8]) -> Classification {
+        match self {
+            Introducer::Apc if prefix == b"G" => Classification::Graphics(SequenceKind::Kitty),
+            Introducer::Apc => Classification::Other,
+            Introducer::Osc if prefix == ITERM_PREFIX => {
+                Classification::Graphics(SequenceKind::ITerm)
+            }
+            Introducer::Osc if ITERM_PREFIX.starts_with(prefix) => Classification::Pending,
+            Introducer::Osc => Classification::Other,
+            Introducer::Dcs => match prefix.split_last() {
+                Some((b'q', parameters)) if parameters.iter().all(is_dcs_parameter) => {
+                    Classification::Graphics(SequenceKind::Sixel)
+                }
+                Some((last, _)) if is_dcs_parameter(last) && prefix.len() < 32 => {
+                    Classification::Pending
+                }
+                _ => Classification::Other,
+            },
+        }
+    }
+}
+
+fn is_dcs_parameter(byte: &u8) -> bool {
+    byte.is_ascii_digit() || *byte == b';'
+}
+
+impl GraphicsParser {
+    pub(crate) fn new(
+        images: TerminalImages,
+        listener: ZedListener,
+        allow_file_transmission: bool,
+    ) -> Self {
+        Self {
+            state: ScanState::Ground,
+            sequence: Vec::new(),
+            overflowed: false,
+            kitty: kitty::KittyState::default(),
+            images,
+            listener,
+            allow_file_transmission,
+        }
+    }
+
+    /// Copies `bytes` into `output`, swapping graphics sequences for placeholder cells.
+    /// Sequences may be split across calls.
+    pub(crate) fn advance(&mut self, bytes: &[u8], output: &mut Vec<u8>) {
+        let mut index = 0;
+        while index < bytes.len() {
+            let byte = bytes[index];
+            match self.state {
+                ScanState::Ground => {
+                    let end = bytes[index..]
+                        .iter()
+                        .position(|byte| *byte == ESC)
+                        .map_or(bytes.len(), |position| index + position);
+                    output.extend_from_slice(&bytes[index..end]);
+                    index = end;
+                    if index < bytes.len() {
+                        self.state = ScanState::Escape;
+                        index += 1;
+                    }
+                    continue;
+                }
+                ScanState::Escape => {
+                    let introducer = match byte {
+                        b'_' => Introducer::Apc,
+                        b']' => Introducer::Osc,
+                        b'P' => Introducer::Dcs,
+                        _ => {
+                            output.push(ESC);
+                            self.state = ScanState::Ground;
+                            continue;
+                        }
+                    };
+                    self.sequence.clear();
+                    self.state = ScanState::Introducer(introducer);
+                }
+                ScanState::Introducer(introducer) => {
+                    self.sequence.push(byte);
+                    match introducer.classify(&self.sequence) {
+                        Classification::Pending => {}
+                        Classification::Graphics(kind) => {
+                            self.state = ScanState::Capture {
+                                kind,
+                                escape: false,
+                            };
+                        }
+                        Classification::Other => {
+                            self.sequence.pop();
+                            output.push(ESC);
+                            output.push(match introducer {
+                                Introducer::Apc => b'_',
+                                Introducer::Osc => b']',
+                                Introducer::Dcs => b'P',
+                            });
+                            output.append(&mut self.sequence);
+                            self.state = ScanState::Ground;
+                            continue;
+                        }
+                    }
+                }
+                ScanState::Capture { kind, escape: true } => {
+                    if byte == b'\\' {
+                        self.state = ScanState::Ground;
+                        self.dispatch(kind, output);
+                    } else {
+                        // An escape aborts an unterminated string and starts a new sequence.
+                        self.abort_capture();
+                        self.state = ScanState::Escape;
+                        continue;
+                    }
+                }
+                ScanState::Capture {
+                    kind,
+                    escape: false,
+                } => match byte {
+                    ESC => {
+                        self.state = ScanState::Capture { kind, escape: true };
+                    }
+                    BEL if kind == SequenceKind::ITerm => {
+                        self.state = ScanState::Ground;
+                        self.dispatch(kind, output);
+                    }
+                    CAN | SUB => {
+                        self.abort_capture();
+                        self.state = ScanState::Ground;
+                    }
+                    _ => {
+                        let end = bytes[index..]
+                            .iter()
+                            .position(|byte| matches!(*byte, ESC | BEL | CAN | SUB))
+                            .map_or(bytes.len(), |position| index + position);
+                        if self.sequence.len() + (end - index) > MAX_SEQUENCE_LEN {
+                            self.overflowed = true;
+                        }
+                        if !self.overflowed {
+                            self.sequence.extend_from_slice(&bytes[index..end]);
+                        }
+                        index = end;
+                        continue;
+                    }
+                },
+            }
+            index += 1;
+        }
+    }
+
+    fn abort_capture(&mut self) {
+        self.sequence.clear();
+        self.overflowed = false;
+    }
+
+    fn dispatch(&mut self, kind: SequenceKind, output: &mut Vec<u8>) {
+        let sequence = std::mem::take(&mut self.sequence);
+        if std::mem::take(&mut self.overflowed) {
+            log::warn!("Dropped a {kind:?} image sequence larger than {MAX_SEQUENCE_LEN} bytes");
+            return;
+        }
+
+        let request = match kind {
+            SequenceKind::Kitty => {
+                let (request, response) =
+                    self.kitty
+                        .handle(&sequence[1..], &self.images, self.allow_file_transmission);
+                if let Some(response) = response {
+                    self.listener.send_event(AlacTermEvent::PtyWrite(response));
+                }
+                request
+            }
+            SequenceKind::ITerm => iterm::parse(&sequence[ITERM_PREFIX.len()..])
+                .log_err()
+                .flatten(),
+            SequenceKind::Sixel => sixel::decode(&sequence)
+                .log_err()
+                .map(|image| PlacementRequest::new(image, CursorMovement::BelowImage)),
+        };
+
+        if let Some(request) = request {
+            self.place(request, output);
+        }
+    }
+
+    fn place(&mut self, request: PlacementRequest, output: &mut Vec<u8>) {
+        let terminal_size = self.images.size();
+        let display_size = request.display_size(&terminal_size);
+        let columns = ((display_size.width / terminal_size.cell_width).ceil() as usize)
+            .clamp(1, terminal_size.num_columns().max(1));
+        let rows = ((display_size.height / terminal_size.line_height).ceil() as usize).max(1);
+
+        let placement_id = self.images.insert(ImagePlacement {
+            image: request.image,
+            columns,
+            rows,
+            preserve_aspect_ratio: request.preserve_aspect_ratio,
+            kitty_ids: request.kitty_ids,
+        });
+        write_placeholder(placement_id, columns, rows, request.cursor_movement, output);
+    }
+}
+
+/// Writes blank cells covering the placement, each row linked to the placement so it can be
+/// found on the grid later.
+fn write_placeholder(
+    placement_id: u64,
+    columns: usize,
+    rows: usize,
+    cursor_movement: CursorMovement,
+    output: &mut Vec<u8>,
+) {
+    let blank = " ".repeat(columns);
+    let mut placeholder = String::new();
+    for row in 0..rows {
+        if row > 0 {
+            // Moving back is clamped at the left edge, so this lands on the image's left
+            // column even when the last write left a wrap pending.
+            placeholder.push_str(&format!("\x1b[{columns}D\n"));
+        }
+        placeholder.push_str(&format!(
+            "\x1b]8;id=zed-image-{placement_id}-{row};{IMAGE_URI_SCHEME}{placement_id}/{row}\x1b\\"
+        ));
+        placeholder.push_str(&blank);
+        placeholder.push_str("\x1b]8;;\x1b\\");
+    }
+    match cursor_movement {
+        CursorMovement::AfterImage => {}
+        CursorMovement::BelowImage => placeholder.push_str(&format!("\x1b[{columns}D\n")),
+        CursorMovement::Unchanged => {
+            placeholder.push_str(&format!("\x1b[{columns}D"));
+            if rows > 1 {
+                placeholder.push_str(&format!("\x1b[{}A", rows - 1));
+            }
+        }
+    }
+    output.extend_from_slice(placeholder.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::Event as AlacTermEvent,
+        index::{Column, Line, Point as AlacPoint},
+        term::cell::{Cell, Hyperlink},
+    };
+    use base64::Engine as _;
+    use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+    use gpui::{px, size};
+
+    use super::*;
+
+    fn parser() -> (GraphicsParser, UnboundedReceiver<AlacTermEvent>) {
+        let (events_tx, events_rx) = unbounded();
+        let images = TerminalImages::default();
+        images.set_size(TerminalSize::new(px(10.), px(5.), size(px(100.), px(100.))));
+        (
+            GraphicsParser::new(images, ZedListener(events_tx), false),
+            events_rx,
+        )
+    }
+
+    fn advance_in_chunks(parser: &mut GraphicsParser, input: &[u8], chunk_size: usize) -> String {
+        let mut output = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            parser.advance(chunk, &mut output);
+        }
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_passes_through_other_sequences() {
+        let input = "plain \x1b[31mred\x1b[0m \x1b]0;title\x07 \x1b]8;;https://zed.dev\x1b\\link\x1b]8;;\x1b\\ \x1bP1$r\x1b\\ \x1b_other\x1b\\ done";
+        for chunk_size in [1, 3, input.len()] {
+            let (mut parser, _) = parser();
+            assert_eq!(
+                advance_in_chunks(&mut parser, input.as_bytes(), chunk_size),
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_replaces_images_with_placeholders() {
+        // A 2x1 RGBA image at 10x5 pixel cells covers one cell.
+        let payload = BASE64.encode([255u8, 0, 0, 255, 0, 255, 0, 255]);
+        let input = format!("before\x1b_Gf=32,s=2,v=1,a=T;{payload}\x1b\\after");
+        for chunk_size in [1, 7, input.len()] {
+            let (mut parser, _) = parser();
+            let output = advance_in_chunks(&mut parser, input.as_bytes(), chunk_size);
+            assert!(output.starts_with("before\x1b]8;id=zed-image-0-0;zed-terminal-image://0/0"));
+            assert!(output.ends_with("\x1b]8;;\x1b\\after"));
+            assert_eq!(parser.images.0.lock().placements.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_kitty_responses() {
+        let (mut parser, mut events) = parser();
+        let payload = BASE64.encode([0u8; 4]);
+        let input = format!("\x1b_Gi=31,s=1,v=1,a=q;{payload}\x1b\\\x1b_Gi=32,a=p\x1b\\");
+        let output = advance_in_chunks(&mut parser, input.as_bytes(), input.len());
+        assert_eq!(output, "");
+
+        let mut responses = Vec::new();
+        while let Ok(Some(AlacTermEvent::PtyWrite(response))) = events.try_next() {
+            responses.push(response);
+        }
+        assert_eq!(
+            responses,
+            [
+                "\x1b_Gi=31;OK\x1b\\".to_string(),
+                "\x1b_Gi=32;ENOENT:image not found\x1b\\".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_visible_images() {
+        let images = TerminalImages::default();
+        let placement_id = images.insert(ImagePlacement {
+            image: DecodedImage::new(RgbaImage::new(1, 1)),
+            columns: 2,
+            rows: 3,
+            preserve_aspect_ratio: true,
+            kitty_ids: None,
+        });
+
+        // The first row has been scrolled out of the viewport.
+        let cells = (1..3)
+            .flat_map(|row| {
+                (4..6).map(move |column| {
+                    let mut cell = Cell::default();
+                    cell.set_hyperlink(Some(Hyperlink::new(
+                        None::<String>,
+                        format!("{IMAGE_URI_SCHEME}{placement_id}/{row}"),
+                    )));
+                    IndexedCell {
+                        point: AlacPoint::new(Line(row - 1), Column(column)),
+                        cell,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let visible = images.visible_images(&cells, 0);
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].line, -1);
+        assert_eq!(visible[0].column, 4);
+        assert_eq!((visible[0].columns, visible[0].rows), (2, 3));
+
+        images.remove_kitty_placements(None);
+        assert_eq!(images.visible_images(&cells, 0).len(), 1);
+    }
+}
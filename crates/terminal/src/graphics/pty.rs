@@ -0,0 +1,121 @@
+use std::{
+    io::{self, Read},
+    sync::Arc,
+};
+
+use alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    tty::{ChildEvent, EventedPty, EventedReadWrite},
+};
+use polling::{Event, PollMode, Poller};
+
+use super::{GraphicsParser, TerminalImages};
+use crate::ZedListener;
+
+const READ_BUFFER_SIZE: usize = 0x10_000;
+
+/// A PTY whose output is stripped of graphics sequences before Alacritty parses it.
+pub struct GraphicsPty<T> {
+    reader: GraphicsReader<T>,
+}
+
+impl<T: EventedPty> GraphicsPty<T> {
+    pub(crate) fn new(
+        pty: T,
+        images: TerminalImages,
+        listener: ZedListener,
+        allow_file_transmission: bool,
+    ) -> Self {
+        Self {
+            reader: GraphicsReader {
+                pty,
+                parser: GraphicsParser::new(images, listener, allow_file_transmission),
+                read_buffer: vec![0; READ_BUFFER_SIZE].into_boxed_slice(),
+                output: Vec::new(),
+                output_position: 0,
+            },
+        }
+    }
+}
+
+pub struct GraphicsReader<T> {
+    pty: T,
+    parser: GraphicsParser,
+    read_buffer: Box<[u8]>,
+    output: Vec<u8>,
+    output_position: usize,
+}
+
+impl<T: EventedReadWrite> Read for GraphicsReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let pending = &self.output[self.output_position..];
+            if !pending.is_empty() {
+                let len = pending.len().min(buf.len());
+                buf[..len].copy_from_slice(&pending[..len]);
+                self.output_position += len;
+                if self.output_position == self.output.len() {
+                    self.output.clear();
+                    self.output_position = 0;
+                }
+                return Ok(len);
+            }
+
+            // Keep reading when a whole chunk was swallowed, as returning nothing would
+            // read as the end of the stream.
+            let len = self.pty.reader().read(&mut self.read_buffer)?;
+            if len == 0 {
+                return Ok(0);
+            }
+            self.parser
+                .advance(&self.read_buffer[..len], &mut self.output);
+        }
+    }
+}
+
+impl<T: EventedReadWrite> EventedReadWrite for GraphicsPty<T> {
+    type Reader = GraphicsReader<T>;
+    type Writer = T::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.reader.pty.register(poller, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.reader.pty.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Arc<Poller>) -> io::Result<()> {
+        self.reader.pty.deregister(poller)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        &mut self.reader
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.reader.pty.writer()
+    }
+}
+
+impl<T: EventedPty> EventedPty for GraphicsPty<T> {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.reader.pty.next_child_event()
+    }
+}
+
+impl<T: OnResize> OnResize for GraphicsPty<T> {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.reader.pty.on_resize(window_size)
+    }
+}
@@ -0,0 +1,251 @@
+//! Decoder for DEC Sixel graphics, `DCS P1 ; P2 ; P3 q data ST`.
+//!
+//! Pixel aspect ratio and background selection parameters are ignored: images are decoded
+//! with square pixels, and pixels no sixel was drawn to stay transparent.
+
+use anyhow::{bail, Result};
+use image::RgbaImage;
+
+use super::{DecodedImage, MAX_STORED_BYTES};
+
+const MAX_REPEAT: usize = 10_000;
+const MAX_WIDTH: usize = 10_000;
+const MAX_HEIGHT: usize = 10_000;
+
+/// The VT340's default color registers.
+const DEFAULT_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [51, 51, 204],
+    [204, 36, 36],
+    [51, 204, 51],
+    [204, 51, 204],
+    [51, 204, 204],
+    [204, 204, 51],
+    [120, 120, 120],
+    [69, 69, 69],
+    [87, 87, 153],
+    [153, 69, 69],
+    [87, 153, 87],
+    [153, 87, 153],
+    [87, 153, 153],
+    [153, 153, 87],
+    [204, 204, 204],
+];
+
+/// Decodes a sixel sequence, starting with its parameters and the `q` introducer.
+pub(super) fn decode(sequence: &[u8]) -> Result<DecodedImage> {
+    let data = match sequence.iter().position(|byte| *byte == b'q') {
+        Some(introducer) => &sequence[introducer + 1..],
+        None => bail!("missing sixel introducer"),
+    };
+
+    let mut palette = [[0, 0, 0, 255]; 256];
+    for (register, color) in palette.iter_mut().zip(DEFAULT_PALETTE) {
+        *register = [color[0], color[1], color[2], 255];
+    }
+
+    let mut canvas = Canvas::default();
+    let mut color = palette[0];
+    let mut x = 0;
+    let mut y = 0;
+    let mut repeat = 1;
+
+    let mut bytes = data.iter().copied().peekable();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'"' => {
+                // Raster attributes: pixel aspect ratio and the image size.
+                let parameters = parse_parameters(&mut bytes);
+                if let [_, _, width, height, ..] = parameters[..] {
+                    canvas.reserve(
+                        (width as usize).min(MAX_WIDTH),
+                        (height as usize).min(MAX_HEIGHT),
+                    );
+                }
+            }
+            b'#' => {
+                let parameters = parse_parameters(&mut bytes);
+                let Some(register) = parameters.first().map(|register| *register as usize % 256)
+                else {
+                    continue;
+                };
+                if let [_, space, a, b, c] = parameters[..] {
+                    let [red, green, blue] = match space {
+                        1 => hls_to_rgb(a, b, c),
+                        _ => [a, b, c].map(|component| (component.min(100) * 255 / 100) as u8),
+                    };
+                    palette[register] = [red, green, blue, 255];
+                }
+                color = palette[register];
+            }
+            b'!' => {
+                let parameters = parse_parameters(&mut bytes);
+                repeat = parameters
+                    .first()
+                    .map_or(1, |count| (*count as usize).clamp(1, MAX_REPEAT));
+            }
+            b'$' => x = 0,
+            b'-' => {
+                x = 0;
+                y += 6;
+                if y >= MAX_HEIGHT {
+                    bail!("sixel image is too tall");
+                }
+            }
+            b'?'..=b'~' => {
+                let bits = byte - b'?';
+                if x + repeat > MAX_WIDTH {
+                    bail!("sixel image is too wide");
+                }
+                for _ in 0..repeat {
+                    for bit in 0..6 {
+                        if bits & (1 << bit) != 0 {
+                            canvas.set(x, y + bit, color);
+                        }
+                    }
+                    x += 1;
+                }
+                canvas.extend_width(x);
+                repeat = 1;
+            }
+            _ => {}
+        }
+    }
+
+    canvas.into_image().map(DecodedImage::new)
+}
+
+fn parse_parameters(bytes: &mut std::iter::Peekable<impl Iterator<Item = u8>>) -> Vec<u32> {
+    let mut parameters = vec![0u32];
+    while let Some(byte) = bytes.peek().copied() {
+        match byte {
+            b'0'..=b'9' => {
+                let parameter = parameters.last_mut().unwrap();
+                *parameter = parameter
+                    .saturating_mul(10)
+                    .saturating_add((byte - b'0') as u32);
+            }
+            b';' => parameters.push(0),
+            _ => break,
+        }
+        bytes.next();
+    }
+    parameters
+}
+
+/// Converts DEC's HLS, where hue 0 is blue, lightness and saturation are percentages.
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 3] {
+    let hue = ((hue + 240) % 360) as f32 / 360.;
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+    if saturation == 0. {
+        let value = (lightness * 255.).round() as u8;
+        return [value; 3];
+    }
+
+    let q = if lightness < 0.5 {
+        lightness * (1. + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2. * lightness - q;
+    let channel = |mut t: f32| {
+        if t < 0. {
+            t += 1.;
+        }
+        if t > 1. {
+            t -= 1.;
+        }
+        let value = if t < 1. / 6. {
+            p + (q - p) * 6. * t
+        } else if t < 1. / 2. {
+            q
+        } else if t < 2. / 3. {
+            p + (q - p) * (2. / 3. - t) * 6.
+        } else {
+            p
+        };
+        (value * 255.).round() as u8
+    };
+    [channel(hue + 1. / 3.), channel(hue), channel(hue - 1. / 3.)]
+}
+
+#[derive(Default)]
+struct Canvas {
+    width: usize,
+    rows: Vec<Vec<[u8; 4]>>,
+}
+
+impl Canvas {
+    fn reserve(&mut self, width: usize, height: usize) {
+        self.width = self.width.max(width);
+        if self.rows.len() < height {
+            self.rows.resize_with(height, Vec::new);
+        }
+    }
+
+    fn extend_width(&mut self, width: usize) {
+        self.width = self.width.max(width);
+    }
+
+    fn set(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        if self.rows.len() <= y {
+            self.rows.resize_with(y + 1, Vec::new);
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(x + 1, [0; 4]);
+        }
+        row[x] = color;
+    }
+
+    fn into_image(self) -> Result<RgbaImage> {
+        let width = self.width;
+        let height = self.rows.len();
+        if width == 0 || height == 0 {
+            bail!("empty sixel image");
+        }
+        if width * height * 4 > MAX_STORED_BYTES {
+            bail!("sixel image is too large");
+        }
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for mut row in self.rows {
+            row.resize(width, [0; 4]);
+            pixels.extend(row.into_iter().flatten());
+        }
+        Ok(RgbaImage::from_raw(width as u32, height as u32, pixels)
+            .expect("pixel buffer matches the image size"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_sixel() {
+        // Two red columns of six pixels, then a blue pixel in the second band.
+        let image = decode(b"0;1;0q\"1;1;3;12#1;2;100;0;0!2~-#2;2;0;0;100@").unwrap();
+        assert_eq!((image.width, image.height), (3, 12));
+        assert_eq!(image.data.as_bytes(0).unwrap().len(), 3 * 12 * 4);
+
+        let bgra = |x: usize, y: usize| {
+            let offset = (y * 3 + x) * 4;
+            image.data.as_bytes(0).unwrap()[offset..offset + 4].to_vec()
+        };
+        assert_eq!(bgra(0, 0), [0, 0, 255, 255]);
+        assert_eq!(bgra(1, 5), [0, 0, 255, 255]);
+        assert_eq!(bgra(2, 0), [0, 0, 0, 0]);
+        assert_eq!(bgra(0, 6), [255, 0, 0, 255]);
+        assert_eq!(bgra(1, 6), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_hls_to_rgb() {
+        assert_eq!(hls_to_rgb(0, 50, 100), [0, 0, 255]);
+        assert_eq!(hls_to_rgb(120, 50, 100), [255, 0, 0]);
+        assert_eq!(hls_to_rgb(240, 50, 100), [0, 255, 0]);
+        assert_eq!(hls_to_rgb(0, 100, 0), [255, 255, 255]);
+    }
+}
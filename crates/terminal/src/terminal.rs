@@ -1,3 +1,4 @@
+pub mod graphics;
 pub mod mappings;
 
 pub use alacritty_terminal;
@@ -38,6 +39,7 @@ use mappings::mouse::{
 
 use collections::{HashMap, VecDeque};
 use futures::StreamExt;
+use graphics::{GraphicsPty, TerminalImages, VisibleImage};
 use pty_info::PtyProcessInfo;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
@@ -434,6 +436,16 @@ impl TerminalBuilder {
 
         let pty_info = PtyProcessInfo::new(&pty);
 
+        // Images are decoded on the PTY reader thread, before Alacritty drops their sequences.
+        // Programs behind SSH can't point us at files on this machine.
+        let images = TerminalImages::default();
+        let pty = GraphicsPty::new(
+            pty,
+            images.clone(),
+            ZedListener(events_tx.clone()),
+            !is_ssh_terminal,
+        );
+
         //And connect them together
         let event_loop = EventLoop::new(
             term.clone(),
@@ -471,6 +483,7 @@ impl TerminalBuilder {
             word_regex: RegexSearch::new(WORD_REGEX).unwrap(),
             vi_mode_enabled: false,
             is_ssh_terminal,
+            images,
         };
 
         Ok(TerminalBuilder {
@@ -569,6 +582,7 @@ pub struct TerminalContent {
     pub cursor_char: char,
     pub size: TerminalSize,
     pub last_hovered_word: Option<HoveredWord>,
+    pub images: Vec<VisibleImage>,
 }
 
 #[derive(Clone)]
@@ -593,6 +607,7 @@ impl Default for TerminalContent {
             cursor_char: Default::default(),
             size: Default::default(),
             last_hovered_word: None,
+            images: Vec::new(),
         }
     }
 }
@@ -629,6 +644,7 @@ pub struct Terminal {
     task: Option<TaskState>,
     vi_mode_enabled: bool,
     is_ssh_terminal: bool,
+    images: TerminalImages,
 }
 
 pub struct TaskState {
@@ -750,6 +766,7 @@ impl Terminal {
                 new_size.size.width = cmp::max(new_size.cell_width, new_size.width());
 
                 self.last_content.size = new_size;
+                self.images.set_size(new_size);
 
                 self.pty_tx.0.send(Msg::Resize(new_size.into())).ok();
 
@@ -888,7 +905,11 @@ impl Terminal {
                 )
                 .grid_clamp(term, Boundary::Grid);
 
-                let link = term.grid().index(point).hyperlink();
+                let link = term
+                    .grid()
+                    .index(point)
+                    .hyperlink()
+                    .filter(|link| !graphics::is_image_uri(link.uri()));
                 let found_word = if link.is_some() {
                     let mut min_index = point;
                     loop {
@@ -1299,26 +1320,32 @@ impl Terminal {
             self.process_terminal_event(&e, &mut terminal, cx)
         }
 
-        self.last_content = Self::make_content(&terminal, &self.last_content);
+        self.last_content = Self::make_content(&terminal, &self.last_content, &self.images);
     }
 
-    fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
+    fn make_content(
+        term: &Term<ZedListener>,
+        last_content: &TerminalContent,
+        images: &TerminalImages,
+    ) -> TerminalContent {
         let content = term.renderable_content();
+        let cells = content
+            .display_iter
+            //TODO: Add this once there's a way to retain empty lines
+            // .filter(|ic| {
+            //     !ic.flags.contains(Flags::HIDDEN)
+            //         && !(ic.bg == Named(NamedColor::Background)
+            //             && ic.c == ' '
+            //             && !ic.flags.contains(Flags::INVERSE))
+            // })
+            .map(|ic| IndexedCell {
+                point: ic.point,
+                cell: ic.cell.clone(),
+            })
+            .collect::<Vec<IndexedCell>>();
         TerminalContent {
-            cells: content
-                .display_iter
-                //TODO: Add this once there's a way to retain empty lines
-                // .filter(|ic| {
-                //     !ic.flags.contains(Flags::HIDDEN)
-                //         && !(ic.bg == Named(NamedColor::Background)
-                //             && ic.c == ' '
-                //             && !ic.flags.contains(Flags::INVERSE))
-                // })
-                .map(|ic| IndexedCell {
-                    point: ic.point,
-                    cell: ic.cell.clone(),
-                })
-                .collect::<Vec<IndexedCell>>(),
+            images: images.visible_images(&cells, content.display_offset),
+            cells,
             mode: content.mode,
             display_offset: content.display_offset,
             selection_text: term.selection_to_string(),
@@ -1544,7 +1571,10 @@ impl Terminal {
             //Hyperlinks
             if self.selection_phase == SelectionPhase::Ended {
                 let mouse_cell_index = content_index_for_mouse(position, &self.last_content.size);
-                if let Some(link) = self.last_content.cells[mouse_cell_index].hyperlink() {
+                if let Some(link) = self.last_content.cells[mouse_cell_index]
+                    .hyperlink()
+                    .filter(|link| !graphics::is_image_uri(link.uri()))
+                {
                     cx.open_url(link.uri());
                 } else if self.secondary_pressed {
                     self.events
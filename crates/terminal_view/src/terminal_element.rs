@@ -1,6 +1,6 @@
 use editor::{CursorLayout, HighlightedRange, HighlightedRangeLine};
 use gpui::{
-    div, fill, point, px, relative, size, AnyElement, AvailableSpace, Bounds, ContentMask,
+    div, fill, point, px, relative, size, AnyElement, AvailableSpace, Bounds, ContentMask, Corners,
     DispatchPhase, Element, ElementId, FocusHandle, Font, FontStyle, FontWeight, GlobalElementId,
     HighlightStyle, Hitbox, Hsla, InputHandler, InteractiveElement, Interactivity, IntoElement,
     LayoutId, Model, ModelContext, ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels,
@@ -20,11 +20,13 @@ use terminal::{
             CursorShape as AlacCursorShape, NamedColor,
         },
     },
+    graphics::VisibleImage,
     terminal_settings::TerminalSettings,
     HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalSize,
 };
 use theme::{ActiveTheme, Theme, ThemeSettings};
 use ui::{ParentElement, Tooltip};
+use util::ResultExt;
 use workspace::Workspace;
 
 use std::mem;
@@ -37,6 +39,7 @@ pub struct LayoutState {
     hitbox: Hitbox,
     cells: Vec<LayoutCell>,
     rects: Vec<LayoutRect>,
+    images: Vec<VisibleImage>,
     relative_highlighted_ranges: Vec<(RangeInclusive<AlacPoint>, Hsla)>,
     cursor: Option<CursorLayout>,
     background_color: Hsla,
@@ -145,6 +148,44 @@ impl LayoutRect {
     }
 }
 
+/// Paints an inline image over the cells reserved for it, keeping its aspect ratio unless the
+/// program asked for the image to be stretched.
+fn paint_image(
+    image: &VisibleImage,
+    origin: Point<Pixels>,
+    dimensions: &TerminalSize,
+    cx: &mut WindowContext,
+) {
+    let position = point(
+        (origin.x + image.column as f32 * dimensions.cell_width).floor(),
+        origin.y + image.line as f32 * dimensions.line_height,
+    );
+    let reserved = size(
+        dimensions.cell_width * image.columns as f32,
+        dimensions.line_height * image.rows as f32,
+    );
+    let image_size = image.image.size(0);
+    let size = if image.preserve_aspect_ratio && image_size.width.0 > 0 && image_size.height.0 > 0 {
+        let scale = (reserved.width / px(image_size.width.0 as f32))
+            .min(reserved.height / px(image_size.height.0 as f32));
+        size(
+            px(image_size.width.0 as f32) * scale,
+            px(image_size.height.0 as f32) * scale,
+        )
+    } else {
+        reserved
+    };
+
+    cx.paint_image(
+        Bounds::new(position, size),
+        Corners::default(),
+        image.image.clone(),
+        0,
+        false,
+    )
+    .log_err();
+}
+
 /// The GPUI element that paints the terminal.
 /// We need to keep a reference to the view for mouse events, do we need it for any other terminal stuff, or can we move that to connection?
 pub struct TerminalElement {
@@ -730,6 +771,7 @@ impl Element for TerminalElement {
 
                 let TerminalContent {
                     cells,
+                    images,
                     mode,
                     display_offset,
                     cursor_char,
@@ -739,6 +781,7 @@ impl Element for TerminalElement {
                 } = &self.terminal.read(cx).last_content;
                 let mode = *mode;
                 let display_offset = *display_offset;
+                let images = images.clone();
 
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
@@ -850,6 +893,7 @@ impl Element for TerminalElement {
                     background_color,
                     dimensions,
                     rects,
+                    images,
                     relative_highlighted_ranges,
                     mode,
                     display_offset,
@@ -919,6 +963,10 @@ impl Element for TerminalElement {
                         rect.paint(origin, &layout.dimensions, cx);
                     }
 
+                    for image in &layout.images {
+                        paint_image(image, origin, &layout.dimensions, cx);
+                    }
+
                     for (relative_highlighted_range, color) in
                         layout.relative_highlighted_ranges.iter()
                     {
@@ -1254,6 +1254,7 @@ impl SearchableItem for TerminalView {
             case: false,
             word: false,
             regex: true,
+            structural: false,
             replacement: false,
             selection: false,
         }
@@ -1314,6 +1315,7 @@ impl SearchableItem for TerminalView {
                 .unwrap()),
             ),
             SearchQuery::Regex { .. } => regex_search_for_query(&query),
+            SearchQuery::Structural { .. } => None,
         };
 
         if let Some(s) = searcher {
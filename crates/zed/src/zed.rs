@@ -250,6 +250,7 @@ pub fn initialize_workspace(
                 workspace_handle.clone(),
                 cx.clone(),
             );
+            let variables_panel = repl::VariablesPanel::load(workspace_handle.clone(), cx.clone());
 
             let (
                 project_panel,
@@ -259,6 +260,7 @@ pub fn initialize_workspace(
                 channels_panel,
                 chat_panel,
                 notification_panel,
+                variables_panel,
             ) = futures::try_join!(
                 project_panel,
                 outline_panel,
@@ -267,6 +269,7 @@ pub fn initialize_workspace(
                 channels_panel,
                 chat_panel,
                 notification_panel,
+                variables_panel,
             )?;
 
             workspace_handle.update(&mut cx, |workspace, cx| {
@@ -277,6 +280,7 @@ pub fn initialize_workspace(
                 workspace.add_panel(channels_panel, cx);
                 workspace.add_panel(chat_panel, cx);
                 workspace.add_panel(notification_panel, cx);
+                workspace.add_panel(variables_panel, cx);
             })
         })
         .detach();